@@ -0,0 +1,20 @@
+//! Embeds the git commit the binary is built from, reported in the metrics
+//! log's `run_start` record. Builds outside a git checkout simply omit it.
+
+use std::process::Command;
+
+fn main() {
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default();
+    if !hash.is_empty() {
+        println!("cargo:rustc-env=SHREDTOP_GIT_HASH={}", hash);
+    }
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+}
@@ -0,0 +1,99 @@
+//! Phase 1 of the decoder: finding the first Entry of a slot joined
+//! mid-stream.
+//!
+//! Compares [`find_entry_boundary`] with the scan it replaced, which tried a
+//! full bincode deserialize at every offset whose tx count read as 512 or
+//! less. The buffer is what a feed holds when its first shred of a slot is
+//! not index 0: the tail of an Entry of transfers, then whole Entries.
+//!
+//! ```text
+//! cargo bench -p shred-ingest --bench boundary_scan
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use solana_entry::entry::Entry;
+use solana_message::compiled_instruction::CompiledInstruction;
+use solana_message::{Message, MessageHeader, VersionedMessage};
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_transaction::versioned::VersionedTransaction;
+
+use shred_ingest::decoder::find_entry_boundary;
+
+/// Deterministic filler for keys and signatures.
+fn bytes<const N: usize>(seed: &mut u64) -> [u8; N] {
+    let mut out = [0u8; N];
+    for b in &mut out {
+        *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        *b = (*seed >> 56) as u8;
+    }
+    out
+}
+
+/// A system transfer of a small amount, mostly zero bytes in its data.
+fn transfer(seed: &mut u64, lamports: u64) -> VersionedTransaction {
+    let mut data = vec![2, 0, 0, 0];
+    data.extend(lamports.to_le_bytes());
+    VersionedTransaction {
+        signatures: vec![Signature::from(bytes::<64>(seed))],
+        message: VersionedMessage::Legacy(Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![
+                Pubkey::from(bytes::<32>(seed)),
+                Pubkey::from(bytes::<32>(seed)),
+                Pubkey::from([0; 32]),
+            ],
+            instructions: vec![CompiledInstruction {
+                program_id_index: 2,
+                accounts: vec![0, 1],
+                data,
+            }],
+            ..Default::default()
+        }),
+    }
+}
+
+/// The tail of a 128-transaction Entry followed by three whole Entries, and
+/// the offset of the first of those.
+fn mid_slot_buffer() -> (Vec<u8>, usize) {
+    let mut seed = 7;
+    let mut entry = |txs: u64| Entry {
+        num_hashes: 1,
+        transactions: (0..txs).map(|i| transfer(&mut seed, 1_000 + i)).collect(),
+        ..Default::default()
+    };
+    let partial = bincode::serialize(&entry(128)).unwrap();
+    let mut buf = partial[100..].to_vec();
+    let boundary = buf.len();
+    for _ in 0..3 {
+        buf.extend(bincode::serialize(&entry(64)).unwrap());
+    }
+    (buf, boundary)
+}
+
+/// The scan before the structured checks.
+fn brute_force(buf: &[u8]) -> Option<usize> {
+    (0..buf.len().saturating_sub(47)).find(|&off| {
+        let tx_count = u64::from_le_bytes(buf[off + 40..off + 48].try_into().unwrap());
+        if tx_count > 512 {
+            return false;
+        }
+        let mut cur = std::io::Cursor::new(&buf[off..]);
+        bincode::deserialize_from::<_, Entry>(&mut cur).is_ok()
+    })
+}
+
+fn boundary_scan(c: &mut Criterion) {
+    let (buf, boundary) = mid_slot_buffer();
+    let mut group = c.benchmark_group(format!("boundary_scan/{}B_tail", boundary));
+    group.bench_function("structured", |b| b.iter(|| find_entry_boundary(black_box(&buf))));
+    group.bench_function("brute_force", |b| b.iter(|| brute_force(black_box(&buf))));
+    group.finish();
+}
+
+criterion_group!(benches, boundary_scan);
+criterion_main!(benches);
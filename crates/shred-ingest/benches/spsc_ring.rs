@@ -0,0 +1,98 @@
+//! The receiver → decoder queue: the `shred_ingest::spsc` ring against the
+//! bounded crossbeam channel it replaced, both 4096 deep.
+//!
+//! - `throughput`: one thread sends 64K shreds in batches of 64, as a
+//!   receive thread does per `recvmmsg` call, while another drains them.
+//! - `round_trip`: one shred bounced between two threads through a pair of
+//!   queues, the latency a lone packet sees on an idle feed.
+//!
+//! The shreds carry no payload, so the numbers are the queue's and not the
+//! allocator's.
+//!
+//! ```text
+//! cargo bench -p shred-ingest --bench spsc_ring
+//! ```
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::time::{Duration, Instant};
+
+use shred_ingest::clock::MonotonicNs;
+use shred_ingest::receiver::{shred_queue, RawShred, ShredRx, ShredTx};
+
+const SHREDS: usize = 64 * 1024;
+const BATCH: usize = 64;
+
+/// A ring (one producer) or a channel (`producers` > 1 gives one).
+fn queue(ring: bool) -> (ShredTx, ShredRx) {
+    shred_queue(if ring { 1 } else { 2 })
+}
+
+fn shred() -> RawShred {
+    RawShred { data: Vec::new(), recv_timestamp_ns: MonotonicNs(0) }
+}
+
+/// Send [`SHREDS`] shreds from one thread to another; the time until the
+/// last one arrives.
+fn send_all(ring: bool) -> Duration {
+    let (tx, rx) = queue(ring);
+    let consumer = std::thread::spawn(move || {
+        let mut n = 0;
+        while rx.recv().is_some() {
+            n += 1;
+        }
+        n
+    });
+    let start = Instant::now();
+    for i in 0..SHREDS {
+        assert!(tx.send(shred()));
+        if i % BATCH == BATCH - 1 {
+            tx.flush();
+        }
+    }
+    drop(tx);
+    assert_eq!(consumer.join().unwrap(), SHREDS);
+    start.elapsed()
+}
+
+/// `iters` round trips of one shred; the total time.
+fn round_trips(ring: bool, iters: u64) -> Duration {
+    let (ping_tx, ping_rx) = queue(ring);
+    let (pong_tx, pong_rx) = queue(ring);
+    let echo = std::thread::spawn(move || {
+        while let Some(shred) = ping_rx.recv() {
+            pong_tx.send(shred);
+            pong_tx.flush();
+        }
+    });
+    let mut shred = shred();
+    let start = Instant::now();
+    for _ in 0..iters {
+        ping_tx.send(shred);
+        ping_tx.flush();
+        shred = pong_rx.recv().unwrap();
+    }
+    let elapsed = start.elapsed();
+    drop(ping_tx);
+    echo.join().unwrap();
+    elapsed
+}
+
+fn shred_queues(c: &mut Criterion) {
+    let mut group = c.benchmark_group("shred_queue/throughput");
+    group.throughput(Throughput::Elements(SHREDS as u64));
+    for (name, ring) in [("spsc_ring", true), ("crossbeam_bounded", false)] {
+        group.bench_function(name, |b| {
+            b.iter_custom(|iters| (0..iters).map(|_| send_all(ring)).sum())
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("shred_queue/round_trip");
+    for (name, ring) in [("spsc_ring", true), ("crossbeam_bounded", false)] {
+        group.bench_function(name, |b| b.iter_custom(|iters| round_trips(ring, iters)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, shred_queues);
+criterion_main!(benches);
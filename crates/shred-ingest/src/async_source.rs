@@ -0,0 +1,148 @@
+//! Async facade over the thread-based sources.
+//!
+//! Every [`TxSource`] runs on dedicated OS threads and writes batches to a
+//! crossbeam channel, which an async application would otherwise have to
+//! bridge itself. [`AsyncTxSource::into_stream`] starts any source and
+//! returns a [`TxStream`], a [`Stream`] of its transactions that can be
+//! polled from any executor.
+//!
+//! One bridge thread per stream moves batches from the source's channel to
+//! a tokio channel. A consumer that falls behind fills both, after which the
+//! source drops batches as it does under `run`, and counts nothing extra.
+//! Dropping the stream stops the bridge. The source's own threads keep
+//! running, as sources have no stop signal, but their sends then fail.
+
+use futures_util::Stream;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::thread::JoinHandle;
+
+use crate::decoder::DecodedTx;
+use crate::fan_in::TxSource;
+use crate::shred_race::ShredRaceTracker;
+use crate::source_metrics::SourceMetrics;
+
+/// Batches buffered on each side of the bridge thread.
+const STREAM_BATCHES: usize = 64;
+
+/// A source whose transactions can be consumed as a [`Stream`]. Implemented
+/// for every [`TxSource`], including `Box<dyn TxSource>`.
+pub trait AsyncTxSource: Send + 'static {
+    /// Start the source and stream what it decodes, in arrival order.
+    /// `metrics` and `race` are as for [`TxSource::start`].
+    fn into_stream(
+        self: Box<Self>,
+        metrics: Arc<SourceMetrics>,
+        race: Option<Arc<ShredRaceTracker>>,
+    ) -> TxStream;
+}
+
+impl<T: TxSource + ?Sized> AsyncTxSource for T {
+    fn into_stream(
+        self: Box<Self>,
+        metrics: Arc<SourceMetrics>,
+        race: Option<Arc<ShredRaceTracker>>,
+    ) -> TxStream {
+        let name = self.name();
+        let (tx, batches) = crossbeam_channel::bounded::<Vec<DecodedTx>>(STREAM_BATCHES);
+        let threads = self.start(tx, metrics, race);
+        let (async_tx, rx) = tokio::sync::mpsc::channel(STREAM_BATCHES);
+        let bridge = std::thread::Builder::new()
+            .name(format!("{}-stream", name))
+            .spawn(move || {
+                for batch in batches {
+                    if async_tx.blocking_send(batch).is_err() {
+                        return;
+                    }
+                }
+            })
+            .expect("async_source: failed to spawn bridge thread");
+        TxStream { rx, batch: Vec::new().into_iter(), threads, _bridge: bridge }
+    }
+}
+
+/// Transactions from one source, one at a time. Ends if every sender of the
+/// source has stopped.
+pub struct TxStream {
+    rx: tokio::sync::mpsc::Receiver<Vec<DecodedTx>>,
+    /// Rest of the batch being handed out.
+    batch: std::vec::IntoIter<DecodedTx>,
+    threads: Vec<JoinHandle<()>>,
+    _bridge: JoinHandle<()>,
+}
+
+impl TxStream {
+    /// The source's own threads, as returned by [`TxSource::start`].
+    pub fn threads(&self) -> &[JoinHandle<()>] {
+        &self.threads
+    }
+}
+
+impl Stream for TxStream {
+    type Item = DecodedTx;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<DecodedTx>> {
+        loop {
+            if let Some(tx) = self.batch.next() {
+                return Poll::Ready(Some(tx));
+            }
+            match self.rx.poll_recv(cx) {
+                Poll::Ready(Some(batch)) => self.batch = batch.into_iter(),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MonotonicNs;
+    use crossbeam_channel::Sender;
+    use futures_util::StreamExt;
+    use solana_transaction::versioned::VersionedTransaction;
+
+    /// Sends batches of two transactions for slots 0..n, then stops.
+    struct Batches(usize);
+
+    impl TxSource for Batches {
+        fn name(&self) -> Arc<str> {
+            "batches".into()
+        }
+
+        fn start(
+            self: Box<Self>,
+            tx: Sender<Vec<DecodedTx>>,
+            _metrics: Arc<SourceMetrics>,
+            _race: Option<Arc<ShredRaceTracker>>,
+        ) -> Vec<JoinHandle<()>> {
+            let n = self.0;
+            vec![std::thread::spawn(move || {
+                for slot in 0..n as u64 {
+                    let decoded = |_| DecodedTx {
+                        transaction: VersionedTransaction::default(),
+                        slot,
+                        shred_recv_ns: MonotonicNs(0),
+                        decode_done_ns: MonotonicNs(0),
+                    };
+                    tx.send((0..2).map(decoded).collect()).unwrap();
+                }
+            })]
+        }
+    }
+
+    #[test]
+    fn test_stream_flattens_batches_and_ends() {
+        let source: Box<dyn TxSource> = Box::new(Batches(3));
+        let stream = source.into_stream(SourceMetrics::new("batches".into(), false), None);
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let slots: Vec<u64> = rt.block_on(stream.map(|tx| tx.slot).collect());
+        assert_eq!(slots, vec![0, 0, 1, 1, 2, 2]);
+    }
+}
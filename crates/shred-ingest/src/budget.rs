@@ -0,0 +1,214 @@
+//! Process-wide memory budget (`[limits] max_memory_mb`).
+//!
+//! Most of the probe's memory is held in four places: the decoders' slot maps,
+//! their FEC sets, the fan-in's dedup map and the shred race's arrival window.
+//! Each is bounded by slot distance or age, but a run of pathological slots
+//! with tens of thousands of shreds can make them all balloon at once.
+//!
+//! With a [`MemoryBudget`] set, each holder keeps an approximate count of the
+//! bytes it holds in its [`BudgetShare`]. While the total is over the limit,
+//! each holder holding more than an even split of the limit sheds its oldest
+//! state first; one that holds little keeps its state, however much the
+//! others hold. A decoder finalizes its oldest slot early and drops that
+//! slot's FEC sets. The dedup map drops its oldest signatures, and the race
+//! window drops its oldest slot. Every shed is counted, so a run that lost
+//! state to the budget says so.
+//!
+//! The counts cover payloads and per-slot buffers, not allocator overhead or
+//! the rest of the process, so set the limit below the memory actually
+//! available.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::sync::Arc;
+
+/// What holds a [`BudgetShare`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BudgetUser {
+    /// Slot maps and FEC sets of every decoder.
+    Decoder,
+    /// The fan-in's signature dedup map.
+    Dedup,
+    /// The shred race's arrival windows.
+    Race,
+}
+
+/// Shared budget; cheap to clone, one per probe.
+#[derive(Clone)]
+pub struct MemoryBudget {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    limit: u64,
+    used: AtomicU64,
+    /// Live [`BudgetShare`]s, which split the limit evenly between them.
+    holders: AtomicU64,
+    /// Bytes held per [`BudgetUser`], in declaration order.
+    by_user: [AtomicU64; 3],
+    slots_shed: AtomicU64,
+    fec_sets_shed: AtomicU64,
+    dedup_entries_shed: AtomicU64,
+    race_slots_shed: AtomicU64,
+}
+
+/// One holder's part of the budget. Dropping it releases what it held.
+pub(crate) struct BudgetShare {
+    budget: MemoryBudget,
+    user: BudgetUser,
+    held: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetSnapshot {
+    pub limit_bytes: u64,
+    pub used_bytes: u64,
+    pub decoder_bytes: u64,
+    pub dedup_bytes: u64,
+    pub race_bytes: u64,
+    /// Slots a decoder finalized early, and the FEC sets dropped with them.
+    pub slots_shed: u64,
+    pub fec_sets_shed: u64,
+    /// Signatures dropped from the dedup map before their TTL. A transaction
+    /// seen again after its entry was shed counts as a new first arrival.
+    pub dedup_entries_shed: u64,
+    /// Slots dropped from the race window before they aged out.
+    pub race_slots_shed: u64,
+}
+
+impl MemoryBudget {
+    pub fn new(limit_bytes: u64) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                limit: limit_bytes,
+                used: AtomicU64::new(0),
+                holders: AtomicU64::new(0),
+                by_user: Default::default(),
+                slots_shed: AtomicU64::new(0),
+                fec_sets_shed: AtomicU64::new(0),
+                dedup_entries_shed: AtomicU64::new(0),
+                race_slots_shed: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub(crate) fn share(&self, user: BudgetUser) -> BudgetShare {
+        self.inner.holders.fetch_add(1, Relaxed);
+        BudgetShare { budget: self.clone(), user, held: 0 }
+    }
+
+    /// Whether the holders together are over the limit.
+    pub fn over(&self) -> bool {
+        self.inner.used.load(Relaxed) > self.inner.limit
+    }
+
+    pub(crate) fn count_slot_shed(&self) {
+        self.inner.slots_shed.fetch_add(1, Relaxed);
+    }
+
+    pub(crate) fn count_fec_sets_shed(&self, sets: usize) {
+        self.inner.fec_sets_shed.fetch_add(sets as u64, Relaxed);
+    }
+
+    pub(crate) fn count_dedup_shed(&self, entries: usize) {
+        self.inner.dedup_entries_shed.fetch_add(entries as u64, Relaxed);
+    }
+
+    pub(crate) fn count_race_slot_shed(&self) {
+        self.inner.race_slots_shed.fetch_add(1, Relaxed);
+    }
+
+    pub fn snapshot(&self) -> BudgetSnapshot {
+        let i = &self.inner;
+        let user = |u: BudgetUser| i.by_user[u as usize].load(Relaxed);
+        BudgetSnapshot {
+            limit_bytes: i.limit,
+            used_bytes: i.used.load(Relaxed),
+            decoder_bytes: user(BudgetUser::Decoder),
+            dedup_bytes: user(BudgetUser::Dedup),
+            race_bytes: user(BudgetUser::Race),
+            slots_shed: i.slots_shed.load(Relaxed),
+            fec_sets_shed: i.fec_sets_shed.load(Relaxed),
+            dedup_entries_shed: i.dedup_entries_shed.load(Relaxed),
+            race_slots_shed: i.race_slots_shed.load(Relaxed),
+        }
+    }
+}
+
+impl BudgetShare {
+    /// Report that the holder now holds `bytes`.
+    pub(crate) fn set(&mut self, bytes: u64) {
+        // Wrapping, so a shrinking share subtracts.
+        let delta = bytes.wrapping_sub(self.held);
+        self.held = bytes;
+        let inner = &self.budget.inner;
+        inner.used.fetch_add(delta, Relaxed);
+        inner.by_user[self.user as usize].fetch_add(delta, Relaxed);
+    }
+
+    /// Whether this holder should shed: the budget is exceeded and this
+    /// holder holds more than its even split of the limit.
+    pub(crate) fn over(&self) -> bool {
+        let inner = &self.budget.inner;
+        let split = inner.limit / inner.holders.load(Relaxed).max(1);
+        self.budget.over() && self.held > split
+    }
+
+    pub(crate) fn budget(&self) -> &MemoryBudget {
+        &self.budget
+    }
+}
+
+impl Drop for BudgetShare {
+    fn drop(&mut self) {
+        self.set(0);
+        self.budget.inner.holders.fetch_sub(1, Relaxed);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shares_add_up_and_release_on_drop() {
+        let budget = MemoryBudget::new(1000);
+        let mut a = budget.share(BudgetUser::Decoder);
+        let mut b = budget.share(BudgetUser::Decoder);
+        let mut race = budget.share(BudgetUser::Race);
+        a.set(600);
+        b.set(300);
+        race.set(50);
+        assert!(!budget.over());
+        a.set(700);
+        assert!(budget.over());
+
+        a.set(100);
+        drop(b);
+        let snap = budget.snapshot();
+        assert_eq!((snap.used_bytes, snap.decoder_bytes, snap.race_bytes), (150, 100, 50));
+        assert!(!budget.over());
+    }
+
+    #[test]
+    fn test_only_holders_over_their_split_shed() {
+        let budget = MemoryBudget::new(1000);
+        let mut decoder = budget.share(BudgetUser::Decoder);
+        let mut dedup = budget.share(BudgetUser::Dedup);
+        decoder.set(200);
+        dedup.set(900);
+        assert!(budget.over());
+        assert!(dedup.over());
+        assert!(!decoder.over(), "a small holder keeps its state");
+
+        dedup.set(700);
+        assert!(!budget.over() && !dedup.over());
+        drop(decoder);
+        dedup.set(1100);
+        assert!(dedup.over(), "a lone holder has the whole limit");
+    }
+}
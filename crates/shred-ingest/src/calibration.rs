@@ -0,0 +1,97 @@
+//! Auto-calibration of the receive-timestamp offset of gRPC sources.
+//!
+//! UDP sources are stamped by the kernel as the datagram lands. Geyser and
+//! jito-grpc sources can only read the clock once tonic has handed over a
+//! fully decoded message, so their timestamps carry the protobuf decode time
+//! and look later than they are. [`DecodeCalibration`] measures that overhead
+//! on the live stream: the first messages after connect are re-encoded and
+//! decoded again on the same thread, and the median decode time becomes the
+//! source's offset (see [`SourceMetrics::recv_offset_ns`]).
+//!
+//! [`SourceMetrics::recv_offset_ns`]: crate::source_metrics::SourceMetrics::recv_offset_ns
+
+use std::time::Instant;
+
+use crate::source_metrics::SourceMetrics;
+
+/// Messages timed before the offset is fixed.
+pub const CALIBRATION_SAMPLES: usize = 256;
+
+#[derive(Default)]
+pub struct DecodeCalibration {
+    samples: Vec<u64>,
+}
+
+impl DecodeCalibration {
+    /// Time one loopback decode of `msg`. Returns the median decode time in
+    /// nanoseconds once [`CALIBRATION_SAMPLES`] messages have been timed;
+    /// further calls after that keep returning it without sampling.
+    pub fn sample<M: prost::Message + Default>(&mut self, msg: &M) -> Option<u64> {
+        if self.samples.len() < CALIBRATION_SAMPLES {
+            let bytes = msg.encode_to_vec();
+            let start = Instant::now();
+            let decoded = M::decode(bytes.as_slice());
+            let ns = start.elapsed().as_nanos() as u64;
+            if decoded.is_ok() {
+                self.samples.push(ns);
+            }
+        }
+        self.median()
+    }
+
+    fn median(&self) -> Option<u64> {
+        if self.samples.len() < CALIBRATION_SAMPLES {
+            return None;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort_unstable();
+        Some(sorted[sorted.len() / 2])
+    }
+}
+
+/// Feed one received message to a pending calibration. When it completes,
+/// the measured overhead is added to the source's offset and `pending` is
+/// cleared, so later messages (and reconnects) cost nothing.
+pub fn calibrate<M: prost::Message + Default>(
+    pending: &mut Option<DecodeCalibration>,
+    msg: &M,
+    metrics: &SourceMetrics,
+) {
+    let Some(cal) = pending.as_mut() else { return };
+    if let Some(ns) = cal.sample(msg) {
+        metrics.add_recv_offset_ns(ns as i64);
+        tracing::info!(
+            "source '{}': calibrated gRPC decode overhead at {:.1}µs",
+            metrics.name,
+            ns as f64 / 1000.0
+        );
+        *pending = None;
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Payload {
+        #[prost(bytes = "vec", tag = "1")]
+        data: Vec<u8>,
+    }
+
+    #[test]
+    fn test_median_after_full_sample() {
+        let mut cal = DecodeCalibration::default();
+        let msg = Payload { data: vec![7; 1232] };
+        for _ in 0..CALIBRATION_SAMPLES - 1 {
+            assert_eq!(cal.sample(&msg), None);
+        }
+        let median = cal.sample(&msg).expect("median after the last sample");
+        assert_eq!(cal.sample(&msg), Some(median), "fixed once calibrated");
+        assert_eq!(cal.samples.len(), CALIBRATION_SAMPLES);
+    }
+}
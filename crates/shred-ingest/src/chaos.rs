@@ -0,0 +1,338 @@
+//! Controlled degradation of a shred feed, for testing.
+//!
+//! A chaos stage sits between a source's [`crate::receiver::ShredReceiver`] and
+//! its [`crate::decoder::ShredDecoder`] and applies, in order:
+//!
+//! 1. **loss** — each shred is dropped with probability `drop_pct / 100`;
+//! 2. **reorder** — survivors pass through a buffer of `reorder_window` shreds
+//!    from which a random one is released each time it overflows;
+//! 3. **delay** — each shred's receive timestamp is pushed back by
+//!    `delay_us` plus a uniform `0..=jitter_us`, and it is held until that
+//!    time. Jitter larger than the inter-shred gap reorders as well.
+//!
+//! The stage also takes over the shred-race tap, so race arrivals carry the
+//! degraded timestamps and dropped shreds never race. The receiver still counts
+//! every packet it read; the decoder's coverage and FEC-recovery counters show
+//! what the degradation did.
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::clock::MonotonicNs;
+use crate::metrics::now_ns;
+use crate::receiver::{RawShred, ShredTx};
+use crate::shred_race::ShredArrival;
+
+/// With no new input for this long, the reorder buffer is released so the tail
+/// of a burst is not held back indefinitely.
+const IDLE_FLUSH: Duration = Duration::from_millis(20);
+
+/// Degradation applied by a chaos stage. All-zero means pass-through.
+#[derive(Debug, Clone, Default)]
+pub struct ChaosConfig {
+    /// Percentage of shreds dropped, 0–100.
+    pub drop_pct: f64,
+    /// Reorder buffer size in shreds; 0 or 1 keeps arrival order.
+    pub reorder_window: usize,
+    /// Fixed latency added to every shred, in microseconds.
+    pub delay_us: u64,
+    /// Additional uniform random latency, `0..=jitter_us` microseconds.
+    pub jitter_us: u64,
+    /// RNG seed, for reproducible runs.
+    pub seed: u64,
+}
+
+/// Insert a chaos stage in front of `decoder_tx` if `cfg` is set.
+///
+/// Returns the sender the receiver should write to and the race sender it
+/// should use (`None` when the stage owns the race tap), plus the stage thread.
+pub(crate) fn interpose(
+    cfg: Option<ChaosConfig>,
+    name: Arc<str>,
+    decoder_tx: ShredTx,
+    race_tx: Option<Sender<ShredArrival>>,
+) -> (ShredTx, Option<Sender<ShredArrival>>, Option<JoinHandle<()>>) {
+    let Some(cfg) = cfg else {
+        return (decoder_tx, race_tx, None);
+    };
+    let (in_tx, in_rx) = crossbeam_channel::bounded(4096);
+    let thread_name = format!("{}-chaos", name);
+    let stage = ChaosStage::new(name, cfg, decoder_tx, race_tx);
+    let handle = std::thread::Builder::new()
+        .name(thread_name)
+        .spawn(move || stage.run(in_rx))
+        .expect("failed to spawn chaos thread");
+    (ShredTx::Channel(in_tx), None, Some(handle))
+}
+
+struct ChaosStage {
+    name: Arc<str>,
+    cfg: ChaosConfig,
+    out: ShredTx,
+    race_tx: Option<Sender<ShredArrival>>,
+    rng: SplitMix64,
+    window: Vec<RawShred>,
+    /// Shreds waiting for their delayed timestamp, earliest first.
+    delayed: BinaryHeap<Reverse<Delayed>>,
+    /// Tie-breaker so equal timestamps leave in insertion order.
+    seq: u64,
+}
+
+struct Delayed {
+    due_ns: u64,
+    seq: u64,
+    shred: RawShred,
+}
+
+impl PartialEq for Delayed {
+    fn eq(&self, other: &Self) -> bool {
+        (self.due_ns, self.seq) == (other.due_ns, other.seq)
+    }
+}
+impl Eq for Delayed {}
+impl PartialOrd for Delayed {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Delayed {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.due_ns, self.seq).cmp(&(other.due_ns, other.seq))
+    }
+}
+
+impl ChaosStage {
+    fn new(
+        name: Arc<str>,
+        cfg: ChaosConfig,
+        out: ShredTx,
+        race_tx: Option<Sender<ShredArrival>>,
+    ) -> Self {
+        Self {
+            name,
+            rng: SplitMix64(cfg.seed),
+            cfg,
+            out,
+            race_tx,
+            window: Vec::new(),
+            delayed: BinaryHeap::new(),
+            seq: 0,
+        }
+    }
+
+    fn run(mut self, input: Receiver<RawShred>) {
+        let mut last_input_ns = now_ns();
+        loop {
+            let now = now_ns();
+            let wait = match self.delayed.peek() {
+                Some(Reverse(d)) => Duration::from_nanos(d.due_ns.saturating_sub(now)).min(IDLE_FLUSH),
+                None => IDLE_FLUSH,
+            };
+            match input.recv_timeout(wait) {
+                Ok(shred) => {
+                    last_input_ns = now_ns();
+                    self.accept(shred);
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if now_ns().saturating_sub(last_input_ns) >= IDLE_FLUSH.as_nanos() as u64 {
+                        self.flush_window();
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.flush_window();
+                    while let Some(Reverse(d)) = self.delayed.pop() {
+                        sleep_until(d.due_ns);
+                        if !self.emit(d.shred) {
+                            return;
+                        }
+                    }
+                    return;
+                }
+            }
+            if !self.emit_due() {
+                return;
+            }
+        }
+    }
+
+    /// Apply loss and reordering to one incoming shred.
+    fn accept(&mut self, shred: RawShred) {
+        if self.rng.next_f64() * 100.0 < self.cfg.drop_pct {
+            return;
+        }
+        if self.cfg.reorder_window <= 1 {
+            self.delay(shred);
+            return;
+        }
+        self.window.push(shred);
+        if self.window.len() >= self.cfg.reorder_window {
+            let i = self.rng.below(self.window.len() as u64) as usize;
+            let shred = self.window.swap_remove(i);
+            self.delay(shred);
+        }
+    }
+
+    fn flush_window(&mut self) {
+        while !self.window.is_empty() {
+            let i = self.rng.below(self.window.len() as u64) as usize;
+            let shred = self.window.swap_remove(i);
+            self.delay(shred);
+        }
+    }
+
+    fn delay(&mut self, mut shred: RawShred) {
+        let jitter = match self.cfg.jitter_us {
+            0 => 0,
+            j => self.rng.below(j.saturating_add(1)),
+        };
+        // Never schedule into the past: a reordered shred leaves no earlier
+        // than the moment it left the reorder buffer.
+        let due = shred
+            .recv_timestamp_ns
+            .0
+            .saturating_add(self.cfg.delay_us.saturating_add(jitter).saturating_mul(1_000))
+            .max(now_ns());
+        shred.recv_timestamp_ns = MonotonicNs(due);
+        self.seq += 1;
+        self.delayed.push(Reverse(Delayed { due_ns: due, seq: self.seq, shred }));
+    }
+
+    /// Forward every shred whose time has come. Returns false once the
+    /// decoder has gone away.
+    fn emit_due(&mut self) -> bool {
+        let now = now_ns();
+        while self.delayed.peek().is_some_and(|Reverse(d)| d.due_ns <= now) {
+            let Reverse(d) = self.delayed.pop().unwrap();
+            if !self.emit(d.shred) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn emit(&self, shred: RawShred) -> bool {
+        if let (Some(rtx), true) = (&self.race_tx, shred.data.len() >= 77) {
+            let _ = rtx.try_send(ShredArrival {
+                source: self.name.clone(),
+                slot: u64::from_le_bytes(shred.data[65..73].try_into().unwrap()),
+                idx: u32::from_le_bytes(shred.data[73..77].try_into().unwrap()),
+                recv_ns: shred.recv_timestamp_ns,
+                recovered: false,
+            });
+        }
+        // Shreds leave one by one; none may wait in a ring's batch.
+        let sent = self.out.send(shred);
+        self.out.flush();
+        sent
+    }
+}
+
+fn sleep_until(due_ns: u64) {
+    let now = now_ns();
+    if due_ns > now {
+        std::thread::sleep(Duration::from_nanos(due_ns - now));
+    }
+}
+
+/// Small deterministic PRNG; quality is ample for fault injection.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in `0..n` (n > 0).
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shred(idx: u32, ts: u64) -> RawShred {
+        let mut data = vec![0u8; 100];
+        data[65..73].copy_from_slice(&7u64.to_le_bytes());
+        data[73..77].copy_from_slice(&idx.to_le_bytes());
+        RawShred { data, recv_timestamp_ns: MonotonicNs(ts) }
+    }
+
+    fn idx(s: &RawShred) -> u32 {
+        u32::from_le_bytes(s.data[73..77].try_into().unwrap())
+    }
+
+    /// Push `n` shreds through a stage and collect what comes out.
+    fn run_stage(cfg: ChaosConfig, n: u32) -> (Vec<RawShred>, Vec<ShredArrival>) {
+        let (out_tx, out_rx) = crossbeam_channel::unbounded();
+        let (race_tx, race_rx) = crossbeam_channel::unbounded();
+        let (in_tx, race, handle) =
+            interpose(Some(cfg), "chaos-test".into(), out_tx.into(), Some(race_tx));
+        assert!(race.is_none(), "stage must own the race tap");
+        let t0 = now_ns();
+        for i in 0..n {
+            assert!(in_tx.send(shred(i, t0)));
+        }
+        drop(in_tx);
+        handle.unwrap().join().unwrap();
+        (out_rx.try_iter().collect(), race_rx.try_iter().collect())
+    }
+
+    #[test]
+    fn test_pass_through_when_zero() {
+        let (out, races) = run_stage(ChaosConfig::default(), 50);
+        assert_eq!(out.iter().map(idx).collect::<Vec<_>>(), (0..50).collect::<Vec<_>>());
+        assert_eq!(races.len(), 50);
+    }
+
+    #[test]
+    fn test_drop_rate_and_seed_are_reproducible() {
+        let cfg = ChaosConfig { drop_pct: 30.0, seed: 42, ..Default::default() };
+        let (a, races) = run_stage(cfg.clone(), 2000);
+        let (b, _) = run_stage(cfg, 2000);
+        assert!((1250..1550).contains(&a.len()), "kept {}", a.len());
+        assert_eq!(races.len(), a.len(), "dropped shreds must not race");
+        assert_eq!(a.iter().map(idx).collect::<Vec<_>>(), b.iter().map(idx).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_reorder_keeps_every_shred() {
+        let cfg = ChaosConfig { reorder_window: 8, seed: 1, ..Default::default() };
+        let (out, _) = run_stage(cfg, 200);
+        let mut got: Vec<u32> = out.iter().map(idx).collect();
+        assert_ne!(got, (0..200).collect::<Vec<_>>());
+        got.sort_unstable();
+        assert_eq!(got, (0..200).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_delay_shifts_timestamps() {
+        let t0 = now_ns();
+        let cfg = ChaosConfig { delay_us: 2_000, ..Default::default() };
+        let (out, races) = run_stage(cfg, 5);
+        assert_eq!(out.len(), 5);
+        for s in &out {
+            assert!(s.recv_timestamp_ns.0 >= t0 + 2_000_000);
+        }
+        assert!(now_ns() >= t0 + 2_000_000, "shreds must be held until due");
+        assert_eq!(races[0].recv_ns, out[0].recv_timestamp_ns);
+    }
+}
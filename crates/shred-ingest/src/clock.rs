@@ -0,0 +1,310 @@
+//! Typed timestamps for the pipeline's two clocks.
+//!
+//! [`MonotonicNs`] is the `CLOCK_MONOTONIC_RAW` frame of [`metrics::now_ns`]:
+//! every receive, decode and race timestamp inside the process.
+//! [`RealtimeNs`] is Unix-epoch time (`CLOCK_REALTIME`): kernel
+//! `SO_TIMESTAMPNS` values, pcap records, and anything written out for
+//! comparison across hosts. Being distinct types, the two cannot be
+//! subtracted from each other by accident, and the conversions between them
+//! live here and nowhere else.
+//!
+//! Values still enter as raw integers (from the kernel, a capture, a test),
+//! so two runtime checks back the types up:
+//!
+//! - [`MonotonicNs::since`] counts a delta that comes out negative instead of
+//!   letting `saturating_sub` hide it. Two readings of one monotonic clock
+//!   taken in order can't do that, so a nonzero count means timestamps were
+//!   taken out of order or from another clock.
+//! - [`MonotonicNs::check_live`] counts a timestamp that lies in the future
+//!   or looks like wall-clock time as cross-domain.
+//!
+//! [`snapshot`] returns both counts since the process started.
+//!
+//! A deterministic replay runs on a third clock, [`ReplayClock`]: capture
+//! times moved onto the monotonic axis, which it converts back exactly.
+
+use crate::metrics;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering::Relaxed};
+use tracing::warn;
+
+static NEGATIVE_DELTAS: AtomicU64 = AtomicU64::new(0);
+static CROSS_DOMAIN: AtomicU64 = AtomicU64::new(0);
+static CROSS_DOMAIN_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Values at or above this (2001-09-09 in Unix time) are wall-clock time:
+/// the monotonic clock counts from boot and would need 31 years of uptime.
+const REALTIME_FLOOR_NS: u64 = 1_000_000_000_000_000_000;
+/// How far past the checking thread's clock reading a live timestamp may
+/// lie. Another thread may have read the clock a little later.
+const FUTURE_SLACK_NS: u64 = 1_000_000_000;
+/// How old a [`RealtimeOffset`] may get before [`RealtimeOffset::refresh`]
+/// samples it again. NTP and PTP slew the wall clock by up to 500 ppm and
+/// leave `CLOCK_MONOTONIC_RAW` alone, so the offset drifts by at most 50 µs
+/// in this time.
+const OFFSET_REFRESH_NS: u64 = 100_000_000;
+/// Where a replay's origin lands on the monotonic axis: an hour in, so
+/// captures older than the origin (input read out of order) still have room
+/// below it, and no replayed time is 0, which some state reads as unset.
+const REPLAY_START_NS: u64 = 3_600_000_000_000;
+
+/// A `CLOCK_MONOTONIC_RAW` timestamp in nanoseconds (see [`metrics::now_ns`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MonotonicNs(pub u64);
+
+/// A `CLOCK_REALTIME` timestamp in nanoseconds since the Unix epoch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RealtimeNs(pub u64);
+
+impl MonotonicNs {
+    #[inline(always)]
+    pub fn now() -> Self {
+        Self(metrics::now_ns())
+    }
+
+    /// Nanoseconds from `earlier` to `self`. A negative delta reads as 0 and
+    /// is counted (see [`snapshot`]).
+    #[inline]
+    pub fn since(self, earlier: MonotonicNs) -> u64 {
+        self.0.checked_sub(earlier.0).unwrap_or_else(|| {
+            NEGATIVE_DELTAS.fetch_add(1, Relaxed);
+            0
+        })
+    }
+
+    /// Signed nanoseconds from `other` to `self`, for comparisons where
+    /// either may come first, such as the lead of one feed over another.
+    #[inline]
+    pub fn delta(self, other: MonotonicNs) -> i64 {
+        self.0 as i64 - other.0 as i64
+    }
+
+    pub fn saturating_add_signed(self, ns: i64) -> Self {
+        Self(self.0.saturating_add_signed(ns))
+    }
+
+    /// `ns` later, for deadlines.
+    pub fn saturating_add(self, ns: u64) -> Self {
+        Self(self.0.saturating_add(ns))
+    }
+
+    /// `ns` earlier, for age cutoffs.
+    pub fn saturating_sub(self, ns: u64) -> Self {
+        Self(self.0.saturating_sub(ns))
+    }
+
+    /// `self`, after counting it as cross-domain if it lies more than a
+    /// second past `now` or looks like wall-clock time. Meant for live
+    /// timestamps arriving from another thread; replayed ones fail it.
+    pub fn check_live(self, now: MonotonicNs) -> Self {
+        if self.0 >= REALTIME_FLOOR_NS || self.0 > now.0.saturating_add(FUTURE_SLACK_NS) {
+            CROSS_DOMAIN.fetch_add(1, Relaxed);
+            if !CROSS_DOMAIN_WARNED.swap(true, Relaxed) {
+                warn!(
+                    "timestamp {} ns is not in the monotonic clock's domain (now {} ns); \
+                     further occurrences are only counted",
+                    self.0, now.0
+                );
+            }
+        }
+        self
+    }
+
+    /// The same instant as Unix-epoch time, given the clocks' `offset`.
+    #[inline]
+    pub fn to_realtime_at(self, offset: RealtimeOffset) -> RealtimeNs {
+        RealtimeNs(self.0.saturating_add(offset.ns))
+    }
+
+    /// The same instant as Unix-epoch time.
+    ///
+    /// The offset between the two clocks is sampled on every call rather than
+    /// once at startup, so NTP/PTP slewing of the wall clock is followed.
+    /// Intended for values that leave the process, not the hot path.
+    pub fn to_realtime(self) -> RealtimeNs {
+        let age = Self::now().since(self);
+        RealtimeNs(RealtimeNs::now().0.saturating_sub(age))
+    }
+}
+
+impl RealtimeNs {
+    pub fn now() -> Self {
+        Self(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(0),
+        )
+    }
+
+    /// The same instant on the monotonic clock, given the clocks' `offset`.
+    /// `None`, counted as cross-domain, when `self` lies before the
+    /// monotonic clock's start, i.e. was not wall-clock time to begin with.
+    #[inline]
+    pub fn to_monotonic(self, offset: RealtimeOffset) -> Option<MonotonicNs> {
+        let mono = self.0.checked_sub(offset.ns).map(MonotonicNs);
+        if mono.is_none() {
+            CROSS_DOMAIN.fetch_add(1, Relaxed);
+        }
+        mono
+    }
+}
+
+/// `CLOCK_REALTIME − CLOCK_MONOTONIC_RAW`, for converting kernel receive
+/// timestamps on the hot path (see [`RealtimeNs::to_monotonic`]).
+///
+/// The wall clock is slewed and the raw monotonic clock is not, so the
+/// offset drifts. A long-lived holder calls [`Self::refresh`] with the
+/// current time every so often; a stale offset would move converted
+/// timestamps against [`MonotonicNs::now`] readings.
+#[derive(Debug, Clone, Copy)]
+pub struct RealtimeOffset {
+    ns: u64,
+    sampled_at: MonotonicNs,
+}
+
+impl RealtimeOffset {
+    /// Sample the offset now.
+    ///
+    /// We take the minimum of 8 paired samples to reduce the effect of
+    /// scheduler preemption between the two `clock_gettime` calls.
+    pub fn sample() -> Self {
+        #[cfg(target_os = "linux")]
+        {
+            let read_rt = || unsafe {
+                let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+                libc::clock_gettime(libc::CLOCK_REALTIME, &mut ts);
+                ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+            };
+            // Read RT then MONO in tight succession; take min over 8 rounds to
+            // minimise preemption-induced inflation of the difference.
+            let ns = (0..8).map(|_| read_rt().saturating_sub(metrics::now_ns())).min().unwrap_or(0);
+            Self { ns, sampled_at: MonotonicNs::now() }
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Self { ns: 0, sampled_at: MonotonicNs::now() }
+        }
+    }
+
+    /// Sample the offset again if it is more than 100 ms older than `now`.
+    #[inline]
+    pub fn refresh(&mut self, now: MonotonicNs) {
+        if now.0.saturating_sub(self.sampled_at.0) >= OFFSET_REFRESH_NS {
+            *self = Self::sample();
+        }
+    }
+}
+
+/// The clock of a deterministic replay (`offline`, `analyze`): capture times
+/// (Unix-epoch ns) moved onto the monotonic axis relative to an origin near
+/// the start of the capture. Replayed times are small, like boot-relative
+/// readings, so they never read as wall-clock time, and
+/// [`Self::to_realtime`] gives a capture time back exactly. They still must
+/// never meet live ones.
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayClock {
+    origin: RealtimeNs,
+}
+
+impl ReplayClock {
+    /// Replay clock for a capture whose first timestamp is `first`.
+    pub fn starting_at(first: RealtimeNs) -> Self {
+        Self { origin: first }
+    }
+
+    /// A clock under which replayed and capture times are the same numbers,
+    /// for callers that supply receive times of their own.
+    pub fn identity() -> Self {
+        Self { origin: RealtimeNs(REPLAY_START_NS) }
+    }
+
+    /// Capture time `t` on the replay clock. A time more than an hour before
+    /// the origin reads as 0 and is counted as a negative delta.
+    pub fn to_monotonic(self, t: RealtimeNs) -> MonotonicNs {
+        let ns = t.0.saturating_add(REPLAY_START_NS).checked_sub(self.origin.0);
+        MonotonicNs(ns.unwrap_or_else(|| {
+            NEGATIVE_DELTAS.fetch_add(1, Relaxed);
+            0
+        }))
+    }
+
+    /// The capture time a replayed time came from.
+    pub fn to_realtime(self, t: MonotonicNs) -> RealtimeNs {
+        RealtimeNs(t.0.saturating_add(self.origin.0).saturating_sub(REPLAY_START_NS))
+    }
+}
+
+/// Clock check counts since the process started (see the module docs).
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ClockCheckSnapshot {
+    pub negative_deltas: u64,
+    pub cross_domain: u64,
+}
+
+impl ClockCheckSnapshot {
+    pub fn is_clean(&self) -> bool {
+        self.negative_deltas == 0 && self.cross_domain == 0
+    }
+}
+
+pub fn snapshot() -> ClockCheckSnapshot {
+    ClockCheckSnapshot {
+        negative_deltas: NEGATIVE_DELTAS.load(Relaxed),
+        cross_domain: CROSS_DOMAIN.load(Relaxed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The counters are process-wide and other tests run concurrently, so
+    // these check that each case adds to its counter, not exact totals.
+
+    #[test]
+    fn test_negative_delta_is_counted() {
+        let before = snapshot().negative_deltas;
+        assert_eq!(MonotonicNs(5).since(MonotonicNs(10)), 0);
+        assert_eq!(MonotonicNs(10).since(MonotonicNs(4)), 6);
+        assert!(snapshot().negative_deltas > before);
+        assert_eq!(MonotonicNs(5).delta(MonotonicNs(10)), -5);
+    }
+
+    #[test]
+    fn test_check_live_flags_other_domains() {
+        let now = MonotonicNs::now();
+        let before = snapshot().cross_domain;
+        now.check_live(now);
+        MonotonicNs(now.0.saturating_sub(1_000)).check_live(now);
+        let wall = MonotonicNs(RealtimeNs::now().0);
+        wall.check_live(now);
+        assert!(snapshot().cross_domain > before);
+    }
+
+    #[test]
+    fn test_realtime_round_trip() {
+        let offset = RealtimeOffset::sample();
+        let mono = MonotonicNs::now();
+        let back = mono.to_realtime().to_monotonic(offset).unwrap();
+        // Offset sampling and slew leave a small error.
+        assert!(back.delta(mono).abs() < 50_000_000, "{:?} vs {:?}", back, mono);
+        let ahead = RealtimeOffset { ns: 10, sampled_at: MonotonicNs::now() };
+        assert_eq!(RealtimeNs(1).to_monotonic(ahead), None);
+    }
+
+    #[test]
+    fn test_replay_clock_round_trip() {
+        let first = RealtimeNs(1_700_000_000_000_000_000);
+        let clock = ReplayClock::starting_at(first);
+        let t = clock.to_monotonic(RealtimeNs(first.0 + 5));
+        assert!(t.0 < REALTIME_FLOOR_NS && t > clock.to_monotonic(first));
+        assert_eq!(clock.to_realtime(t), RealtimeNs(first.0 + 5));
+        // Out-of-order input before the origin keeps its order.
+        assert!(clock.to_monotonic(RealtimeNs(first.0 - 1)) < clock.to_monotonic(first));
+
+        let identity = ReplayClock::identity();
+        assert_eq!(identity.to_monotonic(RealtimeNs(42)), MonotonicNs(42));
+        assert_eq!(identity.to_realtime(MonotonicNs(42)), RealtimeNs(42));
+    }
+}
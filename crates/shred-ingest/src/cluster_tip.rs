@@ -0,0 +1,167 @@
+//! The cluster tip: the highest slot the shred-tier feeds have delivered,
+//! shared by their decoders to expire slots against (see [`SlotExpiry`]).
+
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::sync::{Mutex, OnceLock};
+
+use crate::clock::MonotonicNs;
+use crate::coverage::SlotExpiry;
+
+/// Slots a feed may run ahead of the tip and still move it, before the
+/// allowance for elapsed time.
+const TIP_MARGIN_SLOTS: u64 = 32;
+/// One more slot of allowance per this many nanoseconds since the tip last
+/// moved — four times the nominal slot rate.
+const TIP_SLOT_NS: u64 = 100_000_000;
+/// Observations within [`TIP_MARGIN_SLOTS`] of each other needed to seed the
+/// tip when there is no baseline to seed it from.
+pub(crate) const TIP_SEED_OBSERVATIONS: u32 = 8;
+/// Distinct candidate slots remembered while the tip is unseeded.
+const TIP_SEED_CANDIDATES: usize = 8;
+
+/// Highest slot the shred-tier feeds have delivered, shared by their
+/// decoders. A feed's own highest slot cannot show that the feed as a whole
+/// is behind; this can. A slot too far ahead of the tip, for the time since
+/// it last moved, does not move it, so one feed's garbage cannot age out
+/// the others' slots. Nor can the first slot seen: the tip is seeded from
+/// the RPC baseline when there is one, and otherwise only once
+/// [`TIP_SEED_OBSERVATIONS`] shreds agree on roughly where it is. With
+/// [`SlotExpiry::Baseline`] the RPC baseline's confirmed slot stands in for
+/// it.
+#[derive(Default)]
+pub struct ClusterTip {
+    slot: AtomicU64,
+    /// When `slot` last moved.
+    moved_at: AtomicU64,
+    /// Latest slot confirmed by an RPC source; 0 before any.
+    baseline: AtomicU64,
+    /// Candidate slots and how many observations agreed with each, until
+    /// the tip is seeded.
+    seed: Mutex<Vec<(u64, u32)>>,
+    expiry: OnceLock<SlotExpiry>,
+}
+
+impl ClusterTip {
+    /// Expire slots against this tip instead of each decoder's own highest
+    /// slot. Only the first call counts; set it before decoders start.
+    pub fn set_expiry(&self, expiry: SlotExpiry) {
+        let _ = self.expiry.set(expiry);
+    }
+
+    /// Record that a feed delivered `slot` at `now`. Decoders call it for
+    /// every shred; a slot at or below the tip costs one load.
+    pub fn observe(&self, slot: u64, now: MonotonicNs) {
+        let tip = self.slot.load(Relaxed);
+        if slot <= tip {
+            return;
+        }
+        if tip == 0 {
+            if let Some(seed) = self.seed(slot) {
+                self.slot.fetch_max(seed, Relaxed);
+                self.moved_at.fetch_max(now.0, Relaxed);
+            }
+            return;
+        }
+        let idle = now.0.saturating_sub(self.moved_at.load(Relaxed));
+        let allowed = TIP_MARGIN_SLOTS.saturating_add(idle / TIP_SLOT_NS);
+        if slot > tip.saturating_add(allowed) {
+            return;
+        }
+        self.slot.fetch_max(slot, Relaxed);
+        self.moved_at.fetch_max(now.0, Relaxed);
+    }
+
+    /// The first tip, if `slot` is close enough to the baseline or enough
+    /// observations now agree with it.
+    fn seed(&self, slot: u64) -> Option<u64> {
+        let baseline = self.baseline.load(Relaxed);
+        if baseline > 0 {
+            return (slot <= baseline.saturating_add(TIP_MARGIN_SLOTS)).then_some(slot);
+        }
+        let mut candidates = self.seed.lock().unwrap();
+        let agreeing = candidates.iter().position(|(c, _)| c.abs_diff(slot) <= TIP_MARGIN_SLOTS);
+        match agreeing {
+            Some(i) => {
+                let (candidate, count) = &mut candidates[i];
+                *candidate = (*candidate).min(slot);
+                *count += 1;
+                if *count >= TIP_SEED_OBSERVATIONS {
+                    let seed = *candidate;
+                    candidates.clear();
+                    return Some(seed);
+                }
+            }
+            None if candidates.len() < TIP_SEED_CANDIDATES => candidates.push((slot, 1)),
+            None => {
+                // Make room by forgetting the least supported candidate.
+                let weakest = candidates.iter_mut().min_by_key(|(_, count)| *count).unwrap();
+                *weakest = (slot, 1);
+            }
+        }
+        None
+    }
+
+    /// Record a slot the RPC baseline confirmed.
+    pub fn observe_baseline(&self, slot: u64) {
+        self.baseline.fetch_max(slot, Relaxed);
+    }
+
+    /// The cluster's highest slot: the baseline's under
+    /// [`SlotExpiry::Baseline`] once it has one, else the feeds'. 0 before
+    /// any.
+    pub fn get(&self) -> u64 {
+        let baseline = match self.expiry.get() {
+            Some(SlotExpiry::Baseline) => self.baseline.load(Relaxed),
+            _ => 0,
+        };
+        if baseline > 0 {
+            baseline
+        } else {
+            self.slot.load(Relaxed)
+        }
+    }
+
+    /// The slot decoders expire against, when it is shared. 0 until the tip
+    /// is seeded, so nothing expires against a slot no one agrees on yet.
+    pub fn expiry_tip(&self) -> Option<u64> {
+        match self.expiry.get() {
+            None | Some(SlotExpiry::Local) => None,
+            Some(_) => Some(self.get()),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_junk_first_slot_does_not_seed_tip() {
+        let tip = ClusterTip::default();
+        tip.observe(u64::MAX - 1, MonotonicNs(0));
+        for i in 0..TIP_SEED_OBSERVATIONS as u64 - 1 {
+            tip.observe(1000 + i, MonotonicNs(i));
+        }
+        assert_eq!(tip.get(), 0, "not enough agreement yet");
+        tip.observe(1010, MonotonicNs(10));
+        assert_eq!(tip.get(), 1000);
+        // Once seeded, the junk slot is still too far ahead to move it, and
+        // the margin arithmetic does not overflow.
+        tip.observe(u64::MAX, MonotonicNs(u64::MAX));
+        assert_eq!(tip.get(), 1000);
+        tip.observe(1020, MonotonicNs(20));
+        assert_eq!(tip.get(), 1020);
+
+        // With a baseline, the first plausible slot seeds it at once.
+        let tip = ClusterTip::default();
+        tip.observe_baseline(500);
+        tip.observe(900, MonotonicNs(0));
+        assert_eq!(tip.get(), 0);
+        tip.observe(510, MonotonicNs(0));
+        assert_eq!(tip.get(), 510);
+    }
+}
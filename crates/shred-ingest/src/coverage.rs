@@ -1,4 +1,12 @@
-//! Slot-level coverage event types for `SourceMetrics` tracking.
+//! Slot-level coverage event types for `SourceMetrics` tracking, coverage
+//! split by position within the slot, and what decoders expire slots against
+//! ([`SlotExpiry`]).
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::sync::Mutex;
 
 /// Slot-level outcome events emitted by the decoder when a slot is finalised.
 /// Used to update `SourceMetrics` slot counters.
@@ -10,3 +18,168 @@ pub enum SlotCoverageEvent {
     /// Slot expired with zero decoded transactions.
     Dropped { slot: u64 },
 }
+
+// ---------------------------------------------------------------------------
+// Coverage by position within the slot
+// ---------------------------------------------------------------------------
+
+/// Names of the three position bands, in index order.
+pub const POSITION_BANDS: [&str; 3] = ["head", "middle", "tail"];
+
+/// Band of data shred `index` in a slot of `len` data shreds: the first,
+/// middle or final third of the indices.
+pub fn position_band(index: u32, len: u32) -> usize {
+    ((index as u64 * 3) / len.max(1) as u64).min(2) as usize
+}
+
+/// Cumulative data shreds seen and expected per position band, over the slots
+/// whose length is known. A full-block feed is flat across the bands; a feed
+/// that only relays the tail of each block (as DoubleZero does) shows near-zero
+/// head coverage.
+#[derive(Default)]
+pub struct PositionCoverage {
+    seen: [AtomicU64; 3],
+    expected: [AtomicU64; 3],
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionCoverageSnapshot {
+    pub seen: [u64; 3],
+    pub expected: [u64; 3],
+}
+
+impl PositionCoverage {
+    /// Count one finished slot of `len` data shreds; `received` says whether
+    /// the feed had a given index (directly or through FEC recovery).
+    pub fn record_slot(&self, len: u32, received: impl Fn(u32) -> bool) {
+        let mut snap = PositionCoverageSnapshot::default();
+        for idx in 0..len {
+            let band = position_band(idx, len);
+            snap.expected[band] += 1;
+            if received(idx) {
+                snap.seen[band] += 1;
+            }
+        }
+        self.add(&snap);
+    }
+
+    /// Add a snapshot's counts, e.g. when restoring saved state.
+    pub fn add(&self, snap: &PositionCoverageSnapshot) {
+        for band in 0..3 {
+            self.seen[band].fetch_add(snap.seen[band], Relaxed);
+            self.expected[band].fetch_add(snap.expected[band], Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> PositionCoverageSnapshot {
+        PositionCoverageSnapshot {
+            seen: std::array::from_fn(|b| self.seen[b].load(Relaxed)),
+            expected: std::array::from_fn(|b| self.expected[b].load(Relaxed)),
+        }
+    }
+}
+
+impl PositionCoverageSnapshot {
+    pub fn is_empty(&self) -> bool {
+        self.expected.iter().all(|&n| n == 0)
+    }
+
+    /// Coverage percentage per band, `None` for a band with nothing expected.
+    pub fn pct(&self) -> [Option<f64>; 3] {
+        std::array::from_fn(|b| {
+            (self.expected[b] > 0)
+                .then(|| (self.seen[b] as f64 / self.expected[b] as f64 * 100.0).min(100.0))
+        })
+    }
+}
+
+/// Slots remembered by [`SlotLengths`]. Decoders finalize a slot 32 slots
+/// behind the tip, so this leaves ample margin.
+const SLOT_LENGTHS_CAP: usize = 256;
+
+/// Data shred count of recent slots, learned from whichever feed received the
+/// slot's last-in-slot shred. Shared by the decoders of all shred-tier feeds so
+/// a tail-only feed that never sees a slot's end can still place its shreds.
+#[derive(Default)]
+pub struct SlotLengths {
+    lengths: Mutex<BTreeMap<u64, u32>>,
+}
+
+impl SlotLengths {
+    /// Record that `last_index` is the last data shred of `slot`.
+    pub fn record(&self, slot: u64, last_index: u32) {
+        let mut lengths = self.lengths.lock().unwrap();
+        lengths.insert(slot, last_index.saturating_add(1));
+        while lengths.len() > SLOT_LENGTHS_CAP {
+            lengths.pop_first();
+        }
+    }
+
+    /// Data shreds in `slot`, if any feed saw its last shred.
+    pub fn get(&self, slot: u64) -> Option<u32> {
+        self.lengths.lock().unwrap().get(&slot).copied()
+    }
+}
+
+/// What a decoder measures slot age against when it expires slots: a slot
+/// more than 32 slots behind is given up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SlotExpiry {
+    /// The decoder's own highest slot. A burst of future-slot garbage on one
+    /// feed expires every slot that feed is still assembling.
+    #[default]
+    Local,
+    /// The [`crate::cluster_tip::ClusterTip`] of all shred-tier feeds.
+    Sources,
+    /// The slot the RPC baseline last confirmed; the feeds' tip until it has
+    /// reported one.
+    Baseline,
+}
+
+impl SlotExpiry {
+    /// From a config value: "local", "sources" or "baseline".
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "local" => Ok(Self::Local),
+            "sources" => Ok(Self::Sources),
+            "baseline" => Ok(Self::Baseline),
+            other => anyhow::bail!(
+                "unknown slot expiry '{}' (expected local, sources or baseline)",
+                other
+            ),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bands_split_indices_into_thirds() {
+        let bands: Vec<usize> = (0..9).map(|i| position_band(i, 9)).collect();
+        assert_eq!(bands, [0, 0, 0, 1, 1, 1, 2, 2, 2]);
+        assert_eq!(position_band(0, 1), 0);
+        assert_eq!(position_band(99, 10), 2, "indices past the end count as tail");
+    }
+
+    #[test]
+    fn test_tail_only_feed_shows_empty_head() {
+        let cov = PositionCoverage::default();
+        cov.record_slot(300, |idx| idx >= 200);
+        let snap = cov.snapshot();
+        assert_eq!(snap.expected, [100, 100, 100]);
+        assert_eq!(snap.pct(), [Some(0.0), Some(0.0), Some(100.0)]);
+
+        let lengths = SlotLengths::default();
+        for slot in 0..(SLOT_LENGTHS_CAP as u64 + 10) {
+            lengths.record(slot, 63);
+        }
+        assert_eq!(lengths.get(SLOT_LENGTHS_CAP as u64), Some(64));
+        assert_eq!(lengths.get(0), None, "oldest slots are forgotten");
+    }
+}
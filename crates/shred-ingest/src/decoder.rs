@@ -7,16 +7,26 @@
 //! FEC (Reed-Solomon erasure) recovery is implemented for Merkle coding shreds.
 //! When a FEC set accumulates enough shards (data + coding >= num_data), missing
 //! data shreds are reconstructed and inserted into the slot's data_payloads map.
+//! The RS symbol is each shred's erasure-coded region, whose width depends on
+//! the variant's proof size and resigned flag.
 
 use anyhow::Result;
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
 use reed_solomon_erasure::galois_8::ReedSolomon;
 use solana_transaction::versioned::VersionedTransaction;
 use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
 use std::sync::atomic::Ordering::Relaxed;
 use std::sync::Arc;
 
+use crate::budget::{BudgetShare, BudgetUser, MemoryBudget};
+use crate::clock::{MonotonicNs, ReplayClock};
+use crate::fan_in::send_batch;
+use crate::forensics::{self, FecSetForensics, ForensicsTap, SlotForensics};
 use crate::metrics;
+use crate::repair::RepairClient;
+use crate::shred_race::{ShredArrival, ShredRaceTracker};
+use crate::shred_verify::{ShredVerifier, SigCache};
 use crate::source_metrics::{SlotOutcome, SlotStats, SourceMetrics};
 
 // ---------------------------------------------------------------------------
@@ -71,13 +81,53 @@ const CODE_NUM_CODE_OFF: usize = 85; // u16 LE: number of coding shreds in FEC s
 const CODE_POSITION_OFF: usize = 87; // u16 LE: this coding shred's position (0-based)
 const CODE_HDR_END: usize = 89; // minimum length for a coding shred
 
-// Agave Merkle shred fixed buffer size used as the RS symbol width.
-// Both data and coding shreds are padded / truncated to this size for RS.
-const SHRED_RS_SIZE: usize = 1228;
+// Merkle erasure-shard geometry (Agave shred/merkle.rs).
+const SIGNATURE_SIZE: usize = 64;
+const MERKLE_PROOF_ENTRY_SIZE: usize = 20;
+const MERKLE_CODE_PAYLOAD_SIZE: usize = 1228;
+/// Erasure shard length of a Merkle shred with no proof entries and no
+/// retransmitter signature: a coding shred's payload minus its headers, which
+/// equals a data shred's payload (1203) minus its signature.
+const MERKLE_SHARD_BASE_SIZE: usize = MERKLE_CODE_PAYLOAD_SIZE - CODE_HDR_END;
+
+/// Where the Reed-Solomon symbol sits inside a Merkle shred.
+///
+/// Agave erasure-codes the bytes between the signature (data shreds) or the
+/// coding headers (coding shreds) and the Merkle proof. The chained Merkle root
+/// lies inside that region; the proof and a resigned shred's retransmitter
+/// signature do not. All shreds of a FEC set share the variant's proof size
+/// and flags, so their symbols have equal length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ErasureShard {
+    pub(crate) offset: usize,
+    pub(crate) len: usize,
+}
+
+/// Erasure-shard layout for a Merkle shred variant, or `None` for legacy and
+/// unknown variants, which are not recovered.
+///
+/// Variant byte: high nibble selects the type, low nibble is the proof size.
+///   0x4_ code, 0x6_ code chained, 0x7_ code chained + resigned
+///   0x8_ data, 0x9_ data chained, 0xb_ data chained + resigned
+pub(crate) fn erasure_shard(variant: u8) -> Option<ErasureShard> {
+    let proof_size = (variant & 0x0F) as usize;
+    let (is_code, resigned) = match variant & 0xF0 {
+        0x40 | 0x60 => (true, false),
+        0x70 => (true, true),
+        0x80 | 0x90 => (false, false),
+        0xb0 => (false, true),
+        _ => return None,
+    };
+    let len = MERKLE_SHARD_BASE_SIZE.checked_sub(
+        proof_size * MERKLE_PROOF_ENTRY_SIZE + if resigned { SIGNATURE_SIZE } else { 0 },
+    )?;
+    let offset = if is_code { CODE_HDR_END } else { SIGNATURE_SIZE };
+    Some(ErasureShard { offset, len })
+}
 
 /// Parse slot, index and fec_set_index from any shred type (code or data).
 /// Returns None only if the buffer is shorter than the common header.
-fn shred_slot_index(bytes: &[u8]) -> Option<(u64, u32, u32)> {
+pub(crate) fn shred_slot_index(bytes: &[u8]) -> Option<(u64, u32, u32)> {
     if bytes.len() < FEC_SET_INDEX_OFF + 4 {
         return None;
     }
@@ -90,16 +140,16 @@ fn shred_slot_index(bytes: &[u8]) -> Option<(u64, u32, u32)> {
 }
 
 /// Parsed fields from a coding shred header.
-struct CodingShredInfo {
-    num_data: u16,
-    num_coding: u16,
+pub(crate) struct CodingShredInfo {
+    pub(crate) num_data: u16,
+    pub(crate) num_coding: u16,
     /// This coding shred's 0-based position within the coding shreds of the FEC set.
-    position: u16,
+    pub(crate) position: u16,
 }
 
 /// Parse the coding-shred-specific header fields.
 /// Returns None for non-coding shreds, malformed buffers, or zero num_data/num_coding.
-fn parse_coding_header(bytes: &[u8]) -> Option<CodingShredInfo> {
+pub(crate) fn parse_coding_header(bytes: &[u8]) -> Option<CodingShredInfo> {
     if bytes.len() < CODE_HDR_END {
         return None;
     }
@@ -129,7 +179,7 @@ fn parse_coding_header(bytes: &[u8]) -> Option<CodingShredInfo> {
 /// Parse a data shred's entry payload.
 /// Returns `(last_in_slot, data_bytes)` for data shreds, `None` for code
 /// shreds or malformed payloads.
-fn parse_data_payload(bytes: &[u8]) -> Option<(bool, Vec<u8>)> {
+fn parse_data_payload(bytes: &[u8]) -> Option<(bool, &[u8])> {
     if bytes.len() < DATA_OFF {
         return None;
     }
@@ -148,10 +198,10 @@ fn parse_data_payload(bytes: &[u8]) -> Option<(bool, Vec<u8>)> {
         return None;
     }
 
-    Some((last_in_slot, bytes[DATA_OFF..size].to_vec()))
+    Some((last_in_slot, &bytes[DATA_OFF..size]))
 }
 
-use crate::receiver::RawShred;
+use crate::receiver::{RawShred, ShredRx};
 
 // ---------------------------------------------------------------------------
 // Public types
@@ -161,14 +211,55 @@ use crate::receiver::RawShred;
 pub struct DecodedTx {
     pub transaction: VersionedTransaction,
     pub slot: u64,
-    pub shred_recv_ns: u64,
-    pub decode_done_ns: u64,
+    pub shred_recv_ns: MonotonicNs,
+    pub decode_done_ns: MonotonicNs,
 }
 
 // ---------------------------------------------------------------------------
 // Per-slot state: accumulate data shred payloads
 // ---------------------------------------------------------------------------
 
+/// Inter-arrival gaps of one slot's data shreds, by receive timestamp. A
+/// feed can win on average and still stall for tens of milliseconds
+/// mid-slot; the max gap and the spread show it.
+#[derive(Default)]
+struct ArrivalGaps {
+    /// Arrival of the previous data shred; 0 until the first one.
+    last_ns: MonotonicNs,
+    count: u32,
+    sum_us: f64,
+    sq_sum_us: f64,
+    max_ns: u64,
+}
+
+impl ArrivalGaps {
+    fn record(&mut self, recv_ns: MonotonicNs) {
+        if self.last_ns != MonotonicNs::default() {
+            let gap = recv_ns.since(self.last_ns);
+            let us = gap as f64 / 1000.0;
+            self.count += 1;
+            self.sum_us += us;
+            self.sq_sum_us += us * us;
+            self.max_ns = self.max_ns.max(gap);
+        }
+        self.last_ns = recv_ns;
+    }
+
+    fn max_us(&self) -> Option<u64> {
+        (self.count > 0).then_some(self.max_ns / 1000)
+    }
+
+    /// Population standard deviation of the gaps, in µs.
+    fn stddev_us(&self) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+        let n = self.count as f64;
+        let mean = self.sum_us / n;
+        Some((self.sq_sum_us / n - mean * mean).max(0.0).sqrt().round() as u64)
+    }
+}
+
 struct SlotState {
     /// Data shred payloads keyed by shred index
     data_payloads: HashMap<u32, Vec<u8>>,
@@ -182,41 +273,79 @@ struct SlotState {
     entry_buf: Vec<u8>,
     /// Bytes already consumed from entry_buf
     consumed: usize,
+    /// First data shred index seen (the contiguity anchor); u32::MAX until set.
+    first_index: u32,
     /// Highest data shred index seen
     max_index: u32,
     /// Whether we've seen the last shred in slot
     last_seen: bool,
-    last_touch_ns: u64,
+    /// Index of the last-in-slot shred, once seen
+    last_index: Option<u32>,
+    /// Bitset of data shred indices received or recovered, for coverage by
+    /// position within the slot
+    received: Vec<u64>,
+    /// Indices set in `received`
+    received_count: u32,
+    /// Whether this slot has been entered in the slot-completion race
+    complete_raced: bool,
+    last_touch_ns: MonotonicNs,
+    /// Timestamp of the first shred (data or coding) that opened this slot
+    first_touch_ns: MonotonicNs,
     /// Number of transactions decoded from this slot
     txs_decoded: u32,
+    /// Number of entries deserialized from this slot
+    entries_decoded: u32,
     /// Unique data shreds received (direct + FEC-recovered)
     shreds_seen: u32,
     /// Data shreds reconstructed via Reed-Solomon FEC for this slot
     fec_recovered_count: u32,
+    /// Gaps between successive directly received data shreds
+    gaps: ArrivalGaps,
     /// Whether this slot has already been counted in slot outcome metrics
     counted: bool,
+    /// Repair rounds requested for this slot, and when the last one was sent
+    repair_attempts: u8,
+    repair_requested_ns: MonotonicNs,
     /// Whether the first Entry boundary has been located within entry_buf.
     /// When starting mid-stream (shred index > 0), the beginning of entry_buf
     /// may contain the tail of an incomplete Entry from earlier shreds.
     /// We scan forward once to skip past it before normal deserialization.
     boundary_scanned: bool,
+    /// Approximate bytes held: the initial buffers, the `received` bitset
+    /// and every payload kept.
+    bytes: u64,
 }
 
+/// Bytes a new slot holds before any payload: the entry buffer and the
+/// payload map, at their initial capacities.
+const SLOT_BASE_BYTES: u64 = 64 * 1024 + 64 * 32;
+
 impl SlotState {
-    fn new(now: u64) -> Self {
+    fn new(now: MonotonicNs) -> Self {
         Self {
             data_payloads: HashMap::with_capacity(64),
             next_contiguous: u32::MAX, // set on first shred receipt
             entry_buf: Vec::with_capacity(64 * 1024),
             consumed: 0,
+            first_index: u32::MAX,
             max_index: 0,
             last_seen: false,
+            last_index: None,
+            received: Vec::new(),
+            received_count: 0,
+            complete_raced: false,
             last_touch_ns: now,
+            first_touch_ns: now,
             txs_decoded: 0,
+            entries_decoded: 0,
             shreds_seen: 0,
             fec_recovered_count: 0,
+            gaps: ArrivalGaps::default(),
             counted: false,
+            repair_attempts: 0,
+            repair_requested_ns: MonotonicNs::default(),
             boundary_scanned: false,
+            bytes: SLOT_BASE_BYTES,
         }
     }
 
@@ -227,6 +356,7 @@ impl SlotState {
     fn set_first_index(&mut self, idx: u32) {
         if self.next_contiguous == u32::MAX {
             self.next_contiguous = idx;
+            self.first_index = idx;
             if idx > 0 {
                 self.boundary_scanned = false;
             } else {
@@ -235,6 +365,104 @@ impl SlotState {
         }
     }
 
+    /// Set `idx` in the bitset. Indices at or past
+    /// [`MAX_DATA_SHREDS_PER_SLOT`] are corrupt and ignored, so a junk index
+    /// cannot size the bitset. Returns the bytes the bitset grew by, which
+    /// are added to `bytes`.
+    fn mark_received(&mut self, idx: u32) -> u64 {
+        if idx >= MAX_DATA_SHREDS_PER_SLOT {
+            return 0;
+        }
+        let word = idx as usize / 64;
+        let mut grown = 0;
+        if word >= self.received.len() {
+            grown = ((word + 1 - self.received.len()) * 8) as u64;
+            self.received.resize(word + 1, 0);
+            self.bytes += grown;
+        }
+        let bit = 1 << (idx % 64);
+        if self.received[word] & bit == 0 {
+            self.received[word] |= bit;
+            self.received_count += 1;
+        }
+        grown
+    }
+
+    fn was_received(&self, idx: u32) -> bool {
+        self.received.get(idx as usize / 64).is_some_and(|w| w & (1 << (idx % 64)) != 0)
+    }
+
+    /// Whether every data shred from index 0 through the last in the slot
+    /// has been received or recovered.
+    fn has_full_range(&self) -> bool {
+        self.last_index.is_some_and(|last| {
+            self.received_count > last && (0..=last).all(|i| self.was_received(i))
+        })
+    }
+
+    fn mark_last(&mut self, idx: u32) {
+        self.last_seen = true;
+        self.last_index = Some(idx);
+    }
+
+    /// Up to `limit` data shred indices still missing between the first
+    /// shred seen and the last in the slot; empty until the last shred is
+    /// known.
+    fn missing(&self, limit: usize) -> Vec<u32> {
+        match self.last_index {
+            Some(last) if self.first_index <= last => {
+                let last = last.min(MAX_DATA_SHREDS_PER_SLOT - 1);
+                (self.first_index..=last)
+                    .filter(|&i| !self.was_received(i))
+                    .take(limit)
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Per-slot record for the metrics slot log; `fec` holds the slot's FEC
+    /// sets.
+    fn stats(
+        &self,
+        slot: u64,
+        outcome: SlotOutcome,
+        now: MonotonicNs,
+        fec: Option<&HashMap<u32, FecSet>>,
+    ) -> SlotStats {
+        let shreds_expected = if self.first_index <= self.max_index {
+            self.max_index - self.first_index + 1
+        } else {
+            0
+        };
+        let completion_us = match outcome {
+            SlotOutcome::Complete => Some(now.since(self.first_touch_ns) / 1000),
+            _ => None,
+        };
+        let fec_sets_complete = fec.into_iter().flatten().filter(|(&idx, set)| {
+            (idx..idx.saturating_add(set.num_data as u32)).all(|i| self.was_received(i))
+        });
+        SlotStats {
+            slot,
+            shreds_seen: self.shreds_seen,
+            fec_recovered: self.fec_recovered_count,
+            txs_decoded: self.txs_decoded,
+            shreds_expected,
+            completion_us,
+            completed_at_ns: None,
+            first_at_ns: None,
+            outcome,
+            confirmed_txs: None,
+            skipped: false,
+            max_gap_us: self.gaps.max_us(),
+            gap_stddev_us: self.gaps.stddev_us(),
+            fec_sets: fec.map_or(0, |sets| sets.len() as u32),
+            fec_sets_complete: fec_sets_complete.count() as u32,
+            entries: self.entries_decoded,
+            txs_first: 0,
+        }
+    }
+
     /// Try to flush contiguous data shred payloads into entry_buf
     fn flush_contiguous(&mut self) {
         while let Some(payload) = self.data_payloads.remove(&self.next_contiguous) {
@@ -250,25 +478,7 @@ impl SlotState {
 
         // ── Phase 1: locate the first Entry boundary ────────────────────────
         if !self.boundary_scanned {
-            let buf = &self.entry_buf[self.consumed..];
-            if buf.len() < 48 {
-                return txs;
-            }
-
-            let mut found_at: Option<usize> = None;
-            for off in 0..buf.len().saturating_sub(47) {
-                let tx_count = u64::from_le_bytes(buf[off + 40..off + 48].try_into().unwrap());
-                if tx_count > 512 {
-                    continue;
-                }
-                let mut cur = std::io::Cursor::new(&buf[off..]);
-                if bincode::deserialize_from::<_, solana_entry::entry::Entry>(&mut cur).is_ok() {
-                    found_at = Some(off);
-                    break;
-                }
-            }
-
-            match found_at {
+            match find_entry_boundary(&self.entry_buf[self.consumed..]) {
                 Some(off) => {
                     self.consumed += off;
                     self.boundary_scanned = true;
@@ -289,6 +499,7 @@ impl SlotState {
             let pos_before = cursor.position();
             match bincode::deserialize_from::<_, solana_entry::entry::Entry>(&mut cursor) {
                 Ok(entry) => {
+                    self.entries_decoded += 1;
                     txs.extend(entry.transactions);
                 }
                 Err(_) => {
@@ -302,24 +513,151 @@ impl SlotState {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Entry boundary scan
+//
+// An Entry is serialized as:
+//   num_hashes   u64 LE
+//   hash         32 bytes
+//   tx count     u64 LE
+//   transactions, each:
+//     signatures   compact-u16 count, 64 bytes each
+//     message      [0x80 | version] (v0 only), then the 3-byte header
+//                  (num_required_signatures, num_readonly_signed,
+//                  num_readonly_unsigned) and compact-u16 account key count
+//
+// A candidate offset must pass cheap checks on these fields before the full
+// bincode deserialize is attempted, so most offsets cost a few loads.
+// ---------------------------------------------------------------------------
+
+const ENTRY_HEADER_LEN: usize = 48;
+/// PoH entries hash far fewer times than this between records.
+const MAX_ENTRY_HASHES: u64 = 1 << 24;
+const MAX_ENTRY_TXS: u64 = 512;
+/// A transaction must fit in one packet.
+const PACKET_DATA_SIZE: usize = 1232;
+
+/// Offset of the first whole Entry in `buf`, which may begin with the tail
+/// of an Entry that started in shreds this feed never delivered. `None`
+/// until a complete Entry is buffered.
+pub fn find_entry_boundary(buf: &[u8]) -> Option<usize> {
+    (0..buf.len().saturating_sub(ENTRY_HEADER_LEN - 1))
+        .filter(|&off| plausible_entry(&buf[off..]))
+        .find(|&off| {
+            let mut cur = std::io::Cursor::new(&buf[off..]);
+            bincode::deserialize_from::<_, solana_entry::entry::Entry>(&mut cur).is_ok()
+        })
+}
+
+/// Whether an Entry could start at `b[0]`, judged by its header and the
+/// header of its first transaction.
+fn plausible_entry(b: &[u8]) -> bool {
+    if b.len() < ENTRY_HEADER_LEN {
+        return false;
+    }
+    let num_hashes = u64::from_le_bytes(b[0..8].try_into().unwrap());
+    let tx_count = u64::from_le_bytes(b[40..48].try_into().unwrap());
+    if num_hashes > MAX_ENTRY_HASHES || tx_count > MAX_ENTRY_TXS {
+        return false;
+    }
+    tx_count == 0 || plausible_tx(&b[ENTRY_HEADER_LEN..])
+}
+
+/// Whether `b` starts with a transaction that passes the message sanitize
+/// rules on signature and account counts. False when `b` is too short to
+/// tell, as the Entry could not deserialize either.
+fn plausible_tx(b: &[u8]) -> bool {
+    let Some((sigs, mut p)) = short_u16(b) else { return false };
+    let sigs = sigs as usize;
+    if sigs == 0 || sigs > PACKET_DATA_SIZE / 64 {
+        return false;
+    }
+    p += sigs * 64;
+    let Some(&prefix) = b.get(p) else { return false };
+    if prefix & 0x80 != 0 {
+        // Versioned message: only v0 exists.
+        if prefix != 0x80 {
+            return false;
+        }
+        p += 1;
+    }
+    let Some(header) = b.get(p..p + 3) else { return false };
+    let Some((keys, _)) = short_u16(&b[p + 3..]) else { return false };
+    let keys = keys as usize;
+    header[0] as usize == sigs
+        && (header[1] as usize) < sigs
+        && keys >= sigs + header[2] as usize
+        && keys <= PACKET_DATA_SIZE / 32
+}
+
+/// A compact-u16 and the bytes it took.
+fn short_u16(b: &[u8]) -> Option<(u16, usize)> {
+    let mut value = 0u32;
+    for (i, &byte) in b.iter().take(3).enumerate() {
+        value |= u32::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return u16::try_from(value).ok().map(|v| (v, i + 1));
+        }
+    }
+    None
+}
+
 // ---------------------------------------------------------------------------
 // FEC set state: buffer shards for Reed-Solomon recovery
 // ---------------------------------------------------------------------------
 
+/// An erasure shard held by reference: the shred buffer it arrived in and the
+/// offset of its erasure-coded region. Nothing is copied unless the set is
+/// actually reconstructed, which most sets never are.
+struct ShardRef {
+    buf: Arc<Vec<u8>>,
+    offset: usize,
+}
+
 struct FecSet {
     num_data: usize,
     num_coding: usize,
-    shards: HashMap<usize, Vec<u8>>,
+    /// Erasure shard length, from the variant of the coding shred that opened the set.
+    shard_len: usize,
+    shards: HashMap<usize, ShardRef>,
     recovered: bool,
+    /// Size of the shred buffers the shards keep alive.
+    bytes: u64,
 }
 
 impl FecSet {
-    fn new(num_data: usize, num_coding: usize) -> Self {
+    fn new(num_data: usize, num_coding: usize, shard_len: usize) -> Self {
         Self {
             num_data,
             num_coding,
+            shard_len,
             shards: HashMap::with_capacity(num_data + num_coding),
             recovered: false,
+            bytes: 0,
+        }
+    }
+
+    /// Keep shard `pos`, which starts at `offset` in `buf`. Truncated shreds
+    /// and positions already held are ignored. Returns the bytes now held on
+    /// top of before.
+    fn insert(&mut self, pos: usize, buf: &Arc<Vec<u8>>, offset: usize) -> u64 {
+        if buf.len() < offset + self.shard_len || self.shards.contains_key(&pos) {
+            return 0;
+        }
+        self.shards.insert(pos, ShardRef { buf: buf.clone(), offset });
+        self.bytes += buf.len() as u64;
+        buf.len() as u64
+    }
+
+    fn forensics(&self, fec_set_index: u32) -> FecSetForensics {
+        let data_shards = self.shards.keys().filter(|&&p| p < self.num_data).count();
+        FecSetForensics {
+            fec_set_index,
+            num_data: self.num_data,
+            num_coding: self.num_coding,
+            data_shards,
+            coding_shards: self.shards.len() - data_shards,
+            recovered: self.recovered,
         }
     }
 
@@ -335,9 +673,6 @@ impl FecSet {
             return Vec::new();
         }
 
-        let mut shard_opts: Vec<Option<Vec<u8>>> =
-            (0..total).map(|i| self.shards.get(&i).cloned()).collect();
-
         let missing_data: Vec<usize> =
             (0..self.num_data).filter(|i| !self.shards.contains_key(i)).collect();
 
@@ -345,6 +680,12 @@ impl FecSet {
             return Vec::new();
         }
 
+        // The only place shard bytes are copied: RS works on owned buffers.
+        let len = self.shard_len;
+        let mut shard_opts: Vec<Option<Vec<u8>>> = (0..total)
+            .map(|i| self.shards.get(&i).map(|s| s.buf[s.offset..s.offset + len].to_vec()))
+            .collect();
+
         let rs = match ReedSolomon::new(self.num_data, self.num_coding) {
             Ok(r) => r,
             Err(e) => {
@@ -383,270 +724,863 @@ impl FecSet {
 // ShredDecoder
 // ---------------------------------------------------------------------------
 
+/// Time the decoder spends in its heavier stages, for `shredtop bench
+/// --offline`. Stages are timed with [`metrics::now_ns`]; the decoder never
+/// blocks inside one, so on a replay from memory this is CPU time.
+#[derive(Default)]
+pub(crate) struct DecodeProfile {
+    pub fec_ns: AtomicU64,
+    pub entries_ns: AtomicU64,
+    pub emit_ns: AtomicU64,
+    pub entries: AtomicU64,
+}
+
 const MAX_ACTIVE_SLOTS: usize = 64;
+/// Agave's cap on data shreds per slot; a larger last index is corrupt.
+const MAX_DATA_SHREDS_PER_SLOT: u32 = 32_768;
 const SLOT_EXPIRY_DISTANCE: u64 = 32;
 
+/// Slots and FEC sets in flight, carried between [`ShredDecoder::process`]
+/// calls.
+pub(crate) struct DecodeState {
+    slots: HashMap<u64, SlotState>,
+    fec_sets: HashMap<u64, HashMap<u32, FecSet>>,
+    highest_slot: u64,
+    /// Slot that expiry was last measured against: `highest_slot`, or the
+    /// shared [`crate::cluster_tip::ClusterTip`] when one is set for expiry.
+    horizon: u64,
+    /// Receive time of the latest shred; the clock in virtual-clock mode.
+    last_recv_ns: MonotonicNs,
+    /// Approximate bytes held by `slots` and `fec_sets`.
+    bytes: u64,
+    /// Set when a [`MemoryBudget`] is; see [`ShredDecoder::enforce_budget`].
+    budget: Option<BudgetShare>,
+    /// Newest slot shed to the budget; its shreds and older are ignored.
+    shed_through: Option<u64>,
+    /// FEC sets whose signature has been checked; see [`crate::shred_verify`].
+    sig_cache: SigCache,
+}
+
+impl Default for DecodeState {
+    fn default() -> Self {
+        Self {
+            slots: HashMap::with_capacity(MAX_ACTIVE_SLOTS),
+            fec_sets: HashMap::with_capacity(MAX_ACTIVE_SLOTS),
+            highest_slot: 0,
+            horizon: 0,
+            last_recv_ns: MonotonicNs::default(),
+            bytes: 0,
+            budget: None,
+            shed_through: None,
+            sig_cache: SigCache::default(),
+        }
+    }
+}
+
+fn fec_bytes(sets: &HashMap<u32, FecSet>) -> u64 {
+    sets.values().map(|set| set.bytes).sum()
+}
+
 pub struct ShredDecoder {
-    rx: Receiver<RawShred>,
-    tx: Sender<DecodedTx>,
+    rx: ShredRx,
+    tx: Sender<Vec<DecodedTx>>,
     metrics: Arc<SourceMetrics>,
+    slot_tx: Option<Sender<SlotStats>>,
+    race: Option<Arc<ShredRaceTracker>>,
+    /// Race tap for FEC-recovered data shreds (availability race).
+    race_tx: Option<Sender<ShredArrival>>,
+    /// Track shred coverage and slot completion only; never deserialize entries.
+    headers_only: bool,
+    /// Receive timestamps are capture times on this replay clock (pcap
+    /// replay) rather than [`metrics::now_ns`] readings.
+    replay_clock: Option<ReplayClock>,
+    /// Read the clock from shred receive times instead of [`metrics::now_ns`],
+    /// so a replay produces the same stats on every run.
+    virtual_clock: bool,
+    profile: Option<Arc<DecodeProfile>>,
+    forensics: Option<ForensicsTap>,
+    repair: Option<Repair>,
+    budget: Option<MemoryBudget>,
+    verifier: Option<ShredVerifier>,
+    /// Skip shreds of slots more than this far behind the cluster tip.
+    max_lag_slots: Option<u64>,
+}
+
+/// A decoder's use of the shared [`RepairClient`]: responses come back on
+/// its own channel.
+struct Repair {
+    client: RepairClient,
+    tx: Sender<RawShred>,
+    rx: Receiver<RawShred>,
 }
 
 impl ShredDecoder {
-    pub fn new(rx: Receiver<RawShred>, tx: Sender<DecodedTx>, metrics: Arc<SourceMetrics>) -> Self {
-        Self { rx, tx, metrics }
+    pub fn new(
+        rx: impl Into<ShredRx>,
+        tx: Sender<Vec<DecodedTx>>,
+        metrics: Arc<SourceMetrics>,
+    ) -> Self {
+        Self {
+            rx: rx.into(),
+            tx,
+            metrics,
+            slot_tx: None,
+            race: None,
+            race_tx: None,
+            headers_only: false,
+            replay_clock: None,
+            virtual_clock: false,
+            profile: None,
+            forensics: None,
+            repair: None,
+            budget: None,
+            verifier: None,
+            max_lag_slots: None,
+        }
+    }
+
+    /// Report slot completions to the slot-completion race and FEC-recovered
+    /// data shreds to the availability race.
+    pub fn with_race(mut self, race: Option<Arc<ShredRaceTracker>>) -> Self {
+        self.race_tx = race.as_ref().map(|r| r.sender());
+        self.race = race;
+        self
+    }
+
+    /// Also send every finalized slot's stats to `slot_tx`. A slot's stats are
+    /// sent after all of its transactions have been sent on the tx channel.
+    pub fn with_slot_stats(mut self, slot_tx: Sender<SlotStats>) -> Self {
+        self.slot_tx = Some(slot_tx);
+        self
+    }
+
+    /// Skip Entry/transaction deserialization. Coverage, FEC recovery, slot
+    /// outcomes and races are unaffected; no transactions are produced.
+    pub fn with_headers_only(mut self, headers_only: bool) -> Self {
+        self.headers_only = headers_only;
+        self.metrics.headers_only.store(headers_only, Relaxed);
+        self
+    }
+
+    /// Send a [`SlotForensics`] record for each slot retired dropped or
+    /// partial with coverage under the tap's threshold.
+    pub fn with_forensics(mut self, forensics: Option<ForensicsTap>) -> Self {
+        self.forensics = forensics;
+        self
+    }
+
+    /// Request missing shreds of slots stuck just short of complete (see
+    /// [`crate::repair`]).
+    pub fn with_repair(mut self, repair: Option<RepairClient>) -> Self {
+        self.repair = repair.map(|client| {
+            let (tx, rx) = crossbeam_channel::bounded(1024);
+            Repair { client, tx, rx }
+        });
+        self
+    }
+
+    /// Account slots and FEC sets against `budget`, and shed the oldest slots
+    /// while it is exceeded (see [`crate::budget`]).
+    pub fn with_budget(mut self, budget: Option<MemoryBudget>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Check every shred's signature against the slot leader (see
+    /// [`crate::shred_verify`]). With `drop_invalid`, the decoder also
+    /// reports each shred that passes to the wire race; the receive threads
+    /// must then leave the race to it.
+    pub fn with_verifier(mut self, verifier: Option<ShredVerifier>) -> Self {
+        self.verifier = verifier;
+        self
+    }
+
+    /// Leave shreds of slots more than `slots` behind the highest slot any
+    /// feed has delivered (the race tracker's [`ClusterTip`]) undecoded: no
+    /// signature check, FEC recovery, slot accounting or transactions. They
+    /// were received, and raced, by then. Saves a lagging feed the CPU of
+    /// decoding slots whose transactions every consumer already has. A no-op
+    /// without [`Self::with_race`].
+    ///
+    /// [`ClusterTip`]: crate::cluster_tip::ClusterTip
+    pub fn with_max_lag_slots(mut self, slots: Option<u64>) -> Self {
+        self.max_lag_slots = slots;
+        self
+    }
+
+    /// Treat receive timestamps as capture times on `clock`, as in a pcap
+    /// replay.
+    #[cfg(feature = "capture")]
+    pub(crate) fn with_replay_clock(mut self, clock: ReplayClock) -> Self {
+        self.replay_clock = Some(clock);
+        self
+    }
+
+    /// Take the time from receive timestamps, capture times on `clock`,
+    /// rather than the wall clock. Stage timings recorded by a profile are
+    /// still real.
+    #[cfg(feature = "capture")]
+    pub(crate) fn with_virtual_clock(mut self, clock: ReplayClock) -> Self {
+        self.replay_clock = Some(clock);
+        self.virtual_clock = true;
+        self
+    }
+
+    /// Receive time `t` as Unix-epoch ns: the capture time under a replay
+    /// clock, else the wall-clock time.
+    fn unix_ns(&self, t: MonotonicNs) -> u64 {
+        match self.replay_clock {
+            Some(clock) => clock.to_realtime(t).0,
+            None => t.to_realtime().0,
+        }
+    }
+
+    /// Current time for slot bookkeeping: the shred's own receive time under
+    /// a virtual clock.
+    fn clock(&self, recv_ns: MonotonicNs) -> MonotonicNs {
+        if self.virtual_clock { recv_ns } else { MonotonicNs::now() }
+    }
+
+    /// Accumulate per-stage timings into `profile`.
+    #[cfg(feature = "capture")]
+    pub(crate) fn with_profile(mut self, profile: Arc<DecodeProfile>) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Run `f`, charging its duration to `stage` when profiling.
+    fn timed<T>(&self, stage: fn(&DecodeProfile) -> &AtomicU64, f: impl FnOnce() -> T) -> T {
+        let Some(ref p) = self.profile else { return f() };
+        let t0 = metrics::now_ns();
+        let out = f();
+        stage(p).fetch_add(metrics::now_ns() - t0, Relaxed);
+        out
+    }
+
+    /// Stats of a slot completed by a shred received at `recv_ns`.
+    fn complete_stats(
+        &self,
+        state: &SlotState,
+        slot: u64,
+        now: MonotonicNs,
+        recv_ns: MonotonicNs,
+        fec: Option<&HashMap<u32, FecSet>>,
+    ) -> SlotStats {
+        let mut stats = state.stats(slot, SlotOutcome::Complete, now, fec);
+        stats.completed_at_ns = Some(self.unix_ns(recv_ns));
+        stats
+    }
+
+    /// Payload bytes to keep for entry deserialization — none in headers-only
+    /// mode, where a slot's payload map only records which indices arrived.
+    fn keep_payload(&self, payload: &[u8]) -> Vec<u8> {
+        if self.headers_only {
+            Vec::new()
+        } else {
+            payload.to_vec()
+        }
+    }
+
+    fn decode_entries(&self, state: &mut SlotState) -> Vec<VersionedTransaction> {
+        if self.headers_only {
+            return Vec::new();
+        }
+        let before = state.entries_decoded;
+        let txs = self.timed(|p| &p.entries_ns, || state.try_deserialize());
+        if let Some(ref p) = self.profile {
+            p.entries.fetch_add((state.entries_decoded - before) as u64, Relaxed);
+        }
+        txs
+    }
+
+    /// Forward a batch of decoded transactions of `slot` as one channel message.
+    fn emit_txs(
+        &self,
+        txs: Vec<VersionedTransaction>,
+        slot: u64,
+        recv_ns: MonotonicNs,
+        decode_done: MonotonicNs,
+    ) {
+        if txs.is_empty() {
+            return;
+        }
+        self.timed(|p| &p.emit_ns, || {
+            let batch = txs
+                .into_iter()
+                .map(|tx| DecodedTx {
+                    transaction: tx,
+                    slot,
+                    shred_recv_ns: recv_ns,
+                    decode_done_ns: decode_done,
+                })
+                .collect();
+            send_batch(&self.tx, batch, &self.metrics);
+        })
+    }
+
+    /// Outcome of a slot finalized before completing. Without decoding there
+    /// are no transactions to go by, so any received shred makes it partial.
+    fn incomplete_outcome(&self, state: &SlotState) -> SlotOutcome {
+        let partial = if self.headers_only { state.shreds_seen > 0 } else { state.txs_decoded > 0 };
+        if partial {
+            self.metrics.slots_partial.fetch_add(1, Relaxed);
+            SlotOutcome::Partial
+        } else {
+            self.metrics.slots_dropped.fetch_add(1, Relaxed);
+            SlotOutcome::Dropped
+        }
+    }
+
+    /// Tell the other feeds' decoders how many data shreds `slot` has.
+    fn share_slot_length(&self, slot: u64, last_index: u32) {
+        if let Some(ref race) = self.race {
+            race.slot_lengths().record(slot, last_index);
+        }
+    }
+
+    /// Count a retired slot's coverage by position. The slot's length comes
+    /// from its own last-in-slot shred or, failing that, from another feed;
+    /// a slot whose length no feed learned is left out.
+    fn record_position_coverage(&self, slot: u64, state: &SlotState) {
+        let len = state.last_index.map(|i| i.saturating_add(1)).or_else(|| {
+            self.race.as_ref().and_then(|r| r.slot_lengths().get(slot))
+        });
+        if let Some(len) = len.filter(|&n| n <= MAX_DATA_SHREDS_PER_SLOT) {
+            self.metrics.position_coverage.record_slot(len, |idx| state.was_received(idx));
+        }
+    }
+
+    /// Record an incomplete slot's received indices and FEC sets if its
+    /// coverage is under the forensics threshold.
+    fn dump_forensics(
+        &self,
+        state: &SlotState,
+        stats: &SlotStats,
+        fec: Option<&HashMap<u32, FecSet>>,
+        now: MonotonicNs,
+    ) {
+        let Some(ref tap) = self.forensics else { return };
+        let coverage_pct = if stats.shreds_expected == 0 {
+            0.0
+        } else {
+            (stats.shreds_seen as f64 * 100.0 / stats.shreds_expected as f64).min(100.0)
+        };
+        if coverage_pct >= tap.min_coverage_pct {
+            return;
+        }
+        let mut fec_sets: Vec<FecSetForensics> =
+            fec.into_iter().flatten().map(|(&idx, set)| set.forensics(idx)).collect();
+        fec_sets.sort_unstable_by_key(|f| f.fec_set_index);
+        let _ = tap.tx.try_send(SlotForensics {
+            source: self.metrics.name.clone(),
+            slot: stats.slot,
+            retired_at_ns: if self.virtual_clock { self.unix_ns(now) } else { now.to_realtime().0 },
+            outcome: stats.outcome.clone(),
+            coverage_pct,
+            shreds_seen: stats.shreds_seen,
+            shreds_expected: stats.shreds_expected,
+            fec_recovered: stats.fec_recovered,
+            last_index: state.last_index,
+            received: forensics::runs(&state.received),
+            fec_sets,
+        });
+    }
+
+    fn finish_slot(&self, stats: SlotStats) {
+        if let Some(ref stx) = self.slot_tx {
+            let _ = stx.send(stats.clone());
+        }
+        self.metrics.push_slot_stats(stats);
+    }
+
+    /// Enter the slot-completion race once every data shred from index 0
+    /// through the last is here. Completion for decoding is anchored at the
+    /// first shred seen; a feed relaying only the tail of each block would
+    /// finish that first, so it does not count here.
+    fn race_slot_complete(&self, slot: u64, state: &mut SlotState, recv_ns: MonotonicNs) {
+        if state.complete_raced || !state.has_full_range() {
+            return;
+        }
+        state.complete_raced = true;
+        if let Some(ref race) = self.race {
+            race.record_slot_complete(self.metrics.name.clone(), slot, recv_ns);
+        }
+    }
+
+    /// Finalize a slot leaving the decoder, unless it already completed.
+    fn retire(
+        &self,
+        slot: u64,
+        state: &SlotState,
+        fec: Option<&HashMap<u32, FecSet>>,
+        now: MonotonicNs,
+    ) {
+        if !state.counted {
+            let outcome = self.incomplete_outcome(state);
+            let stats = state.stats(slot, outcome, now, fec);
+            self.dump_forensics(state, &stats, fec, now);
+            self.finish_slot(stats);
+        }
+        self.record_position_coverage(slot, state);
+    }
+
+    /// Report the decoder's memory use to the budget and, while the decoder
+    /// holds more than its part of it, retire the oldest slot and drop its
+    /// FEC sets. The newest slot is always kept, so one decoder cannot shed
+    /// itself empty.
+    fn enforce_budget(&self, st: &mut DecodeState) {
+        let Some(share) = st.budget.as_mut() else { return };
+        share.set(st.bytes);
+        while share.over() {
+            let oldest = st.slots.keys().chain(st.fec_sets.keys()).copied().min();
+            let Some(slot) = oldest.filter(|&s| s < st.highest_slot) else { break };
+            let fec = st.fec_sets.remove(&slot);
+            if let Some(state) = st.slots.remove(&slot) {
+                self.retire(slot, &state, fec.as_ref(), self.clock(st.last_recv_ns));
+                st.bytes -= state.bytes;
+                share.budget().count_slot_shed();
+            }
+            if let Some(ref sets) = fec {
+                st.bytes -= fec_bytes(sets);
+                share.budget().count_fec_sets_shed(sets.len());
+            }
+            st.shed_through = Some(slot);
+            share.set(st.bytes);
+        }
     }
 
     pub fn run(&self) -> Result<()> {
         tracing::info!("shred decoder started");
 
-        let mut slots: HashMap<u64, SlotState> = HashMap::with_capacity(MAX_ACTIVE_SLOTS);
-        let mut fec_sets: HashMap<u64, HashMap<u32, FecSet>> =
-            HashMap::with_capacity(MAX_ACTIVE_SLOTS);
-        let mut highest_slot: u64 = 0;
+        let mut st = DecodeState {
+            budget: self.budget.as_ref().map(|b| b.share(BudgetUser::Decoder)),
+            ..Default::default()
+        };
+        match self.repair {
+            Some(ref repair) => self.run_with_repair(&mut st, repair),
+            None => {
+                while let Some(raw_shred) = self.rx.recv() {
+                    self.process(&mut st, raw_shred);
+                }
+            }
+        }
+        self.finish(st);
 
-        for raw_shred in &self.rx {
-            let decode_start = metrics::now_ns();
+        Ok(())
+    }
 
-            let (slot, shred_index, fec_set_index) = match shred_slot_index(&raw_shred.data) {
-                Some(si) => si,
-                None => continue,
-            };
+    /// [`Self::run`] with repair: wake up at least every few milliseconds to
+    /// feed in repaired shreds and look for stuck slots.
+    fn run_with_repair(&self, st: &mut DecodeState, repair: &Repair) {
+        let settings = repair.client.settings();
+        let stuck_ns = settings.stuck_ms * 1_000_000;
+        let poll = std::time::Duration::from_millis((settings.stuck_ms / 4).clamp(1, 50));
+        let mut next_check = MonotonicNs::default();
+        loop {
+            match self.rx.recv_timeout(poll) {
+                Ok(raw_shred) => self.process(st, raw_shred),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+            for raw_shred in repair.rx.try_iter() {
+                self.process_repaired(st, raw_shred);
+            }
+            let now = MonotonicNs::now();
+            if now >= next_check {
+                self.request_repairs(st, repair, now, stuck_ns);
+                next_check = now.saturating_add(poll.as_nanos() as u64);
+            }
+        }
+    }
 
-            if slot > highest_slot {
-                highest_slot = slot;
-                slots.retain(|&s, state| {
-                    if s + SLOT_EXPIRY_DISTANCE >= highest_slot {
-                        return true;
-                    }
-                    if !state.counted {
-                        if state.txs_decoded > 0 {
-                            self.metrics.slots_partial.fetch_add(1, Relaxed);
-                            self.metrics.push_slot_stats(SlotStats {
-                                slot: s,
-                                shreds_seen: state.shreds_seen,
-                                fec_recovered: state.fec_recovered_count,
-                                txs_decoded: state.txs_decoded,
-                                outcome: SlotOutcome::Partial,
-                            });
-                        } else {
-                            self.metrics.slots_dropped.fetch_add(1, Relaxed);
-                            self.metrics.push_slot_stats(SlotStats {
-                                slot: s,
-                                shreds_seen: state.shreds_seen,
-                                fec_recovered: state.fec_recovered_count,
-                                txs_decoded: 0,
-                                outcome: SlotOutcome::Dropped,
-                            });
-                        }
+    /// Decode a shred a repair peer sent, counting it if it filled a gap.
+    fn process_repaired(&self, st: &mut DecodeState, raw_shred: RawShred) {
+        let Some((slot, idx, _)) = shred_slot_index(&raw_shred.data) else {
+            return;
+        };
+        let Some(state) = st.slots.get(&slot) else {
+            return;
+        };
+        if state.counted || state.was_received(idx) {
+            return;
+        }
+        self.metrics.shreds_repaired.fetch_add(1, Relaxed);
+        self.check_and_decode(st, raw_shred, false);
+    }
+
+    /// Ask for the missing shreds of every slot whose last shred has arrived,
+    /// that lacks at most `max_missing` data shreds and that has not moved
+    /// for `stuck_ns`.
+    fn request_repairs(
+        &self,
+        st: &mut DecodeState,
+        repair: &Repair,
+        now: MonotonicNs,
+        stuck_ns: u64,
+    ) {
+        let settings = repair.client.settings();
+        for (&slot, state) in st.slots.iter_mut() {
+            if state.counted || state.repair_attempts >= settings.max_attempts {
+                continue;
+            }
+            let idle_since = state.last_touch_ns.max(state.repair_requested_ns);
+            if now.since(idle_since) < stuck_ns {
+                continue;
+            }
+            // One past the limit tells a slot missing too many apart.
+            let missing = state.missing(settings.max_missing as usize + 1);
+            if missing.is_empty() || missing.len() > settings.max_missing as usize {
+                continue;
+            }
+            state.repair_attempts += 1;
+            state.repair_requested_ns = now;
+            for idx in missing {
+                match repair.client.request(slot, idx, &repair.tx) {
+                    Ok(()) => {
+                        self.metrics.repair_requests.fetch_add(1, Relaxed);
                     }
-                    false
-                });
-                fec_sets.retain(|&s, _| s + SLOT_EXPIRY_DISTANCE >= highest_slot);
+                    Err(e) => tracing::debug!("repair request for {}/{} failed: {}", slot, idx, e),
+                }
             }
+        }
+    }
 
-            if highest_slot.saturating_sub(slot) > SLOT_EXPIRY_DISTANCE {
-                continue;
+    /// Decode one shred. [`Self::run`] calls this for every shred it
+    /// receives; a deterministic replay drives it directly.
+    pub(crate) fn process(&self, st: &mut DecodeState, raw_shred: RawShred) {
+        self.check_and_decode(st, raw_shred, true);
+    }
+
+    /// [`Self::process`]; `raced` is false for repaired shreds, which never
+    /// enter the wire race. When the verifier gates the race, even a stale
+    /// shred is checked, so that it still races.
+    fn check_and_decode(&self, st: &mut DecodeState, raw_shred: RawShred, raced: bool) {
+        let races_here = raced
+            && self.race_tx.is_some()
+            && self.verifier.as_ref().is_some_and(ShredVerifier::gates_race);
+        if !races_here && self.is_stale(&raw_shred.data) {
+            self.metrics.shreds_decode_skipped.fetch_add(1, Relaxed);
+            return;
+        }
+        if let Some(ref verifier) = self.verifier {
+            if !verifier.admit(&mut st.sig_cache, &raw_shred.data, &self.metrics) {
+                return;
             }
+        }
+        if races_here {
+            self.race_arrival(&raw_shred);
+            if self.is_stale(&raw_shred.data) {
+                self.metrics.shreds_decode_skipped.fetch_add(1, Relaxed);
+                return;
+            }
+        }
+        self.decode_shred(st, raw_shred);
+        self.enforce_budget(st);
+    }
 
-            let now = metrics::now_ns();
+    /// Report a verified shred to the wire race, as a receive thread would.
+    fn race_arrival(&self, raw_shred: &RawShred) {
+        let (Some(rtx), Some((slot, idx, _))) =
+            (self.race_tx.as_ref(), shred_slot_index(&raw_shred.data))
+        else {
+            return;
+        };
+        let _ = rtx.try_send(ShredArrival {
+            source: self.metrics.name.clone(),
+            slot,
+            idx,
+            recv_ns: raw_shred.recv_timestamp_ns,
+            recovered: false,
+        });
+    }
 
-            // ── Coding shred path ────────────────────────────────────────────
-            if let Some(code_info) = parse_coding_header(&raw_shred.data) {
-                let num_data = code_info.num_data as usize;
-                let num_coding = code_info.num_coding as usize;
-                let code_position = code_info.position as usize;
-                let shard_pos = num_data + code_position;
+    /// Whether the shred's slot is past [`Self::with_max_lag_slots`].
+    fn is_stale(&self, data: &[u8]) -> bool {
+        let (Some(lag), Some(race)) = (self.max_lag_slots, self.race.as_ref()) else {
+            return false;
+        };
+        let tip = race.cluster_tip().get();
+        shred_slot_index(data).is_some_and(|(slot, _, _)| slot.saturating_add(lag) < tip)
+    }
 
-                if code_position >= num_coding {
-                    continue;
-                }
+    fn decode_shred(&self, st: &mut DecodeState, raw_shred: RawShred) {
+        st.last_recv_ns = raw_shred.recv_timestamp_ns;
+        // Shared with any FEC set that keeps one of this shred's shards.
+        let data = Arc::new(raw_shred.data);
+        let decode_start = self.clock(raw_shred.recv_timestamp_ns);
 
-                let slot_fec = fec_sets.entry(slot).or_default();
-                let fec = slot_fec
-                    .entry(fec_set_index)
-                    .or_insert_with(|| {
-                        // Count expected data shreds per FEC set as we discover them.
-                        // This is the correct denominator for coverage on tail-only feeds
-                        // where the last-in-slot marker rarely arrives.
-                        self.metrics
-                            .coverage_shreds_expected
-                            .fetch_add(num_data as u64, Relaxed);
-                        FecSet::new(num_data, num_coding)
-                    });
+        let (slot, shred_index, fec_set_index) = match shred_slot_index(&data) {
+            Some(si) => si,
+            None => return,
+        };
 
-                if fec.num_data != num_data || fec.num_coding != num_coding {
-                    continue;
+        st.highest_slot = st.highest_slot.max(slot);
+        let shared_tip = self.race.as_ref().and_then(|race| {
+            race.cluster_tip().observe(slot, decode_start);
+            race.cluster_tip().expiry_tip()
+        });
+        let horizon = shared_tip.unwrap_or(st.highest_slot);
+        if horizon > st.horizon {
+            st.horizon = horizon;
+            let (fec_sets, highest_slot) = (&st.fec_sets, st.highest_slot);
+            let mut freed = 0;
+            let mut shared_expired = 0;
+            st.slots.retain(|&s, state| {
+                if s.saturating_add(SLOT_EXPIRY_DISTANCE) >= horizon {
+                    return true;
+                }
+                if s.saturating_add(SLOT_EXPIRY_DISTANCE) >= highest_slot {
+                    shared_expired += 1;
+                }
+                self.retire(s, state, fec_sets.get(&s), decode_start);
+                freed += state.bytes;
+                false
+            });
+            st.fec_sets.retain(|&s, sets| {
+                let keep = s.saturating_add(SLOT_EXPIRY_DISTANCE) >= horizon;
+                if !keep {
+                    freed += fec_bytes(sets);
                 }
+                keep
+            });
+            st.bytes -= freed;
+            if shared_expired > 0 {
+                self.metrics.slots_expired_shared_tip.fetch_add(shared_expired, Relaxed);
+            }
+        }
+
+        if st.horizon.saturating_sub(slot) > SLOT_EXPIRY_DISTANCE
+            || st.shed_through.is_some_and(|s| slot <= s)
+        {
+            return;
+        }
+
+        let (slots, fec_sets) = (&mut st.slots, &mut st.fec_sets);
+        let now = self.clock(raw_shred.recv_timestamp_ns);
 
-                fec.shards.entry(shard_pos).or_insert_with(|| {
-                    let mut buf = raw_shred.data.clone();
-                    buf.resize(SHRED_RS_SIZE, 0);
-                    buf
+        // ── Coding shred path ────────────────────────────────────────────
+        if let Some(code_info) = parse_coding_header(&data) {
+            let num_data = code_info.num_data as usize;
+            let num_coding = code_info.num_coding as usize;
+            let code_position = code_info.position as usize;
+            let shard_pos = num_data + code_position;
+
+            if code_position >= num_coding {
+                return;
+            }
+            let Some(layout) = erasure_shard(data[VARIANT_OFF]) else {
+                return;
+            };
+
+            let slot_fec = fec_sets.entry(slot).or_default();
+            let fec = slot_fec
+                .entry(fec_set_index)
+                .or_insert_with(|| {
+                    // Count expected data shreds per FEC set as we discover them.
+                    // This is the correct denominator for coverage on tail-only feeds
+                    // where the last-in-slot marker rarely arrives.
+                    self.metrics
+                        .coverage_shreds_expected
+                        .fetch_add(num_data as u64, Relaxed);
+                    FecSet::new(num_data, num_coding, layout.len)
                 });
 
-                if fec.ready_to_recover() {
-                    let recovered = fec.reconstruct();
-                    if !recovered.is_empty() {
-                        let slot_state = slots.entry(slot).or_insert_with(|| {
-                            self.metrics.slots_attempted.fetch_add(1, Relaxed);
-                            SlotState::new(now)
-                        });
-                        slot_state.last_touch_ns = now;
-
-                        let mut recovered_count = 0u64;
-                        for (data_shard_idx, shard_bytes) in recovered {
-                            let global_idx =
-                                fec_set_index.saturating_add(data_shard_idx as u32);
-                            if slot_state.data_payloads.contains_key(&global_idx) {
-                                continue;
+            if fec.num_data != num_data
+                || fec.num_coding != num_coding
+                || fec.shard_len != layout.len
+            {
+                return;
+            }
+
+            st.bytes += fec.insert(shard_pos, &data, layout.offset);
+
+            if fec.ready_to_recover() {
+                let recovered = self.timed(|p| &p.fec_ns, || fec.reconstruct());
+                if !recovered.is_empty() {
+                    let slot_state = slots.entry(slot).or_insert_with(|| {
+                        self.metrics.slots_attempted.fetch_add(1, Relaxed);
+                        st.bytes += SLOT_BASE_BYTES;
+                        SlotState::new(now)
+                    });
+                    slot_state.last_touch_ns = now;
+
+                    let mut recovered_count = 0u64;
+                    for (data_shard_idx, shard) in recovered {
+                        let global_idx =
+                            fec_set_index.saturating_add(data_shard_idx as u32);
+                        if global_idx >= MAX_DATA_SHREDS_PER_SLOT
+                            || slot_state.data_payloads.contains_key(&global_idx)
+                        {
+                            continue;
+                        }
+                        // The signature is not erasure-coded; the data
+                        // headers that follow it are.
+                        let mut shred = vec![0u8; SIGNATURE_SIZE];
+                        shred.extend_from_slice(&shard);
+                        if let Some((last_in_slot, payload)) = parse_data_payload(&shred) {
+                            slot_state.set_first_index(global_idx);
+                            if global_idx > slot_state.max_index {
+                                slot_state.max_index = global_idx;
                             }
-                            if let Some((last_in_slot, payload)) =
-                                parse_data_payload(&shard_bytes)
-                            {
-                                slot_state.set_first_index(global_idx);
-                                if global_idx > slot_state.max_index {
-                                    slot_state.max_index = global_idx;
-                                }
-                                if last_in_slot {
-                                    slot_state.last_seen = true;
-                                }
-                                slot_state.data_payloads.insert(global_idx, payload);
-                                recovered_count += 1;
+                            if last_in_slot {
+                                slot_state.mark_last(global_idx);
+                                self.share_slot_length(slot, global_idx);
                             }
-                        }
-
-                        if recovered_count > 0 {
-                            self.metrics
-                                .fec_recovered_shreds
-                                .fetch_add(recovered_count, Relaxed);
-                            self.metrics
-                                .coverage_shreds_seen
-                                .fetch_add(recovered_count, Relaxed);
-                            slot_state.shreds_seen += recovered_count as u32;
-                            slot_state.fec_recovered_count += recovered_count as u32;
-
-                            slot_state.flush_contiguous();
-
-                            if slot_state.last_seen
-                                && slot_state.next_contiguous > slot_state.max_index
-                                && !slot_state.counted
-                            {
-                                self.metrics.slots_complete.fetch_add(1, Relaxed);
-                                slot_state.counted = true;
-                                self.metrics.push_slot_stats(SlotStats {
+                            st.bytes += slot_state.mark_received(global_idx);
+                            let payload = self.keep_payload(payload);
+                            slot_state.bytes += payload.len() as u64;
+                            st.bytes += payload.len() as u64;
+                            slot_state.data_payloads.insert(global_idx, payload);
+                            recovered_count += 1;
+                            // Available as of the packet that completed the set.
+                            if let Some(ref rtx) = self.race_tx {
+                                let _ = rtx.try_send(ShredArrival {
+                                    source: self.metrics.name.clone(),
                                     slot,
-                                    shreds_seen: slot_state.shreds_seen,
-                                    fec_recovered: slot_state.fec_recovered_count,
-                                    txs_decoded: slot_state.txs_decoded,
-                                    outcome: SlotOutcome::Complete,
+                                    idx: global_idx,
+                                    recv_ns: raw_shred.recv_timestamp_ns,
+                                    recovered: true,
                                 });
                             }
+                        }
+                    }
 
-                            let txs = slot_state.try_deserialize();
-                            if !txs.is_empty() {
-                                let decode_done = metrics::now_ns();
-                                metrics::METRICS.record_stage(
-                                    &metrics::METRICS.decode_ns,
-                                    decode_done - decode_start,
-                                );
-
-                                let tx_count = txs.len() as u32;
-                                slot_state.txs_decoded += tx_count;
-                                self.metrics.txs_decoded.fetch_add(tx_count as u64, Relaxed);
-
-                                for tx in txs {
-                                    let decoded = DecodedTx {
-                                        transaction: tx,
-                                        slot,
-                                        shred_recv_ns: raw_shred.recv_timestamp_ns,
-                                        decode_done_ns: decode_done,
-                                    };
-                                    let _ = self.tx.try_send(decoded);
-                                }
-                            }
+                    if recovered_count > 0 {
+                        self.metrics
+                            .fec_recovered_shreds
+                            .fetch_add(recovered_count, Relaxed);
+                        self.metrics
+                            .coverage_shreds_seen
+                            .fetch_add(recovered_count, Relaxed);
+                        slot_state.shreds_seen += recovered_count as u32;
+                        slot_state.fec_recovered_count += recovered_count as u32;
+
+                        slot_state.flush_contiguous();
+
+                        let txs = self.decode_entries(slot_state);
+                        if !txs.is_empty() {
+                            let decode_done = self.clock(raw_shred.recv_timestamp_ns);
+                            metrics::METRICS.record_stage(
+                                &metrics::METRICS.decode_ns,
+                                decode_done.since(decode_start),
+                            );
+
+                            let tx_count = txs.len() as u32;
+                            slot_state.txs_decoded += tx_count;
+                            self.metrics.txs_decoded.fetch_add(tx_count as u64, Relaxed);
+
+                            self.emit_txs(txs, slot, raw_shred.recv_timestamp_ns, decode_done);
                         }
+
+                        // Checked after deserializing so the final batch of
+                        // transactions is included in the slot's stats.
+                        if slot_state.last_seen
+                            && slot_state.next_contiguous > slot_state.max_index
+                            && !slot_state.counted
+                        {
+                            self.metrics.slots_complete.fetch_add(1, Relaxed);
+                            slot_state.counted = true;
+                            self.finish_slot(self.complete_stats(
+                                slot_state,
+                                slot,
+                                now,
+                                raw_shred.recv_timestamp_ns,
+                                Some(slot_fec),
+                            ));
+                        }
+                        self.race_slot_complete(slot, slot_state, raw_shred.recv_timestamp_ns);
                     }
                 }
-
-                continue;
             }
 
-            // ── Data shred path ──────────────────────────────────────────────
-            let (last_in_slot, payload) = match parse_data_payload(&raw_shred.data) {
-                Some(d) => d,
-                None => continue,
-            };
+            return;
+        }
 
-            self.metrics.coverage_shreds_seen.fetch_add(1, Relaxed);
+        // ── Data shred path ──────────────────────────────────────────────
+        let (last_in_slot, payload) = match parse_data_payload(&data) {
+            Some(d) => d,
+            None => return,
+        };
+        if shred_index >= MAX_DATA_SHREDS_PER_SLOT {
+            return;
+        }
 
-            let state = slots.entry(slot).or_insert_with(|| {
-                self.metrics.slots_attempted.fetch_add(1, Relaxed);
-                SlotState::new(now)
-            });
-            state.last_touch_ns = now;
-
-            let data_shard_idx = shred_index.checked_sub(fec_set_index).map(|i| i as usize);
-            if let Some(shard_pos) = data_shard_idx {
-                let slot_fec = fec_sets.entry(slot).or_default();
-                if let Some(fec) = slot_fec.get_mut(&fec_set_index) {
-                    fec.shards.entry(shard_pos).or_insert_with(|| {
-                        let mut buf = raw_shred.data.clone();
-                        buf.resize(SHRED_RS_SIZE, 0);
-                        buf
-                    });
+        self.metrics.coverage_shreds_seen.fetch_add(1, Relaxed);
+
+        let state = slots.entry(slot).or_insert_with(|| {
+            self.metrics.slots_attempted.fetch_add(1, Relaxed);
+            st.bytes += SLOT_BASE_BYTES;
+            SlotState::new(now)
+        });
+        state.last_touch_ns = now;
+
+        let data_shard_idx = shred_index.checked_sub(fec_set_index).map(|i| i as usize);
+        let layout = erasure_shard(data[VARIANT_OFF]);
+        if let (Some(shard_pos), Some(layout)) = (data_shard_idx, layout) {
+            let slot_fec = fec_sets.entry(slot).or_default();
+            if let Some(fec) = slot_fec.get_mut(&fec_set_index) {
+                if fec.shard_len == layout.len {
+                    st.bytes += fec.insert(shard_pos, &data, layout.offset);
                 }
             }
+        }
 
-            state.set_first_index(shred_index);
+        state.set_first_index(shred_index);
 
-            if shred_index > state.max_index {
-                state.max_index = shred_index;
-            }
-            if last_in_slot {
-                state.last_seen = true;
-            }
+        if shred_index > state.max_index {
+            state.max_index = shred_index;
+        }
+        if last_in_slot {
+            state.mark_last(shred_index);
+            self.share_slot_length(slot, shred_index);
+        }
+        st.bytes += state.mark_received(shred_index);
+
+        let payload = self.keep_payload(payload);
+        let payload_len = payload.len() as u64;
+        if state.data_payloads.insert(shred_index, payload).is_none() {
+            state.shreds_seen += 1;
+            state.gaps.record(raw_shred.recv_timestamp_ns);
+            state.bytes += payload_len;
+            st.bytes += payload_len;
+        }
+        state.flush_contiguous();
 
-            if state.data_payloads.insert(shred_index, payload).is_none() {
-                state.shreds_seen += 1;
-            }
-            state.flush_contiguous();
+        let txs = self.decode_entries(state);
+        if !txs.is_empty() {
+            let decode_done = self.clock(raw_shred.recv_timestamp_ns);
+            metrics::METRICS
+                .record_stage(&metrics::METRICS.decode_ns, decode_done.since(decode_start));
 
-            if state.last_seen && state.next_contiguous > state.max_index && !state.counted {
-                self.metrics.slots_complete.fetch_add(1, Relaxed);
-                state.counted = true;
-                self.metrics.push_slot_stats(SlotStats {
-                    slot,
-                    shreds_seen: state.shreds_seen,
-                    fec_recovered: state.fec_recovered_count,
-                    txs_decoded: state.txs_decoded,
-                    outcome: SlotOutcome::Complete,
-                });
-            }
+            let tx_count = txs.len() as u32;
+            state.txs_decoded += tx_count;
+            self.metrics.txs_decoded.fetch_add(tx_count as u64, Relaxed);
 
-            let txs = state.try_deserialize();
-            if !txs.is_empty() {
-                let decode_done = metrics::now_ns();
-                metrics::METRICS
-                    .record_stage(&metrics::METRICS.decode_ns, decode_done - decode_start);
-
-                let tx_count = txs.len() as u32;
-                state.txs_decoded += tx_count;
-                self.metrics.txs_decoded.fetch_add(tx_count as u64, Relaxed);
-
-                for tx in txs {
-                    let decoded = DecodedTx {
-                        transaction: tx,
-                        slot,
-                        shred_recv_ns: raw_shred.recv_timestamp_ns,
-                        decode_done_ns: decode_done,
-                    };
-                    let _ = self.tx.try_send(decoded);
-                }
-            }
+            self.emit_txs(txs, slot, raw_shred.recv_timestamp_ns, decode_done);
         }
 
-        Ok(())
+        if state.last_seen && state.next_contiguous > state.max_index && !state.counted {
+            self.metrics.slots_complete.fetch_add(1, Relaxed);
+            state.counted = true;
+            let recv_ns = raw_shred.recv_timestamp_ns;
+            let stats = self.complete_stats(state, slot, now, recv_ns, fec_sets.get(&slot));
+            self.finish_slot(stats);
+        }
+        self.race_slot_complete(slot, state, raw_shred.recv_timestamp_ns);
+    }
+
+    /// Input closed: finalize slots still in flight, oldest first, so
+    /// offline consumers get an outcome for every slot they fed in.
+    pub(crate) fn finish(&self, st: DecodeState) {
+        let now = if self.virtual_clock { st.last_recv_ns } else { MonotonicNs::now() };
+        let mut remaining: Vec<(u64, SlotState)> = st.slots.into_iter().collect();
+        remaining.sort_unstable_by_key(|(s, _)| *s);
+        for (s, state) in remaining.into_iter().filter(|(_, st)| !st.counted) {
+            let outcome = self.incomplete_outcome(&state);
+            let stats = state.stats(s, outcome, now, st.fec_sets.get(&s));
+            self.dump_forensics(&state, &stats, st.fec_sets.get(&s), now);
+            self.finish_slot(stats);
+        }
     }
 }
 
@@ -657,10 +1591,69 @@ impl ShredDecoder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cluster_tip::TIP_SEED_OBSERVATIONS;
+    use crate::coverage::SlotExpiry;
+
+    /// Agree on `slot` often enough for it to seed the cluster tip.
+    fn seed_tip(race: &ShredRaceTracker, slot: u64) {
+        for _ in 0..TIP_SEED_OBSERVATIONS {
+            race.cluster_tip().observe(slot, MonotonicNs(0));
+        }
+    }
+
+    /// A legacy transaction header as serialized: `sigs` signatures, then
+    /// the message header and the account key count.
+    fn tx_header(sigs: u8, header: [u8; 3], keys: u8) -> Vec<u8> {
+        let mut b = vec![sigs];
+        b.resize(1 + sigs as usize * 64, 0xab);
+        b.extend(header);
+        b.push(keys);
+        b
+    }
+
+    #[test]
+    fn test_plausible_tx() {
+        assert!(plausible_tx(&tx_header(1, [1, 0, 1], 3)));
+        assert!(plausible_tx(&tx_header(2, [2, 1, 1], 4)));
+        // A v0 message carries its version byte before the header.
+        let mut v0 = tx_header(1, [1, 0, 1], 3);
+        v0.insert(65, 0x80);
+        assert!(plausible_tx(&v0));
+        v0[65] = 0x81;
+        assert!(!plausible_tx(&v0), "only v0 exists");
+
+        assert!(!plausible_tx(&tx_header(0, [0, 0, 0], 0)), "no fee payer");
+        assert!(!plausible_tx(&tx_header(1, [2, 0, 1], 3)), "signature count mismatch");
+        assert!(!plausible_tx(&tx_header(1, [1, 1, 0], 2)), "fee payer read-only");
+        assert!(!plausible_tx(&tx_header(1, [1, 0, 3], 3)), "too few account keys");
+        assert!(!plausible_tx(&tx_header(1, [1, 0, 1], 3)[..66]), "truncated");
+    }
+
+    #[test]
+    fn test_short_u16() {
+        assert_eq!(short_u16(&[0x05]), Some((5, 1)));
+        assert_eq!(short_u16(&[0x80, 0x01]), Some((128, 2)));
+        assert_eq!(short_u16(&[0xff, 0xff, 0x03]), Some((u16::MAX, 3)));
+        assert_eq!(short_u16(&[0xff, 0xff, 0x04]), None);
+        assert_eq!(short_u16(&[0x80]), None);
+    }
+
+    #[test]
+    fn test_entry_boundary_skips_a_partial_entry() {
+        let tick = solana_entry::entry::Entry { num_hashes: 12_500, ..Default::default() };
+        let mut tick_bytes = bincode::serialize(&tick).unwrap();
+        tick_bytes[8..40].fill(0xab);
+        let mut buf = tick_bytes[28..].to_vec();
+        buf.extend(&tick_bytes);
+        buf.extend(&tick_bytes);
+
+        assert_eq!(find_entry_boundary(&buf), Some(20));
+        assert_eq!(find_entry_boundary(&buf[..20 + 47]), None, "no whole entry yet");
+    }
 
     #[test]
     fn test_flush_contiguous_in_order() {
-        let mut state = SlotState::new(0);
+        let mut state = SlotState::new(MonotonicNs(0));
         state.set_first_index(0);
 
         state.data_payloads.insert(0, vec![1, 2, 3]);
@@ -675,7 +1668,7 @@ mod tests {
 
     #[test]
     fn test_flush_contiguous_out_of_order() {
-        let mut state = SlotState::new(0);
+        let mut state = SlotState::new(MonotonicNs(0));
         state.set_first_index(0);
 
         state.data_payloads.insert(2, vec![7, 8, 9]);
@@ -695,6 +1688,63 @@ mod tests {
         assert!(state.data_payloads.is_empty());
     }
 
+    #[test]
+    fn test_missing_between_first_and_last() {
+        let mut state = SlotState::new(MonotonicNs(0));
+        state.set_first_index(10);
+        for idx in [10, 11, 13, 16] {
+            state.mark_received(idx);
+        }
+        assert!(state.missing(10).is_empty(), "end of slot not known yet");
+        state.mark_last(16);
+        assert_eq!(state.missing(10), vec![12, 14, 15]);
+        assert_eq!(state.missing(2), vec![12, 14]);
+        // A junk last index is clamped to the per-slot cap.
+        state.mark_last(u32::MAX);
+        assert_eq!(state.missing(usize::MAX).len() as u32, MAX_DATA_SHREDS_PER_SLOT - 10 - 4);
+    }
+
+    #[test]
+    fn test_full_range_from_index_zero() {
+        let mut state = SlotState::new(MonotonicNs(0));
+        state.set_first_index(2);
+        for idx in [2, 3] {
+            state.mark_received(idx);
+        }
+        state.mark_last(3);
+        assert!(!state.has_full_range(), "the tail alone is not the whole slot");
+        state.mark_received(0);
+        state.mark_received(0);
+        assert!(!state.has_full_range());
+        state.mark_received(1);
+        assert!(state.has_full_range());
+    }
+
+    #[test]
+    fn test_out_of_range_index_is_dropped() {
+        let shred = |idx: u32| {
+            let mut buf = make_shred(LEGACY_DATA_VARIANT, &[1, 2, 3], false);
+            buf[65..73].copy_from_slice(&100u64.to_le_bytes());
+            buf[73..77].copy_from_slice(&idx.to_le_bytes());
+            RawShred { data: buf, recv_timestamp_ns: MonotonicNs(0) }
+        };
+        let (_raw_tx, raw_rx) = crossbeam_channel::unbounded();
+        let (tx, _tx_rx) = crossbeam_channel::unbounded();
+        let metrics = SourceMetrics::new("junk-index".into(), false);
+        let decoder = ShredDecoder::new(raw_rx, tx, metrics.clone());
+        let mut st = DecodeState::default();
+        decoder.process(&mut st, shred(u32::MAX));
+        assert!(st.slots.is_empty(), "no slot opened for a junk index");
+        decoder.process(&mut st, shred(3));
+        assert!(st.slots[&100].received.len() <= 1);
+
+        let mut state = SlotState::new(MonotonicNs(0));
+        assert_eq!(state.mark_received(u32::MAX), 0);
+        assert!(state.received.is_empty());
+        assert_eq!(state.mark_received(100), 16, "the bitset counts toward bytes");
+        assert_eq!(state.bytes, SLOT_BASE_BYTES + 16);
+    }
+
     fn make_shred(variant: u8, data: &[u8], last_in_slot: bool) -> Vec<u8> {
         let total = 1228;
         let mut buf = vec![0u8; total];
@@ -755,7 +1805,7 @@ mod tests {
 
     #[test]
     fn test_flush_contiguous_mid_stream() {
-        let mut state = SlotState::new(0);
+        let mut state = SlotState::new(MonotonicNs(0));
 
         state.set_first_index(1001);
         state.data_payloads.insert(1001, vec![10, 11]);
@@ -776,7 +1826,7 @@ mod tests {
 
     #[test]
     fn test_complete_detection() {
-        let mut state = SlotState::new(0);
+        let mut state = SlotState::new(MonotonicNs(0));
         state.set_first_index(0);
 
         state.max_index = 2;
@@ -797,8 +1847,258 @@ mod tests {
         assert!(state.counted);
     }
 
+    #[test]
+    fn test_slot_stats_expected_and_completion() {
+        let mut state = SlotState::new(MonotonicNs(1_000_000));
+        let s = state.stats(7, SlotOutcome::Dropped, MonotonicNs(2_000_000), None);
+        assert_eq!(s.shreds_expected, 0, "no index anchored yet");
+        assert_eq!(s.completion_us, None);
+
+        state.set_first_index(40);
+        state.max_index = 49;
+        state.shreds_seen = 8;
+        let s = state.stats(7, SlotOutcome::Complete, MonotonicNs(3_500_000), None);
+        assert_eq!(s.shreds_expected, 10);
+        assert_eq!(s.shreds_seen, 8);
+        assert_eq!(s.completion_us, Some(2_500));
+
+        let s = state.stats(7, SlotOutcome::Partial, MonotonicNs(3_500_000), None);
+        assert_eq!(s.completion_us, None);
+    }
+
+    #[test]
+    fn test_arrival_gaps_max_and_stddev() {
+        let mut gaps = ArrivalGaps::default();
+        gaps.record(MonotonicNs(1_000_000));
+        assert_eq!(gaps.max_us(), None, "one shred has no gap");
+        assert_eq!(gaps.stddev_us(), None);
+
+        // Gaps of 1ms, 1ms, 30ms: a mid-slot stall.
+        for t in [2_000_000, 3_000_000, 33_000_000] {
+            gaps.record(MonotonicNs(t));
+        }
+        assert_eq!(gaps.max_us(), Some(30_000));
+        // mean 10.67ms; population stddev ≈ 13.671ms
+        assert_eq!(gaps.stddev_us(), Some(13_671));
+    }
+
+    #[test]
+    fn test_headers_only_tracks_slots_without_txs() {
+        let entry = solana_entry::entry::Entry {
+            transactions: vec![VersionedTransaction::default(); 3],
+            ..Default::default()
+        };
+        let bytes = bincode::serialize(&entry).unwrap();
+        let shred = |slot: u64, idx: u32, data: &[u8], last: bool| {
+            let mut buf = make_shred(LEGACY_DATA_VARIANT, data, last);
+            buf[65..73].copy_from_slice(&slot.to_le_bytes());
+            buf[73..77].copy_from_slice(&idx.to_le_bytes());
+            RawShred { data: buf, recv_timestamp_ns: MonotonicNs(0) }
+        };
+
+        let (raw_tx, raw_rx) = crossbeam_channel::unbounded();
+        let (tx, tx_rx) = crossbeam_channel::unbounded();
+        let (slot_tx, slot_rx) = crossbeam_channel::unbounded();
+        let metrics = SourceMetrics::new("headers-only".into(), false);
+        raw_tx.send(shred(100, 0, &bytes, true)).unwrap();
+        raw_tx.send(shred(101, 0, &bytes[..10], false)).unwrap();
+        drop(raw_tx);
+        ShredDecoder::new(raw_rx, tx, metrics.clone())
+            .with_slot_stats(slot_tx)
+            .with_headers_only(true)
+            .run()
+            .unwrap();
+
+        assert_eq!(tx_rx.try_iter().flatten().count(), 0);
+        let slots: Vec<SlotStats> = slot_rx.try_iter().collect();
+        assert_eq!(slots.len(), 2);
+        assert!(matches!(slots[0].outcome, SlotOutcome::Complete));
+        assert_eq!(slots[0].txs_decoded, 0);
+        // No transactions to judge by: a slot with shreds counts as partial.
+        assert!(matches!(slots[1].outcome, SlotOutcome::Partial));
+        assert_eq!(metrics.coverage_shreds_seen.load(Relaxed), 2);
+    }
+
+    #[test]
+    fn test_budget_sheds_oldest_slots() {
+        let shred = |slot: u64, idx: u32| {
+            let mut buf = make_shred(LEGACY_DATA_VARIANT, &[1, 2, 3], false);
+            buf[65..73].copy_from_slice(&slot.to_le_bytes());
+            buf[73..77].copy_from_slice(&idx.to_le_bytes());
+            RawShred { data: buf, recv_timestamp_ns: MonotonicNs(0) }
+        };
+        let (raw_tx, raw_rx) = crossbeam_channel::unbounded();
+        let (tx, _tx_rx) = crossbeam_channel::unbounded();
+        let (slot_tx, slot_rx) = crossbeam_channel::unbounded();
+        // Slot 100 comes back after it was shed and is ignored.
+        for (slot, idx) in [(100, 0), (101, 0), (102, 0), (100, 1)] {
+            raw_tx.send(shred(slot, idx)).unwrap();
+        }
+        drop(raw_tx);
+        let metrics = SourceMetrics::new("budget".into(), false);
+        let budget = MemoryBudget::new(1);
+        ShredDecoder::new(raw_rx, tx, metrics.clone())
+            .with_slot_stats(slot_tx)
+            .with_budget(Some(budget.clone()))
+            .run()
+            .unwrap();
+
+        let slots: Vec<u64> = slot_rx.try_iter().map(|s| s.slot).collect();
+        assert_eq!(slots, vec![100, 101, 102]);
+        assert_eq!(metrics.slots_attempted.load(Relaxed), 3);
+        let snap = budget.snapshot();
+        assert_eq!(snap.slots_shed, 2);
+        assert_eq!(snap.used_bytes, 0, "released when the decoder stops");
+    }
+
+    #[test]
+    fn test_max_lag_skips_slots_behind_cluster_tip() {
+        let shred = |slot: u64| {
+            let mut buf = make_shred(LEGACY_DATA_VARIANT, &[1, 2, 3], false);
+            buf[65..73].copy_from_slice(&slot.to_le_bytes());
+            RawShred { data: buf, recv_timestamp_ns: MonotonicNs(0) }
+        };
+        let race = ShredRaceTracker::deterministic();
+        let decode = |name: &str, slots: &[u64], max_lag: Option<u64>| {
+            let (raw_tx, raw_rx) = crossbeam_channel::unbounded();
+            let (tx, _tx_rx) = crossbeam_channel::unbounded();
+            let (slot_tx, slot_rx) = crossbeam_channel::unbounded();
+            for &slot in slots {
+                raw_tx.send(shred(slot)).unwrap();
+            }
+            drop(raw_tx);
+            let metrics = SourceMetrics::new(name.into(), false);
+            ShredDecoder::new(raw_rx, tx, metrics.clone())
+                .with_race(Some(race.clone()))
+                .with_slot_stats(slot_tx)
+                .with_max_lag_slots(max_lag)
+                .run()
+                .unwrap();
+            let slots: Vec<u64> = slot_rx.try_iter().map(|s| s.slot).collect();
+            (slots, metrics.shreds_decode_skipped.load(Relaxed))
+        };
+
+        seed_tip(&race, 200);
+        assert_eq!(decode("fast", &[200], None), (vec![200], 0));
+        assert_eq!(race.cluster_tip().get(), 200);
+        // 150 is 50 behind the tip, 195 only 5: the lagging feed skips the first.
+        assert_eq!(decode("slow", &[150, 195], Some(10)), (vec![195], 1));
+        assert_eq!(decode("slow-ungated", &[150, 195], None), (vec![150, 195], 0));
+        // A junk slot near u64::MAX neither overflows the lag check nor moves
+        // the tip.
+        assert_eq!(decode("junk", &[u64::MAX - 1], Some(10)), (vec![u64::MAX - 1], 0));
+        assert_eq!(race.cluster_tip().get(), 200);
+    }
+
+    #[test]
+    fn test_invalid_shreds_never_race() {
+        let shred = |idx: u32| {
+            let mut buf = vec![0u8; 1203];
+            buf[VARIANT_OFF] = 0x91;
+            buf[65..73].copy_from_slice(&7u64.to_le_bytes());
+            buf[73..77].copy_from_slice(&idx.to_le_bytes());
+            RawShred { data: buf, recv_timestamp_ns: MonotonicNs(0) }
+        };
+        let race = ShredRaceTracker::deterministic();
+        let verifier = ShredVerifier {
+            leaders: crate::shred_verify::LeaderSchedule::default(),
+            drop_invalid: true,
+        };
+        let (_raw_tx, raw_rx) = crossbeam_channel::unbounded();
+        let (tx, _tx_rx) = crossbeam_channel::unbounded();
+        let metrics = SourceMetrics::new("relay".into(), false);
+        let decoder = ShredDecoder::new(raw_rx, tx, metrics)
+            .with_race(Some(race.clone()))
+            .with_verifier(Some(verifier.clone()));
+        let mut st = DecodeState::default();
+        // Leader unknown: kept, and raced. Then a forged shred: dropped
+        // before it can race.
+        decoder.process(&mut st, shred(0));
+        verifier.leaders.insert(7, &[solana_pubkey::Pubkey::new_from_array([9; 32])]);
+        decoder.process(&mut st, shred(1));
+
+        let other = race.sender();
+        for idx in [0, 1] {
+            let recv_ns = MonotonicNs(1_000);
+            let source = "other".into();
+            other.send(ShredArrival { source, slot: 7, idx, recv_ns, recovered: false }).unwrap();
+        }
+        race.process_pending();
+        let wire = &race.snapshots()[0];
+        assert_eq!((wire.source_a.as_ref(), wire.total_matched), ("other", 1));
+    }
+
+    #[test]
+    fn test_shared_tip_expiry() {
+        let shred = |slot: u64| {
+            let mut buf = make_shred(LEGACY_DATA_VARIANT, &[1, 2, 3], false);
+            buf[65..73].copy_from_slice(&slot.to_le_bytes());
+            RawShred { data: buf, recv_timestamp_ns: MonotonicNs(0) }
+        };
+        let feed = |race: &Arc<ShredRaceTracker>, name: &str| {
+            let (_raw_tx, raw_rx) = crossbeam_channel::unbounded();
+            let (tx, _tx_rx) = crossbeam_channel::unbounded();
+            let (slot_tx, slot_rx) = crossbeam_channel::unbounded();
+            let metrics = SourceMetrics::new(name.into(), false);
+            let decoder = ShredDecoder::new(raw_rx, tx, metrics.clone())
+                .with_race(Some(race.clone()))
+                .with_slot_stats(slot_tx);
+            (decoder, DecodeState::default(), slot_rx, metrics)
+        };
+        let finish = |decoder: &ShredDecoder, st, slot_rx: &Receiver<SlotStats>| {
+            decoder.finish(st);
+            let mut slots: Vec<u64> = slot_rx.try_iter().map(|s| s.slot).collect();
+            slots.sort_unstable();
+            slots
+        };
+
+        // Garbage slot 500 between 100 and 101: on its own tip it expires
+        // 100 and drops 101; the shared tip does not take the jump.
+        for (expiry, expected) in
+            [(SlotExpiry::Local, vec![100, 500]), (SlotExpiry::Sources, vec![100, 101, 500])]
+        {
+            let race = ShredRaceTracker::deterministic();
+            race.cluster_tip().set_expiry(expiry);
+            seed_tip(&race, 100);
+            let (decoder, mut st, slot_rx, _) = feed(&race, "noisy");
+            for slot in [100, 500, 101] {
+                decoder.process(&mut st, shred(slot));
+            }
+            assert_eq!(finish(&decoder, st, &slot_rx), expected, "{:?}", expiry);
+            assert_eq!(race.cluster_tip().get(), 101);
+        }
+
+        // A lagging feed loses 160 once the others reach 220, while its own
+        // tip (161) would have kept it.
+        let race = ShredRaceTracker::deterministic();
+        race.cluster_tip().set_expiry(SlotExpiry::Sources);
+        seed_tip(&race, 160);
+        let (slow, mut slow_st, slow_slots, slow_metrics) = feed(&race, "slow");
+        let (fast, mut fast_st, _fast_slots, fast_metrics) = feed(&race, "fast");
+        slow.process(&mut slow_st, shred(160));
+        fast.process(&mut fast_st, shred(190));
+        fast.process(&mut fast_st, shred(220));
+        slow.process(&mut slow_st, shred(161));
+        assert_eq!(race.cluster_tip().get(), 220);
+        assert_eq!(finish(&slow, slow_st, &slow_slots), vec![160]);
+        assert_eq!(slow_metrics.slots_expired_shared_tip.load(Relaxed), 1);
+        assert_eq!(fast_metrics.slots_expired_shared_tip.load(Relaxed), 0);
+
+        // Against the baseline, the confirmed slot expires 300 under the
+        // feed, and 301 arrives too late to be assembled.
+        let race = ShredRaceTracker::deterministic();
+        race.cluster_tip().set_expiry(SlotExpiry::Baseline);
+        let (decoder, mut st, slot_rx, metrics) = feed(&race, "feed");
+        decoder.process(&mut st, shred(300));
+        race.cluster_tip().observe_baseline(340);
+        decoder.process(&mut st, shred(301));
+        assert_eq!(race.cluster_tip().get(), 340);
+        assert_eq!(finish(&decoder, st, &slot_rx), vec![300]);
+        assert_eq!(metrics.slots_expired_shared_tip.load(Relaxed), 1);
+    }
+
     fn make_coding_shred(variant: u8, num_data: u16, num_coding: u16, position: u16) -> Vec<u8> {
-        let mut buf = vec![0u8; SHRED_RS_SIZE];
+        let mut buf = vec![0u8; MERKLE_CODE_PAYLOAD_SIZE];
         buf[VARIANT_OFF] = variant;
         buf[CODE_NUM_DATA_OFF] = num_data as u8;
         buf[CODE_NUM_DATA_OFF + 1] = (num_data >> 8) as u8;
@@ -858,10 +2158,10 @@ mod tests {
         all_shards.push(vec![0u8; SZ]);
         rs.encode(&mut all_shards).unwrap();
 
-        let mut fec = FecSet::new(N, M);
-        fec.shards.insert(0, all_shards[0].clone());
-        fec.shards.insert(2, all_shards[2].clone());
-        fec.shards.insert(3, all_shards[3].clone());
+        let mut fec = FecSet::new(N, M, SZ);
+        for i in [0, 2, 3] {
+            fec.insert(i, &Arc::new(all_shards[i].clone()), 0);
+        }
 
         assert!(fec.ready_to_recover());
 
@@ -872,13 +2172,169 @@ mod tests {
         assert_eq!(bytes, &original[1]);
     }
 
+    #[test]
+    fn test_erasure_shard_layout_by_variant() {
+        // Chained, 6 proof entries: data and coding symbols line up.
+        assert_eq!(erasure_shard(0x96), Some(ErasureShard { offset: 64, len: 1019 }));
+        assert_eq!(erasure_shard(0x66), Some(ErasureShard { offset: 89, len: 1019 }));
+        // Resigned drops the trailing retransmitter signature.
+        assert_eq!(erasure_shard(0xb6), Some(ErasureShard { offset: 64, len: 955 }));
+        assert_eq!(erasure_shard(0x76), Some(ErasureShard { offset: 89, len: 955 }));
+        // Unchained.
+        assert_eq!(erasure_shard(0x80), Some(ErasureShard { offset: 64, len: 1139 }));
+        assert_eq!(erasure_shard(0x4f).unwrap().len, 1139 - 15 * 20);
+        // Legacy and undefined variants are not recovered.
+        for v in [LEGACY_DATA_VARIANT, 0x5a, 0x50, 0xa3, 0x00] {
+            assert_eq!(erasure_shard(v), None, "variant {:#x}", v);
+        }
+    }
+
+    /// A FEC set laid out as Agave does for `code_variant`/`data_variant`:
+    /// data shreds of 1203 bytes, coding shreds of 1228, parity computed over
+    /// the erasure regions only. Bytes outside those regions (signatures,
+    /// proof, retransmitter signature) are filled with junk that must not
+    /// take part in recovery.
+    fn merkle_fec_set(
+        code_variant: u8,
+        data_variant: u8,
+        slot: u64,
+        payloads: &[&[u8]],
+        num_coding: usize,
+    ) -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
+        use reed_solomon_erasure::galois_8::ReedSolomon;
+
+        let num_data = payloads.len();
+        let data_layout = erasure_shard(data_variant).unwrap();
+        let code_layout = erasure_shard(code_variant).unwrap();
+        assert_eq!(data_layout.len, code_layout.len);
+
+        let data: Vec<Vec<u8>> = payloads
+            .iter()
+            .enumerate()
+            .map(|(i, payload)| {
+                let mut buf = vec![0xee; 1203];
+                buf[VARIANT_OFF] = data_variant;
+                buf[SLOT_OFF..SLOT_OFF + 8].copy_from_slice(&slot.to_le_bytes());
+                buf[INDEX_OFF..INDEX_OFF + 4].copy_from_slice(&(i as u32).to_le_bytes());
+                buf[FEC_SET_INDEX_OFF..FEC_SET_INDEX_OFF + 4].fill(0);
+                buf[FLAGS_OFF] = if i == num_data - 1 { LAST_IN_SLOT_FLAG } else { 0 };
+                let size = (DATA_OFF + payload.len()) as u16;
+                buf[SIZE_OFF..SIZE_OFF + 2].copy_from_slice(&size.to_le_bytes());
+                buf[DATA_OFF..DATA_OFF + payload.len()].copy_from_slice(payload);
+                buf[DATA_OFF + payload.len()..data_layout.offset + data_layout.len].fill(0);
+                buf
+            })
+            .collect();
+
+        let mut shards: Vec<Vec<u8>> =
+            data.iter().map(|d| d[data_layout.offset..][..data_layout.len].to_vec()).collect();
+        shards.extend((0..num_coding).map(|_| vec![0u8; code_layout.len]));
+        ReedSolomon::new(num_data, num_coding).unwrap().encode(&mut shards).unwrap();
+
+        let code = (0..num_coding)
+            .map(|pos| {
+                let (n, m) = (num_data as u16, num_coding as u16);
+                let mut buf = make_coding_shred(code_variant, n, m, pos as u16);
+                buf[..VARIANT_OFF].fill(0xee);
+                buf[SLOT_OFF..SLOT_OFF + 8].copy_from_slice(&slot.to_le_bytes());
+                buf[INDEX_OFF..INDEX_OFF + 4].copy_from_slice(&(pos as u32).to_le_bytes());
+                buf[code_layout.offset + code_layout.len..].fill(0xee);
+                buf[code_layout.offset..code_layout.offset + code_layout.len]
+                    .copy_from_slice(&shards[num_data + pos]);
+                buf
+            })
+            .collect();
+        (data, code)
+    }
+
+    #[test]
+    fn test_fec_recovery_for_each_merkle_variant() {
+        let entry = solana_entry::entry::Entry {
+            transactions: vec![VersionedTransaction::default(); 4],
+            ..Default::default()
+        };
+        let bytes = bincode::serialize(&entry).unwrap();
+        let chunks: Vec<&[u8]> = bytes.chunks(bytes.len().div_ceil(3)).collect();
+
+        let variants = [(0x46, 0x86), (0x66, 0x96), (0x76, 0xb6), (0x65, 0x95)];
+        for (code_variant, data_variant) in variants {
+            let (data, code) = merkle_fec_set(code_variant, data_variant, 500, &chunks, 3);
+            let (raw_tx, raw_rx) = crossbeam_channel::unbounded();
+            let (tx, tx_rx) = crossbeam_channel::unbounded();
+            let (slot_tx, slot_rx) = crossbeam_channel::unbounded();
+            // The first coding shred opens the set; data shred 1 is lost and
+            // recovered once the second coding shred arrives.
+            let order = [&code[0], &data[0], &data[2], &code[1], &code[2]];
+            for shred in order.into_iter().cloned() {
+                raw_tx.send(RawShred { data: shred, recv_timestamp_ns: MonotonicNs(0) }).unwrap();
+            }
+            drop(raw_tx);
+            let metrics = SourceMetrics::new("fec-variant".into(), false);
+            ShredDecoder::new(raw_rx, tx, metrics.clone()).with_slot_stats(slot_tx).run().unwrap();
+
+            let stats: Vec<SlotStats> = slot_rx.try_iter().collect();
+            assert_eq!(stats.len(), 1, "variant {:#x}", data_variant);
+            assert!(
+                matches!(stats[0].outcome, SlotOutcome::Complete),
+                "variant {:#x}: {:?}",
+                data_variant,
+                stats[0].outcome
+            );
+            assert_eq!(stats[0].fec_recovered, 1, "variant {:#x}", data_variant);
+            let funnel = (stats[0].fec_sets, stats[0].fec_sets_complete, stats[0].entries);
+            assert_eq!(funnel, (1, 1, 1), "variant {:#x}", data_variant);
+            assert_eq!(tx_rx.try_iter().flatten().count(), 4, "variant {:#x}", data_variant);
+        }
+    }
+
+    #[test]
+    fn test_forensics_for_poorly_covered_slot() {
+        let chunks: Vec<&[u8]> = vec![&[1u8; 100]; 4];
+        let (data, code) = merkle_fec_set(0x66, 0x96, 500, &chunks, 3);
+        let (raw_tx, raw_rx) = crossbeam_channel::unbounded();
+        let (tx, _tx_rx) = crossbeam_channel::unbounded();
+        let (ftx, frx) = crossbeam_channel::unbounded();
+        // Data shred 1 is lost and one coding shred is too few to recover it.
+        for shred in [&code[0], &data[0], &data[2]].into_iter().cloned() {
+            raw_tx.send(RawShred { data: shred, recv_timestamp_ns: MonotonicNs(0) }).unwrap();
+        }
+        drop(raw_tx);
+        let metrics = SourceMetrics::new("forensics".into(), false);
+        let tap = ForensicsTap { tx: ftx, min_coverage_pct: 80.0 };
+        ShredDecoder::new(raw_rx, tx, metrics).with_forensics(Some(tap)).run().unwrap();
+
+        let dumps: Vec<SlotForensics> = frx.try_iter().collect();
+        assert_eq!(dumps.len(), 1);
+        let d = &dumps[0];
+        assert_eq!(d.slot, 500);
+        assert_eq!((d.shreds_seen, d.shreds_expected), (2, 3));
+        assert_eq!(d.received, vec![[0, 0], [2, 2]]);
+        assert_eq!(d.fec_sets.len(), 1);
+        let f = &d.fec_sets[0];
+        assert_eq!((f.num_data, f.num_coding), (4, 3));
+        assert_eq!((f.data_shards, f.coding_shards, f.recovered), (2, 1, false));
+    }
+
     #[test]
     fn test_fec_set_not_ready_when_insufficient_shards() {
-        let mut fec = FecSet::new(4, 4);
-        fec.shards.insert(4, vec![0u8; SHRED_RS_SIZE]);
+        let mut fec = FecSet::new(4, 4, MERKLE_SHARD_BASE_SIZE);
+        fec.insert(4, &Arc::new(vec![0u8; MERKLE_SHARD_BASE_SIZE]), 0);
         assert!(!fec.ready_to_recover());
     }
 
+    #[test]
+    fn test_fec_set_holds_shards_by_reference() {
+        let mut fec = FecSet::new(2, 2, 64);
+        let shred = Arc::new(vec![7u8; 64 + SIGNATURE_SIZE]);
+        fec.insert(0, &shred, SIGNATURE_SIZE);
+        fec.insert(0, &Arc::new(vec![9u8; 64]), 0);
+        fec.insert(1, &Arc::new(vec![0u8; 63]), 0);
+
+        assert_eq!(fec.shards.len(), 1, "duplicate and truncated shards are ignored");
+        assert!(Arc::ptr_eq(&fec.shards[&0].buf, &shred));
+        assert_eq!(Arc::strong_count(&shred), 2);
+    }
+
     #[test]
     fn test_fec_set_reconstruct_no_missing_data() {
         use reed_solomon_erasure::galois_8::ReedSolomon;
@@ -892,9 +2348,9 @@ mod tests {
             vec![vec![3u8; SZ], vec![4u8; SZ], vec![0u8; SZ], vec![0u8; SZ]];
         rs.encode(&mut all_shards).unwrap();
 
-        let mut fec = FecSet::new(N, M);
+        let mut fec = FecSet::new(N, M, SZ);
         for (i, s) in all_shards.iter().enumerate() {
-            fec.shards.insert(i, s.clone());
+            fec.insert(i, &Arc::new(s.clone()), 0);
         }
         let recovered = fec.reconstruct();
         assert!(recovered.is_empty());
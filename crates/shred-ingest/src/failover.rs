@@ -0,0 +1,182 @@
+//! Warm-standby failover between sources carrying the same feed.
+//!
+//! A [`FailoverGroup`] holds two or more sources in priority order. Only the
+//! active member contributes to the fan-in and the shred race; the others are
+//! marked [`SourceMetrics::standby`], which keeps their sockets drained and
+//! their receive counters moving (so liveness is known) without forwarding
+//! anything downstream.
+//!
+//! When the active member has shown no activity for the configured silence
+//! window, the first standby that *has* been active within that window is
+//! promoted. There is no automatic fail-back: the old primary becomes a
+//! standby and is only promoted again if the new active member goes silent.
+
+use crate::source_metrics::SourceMetrics;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How often [`spawn_failover_monitor`] evaluates the groups.
+const TICK: Duration = Duration::from_secs(1);
+
+/// A promotion performed by [`FailoverGroup::tick`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailoverEvent {
+    pub group: String,
+    /// Previously active source.
+    pub from: Arc<str>,
+    /// Newly promoted source.
+    pub to: Arc<str>,
+    /// How long `from` had been silent, in seconds.
+    pub silent_secs: u64,
+}
+
+pub struct FailoverGroup {
+    pub name: String,
+    members: Vec<Arc<SourceMetrics>>,
+    silence_ns: u64,
+    active: usize,
+    /// Activity counter per member as of its last change.
+    last_count: Vec<u64>,
+    /// Monotonic ns of each member's last observed activity.
+    last_change_ns: Vec<u64>,
+}
+
+impl FailoverGroup {
+    /// Build a group from `members` in priority order. The first member starts
+    /// active; all others are put into standby immediately.
+    pub fn new(name: String, members: Vec<Arc<SourceMetrics>>, silence: Duration) -> Self {
+        assert!(members.len() >= 2, "failover group needs at least two members");
+        for (i, m) in members.iter().enumerate() {
+            m.standby.store(i != 0, Relaxed);
+        }
+        let now = crate::metrics::now_ns();
+        let n = members.len();
+        Self {
+            name,
+            last_count: members.iter().map(|m| activity(m)).collect(),
+            members,
+            silence_ns: silence.as_nanos() as u64,
+            active: 0,
+            // Every member gets a full silence window of grace at startup.
+            last_change_ns: vec![now; n],
+        }
+    }
+
+    /// Name of the currently active member.
+    pub fn active(&self) -> &str {
+        &self.members[self.active].name
+    }
+
+    /// Sample member activity at `now_ns` and promote a standby if the active
+    /// member has been silent for the whole window. Returns the promotion, if any.
+    pub fn tick(&mut self, now_ns: u64) -> Option<FailoverEvent> {
+        for (i, m) in self.members.iter().enumerate() {
+            let c = activity(m);
+            if c != self.last_count[i] {
+                self.last_count[i] = c;
+                self.last_change_ns[i] = now_ns;
+            }
+        }
+
+        let silent_ns = now_ns.saturating_sub(self.last_change_ns[self.active]);
+        if silent_ns < self.silence_ns {
+            return None;
+        }
+        let next = (0..self.members.len()).find(|&i| {
+            i != self.active && now_ns.saturating_sub(self.last_change_ns[i]) < self.silence_ns
+        })?;
+
+        let from = &self.members[self.active];
+        let to = &self.members[next];
+        from.standby.store(true, Relaxed);
+        to.standby.store(false, Relaxed);
+        self.active = next;
+
+        Some(FailoverEvent {
+            group: self.name.clone(),
+            from: from.name.clone(),
+            to: to.name.clone(),
+            silent_secs: silent_ns / 1_000_000_000,
+        })
+    }
+}
+
+/// Shreds for UDP sources; decoded transactions for gRPC/RPC sources. Both
+/// keep counting while a source is on standby.
+fn activity(m: &SourceMetrics) -> u64 {
+    m.shreds_received.load(Relaxed) + m.txs_decoded.load(Relaxed)
+}
+
+/// Evaluate `groups` once per second on a background thread, calling
+/// `on_event` for every promotion.
+pub fn spawn_failover_monitor<F>(mut groups: Vec<FailoverGroup>, on_event: F) -> JoinHandle<()>
+where
+    F: Fn(&FailoverEvent) + Send + 'static,
+{
+    std::thread::Builder::new()
+        .name("failover".into())
+        .spawn(move || loop {
+            std::thread::sleep(TICK);
+            let now = crate::metrics::now_ns();
+            for g in &mut groups {
+                if let Some(ev) = g.tick(now) {
+                    on_event(&ev);
+                }
+            }
+        })
+        .expect("failed to spawn failover thread")
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: u64 = 1_000_000_000;
+
+    fn group() -> (FailoverGroup, Arc<SourceMetrics>, Arc<SourceMetrics>) {
+        let a = SourceMetrics::new("dz-a".into(), false);
+        let b = SourceMetrics::new("dz-b".into(), false);
+        let mut g = FailoverGroup::new("dz".into(), vec![a.clone(), b.clone()], Duration::from_secs(5));
+        g.last_change_ns = vec![0, 0];
+        (g, a, b)
+    }
+
+    #[test]
+    fn test_primary_active_standby_marked() {
+        let (g, a, b) = group();
+        assert_eq!(g.active(), "dz-a");
+        assert!(!a.is_standby());
+        assert!(b.is_standby());
+    }
+
+    #[test]
+    fn test_promotes_live_standby_after_silence() {
+        let (mut g, a, b) = group();
+        a.shreds_received.store(10, Relaxed);
+        b.shreds_received.store(10, Relaxed);
+        assert_eq!(g.tick(SEC), None);
+
+        // Primary stops; standby keeps receiving.
+        b.shreds_received.store(20, Relaxed);
+        assert_eq!(g.tick(4 * SEC), None);
+        b.shreds_received.store(30, Relaxed);
+        let ev = g.tick(6 * SEC).unwrap();
+        assert_eq!((&*ev.from, &*ev.to, ev.silent_secs), ("dz-a", "dz-b", 5));
+        assert!(a.is_standby());
+        assert!(!b.is_standby());
+        assert_eq!(g.active(), "dz-b");
+    }
+
+    #[test]
+    fn test_no_promotion_when_all_silent() {
+        let (mut g, _a, _b) = group();
+        assert_eq!(g.tick(60 * SEC), None);
+        assert_eq!(g.active(), "dz-a");
+    }
+}
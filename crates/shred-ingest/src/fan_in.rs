@@ -2,26 +2,56 @@
 //!
 //! [`FanInSource`] accepts any number of [`TxSource`] implementations, starts each on
 //! its own thread(s), and merges their output into a single `Sender<DecodedTx>`.
+//! Sources hand transactions to the fan-in in batches — one per decoder flush or
+//! block — so channel and counter traffic scales with batches, not transactions.
 //!
-//! Deduplication is keyed on `signatures[0]` of each transaction. The first source to
-//! deliver a given transaction wins and forwards it downstream; later arrivals of the
-//! same transaction are counted as duplicates. When a shred source and an RPC source
-//! both deliver the same transaction, their receive timestamps are compared to compute
-//! the shred lead time (positive = shred arrived before RPC).
-
-use crossbeam_channel::Sender;
-use crate::receiver::CaptureEvent;
+//! Deduplication is keyed on `signatures[0]` of each transaction and the slot it was
+//! delivered in. The first source to deliver a given transaction wins and forwards it
+//! downstream; later arrivals of the same transaction are counted as duplicates, and
+//! with [`FanInSource::forward_duplicates_per_sec`] also forwarded, marked as such.
+//! When a shred source and an RPC source both deliver the same transaction, their
+//! receive timestamps are compared to compute the shred lead time (positive = shred
+//! arrived before RPC).
+//!
+//! A transaction resubmitted after its first slot was abandoned arrives again in a
+//! later slot. Compared with its first sighting it would show a lead of several
+//! slots, so a copy in a slot the signature has not been seen in starts a separate
+//! race for that slot instead. It is counted in `txs_slot_mismatch` rather than as a
+//! first or a duplicate, and not forwarded again. With
+//! [`FanInSource::dedup_signature_only`] the slot is ignored as before: such a copy is
+//! a duplicate of the first sighting, and is counted in `txs_slot_mismatch` as well.
+//!
+//! With [`FanInSource::preferred_source`] the forwarded stream favours one source
+//! on near ties: a first copy from any other source is held for up to
+//! [`FanInSource::tie_window_us`], and if the preferred source delivers the same
+//! transaction within that window of it, the preferred copy is forwarded in its
+//! place. The race itself is unaffected: wins, duplicates and lead times are counted
+//! on the true arrival order.
+
+use crossbeam_channel::{Sender, TrySendError};
+use crate::receiver::{CaptureEvent, SocketTuning};
 use dashmap::DashMap;
 use solana_pubkey::Pubkey;
 use std::collections::HashSet;
-use std::sync::atomic::Ordering::Relaxed;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
 use std::sync::Arc;
 use std::thread::JoinHandle;
 
+use crate::budget::{BudgetShare, BudgetUser, MemoryBudget};
+use crate::chaos::ChaosConfig;
+use crate::clock::MonotonicNs;
+use crate::cluster_tip::ClusterTip;
+use crate::coverage::SlotExpiry;
 use crate::decoder::DecodedTx;
+use crate::forensics::ForensicsTap;
+use crate::repair::RepairClient;
 use crate::metrics;
 use crate::shred_race::ShredRaceTracker;
-use crate::source_metrics::SourceMetrics;
+use crate::shred_verify::ShredVerifier;
+use crate::supervise::{RestartPolicy, StopSignal, Supervised};
+use crate::source_metrics::{SlotConfirmations, SourceErrorKind, SourceMetrics};
+use crate::trace::TraceTap;
+use crate::tx_profile::TxProfile;
 
 // ---------------------------------------------------------------------------
 // TxSource trait
@@ -29,21 +59,58 @@ use crate::source_metrics::SourceMetrics;
 
 /// A pluggable transaction source that can be wired into [`FanInSource`].
 pub trait TxSource: Send + 'static {
-    fn name(&self) -> &'static str;
+    fn name(&self) -> Arc<str>;
     /// Returns true if this source is an RPC source (used for lead-time direction).
     fn is_rpc(&self) -> bool {
         false
     }
     /// Start all threads for this source. The source writes decoded transactions to
-    /// `tx` and increments `metrics` counters as it operates.
+    /// `tx`, batched as they become available (an empty batch is never sent), and
+    /// increments `metrics` counters as it operates.
     /// `race` is `Some` only for shred-tier sources; other sources should accept and
     /// ignore it (parameter named `_race`).
     fn start(
         self: Box<Self>,
-        tx: Sender<DecodedTx>,
+        tx: Sender<Vec<DecodedTx>>,
         metrics: Arc<SourceMetrics>,
         race: Option<Arc<ShredRaceTracker>>,
     ) -> Vec<JoinHandle<()>>;
+    /// Called before `start` on RPC-tier sources. Sources that see
+    /// whole confirmed blocks report each slot's outcome through it so the
+    /// shred-tier sources can cross-check theirs; others ignore it.
+    fn set_confirmations(&mut self, _confirmations: Arc<SlotConfirmations>) {}
+    /// Called before `start` on RPC-tier sources when slots are expired
+    /// against the baseline ([`SlotExpiry::Baseline`]). Sources that learn
+    /// the confirmed slot report it through
+    /// [`ClusterTip::observe_baseline`]; others ignore it.
+    fn set_cluster_tip(&mut self, _tip: Arc<ClusterTip>) {}
+    /// Called before `start` on shred-tier sources when slot forensics are
+    /// on. Sources that run a [`crate::decoder::ShredDecoder`] hand it the
+    /// tap; others ignore it.
+    fn set_forensics(&mut self, _forensics: ForensicsTap) {}
+    /// Called before `start` on shred-tier sources when `[repair]` is
+    /// configured; like [`Self::set_forensics`].
+    fn set_repair(&mut self, _repair: RepairClient) {}
+    /// Called before `start` on shred-tier sources when `[limits]` sets a
+    /// memory budget; like [`Self::set_forensics`].
+    fn set_budget(&mut self, _budget: MemoryBudget) {}
+    /// Called before `start` on shred-tier sources when shred signatures are
+    /// checked; like [`Self::set_forensics`].
+    fn set_verifier(&mut self, _verifier: ShredVerifier) {}
+    /// Called before `start` on shred-tier sources when the receive socket
+    /// settings differ from [`SocketTuning::default`]; UDP sources apply them.
+    fn set_tuning(&mut self, _tuning: SocketTuning) {}
+    /// Called before each `start` of a supervised source (see
+    /// [`crate::supervise`]). Sources whose threads would outlive a failed
+    /// sibling, such as several receive sockets feeding one decoder, hand it
+    /// to them and they exit once it is stopped; others ignore it.
+    fn set_stop(&mut self, _stop: StopSignal) {}
+    /// An unstarted copy of this source, with everything set so far, to
+    /// start in its place when its threads stop (see [`crate::supervise`]).
+    /// `None`, the default, for sources that are never restarted.
+    fn respawn(&self) -> Option<Box<dyn TxSource>> {
+        None
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -51,80 +118,174 @@ pub trait TxSource: Send + 'static {
 // ---------------------------------------------------------------------------
 
 /// Wraps [`ShredReceiver`] + [`ShredDecoder`] into a single [`TxSource`].
+#[derive(Clone)]
 pub struct ShredTxSource {
     /// Display name for this source (e.g. "bebop", "jito-shredstream")
-    pub name: &'static str,
+    pub name: Arc<str>,
     pub multicast_addr: String,
     pub port: u16,
+    /// For relays that stripe shreds across a port range: one socket and
+    /// recv thread per port from `port` through this one, all feeding the same
+    /// decoder. Recv thread `i` is pinned to `pin_recv_core + i`.
+    pub last_port: Option<u16>,
     pub interface: String,
     pub pin_recv_core: Option<usize>,
     pub pin_decode_core: Option<usize>,
     pub shred_version: Option<u16>,
     /// Optional capture channel; forwarded to ShredReceiver for the hot-path tap.
     pub capture_tx: Option<crossbeam_channel::Sender<CaptureEvent>>,
+    /// Fault injection between receiver and decoder (see [`crate::chaos`]).
+    pub chaos: Option<ChaosConfig>,
+    /// Skip transaction deserialization; report coverage and races only.
+    pub headers_only: bool,
+    /// Skip decoding slots this far behind the cluster tip (see
+    /// [`crate::decoder::ShredDecoder::with_max_lag_slots`]).
+    pub max_lag_slots: Option<u64>,
+    /// Slot forensics tap, set through [`TxSource::set_forensics`].
+    pub forensics: Option<ForensicsTap>,
+    /// Set through [`TxSource::set_repair`].
+    pub repair: Option<RepairClient>,
+    /// Set through [`TxSource::set_budget`].
+    pub budget: Option<MemoryBudget>,
+    /// Set through [`TxSource::set_verifier`].
+    pub verifier: Option<ShredVerifier>,
+    /// Set through [`TxSource::set_tuning`].
+    pub tuning: Option<SocketTuning>,
+    /// Set through [`TxSource::set_stop`].
+    pub stop: Option<StopSignal>,
 }
 
 impl TxSource for ShredTxSource {
-    fn name(&self) -> &'static str {
-        self.name
+    fn name(&self) -> Arc<str> {
+        self.name.clone()
+    }
+
+    fn respawn(&self) -> Option<Box<dyn TxSource>> {
+        Some(Box::new(self.clone()))
     }
 
     fn is_rpc(&self) -> bool {
         false
     }
 
+    fn set_forensics(&mut self, forensics: ForensicsTap) {
+        self.forensics = Some(forensics);
+    }
+
+    fn set_repair(&mut self, repair: RepairClient) {
+        self.repair = Some(repair);
+    }
+
+    fn set_budget(&mut self, budget: MemoryBudget) {
+        self.budget = Some(budget);
+    }
+
+    fn set_verifier(&mut self, verifier: ShredVerifier) {
+        self.verifier = Some(verifier);
+    }
+
+    fn set_tuning(&mut self, tuning: SocketTuning) {
+        self.tuning = Some(tuning);
+    }
+
+    fn set_stop(&mut self, stop: StopSignal) {
+        self.stop = Some(stop);
+    }
+
     fn start(
         self: Box<Self>,
-        tx: Sender<DecodedTx>,
+        tx: Sender<Vec<DecodedTx>>,
         metrics: Arc<SourceMetrics>,
         race: Option<Arc<ShredRaceTracker>>,
     ) -> Vec<JoinHandle<()>> {
-        let (shred_tx, shred_rx) = crossbeam_channel::bounded(4096);
+        let ports = self.port..=self.last_port.unwrap_or(self.port);
+        let striped = self.last_port.is_some();
+        let (shred_tx, shred_rx) = crate::receiver::shred_queue(ports.len());
 
-        let multicast_addr = self.multicast_addr.clone();
-        let port = self.port;
-        let interface = self.interface.clone();
         let shred_version = self.shred_version;
-        let recv_metrics = metrics.clone();
+        let tuning = self.tuning.unwrap_or_default();
         let pin_recv = self.pin_recv_core;
-        let name = self.name;
-        let race_tx = race.as_ref().map(|r| r.sender());
-        let capture_tx = self.capture_tx.clone();
-
-        let recv_handle = std::thread::Builder::new()
-            .name(format!("{}-recv", name))
-            .spawn(move || {
-                if let Some(core) = pin_recv {
-                    pin_to_core(core);
-                }
-                let mut receiver = crate::receiver::ShredReceiver::new(
-                    &multicast_addr,
-                    port,
-                    &interface,
-                    shred_tx,
-                    recv_metrics,
-                    shred_version,
-                    race_tx,
-                    capture_tx,
-                )
-                .expect("failed to create shred receiver");
-                receiver.run().expect("shred receiver crashed");
-            })
-            .expect("failed to spawn recv thread");
+        let name = self.name.clone();
+        // With drop_invalid, the decoder races shreds once they are verified.
+        let gated = self.verifier.as_ref().is_some_and(ShredVerifier::gates_race);
+        let race_tx = race.as_ref().filter(|_| !gated).map(|r| r.sender());
+        let (shred_tx, race_tx, chaos_handle) =
+            crate::chaos::interpose(self.chaos.clone(), name.clone(), shred_tx, race_tx);
+
+        let mut handles = Vec::new();
+        let shred_txs = shred_tx.fan_out(ports.len());
+        for ((i, port), shred_tx) in ports.enumerate().zip(shred_txs) {
+            let multicast_addr = self.multicast_addr.clone();
+            let interface = self.interface.clone();
+            let recv_metrics = metrics.clone();
+            let race_tx = race_tx.clone();
+            let capture_tx = self.capture_tx.clone();
+            let stop = self.stop.clone();
+            let thread_name =
+                if striped { format!("{}-recv{}", name, i) } else { format!("{}-recv", name) };
+            let recv_handle = std::thread::Builder::new()
+                .name(thread_name)
+                .spawn(move || {
+                    if let Some(core) = pin_recv {
+                        pin_to_core(core + i);
+                    }
+                    let receiver = crate::receiver::ShredReceiver::new(
+                        &multicast_addr,
+                        port,
+                        &interface,
+                        shred_tx,
+                        recv_metrics.clone(),
+                        shred_version,
+                        race_tx,
+                        capture_tx,
+                    );
+                    let mut receiver = match receiver {
+                        Ok(r) => r.with_tuning(tuning).with_stop(stop),
+                        Err(e) => {
+                            let at = format!("{}:{} on {}", multicast_addr, port, interface);
+                            return recv_failed(&recv_metrics, SourceErrorKind::Bind, &at, e);
+                        }
+                    };
+                    if let Err(e) = receiver.run() {
+                        let at = format!("{}:{}", multicast_addr, port);
+                        recv_failed(&recv_metrics, SourceErrorKind::Receive, &at, e);
+                    }
+                })
+                .expect("failed to spawn recv thread");
+            handles.push(recv_handle);
+        }
+        // Only the receivers hold the decoder's queue, so it closes once
+        // they have all stopped.
+        drop(race_tx);
 
         let pin_decode = self.pin_decode_core;
+        let headers_only = self.headers_only;
+        let max_lag_slots = self.max_lag_slots;
+        let forensics = self.forensics.clone();
+        let repair = self.repair.clone();
+        let budget = self.budget.clone();
+        let verifier = self.verifier.clone();
         let decode_handle = std::thread::Builder::new()
             .name(format!("{}-decode", name))
             .spawn(move || {
                 if let Some(core) = pin_decode {
                     pin_to_core(core);
                 }
-                let decoder = crate::decoder::ShredDecoder::new(shred_rx, tx, metrics);
+                let decoder = crate::decoder::ShredDecoder::new(shred_rx, tx, metrics)
+                    .with_race(race)
+                    .with_headers_only(headers_only)
+                    .with_max_lag_slots(max_lag_slots)
+                    .with_forensics(forensics)
+                    .with_repair(repair)
+                    .with_budget(budget)
+                    .with_verifier(verifier);
                 decoder.run().expect("shred decoder crashed");
             })
             .expect("failed to spawn decode thread");
 
-        vec![recv_handle, decode_handle]
+        handles.push(decode_handle);
+        handles.extend(chaos_handle);
+        handles
     }
 }
 
@@ -140,41 +301,85 @@ impl TxSource for ShredTxSource {
 ///
 /// Use this as a baseline to measure how many milliseconds faster a premium shred
 /// feed (bebop, jito-shredstream) delivers each shred vs standard turbine propagation.
+#[derive(Clone)]
 pub struct TurbineTxSource {
     /// Display name (e.g. "turbine")
-    pub name: &'static str,
+    pub name: Arc<str>,
     /// TVU port the validator listens on (default 8002)
     pub port: u16,
     pub pin_recv_core: Option<usize>,
     pub pin_decode_core: Option<usize>,
     pub shred_version: Option<u16>,
     pub capture_tx: Option<crossbeam_channel::Sender<CaptureEvent>>,
+    pub chaos: Option<ChaosConfig>,
+    pub headers_only: bool,
+    pub max_lag_slots: Option<u64>,
+    pub forensics: Option<ForensicsTap>,
+    pub repair: Option<RepairClient>,
+    pub budget: Option<MemoryBudget>,
+    pub verifier: Option<ShredVerifier>,
+    pub tuning: Option<SocketTuning>,
+    pub stop: Option<StopSignal>,
 }
 
 impl TxSource for TurbineTxSource {
-    fn name(&self) -> &'static str {
-        self.name
+    fn name(&self) -> Arc<str> {
+        self.name.clone()
+    }
+
+    fn respawn(&self) -> Option<Box<dyn TxSource>> {
+        Some(Box::new(self.clone()))
     }
 
     fn is_rpc(&self) -> bool {
         false
     }
 
+    fn set_forensics(&mut self, forensics: ForensicsTap) {
+        self.forensics = Some(forensics);
+    }
+
+    fn set_repair(&mut self, repair: RepairClient) {
+        self.repair = Some(repair);
+    }
+
+    fn set_budget(&mut self, budget: MemoryBudget) {
+        self.budget = Some(budget);
+    }
+
+    fn set_verifier(&mut self, verifier: ShredVerifier) {
+        self.verifier = Some(verifier);
+    }
+
+    fn set_tuning(&mut self, tuning: SocketTuning) {
+        self.tuning = Some(tuning);
+    }
+
+    fn set_stop(&mut self, stop: StopSignal) {
+        self.stop = Some(stop);
+    }
+
     fn start(
         self: Box<Self>,
-        tx: Sender<DecodedTx>,
+        tx: Sender<Vec<DecodedTx>>,
         metrics: Arc<SourceMetrics>,
         race: Option<Arc<ShredRaceTracker>>,
     ) -> Vec<JoinHandle<()>> {
-        let (shred_tx, shred_rx) = crossbeam_channel::bounded(4096);
+        let (shred_tx, shred_rx) = crate::receiver::shred_queue(1);
 
         let port = self.port;
         let shred_version = self.shred_version;
+        let tuning = self.tuning.unwrap_or_default();
         let recv_metrics = metrics.clone();
         let pin_recv = self.pin_recv_core;
-        let name = self.name;
-        let race_tx = race.as_ref().map(|r| r.sender());
+        let name = self.name.clone();
+        // With drop_invalid, the decoder races shreds once they are verified.
+        let gated = self.verifier.as_ref().is_some_and(ShredVerifier::gates_race);
+        let race_tx = race.as_ref().filter(|_| !gated).map(|r| r.sender());
         let capture_tx = self.capture_tx.clone();
+        let stop = self.stop.clone();
+        let (shred_tx, race_tx, chaos_handle) =
+            crate::chaos::interpose(self.chaos.clone(), name.clone(), shred_tx, race_tx);
 
         let recv_handle = std::thread::Builder::new()
             .name(format!("{}-recv", name))
@@ -182,32 +387,53 @@ impl TxSource for TurbineTxSource {
                 if let Some(core) = pin_recv {
                     pin_to_core(core);
                 }
-                let mut receiver = crate::receiver::ShredReceiver::new_unicast(
+                let receiver = crate::receiver::ShredReceiver::new_unicast(
                     port,
                     shred_tx,
-                    recv_metrics,
+                    recv_metrics.clone(),
                     shred_version,
                     race_tx,
                     capture_tx,
-                )
-                .expect("failed to create turbine receiver");
-                receiver.run().expect("turbine receiver crashed");
+                );
+                let at = format!("port {}", port);
+                let mut receiver = match receiver {
+                    Ok(r) => r.with_tuning(tuning).with_stop(stop),
+                    Err(e) => return recv_failed(&recv_metrics, SourceErrorKind::Bind, &at, e),
+                };
+                if let Err(e) = receiver.run() {
+                    recv_failed(&recv_metrics, SourceErrorKind::Receive, &at, e);
+                }
             })
             .expect("failed to spawn turbine recv thread");
 
         let pin_decode = self.pin_decode_core;
+        let headers_only = self.headers_only;
+        let max_lag_slots = self.max_lag_slots;
+        let forensics = self.forensics.clone();
+        let repair = self.repair.clone();
+        let budget = self.budget.clone();
+        let verifier = self.verifier.clone();
         let decode_handle = std::thread::Builder::new()
             .name(format!("{}-decode", name))
             .spawn(move || {
                 if let Some(core) = pin_decode {
                     pin_to_core(core);
                 }
-                let decoder = crate::decoder::ShredDecoder::new(shred_rx, tx, metrics);
+                let decoder = crate::decoder::ShredDecoder::new(shred_rx, tx, metrics)
+                    .with_race(race)
+                    .with_headers_only(headers_only)
+                    .with_max_lag_slots(max_lag_slots)
+                    .with_forensics(forensics)
+                    .with_repair(repair)
+                    .with_budget(budget)
+                    .with_verifier(verifier);
                 decoder.run().expect("turbine decoder crashed");
             })
             .expect("failed to spawn turbine decode thread");
 
-        vec![recv_handle, decode_handle]
+        let mut handles = vec![recv_handle, decode_handle];
+        handles.extend(chaos_handle);
+        handles
     }
 }
 
@@ -220,9 +446,14 @@ impl TxSource for TurbineTxSource {
 /// Binds `addr:port` without SO_REUSEPORT. Suitable for receiving shreds forwarded
 /// by a shredder relay or any unicast UDP sender. Use this when you cannot join
 /// a multicast group directly but have a relay forwarding shreds to you.
+///
+/// With `recv_sockets > 1` the port is instead bound by a SO_REUSEPORT group
+/// with a shred-index fanout filter, one recv thread per socket, all feeding
+/// the same decoder (see [`crate::receiver::ShredReceiver::new_unicast_group`]).
+#[derive(Clone)]
 pub struct UnicastTxSource {
     /// Display name (e.g. "my-relay")
-    pub name: &'static str,
+    pub name: Arc<str>,
     /// Local bind address (e.g. "0.0.0.0" or a specific IP)
     pub addr: String,
     /// UDP port to listen on
@@ -231,67 +462,174 @@ pub struct UnicastTxSource {
     pub pin_decode_core: Option<usize>,
     pub shred_version: Option<u16>,
     pub capture_tx: Option<crossbeam_channel::Sender<CaptureEvent>>,
+    /// Number of receive sockets/threads. 1 = single exclusive socket.
+    /// Recv thread `i` is pinned to `pin_recv_core + i`.
+    pub recv_sockets: usize,
+    pub chaos: Option<ChaosConfig>,
+    pub headers_only: bool,
+    pub max_lag_slots: Option<u64>,
+    pub forensics: Option<ForensicsTap>,
+    pub repair: Option<RepairClient>,
+    pub budget: Option<MemoryBudget>,
+    pub verifier: Option<ShredVerifier>,
+    pub tuning: Option<SocketTuning>,
+    pub stop: Option<StopSignal>,
 }
 
 impl TxSource for UnicastTxSource {
-    fn name(&self) -> &'static str {
-        self.name
+    fn name(&self) -> Arc<str> {
+        self.name.clone()
+    }
+
+    fn respawn(&self) -> Option<Box<dyn TxSource>> {
+        Some(Box::new(self.clone()))
     }
 
     fn is_rpc(&self) -> bool {
         false
     }
 
+    fn set_forensics(&mut self, forensics: ForensicsTap) {
+        self.forensics = Some(forensics);
+    }
+
+    fn set_repair(&mut self, repair: RepairClient) {
+        self.repair = Some(repair);
+    }
+
+    fn set_budget(&mut self, budget: MemoryBudget) {
+        self.budget = Some(budget);
+    }
+
+    fn set_verifier(&mut self, verifier: ShredVerifier) {
+        self.verifier = Some(verifier);
+    }
+
+    fn set_tuning(&mut self, tuning: SocketTuning) {
+        self.tuning = Some(tuning);
+    }
+
+    fn set_stop(&mut self, stop: StopSignal) {
+        self.stop = Some(stop);
+    }
+
     fn start(
         self: Box<Self>,
-        tx: Sender<DecodedTx>,
+        tx: Sender<Vec<DecodedTx>>,
         metrics: Arc<SourceMetrics>,
         race: Option<Arc<ShredRaceTracker>>,
     ) -> Vec<JoinHandle<()>> {
-        let (shred_tx, shred_rx) = crossbeam_channel::bounded(4096);
+        let (shred_tx, shred_rx) = crate::receiver::shred_queue(self.recv_sockets.max(1));
 
         let addr = self.addr.clone();
         let port = self.port;
         let shred_version = self.shred_version;
+        let tuning = self.tuning.unwrap_or_default();
         let recv_metrics = metrics.clone();
         let pin_recv = self.pin_recv_core;
-        let name = self.name;
-        let race_tx = race.as_ref().map(|r| r.sender());
+        let name = self.name.clone();
+        // With drop_invalid, the decoder races shreds once they are verified.
+        let gated = self.verifier.as_ref().is_some_and(ShredVerifier::gates_race);
+        let race_tx = race.as_ref().filter(|_| !gated).map(|r| r.sender());
         let capture_tx = self.capture_tx.clone();
-
-        let recv_handle = std::thread::Builder::new()
-            .name(format!("{}-recv", name))
-            .spawn(move || {
-                if let Some(core) = pin_recv {
-                    pin_to_core(core);
-                }
-                let mut receiver = crate::receiver::ShredReceiver::new_generic_unicast(
-                    &addr,
-                    port,
-                    shred_tx,
-                    recv_metrics,
-                    shred_version,
-                    race_tx,
-                    capture_tx,
-                )
-                .expect("failed to create unicast receiver");
-                receiver.run().expect("unicast receiver crashed");
-            })
-            .expect("failed to spawn unicast recv thread");
+        let stop = self.stop.clone();
+        let (shred_tx, race_tx, chaos_handle) =
+            crate::chaos::interpose(self.chaos.clone(), name.clone(), shred_tx, race_tx);
+
+        let mut handles = Vec::new();
+        if self.recv_sockets > 1 {
+            // Bind the whole group before spawning so every socket is in place
+            // (and the fanout filter attached) before any traffic is read.
+            let group = crate::receiver::ShredReceiver::new_unicast_group(
+                &addr,
+                port,
+                self.recv_sockets,
+                shred_tx,
+                recv_metrics.clone(),
+                shred_version,
+                race_tx,
+                capture_tx,
+            );
+            let at = format!("{}:{}", addr, port);
+            // On a bind failure no receive thread starts; the decoder sees its
+            // channel close and exits.
+            let group = group
+                .map_err(|e| recv_failed(&recv_metrics, SourceErrorKind::Bind, &at, e))
+                .unwrap_or_default();
+            for (i, receiver) in group.into_iter().enumerate() {
+                let mut receiver = receiver.with_tuning(tuning).with_stop(stop.clone());
+                let recv_metrics = recv_metrics.clone();
+                let at = at.clone();
+                let handle = std::thread::Builder::new()
+                    .name(format!("{}-recv{}", name, i))
+                    .spawn(move || {
+                        if let Some(core) = pin_recv {
+                            pin_to_core(core + i);
+                        }
+                        if let Err(e) = receiver.run() {
+                            recv_failed(&recv_metrics, SourceErrorKind::Receive, &at, e);
+                        }
+                    })
+                    .expect("failed to spawn unicast recv thread");
+                handles.push(handle);
+            }
+        } else {
+            let recv_handle = std::thread::Builder::new()
+                .name(format!("{}-recv", name))
+                .spawn(move || {
+                    if let Some(core) = pin_recv {
+                        pin_to_core(core);
+                    }
+                    let receiver = crate::receiver::ShredReceiver::new_generic_unicast(
+                        &addr,
+                        port,
+                        shred_tx,
+                        recv_metrics.clone(),
+                        shred_version,
+                        race_tx,
+                        capture_tx,
+                    );
+                    let at = format!("{}:{}", addr, port);
+                    let mut receiver = match receiver {
+                        Ok(r) => r.with_tuning(tuning).with_stop(stop),
+                        Err(e) => return recv_failed(&recv_metrics, SourceErrorKind::Bind, &at, e),
+                    };
+                    if let Err(e) = receiver.run() {
+                        recv_failed(&recv_metrics, SourceErrorKind::Receive, &at, e);
+                    }
+                })
+                .expect("failed to spawn unicast recv thread");
+            handles.push(recv_handle);
+        }
 
         let pin_decode = self.pin_decode_core;
+        let headers_only = self.headers_only;
+        let max_lag_slots = self.max_lag_slots;
+        let forensics = self.forensics.clone();
+        let repair = self.repair.clone();
+        let budget = self.budget.clone();
+        let verifier = self.verifier.clone();
         let decode_handle = std::thread::Builder::new()
             .name(format!("{}-decode", name))
             .spawn(move || {
                 if let Some(core) = pin_decode {
                     pin_to_core(core);
                 }
-                let decoder = crate::decoder::ShredDecoder::new(shred_rx, tx, metrics);
+                let decoder = crate::decoder::ShredDecoder::new(shred_rx, tx, metrics)
+                    .with_race(race)
+                    .with_headers_only(headers_only)
+                    .with_max_lag_slots(max_lag_slots)
+                    .with_forensics(forensics)
+                    .with_repair(repair)
+                    .with_budget(budget)
+                    .with_verifier(verifier);
                 decoder.run().expect("unicast decoder crashed");
             })
             .expect("failed to spawn unicast decode thread");
+        handles.push(decode_handle);
+        handles.extend(chaos_handle);
 
-        vec![recv_handle, decode_handle]
+        handles
     }
 }
 
@@ -300,28 +638,50 @@ impl TxSource for UnicastTxSource {
 // ---------------------------------------------------------------------------
 
 /// Wraps [`RpcSource`] into a single [`TxSource`].
+#[cfg(feature = "rpc")]
 pub struct RpcTxSource {
     pub url: String,
+    /// Websocket endpoint for `blockSubscribe`; `None` polls.
+    pub ws_url: Option<String>,
+    /// Concurrent `getBlock` requests while catching up.
+    pub parallelism: usize,
     pub pin_core: Option<usize>,
+    /// Set by [`FanInSource`] through [`TxSource::set_confirmations`].
+    pub confirmations: Option<Arc<SlotConfirmations>>,
+    /// Set by [`FanInSource`] through [`TxSource::set_cluster_tip`].
+    pub cluster_tip: Option<Arc<ClusterTip>>,
 }
 
+#[cfg(feature = "rpc")]
 impl TxSource for RpcTxSource {
-    fn name(&self) -> &'static str {
-        "rpc"
+    fn name(&self) -> Arc<str> {
+        "rpc".into()
     }
 
     fn is_rpc(&self) -> bool {
         true
     }
 
+    fn set_confirmations(&mut self, confirmations: Arc<SlotConfirmations>) {
+        self.confirmations = Some(confirmations);
+    }
+
+    fn set_cluster_tip(&mut self, tip: Arc<ClusterTip>) {
+        self.cluster_tip = Some(tip);
+    }
+
     fn start(
         self: Box<Self>,
-        tx: Sender<DecodedTx>,
+        tx: Sender<Vec<DecodedTx>>,
         metrics: Arc<SourceMetrics>,
         _race: Option<Arc<ShredRaceTracker>>,
     ) -> Vec<JoinHandle<()>> {
         let url = self.url.clone();
+        let ws_url = self.ws_url.clone();
+        let parallelism = self.parallelism;
         let pin_core = self.pin_core;
+        let confirmations = self.confirmations.clone();
+        let cluster_tip = self.cluster_tip.clone();
         let handle = std::thread::Builder::new()
             .name("rpc-source".into())
             .spawn(move || {
@@ -329,7 +689,11 @@ impl TxSource for RpcTxSource {
                     pin_to_core(core);
                 }
                 let mut source = crate::rpc_source::RpcSource::new(&url, tx, metrics)
-                    .expect("failed to create RPC source");
+                    .expect("failed to create RPC source")
+                    .with_confirmations(confirmations)
+                    .with_cluster_tip(cluster_tip)
+                    .with_ws_url(ws_url)
+                    .with_parallelism(parallelism);
                 source.run().expect("RPC source crashed");
             })
             .expect("failed to spawn rpc-source");
@@ -343,12 +707,323 @@ impl TxSource for RpcTxSource {
 
 /// Tracks the first arrival of a transaction signature in the dedup map.
 struct FirstArrival {
-    /// Receive timestamp from the winning source (nanoseconds)
-    recv_ns: u64,
+    /// Slot the winning copy was delivered in
+    slot: u64,
+    /// Receive timestamp from the winning source
+    recv_ns: MonotonicNs,
     /// Whether the winning source is an RPC source
     is_rpc: bool,
     /// Metrics handle for the winning source, used to record lead time
     metrics: Arc<SourceMetrics>,
+    /// Fee profile of the winning shred-tier copy; only parsed when the fan-in
+    /// has an early threshold configured.
+    profile: Option<TxProfile>,
+}
+
+/// The first arrival of a signature in each slot it was delivered in.
+struct Arrivals {
+    first: FirstArrival,
+    /// Later slots, when the transaction was resubmitted. Empty, and not
+    /// allocated, for nearly every signature.
+    other_slots: Vec<FirstArrival>,
+    /// The first copy, not yet forwarded while the preferred source may
+    /// still tie with it; see [`Preference`].
+    held: Option<Box<ForwardedTx>>,
+}
+
+impl Arrivals {
+    fn in_slot(&self, slot: u64) -> Option<&FirstArrival> {
+        std::iter::once(&self.first).chain(&self.other_slots).find(|a| a.slot == slot)
+    }
+}
+
+type DedupMap = DashMap<[u8; 64], Arrivals>;
+
+/// A transaction leaving the fan-in.
+pub struct ForwardedTx {
+    pub decoded: DecodedTx,
+    /// Source that delivered this copy.
+    pub source: Arc<str>,
+    /// `None` for the first copy of a signature. Duplicates only appear when
+    /// [`FanInSource::forward_duplicates_per_sec`] is set.
+    pub duplicate_of: Option<DuplicateOf>,
+}
+
+/// How a forwarded duplicate relates to the copy that won.
+pub struct DuplicateOf {
+    /// Source whose copy arrived first.
+    pub winner: Arc<str>,
+    /// Receive time of this copy minus that of the winning copy, in µs.
+    pub lag_us: i64,
+}
+
+/// Per-second cap on forwarded duplicates, shared by every relay thread.
+/// Duplicates beyond the cap are dropped as before; first copies are never
+/// throttled.
+struct DuplicateThrottle {
+    per_sec: u64,
+    second: AtomicU64,
+    sent: AtomicU64,
+}
+
+impl DuplicateThrottle {
+    fn new(per_sec: u32) -> Self {
+        Self { per_sec: per_sec as u64, second: AtomicU64::new(0), sent: AtomicU64::new(0) }
+    }
+
+    /// Take one slot in the current second's budget. Approximate at the
+    /// second boundary, where two threads may both reset the count.
+    fn allow(&self) -> bool {
+        let now_s = metrics::now_ns() / 1_000_000_000;
+        if self.second.swap(now_s, Relaxed) != now_s {
+            self.sent.store(0, Relaxed);
+        }
+        self.sent.fetch_add(1, Relaxed) < self.per_sec
+    }
+}
+
+/// Tie-break of the forwarded stream; see [`FanInSource::preferred_source`].
+struct Preference {
+    source: Arc<str>,
+    tie_window_ns: u64,
+    /// Signatures whose first copy is held, with when to release it.
+    held_tx: Sender<(MonotonicNs, [u8; 64])>,
+}
+
+/// Per-source relay state: dedups one source's batches against every other
+/// source and forwards first arrivals downstream.
+struct Relay {
+    dedup: Arc<DedupMap>,
+    out_tx: Sender<ForwardedTx>,
+    /// Set when duplicates are forwarded too.
+    duplicates: Option<Arc<DuplicateThrottle>>,
+    /// See [`FanInSource::filter_programs`].
+    filter: Arc<HashSet<Pubkey>>,
+    metrics: Arc<SourceMetrics>,
+    is_rpc: bool,
+    early_threshold_us: Option<i64>,
+    /// See [`FanInSource::sample_tx_prefix_bits`].
+    sample_bits: u8,
+    /// Copies in different slots race separately; see
+    /// [`FanInSource::dedup_signature_only`].
+    by_slot: bool,
+    /// See [`FanInSource::trace`].
+    trace: Option<TraceTap>,
+    /// See [`FanInSource::preferred_source`].
+    prefer: Option<Arc<Preference>>,
+    /// Check receive times against the clock (see [`MonotonicNs::check_live`]).
+    /// Off for replays, whose receive times are capture time.
+    live: bool,
+}
+
+impl Relay {
+    /// Dedup and forward one batch from the source. The pause check is made
+    /// and the first/duplicate counters are added once per batch; each
+    /// transaction still costs one dedup map operation.
+    fn relay_batch(&self, batch: Vec<DecodedTx>) {
+        // Paused sources (e.g. `shredtop ctl pause`) and failover
+        // standbys take no part in dedup or lead-time accounting.
+        if self.metrics.is_paused() || self.metrics.is_standby() {
+            return;
+        }
+
+        // A decoder batch holds one slot's transactions.
+        let slot = batch.first().map(|decoded| decoded.slot);
+        let keyed = batch
+            .into_iter()
+            .filter(|decoded| self.passes_filter(decoded))
+            .filter_map(|decoded| {
+                let sig = decoded.transaction.signatures.first()?;
+                Some((<[u8; 64]>::try_from(sig.as_ref()).ok()?, decoded))
+            })
+            .filter(|(sig, _)| sampled(sig, self.sample_bits));
+
+        // Receive times are compared after removing the source's timestamp
+        // offset; the forwarded transaction keeps its raw timestamp.
+        let offset_ns = self.metrics.recv_offset_ns();
+        let now = if self.live || self.trace.is_some() {
+            MonotonicNs::now()
+        } else {
+            MonotonicNs::default()
+        };
+        let (mut firsts, mut duplicates, mut slot_mismatches) = (0u64, 0u64, 0u64);
+        for (sig, decoded) in keyed {
+            use dashmap::mapref::entry::Entry;
+            if self.live {
+                decoded.shred_recv_ns.check_live(now);
+            }
+            let recv_ns = decoded.shred_recv_ns.saturating_add_signed(-offset_ns);
+            let trace = self.trace.as_ref().filter(|t| t.samples(&sig));
+            match self.dedup.entry(sig) {
+                Entry::Vacant(e) => {
+                    // First arrival — forward downstream
+                    firsts += 1;
+                    let first = self.arrival(&decoded, recv_ns);
+                    if let Some(trace) = trace {
+                        trace.record_copy(&decoded, &self.metrics.name, now, None);
+                    }
+                    let forwarded = ForwardedTx {
+                        decoded,
+                        source: self.metrics.name.clone(),
+                        duplicate_of: None,
+                    };
+                    match self.prefer.as_ref().filter(|p| p.source != self.metrics.name) {
+                        Some(prefer) => {
+                            // Held until the preferred source's tie window has passed.
+                            let held = Some(Box::new(forwarded));
+                            e.insert(Arrivals { first, other_slots: Vec::new(), held });
+                            let release = MonotonicNs::now().saturating_add(prefer.tie_window_ns);
+                            let _ = prefer.held_tx.send((release, sig));
+                        }
+                        None => {
+                            e.insert(Arrivals { first, other_slots: Vec::new(), held: None });
+                            let _ = self.out_tx.try_send(forwarded);
+                        }
+                    }
+                }
+                Entry::Occupied(mut e) => {
+                    let arrivals = e.get_mut();
+                    let same_slot = arrivals.in_slot(decoded.slot).is_some();
+                    if !same_slot {
+                        slot_mismatches += 1;
+                        if self.by_slot {
+                            // Resubmitted into a new slot: a race of its own.
+                            let arrival = self.arrival(&decoded, recv_ns);
+                            arrivals.other_slots.push(arrival);
+                            if let Some(trace) = trace {
+                                trace.record_copy(&decoded, &self.metrics.name, now, None);
+                            }
+                            continue;
+                        }
+                    }
+                    // Duplicate — record lead time
+                    duplicates += 1;
+                    let first = match arrivals.in_slot(decoded.slot) {
+                        Some(first) if self.by_slot => first,
+                        _ => &arrivals.first,
+                    };
+                    self.record_lead(first, recv_ns);
+                    if let Some(trace) = trace {
+                        let lag_us = recv_ns.delta(first.recv_ns) / 1000;
+                        let winner = Some((first.metrics.name.clone(), lag_us));
+                        trace.record_copy(&decoded, &self.metrics.name, now, winner);
+                    }
+                    if self.ties(arrivals, &decoded, recv_ns) {
+                        // Forwarded in place of the held copy, which is dropped.
+                        arrivals.held = None;
+                        drop(e);
+                        let _ = self.out_tx.try_send(ForwardedTx {
+                            decoded,
+                            source: self.metrics.name.clone(),
+                            duplicate_of: None,
+                        });
+                        continue;
+                    }
+                    if self.duplicates.as_ref().is_some_and(|t| t.allow()) {
+                        let duplicate_of = DuplicateOf {
+                            winner: first.metrics.name.clone(),
+                            lag_us: recv_ns.delta(first.recv_ns) / 1000,
+                        };
+                        drop(e);
+                        let _ = self.out_tx.try_send(ForwardedTx {
+                            decoded,
+                            source: self.metrics.name.clone(),
+                            duplicate_of: Some(duplicate_of),
+                        });
+                    }
+                }
+            }
+        }
+        self.metrics.txs_first.fetch_add(firsts, Relaxed);
+        self.metrics.txs_duplicate.fetch_add(duplicates, Relaxed);
+        if slot_mismatches > 0 {
+            self.metrics.txs_slot_mismatch.fetch_add(slot_mismatches, Relaxed);
+        }
+        if let Some(slot) = slot.filter(|_| firsts > 0 && !self.is_rpc) {
+            self.metrics.record_slot_firsts(slot, firsts as u32);
+        }
+    }
+
+    /// The first arrival of `decoded` in its slot, received at `recv_ns`
+    /// (offset-corrected).
+    fn arrival(&self, decoded: &DecodedTx, recv_ns: MonotonicNs) -> FirstArrival {
+        let profile = if self.early_threshold_us.is_some() && !self.is_rpc {
+            TxProfile::from_transaction(&decoded.transaction)
+        } else {
+            None
+        };
+        FirstArrival {
+            slot: decoded.slot,
+            recv_ns,
+            is_rpc: self.is_rpc,
+            metrics: self.metrics.clone(),
+            profile,
+        }
+    }
+
+    /// Whether this source is the preferred one and its copy of `decoded`,
+    /// received at `recv_ns`, ties with a first copy still held.
+    fn ties(&self, arrivals: &Arrivals, decoded: &DecodedTx, recv_ns: MonotonicNs) -> bool {
+        let Some(prefer) = self.prefer.as_ref().filter(|p| p.source == self.metrics.name) else {
+            return false;
+        };
+        arrivals.held.is_some()
+            && arrivals.first.slot == decoded.slot
+            && recv_ns.delta(arrivals.first.recv_ns) <= prefer.tie_window_ns as i64
+    }
+
+    /// Apply the program/account filter to shred-tier sources. RPC-tier
+    /// sources are exempt so they always provide timestamps.
+    fn passes_filter(&self, decoded: &DecodedTx) -> bool {
+        if self.filter.is_empty() || self.is_rpc {
+            return true;
+        }
+        let keys = decoded.transaction.message.static_account_keys();
+        keys.iter().any(|k| self.filter.contains(k))
+    }
+
+    /// Lead time of a duplicate received at `recv_ns` (offset-corrected)
+    /// against the first arrival of its signature.
+    fn record_lead(&self, first: &FirstArrival, recv_ns: MonotonicNs) {
+        // Lead time: positive = shred arrived before RPC.
+        // If the first arrival was shred and the duplicate is RPC,
+        // the lead is (rpc_recv - shred_recv).
+        // If the first arrival was RPC and the duplicate is shred,
+        // the lead is negative (shred arrived late).
+        let (shred_ns, rpc_ns) = if !first.is_rpc && self.is_rpc {
+            // First=shred, current=rpc
+            (first.recv_ns, recv_ns)
+        } else if first.is_rpc && !self.is_rpc {
+            // First=rpc, current=shred
+            (recv_ns, first.recv_ns)
+        } else {
+            // Both same type — compare timestamps directly
+            // (shred vs shred: measures relative lead between feeds)
+            if !self.is_rpc {
+                (recv_ns, first.recv_ns)
+            } else {
+                return; // rpc vs rpc: skip
+            }
+        };
+
+        let lead_us = rpc_ns.delta(shred_ns) / 1000;
+
+        if !first.is_rpc {
+            // Record on the shred source that arrived first
+            first.metrics.record_lead_time_us(lead_us);
+            if self.is_rpc {
+                let early = (self.early_threshold_us, &first.profile);
+                if let (Some(threshold), Some(profile)) = early {
+                    if lead_us > threshold && lead_us <= SourceMetrics::LEAD_TIME_MAX_US {
+                        first.metrics.record_early_tx(profile);
+                    }
+                }
+            }
+        } else {
+            // Current source (shred) arrived after RPC — record negative lead
+            self.metrics.record_lead_time_us(lead_us);
+        }
+    }
 }
 
 /// Multi-source fan-in with deduplication.
@@ -361,11 +1036,70 @@ pub struct FanInSource {
     /// account keys include at least one of these pubkeys are counted for lead-time.
     /// Applies to shred-tier sources only; RPC-tier sources (is_rpc=true) are exempt.
     pub filter_programs: Vec<String>,
+    /// When set, transactions that beat RPC by more than this many µs have their
+    /// fee payer and compute-budget price recorded on the winning source
+    /// (see [`SourceMetrics::record_early_tx`]).
+    pub early_tx_threshold_us: Option<i64>,
+    /// When set, duplicates are forwarded downstream as well, marked with the
+    /// winning source and how far behind it they arrived, up to this many per
+    /// second across all sources. `None` drops every duplicate.
+    pub forward_duplicates_per_sec: Option<u32>,
+    /// When non-zero, only transactions whose first signature starts with
+    /// this many zero bits take part in dedup and lead-time accounting — a
+    /// 1-in-2^N sample. The signature is the same on every source, so each
+    /// source sees the same sample and comparisons stay unbiased. At most 16.
+    pub sample_tx_prefix_bits: u8,
+    /// Dedup on the signature alone, as before slots were part of the key: a
+    /// copy delivered in another slot than the first is a duplicate of it and
+    /// its lead is measured against it. Off by default; see the module docs.
+    pub dedup_signature_only: bool,
+    /// Handed to every shred-tier source; see [`crate::forensics`].
+    pub forensics: Option<ForensicsTap>,
+    /// Handed to every shred-tier source; see [`crate::repair`].
+    pub repair: Option<RepairClient>,
+    /// Shared by the decoders, the dedup map and the shred race; see
+    /// [`crate::budget`].
+    pub budget: Option<MemoryBudget>,
+    /// Handed to every shred-tier source; see [`crate::shred_verify`].
+    pub verifier: Option<ShredVerifier>,
+    /// Receive socket settings handed to every shred-tier source; `None`
+    /// keeps [`SocketTuning::default`].
+    pub tuning: Option<SocketTuning>,
+    /// Traces every copy of the sampled signatures; see [`crate::trace`].
+    pub trace: Option<TraceTap>,
+    /// Restarts of sources whose threads stopped; see [`crate::supervise`].
+    pub restart: RestartPolicy,
+    /// Source whose copy is forwarded when it arrives within
+    /// [`Self::tie_window_us`] of the first one; see the module docs. `None`
+    /// forwards the first copy straight away.
+    pub preferred_source: Option<String>,
+    /// Receive-time difference still counted as a tie, and how long first
+    /// copies from other sources are held, in µs. 0 turns preference off.
+    pub tie_window_us: u64,
+    /// What the decoders expire slots against; see [`SlotExpiry`].
+    pub slot_expiry: SlotExpiry,
 }
 
 impl FanInSource {
     pub fn new() -> Self {
-        Self { sources: Vec::new(), filter_programs: Vec::new() }
+        Self {
+            sources: Vec::new(),
+            filter_programs: Vec::new(),
+            early_tx_threshold_us: None,
+            forward_duplicates_per_sec: None,
+            sample_tx_prefix_bits: 0,
+            dedup_signature_only: false,
+            forensics: None,
+            repair: None,
+            budget: None,
+            verifier: None,
+            tuning: None,
+            trace: None,
+            restart: RestartPolicy::default(),
+            preferred_source: None,
+            tie_window_us: 0,
+            slot_expiry: SlotExpiry::Local,
+        }
     }
 
     pub fn add_source(&mut self, source: Box<dyn TxSource>, metrics: Arc<SourceMetrics>) {
@@ -376,13 +1110,15 @@ impl FanInSource {
     /// and all thread handles.
     pub fn start(
         self,
-        out_tx: Sender<DecodedTx>,
+        out_tx: Sender<ForwardedTx>,
     ) -> (Vec<Arc<SourceMetrics>>, Arc<ShredRaceTracker>, Vec<JoinHandle<()>>) {
-        let dedup: Arc<DashMap<[u8; 64], FirstArrival>> = Arc::new(DashMap::new());
+        let dedup: Arc<DedupMap> = Arc::new(DashMap::new());
         let mut all_handles: Vec<JoinHandle<()>> = Vec::new();
         let mut all_metrics: Vec<Arc<SourceMetrics>> = Vec::new();
+        let mut supervised: Vec<Supervised> = Vec::new();
 
-        let race_tracker = ShredRaceTracker::new();
+        let race_tracker = ShredRaceTracker::with_budget(self.budget.clone());
+        race_tracker.cluster_tip().set_expiry(self.slot_expiry);
 
         // Parse filter programs once at start time; shared across relay threads.
         let filter_set: Arc<HashSet<Pubkey>> = Arc::new(
@@ -392,106 +1128,126 @@ impl FanInSource {
                 .collect(),
         );
 
-        for (source, source_metrics) in self.sources {
+        let early_threshold_us = self.early_tx_threshold_us;
+        let sample_bits = self.sample_tx_prefix_bits.min(16);
+        let duplicates =
+            self.forward_duplicates_per_sec.map(|n| Arc::new(DuplicateThrottle::new(n)));
+
+        let prefer = match self.preferred_source {
+            Some(source) if self.tie_window_us > 0 => {
+                let (held_tx, held_rx) = crossbeam_channel::unbounded::<(MonotonicNs, [u8; 64])>();
+                let (dedup, out_tx) = (dedup.clone(), out_tx.clone());
+                let handle = std::thread::Builder::new()
+                    .name("fan-in-tiebreak".into())
+                    .spawn(move || {
+                        for (release, sig) in held_rx {
+                            let wait_ns = release.delta(MonotonicNs::now());
+                            if wait_ns > 0 {
+                                std::thread::sleep(std::time::Duration::from_nanos(wait_ns as u64));
+                            }
+                            release_held(&dedup, &out_tx, &sig);
+                        }
+                    })
+                    .expect("failed to spawn tiebreak thread");
+                all_handles.push(handle);
+                let tie_window_ns = self.tie_window_us * 1000;
+                Some(Arc::new(Preference { source: source.into(), tie_window_ns, held_tx }))
+            }
+            _ => None,
+        };
+
+        // RPC-tier sources resolve slots for every shred-tier one. A slot
+        // reported twice (several `rpc` sources) is only counted once.
+        let confirmations = Arc::new(SlotConfirmations::new(
+            self.sources
+                .iter()
+                .filter(|(source, _)| !source.is_rpc())
+                .map(|(_, metrics)| metrics.clone())
+                .collect(),
+        ));
+
+        for (mut source, source_metrics) in self.sources {
             let source_name = source.name();
             let source_is_rpc = source.is_rpc();
-            let (inner_tx, inner_rx) = crossbeam_channel::bounded::<DecodedTx>(4096);
+            if source_is_rpc {
+                source.set_confirmations(confirmations.clone());
+                if self.slot_expiry == SlotExpiry::Baseline {
+                    source.set_cluster_tip(race_tracker.cluster_tip().clone());
+                }
+            } else {
+                if let Some(ref tap) = self.forensics {
+                    source.set_forensics(tap.clone());
+                }
+                if let Some(ref repair) = self.repair {
+                    source.set_repair(repair.clone());
+                }
+                if let Some(ref budget) = self.budget {
+                    source.set_budget(budget.clone());
+                }
+                if let Some(ref verifier) = self.verifier {
+                    source.set_verifier(verifier.clone());
+                }
+                if let Some(tuning) = self.tuning {
+                    source.set_tuning(tuning);
+                }
+            }
+            // One message per decoder flush or block, so far fewer than 4096
+            // transactions' worth of slots are needed.
+            let (inner_tx, inner_rx) = crossbeam_channel::bounded::<Vec<DecodedTx>>(1024);
 
             // Pass the race tracker to shred-tier sources; None for RPC-tier.
             let race_arg = if !source_is_rpc { Some(race_tracker.clone()) } else { None };
-            let source_handles = source.start(inner_tx, source_metrics.clone(), race_arg);
-            all_handles.extend(source_handles);
+            supervised.push(Supervised::new(source, inner_tx, source_metrics.clone(), race_arg));
             all_metrics.push(source_metrics.clone());
 
-            let dedup_clone = dedup.clone();
-            let out_tx_clone = out_tx.clone();
-            let filter_clone = filter_set.clone();
+            let relay = Relay {
+                dedup: dedup.clone(),
+                out_tx: out_tx.clone(),
+                duplicates: duplicates.clone(),
+                filter: filter_set.clone(),
+                metrics: source_metrics,
+                is_rpc: source_is_rpc,
+                early_threshold_us,
+                sample_bits,
+                by_slot: !self.dedup_signature_only,
+                trace: self.trace.clone(),
+                prefer: prefer.clone(),
+                live: true,
+            };
 
             let relay_handle = std::thread::Builder::new()
                 .name(format!("fan-in-{}", source_name))
                 .spawn(move || {
-                    for decoded in &inner_rx {
-                        // Apply program/account filter for shred-tier sources.
-                        // RPC-tier sources are exempt so they always provide timestamps.
-                        if !filter_clone.is_empty() && !source_is_rpc {
-                            let keys = decoded.transaction.message.static_account_keys();
-                            if !keys.iter().any(|k| filter_clone.contains(k)) {
-                                continue;
-                            }
-                        }
-
-                        let sig_bytes: [u8; 64] = match decoded.transaction.signatures.first() {
-                            Some(sig) => match sig.as_ref().try_into() {
-                                Ok(b) => b,
-                                Err(_) => continue,
-                            },
-                            None => continue,
-                        };
-
-                        use dashmap::mapref::entry::Entry;
-                        match dedup_clone.entry(sig_bytes) {
-                            Entry::Vacant(e) => {
-                                // First arrival — forward downstream
-                                source_metrics.txs_first.fetch_add(1, Relaxed);
-                                e.insert(FirstArrival {
-                                    recv_ns: decoded.shred_recv_ns,
-                                    is_rpc: source_is_rpc,
-                                    metrics: source_metrics.clone(),
-                                });
-                                let _ = out_tx_clone.try_send(decoded);
-                            }
-                            Entry::Occupied(e) => {
-                                // Duplicate — record lead time
-                                source_metrics.txs_duplicate.fetch_add(1, Relaxed);
-                                let first = e.get();
-
-                                // Lead time: positive = shred arrived before RPC.
-                                // If the first arrival was shred and the duplicate is RPC,
-                                // the lead is (rpc_recv - shred_recv).
-                                // If the first arrival was RPC and the duplicate is shred,
-                                // the lead is negative (shred arrived late).
-                                let (shred_ns, rpc_ns) = if !first.is_rpc && source_is_rpc {
-                                    // First=shred, current=rpc
-                                    (first.recv_ns, decoded.shred_recv_ns)
-                                } else if first.is_rpc && !source_is_rpc {
-                                    // First=rpc, current=shred
-                                    (decoded.shred_recv_ns, first.recv_ns)
-                                } else {
-                                    // Both same type — compare timestamps directly
-                                    // (shred vs shred: measures relative lead between feeds)
-                                    if !source_is_rpc {
-                                        (decoded.shred_recv_ns, first.recv_ns)
-                                    } else {
-                                        continue; // rpc vs rpc: skip
-                                    }
-                                };
-
-                                let lead_us = (rpc_ns as i64 - shred_ns as i64) / 1000;
-
-                                if !first.is_rpc {
-                                    // Record on the shred source that arrived first
-                                    first.metrics.record_lead_time_us(lead_us);
-                                } else {
-                                    // Current source (shred) arrived after RPC — record negative lead
-                                    source_metrics.record_lead_time_us(lead_us);
-                                }
-                            }
-                        }
+                    for batch in &inner_rx {
+                        relay.relay_batch(batch);
                     }
                 })
                 .expect("failed to spawn relay thread");
 
             all_handles.push(relay_handle);
         }
+        all_handles.push(crate::supervise::spawn(supervised, self.restart));
 
-        // Eviction thread: every 60s, drop dedup entries older than 15 minutes
+        // Eviction thread: every 60s, drop dedup entries older than 15 minutes.
+        // With a memory budget it also wakes every second to shed entries.
         let dedup_evict = dedup;
+        let mut share = self.budget.map(|b| b.share(BudgetUser::Dedup));
         let evict_handle = std::thread::Builder::new()
             .name("fan-in-evict".into())
-            .spawn(move || loop {
-                std::thread::sleep(std::time::Duration::from_secs(60));
-                let cutoff_ns = metrics::now_ns().saturating_sub(900_000_000_000);
-                dedup_evict.retain(|_, v| v.recv_ns > cutoff_ns);
+            .spawn(move || {
+                let tick_secs = if share.is_some() { 1 } else { EVICT_INTERVAL_SECS };
+                let mut next_evict = MonotonicNs::now().saturating_add(EVICT_INTERVAL_NS);
+                loop {
+                    std::thread::sleep(std::time::Duration::from_secs(tick_secs));
+                    let now = MonotonicNs::now();
+                    if now >= next_evict {
+                        evict(&dedup_evict, now);
+                        next_evict = now.saturating_add(EVICT_INTERVAL_NS);
+                    }
+                    if let Some(ref mut share) = share {
+                        shed_dedup(&dedup_evict, share, now);
+                    }
+                }
             })
             .expect("failed to spawn evict thread");
         all_handles.push(evict_handle);
@@ -506,10 +1262,122 @@ impl Default for FanInSource {
     }
 }
 
+const EVICT_INTERVAL_SECS: u64 = 60;
+const EVICT_INTERVAL_NS: u64 = EVICT_INTERVAL_SECS * 1_000_000_000;
+const DEDUP_TTL_NS: u64 = 900_000_000_000;
+/// Approximate bytes per dedup entry: key, value and the map's own overhead.
+const DEDUP_ENTRY_BYTES: u64 = 160;
+/// Entries younger than this are never shed to the memory budget.
+const DEDUP_MIN_AGE_NS: u64 = 10_000_000_000;
+
+/// Whether `sig` is in the `bits`-bit sample: its first `bits` bits are zero.
+fn sampled(sig: &[u8; 64], bits: u8) -> bool {
+    bits == 0 || u16::from_be_bytes([sig[0], sig[1]]) >> (16 - bits.min(16)) == 0
+}
+
+/// Forward the held first copy of `sig`, unless the preferred source's copy
+/// has replaced it.
+fn release_held(dedup: &DedupMap, out_tx: &Sender<ForwardedTx>, sig: &[u8; 64]) {
+    let held = dedup.get_mut(sig).and_then(|mut arrivals| arrivals.held.take());
+    if let Some(forwarded) = held {
+        let _ = out_tx.try_send(*forwarded);
+    }
+}
+
+/// Drop dedup entries first seen more than 15 minutes before `now`.
+fn evict(dedup: &DedupMap, now: MonotonicNs) {
+    let cutoff = now.saturating_sub(DEDUP_TTL_NS);
+    dedup.retain(|_, v| v.first.recv_ns > cutoff);
+}
+
+/// Report the dedup map's size to the budget and, while the map holds more
+/// than its part of it, halve the age of the entries kept, down to [`DEDUP_MIN_AGE_NS`].
+fn shed_dedup(dedup: &DedupMap, share: &mut BudgetShare, now: MonotonicNs) {
+    let mut max_age_ns = DEDUP_TTL_NS;
+    loop {
+        let len = dedup.len();
+        share.set(len as u64 * DEDUP_ENTRY_BYTES);
+        if !share.over() || max_age_ns == DEDUP_MIN_AGE_NS {
+            return;
+        }
+        max_age_ns = (max_age_ns / 2).max(DEDUP_MIN_AGE_NS);
+        let cutoff = now.saturating_sub(max_age_ns);
+        dedup.retain(|_, v| v.first.recv_ns > cutoff);
+        share.budget().count_dedup_shed(len.saturating_sub(dedup.len()));
+    }
+}
+
+/// Single-threaded fan-in for deterministic replays. Batches are relayed on
+/// the caller's thread in the order they are offered, so the same input
+/// always produces the same dedup winners and lead times. Eviction runs on a
+/// virtual clock driven by receive timestamps; duplicates are not forwarded.
+#[cfg(feature = "capture")]
+pub(crate) struct ReplayFanIn {
+    dedup: Arc<DedupMap>,
+    relays: Vec<Relay>,
+    next_evict: MonotonicNs,
+}
+
+#[cfg(feature = "capture")]
+impl ReplayFanIn {
+    /// One relay per entry of `sources`, all shred-tier.
+    pub(crate) fn new(sources: &[Arc<SourceMetrics>], out_tx: Sender<ForwardedTx>) -> Self {
+        let dedup: Arc<DedupMap> = Arc::new(DashMap::new());
+        let relays = sources
+            .iter()
+            .map(|metrics| Relay {
+                dedup: dedup.clone(),
+                out_tx: out_tx.clone(),
+                duplicates: None,
+                filter: Arc::default(),
+                metrics: metrics.clone(),
+                is_rpc: metrics.is_rpc,
+                early_threshold_us: None,
+                sample_bits: 0,
+                by_slot: true,
+                trace: None,
+                prefer: None,
+                live: false,
+            })
+            .collect();
+        Self { dedup, relays, next_evict: MonotonicNs::default() }
+    }
+
+    /// Relay one batch from source `index`, with the replay clock at `now`.
+    pub(crate) fn relay(&mut self, index: usize, batch: Vec<DecodedTx>, now: MonotonicNs) {
+        if now >= self.next_evict {
+            evict(&self.dedup, now);
+            self.next_evict = now.saturating_add(EVICT_INTERVAL_NS);
+        }
+        self.relays[index].relay_batch(batch);
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
 
+/// Log why a receive socket could not be set up or stopped reading, and
+/// record it against the source. The rest of the probe keeps running.
+fn recv_failed(metrics: &SourceMetrics, kind: SourceErrorKind, at: &str, e: anyhow::Error) {
+    let error = format!("{}: {:#}", at, e);
+    tracing::error!("source '{}': {:?} failed on {}", metrics.name, kind, error);
+    metrics.record_error(kind, error);
+}
+
+/// Hand a batch to the fan-in without blocking the source. When the channel
+/// is full the batch is dropped, and its transactions are counted in
+/// `txs_dropped`.
+pub(crate) fn send_batch(
+    tx: &Sender<Vec<DecodedTx>>,
+    batch: Vec<DecodedTx>,
+    metrics: &SourceMetrics,
+) {
+    if let Err(TrySendError::Full(batch)) = tx.try_send(batch) {
+        metrics.txs_dropped.fetch_add(batch.len() as u64, Relaxed);
+    }
+}
+
 fn pin_to_core(core_id: usize) {
     #[cfg(target_os = "linux")]
     unsafe {
@@ -534,16 +1402,18 @@ mod tests {
     #[test]
     fn test_first_arrival_wins() {
         let dedup: DashMap<[u8; 64], FirstArrival> = DashMap::new();
-        let metrics = SourceMetrics::new("test", false);
+        let metrics = SourceMetrics::new("test".into(), false);
         let sig: [u8; 64] = [0xAB; 64];
 
         match dedup.entry(sig) {
             Entry::Vacant(e) => {
                 metrics.txs_first.fetch_add(1, Relaxed);
                 e.insert(FirstArrival {
-                    recv_ns: 100_000,
+                    slot: 1,
+                    recv_ns: MonotonicNs(100_000),
                     is_rpc: false,
                     metrics: metrics.clone(),
+                    profile: None,
                 });
             }
             Entry::Occupied(_) => {
@@ -558,9 +1428,11 @@ mod tests {
             Entry::Vacant(e) => {
                 metrics.txs_first.fetch_add(1, Relaxed);
                 e.insert(FirstArrival {
-                    recv_ns: 200_000,
+                    slot: 1,
+                    recv_ns: MonotonicNs(200_000),
                     is_rpc: false,
                     metrics: metrics.clone(),
+                    profile: None,
                 });
             }
             Entry::Occupied(_) => {
@@ -572,6 +1444,179 @@ mod tests {
         assert_eq!(metrics.txs_duplicate.load(Relaxed), 1);
     }
 
+    fn decoded(sig: u8, recv_ns: u64) -> DecodedTx {
+        use solana_message::{Message, VersionedMessage};
+        DecodedTx {
+            transaction: solana_transaction::versioned::VersionedTransaction {
+                signatures: vec![solana_signature::Signature::from([sig; 64])],
+                message: VersionedMessage::Legacy(Message::default()),
+            },
+            slot: 1,
+            shred_recv_ns: MonotonicNs(recv_ns),
+            decode_done_ns: MonotonicNs(recv_ns),
+        }
+    }
+
+    /// A relay with every option off, sharing `dedup` and `out_tx` with the
+    /// other relays of a test.
+    fn relay(
+        name: &str,
+        is_rpc: bool,
+        dedup: &Arc<DedupMap>,
+        out_tx: &Sender<ForwardedTx>,
+    ) -> Relay {
+        Relay {
+            dedup: dedup.clone(),
+            out_tx: out_tx.clone(),
+            duplicates: None,
+            filter: Arc::default(),
+            metrics: SourceMetrics::new(name.into(), is_rpc),
+            is_rpc,
+            early_threshold_us: None,
+            sample_bits: 0,
+            by_slot: true,
+            trace: None,
+            prefer: None,
+            live: false,
+        }
+    }
+
+    #[test]
+    fn test_relay_batch_dedups_and_tallies() {
+        let dedup = Arc::new(DashMap::new());
+        let (out_tx, out_rx) = crossbeam_channel::unbounded();
+        let shred = relay("shred", false, &dedup, &out_tx);
+        let rpc = relay("rpc", true, &dedup, &out_tx);
+
+        shred.relay_batch(vec![decoded(1, 1_000_000), decoded(2, 1_000_000)]);
+        rpc.relay_batch(vec![decoded(1, 3_000_000), decoded(3, 3_000_000)]);
+
+        assert_eq!(out_rx.try_iter().count(), 3);
+        assert_eq!(shred.metrics.txs_first.load(Relaxed), 2);
+        assert_eq!(rpc.metrics.txs_first.load(Relaxed), 1);
+        assert_eq!(rpc.metrics.txs_duplicate.load(Relaxed), 1);
+        assert_eq!(shred.metrics.lead_time_sum_us.load(Relaxed), 2_000);
+    }
+
+    #[test]
+    fn test_resubmitted_tx_races_in_its_new_slot() {
+        for by_slot in [true, false] {
+            let dedup = Arc::new(DashMap::new());
+            let (out_tx, out_rx) = crossbeam_channel::unbounded();
+            let shred = Relay { by_slot, ..relay("shred", false, &dedup, &out_tx) };
+            let rpc = Relay { by_slot, ..relay("rpc", true, &dedup, &out_tx) };
+            let in_slot = |slot, recv_ns| DecodedTx { slot, ..decoded(1, recv_ns) };
+
+            // Seen in slot 1, whose fork was abandoned, then landed in slot 2.
+            shred.relay_batch(vec![in_slot(1, 1_000_000)]);
+            shred.relay_batch(vec![in_slot(2, 50_000_000)]);
+            rpc.relay_batch(vec![in_slot(2, 52_000_000)]);
+
+            assert_eq!(out_rx.try_iter().count(), 1, "forwarded once");
+            assert_eq!(shred.metrics.txs_first.load(Relaxed), 1);
+            assert_eq!(shred.metrics.txs_slot_mismatch.load(Relaxed), 1);
+            assert_eq!(rpc.metrics.txs_duplicate.load(Relaxed), 1);
+            if by_slot {
+                // Against the slot-2 copy, not the one 49ms earlier.
+                assert_eq!(shred.metrics.txs_duplicate.load(Relaxed), 0);
+                assert_eq!(shred.metrics.lead_time_count.load(Relaxed), 1);
+                assert_eq!(shred.metrics.lead_time_sum_us.load(Relaxed), 2_000);
+            } else {
+                // Both slot-2 copies are measured against the slot-1 one.
+                assert_eq!(shred.metrics.txs_duplicate.load(Relaxed), 1);
+                assert_eq!(rpc.metrics.txs_slot_mismatch.load(Relaxed), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_recv_offset_applied_to_lead() {
+        let dedup = Arc::new(DashMap::new());
+        let (out_tx, out_rx) = crossbeam_channel::unbounded();
+        let shred = relay("shred", false, &dedup, &out_tx);
+        let geyser = relay("geyser", true, &dedup, &out_tx);
+        geyser.metrics.add_recv_offset_ns(400_000);
+
+        geyser.relay_batch(vec![decoded(1, 1_200_000)]);
+        shred.relay_batch(vec![decoded(1, 1_000_000)]);
+
+        // Stamped 200µs after the shred copy, but 400µs of that is decode
+        // overhead: the baseline really arrived first.
+        assert_eq!(shred.metrics.lead_time_sum_us.load(Relaxed), -200);
+        let out: Vec<ForwardedTx> = out_rx.try_iter().collect();
+        assert_eq!(
+            out[0].decoded.shred_recv_ns,
+            MonotonicNs(1_200_000),
+            "raw timestamp forwarded"
+        );
+    }
+
+    #[test]
+    fn test_duplicates_forwarded_marked_and_throttled() {
+        let dedup = Arc::new(DashMap::new());
+        let (out_tx, out_rx) = crossbeam_channel::unbounded();
+        let duplicates = Some(Arc::new(DuplicateThrottle::new(1)));
+        let a = Relay { duplicates: duplicates.clone(), ..relay("a", false, &dedup, &out_tx) };
+        let b = Relay { duplicates, ..relay("b", false, &dedup, &out_tx) };
+
+        a.relay_batch(vec![decoded(1, 1_000_000), decoded(2, 1_000_000)]);
+        b.relay_batch(vec![decoded(1, 1_250_000), decoded(2, 1_500_000)]);
+
+        let out: Vec<ForwardedTx> = out_rx.try_iter().collect();
+        assert_eq!(out.len(), 3, "two firsts, one duplicate within the cap");
+        let dup = out[2].duplicate_of.as_ref().unwrap();
+        assert_eq!((&*out[2].source, &*dup.winner, dup.lag_us), ("b", "a", 250));
+        assert_eq!(b.metrics.txs_duplicate.load(Relaxed), 2);
+    }
+
+    #[test]
+    fn test_preferred_source_forwarded_on_ties_only() {
+        let dedup = Arc::new(DashMap::new());
+        let (out_tx, out_rx) = crossbeam_channel::unbounded();
+        let (held_tx, held_rx) = crossbeam_channel::unbounded();
+        let prefer = Preference { source: "b".into(), tie_window_ns: 100_000, held_tx };
+        let prefer = Some(Arc::new(prefer));
+        let a = Relay { prefer: prefer.clone(), ..relay("a", false, &dedup, &out_tx) };
+        let b = Relay { prefer, ..relay("b", false, &dedup, &out_tx) };
+
+        // `a` wins 1 by 50µs (a tie) and 2 by 300µs; `b` wins 3 outright.
+        a.relay_batch(vec![decoded(1, 1_000_000), decoded(2, 1_000_000)]);
+        b.relay_batch(vec![decoded(1, 1_050_000), decoded(2, 1_300_000), decoded(3, 1_300_000)]);
+        assert_eq!(out_rx.try_iter().count(), 2, "a's copies held, b's forwarded");
+        for (_, sig) in held_rx.try_iter() {
+            release_held(&dedup, &out_tx, &sig);
+        }
+
+        let out: Vec<(u8, String)> = out_rx
+            .try_iter()
+            .map(|f| (f.decoded.transaction.signatures[0].as_ref()[0], f.source.to_string()))
+            .collect();
+        assert_eq!(out, [(2, "a".to_string())], "only the copy b did not tie with");
+        // The race is counted on the true arrival order.
+        assert_eq!(a.metrics.txs_first.load(Relaxed), 2);
+        assert_eq!(b.metrics.txs_first.load(Relaxed), 1);
+        assert_eq!(b.metrics.txs_duplicate.load(Relaxed), 2);
+    }
+
+    #[test]
+    fn test_sampling_admits_same_signatures_on_every_source() {
+        let dedup = Arc::new(DashMap::new());
+        let (out_tx, out_rx) = crossbeam_channel::unbounded();
+        let shred = Relay { sample_bits: 2, ..relay("shred", false, &dedup, &out_tx) };
+        let rpc = Relay { sample_bits: 2, ..relay("rpc", true, &dedup, &out_tx) };
+        // First bytes 0x00 and 0x3f have the top two bits clear; 0x40 and
+        // 0xff do not.
+        let batch = || [0x00, 0x3f, 0x40, 0xff].map(|s| decoded(s, 1_000_000));
+
+        shred.relay_batch(batch().into());
+        rpc.relay_batch(batch().into());
+
+        assert_eq!(out_rx.try_iter().count(), 2);
+        assert_eq!(shred.metrics.txs_first.load(Relaxed), 2);
+        assert_eq!(rpc.metrics.txs_duplicate.load(Relaxed), 2);
+        assert!(sampled(&[0xff; 64], 0));
+    }
+
     #[test]
     fn test_lead_time_shred_first() {
         let shred_recv_ns: u64 = 100_000;
@@ -581,7 +1626,7 @@ mod tests {
         assert!(lead_us > 0);
         assert_eq!(lead_us, 100);
 
-        let shred_metrics = SourceMetrics::new("shred", false);
+        let shred_metrics = SourceMetrics::new("shred".into(), false);
         shred_metrics.record_lead_time_us(lead_us);
         assert_eq!(shred_metrics.lead_time_count.load(Relaxed), 1);
         assert_eq!(shred_metrics.lead_time_sum_us.load(Relaxed), 100);
@@ -596,7 +1641,7 @@ mod tests {
         assert!(lead_us < 0);
         assert_eq!(lead_us, -100);
 
-        let shred_metrics = SourceMetrics::new("shred", false);
+        let shred_metrics = SourceMetrics::new("shred".into(), false);
         shred_metrics.record_lead_time_us(lead_us);
         assert_eq!(shred_metrics.lead_time_count.load(Relaxed), 1);
         assert_eq!(shred_metrics.lead_time_sum_us.load(Relaxed), -100);
@@ -0,0 +1,92 @@
+//! Forensic records of badly covered slots.
+//!
+//! A slot that ends dropped or partial leaves only a counter behind. With a
+//! [`ForensicsTap`] set, the decoder also sends a [`SlotForensics`] record for
+//! every such slot whose coverage is under the tap's threshold: which data
+//! shred indices arrived and what each FEC set held when the slot was retired.
+//! A coverage dip can then be examined after the fact.
+
+use crossbeam_channel::Sender;
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::source_metrics::SlotOutcome;
+
+/// Where a decoder sends forensic records, and which slots qualify.
+#[derive(Debug, Clone)]
+pub struct ForensicsTap {
+    pub tx: Sender<SlotForensics>,
+    /// Slots retired with at least this coverage are not recorded.
+    pub min_coverage_pct: f64,
+}
+
+/// State of one slot retired dropped or partial.
+#[derive(Debug, Clone, Serialize)]
+pub struct SlotForensics {
+    pub source: Arc<str>,
+    pub slot: u64,
+    /// When the slot was retired, as Unix-epoch ns.
+    pub retired_at_ns: u64,
+    pub outcome: SlotOutcome,
+    /// `shreds_seen` against `shreds_expected`; 0 if no index was anchored.
+    pub coverage_pct: f64,
+    pub shreds_seen: u32,
+    pub shreds_expected: u32,
+    pub fec_recovered: u32,
+    /// Index of the last-in-slot shred, if it was seen.
+    pub last_index: Option<u32>,
+    /// Data shred indices received or recovered, as inclusive `[first, last]`
+    /// runs.
+    pub received: Vec<[u32; 2]>,
+    /// FEC sets opened by a coding shred, by `fec_set_index`.
+    pub fec_sets: Vec<FecSetForensics>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FecSetForensics {
+    pub fec_set_index: u32,
+    pub num_data: usize,
+    pub num_coding: usize,
+    /// Shards held when the slot was retired; recovery needs `num_data` of
+    /// them in total.
+    pub data_shards: usize,
+    pub coding_shards: usize,
+    pub recovered: bool,
+}
+
+/// Inclusive runs of the set bits in a bitset of shred indices.
+pub(crate) fn runs(bits: &[u64]) -> Vec<[u32; 2]> {
+    let mut out: Vec<[u32; 2]> = Vec::new();
+    for (w, &word) in bits.iter().enumerate() {
+        for b in 0..64 {
+            if word & (1 << b) == 0 {
+                continue;
+            }
+            let idx = (w * 64 + b) as u32;
+            match out.last_mut() {
+                Some(run) if run[1] + 1 == idx => run[1] = idx,
+                _ => out.push([idx, idx]),
+            }
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs_across_words() {
+        let mut bits = vec![0u64; 2];
+        for idx in [0, 1, 2, 5, 63, 64, 65, 100] {
+            bits[idx / 64] |= 1 << (idx % 64);
+        }
+        assert_eq!(runs(&bits), vec![[0, 2], [5, 5], [63, 65], [100, 100]]);
+        assert!(runs(&[]).is_empty());
+    }
+}
@@ -5,8 +5,12 @@
 //! pipeline for lead-time comparison against raw shred feeds.
 //!
 //! The source reconnects automatically on disconnect (5s delay between attempts).
+//!
+//! Authentication is pluggable via [`GeyserAuth`]: the Yellowstone `x-token`
+//! header, a Bearer or Basic `authorization` header, or any custom header name.
 
 use anyhow::Result;
+use base64::Engine as _;
 use crossbeam_channel::Sender;
 use futures_util::StreamExt;
 use std::collections::HashMap;
@@ -23,10 +27,96 @@ use yellowstone_grpc_proto::geyser::{
     SubscribeRequestFilterTransactions,
 };
 
+use crate::calibration::{self, DecodeCalibration};
+use crate::clock::MonotonicNs;
 use crate::decoder::DecodedTx;
-use crate::fan_in::TxSource;
-use crate::metrics;
-use crate::source_metrics::SourceMetrics;
+use crate::fan_in::{send_batch, TxSource};
+use crate::grpc::GrpcSettings;
+use crate::source_metrics::{SourceErrorKind, SourceMetrics};
+use crate::stream_slots::StreamSlots;
+
+// ---------------------------------------------------------------------------
+// Authentication
+// ---------------------------------------------------------------------------
+
+/// How credentials are attached to every Geyser request.
+#[derive(Debug, Clone, Default)]
+pub enum GeyserAuth {
+    #[default]
+    None,
+    /// `x-token: <token>` — the Yellowstone default.
+    XToken(String),
+    /// `authorization: Bearer <token>`
+    Bearer(String),
+    /// `authorization: Basic base64(<username>:<password>)`
+    Basic { username: String, password: String },
+    /// Arbitrary `<name>: <value>` metadata header.
+    Header { name: String, value: String },
+}
+
+type AsciiKey = tonic::metadata::MetadataKey<tonic::metadata::Ascii>;
+type AsciiValue = tonic::metadata::MetadataValue<tonic::metadata::Ascii>;
+
+impl GeyserAuth {
+    /// Build from a config scheme name. `token` is `user:password` for "basic";
+    /// `header` is the header name for "header".
+    pub fn from_scheme(scheme: &str, token: &str, header: Option<&str>) -> Result<Self> {
+        Ok(match scheme {
+            "x-token" => Self::XToken(token.to_string()),
+            "bearer" => Self::Bearer(token.to_string()),
+            "basic" => {
+                let (username, password) = token.split_once(':').ok_or_else(|| {
+                    anyhow::anyhow!("basic auth token must be formatted as user:password")
+                })?;
+                Self::Basic { username: username.to_string(), password: password.to_string() }
+            }
+            "header" => {
+                let name = header
+                    .ok_or_else(|| anyhow::anyhow!("auth scheme \"header\" requires a header name"))?;
+                Self::Header { name: name.to_string(), value: token.to_string() }
+            }
+            other => anyhow::bail!(
+                "unknown auth scheme '{}' (expected x-token, bearer, basic, or header)",
+                other
+            ),
+        })
+    }
+
+    /// Check that the header name and value are valid gRPC ASCII metadata.
+    pub fn validate(&self) -> Result<()> {
+        self.metadata().map(|_| ())
+    }
+
+    /// The header this scheme sends, as a lowercase name and its value. Also
+    /// usable for plain HTTP requests.
+    pub fn header(&self) -> Option<(String, String)> {
+        Some(match self {
+            Self::None => return None,
+            Self::XToken(t) => ("x-token".to_string(), t.clone()),
+            Self::Bearer(t) => ("authorization".to_string(), format!("Bearer {}", t)),
+            Self::Basic { username, password } => (
+                "authorization".to_string(),
+                format!(
+                    "Basic {}",
+                    base64::engine::general_purpose::STANDARD
+                        .encode(format!("{}:{}", username, password))
+                ),
+            ),
+            Self::Header { name, value } => (name.to_ascii_lowercase(), value.clone()),
+        })
+    }
+
+    fn metadata(&self) -> Result<Option<(AsciiKey, AsciiValue)>> {
+        let Some((name, value)) = self.header() else { return Ok(None) };
+        let key = name
+            .parse::<AsciiKey>()
+            .map_err(|_| anyhow::anyhow!("invalid auth header name '{}'", name))?;
+        let val = value
+            .parse::<AsciiValue>()
+            .map_err(|_| anyhow::anyhow!("auth value for '{}' is not valid ASCII metadata", name))?;
+        Ok(Some((key, val)))
+    }
+}
 
 // ---------------------------------------------------------------------------
 // GeyserTxSource
@@ -39,16 +129,21 @@ use crate::source_metrics::SourceMetrics;
 /// earlier shreds arrive vs. the Geyser stream.
 pub struct GeyserTxSource {
     /// Display name for this source in the dashboard
-    pub name: &'static str,
+    pub name: Arc<str>,
     /// gRPC endpoint URL (e.g. "http://grpc.example.com:10000" or "https://...")
     pub url: String,
-    /// Optional authentication token sent as `x-token` metadata header
-    pub x_token: Option<String>,
+    /// Credentials attached to every request
+    pub auth: GeyserAuth,
+    /// Timeouts, keepalive, TLS and compression
+    pub grpc: GrpcSettings,
+    /// Measure the gRPC decode overhead on the first messages and subtract
+    /// it from receive timestamps (see [`crate::calibration`]).
+    pub calibrate: bool,
 }
 
 impl TxSource for GeyserTxSource {
-    fn name(&self) -> &'static str {
-        self.name
+    fn name(&self) -> Arc<str> {
+        self.name.clone()
     }
 
     /// Geyser delivers confirmed transactions — same semantics as RPC, so we
@@ -59,13 +154,15 @@ impl TxSource for GeyserTxSource {
 
     fn start(
         self: Box<Self>,
-        tx: Sender<DecodedTx>,
+        tx: Sender<Vec<DecodedTx>>,
         metrics: Arc<SourceMetrics>,
         _race: Option<Arc<crate::shred_race::ShredRaceTracker>>,
     ) -> Vec<JoinHandle<()>> {
-        let name = self.name;
+        let name = self.name.clone();
         let url = self.url.clone();
-        let x_token = self.x_token.clone();
+        let auth = self.auth.clone();
+        let grpc = self.grpc.clone();
+        let mut calibration = self.calibrate.then(DecodeCalibration::default);
 
         let handle = std::thread::Builder::new()
             .name(format!("{}-geyser", name))
@@ -76,15 +173,26 @@ impl TxSource for GeyserTxSource {
                     .expect("geyser: failed to build tokio runtime");
 
                 rt.block_on(async move {
+                    let mut slots = StreamSlots::new(metrics.clone());
                     loop {
-                        if let Err(e) =
-                            run_geyser(&url, &x_token, tx.clone(), metrics.clone()).await
-                        {
+                        let result = run_geyser(
+                            &url,
+                            &auth,
+                            &grpc,
+                            tx.clone(),
+                            metrics.clone(),
+                            &mut calibration,
+                            &mut slots,
+                        )
+                        .await;
+                        slots.flush();
+                        if let Err(e) = result {
                             tracing::warn!(
                                 "geyser source '{}' disconnected: {}  reconnecting in 5s",
                                 name,
                                 e
                             );
+                            metrics.record_error(SourceErrorKind::Disconnect, e.to_string());
                         }
                         tokio::time::sleep(std::time::Duration::from_secs(5)).await;
                     }
@@ -102,25 +210,26 @@ impl TxSource for GeyserTxSource {
 
 async fn run_geyser(
     url: &str,
-    x_token: &Option<String>,
-    tx: Sender<DecodedTx>,
+    auth: &GeyserAuth,
+    grpc: &GrpcSettings,
+    tx: Sender<Vec<DecodedTx>>,
     metrics: Arc<SourceMetrics>,
+    calibration: &mut Option<DecodeCalibration>,
+    slots: &mut StreamSlots,
 ) -> Result<()> {
-    let channel = tonic::transport::Channel::from_shared(url.to_owned())?
-        .connect()
-        .await?;
+    let header = auth.metadata()?;
+
+    let channel = grpc.connect(url).await?;
 
-    let token = x_token.clone();
     let mut client = GeyserClient::with_interceptor(channel, move |mut req: tonic::Request<()>| {
-        if let Some(ref t) = token {
-            if let Ok(val) =
-                t.parse::<tonic::metadata::MetadataValue<tonic::metadata::Ascii>>()
-            {
-                req.metadata_mut().insert("x-token", val);
-            }
+        if let Some((ref key, ref val)) = header {
+            req.metadata_mut().insert(key.clone(), val.clone());
         }
         Ok(req)
     });
+    if let Some(encoding) = grpc.accept_encoding() {
+        client = client.accept_compressed(encoding);
+    }
 
     // Subscribe to all non-vote, non-failed confirmed transactions.
     let request = SubscribeRequest {
@@ -144,16 +253,20 @@ async fn run_geyser(
 
     while let Some(msg) = stream.next().await {
         let msg = msg?;
+        let recv_ns = MonotonicNs::now();
+        if matches!(msg.update_oneof, Some(UpdateOneof::Transaction(_))) {
+            calibration::calibrate(calibration, &msg, &metrics);
+        }
         if let Some(UpdateOneof::Transaction(tx_update)) = msg.update_oneof {
             if let Some(tx_info) = tx_update.transaction {
-                let recv_ns = metrics::now_ns();
                 let slot = tx_update.slot;
 
                 metrics.txs_decoded.fetch_add(1, Relaxed);
+                slots.record(slot, recv_ns, 1, 0);
 
                 if let Some(decoded) = make_decoded_tx(&tx_info.signature, slot, recv_ns) {
                     metrics.txs_emitted.fetch_add(1, Relaxed);
-                    let _ = tx.try_send(decoded);
+                    send_batch(&tx, vec![decoded], &metrics);
                 }
             }
         }
@@ -170,7 +283,7 @@ async fn run_geyser(
 ///
 /// The fan-in pipeline only needs `signatures[0]` for deduplication and
 /// `shred_recv_ns` for timing — the rest of the transaction is not used.
-fn make_decoded_tx(sig_bytes: &[u8], slot: u64, recv_ns: u64) -> Option<DecodedTx> {
+fn make_decoded_tx(sig_bytes: &[u8], slot: u64, recv_ns: MonotonicNs) -> Option<DecodedTx> {
     let sig_arr: [u8; 64] = sig_bytes.try_into().ok()?;
     let transaction = VersionedTransaction {
         signatures: vec![Signature::from(sig_arr)],
@@ -183,3 +296,48 @@ fn make_decoded_tx(sig_bytes: &[u8], slot: u64, recv_ns: u64) -> Option<DecodedT
         decode_done_ns: recv_ns,
     })
 }
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header_of(auth: &GeyserAuth) -> (String, String) {
+        let (k, v) = auth.metadata().unwrap().unwrap();
+        (k.as_str().to_string(), v.to_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn test_auth_headers() {
+        assert!(GeyserAuth::None.metadata().unwrap().is_none());
+        assert_eq!(
+            header_of(&GeyserAuth::XToken("abc".into())),
+            ("x-token".into(), "abc".into())
+        );
+        assert_eq!(
+            header_of(&GeyserAuth::from_scheme("bearer", "abc", None).unwrap()),
+            ("authorization".into(), "Bearer abc".into())
+        );
+        assert_eq!(
+            header_of(&GeyserAuth::from_scheme("basic", "user:pass", None).unwrap()),
+            ("authorization".into(), "Basic dXNlcjpwYXNz".into())
+        );
+        assert_eq!(
+            header_of(&GeyserAuth::from_scheme("header", "k", Some("X-Api-Key")).unwrap()),
+            ("x-api-key".into(), "k".into())
+        );
+    }
+
+    #[test]
+    fn test_auth_scheme_errors() {
+        assert!(GeyserAuth::from_scheme("basic", "nocolon", None).is_err());
+        assert!(GeyserAuth::from_scheme("header", "v", None).is_err());
+        assert!(GeyserAuth::from_scheme("digest", "v", None).is_err());
+        assert!(GeyserAuth::Header { name: "bad header".into(), value: "v".into() }
+            .validate()
+            .is_err());
+    }
+}
@@ -0,0 +1,331 @@
+//! Connection settings shared by the tonic-based sources (geyser, jito-grpc
+//! and the jito-udp block engine session).
+//!
+//! A long-haul stream that dies without a FIN or RST is otherwise only
+//! noticed when TCP gives up on it, which can take more than ten minutes.
+//! HTTP/2 keepalive pings are on by default so a dead peer is detected in
+//! `keepalive_interval + keepalive_timeout`, and the source reconnects.
+//!
+//! `https://` URLs use TLS with the system roots, or with a custom CA. For
+//! test endpoints with self-signed certificates, verification can be turned
+//! off altogether: tonic has no hook for a custom certificate verifier, so the
+//! channel then runs over plain HTTP/2 to tonic and an [`InsecureConnector`]
+//! does the TLS handshake underneath it.
+//!
+//! With `compression` set, tonic decompresses each response before the source
+//! reads its clock, so the time spent there is part of the receive timestamp.
+//! tonic offers no hook around its codec to time it; `timestamp_offset_us`
+//! can take it out.
+
+use anyhow::{Context, Result};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
+use tonic::codec::CompressionEncoding;
+use tonic::codegen::http::uri::Scheme;
+use tonic::codegen::Service;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Uri};
+
+use hyper_util::rt::TokioIo;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::TlsConnector;
+
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+pub const DEFAULT_KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How a gRPC source connects.
+#[derive(Debug, Clone)]
+pub struct GrpcSettings {
+    pub connect_timeout: Duration,
+    /// Time allowed for a call's response to start. For a subscription that
+    /// is the response headers; the stream itself is not limited.
+    pub request_timeout: Option<Duration>,
+    /// HTTP/2 ping interval; `None` sends no pings.
+    pub keepalive_interval: Option<Duration>,
+    /// How long a ping may go unanswered before the connection is dropped.
+    pub keepalive_timeout: Duration,
+    pub tls: GrpcTls,
+    /// Ask the server to compress responses.
+    pub compression: Option<GrpcCompression>,
+}
+
+/// TLS for `https://` URLs.
+#[derive(Debug, Clone, Default)]
+pub struct GrpcTls {
+    /// PEM CA certificate(s) to trust instead of the system roots.
+    pub ca_cert_pem: Option<Vec<u8>>,
+    /// Name to verify the certificate against, when it differs from the
+    /// URL's host.
+    pub domain: Option<String>,
+    /// Accept any server certificate. For testing only.
+    pub insecure_skip_verify: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrpcCompression {
+    Gzip,
+    Zstd,
+}
+
+impl Default for GrpcSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            request_timeout: None,
+            keepalive_interval: Some(DEFAULT_KEEPALIVE_INTERVAL),
+            keepalive_timeout: DEFAULT_KEEPALIVE_TIMEOUT,
+            tls: GrpcTls::default(),
+            compression: None,
+        }
+    }
+}
+
+impl GrpcCompression {
+    /// From a config value: "gzip" or "zstd".
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "gzip" => Ok(Self::Gzip),
+            "zstd" => Ok(Self::Zstd),
+            other => anyhow::bail!("unknown compression '{}' (expected gzip or zstd)", other),
+        }
+    }
+
+    pub(crate) fn encoding(self) -> CompressionEncoding {
+        match self {
+            Self::Gzip => CompressionEncoding::Gzip,
+            Self::Zstd => CompressionEncoding::Zstd,
+        }
+    }
+}
+
+impl GrpcSettings {
+    /// Check the settings against `url` without connecting.
+    pub fn validate(&self, url: &str) -> Result<()> {
+        self.endpoint(url)?;
+        if self.tls.insecure_skip_verify {
+            InsecureConnector::new(url, self.tls.domain.as_deref())?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn connect(&self, url: &str) -> Result<Channel> {
+        let endpoint = self.endpoint(url)?;
+        if !self.tls.insecure_skip_verify {
+            return Ok(endpoint.connect().await?);
+        }
+        let connector = InsecureConnector::new(url, self.tls.domain.as_deref())?;
+        Ok(endpoint.connect_with_connector(connector).await?)
+    }
+
+    /// Response encoding to accept, if any.
+    pub(crate) fn accept_encoding(&self) -> Option<CompressionEncoding> {
+        self.compression.map(GrpcCompression::encoding)
+    }
+
+    fn endpoint(&self, url: &str) -> Result<Endpoint> {
+        let mut endpoint = Endpoint::from_shared(url.to_owned())
+            .with_context(|| format!("invalid gRPC url '{}'", url))?;
+
+        let tls = &self.tls;
+        let custom = tls.ca_cert_pem.is_some() || tls.domain.is_some() || tls.insecure_skip_verify;
+        if endpoint.uri().scheme_str() != Some("https") {
+            anyhow::ensure!(!custom, "TLS settings need an https:// url (got '{}')", url);
+        } else if tls.insecure_skip_verify {
+            anyhow::ensure!(
+                tls.ca_cert_pem.is_none(),
+                "a CA certificate and insecure_skip_verify cannot be combined"
+            );
+            // The connector does the TLS, so tonic must not try to; requests
+            // still carry the https origin.
+            let origin = endpoint.uri().clone();
+            let mut parts = origin.clone().into_parts();
+            parts.scheme = Some(Scheme::HTTP);
+            endpoint = Endpoint::from(Uri::from_parts(parts)?).origin(origin);
+        } else {
+            let mut config = ClientTlsConfig::new();
+            if let Some(ref domain) = tls.domain {
+                config = config.domain_name(domain.clone());
+            }
+            config = match tls.ca_cert_pem {
+                Some(ref pem) => config.ca_certificate(Certificate::from_pem(pem)),
+                None => config.with_enabled_roots(),
+            };
+            endpoint = endpoint.tls_config(config)?;
+        }
+
+        endpoint = endpoint
+            .connect_timeout(self.connect_timeout)
+            .keep_alive_timeout(self.keepalive_timeout)
+            .keep_alive_while_idle(true);
+        if let Some(interval) = self.keepalive_interval {
+            endpoint = endpoint.http2_keep_alive_interval(interval);
+        }
+        if let Some(timeout) = self.request_timeout {
+            endpoint = endpoint.timeout(timeout);
+        }
+        Ok(endpoint)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// insecure_skip_verify
+// ---------------------------------------------------------------------------
+
+/// Opens TCP to the URL's host and port and runs a TLS handshake that accepts
+/// any server certificate.
+#[derive(Clone)]
+struct InsecureConnector {
+    tls: TlsConnector,
+    host: String,
+    port: u16,
+    server_name: ServerName<'static>,
+}
+
+impl InsecureConnector {
+    fn new(url: &str, domain: Option<&str>) -> Result<Self> {
+        let uri: Uri = url.parse().with_context(|| format!("invalid gRPC url '{}'", url))?;
+        let host = uri.host().context("gRPC url has no host")?;
+        // IPv6 literals keep their brackets in the URI.
+        let host = host.trim_start_matches('[').trim_end_matches(']').to_owned();
+        let server_name = ServerName::try_from(domain.unwrap_or(&host).to_owned())
+            .context("invalid TLS server name")?;
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let mut config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerification::new()))
+            .with_no_client_auth();
+        config.alpn_protocols = vec![b"h2".to_vec()];
+
+        Ok(Self {
+            tls: TlsConnector::from(Arc::new(config)),
+            port: uri.port_u16().unwrap_or(443),
+            host,
+            server_name,
+        })
+    }
+}
+
+impl Service<Uri> for InsecureConnector {
+    type Response = TokioIo<TlsStream<TcpStream>>;
+    type Error = std::io::Error;
+    type Future = Pin<Box<dyn Future<Output = std::io::Result<Self::Response>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut std::task::Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// The channel's URI is the `http://` stand-in; the real address was
+    /// taken from the configured URL.
+    fn call(&mut self, _uri: Uri) -> Self::Future {
+        let this = self.clone();
+        Box::pin(async move {
+            let tcp = TcpStream::connect((this.host.as_str(), this.port)).await?;
+            tcp.set_nodelay(true)?;
+            let tls = this.tls.connect(this.server_name, tcp).await?;
+            Ok(TokioIo::new(tls))
+        })
+    }
+}
+
+/// Accepts any certificate, but still checks that the handshake was signed
+/// by the key in it.
+#[derive(Debug)]
+struct NoVerification {
+    algorithms: rustls::crypto::WebPkiSupportedAlgorithms,
+}
+
+impl NoVerification {
+    fn new() -> Self {
+        Self {
+            algorithms: rustls::crypto::ring::default_provider().signature_verification_algorithms,
+        }
+    }
+}
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tls_settings_need_https() {
+        let mut settings = GrpcSettings::default();
+        settings.validate("http://127.0.0.1:10000").unwrap();
+        settings.validate("https://grpc.example.com").unwrap();
+
+        settings.tls.insecure_skip_verify = true;
+        assert!(settings.validate("http://127.0.0.1:10000").is_err());
+        settings.validate("https://grpc.example.com").unwrap();
+
+        settings.tls.ca_cert_pem = Some(Vec::new());
+        assert!(settings.validate("https://grpc.example.com").is_err());
+    }
+
+    #[test]
+    fn test_insecure_tls_left_to_connector() {
+        let mut settings = GrpcSettings::default();
+        settings.tls.insecure_skip_verify = true;
+        let endpoint = settings.endpoint("https://[::1]:10000").unwrap();
+        assert_eq!(endpoint.uri().scheme_str(), Some("http"));
+
+        let connector = InsecureConnector::new("https://[::1]:10000", None).unwrap();
+        assert_eq!((connector.host.as_str(), connector.port), ("::1", 10000));
+        let connector = InsecureConnector::new("https://10.0.0.1", Some("grpc.test")).unwrap();
+        assert_eq!(connector.port, 443);
+        assert_eq!(connector.server_name, ServerName::try_from("grpc.test").unwrap());
+    }
+
+    #[test]
+    fn test_compression_names() {
+        assert!(GrpcCompression::from_name("gzip").is_ok());
+        assert!(GrpcCompression::from_name("zstd").is_ok());
+        assert!(GrpcCompression::from_name("br").is_err());
+    }
+}
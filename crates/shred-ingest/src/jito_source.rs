@@ -21,10 +21,13 @@ use std::thread::JoinHandle;
 #[allow(deprecated)]
 use solana_entry::entry::Entry;
 
+use crate::calibration::{self, DecodeCalibration};
+use crate::clock::MonotonicNs;
 use crate::decoder::DecodedTx;
-use crate::fan_in::TxSource;
-use crate::metrics;
-use crate::source_metrics::SourceMetrics;
+use crate::fan_in::{send_batch, TxSource};
+use crate::grpc::GrpcSettings;
+use crate::source_metrics::{SourceErrorKind, SourceMetrics};
+use crate::stream_slots::StreamSlots;
 
 // ---------------------------------------------------------------------------
 // Minimal protobuf message types for the ShredStream proxy protocol
@@ -61,14 +64,19 @@ struct JitoEntry {
 /// confirmation, giving similar lead times to raw UDP shred feeds.
 pub struct JitoShredstreamSource {
     /// Display name for this source in the dashboard
-    pub name: &'static str,
+    pub name: Arc<str>,
     /// gRPC endpoint of the local ShredStream proxy (e.g. "http://127.0.0.1:9999")
     pub url: String,
+    /// Timeouts, keepalive, TLS and compression
+    pub grpc: GrpcSettings,
+    /// Measure the gRPC decode overhead on the first messages and subtract
+    /// it from receive timestamps (see [`crate::calibration`]).
+    pub calibrate: bool,
 }
 
 impl TxSource for JitoShredstreamSource {
-    fn name(&self) -> &'static str {
-        self.name
+    fn name(&self) -> Arc<str> {
+        self.name.clone()
     }
 
     /// Jito ShredStream entries arrive before block confirmation, so this
@@ -79,12 +87,14 @@ impl TxSource for JitoShredstreamSource {
 
     fn start(
         self: Box<Self>,
-        tx: Sender<DecodedTx>,
+        tx: Sender<Vec<DecodedTx>>,
         metrics: Arc<SourceMetrics>,
         _race: Option<Arc<crate::shred_race::ShredRaceTracker>>,
     ) -> Vec<JoinHandle<()>> {
-        let name = self.name;
+        let name = self.name.clone();
         let url = self.url.clone();
+        let grpc = self.grpc.clone();
+        let mut calibration = self.calibrate.then(DecodeCalibration::default);
 
         let handle = std::thread::Builder::new()
             .name(format!("{}-jito-grpc", name))
@@ -95,15 +105,25 @@ impl TxSource for JitoShredstreamSource {
                     .expect("jito-grpc: failed to build tokio runtime");
 
                 rt.block_on(async move {
+                    let mut slots = StreamSlots::new(metrics.clone());
                     loop {
-                        if let Err(e) =
-                            run_jito_shredstream(&url, tx.clone(), metrics.clone()).await
-                        {
+                        let result = run_jito_shredstream(
+                            &url,
+                            &grpc,
+                            tx.clone(),
+                            metrics.clone(),
+                            &mut calibration,
+                            &mut slots,
+                        )
+                        .await;
+                        slots.flush();
+                        if let Err(e) = result {
                             tracing::warn!(
                                 "jito-shredstream source '{}' disconnected: {}  reconnecting in 5s",
                                 name,
                                 e
                             );
+                            metrics.record_error(SourceErrorKind::Disconnect, e.to_string());
                         }
                         tokio::time::sleep(std::time::Duration::from_secs(5)).await;
                     }
@@ -121,15 +141,19 @@ impl TxSource for JitoShredstreamSource {
 
 async fn run_jito_shredstream(
     url: &str,
-    tx: Sender<DecodedTx>,
+    settings: &GrpcSettings,
+    tx: Sender<Vec<DecodedTx>>,
     metrics: Arc<SourceMetrics>,
+    calibration: &mut Option<DecodeCalibration>,
+    slots: &mut StreamSlots,
 ) -> Result<()> {
-    let channel = tonic::transport::Channel::from_shared(url.to_owned())?
-        .connect()
-        .await?;
+    let channel = settings.connect(url).await?;
 
     let mut grpc: tonic::client::Grpc<tonic::transport::Channel> =
         tonic::client::Grpc::new(channel);
+    if let Some(encoding) = settings.accept_encoding() {
+        grpc = grpc.accept_compressed(encoding);
+    }
 
     let path = tonic::codegen::http::uri::PathAndQuery::from_static(
         "/shredstream.ShredstreamProxy/SubscribeEntries",
@@ -149,7 +173,8 @@ async fn run_jito_shredstream(
 
     while let Some(msg) = stream.next().await {
         let msg = msg?;
-        let recv_ns = metrics::now_ns();
+        let recv_ns = MonotonicNs::now();
+        calibration::calibrate(calibration, &msg, &metrics);
         let slot = msg.slot;
 
         // The proxy sends bincode-serialized Vec<solana_entry::entry::Entry>
@@ -158,21 +183,26 @@ async fn run_jito_shredstream(
             Ok(e) => e,
             Err(_) => continue,
         };
+        let entry_count = entries.len() as u32;
 
         #[allow(deprecated)]
-        for entry in entries {
-            for transaction in entry.transactions {
-                metrics.txs_decoded.fetch_add(1, Relaxed);
-                let decoded = DecodedTx {
-                    transaction,
-                    slot,
-                    shred_recv_ns: recv_ns,
-                    decode_done_ns: recv_ns,
-                };
-                metrics.txs_emitted.fetch_add(1, Relaxed);
-                let _ = tx.try_send(decoded);
-            }
+        let batch: Vec<DecodedTx> = entries
+            .into_iter()
+            .flat_map(|entry| entry.transactions)
+            .map(|transaction| DecodedTx {
+                transaction,
+                slot,
+                shred_recv_ns: recv_ns,
+                decode_done_ns: recv_ns,
+            })
+            .collect();
+        slots.record(slot, recv_ns, batch.len() as u32, entry_count);
+        if batch.is_empty() {
+            continue;
         }
+        metrics.txs_decoded.fetch_add(batch.len() as u64, Relaxed);
+        metrics.txs_emitted.fetch_add(batch.len() as u64, Relaxed);
+        send_batch(&tx, batch, &metrics);
     }
 
     Ok(())
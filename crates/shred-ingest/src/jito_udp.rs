@@ -0,0 +1,423 @@
+//! Native Jito ShredStream receiver (`type = "jito-udp"`).
+//!
+//! Does the receive half of the ShredStream proxy in-process: authenticates to
+//! the block engine with the subscriber keypair (challenge-response), then
+//! sends periodic heartbeats naming the public `ip:port` Jito should deliver
+//! shreds to. The shreds arrive as plain UDP and go through the same receiver
+//! and decoder as a `unicast` source, so the measurement no longer includes
+//! the proxy's localhost forwarding hop.
+//!
+//! A heartbeat is sent every half of the TTL the block engine grants. On any
+//! error the session is rebuilt from scratch after 5s; shreds keep arriving
+//! until the last heartbeat's TTL runs out. When the receive side stops, the
+//! supervisor (see [`crate::supervise`]) stops the heartbeats too and starts
+//! a fresh copy, which registers again.
+
+use anyhow::Result;
+use crossbeam_channel::Sender;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use crate::decoder::DecodedTx;
+use crate::fan_in::{TxSource, UnicastTxSource};
+use crate::budget::MemoryBudget;
+use crate::forensics::ForensicsTap;
+use crate::grpc::GrpcSettings;
+use crate::repair::RepairClient;
+use crate::metrics;
+use crate::receiver::{SocketTuning, STOP_POLL};
+use crate::shred_race::ShredRaceTracker;
+use crate::shred_verify::ShredVerifier;
+use crate::source_metrics::{SourceErrorKind, SourceMetrics};
+use crate::supervise::StopSignal;
+
+// ---------------------------------------------------------------------------
+// Minimal protobuf message types for the block engine protocol
+//
+// Wire format matches auth.proto, shredstream.proto and shared.proto from
+// jito-labs/mev-protos:
+//   service AuthService {
+//     rpc GenerateAuthChallenge(GenerateAuthChallengeRequest)
+//         returns (GenerateAuthChallengeResponse);
+//     rpc GenerateAuthTokens(GenerateAuthTokensRequest) returns (GenerateAuthTokensResponse);
+//     rpc RefreshAccessToken(RefreshAccessTokenRequest) returns (RefreshAccessTokenResponse);
+//   }
+//   service Shredstream { rpc SendHeartbeat(Heartbeat) returns (HeartbeatResponse); }
+// ---------------------------------------------------------------------------
+
+/// `auth.Role.SHREDSTREAM_SUBSCRIBER`
+const ROLE_SHREDSTREAM_SUBSCRIBER: i32 = 3;
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct GenerateAuthChallengeRequest {
+    #[prost(int32, tag = "1")]
+    role: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    pubkey: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct GenerateAuthChallengeResponse {
+    #[prost(string, tag = "1")]
+    challenge: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct GenerateAuthTokensRequest {
+    #[prost(string, tag = "1")]
+    challenge: String,
+    #[prost(bytes = "vec", tag = "2")]
+    client_pubkey: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    signed_challenge: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Timestamp {
+    #[prost(int64, tag = "1")]
+    seconds: i64,
+    #[prost(int32, tag = "2")]
+    nanos: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Token {
+    #[prost(string, tag = "1")]
+    value: String,
+    #[prost(message, optional, tag = "2")]
+    expires_at_utc: Option<Timestamp>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct GenerateAuthTokensResponse {
+    #[prost(message, optional, tag = "1")]
+    access_token: Option<Token>,
+    #[prost(message, optional, tag = "2")]
+    refresh_token: Option<Token>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct RefreshAccessTokenRequest {
+    #[prost(string, tag = "1")]
+    refresh_token: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct RefreshAccessTokenResponse {
+    #[prost(message, optional, tag = "1")]
+    access_token: Option<Token>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Socket {
+    #[prost(string, tag = "1")]
+    ip: String,
+    #[prost(int64, tag = "2")]
+    port: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Heartbeat {
+    #[prost(message, optional, tag = "1")]
+    socket: Option<Socket>,
+    #[prost(string, repeated, tag = "2")]
+    regions: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct HeartbeatResponse {
+    #[prost(uint32, tag = "1")]
+    ttl_ms: u32,
+}
+
+// ---------------------------------------------------------------------------
+// JitoUdpSource
+// ---------------------------------------------------------------------------
+
+/// Jito ShredStream without the proxy: block engine registration plus a
+/// [`UnicastTxSource`] on the registered port.
+pub struct JitoUdpSource {
+    /// Block engine gRPC endpoint, e.g. `https://frankfurt.mainnet.block-engine.jito.wtf`.
+    pub block_engine_url: String,
+    /// Timeouts, keepalive and TLS for the block engine connection.
+    pub grpc: GrpcSettings,
+    /// Keypair approved by Jito for ShredStream (see [`read_keypair`]).
+    pub keypair: Keypair,
+    /// Address Jito should send shreds to, as reachable from the internet.
+    pub public_ip: IpAddr,
+    /// Block engine regions to receive shreds from, e.g. `["frankfurt"]`.
+    pub regions: Vec<String>,
+    /// Receive side; its `port` is the one announced in heartbeats.
+    pub udp: UnicastTxSource,
+}
+
+/// Load a Solana JSON keypair file.
+pub fn read_keypair(path: &Path) -> Result<Keypair> {
+    solana_keypair::read_keypair_file(path)
+        .map_err(|e| anyhow::anyhow!("reading keypair {}: {}", path.display(), e))
+}
+
+impl TxSource for JitoUdpSource {
+    fn name(&self) -> Arc<str> {
+        self.udp.name.clone()
+    }
+
+    fn respawn(&self) -> Option<Box<dyn TxSource>> {
+        Some(Box::new(JitoUdpSource {
+            block_engine_url: self.block_engine_url.clone(),
+            grpc: self.grpc.clone(),
+            keypair: self.keypair.insecure_clone(),
+            public_ip: self.public_ip,
+            regions: self.regions.clone(),
+            udp: self.udp.clone(),
+        }))
+    }
+
+    fn is_rpc(&self) -> bool {
+        false
+    }
+
+    fn set_forensics(&mut self, forensics: ForensicsTap) {
+        self.udp.set_forensics(forensics);
+    }
+
+    fn set_repair(&mut self, repair: RepairClient) {
+        self.udp.set_repair(repair);
+    }
+
+    fn set_budget(&mut self, budget: MemoryBudget) {
+        self.udp.set_budget(budget);
+    }
+
+    fn set_verifier(&mut self, verifier: ShredVerifier) {
+        self.udp.set_verifier(verifier);
+    }
+
+    fn set_tuning(&mut self, tuning: SocketTuning) {
+        self.udp.set_tuning(tuning);
+    }
+
+    fn set_stop(&mut self, stop: StopSignal) {
+        self.udp.set_stop(stop);
+    }
+
+    fn start(
+        self: Box<Self>,
+        tx: Sender<Vec<DecodedTx>>,
+        metrics: Arc<SourceMetrics>,
+        race: Option<Arc<ShredRaceTracker>>,
+    ) -> Vec<JoinHandle<()>> {
+        let name = self.udp.name.clone();
+        let session = Session {
+            url: self.block_engine_url,
+            grpc: self.grpc,
+            keypair: self.keypair,
+            heartbeat: Heartbeat {
+                socket: Some(Socket {
+                    ip: self.public_ip.to_string(),
+                    port: self.udp.port as i64,
+                }),
+                regions: self.regions,
+            },
+        };
+        let hb_metrics = metrics.clone();
+        let stop = self.udp.stop.clone();
+
+        let mut handles = Box::new(self.udp).start(tx, metrics, race);
+        let hb_handle = std::thread::Builder::new()
+            .name(format!("{}-heartbeat", name))
+            .spawn(move || {
+                let rt = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("jito-udp: failed to build tokio runtime");
+
+                rt.block_on(async move {
+                    loop {
+                        if let Err(e) = session.run(&name, &hb_metrics, stop.as_ref()).await {
+                            tracing::warn!(
+                                "jito-udp source '{}' registration lost: {:#}  retrying in 5s",
+                                name,
+                                e
+                            );
+                            let error = format!("{:#}", e);
+                            hb_metrics.record_error(SourceErrorKind::Registration, error);
+                        }
+                        if pause(Duration::from_secs(5), stop.as_ref()).await {
+                            break;
+                        }
+                    }
+                });
+            })
+            .expect("jito-udp: failed to spawn heartbeat thread");
+        handles.push(hb_handle);
+        handles
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Block engine session
+// ---------------------------------------------------------------------------
+
+type Grpc = tonic::client::Grpc<tonic::transport::Channel>;
+
+/// Tokens are renewed this long before they expire.
+const TOKEN_MARGIN_SECS: i64 = 60;
+
+struct Session {
+    url: String,
+    grpc: GrpcSettings,
+    keypair: Keypair,
+    heartbeat: Heartbeat,
+}
+
+struct Tokens {
+    access: String,
+    access_expires: i64,
+    refresh: String,
+    refresh_expires: i64,
+}
+
+impl Session {
+    /// Authenticate, then heartbeat until something fails or `stop` is set.
+    async fn run(
+        &self,
+        name: &str,
+        metrics: &SourceMetrics,
+        stop: Option<&StopSignal>,
+    ) -> Result<()> {
+        let channel = self.grpc.connect(&self.url).await?;
+        let mut grpc = tonic::client::Grpc::new(channel);
+        if let Some(encoding) = self.grpc.accept_encoding() {
+            grpc = grpc.accept_compressed(encoding);
+        }
+        let mut tokens = self.authenticate(&mut grpc).await?;
+        let mut registered = false;
+
+        loop {
+            let now = unix_secs();
+            if tokens.access_expires - TOKEN_MARGIN_SECS <= now {
+                if tokens.refresh_expires - TOKEN_MARGIN_SECS <= now {
+                    tokens = self.authenticate(&mut grpc).await?;
+                } else {
+                    refresh(&mut grpc, &mut tokens).await?;
+                }
+            }
+
+            let mut req = tonic::Request::new(self.heartbeat.clone());
+            req.metadata_mut()
+                .insert("authorization", format!("Bearer {}", tokens.access).parse()?);
+            let resp: HeartbeatResponse =
+                unary(&mut grpc, "/shredstream.Shredstream/SendHeartbeat", req).await?;
+            metrics.last_heartbeat_ns.store(metrics::now_ns(), Relaxed);
+            if !registered {
+                registered = true;
+                tracing::info!(
+                    "jito-udp source '{}' registered {}:{} (ttl {}ms)",
+                    name,
+                    self.heartbeat.socket.as_ref().map_or("", |s| s.ip.as_str()),
+                    self.heartbeat.socket.as_ref().map_or(0, |s| s.port),
+                    resp.ttl_ms
+                );
+            }
+            let interval = Duration::from_millis((resp.ttl_ms / 2).max(500) as u64);
+            if pause(interval, stop).await {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Challenge-response login: sign `<pubkey>-<challenge>` with the keypair.
+    async fn authenticate(&self, grpc: &mut Grpc) -> Result<Tokens> {
+        let pubkey = self.keypair.pubkey();
+        let resp: GenerateAuthChallengeResponse = unary(
+            grpc,
+            "/auth.AuthService/GenerateAuthChallenge",
+            tonic::Request::new(GenerateAuthChallengeRequest {
+                role: ROLE_SHREDSTREAM_SUBSCRIBER,
+                pubkey: pubkey.as_ref().to_vec(),
+            }),
+        )
+        .await?;
+
+        let challenge = format!("{}-{}", pubkey, resp.challenge);
+        let signed = self.keypair.sign_message(challenge.as_bytes());
+        let resp: GenerateAuthTokensResponse = unary(
+            grpc,
+            "/auth.AuthService/GenerateAuthTokens",
+            tonic::Request::new(GenerateAuthTokensRequest {
+                challenge,
+                client_pubkey: pubkey.as_ref().to_vec(),
+                signed_challenge: signed.as_ref().to_vec(),
+            }),
+        )
+        .await?;
+
+        let (access, access_expires) = token(resp.access_token)?;
+        let (refresh, refresh_expires) = token(resp.refresh_token)?;
+        Ok(Tokens { access, access_expires, refresh, refresh_expires })
+    }
+}
+
+async fn refresh(grpc: &mut Grpc, tokens: &mut Tokens) -> Result<()> {
+    let resp: RefreshAccessTokenResponse = unary(
+        grpc,
+        "/auth.AuthService/RefreshAccessToken",
+        tonic::Request::new(RefreshAccessTokenRequest { refresh_token: tokens.refresh.clone() }),
+    )
+    .await?;
+    (tokens.access, tokens.access_expires) = token(resp.access_token)?;
+    Ok(())
+}
+
+async fn unary<Req, Resp>(
+    grpc: &mut Grpc,
+    path: &'static str,
+    req: tonic::Request<Req>,
+) -> Result<Resp>
+where
+    Req: prost::Message + Send + Sync + 'static,
+    Resp: prost::Message + Default + Send + Sync + 'static,
+{
+    grpc.ready()
+        .await
+        .map_err(|e| anyhow::anyhow!("block engine not ready: {}", e))?;
+    let path = tonic::codegen::http::uri::PathAndQuery::from_static(path);
+    let codec = tonic_prost::ProstCodec::<Req, Resp>::default();
+    Ok(grpc.unary(req, path, codec).await?.into_inner())
+}
+
+/// Token value and expiry (Unix seconds). A token without an expiry is
+/// treated as valid for an hour.
+fn token(t: Option<Token>) -> Result<(String, i64)> {
+    let t = t.ok_or_else(|| anyhow::anyhow!("block engine returned no token"))?;
+    let expires = t.expires_at_utc.map_or(unix_secs() + 3600, |ts| ts.seconds);
+    Ok((t.value, expires))
+}
+
+/// Sleep for `d`, waking every [`STOP_POLL`] to check `stop`. Returns whether
+/// it was set.
+async fn pause(d: Duration, stop: Option<&StopSignal>) -> bool {
+    let until = tokio::time::Instant::now() + d;
+    loop {
+        if stop.is_some_and(StopSignal::is_stopped) {
+            return true;
+        }
+        let left = until.saturating_duration_since(tokio::time::Instant::now());
+        if left.is_zero() {
+            return false;
+        }
+        tokio::time::sleep(left.min(STOP_POLL)).await;
+    }
+}
+
+fn unix_secs() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64
+}
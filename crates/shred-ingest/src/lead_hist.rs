@@ -0,0 +1,287 @@
+//! Cumulative log-bucketed histogram of lead times.
+//!
+//! The lead-time reservoirs only hold the most recent 4096 samples, so their
+//! percentiles describe a short window and are lost on restart. A
+//! [`LeadHistogram`] counts every sample for the life of the counters — it is
+//! written to each metrics-log line and to the state file — so percentiles can
+//! be computed over a whole run, and over any span offline by subtracting the
+//! histograms of two log lines.
+//!
+//! Buckets are exact below 16 µs and split each power of two above that into
+//! eight, so a reported percentile is within ~6% of the true value. Serialized
+//! as sparse `[bound_us, count]` pairs, where `bound_us` is the bucket edge
+//! nearest zero, signed; the bucket extends away from zero by 1 µs below 16 µs
+//! and by 1/8 of the largest power of two ≤ |bound_us| above.
+//!
+//! Hot paths record into an [`AtomicLeadHistogram`], which has a counter for
+//! every bucket and takes no lock, and read it out as a [`LeadHistogram`].
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+const LINEAR: u64 = 16;
+const SUB_BUCKETS: u64 = 8;
+/// Magnitude keys of every `i64`: the linear ones, then octaves 4 to 63.
+const MAGNITUDE_KEYS: usize = (LINEAR + 60 * SUB_BUCKETS) as usize;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "Vec<(i64, u64)>", into = "Vec<(i64, u64)>")]
+pub struct LeadHistogram {
+    /// Signed bucket key → count. Negative keys hold negative samples.
+    buckets: BTreeMap<i32, u64>,
+}
+
+impl LeadHistogram {
+    pub fn record(&mut self, us: i64) {
+        *self.buckets.entry(key(us)).or_default() += 1;
+    }
+
+    pub fn merge(&mut self, other: &LeadHistogram) {
+        for (&k, &n) in &other.buckets {
+            *self.buckets.entry(k).or_default() += n;
+        }
+    }
+
+    /// The samples recorded since `earlier`, an older copy of this
+    /// histogram. `None` when `earlier` counts samples this one lacks, i.e.
+    /// the counters were reset in between.
+    pub fn since(&self, earlier: &LeadHistogram) -> Option<LeadHistogram> {
+        let mut buckets = self.buckets.clone();
+        for (k, &n) in &earlier.buckets {
+            let count = buckets.get_mut(k)?;
+            *count = count.checked_sub(n)?;
+        }
+        buckets.retain(|_, n| *n > 0);
+        Some(Self { buckets })
+    }
+
+    pub fn count(&self) -> u64 {
+        self.buckets.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// Value at percentile `p` (0–100), as the midpoint of its bucket.
+    pub fn percentile(&self, p: f64) -> Option<i64> {
+        let total = self.count();
+        if total == 0 {
+            return None;
+        }
+        // Same rank as the reservoirs: the sample at index ⌊n·p/100⌋.
+        let rank = ((total as f64 * p / 100.0) as u64).min(total - 1);
+        let mut seen = 0;
+        for (&k, &n) in &self.buckets {
+            seen += n;
+            if seen > rank {
+                return Some(midpoint(k));
+            }
+        }
+        None
+    }
+
+    /// Share of samples of at least `us`, 0–1. Samples in the bucket holding
+    /// `us` are assumed spread evenly over it.
+    pub fn fraction_at_least(&self, us: i64) -> Option<f64> {
+        let total = self.count();
+        if total == 0 {
+            return None;
+        }
+        let k = key(us);
+        let above: u64 = self.buckets.range(k + 1..).map(|(_, &n)| n).sum();
+        let Some(&within) = self.buckets.get(&k) else {
+            return Some(above as f64 / total as f64);
+        };
+        // Distance from `us` to the bucket's upper end, as a share of its width.
+        let (lo, hi) = magnitude_range(if k >= 0 { k as u64 } else { (-k - 1) as u64 });
+        let m = us.unsigned_abs();
+        let share = if us >= 0 { hi - m } else { m - lo + 1 } as f64 / (hi - lo) as f64;
+        Some((above as f64 + within as f64 * share) / total as f64)
+    }
+}
+
+/// A [`LeadHistogram`] with a fixed counter per bucket (about 8 KB), so
+/// threads record into it without a lock.
+pub(crate) struct AtomicLeadHistogram {
+    /// Indexed by key + [`MAGNITUDE_KEYS`]; negative keys come first.
+    counts: Box<[AtomicU64]>,
+}
+
+impl Default for AtomicLeadHistogram {
+    fn default() -> Self {
+        Self { counts: (0..2 * MAGNITUDE_KEYS).map(|_| AtomicU64::new(0)).collect() }
+    }
+}
+
+impl AtomicLeadHistogram {
+    pub(crate) fn record(&self, us: i64) {
+        self.counts[index(key(us))].fetch_add(1, Relaxed);
+    }
+
+    pub(crate) fn merge(&self, other: &LeadHistogram) {
+        for (&k, &n) in &other.buckets {
+            self.counts[index(k)].fetch_add(n, Relaxed);
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> LeadHistogram {
+        let buckets = self
+            .counts
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (i as i32 - MAGNITUDE_KEYS as i32, n.load(Relaxed)))
+            .filter(|&(_, n)| n > 0)
+            .collect();
+        LeadHistogram { buckets }
+    }
+}
+
+fn index(k: i32) -> usize {
+    (k + MAGNITUDE_KEYS as i32) as usize
+}
+
+/// Bucket of a magnitude: exact below [`LINEAR`], then [`SUB_BUCKETS`] per
+/// power of two.
+fn magnitude_key(m: u64) -> i32 {
+    if m < LINEAR {
+        return m as i32;
+    }
+    let octave = 63 - m.leading_zeros() as u64; // ≥ 4
+    let sub = (m >> (octave - 3)) & (SUB_BUCKETS - 1);
+    (LINEAR + (octave - 4) * SUB_BUCKETS + sub) as i32
+}
+
+/// Magnitude range `[lo, hi)` of a magnitude key.
+fn magnitude_range(k: u64) -> (u64, u64) {
+    if k < LINEAR {
+        return (k, k + 1);
+    }
+    let octave = (k - LINEAR) / SUB_BUCKETS + 4;
+    let sub = (k - LINEAR) % SUB_BUCKETS;
+    let width = 1u64 << (octave - 3);
+    let lo = (SUB_BUCKETS + sub) * width;
+    (lo, lo + width)
+}
+
+/// Negative samples use `-(key + 1)`, keeping them apart from the bucket of 0
+/// and ordering all keys by value.
+fn key(us: i64) -> i32 {
+    if us >= 0 {
+        magnitude_key(us as u64)
+    } else {
+        -magnitude_key(us.unsigned_abs()) - 1
+    }
+}
+
+fn bound(k: i32) -> i64 {
+    if k >= 0 {
+        magnitude_range(k as u64).0 as i64
+    } else {
+        -(magnitude_range((-k - 1) as u64).0 as i64)
+    }
+}
+
+fn midpoint(k: i32) -> i64 {
+    let (lo, hi) = magnitude_range(if k >= 0 { k as u64 } else { (-k - 1) as u64 });
+    let mid = ((lo + hi - 1) / 2) as i64;
+    if k >= 0 { mid } else { -mid }
+}
+
+impl From<Vec<(i64, u64)>> for LeadHistogram {
+    fn from(pairs: Vec<(i64, u64)>) -> Self {
+        let mut h = LeadHistogram::default();
+        for (b, n) in pairs {
+            *h.buckets.entry(key(b)).or_default() += n;
+        }
+        h
+    }
+}
+
+impl From<LeadHistogram> for Vec<(i64, u64)> {
+    fn from(h: LeadHistogram) -> Self {
+        h.buckets.into_iter().map(|(k, n)| (bound(k), n)).collect()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_bounds_round_trip() {
+        for v in [-2_000_000, -1153, -16, -15, -1, 0, 1, 15, 16, 17, 1000, 1_999_999] {
+            let k = key(v);
+            let (lo, hi) = magnitude_range(if k >= 0 { k as u64 } else { (-k - 1) as u64 });
+            assert!((lo..hi).contains(&v.unsigned_abs()), "{} not in [{}, {})", v, lo, hi);
+            assert_eq!(key(bound(k)), k, "bound of {} maps back", v);
+        }
+    }
+
+    #[test]
+    fn test_percentiles_within_bucket_error() {
+        let mut h = LeadHistogram::default();
+        for v in 1..=10_000i64 {
+            h.record(v);
+        }
+        for (p, want) in [(50.0, 5_000.0), (95.0, 9_500.0), (99.0, 9_900.0)] {
+            let got = h.percentile(p).unwrap() as f64;
+            assert!((got - want).abs() / want < 0.07, "p{}: {} vs {}", p, got, want);
+        }
+        assert_eq!(h.count(), 10_000);
+    }
+
+    #[test]
+    fn test_fraction_at_least() {
+        let mut h = LeadHistogram::default();
+        for v in -5_000..5_000i64 {
+            h.record(v * 20);
+        }
+        let cases = [(-100_000, 1.0), (-50_000, 0.75), (0, 0.5), (80_000, 0.1), (100_000, 0.0)];
+        for (us, want) in cases {
+            let got = h.fraction_at_least(us).unwrap();
+            assert!((got - want).abs() < 0.01, "≥{}: {} vs {}", us, got, want);
+        }
+        assert_eq!(LeadHistogram::default().fraction_at_least(0), None);
+    }
+
+    #[test]
+    fn test_pairs_round_trip_and_merge() {
+        let mut a = LeadHistogram::default();
+        for v in [-300, -5, 0, 7, 40, 40, 123_456] {
+            a.record(v);
+        }
+        let pairs: Vec<(i64, u64)> = a.clone().into();
+        assert_eq!(pairs[0], (-288, 1), "bucket of -300 is [-319, -288]");
+        let back = LeadHistogram::from(pairs);
+        assert_eq!(back, a);
+
+        let mut merged = back.clone();
+        merged.merge(&a);
+        assert_eq!(merged.count(), 2 * a.count());
+        assert!(merged.percentile(0.0).unwrap() < 0);
+
+        assert_eq!(merged.since(&a), Some(a.clone()));
+        assert_eq!(a.since(&merged), None, "counts went down");
+        assert_eq!(a.since(&a).map(|h| h.is_empty()), Some(true));
+    }
+
+    #[test]
+    fn test_atomic_histogram_matches() {
+        let mut h = LeadHistogram::default();
+        let atomic = AtomicLeadHistogram::default();
+        for v in [i64::MIN, -300, -5, 0, 7, 40, 40, 123_456, i64::MAX] {
+            h.record(v);
+            atomic.record(v);
+        }
+        assert_eq!(atomic.snapshot(), h);
+        atomic.merge(&h);
+        h.merge(&h.clone());
+        assert_eq!(atomic.snapshot(), h);
+    }
+}
@@ -1,22 +1,91 @@
+#[cfg(feature = "async")]
+pub mod async_source;
+pub mod budget;
+#[cfg(feature = "grpc")]
+pub mod calibration;
+pub mod chaos;
+pub mod clock;
+pub mod cluster_tip;
 pub mod coverage;
 pub mod decoder;
+pub mod failover;
 pub mod fan_in;
+pub mod forensics;
+#[cfg(feature = "geyser")]
 pub mod geyser_source;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "jito")]
 pub mod jito_source;
+#[cfg(feature = "jito")]
+pub mod jito_udp;
+pub mod lead_hist;
 pub mod metrics;
+#[cfg(feature = "capture")]
+pub mod offline;
+pub mod output;
+#[cfg(feature = "grpc")]
+pub mod output_grpc;
+pub mod output_nats;
 pub mod receiver;
+pub mod repair;
+#[cfg(feature = "rpc")]
 pub mod rpc_source;
+pub mod senders;
+pub mod shred_mix;
 pub mod shred_race;
+pub mod shred_verify;
 pub mod source;
 pub mod source_metrics;
+pub mod spsc;
+#[cfg(feature = "grpc")]
+pub mod stream_slots;
+pub mod supervise;
+pub mod trace;
+pub mod tx_profile;
 
-pub use coverage::SlotCoverageEvent;
+#[cfg(feature = "async")]
+pub use async_source::{AsyncTxSource, TxStream};
+pub use budget::{BudgetSnapshot, MemoryBudget};
+pub use chaos::ChaosConfig;
+pub use clock::{ClockCheckSnapshot, MonotonicNs, RealtimeNs};
+pub use cluster_tip::ClusterTip;
+pub use coverage::{PositionCoverageSnapshot, SlotCoverageEvent, SlotExpiry, POSITION_BANDS};
 pub use decoder::{DecodedTx, ShredDecoder};
-pub use fan_in::{FanInSource, RpcTxSource, ShredTxSource, TurbineTxSource, UnicastTxSource, TxSource};
-pub use geyser_source::GeyserTxSource;
+pub use failover::{spawn_failover_monitor, FailoverEvent, FailoverGroup};
+pub use forensics::{ForensicsTap, SlotForensics};
+#[cfg(feature = "rpc")]
+pub use fan_in::RpcTxSource;
+pub use fan_in::{
+    DuplicateOf, FanInSource, ForwardedTx, ShredTxSource, TurbineTxSource, UnicastTxSource,
+    TxSource,
+};
+#[cfg(feature = "geyser")]
+pub use geyser_source::{GeyserAuth, GeyserTxSource};
+#[cfg(feature = "grpc")]
+pub use grpc::{GrpcCompression, GrpcSettings, GrpcTls};
+#[cfg(feature = "jito")]
 pub use jito_source::JitoShredstreamSource;
-pub use receiver::{CaptureEvent, ShredReceiver};
+#[cfg(feature = "jito")]
+pub use jito_udp::JitoUdpSource;
+pub use lead_hist::LeadHistogram;
+pub use output::{OutputHub, OutputSink, SinkRegistry, Snapshot};
+pub use receiver::{CaptureEvent, ShredReceiver, SocketTuning};
+pub use repair::{RepairClient, RepairPeer, RepairSettings};
+#[cfg(feature = "rpc")]
 pub use rpc_source::RpcSource;
-pub use shred_race::{ShredPairSnapshot, ShredRaceTracker};
+pub use senders::SenderMixSnapshot;
+pub use shred_mix::ShredMixSnapshot;
+pub use shred_race::{
+    PairRule, PairWindow, RaceEvent, ShredPairSnapshot, ShredPairState, ShredRaceTracker,
+    ShredRankSnapshot, ShredRankState,
+};
+pub use shred_verify::{LeaderSchedule, ShredVerifier};
 pub use source::{start_source, SourceConfig};
-pub use source_metrics::{SlotOutcome, SlotStats, SourceMetrics, SourceMetricsSnapshot};
+pub use source_metrics::{
+    Confirmation, SlotConfirmations, SlotOutcome, SlotStats, SourceCounters, SourceError,
+    SourceErrorKind, SourceMetrics, SourceMetricsSnapshot, SourceMetricsState,
+};
+pub use supervise::{RestartPolicy, StopSignal};
+pub use trace::{Hop, TraceTap, TxTrace};
+pub use tx_profile::{EarlyTxSnapshot, TxProfile};
@@ -5,8 +5,12 @@
 //! On other platforms, an `Instant`-based fallback is used.
 //!
 //! Kernel `SO_TIMESTAMPNS` timestamps arrive in `CLOCK_REALTIME`. The receiver
-//! converts them to `CLOCK_MONOTONIC_RAW` using a one-time offset sampled at startup
-//! so all timestamps throughout the pipeline share the same reference frame.
+//! converts them to `CLOCK_MONOTONIC_RAW` so all timestamps throughout the
+//! pipeline share the same reference frame. NTP and PTP slew the wall clock but
+//! not the raw monotonic one, so the offset between them drifts: the receiver
+//! re-samples it every 100 ms, and values leaving the process are converted
+//! back with the offset as of the conversion. The typed forms of both clocks,
+//! and the conversions, are in [`crate::clock`].
 
 use std::sync::atomic::{AtomicU64, Ordering};
 
@@ -33,6 +37,26 @@ pub fn now_ns() -> u64 {
     }
 }
 
+/// CPU time consumed by the calling thread, in nanoseconds. Always 0 on
+/// platforms without `CLOCK_THREAD_CPUTIME_ID`.
+pub fn thread_cpu_ns() -> u64 {
+    #[cfg(unix)]
+    {
+        let mut ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        unsafe {
+            libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts);
+        }
+        (ts.tv_sec as u64) * 1_000_000_000 + (ts.tv_nsec as u64)
+    }
+    #[cfg(not(unix))]
+    {
+        0
+    }
+}
+
 /// Accumulates total duration and call count for each pipeline stage.
 ///
 /// All fields use `Relaxed` ordering — these are sampling metrics, not synchronisation.
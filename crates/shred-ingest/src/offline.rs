@@ -0,0 +1,562 @@
+//! Decode-only consumer API for captured shreds.
+//!
+//! Runs the production [`ShredDecoder`] over a pcap (or any stream of raw shred
+//! payloads) without sources, fan-in or metrics plumbing, and yields one item
+//! per finalized slot:
+//!
+//! ```no_run
+//! for (stats, txs) in shred_ingest::offline::decode_pcap("capture.pcap")? {
+//!     println!("slot {} {:?}: {} txs", stats.slot, stats.outcome, txs.len());
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! Slots are yielded in the order the decoder finalizes them: complete slots as
+//! soon as their last shred decodes, partial/dropped slots once the stream has
+//! moved 32 slots past them (or at end of input). `completion_us` measures
+//! decode time in this process, not capture time; `completed_at_ns` is the
+//! timestamp of the completing shred exactly as supplied (capture time for
+//! pcaps).
+//!
+//! [`bench_pcap`] measures decoder throughput instead: it loads a pcap into
+//! memory and replays it through the decoder as fast as it will go.
+//!
+//! [`replay_pcap`] runs the whole pipeline — one decoder per feed, the fan-in
+//! and the shred races — deterministically: a single thread, packets in
+//! capture-timestamp order, and a virtual clock taken from those timestamps.
+//! The same pcap always produces the same report, which makes it usable as a
+//! golden output when metric code changes.
+
+use anyhow::{Context, Result};
+use crossbeam_channel::Receiver;
+use pcap_file::pcap::{PcapPacket, PcapReader};
+use solana_transaction::versioned::VersionedTransaction;
+use std::collections::HashMap;
+use std::fs::File;
+use serde::Serialize;
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use crate::clock::{RealtimeNs, ReplayClock};
+use crate::decoder::{DecodeProfile, DecodeState, DecodedTx, ShredDecoder};
+use crate::fan_in::ReplayFanIn;
+use crate::lead_hist::LeadHistogram;
+use crate::metrics::{now_ns, thread_cpu_ns};
+use crate::receiver::{is_shred_shaped, RawShred};
+use crate::shred_race::{ShredArrival, ShredPairSnapshot, ShredRaceTracker};
+use crate::source_metrics::{SlotStats, SourceMetrics};
+
+/// Ethernet (14) + minimal IPv4 (20) + UDP (8).
+const MIN_FRAME_HDR: usize = 42;
+
+/// Iterator over decoded slots. See [`decode_pcap`] / [`decode_shreds`].
+pub struct DecodedSlots {
+    slot_rx: Receiver<SlotStats>,
+    tx_rx: Receiver<Vec<DecodedTx>>,
+    pending: HashMap<u64, Vec<VersionedTransaction>>,
+    _threads: Vec<JoinHandle<()>>,
+}
+
+impl Iterator for DecodedSlots {
+    type Item = (SlotStats, Vec<VersionedTransaction>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let stats = self.slot_rx.recv().ok()?;
+        // The decoder sends a slot's transactions before its stats, so every
+        // transaction of `stats.slot` is already queued.
+        for d in self.tx_rx.try_iter().flatten() {
+            self.pending.entry(d.slot).or_default().push(d.transaction);
+        }
+        let txs = self.pending.remove(&stats.slot).unwrap_or_default();
+        Some((stats, txs))
+    }
+}
+
+/// Decode every UDP payload in an Ethernet/IPv4 pcap as a shred. Packets that
+/// are not shreds are ignored by the decoder, exactly as in production.
+/// Capture timestamps are passed through as the shreds' receive times.
+pub fn decode_pcap(path: impl AsRef<Path>) -> Result<DecodedSlots> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut reader = PcapReader::new(file)
+        .with_context(|| format!("failed to read pcap header of {}", path.display()))?;
+
+    // The first packet's capture time is the replay clock's origin.
+    let mut first = None;
+    while let Some(pkt) = reader.next_packet() {
+        if let Ok(pkt) = pkt {
+            first = Some(pkt.into_owned());
+            break;
+        }
+    }
+    let clock = ReplayClock::starting_at(first.as_ref().map_or(RealtimeNs(0), capture_time));
+
+    let (raw_tx, raw_rx) = crossbeam_channel::bounded::<RawShred>(4096);
+    let reader_thread = std::thread::Builder::new()
+        .name("offline-pcap".into())
+        .spawn(move || {
+            if let Some(shred) = first.as_ref().and_then(|pkt| raw_shred(pkt, clock)) {
+                if raw_tx.send(shred).is_err() {
+                    return;
+                }
+            }
+            while let Some(pkt) = reader.next_packet() {
+                let Ok(pkt) = pkt else { continue };
+                let Some(shred) = raw_shred(&pkt, clock) else { continue };
+                if raw_tx.send(shred).is_err() {
+                    break;
+                }
+            }
+        })?;
+
+    let mut slots = spawn_decoder(raw_rx, clock)?;
+    slots._threads.push(reader_thread);
+    Ok(slots)
+}
+
+/// Decode an arbitrary sequence of raw shreds (UDP payloads).
+pub fn decode_shreds<I>(shreds: I) -> Result<DecodedSlots>
+where
+    I: IntoIterator<Item = RawShred> + Send + 'static,
+    I::IntoIter: Send,
+{
+    let (raw_tx, raw_rx) = crossbeam_channel::bounded::<RawShred>(4096);
+    let feeder = std::thread::Builder::new()
+        .name("offline-feed".into())
+        .spawn(move || {
+            for shred in shreds {
+                if raw_tx.send(shred).is_err() {
+                    break;
+                }
+            }
+        })?;
+
+    let mut slots = spawn_decoder(raw_rx, ReplayClock::identity())?;
+    slots._threads.push(feeder);
+    Ok(slots)
+}
+
+/// A pcap record's capture time.
+fn capture_time(pkt: &PcapPacket<'_>) -> RealtimeNs {
+    RealtimeNs(pkt.timestamp.as_nanos() as u64)
+}
+
+/// A pcap record's UDP payload, received at its capture time on `clock`.
+fn raw_shred(pkt: &PcapPacket<'_>, clock: ReplayClock) -> Option<RawShred> {
+    Some(RawShred {
+        data: udp_payload(&pkt.data)?.to_vec(),
+        recv_timestamp_ns: clock.to_monotonic(capture_time(pkt)),
+    })
+}
+
+fn spawn_decoder(raw_rx: Receiver<RawShred>, clock: ReplayClock) -> Result<DecodedSlots> {
+    // Unbounded: the decoder uses try_send, and offline nothing may be dropped.
+    let (tx, tx_rx) = crossbeam_channel::unbounded();
+    let (slot_tx, slot_rx) = crossbeam_channel::unbounded();
+    let metrics = SourceMetrics::new("offline".into(), false);
+    let decoder = ShredDecoder::new(raw_rx, tx, metrics)
+        .with_slot_stats(slot_tx)
+        .with_replay_clock(clock);
+    let handle = std::thread::Builder::new()
+        .name("offline-decode".into())
+        .spawn(move || {
+            let _ = decoder.run();
+        })?;
+
+    Ok(DecodedSlots {
+        slot_rx,
+        tx_rx,
+        pending: HashMap::new(),
+        _threads: vec![handle],
+    })
+}
+
+/// Result of [`bench_pcap`]. Times are nanoseconds.
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodeBench {
+    /// UDP payloads replayed (shreds and anything else on the wire).
+    pub packets: u64,
+    pub entries: u64,
+    pub txs: u64,
+    pub slots_complete: u64,
+    pub fec_recovered_shreds: u64,
+    /// Wall time of the decode pass alone.
+    pub decode_wall_ns: u64,
+    /// CPU time reading and parsing the pcap into memory (not part of the
+    /// decode pass).
+    pub load_cpu_ns: u64,
+    /// Decoder thread CPU time, all stages.
+    pub decode_cpu_ns: u64,
+    /// Reed-Solomon reconstruction of FEC sets.
+    pub fec_cpu_ns: u64,
+    /// Entry deserialization and transaction extraction.
+    pub entries_cpu_ns: u64,
+    /// Handing decoded transactions to the output channel.
+    pub emit_cpu_ns: u64,
+}
+
+impl DecodeBench {
+    /// Decoder CPU time outside the named stages: header parsing, slot and
+    /// FEC-set bookkeeping, payload copies.
+    pub fn shred_cpu_ns(&self) -> u64 {
+        self.decode_cpu_ns
+            .saturating_sub(self.fec_cpu_ns + self.entries_cpu_ns + self.emit_cpu_ns)
+    }
+}
+
+/// Replay every UDP payload of a pcap through [`ShredDecoder`] with no
+/// sockets, sleeps or pacing. The pcap is loaded into memory first so the
+/// decode pass measures the decoder alone.
+pub fn bench_pcap(path: impl AsRef<Path>) -> Result<DecodeBench> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut reader = PcapReader::new(file)
+        .with_context(|| format!("failed to read pcap header of {}", path.display()))?;
+
+    let load_start = thread_cpu_ns();
+    let (raw_tx, raw_rx) = crossbeam_channel::unbounded::<RawShred>();
+    let mut packets = 0u64;
+    let mut clock = None;
+    while let Some(pkt) = reader.next_packet() {
+        let Ok(pkt) = pkt else { continue };
+        let clock = *clock.get_or_insert_with(|| ReplayClock::starting_at(capture_time(&pkt)));
+        let Some(shred) = raw_shred(&pkt, clock) else { continue };
+        packets += 1;
+        let _ = raw_tx.send(shred);
+    }
+    drop(raw_tx);
+    let load_cpu_ns = thread_cpu_ns() - load_start;
+
+    // Drained as in production, so a slow consumer cannot skew the result.
+    let (tx, tx_rx) = crossbeam_channel::bounded::<Vec<DecodedTx>>(1024);
+    let drain = std::thread::Builder::new()
+        .name("bench-drain".into())
+        .spawn(move || for _ in tx_rx {})?;
+
+    let metrics = SourceMetrics::new("bench".into(), false);
+    let profile = Arc::new(DecodeProfile::default());
+    let decoder = ShredDecoder::new(raw_rx, tx, metrics.clone())
+        .with_replay_clock(clock.unwrap_or_else(ReplayClock::identity))
+        .with_profile(profile.clone());
+    let wall_start = now_ns();
+    let decode_cpu_ns = std::thread::Builder::new()
+        .name("bench-decode".into())
+        .spawn(move || {
+            let cpu_start = thread_cpu_ns();
+            let _ = decoder.run();
+            thread_cpu_ns() - cpu_start
+        })?
+        .join()
+        .map_err(|_| anyhow::anyhow!("decoder thread panicked"))?;
+    let decode_wall_ns = now_ns() - wall_start;
+    let _ = drain.join();
+
+    Ok(DecodeBench {
+        packets,
+        entries: profile.entries.load(Relaxed),
+        txs: metrics.txs_decoded.load(Relaxed),
+        slots_complete: metrics.slots_complete.load(Relaxed),
+        fec_recovered_shreds: metrics.fec_recovered_shreds.load(Relaxed),
+        decode_wall_ns,
+        load_cpu_ns,
+        decode_cpu_ns,
+        fec_cpu_ns: profile.fec_ns.load(Relaxed),
+        entries_cpu_ns: profile.entries_ns.load(Relaxed),
+        emit_cpu_ns: profile.emit_ns.load(Relaxed),
+    })
+}
+
+/// Per-feed outcome of a [`replay_pcap`] run.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayFeed {
+    pub name: String,
+    /// UDP payloads sent to the feed's address.
+    pub packets: u64,
+    pub non_shred_packets: u64,
+    pub slots_complete: u64,
+    pub slots_partial: u64,
+    pub slots_dropped: u64,
+    pub fec_recovered_shreds: u64,
+    pub txs_decoded: u64,
+    /// Transactions this feed delivered first / after another feed.
+    pub txs_first: u64,
+    pub txs_duplicate: u64,
+    pub lead_time_mean_us: Option<f64>,
+    pub lead_time_hist: LeadHistogram,
+}
+
+/// Result of [`replay_pcap`]. Contains no wall-clock values, so two runs over
+/// the same input serialize identically.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayReport {
+    /// Packets that matched a feed address.
+    pub packets: u64,
+    pub feeds: Vec<ReplayFeed>,
+    pub shred_race: Vec<ShredPairSnapshot>,
+    pub slot_race: Vec<ShredPairSnapshot>,
+    pub start_race: Vec<ShredPairSnapshot>,
+    pub handoff_race: Vec<ShredPairSnapshot>,
+    pub avail_race: Vec<ShredPairSnapshot>,
+}
+
+/// Replay a pcap deterministically through per-feed decoders, the fan-in and
+/// the shred races. `feeds` maps destination addresses to feed names; other
+/// packets are ignored. Packets are processed one at a time in capture order
+/// (stable-sorted by timestamp, so a merged capture replays the same way),
+/// and every clock read in the pipeline is the current packet's timestamp.
+pub fn replay_pcap(path: impl AsRef<Path>, feeds: &[(Ipv4Addr, String)]) -> Result<ReplayReport> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut reader = PcapReader::new(file)
+        .with_context(|| format!("failed to read pcap header of {}", path.display()))?;
+
+    let mut packets: Vec<(u64, usize, Vec<u8>)> = Vec::new();
+    while let Some(pkt) = reader.next_packet() {
+        let Ok(pkt) = pkt else { continue };
+        let Some((dst, payload)) = udp_packet(&pkt.data) else { continue };
+        if let Some(feed) = feeds.iter().position(|(ip, _)| *ip == dst) {
+            packets.push((pkt.timestamp.as_nanos() as u64, feed, payload.to_vec()));
+        }
+    }
+    let names: Vec<&str> = feeds.iter().map(|(_, name)| name.as_str()).collect();
+    Ok(replay(packets, &names))
+}
+
+/// [`replay_pcap`] over `(timestamp_ns, feed index, UDP payload)` packets.
+fn replay(mut packets: Vec<(u64, usize, Vec<u8>)>, feeds: &[&str]) -> ReplayReport {
+    packets.sort_by_key(|&(ts, _, _)| ts);
+    let clock = ReplayClock::starting_at(RealtimeNs(packets.first().map_or(0, |p| p.0)));
+
+    let race = ShredRaceTracker::deterministic();
+    let race_tx = race.sender();
+    let metrics: Vec<Arc<SourceMetrics>> = feeds
+        .iter()
+        .map(|name| SourceMetrics::new((*name).into(), false))
+        .collect();
+    let (out_tx, out_rx) = crossbeam_channel::unbounded();
+    let mut fan_in = ReplayFanIn::new(&metrics, out_tx);
+
+    // Decoders are driven shred by shred; their input channels stay unused.
+    let (_raw_tx, raw_rx) = crossbeam_channel::bounded::<RawShred>(0);
+    let mut decoders: Vec<_> = metrics
+        .iter()
+        .map(|m| {
+            let (tx, rx) = crossbeam_channel::unbounded::<Vec<DecodedTx>>();
+            let decoder = ShredDecoder::new(raw_rx.clone(), tx, m.clone())
+                .with_race(Some(race.clone()))
+                .with_virtual_clock(clock);
+            (decoder, DecodeState::default(), rx)
+        })
+        .collect();
+
+    let mut counts = vec![(0u64, 0u64); feeds.len()];
+    for (ts, feed, data) in packets {
+        let ts = clock.to_monotonic(RealtimeNs(ts));
+        let m = &metrics[feed];
+        counts[feed].0 += 1;
+        if !is_shred_shaped(&data) {
+            counts[feed].1 += 1;
+            continue;
+        }
+        m.shreds_received.fetch_add(1, Relaxed);
+        m.bytes_received.fetch_add(data.len() as u64, Relaxed);
+        let _ = race_tx.send(ShredArrival {
+            source: m.name.clone(),
+            slot: u64::from_le_bytes(data[65..73].try_into().unwrap()),
+            idx: u32::from_le_bytes(data[73..77].try_into().unwrap()),
+            recv_ns: ts,
+            recovered: false,
+        });
+
+        let (decoder, state, tx_rx) = &mut decoders[feed];
+        decoder.process(state, RawShred { data, recv_timestamp_ns: ts });
+        race.process_pending();
+        for batch in tx_rx.try_iter() {
+            fan_in.relay(feed, batch, ts);
+        }
+        out_rx.try_iter().for_each(drop);
+    }
+    for (decoder, state, _) in decoders {
+        decoder.finish(state);
+    }
+
+    let feeds = metrics
+        .iter()
+        .zip(counts)
+        .map(|(m, (packets, non_shred_packets))| {
+            let snap = m.snapshot();
+            ReplayFeed {
+                name: m.name.to_string(),
+                packets,
+                non_shred_packets,
+                slots_complete: snap.slots_complete,
+                slots_partial: snap.slots_partial,
+                slots_dropped: snap.slots_dropped,
+                fec_recovered_shreds: snap.fec_recovered_shreds,
+                txs_decoded: snap.txs_decoded,
+                txs_first: m.txs_first.load(Relaxed),
+                txs_duplicate: m.txs_duplicate.load(Relaxed),
+                lead_time_mean_us: m.mean_lead_time_us(),
+                lead_time_hist: snap.lead_time_hist,
+            }
+        })
+        .collect::<Vec<_>>();
+    ReplayReport {
+        packets: feeds.iter().map(|f| f.packets).sum(),
+        feeds,
+        shred_race: race.snapshots(),
+        slot_race: race.slot_snapshots(),
+        start_race: race.start_snapshots(),
+        handoff_race: race.handoff_snapshots(),
+        avail_race: race.avail_snapshots(),
+    }
+}
+
+/// UDP payload of an Ethernet II / IPv4 frame, or `None` for anything else.
+fn udp_payload(frame: &[u8]) -> Option<&[u8]> {
+    udp_packet(frame).map(|(_, payload)| payload)
+}
+
+/// Destination address and UDP payload of an Ethernet II / IPv4 frame.
+fn udp_packet(frame: &[u8]) -> Option<(Ipv4Addr, &[u8])> {
+    if frame.len() < MIN_FRAME_HDR || frame[12] != 0x08 || frame[13] != 0x00 {
+        return None;
+    }
+    let ihl = (frame[14] & 0x0f) as usize * 4;
+    if ihl < 20 || frame[14 + 9] != 0x11 {
+        return None;
+    }
+    let dst: [u8; 4] = frame[14 + 16..14 + 20].try_into().ok()?;
+    Some((Ipv4Addr::from(dst), frame.get(14 + ihl + 8..)?))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MonotonicNs;
+    use crate::source_metrics::SlotOutcome;
+    use solana_entry::entry::Entry;
+
+    /// Legacy data shred carrying `data`.
+    fn data_shred(slot: u64, index: u32, data: &[u8], last: bool) -> RawShred {
+        let mut buf = vec![0u8; 1228];
+        buf[64] = 0xa5;
+        buf[65..73].copy_from_slice(&slot.to_le_bytes());
+        buf[73..77].copy_from_slice(&index.to_le_bytes());
+        buf[85] = if last { 0x01 } else { 0 };
+        buf[86..88].copy_from_slice(&((88 + data.len()) as u16).to_le_bytes());
+        buf[88..88 + data.len()].copy_from_slice(data);
+        RawShred { data: buf, recv_timestamp_ns: MonotonicNs(0) }
+    }
+
+    #[test]
+    fn test_decode_shreds_yields_complete_slot_with_txs() {
+        let entry = Entry {
+            transactions: vec![VersionedTransaction::default(); 2],
+            ..Default::default()
+        };
+        let bytes = bincode::serialize(&entry).unwrap();
+        let (a, b) = bytes.split_at(bytes.len() / 2);
+
+        let slots: Vec<_> = decode_shreds(vec![
+            data_shred(100, 0, a, false),
+            data_shred(100, 1, b, true),
+            data_shred(101, 0, &bytes[..10], false),
+        ])
+        .unwrap()
+        .collect();
+
+        assert_eq!(slots.len(), 2);
+        let (stats, txs) = &slots[0];
+        assert_eq!(stats.slot, 100);
+        assert!(matches!(stats.outcome, SlotOutcome::Complete));
+        assert_eq!(stats.txs_decoded, 2);
+        assert_eq!(txs.len(), 2);
+
+        // Slot 101 never completes and is finalized at end of input.
+        assert_eq!(slots[1].0.slot, 101);
+        assert!(matches!(slots[1].0.outcome, SlotOutcome::Dropped));
+        assert!(slots[1].1.is_empty());
+    }
+
+    #[test]
+    fn test_profile_counts_entries() {
+        let entry = Entry {
+            transactions: vec![VersionedTransaction::default(); 3],
+            ..Default::default()
+        };
+        let mut bytes = bincode::serialize(&entry).unwrap();
+        bytes.extend(bincode::serialize(&Entry::default()).unwrap());
+
+        let (raw_tx, raw_rx) = crossbeam_channel::unbounded();
+        raw_tx.send(data_shred(100, 0, &bytes, true)).unwrap();
+        drop(raw_tx);
+        let (tx, _tx_rx) = crossbeam_channel::unbounded();
+        let profile = Arc::new(DecodeProfile::default());
+        let metrics = SourceMetrics::new("profile".into(), false);
+        ShredDecoder::new(raw_rx, tx, metrics.clone())
+            .with_profile(profile.clone())
+            .run()
+            .unwrap();
+
+        assert_eq!(profile.entries.load(Relaxed), 2);
+        assert_eq!(metrics.txs_decoded.load(Relaxed), 3);
+        assert!(profile.entries_ns.load(Relaxed) > 0);
+    }
+
+    #[test]
+    fn test_replay_is_deterministic() {
+        let entry = Entry {
+            transactions: vec![VersionedTransaction {
+                signatures: vec![Default::default()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let bytes = bincode::serialize(&entry).unwrap();
+        let (a, b) = bytes.split_at(bytes.len() / 2);
+        let shreds = [data_shred(100, 0, a, false).data, data_shred(100, 1, b, true).data];
+
+        // Feed 0 is 300µs ahead on shred 0, feed 1 is 100µs ahead on shred 1
+        // and so completes the slot first. Packets are offered out of order;
+        // the replay sorts them by timestamp.
+        let packets = [
+            (1_000_000, 0, shreds[0].clone()),
+            (1_300_000, 1, shreds[0].clone()),
+            (1_500_000, 1, shreds[1].clone()),
+            (1_600_000, 0, shreds[1].clone()),
+            (1_600_000, 1, vec![0u8; 40]),
+        ];
+        let run = || replay(packets.iter().rev().cloned().collect(), &["a", "b"]);
+        let report = run();
+
+        assert_eq!(format!("{:?}", report), format!("{:?}", run()));
+        assert_eq!(report.packets, 5);
+        assert_eq!(report.feeds[1].non_shred_packets, 1);
+        let wire = &report.shred_race[0];
+        assert_eq!((wire.a_wins, wire.b_wins), (1, 1));
+        assert_eq!(report.slot_race[0].b_wins, 1);
+        assert_eq!((report.feeds[0].txs_first, report.feeds[0].txs_duplicate), (0, 1));
+        assert_eq!(report.feeds[1].txs_first, 1);
+    }
+
+    #[test]
+    fn test_udp_payload_offsets() {
+        let mut frame = vec![0u8; 60];
+        frame[12] = 0x08;
+        frame[14] = 0x45;
+        frame[23] = 0x11;
+        frame[42] = 0xaa;
+        assert_eq!(udp_payload(&frame).unwrap()[0], 0xaa);
+
+        frame[23] = 0x06; // TCP
+        assert!(udp_payload(&frame).is_none());
+    }
+}
@@ -0,0 +1,864 @@
+//! Pluggable outputs for transactions, metric snapshots and race events.
+//!
+//! An [`OutputSink`] is told about every transaction the fan-in forwards,
+//! every metrics snapshot and every wire race, and delivers them wherever it
+//! likes. Sinks are built from their settings by a [`SinkRegistry`], keyed
+//! by `type`, so a new delivery target only needs a factory registered there.
+//! Built in:
+//!
+//! - `jsonl`: appends to a file.
+//! - `unix`: streams to every client of a Unix socket.
+//! - `nats`: publishes to a NATS server (see [`crate::output_nats`]).
+//! - `grpc`: streams to a collector over gRPC (see [`crate::output_grpc`]).
+//! - `race_csv`: every race sample, as hourly gzipped CSV files.
+//!
+//! Except for `race_csv`, the built-in sinks write each event as one JSON
+//! object whose `event` field is `tx`, `snapshot` or `race` (see
+//! [`event_json`]).
+//!
+//! [`OutputHub`] runs each sink on its own thread behind a bounded queue. A
+//! sink that falls behind loses events, counted per sink, and never slows the
+//! pipeline or the other sinks. [`OutputHub::close`] drains the queues and
+//! drops the sinks, so files are complete at shutdown.
+
+use anyhow::{Context, Result};
+use base64::Engine as _;
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufWriter, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::fan_in::ForwardedTx;
+use crate::shred_race::RaceEvent;
+use crate::source_metrics::SourceMetricsSnapshot;
+
+/// Events queued per sink, or per Unix socket client, before new ones are
+/// dropped.
+const QUEUE: usize = 16_384;
+
+/// A delivery target. Every method has an empty default, so a sink only
+/// implements the events it handles. Methods run on the sink's own thread.
+pub trait OutputSink: Send {
+    fn on_transaction(&mut self, _tx: &ForwardedTx) {}
+    fn on_snapshot(&mut self, _snapshot: &Snapshot) {}
+    fn on_race_event(&mut self, _event: &RaceEvent) {}
+    /// Called whenever the sink's queue runs dry, for sinks that buffer.
+    fn flush(&mut self) {}
+    /// Events the sink handles, and so receives unless its settings narrow
+    /// them further.
+    fn events(&self) -> EventMask {
+        EventMask::ALL
+    }
+}
+
+/// One metrics snapshot of `shredtop run`.
+pub struct Snapshot {
+    /// Unix time, seconds.
+    pub ts: u64,
+    pub sources: Vec<SourceMetricsSnapshot>,
+    /// The snapshot as written to the metrics log, a JSON object.
+    pub json: String,
+}
+
+/// An event as queued for the sinks.
+#[derive(Clone)]
+pub enum OutputEvent {
+    Transaction(Arc<ForwardedTx>),
+    Snapshot(Arc<Snapshot>),
+    Race(Arc<RaceEvent>),
+}
+
+/// The events a sink receives (`events` in its settings).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventMask {
+    pub transactions: bool,
+    pub snapshots: bool,
+    pub races: bool,
+}
+
+impl EventMask {
+    pub const ALL: Self = Self { transactions: true, snapshots: true, races: true };
+
+    /// From event names: `transactions`, `snapshots`, `races`.
+    pub fn parse<S: AsRef<str>>(names: &[S]) -> Result<Self> {
+        let mut mask = Self { transactions: false, snapshots: false, races: false };
+        for name in names {
+            match name.as_ref() {
+                "transactions" => mask.transactions = true,
+                "snapshots" => mask.snapshots = true,
+                "races" => mask.races = true,
+                other => anyhow::bail!(
+                    "unknown output event '{}' (expected transactions, snapshots or races)",
+                    other
+                ),
+            }
+        }
+        Ok(mask)
+    }
+
+    fn wants(&self, event: &OutputEvent) -> bool {
+        match event {
+            OutputEvent::Transaction(_) => self.transactions,
+            OutputEvent::Snapshot(_) => self.snapshots,
+            OutputEvent::Race(_) => self.races,
+        }
+    }
+}
+
+/// A sink with the name it is logged under and the events it receives.
+pub struct NamedSink {
+    pub name: String,
+    pub events: EventMask,
+    pub sink: Box<dyn OutputSink>,
+}
+
+// ---------------------------------------------------------------------------
+// Registry
+// ---------------------------------------------------------------------------
+
+/// Builds a sink from its settings, less `type`, `name` and `events`.
+pub type SinkFactory = fn(serde_json::Value) -> Result<Box<dyn OutputSink>>;
+
+/// Sink factories by `type`.
+pub struct SinkRegistry {
+    factories: BTreeMap<String, SinkFactory>,
+}
+
+impl Default for SinkRegistry {
+    /// The built-in sinks.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("jsonl", JsonlSink::from_settings);
+        registry.register("unix", UnixSink::from_settings);
+        registry.register("nats", crate::output_nats::NatsSink::from_settings);
+        #[cfg(feature = "grpc")]
+        registry.register("grpc", crate::output_grpc::GrpcSink::from_settings);
+        registry.register("race_csv", RaceCsvSink::from_settings);
+        registry
+    }
+}
+
+impl SinkRegistry {
+    pub fn empty() -> Self {
+        Self { factories: BTreeMap::new() }
+    }
+
+    /// Add a sink type, or replace the factory of an existing one.
+    pub fn register(&mut self, kind: &str, factory: SinkFactory) {
+        self.factories.insert(kind.to_string(), factory);
+    }
+
+    /// Build the sink `settings` describe. `type` picks the factory; the
+    /// optional `name` (default: the type) and `events` (default: all the
+    /// sink handles) are taken out before the factory sees the rest.
+    pub fn build(&self, mut settings: serde_json::Value) -> Result<NamedSink> {
+        let table = settings.as_object_mut().context("an output must be a table")?;
+        let kind = match table.remove("type") {
+            Some(serde_json::Value::String(kind)) => kind,
+            _ => anyhow::bail!("an output needs a `type`"),
+        };
+        let name = match table.remove("name") {
+            Some(serde_json::Value::String(name)) => name,
+            Some(_) => anyhow::bail!("output `name` must be a string"),
+            None => kind.clone(),
+        };
+        let events = match table.remove("events") {
+            Some(events) => {
+                let names: Vec<String> = serde_json::from_value(events)
+                    .context("output `events` must be a list of event names")?;
+                Some(EventMask::parse(&names)?)
+            }
+            None => None,
+        };
+        let Some(factory) = self.factories.get(&kind) else {
+            let known: Vec<&str> = self.factories.keys().map(String::as_str).collect();
+            anyhow::bail!("unknown output type '{}' (known: {})", kind, known.join(", "));
+        };
+        let sink = factory(settings).with_context(|| format!("output '{}'", name))?;
+        let events = events.unwrap_or_else(|| sink.events());
+        Ok(NamedSink { name, events, sink })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Hub
+// ---------------------------------------------------------------------------
+
+struct HubSink {
+    name: String,
+    events: EventMask,
+    /// `None` tells the sink's thread to stop.
+    tx: Sender<Option<OutputEvent>>,
+    dropped: AtomicU64,
+}
+
+/// Hands every event to the sinks that want it, each on its own thread.
+pub struct OutputHub {
+    sinks: Vec<HubSink>,
+    threads: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl OutputHub {
+    pub fn spawn(sinks: Vec<NamedSink>) -> Result<Self> {
+        let mut hub = Self { sinks: Vec::with_capacity(sinks.len()), threads: Mutex::default() };
+        for NamedSink { name, events, sink } in sinks {
+            let (tx, rx) = crossbeam_channel::bounded(QUEUE);
+            let thread = std::thread::Builder::new()
+                .name(format!("output-{}", name))
+                .spawn(move || deliver(sink, rx))
+                .with_context(|| format!("failed to spawn output '{}'", name))?;
+            hub.threads.lock().unwrap().push(thread);
+            hub.sinks.push(HubSink { name, events, tx, dropped: AtomicU64::new(0) });
+        }
+        Ok(hub)
+    }
+
+    /// Let every sink finish its queue and drop it, waiting up to `timeout`
+    /// in all. Events published afterwards are counted as dropped.
+    pub fn close(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        for s in &self.sinks {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let _ = s.tx.send_timeout(None, left);
+        }
+        for thread in self.threads.lock().unwrap().drain(..) {
+            while !thread.is_finished() && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            if thread.is_finished() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    pub fn wants_races(&self) -> bool {
+        self.sinks.iter().any(|s| s.events.races)
+    }
+
+    /// Whether any sink takes snapshots; callers can skip building one.
+    pub fn wants_snapshots(&self) -> bool {
+        self.sinks.iter().any(|s| s.events.snapshots)
+    }
+
+    pub fn transaction(&self, tx: ForwardedTx) {
+        if self.sinks.iter().any(|s| s.events.transactions) {
+            self.publish(OutputEvent::Transaction(Arc::new(tx)));
+        }
+    }
+
+    pub fn snapshot(&self, snapshot: Snapshot) {
+        if self.wants_snapshots() {
+            self.publish(OutputEvent::Snapshot(Arc::new(snapshot)));
+        }
+    }
+
+    pub fn race(&self, event: &RaceEvent) {
+        if self.wants_races() {
+            self.publish(OutputEvent::Race(Arc::new(event.clone())));
+        }
+    }
+
+    /// Events each sink has dropped while it lagged, by name.
+    pub fn dropped(&self) -> Vec<(String, u64)> {
+        self.sinks.iter().map(|s| (s.name.clone(), s.dropped.load(Relaxed))).collect()
+    }
+
+    fn publish(&self, event: OutputEvent) {
+        for s in self.sinks.iter().filter(|s| s.events.wants(&event)) {
+            let sent = s.tx.try_send(Some(event.clone())).is_ok();
+            if !sent && s.dropped.fetch_add(1, Relaxed) == 0 {
+                tracing::warn!(
+                    "output '{}' is falling behind; events are dropped and counted",
+                    s.name
+                );
+            }
+        }
+    }
+}
+
+fn deliver(mut sink: Box<dyn OutputSink>, rx: Receiver<Option<OutputEvent>>) {
+    let handle = |sink: &mut dyn OutputSink, event: OutputEvent| match event {
+        OutputEvent::Transaction(tx) => sink.on_transaction(&tx),
+        OutputEvent::Snapshot(s) => sink.on_snapshot(&s),
+        OutputEvent::Race(e) => sink.on_race_event(&e),
+    };
+    while let Ok(Some(event)) = rx.recv() {
+        handle(sink.as_mut(), event);
+        while let Ok(event) = rx.try_recv() {
+            let Some(event) = event else {
+                sink.flush();
+                return;
+            };
+            handle(sink.as_mut(), event);
+        }
+        sink.flush();
+    }
+    sink.flush();
+}
+
+// ---------------------------------------------------------------------------
+// JSON encoding
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize)]
+struct TxJson<'a> {
+    event: &'static str,
+    signature: String,
+    source: &'a str,
+    slot: u64,
+    /// Receive time of the shred (or message) that carried it, Unix ns.
+    recv_unix_ns: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicate_of: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lag_us: Option<i64>,
+    /// bincode `VersionedTransaction`, base64.
+    tx: String,
+}
+
+#[derive(Serialize)]
+struct RaceJson<'a> {
+    event: &'static str,
+    slot: u64,
+    idx: u32,
+    winner: &'a str,
+    loser: &'a str,
+    lead_us: i64,
+    /// Receive time of the winning copy, Unix ns.
+    recv_unix_ns: u64,
+    /// Receive time of the losing copy, Unix ns.
+    loser_recv_unix_ns: u64,
+}
+
+/// `event` as the built-in sinks write it, one line of JSON.
+pub fn event_json(event: &OutputEvent) -> String {
+    match event {
+        OutputEvent::Transaction(tx) => tx_json(tx),
+        OutputEvent::Snapshot(s) => snapshot_json(s),
+        OutputEvent::Race(e) => race_json(e),
+    }
+}
+
+pub fn tx_json(tx: &ForwardedTx) -> String {
+    let decoded = &tx.decoded;
+    let bytes = bincode::serialize(&decoded.transaction).unwrap_or_default();
+    let signature = decoded.transaction.signatures.first();
+    let json = TxJson {
+        event: "tx",
+        signature: signature.map(|s| s.to_string()).unwrap_or_default(),
+        source: &tx.source,
+        slot: decoded.slot,
+        recv_unix_ns: decoded.shred_recv_ns.to_realtime().0,
+        duplicate_of: tx.duplicate_of.as_ref().map(|d| &*d.winner),
+        lag_us: tx.duplicate_of.as_ref().map(|d| d.lag_us),
+        tx: base64::engine::general_purpose::STANDARD.encode(bytes),
+    };
+    serde_json::to_string(&json).unwrap_or_default()
+}
+
+pub fn snapshot_json(snapshot: &Snapshot) -> String {
+    format!(r#"{{"event":"snapshot","ts":{},"entry":{}}}"#, snapshot.ts, snapshot.json)
+}
+
+pub fn race_json(event: &RaceEvent) -> String {
+    let json = RaceJson {
+        event: "race",
+        slot: event.slot,
+        idx: event.idx,
+        winner: &event.winner,
+        loser: &event.loser,
+        lead_us: event.lead_us,
+        recv_unix_ns: event.winner_recv_ns.to_realtime().0,
+        loser_recv_unix_ns: event.loser_recv_ns.to_realtime().0,
+    };
+    serde_json::to_string(&json).unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// jsonl
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonlSettings {
+    path: String,
+}
+
+/// Appends one JSON line per event to a file.
+pub struct JsonlSink {
+    out: BufWriter<std::fs::File>,
+}
+
+impl JsonlSink {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        Ok(Self { out: BufWriter::new(file) })
+    }
+
+    fn from_settings(settings: serde_json::Value) -> Result<Box<dyn OutputSink>> {
+        let s: JsonlSettings = serde_json::from_value(settings)?;
+        Ok(Box::new(Self::open(Path::new(&s.path))?))
+    }
+
+    fn write(&mut self, line: String) {
+        if let Err(e) = writeln!(self.out, "{}", line) {
+            tracing::warn!("jsonl output: write failed: {}", e);
+        }
+    }
+}
+
+impl OutputSink for JsonlSink {
+    fn on_transaction(&mut self, tx: &ForwardedTx) {
+        self.write(tx_json(tx));
+    }
+
+    fn on_snapshot(&mut self, snapshot: &Snapshot) {
+        self.write(snapshot_json(snapshot));
+    }
+
+    fn on_race_event(&mut self, event: &RaceEvent) {
+        self.write(race_json(event));
+    }
+
+    fn flush(&mut self) {
+        let _ = self.out.flush();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// race_csv
+// ---------------------------------------------------------------------------
+
+type GzFile = flate2::write::GzEncoder<BufWriter<std::fs::File>>;
+
+const RACE_CSV_HEADER: &str =
+    "slot,idx,winner,loser,lead_us,winner_recv_unix_ns,loser_recv_unix_ns\n";
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RaceCsvSettings {
+    dir: String,
+    /// Start a new file this often.
+    #[serde(default = "RaceCsvSettings::default_rotate_secs")]
+    rotate_secs: u64,
+    /// Samples written per second at most; 0 for no limit.
+    #[serde(default)]
+    max_per_sec: u64,
+}
+
+impl RaceCsvSettings {
+    fn default_rotate_secs() -> u64 {
+        3600
+    }
+}
+
+/// Writes every race sample, with both copies' receive times, to gzipped
+/// CSV files `<dir>/races-<unix time>.csv.gz` for offline analysis.
+///
+/// A file is complete once the sink moves on to the next one or is dropped.
+/// Samples over `max_per_sec`, or lost to a write error, are skipped and
+/// counted; the count is logged as each file is closed.
+pub struct RaceCsvSink {
+    dir: std::path::PathBuf,
+    rotate: Duration,
+    max_per_sec: u64,
+    file: Option<(GzFile, Instant)>,
+    /// After a write error, no file is opened before this.
+    retry_at: Option<Instant>,
+    second: Option<Instant>,
+    in_second: u64,
+    skipped: u64,
+}
+
+impl RaceCsvSink {
+    pub fn new(dir: &Path, rotate: Duration, max_per_sec: u64) -> Result<Self> {
+        anyhow::ensure!(!rotate.is_zero(), "race_csv rotate_secs must be at least 1");
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            rotate,
+            max_per_sec,
+            file: None,
+            retry_at: None,
+            second: None,
+            in_second: 0,
+            skipped: 0,
+        })
+    }
+
+    fn from_settings(settings: serde_json::Value) -> Result<Box<dyn OutputSink>> {
+        let s: RaceCsvSettings = serde_json::from_value(settings)?;
+        let rotate = Duration::from_secs(s.rotate_secs);
+        Ok(Box::new(Self::new(Path::new(&s.dir), rotate, s.max_per_sec)?))
+    }
+
+    /// Whether the rate limit lets one more sample through.
+    fn admit(&mut self, now: Instant) -> bool {
+        if self.max_per_sec == 0 {
+            return true;
+        }
+        if !matches!(self.second, Some(t) if now.duration_since(t) < Duration::from_secs(1)) {
+            self.second = Some(now);
+            self.in_second = 0;
+        }
+        self.in_second += 1;
+        self.in_second <= self.max_per_sec
+    }
+
+    /// The file to write to, rotated when due.
+    fn file(&mut self, now: Instant) -> Result<&mut GzFile> {
+        let due = |(_, opened): &(GzFile, Instant)| now.duration_since(*opened) >= self.rotate;
+        if self.file.as_ref().is_some_and(due) {
+            self.close_file();
+        }
+        if self.file.is_none() {
+            let unix = crate::clock::RealtimeNs::now().0 / 1_000_000_000;
+            let path = self.dir.join(format!("races-{}.csv.gz", unix));
+            let f = std::fs::File::create(&path)
+                .with_context(|| format!("failed to create {}", path.display()))?;
+            let mut gz = flate2::write::GzEncoder::new(BufWriter::new(f), Default::default());
+            gz.write_all(RACE_CSV_HEADER.as_bytes())?;
+            self.file = Some((gz, now));
+        }
+        Ok(&mut self.file.as_mut().expect("opened above").0)
+    }
+
+    fn close_file(&mut self) {
+        let Some((gz, _)) = self.file.take() else { return };
+        if let Err(e) = gz.finish().and_then(|mut out| out.flush()) {
+            tracing::warn!("race_csv output: failed to finish file: {}", e);
+        }
+        if self.skipped > 0 {
+            tracing::info!("race_csv output: {} samples skipped", self.skipped);
+            self.skipped = 0;
+        }
+    }
+}
+
+impl OutputSink for RaceCsvSink {
+    fn on_race_event(&mut self, e: &RaceEvent) {
+        let now = Instant::now();
+        let failed = self.retry_at.is_some_and(|t| now < t);
+        if failed || !self.admit(now) {
+            self.skipped += 1;
+            return;
+        }
+        let line = format!(
+            "{},{},{},{},{},{},{}\n",
+            e.slot,
+            e.idx,
+            e.winner,
+            e.loser,
+            e.lead_us,
+            e.winner_recv_ns.to_realtime().0,
+            e.loser_recv_ns.to_realtime().0,
+        );
+        let written = self.file(now).and_then(|f| Ok(f.write_all(line.as_bytes())?));
+        if let Err(e) = written {
+            tracing::warn!("race_csv output: {:#}  retrying in 5s", e);
+            self.file = None;
+            self.retry_at = Some(now + Duration::from_secs(5));
+        }
+    }
+
+    fn events(&self) -> EventMask {
+        EventMask { transactions: false, snapshots: false, races: true }
+    }
+}
+
+impl Drop for RaceCsvSink {
+    fn drop(&mut self) {
+        self.close_file();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// unix
+// ---------------------------------------------------------------------------
+
+struct Client {
+    tx: Sender<Arc<[u8]>>,
+    dropped: u64,
+}
+
+/// A Unix socket that writes every frame to each connected client.
+///
+/// Each client gets its own writer thread and a bounded queue. A client that
+/// cannot keep up loses whole frames — never part of one — and never slows
+/// the caller or the other clients. Drops are logged when the client goes
+/// away.
+#[derive(Clone)]
+pub struct UnixBroadcast {
+    clients: Arc<Mutex<Vec<Client>>>,
+}
+
+impl UnixBroadcast {
+    /// Bind `path` and accept clients on a background thread named `name`.
+    /// A stale socket file from a previous run is removed first.
+    pub fn bind(path: &Path, name: &str) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        let _ = std::fs::remove_file(path);
+        let listener = UnixListener::bind(path)
+            .with_context(|| format!("failed to bind {}", path.display()))?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o660))?;
+
+        let clients: Arc<Mutex<Vec<Client>>> = Arc::default();
+        let accepted = clients.clone();
+        let label = name.to_string();
+        std::thread::Builder::new().name(name.into()).spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(s) => {
+                        let (tx, rx) = crossbeam_channel::bounded(QUEUE);
+                        let spawned = std::thread::Builder::new()
+                            .name(format!("{}-client", label))
+                            .spawn(move || write_frames(s, rx));
+                        match spawned {
+                            Ok(_) => {
+                                tracing::info!("{}: client connected", label);
+                                accepted.lock().unwrap().push(Client { tx, dropped: 0 });
+                            }
+                            Err(e) => tracing::warn!("{}: failed to spawn writer: {}", label, e),
+                        }
+                    }
+                    Err(e) => tracing::warn!("{}: accept failed: {}", label, e),
+                }
+            }
+        })?;
+        Ok(Self { clients })
+    }
+
+    pub fn has_clients(&self) -> bool {
+        !self.clients.lock().unwrap().is_empty()
+    }
+
+    /// Queue `frame` for every connected client.
+    pub fn send(&self, frame: Arc<[u8]>) {
+        self.clients.lock().unwrap().retain_mut(|c| match c.tx.try_send(frame.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                c.dropped += 1;
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                tracing::info!(
+                    "unix socket client disconnected ({} frames dropped while it lagged)",
+                    c.dropped
+                );
+                false
+            }
+        });
+    }
+}
+
+/// Drain one client's queue, flushing whenever it runs dry. Returns (and so
+/// disconnects the client) on the first write error.
+fn write_frames(stream: UnixStream, rx: Receiver<Arc<[u8]>>) {
+    let mut out = BufWriter::new(stream);
+    while let Ok(frame) = rx.recv() {
+        if out.write_all(&frame).is_err() {
+            return;
+        }
+        while let Ok(frame) = rx.try_recv() {
+            if out.write_all(&frame).is_err() {
+                return;
+            }
+        }
+        if out.flush().is_err() {
+            return;
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UnixSettings {
+    socket: String,
+}
+
+/// Streams one JSON line per event to every client of a Unix socket.
+pub struct UnixSink {
+    socket: UnixBroadcast,
+}
+
+impl UnixSink {
+    fn from_settings(settings: serde_json::Value) -> Result<Box<dyn OutputSink>> {
+        let s: UnixSettings = serde_json::from_value(settings)?;
+        let socket = UnixBroadcast::bind(Path::new(&s.socket), "output-unix")?;
+        Ok(Box::new(Self { socket }))
+    }
+
+    fn send(&self, encode: impl FnOnce() -> String) {
+        if self.socket.has_clients() {
+            let mut line = encode();
+            line.push('\n');
+            self.socket.send(line.into_bytes().into());
+        }
+    }
+}
+
+impl OutputSink for UnixSink {
+    fn on_transaction(&mut self, tx: &ForwardedTx) {
+        self.send(|| tx_json(tx));
+    }
+
+    fn on_snapshot(&mut self, snapshot: &Snapshot) {
+        self.send(|| snapshot_json(snapshot));
+    }
+
+    fn on_race_event(&mut self, event: &RaceEvent) {
+        self.send(|| race_json(event));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MonotonicNs;
+    use crate::decoder::DecodedTx;
+
+    /// Collects what it is told, for the hub test.
+    struct Collect(Sender<String>);
+
+    impl OutputSink for Collect {
+        fn on_transaction(&mut self, tx: &ForwardedTx) {
+            let _ = self.0.send(format!("tx {}", tx.source));
+        }
+
+        fn on_race_event(&mut self, event: &RaceEvent) {
+            let _ = self.0.send(format!("race {}", event.winner));
+        }
+    }
+
+    fn race() -> RaceEvent {
+        RaceEvent {
+            slot: 7,
+            idx: 3,
+            winner: "a".into(),
+            loser: "b".into(),
+            lead_us: 250,
+            winner_recv_ns: MonotonicNs::now(),
+            loser_recv_ns: MonotonicNs::now(),
+        }
+    }
+
+    fn forwarded(source: &str) -> ForwardedTx {
+        ForwardedTx {
+            decoded: DecodedTx {
+                transaction: Default::default(),
+                slot: 7,
+                shred_recv_ns: MonotonicNs::now(),
+                decode_done_ns: MonotonicNs::now(),
+            },
+            source: source.into(),
+            duplicate_of: None,
+        }
+    }
+
+    #[test]
+    fn test_registry_builds_by_type_and_rejects_unknown() {
+        fn collect(_: serde_json::Value) -> Result<Box<dyn OutputSink>> {
+            Ok(Box::new(Collect(crossbeam_channel::unbounded().0)))
+        }
+        let mut registry = SinkRegistry::empty();
+        registry.register("collect", collect);
+        let sink = registry
+            .build(serde_json::json!({ "type": "collect", "events": ["races"] }))
+            .unwrap();
+        assert_eq!(sink.name, "collect");
+        assert_eq!(sink.events, EventMask { transactions: false, snapshots: false, races: true });
+
+        let err = registry.build(serde_json::json!({ "type": "kafka" })).err().unwrap();
+        assert!(err.to_string().contains("unknown output type 'kafka'"), "{}", err);
+        let err = registry
+            .build(serde_json::json!({ "type": "collect", "events": ["blocks"] }))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("blocks"), "{}", err);
+        assert!(SinkRegistry::default()
+            .build(serde_json::json!({ "type": "jsonl", "file": "x" }))
+            .is_err());
+    }
+
+    #[test]
+    fn test_hub_delivers_only_subscribed_events() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let hub = OutputHub::spawn(vec![NamedSink {
+            name: "collect".into(),
+            events: EventMask { transactions: false, snapshots: true, races: true },
+            sink: Box::new(Collect(tx)),
+        }])
+        .unwrap();
+        assert!(hub.wants_races() && hub.wants_snapshots());
+        hub.transaction(forwarded("a"));
+        hub.race(&race());
+        let got = rx.recv_timeout(std::time::Duration::from_secs(1)).unwrap();
+        assert_eq!(got, "race a");
+        assert!(rx.recv_timeout(std::time::Duration::from_millis(50)).is_err());
+        assert_eq!(hub.dropped(), vec![("collect".to_string(), 0)]);
+    }
+
+    #[test]
+    fn test_race_csv_limits_and_completes_file() {
+        use std::io::Read;
+        let dir = std::env::temp_dir().join(format!("shredtop-race-csv-{}", std::process::id()));
+        let mut sink = RaceCsvSink::new(&dir, Duration::from_secs(3600), 2).unwrap();
+        assert!(sink.events().races && !sink.events().transactions);
+        for _ in 0..5 {
+            sink.on_race_event(&race());
+        }
+        drop(sink);
+
+        let file = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        let mut csv = String::new();
+        flate2::read::GzDecoder::new(std::fs::File::open(&file).unwrap())
+            .read_to_string(&mut csv)
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3, "{}", csv);
+        assert_eq!(lines[0], RACE_CSV_HEADER.trim_end());
+        assert!(lines[1].starts_with("7,3,a,b,250,"), "{}", lines[1]);
+    }
+
+    #[test]
+    fn test_event_json() {
+        let line = race_json(&race());
+        let v: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(v["event"], "race");
+        assert_eq!(v["winner"], "a");
+        assert_eq!(v["lead_us"], 250);
+
+        let snapshot = Snapshot { ts: 9, sources: Vec::new(), json: r#"{"ts":9}"#.into() };
+        let v: serde_json::Value = serde_json::from_str(&snapshot_json(&snapshot)).unwrap();
+        assert_eq!(v["entry"]["ts"], 9);
+
+        let v: serde_json::Value = serde_json::from_str(&tx_json(&forwarded("b"))).unwrap();
+        assert_eq!(v["event"], "tx");
+        assert_eq!(v["source"], "b");
+        assert!(v.get("duplicate_of").is_none());
+    }
+}
@@ -0,0 +1,139 @@
+//! gRPC output sink: streams events to a collector.
+//!
+//! One client-streaming call carries every event for as long as the
+//! connection lasts; the sink reconnects automatically (5s delay). Events
+//! are the JSON objects of [`crate::output::event_json`], wrapped as:
+//!
+//! ```text
+//! message Event { string event = 1; string json = 2; }
+//! message PublishAck {}
+//! service Output { rpc Publish(stream Event) returns (PublishAck); }
+//! ```
+//!
+//! in package `shredtop.output.v1`. Events queued while disconnected are
+//! kept up to the queue size, later ones dropped.
+
+use anyhow::Result;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::Arc;
+use tokio::sync::mpsc::error::TrySendError;
+
+use crate::fan_in::ForwardedTx;
+use crate::grpc::GrpcSettings;
+use crate::output::{race_json, snapshot_json, tx_json, OutputSink, Snapshot};
+use crate::shred_race::RaceEvent;
+
+/// Events held for the collector before new ones are dropped.
+const QUEUE: usize = 16_384;
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Event {
+    #[prost(string, tag = "1")]
+    pub event: String,
+    #[prost(string, tag = "2")]
+    pub json: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct PublishAck {}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GrpcSinkSettings {
+    /// Collector endpoint, e.g. "http://127.0.0.1:50051".
+    url: String,
+}
+
+pub struct GrpcSink {
+    tx: tokio::sync::mpsc::Sender<Event>,
+    dropped: u64,
+}
+
+impl GrpcSink {
+    pub(crate) fn from_settings(settings: serde_json::Value) -> Result<Box<dyn OutputSink>> {
+        let s: GrpcSinkSettings = serde_json::from_value(settings)?;
+        let grpc = GrpcSettings::default();
+        grpc.validate(&s.url)?;
+        let (tx, rx) = tokio::sync::mpsc::channel(QUEUE);
+        let rx = Arc::new(tokio::sync::Mutex::new(rx));
+        std::thread::Builder::new().name("output-grpc-rt".into()).spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("output-grpc: failed to build tokio runtime");
+            rt.block_on(async move {
+                loop {
+                    match publish(&s.url, &grpc, rx.clone()).await {
+                        // The sink, and with it the queue, is gone.
+                        Ok(()) => return,
+                        Err(e) => tracing::warn!(
+                            "grpc output to {} disconnected: {}  reconnecting in 5s",
+                            s.url,
+                            e
+                        ),
+                    }
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                }
+            });
+        })?;
+        Ok(Box::new(Self { tx, dropped: 0 }))
+    }
+
+    fn send(&mut self, event: &str, json: String) {
+        let event = Event { event: event.into(), json };
+        if let Err(TrySendError::Full(_)) = self.tx.try_send(event) {
+            self.dropped += 1;
+            if self.dropped == 1 {
+                tracing::warn!("grpc output: collector is behind; events are dropped");
+            }
+        }
+    }
+}
+
+impl OutputSink for GrpcSink {
+    fn on_transaction(&mut self, tx: &ForwardedTx) {
+        self.send("tx", tx_json(tx));
+    }
+
+    fn on_snapshot(&mut self, snapshot: &Snapshot) {
+        self.send("snapshot", snapshot_json(snapshot));
+    }
+
+    fn on_race_event(&mut self, event: &RaceEvent) {
+        self.send("race", race_json(event));
+    }
+}
+
+/// One `Publish` call, fed from the queue until the connection fails.
+/// `Ok` once the queue is closed and drained; a call the collector ends
+/// before then is an error, so the sink reconnects.
+async fn publish(
+    url: &str,
+    grpc: &GrpcSettings,
+    rx: Arc<tokio::sync::Mutex<tokio::sync::mpsc::Receiver<Event>>>,
+) -> Result<()> {
+    let channel = grpc.connect(url).await?;
+    let mut client = tonic::client::Grpc::new(channel);
+    client.ready().await?;
+    tracing::info!("grpc output: connected to {}", url);
+
+    let drained = Arc::new(AtomicBool::new(false));
+    let flag = drained.clone();
+    let events = futures_util::stream::unfold(rx, move |rx| {
+        let drained = flag.clone();
+        async move {
+            let Some(event) = rx.lock().await.recv().await else {
+                drained.store(true, Relaxed);
+                return None;
+            };
+            Some((event, rx))
+        }
+    });
+    let path =
+        tonic::codegen::http::uri::PathAndQuery::from_static("/shredtop.output.v1.Output/Publish");
+    let codec = tonic_prost::ProstCodec::<Event, PublishAck>::default();
+    client.client_streaming(tonic::Request::new(events), path, codec).await?;
+    anyhow::ensure!(drained.load(Relaxed), "collector ended the stream");
+    Ok(())
+}
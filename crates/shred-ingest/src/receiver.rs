@@ -4,37 +4,158 @@
 //! raw shred bytes with a nanosecond receive timestamp.
 //!
 //! ## Hot-path design (Linux)
-//! * `SO_BUSY_POLL 50µs` — spin-waits for packets, eliminates scheduler wakeup latency
+//! * `SO_BUSY_POLL` (50µs by default) — spin-waits for packets, eliminates scheduler
+//!   wakeup latency
 //! * `SO_TIMESTAMPNS` — kernel captures receive timestamp at NIC driver level,
 //!   before any userspace scheduling jitter; more accurate than `clock_gettime` after `recv`
-//! * `recvmmsg(MSG_WAITFORONE, batch=64)` — returns as soon as ≥1 packet is available,
-//!   filling more if already queued; reduces syscall overhead at high packet rates
-//! * `SO_RCVBUFFORCE 32MB` — bypasses `net.core.rmem_max`; falls back to `SO_RCVBUF`
-//!   with a warning if not running as root
+//! * `recvmmsg(MSG_WAITFORONE)` (batches of 64 by default) — returns as soon as ≥1
+//!   packet is available, filling more if already queued; reduces syscall overhead at
+//!   high packet rates
+//! * Shreds reach the decoder through a lock-free SPSC ring ([`crate::spsc`]),
+//!   published once per `recvmmsg` batch; see [`shred_queue`]
+//! * `SO_RCVBUFFORCE` (256MB by default) — bypasses `net.core.rmem_max`; falls back to
+//!   `SO_RCVBUF` with a warning if not running as root
+//! * `SO_MEMINFO` — sampled after full batches and every 100 ms to track the
+//!   receive-queue high-water mark and kernel drops (buffer overflow) per source
+//! * Busy/idle accounting — each batch's wait inside `recvmmsg` and the time spent
+//!   handling the previous batch are summed per source, with packets per call, so
+//!   a receive thread close to saturation shows up before the kernel drops packets
+//!
+//! Busy-poll time, buffer size and batch size are the defaults of
+//! [`SocketTuning`], applied when [`ShredReceiver::run`] starts;
+//! [`ShredReceiver::with_tuning`] overrides them (`shredtop bench --matrix`).
+//!
+//! ## Multi-socket fanout (unicast only)
+//! A single socket caps out at roughly one core of softirq plus one recv thread.
+//! [`ShredReceiver::new_unicast_group`] binds N sockets to the same address with
+//! `SO_REUSEPORT` and attaches a classic BPF program (`SO_ATTACH_REUSEPORT_CBPF`)
+//! that picks the socket by `shred_index % N`. Distribution is therefore by shred,
+//! not by flow hash — a relay sending everything from one `ip:port` still spreads
+//! across all sockets. Multicast delivery ignores reuseport selection (every
+//! member socket receives every datagram), so fanout is not offered there.
 
 use anyhow::Result;
-use crossbeam_channel::Sender;
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TrySendError};
 use socket2::{Domain, Protocol, Socket, Type};
 use std::net::{Ipv4Addr, SocketAddrV4};
 use std::sync::atomic::Ordering::Relaxed;
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::clock::{MonotonicNs, RealtimeNs, RealtimeOffset};
 use crate::metrics;
+use crate::senders::FirstSeen;
 use crate::shred_race::ShredArrival;
 use crate::source_metrics::SourceMetrics;
+use crate::spsc;
+use crate::supervise::StopSignal;
 
 /// Raw shred bytes received from UDP multicast.
 pub struct RawShred {
     pub data: Vec<u8>,
-    pub recv_timestamp_ns: u64,
+    pub recv_timestamp_ns: MonotonicNs,
+}
+
+/// Shreds queued between the receive threads and the decoder of a source.
+pub const SHRED_QUEUE: usize = 4096;
+
+/// The queue from `producers` receive threads to one decoder: an
+/// [`spsc`] ring for a single thread, a channel when several share the
+/// decoder (striped ports, fanout groups).
+pub fn shred_queue(producers: usize) -> (ShredTx, ShredRx) {
+    if producers == 1 {
+        let (tx, rx) = spsc::ring(SHRED_QUEUE);
+        (ShredTx::Ring(tx), ShredRx::Ring(rx))
+    } else {
+        let (tx, rx) = crossbeam_channel::bounded(SHRED_QUEUE);
+        (ShredTx::Channel(tx), ShredRx::Channel(rx))
+    }
+}
+
+/// The sending end of a [`shred_queue`].
+pub enum ShredTx {
+    Ring(spsc::Producer<RawShred>),
+    Channel(Sender<RawShred>),
+}
+
+impl From<Sender<RawShred>> for ShredTx {
+    fn from(tx: Sender<RawShred>) -> Self {
+        Self::Channel(tx)
+    }
+}
+
+impl ShredTx {
+    pub fn try_send(&self, shred: RawShred) -> Result<(), TrySendError<RawShred>> {
+        match self {
+            Self::Ring(tx) => tx.try_push(shred),
+            Self::Channel(tx) => tx.try_send(shred),
+        }
+    }
+
+    /// Send `shred`, waiting for room; `false` once the decoder is gone.
+    pub fn send(&self, shred: RawShred) -> bool {
+        match self {
+            Self::Ring(tx) => tx.push(shred).is_ok(),
+            Self::Channel(tx) => tx.send(shred).is_ok(),
+        }
+    }
+
+    /// Hand what a ring has staged to the decoder; call before blocking.
+    pub fn flush(&self) {
+        if let Self::Ring(tx) = self {
+            tx.flush();
+        }
+    }
+
+    /// One sender per receive thread of a group of `n`.
+    ///
+    /// # Panics
+    /// If a ring, which has exactly one producer, is split.
+    pub fn fan_out(self, n: usize) -> Vec<ShredTx> {
+        match self {
+            Self::Channel(tx) => (0..n).map(|_| Self::Channel(tx.clone())).collect(),
+            Self::Ring(_) if n == 1 => vec![self],
+            Self::Ring(_) => panic!("an SPSC ring cannot feed {} receive threads", n),
+        }
+    }
+}
+
+/// The receiving end of a [`shred_queue`].
+pub enum ShredRx {
+    Ring(spsc::Consumer<RawShred>),
+    Channel(Receiver<RawShred>),
+}
+
+impl From<Receiver<RawShred>> for ShredRx {
+    fn from(rx: Receiver<RawShred>) -> Self {
+        Self::Channel(rx)
+    }
+}
+
+impl ShredRx {
+    /// The next shred; `None` once every sender is gone and the queue is empty.
+    pub fn recv(&self) -> Option<RawShred> {
+        match self {
+            Self::Ring(rx) => rx.recv(),
+            Self::Channel(rx) => rx.recv().ok(),
+        }
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<RawShred, RecvTimeoutError> {
+        match self {
+            Self::Ring(rx) => rx.recv_timeout(timeout),
+            Self::Channel(rx) => rx.recv_timeout(timeout),
+        }
+    }
 }
 
 /// Event sent from the UDP receiver hot-path to the capture thread.
 /// The channel is bounded(4096); `try_send` never blocks — packets are
 /// silently dropped on overflow rather than stalling the hot path.
 pub struct CaptureEvent {
-    pub ts_ns: u64,
-    pub feed: &'static str,
+    /// Receive time on the monotonic clock ([`metrics::now_ns`]).
+    pub ts_ns: MonotonicNs,
+    pub feed: Arc<str>,
     pub dst_ip: [u8; 4],
     pub dst_port: u16,
     pub payload: Vec<u8>,
@@ -42,16 +163,16 @@ pub struct CaptureEvent {
 
 pub struct ShredReceiver {
     socket: Socket,
-    tx: Sender<RawShred>,
+    tx: ShredTx,
     metrics: Arc<SourceMetrics>,
     /// Optional shred version filter (bytes 77-78). Shreds with a different
     /// version are silently dropped before they reach the decoder.
     shred_version: Option<u16>,
-    /// CLOCK_REALTIME − CLOCK_MONOTONIC_RAW sampled at construction time (ns).
+    /// CLOCK_REALTIME − CLOCK_MONOTONIC_RAW, refreshed as batches arrive.
     /// Applied to every SO_TIMESTAMPNS kernel timestamp to bring it into the
     /// CLOCK_MONOTONIC_RAW reference frame used by the rest of the pipeline.
     #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
-    rt_to_mono_offset_ns: u64,
+    rt_offset: RealtimeOffset,
     /// Optional channel to the shred race tracker. Each received shred's
     /// (slot, shred_index) is forwarded here for cross-feed comparison.
     race_tx: Option<Sender<ShredArrival>>,
@@ -62,31 +183,177 @@ pub struct ShredReceiver {
     dst_ip: [u8; 4],
     /// UDP destination port stored for capture event metadata.
     dst_port: u16,
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    sock_stats: SockStats,
+    slot_gate: SlotGate,
+    /// Recent shreds, to credit the first of several retransmitters sending
+    /// the same shred.
+    first_seen: FirstSeen,
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    tuning: SocketTuning,
+    /// Set when supervised; `run` returns once it is stopped.
+    stop: Option<StopSignal>,
+}
+
+/// State for periodic `SO_MEMINFO` sampling of the receive socket.
+#[derive(Default)]
+struct SockStats {
+    /// Kernel drop counter at the previous sample (u32, wraps).
+    last_drops: u32,
+    last_sample_ns: u64,
+}
+
+/// Rejects packets whose slot is implausibly far ahead of the highest slot
+/// this receiver has accepted. The allowance grows with the time since that
+/// slot was seen, so a feed that resumes after a long gap is not locked out.
+/// The first packet is not taken on trust: the gate only closes once
+/// [`SLOT_GATE_SEED_PACKETS`] accepted packets in a row agree on the slot.
+#[derive(Default)]
+struct SlotGate {
+    highest: u64,
+    highest_at: MonotonicNs,
+    /// Candidate first slot and the packets in a row that agreed with it.
+    seed: (u64, u32),
+}
+
+/// Slots a packet may run ahead of the highest accepted slot, before the
+/// allowance for elapsed time.
+const FUTURE_SLOT_MARGIN: u64 = 1_000;
+/// One more slot of allowance per this many nanoseconds since the highest
+/// slot was accepted — four times the nominal slot rate.
+const FUTURE_SLOT_NS: u64 = 100_000_000;
+/// Accepted packets within [`FUTURE_SLOT_MARGIN`] of each other needed
+/// before the gate trusts their slot.
+const SLOT_GATE_SEED_PACKETS: u32 = 8;
+
+impl SlotGate {
+    fn admits(&self, slot: u64, now: MonotonicNs) -> bool {
+        let elapsed = now.since(self.highest_at);
+        let allowance = FUTURE_SLOT_MARGIN.saturating_add(elapsed / FUTURE_SLOT_NS);
+        self.highest == 0 || slot <= self.highest.saturating_add(allowance)
+    }
+
+    fn accept(&mut self, slot: u64, now: MonotonicNs) {
+        if self.highest == 0 {
+            let (candidate, agreed) = self.seed;
+            self.seed = if agreed > 0 && candidate.abs_diff(slot) <= FUTURE_SLOT_MARGIN {
+                (candidate.max(slot), agreed + 1)
+            } else {
+                (slot, 1)
+            };
+            if self.seed.1 >= SLOT_GATE_SEED_PACKETS {
+                self.highest = self.seed.0;
+                self.highest_at = now;
+            }
+            return;
+        }
+        if slot > self.highest {
+            self.highest = slot;
+            self.highest_at = now;
+        }
+    }
+}
+
+/// Whether a packet has the shape of a shred: long enough for the common and
+/// coding headers (data shreds need 88B, coding shreds 89B — use 89 for both),
+/// and a variant byte whose top two bits name a shred type (`01` code, `10`
+/// data). Bytes with `00` or `11` there cannot encode any shred variant.
+pub(crate) fn is_shred_shaped(pkt: &[u8]) -> bool {
+    pkt.len() >= 89 && matches!(pkt[64] & 0xC0, 0x40 | 0x80)
+}
+
+/// Slot from the common header (bytes 65–72, u64 LE). Callers check the length.
+fn shred_slot(pkt: &[u8]) -> u64 {
+    u64::from_le_bytes(pkt[65..73].try_into().unwrap())
+}
+
+/// Shred index from the common header (bytes 73–76, u32 LE). Callers check
+/// the length.
+fn shred_index(pkt: &[u8]) -> u32 {
+    u32::from_le_bytes(pkt[73..77].try_into().unwrap())
 }
 
 // Standard Solana shred MTU — used by both Linux and fallback paths.
 const PKT_CAP: usize = 1500;
 
 // Linux hot-path constants.
-// Batch size for recvmmsg. 64 is a common sweet-spot: enough to amortise
-// syscall overhead without holding packets in kernel longer than necessary.
-#[cfg(target_os = "linux")]
-const BATCH: usize = 64;
 // cmsg buffer: cmsghdr (16B) + timespec (16B) + alignment padding = 64B is safe.
 #[cfg(target_os = "linux")]
 const CMSG_CAP: usize = 64;
+// Upper bound on sockets in a reuseport fanout group.
+pub const MAX_FANOUT_SOCKETS: usize = 16;
+// Upper bound on `SocketTuning::batch`.
+pub const MAX_RECV_BATCH: usize = 1024;
+/// How often an idle supervised receiver checks its stop signal.
+pub(crate) const STOP_POLL: Duration = Duration::from_millis(200);
+
+/// Receive socket settings (Linux). Every source runs with the defaults;
+/// `shredtop bench --matrix` sweeps them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SocketTuning {
+    /// `SO_BUSY_POLL`: µs to spin for packets before blocking; 0 disables.
+    pub busy_poll_us: u32,
+    /// Requested kernel receive buffer (`SO_RCVBUFFORCE`, else `SO_RCVBUF`).
+    pub recv_buf_bytes: usize,
+    /// Datagrams per `recvmmsg` call.
+    pub batch: usize,
+}
+
+impl Default for SocketTuning {
+    fn default() -> Self {
+        Self {
+            busy_poll_us: 50,
+            recv_buf_bytes: 256 * 1024 * 1024,
+            // 64 is a common sweet-spot: enough to amortise syscall overhead
+            // without holding packets in kernel longer than necessary.
+            batch: 64,
+        }
+    }
+}
+
+impl SocketTuning {
+    pub fn validate(&self) -> Result<()> {
+        anyhow::ensure!(
+            (1..=MAX_RECV_BATCH).contains(&self.batch),
+            "recv batch must be between 1 and {}",
+            MAX_RECV_BATCH
+        );
+        anyhow::ensure!(
+            self.recv_buf_bytes > 0 && self.recv_buf_bytes <= 1 << 30,
+            "recv buffer must be between 1 byte and 1 GiB"
+        );
+        Ok(())
+    }
+}
+
 // MSG_WAITFORONE: return as soon as ≥1 message is available, fill more if queued.
 // Value 0x10000 from <linux/socket.h>; may not be exposed by the libc crate version.
 #[cfg(target_os = "linux")]
 const MSG_WAITFORONE: libc::c_int = 0x10000;
 
+// SO_MEMINFO returns u32[SK_MEMINFO_VARS]; indices from <linux/sock_diag.h>.
+#[cfg(target_os = "linux")]
+const SO_MEMINFO: libc::c_int = 55;
+#[cfg(target_os = "linux")]
+const SK_MEMINFO_RMEM_ALLOC: usize = 0;
+#[cfg(target_os = "linux")]
+const SK_MEMINFO_RCVBUF: usize = 1;
+#[cfg(target_os = "linux")]
+const SK_MEMINFO_DROPS: usize = 8;
+#[cfg(target_os = "linux")]
+const SK_MEMINFO_VARS: usize = 9;
+// Socket memory is sampled after every full recvmmsg batch (the queue had a
+// backlog) and otherwise at least this often.
+#[cfg(target_os = "linux")]
+const SOCK_STATS_INTERVAL_NS: u64 = 100_000_000;
+
 impl ShredReceiver {
     /// Bind to the multicast group on the specified interface.
     pub fn new(
         multicast_addr: &str,
         port: u16,
         interface: &str,
-        tx: Sender<RawShred>,
+        tx: impl Into<ShredTx>,
         metrics: Arc<SourceMetrics>,
         shred_version: Option<u16>,
         race_tx: Option<Sender<ShredArrival>>,
@@ -113,30 +380,6 @@ impl ShredReceiver {
             use std::os::unix::io::AsRawFd;
             let fd = socket.as_raw_fd();
             unsafe {
-                // SO_BUSY_POLL: spin for up to 50µs before blocking.
-                let val: libc::c_int = 50;
-                libc::setsockopt(fd, libc::SOL_SOCKET, libc::SO_BUSY_POLL,
-                    &val as *const _ as _, size_of::<libc::c_int>() as _);
-
-                // SO_RCVBUFFORCE: bypasses net.core.rmem_max (requires root).
-                // Falls back to SO_RCVBUF with a warning if unprivileged.
-                const RECV_BUF: usize = 256 * 1024 * 1024;
-                let buf_val = RECV_BUF as libc::c_int;
-                let force_ok = libc::setsockopt(fd, libc::SOL_SOCKET, libc::SO_RCVBUFFORCE,
-                    &buf_val as *const _ as _, size_of::<libc::c_int>() as _) == 0;
-                if !force_ok {
-                    socket.set_recv_buffer_size(RECV_BUF).ok();
-                    if let Ok(actual) = socket.recv_buffer_size() {
-                        if actual < RECV_BUF / 2 {
-                            tracing::warn!(
-                                "recv buffer is {}KB (wanted {}KB); \
-                                 run as root or: sysctl -w net.core.rmem_max={}",
-                                actual / 1024, RECV_BUF / 1024, RECV_BUF * 2
-                            );
-                        }
-                    }
-                }
-
                 // SO_TIMESTAMPNS: kernel records the receive timestamp at NIC
                 // driver level, returned via SCM_TIMESTAMPNS cmsg on recvmsg/recvmmsg.
                 let one: libc::c_int = 1;
@@ -148,19 +391,24 @@ impl ShredReceiver {
         #[cfg(not(target_os = "linux"))]
         socket.set_recv_buffer_size(4 * 1024 * 1024)?;
 
-        let rt_to_mono_offset_ns = sample_rt_to_mono_offset_ns();
+        let rt_offset = RealtimeOffset::sample();
         let dst_ip = mcast_addr.octets();
 
         Ok(Self {
             socket,
-            tx,
+            tx: tx.into(),
             metrics,
             shred_version,
-            rt_to_mono_offset_ns,
+            rt_offset,
             race_tx,
             capture_tx,
             dst_ip,
             dst_port: port,
+            sock_stats: SockStats::default(),
+            slot_gate: SlotGate::default(),
+            first_seen: FirstSeen::default(),
+            tuning: SocketTuning::default(),
+            stop: None,
         })
     }
 
@@ -174,7 +422,7 @@ impl ShredReceiver {
     /// sample with accurate kernel timestamps, sufficient for lead-time measurement.
     pub fn new_unicast(
         port: u16,
-        tx: Sender<RawShred>,
+        tx: impl Into<ShredTx>,
         metrics: Arc<SourceMetrics>,
         shred_version: Option<u16>,
         race_tx: Option<Sender<ShredArrival>>,
@@ -211,18 +459,6 @@ impl ShredReceiver {
             use std::os::unix::io::AsRawFd;
             let fd = socket.as_raw_fd();
             unsafe {
-                let val: libc::c_int = 50;
-                libc::setsockopt(fd, libc::SOL_SOCKET, libc::SO_BUSY_POLL,
-                    &val as *const _ as _, size_of::<libc::c_int>() as _);
-
-                const RECV_BUF: usize = 256 * 1024 * 1024;
-                let buf_val = RECV_BUF as libc::c_int;
-                let force_ok = libc::setsockopt(fd, libc::SOL_SOCKET, libc::SO_RCVBUFFORCE,
-                    &buf_val as *const _ as _, size_of::<libc::c_int>() as _) == 0;
-                if !force_ok {
-                    socket.set_recv_buffer_size(RECV_BUF).ok();
-                }
-
                 let one: libc::c_int = 1;
                 libc::setsockopt(fd, libc::SOL_SOCKET, libc::SO_TIMESTAMPNS,
                     &one as *const _ as _, size_of::<libc::c_int>() as _);
@@ -232,18 +468,23 @@ impl ShredReceiver {
         #[cfg(not(target_os = "linux"))]
         socket.set_recv_buffer_size(4 * 1024 * 1024)?;
 
-        let rt_to_mono_offset_ns = sample_rt_to_mono_offset_ns();
+        let rt_offset = RealtimeOffset::sample();
 
         Ok(Self {
             socket,
-            tx,
+            tx: tx.into(),
             metrics,
             shred_version,
-            rt_to_mono_offset_ns,
+            rt_offset,
             race_tx,
             capture_tx,
             dst_ip: [0, 0, 0, 0],
             dst_port: port,
+            sock_stats: SockStats::default(),
+            slot_gate: SlotGate::default(),
+            first_seen: FirstSeen::default(),
+            tuning: SocketTuning::default(),
+            stop: None,
         })
     }
 
@@ -258,17 +499,82 @@ impl ShredReceiver {
     pub fn new_generic_unicast(
         addr: &str,
         port: u16,
-        tx: Sender<RawShred>,
+        tx: impl Into<ShredTx>,
         metrics: Arc<SourceMetrics>,
         shred_version: Option<u16>,
         race_tx: Option<Sender<ShredArrival>>,
         capture_tx: Option<Sender<CaptureEvent>>,
     ) -> Result<Self> {
+        // No SO_REUSEPORT — exclusive bind to this address.
+        let socket = Self::bind_unicast_socket(addr, port, false)?;
+        Ok(Self::from_unicast_socket(
+            socket, addr, port, tx.into(), metrics, shred_version, race_tx, capture_tx,
+        ))
+    }
+
+    /// Bind `sockets` receivers to the same unicast `addr:port` with SO_REUSEPORT
+    /// and a shred-index fanout filter. Each receiver should run on its own thread;
+    /// all of them feed the same decoder channel.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_unicast_group(
+        addr: &str,
+        port: u16,
+        sockets: usize,
+        tx: impl Into<ShredTx>,
+        metrics: Arc<SourceMetrics>,
+        shred_version: Option<u16>,
+        race_tx: Option<Sender<ShredArrival>>,
+        capture_tx: Option<Sender<CaptureEvent>>,
+    ) -> Result<Vec<Self>> {
+        anyhow::ensure!(
+            (1..=MAX_FANOUT_SOCKETS).contains(&sockets),
+            "recv_sockets must be between 1 and {}",
+            MAX_FANOUT_SOCKETS
+        );
+        let mut group = Vec::with_capacity(sockets);
+        for (i, tx) in tx.into().fan_out(sockets).into_iter().enumerate() {
+            let socket = Self::bind_unicast_socket(addr, port, true)?;
+            if i == 0 {
+                // The program is shared by the whole reuseport group; attach once.
+                attach_reuseport_index_filter(&socket, sockets)?;
+            }
+            group.push(Self::from_unicast_socket(
+                socket,
+                addr,
+                port,
+                tx,
+                metrics.clone(),
+                shred_version,
+                race_tx.clone(),
+                capture_tx.clone(),
+            ));
+        }
+        Ok(group)
+    }
+
+    fn bind_unicast_socket(addr: &str, port: u16, reuse_port: bool) -> Result<Socket> {
         let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
         socket.set_reuse_address(true)?;
-        // No SO_REUSEPORT — exclusive bind to this address.
         // No multicast group join — unicast only.
 
+        #[cfg(target_os = "linux")]
+        if reuse_port {
+            use std::mem::size_of;
+            use std::os::unix::io::AsRawFd;
+            let one: libc::c_int = 1;
+            let rc = unsafe {
+                libc::setsockopt(socket.as_raw_fd(), libc::SOL_SOCKET, libc::SO_REUSEPORT,
+                    &one as *const _ as _, size_of::<libc::c_int>() as _)
+            };
+            if rc != 0 {
+                anyhow::bail!("SO_REUSEPORT failed: {}", std::io::Error::last_os_error());
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        if reuse_port {
+            anyhow::bail!("multi-socket receive requires Linux");
+        }
+
         let bind_ip: Ipv4Addr = addr.parse().unwrap_or(Ipv4Addr::UNSPECIFIED);
         let bind_addr = SocketAddrV4::new(bind_ip, port);
         socket.bind(&bind_addr.into())?;
@@ -279,18 +585,6 @@ impl ShredReceiver {
             use std::os::unix::io::AsRawFd;
             let fd = socket.as_raw_fd();
             unsafe {
-                let val: libc::c_int = 50;
-                libc::setsockopt(fd, libc::SOL_SOCKET, libc::SO_BUSY_POLL,
-                    &val as *const _ as _, size_of::<libc::c_int>() as _);
-
-                const RECV_BUF: usize = 256 * 1024 * 1024;
-                let buf_val = RECV_BUF as libc::c_int;
-                let force_ok = libc::setsockopt(fd, libc::SOL_SOCKET, libc::SO_RCVBUFFORCE,
-                    &buf_val as *const _ as _, size_of::<libc::c_int>() as _) == 0;
-                if !force_ok {
-                    socket.set_recv_buffer_size(RECV_BUF).ok();
-                }
-
                 let one: libc::c_int = 1;
                 libc::setsockopt(fd, libc::SOL_SOCKET, libc::SO_TIMESTAMPNS,
                     &one as *const _ as _, size_of::<libc::c_int>() as _);
@@ -300,19 +594,57 @@ impl ShredReceiver {
         #[cfg(not(target_os = "linux"))]
         socket.set_recv_buffer_size(4 * 1024 * 1024)?;
 
-        let rt_to_mono_offset_ns = sample_rt_to_mono_offset_ns();
+        Ok(socket)
+    }
 
-        Ok(Self {
+    #[allow(clippy::too_many_arguments)]
+    fn from_unicast_socket(
+        socket: Socket,
+        addr: &str,
+        port: u16,
+        tx: ShredTx,
+        metrics: Arc<SourceMetrics>,
+        shred_version: Option<u16>,
+        race_tx: Option<Sender<ShredArrival>>,
+        capture_tx: Option<Sender<CaptureEvent>>,
+    ) -> Self {
+        let bind_ip: Ipv4Addr = addr.parse().unwrap_or(Ipv4Addr::UNSPECIFIED);
+        Self {
             socket,
             tx,
             metrics,
             shred_version,
-            rt_to_mono_offset_ns,
+            rt_offset: RealtimeOffset::sample(),
             race_tx,
             capture_tx,
             dst_ip: bind_ip.octets(),
             dst_port: port,
-        })
+            sock_stats: SockStats::default(),
+            slot_gate: SlotGate::default(),
+            first_seen: FirstSeen::default(),
+            tuning: SocketTuning::default(),
+            stop: None,
+        }
+    }
+
+    /// Receive with `tuning` instead of [`SocketTuning::default`].
+    pub fn with_tuning(mut self, tuning: SocketTuning) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    /// Return from [`Self::run`] once `stop` is stopped. The socket then
+    /// times out every [`STOP_POLL`] while idle, so an idle receiver notices.
+    pub fn with_stop(mut self, stop: Option<StopSignal>) -> Self {
+        if stop.is_some() {
+            self.socket.set_read_timeout(Some(STOP_POLL)).ok();
+        }
+        self.stop = stop;
+        self
+    }
+
+    fn stopped(&self) -> bool {
+        self.stop.as_ref().is_some_and(StopSignal::is_stopped)
     }
 
     /// Main receive loop — should run on a pinned, isolated core.
@@ -323,6 +655,7 @@ impl ShredReceiver {
         {
             use std::os::unix::io::AsRawFd;
             let fd = self.socket.as_raw_fd();
+            self.apply_tuning(fd);
             self.run_linux(fd)
         }
 
@@ -330,23 +663,80 @@ impl ShredReceiver {
         self.run_fallback()
     }
 
+    /// Set busy polling and the receive buffer size from `self.tuning`.
+    #[cfg(target_os = "linux")]
+    fn apply_tuning(&self, fd: libc::c_int) {
+        use std::mem::size_of;
+        let t = &self.tuning;
+        unsafe {
+            // SO_BUSY_POLL: spin for up to this many µs before blocking.
+            let val = t.busy_poll_us.min(i32::MAX as u32) as libc::c_int;
+            libc::setsockopt(fd, libc::SOL_SOCKET, libc::SO_BUSY_POLL,
+                &val as *const _ as _, size_of::<libc::c_int>() as _);
+
+            // SO_RCVBUFFORCE: bypasses net.core.rmem_max (requires root).
+            // Falls back to SO_RCVBUF with a warning if unprivileged.
+            let buf_val = t.recv_buf_bytes as libc::c_int;
+            let force_ok = libc::setsockopt(fd, libc::SOL_SOCKET, libc::SO_RCVBUFFORCE,
+                &buf_val as *const _ as _, size_of::<libc::c_int>() as _) == 0;
+            if !force_ok {
+                self.socket.set_recv_buffer_size(t.recv_buf_bytes).ok();
+                if let Ok(actual) = self.socket.recv_buffer_size() {
+                    if actual < t.recv_buf_bytes / 2 {
+                        tracing::warn!(
+                            "recv buffer is {}KB (wanted {}KB); \
+                             run as root or: sysctl -w net.core.rmem_max={}",
+                            actual / 1024, t.recv_buf_bytes / 1024, t.recv_buf_bytes * 2
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Read `SO_MEMINFO` and fold it into the source metrics: the granted
+    /// buffer size, the receive-queue high-water mark, and new kernel drops.
+    #[cfg(target_os = "linux")]
+    fn sample_socket_stats(&mut self, fd: libc::c_int) {
+        self.sock_stats.last_sample_ns = metrics::now_ns();
+        let mut info = [0u32; SK_MEMINFO_VARS];
+        let mut len = std::mem::size_of_val(&info) as libc::socklen_t;
+        let rc = unsafe {
+            libc::getsockopt(fd, libc::SOL_SOCKET, SO_MEMINFO, info.as_mut_ptr() as _, &mut len)
+        };
+        if rc != 0 || (len as usize) < (SK_MEMINFO_DROPS + 1) * 4 {
+            return;
+        }
+        let m = &self.metrics;
+        m.socket_rcvbuf_bytes.store(info[SK_MEMINFO_RCVBUF] as u64, Relaxed);
+        m.socket_rmem_hwm_bytes.fetch_max(info[SK_MEMINFO_RMEM_ALLOC] as u64, Relaxed);
+        // The socket is ours from creation, so its counter starts at zero.
+        let drops = info[SK_MEMINFO_DROPS];
+        m.socket_drops.fetch_add(drops.wrapping_sub(self.sock_stats.last_drops) as u64, Relaxed);
+        self.sock_stats.last_drops = drops;
+    }
+
     /// Linux hot path: recvmmsg with kernel timestamps.
     #[cfg(target_os = "linux")]
     fn run_linux(&mut self, fd: libc::c_int) -> Result<()> {
         use std::ptr::null_mut;
         // Pre-allocate batch buffers once; pointers into these are held by
         // iovs/msgs for the lifetime of the loop.
-        let mut pkts = vec![[0u8; PKT_CAP]; BATCH];
-        let mut cmsgs = vec![[0u8; CMSG_CAP]; BATCH];
+        let batch = self.tuning.batch.clamp(1, MAX_RECV_BATCH);
+        let mut pkts = vec![[0u8; PKT_CAP]; batch];
+        let mut cmsgs = vec![[0u8; CMSG_CAP]; batch];
+        // Sender addresses, to tell retransmitters on one group apart.
+        let mut addrs: Vec<libc::sockaddr_in> = vec![unsafe { std::mem::zeroed() }; batch];
+        const ADDR_LEN: libc::socklen_t = std::mem::size_of::<libc::sockaddr_in>() as _;
         let mut iovs: Vec<libc::iovec> = pkts
             .iter_mut()
             .map(|b| libc::iovec { iov_base: b.as_mut_ptr() as _, iov_len: PKT_CAP })
             .collect();
-        let mut msgs: Vec<libc::mmsghdr> = (0..BATCH)
+        let mut msgs: Vec<libc::mmsghdr> = (0..batch)
             .map(|i| libc::mmsghdr {
                 msg_hdr: libc::msghdr {
-                    msg_name: null_mut(),
-                    msg_namelen: 0,
+                    msg_name: &mut addrs[i] as *mut _ as _,
+                    msg_namelen: ADDR_LEN,
                     msg_iov: &mut iovs[i] as *mut _,
                     msg_iovlen: 1,
                     msg_control: cmsgs[i].as_mut_ptr() as _,
@@ -356,21 +746,52 @@ impl ShredReceiver {
                 msg_len: 0,
             })
             .collect();
+        // When the last batch came back, until the next call starts; and the
+        // handling time not yet recorded (failed calls do not record).
+        let mut handling_since: Option<u64> = None;
+        let mut busy_ns = 0u64;
 
         loop {
             // Reset fields that recvmmsg may have modified.
             for (i, msg) in msgs.iter_mut().enumerate() {
                 msg.msg_hdr.msg_controllen = CMSG_CAP;
+                msg.msg_hdr.msg_namelen = ADDR_LEN;
                 msg.msg_hdr.msg_iov = &mut iovs[i] as *mut _;
                 iovs[i].iov_len = PKT_CAP;
             }
 
+            // Publish the last batch before waiting for the next.
+            self.tx.flush();
+            if self.stopped() {
+                return Ok(());
+            }
+            let wait_start = metrics::now_ns();
+            if let Some(since) = handling_since.take() {
+                busy_ns += wait_start.saturating_sub(since);
+            }
             let n = unsafe {
-                libc::recvmmsg(fd, msgs.as_mut_ptr(), BATCH as _, MSG_WAITFORONE, null_mut())
+                libc::recvmmsg(fd, msgs.as_mut_ptr(), batch as _, MSG_WAITFORONE, null_mut())
             };
             if n <= 0 {
                 continue;
             }
+            let batch_ns = metrics::now_ns();
+            let batch_at = MonotonicNs(batch_ns);
+            let wait_ns = batch_ns.saturating_sub(wait_start);
+            self.metrics.record_recv_batch(n as u64, wait_ns, std::mem::take(&mut busy_ns));
+            handling_since = Some(batch_ns);
+            self.rt_offset.refresh(batch_at);
+            if n as usize == batch
+                || batch_ns.saturating_sub(self.sock_stats.last_sample_ns) >= SOCK_STATS_INTERVAL_NS
+            {
+                self.sample_socket_stats(fd);
+            }
+            // Paused sources keep draining the socket so the kernel buffer does
+            // not fill up, but nothing is counted or forwarded.
+            if self.metrics.is_paused() {
+                continue;
+            }
+            let standby = self.metrics.is_standby();
 
             for i in 0..n as usize {
                 let len = msgs[i].msg_len as usize;
@@ -390,16 +811,20 @@ impl ShredReceiver {
                     continue;
                 }
 
-                // Minimum shred size: common header (65B) + coding header end (89B).
-                // Data shreds need 88B; coding shreds need 89B — use 89 for both.
-                // Anything shorter is malformed and cannot be parsed.
-                if len < 89 {
-                    self.metrics.shreds_invalid.fetch_add(1, Relaxed);
+                // Pre-filter: other traffic on the group (control packets,
+                // stray senders) is counted apart and never seen as a shred.
+                let pkt = &pkts[i][..len];
+                if !is_shred_shaped(pkt) || !self.slot_gate.admits(shred_slot(pkt), batch_at) {
+                    self.metrics.non_shred_packets.fetch_add(1, Relaxed);
                     continue;
                 }
+                self.metrics.shred_mix.record_variant(pkt);
+                let sender = Ipv4Addr::from(u32::from_be(addrs[i].sin_addr.s_addr));
+                let first = self.first_seen.insert(shred_slot(pkt), shred_index(pkt));
+                self.metrics.senders.record(sender, first);
 
                 // Variant byte (offset 64) must be a known data or coding value.
-                // Unknown variants indicate garbage UDP payloads — drop before decoder.
+                // Unknown variants cannot be decoded — drop before decoder.
                 let variant = pkts[i][64];
                 let is_data = variant == 0xa5
                     || matches!(variant & 0xF0, 0x80 | 0x90 | 0xa0 | 0xb0);
@@ -415,16 +840,26 @@ impl ShredReceiver {
                     if len >= 79 {
                         let v = u16::from_le_bytes([pkts[i][77], pkts[i][78]]);
                         if v != ver {
+                            self.metrics.shred_mix.record_filtered_version();
                             continue;
                         }
                     }
                 }
+                self.metrics.shred_mix.record_version(&pkts[i][..len]);
+                self.slot_gate.accept(shred_slot(&pkts[i][..len]), batch_at);
+
+                // Failover standby: count for liveness, forward nothing.
+                if standby {
+                    self.metrics.shreds_received.fetch_add(1, Relaxed);
+                    self.metrics.bytes_received.fetch_add(len as u64, Relaxed);
+                    continue;
+                }
 
                 // Prefer kernel timestamp (CLOCK_REALTIME) converted to
                 // CLOCK_MONOTONIC_RAW; fall back to userspace clock.
                 let ts = kernel_ts(&msgs[i].msg_hdr)
-                    .map(|rt| rt.saturating_sub(self.rt_to_mono_offset_ns))
-                    .unwrap_or_else(metrics::now_ns);
+                    .and_then(|rt| RealtimeNs(rt).to_monotonic(self.rt_offset))
+                    .unwrap_or_else(MonotonicNs::now);
 
                 // Shred race: parse (slot, shred_index) from the shred header.
                 // Layout: bytes 65–72 = slot (u64 LE), 73–76 = shred_index (u32 LE).
@@ -433,10 +868,11 @@ impl ShredReceiver {
                         let slot = u64::from_le_bytes(pkts[i][65..73].try_into().unwrap());
                         let idx = u32::from_le_bytes(pkts[i][73..77].try_into().unwrap());
                         let _ = rtx.try_send(ShredArrival {
-                            source: self.metrics.name,
+                            source: self.metrics.name.clone(),
                             slot,
                             idx,
                             recv_ns: ts,
+                            recovered: false,
                         });
                     }
                 }
@@ -446,7 +882,7 @@ impl ShredReceiver {
                 if let Some(ref ctx) = self.capture_tx {
                     let _ = ctx.try_send(CaptureEvent {
                         ts_ns: ts,
-                        feed: self.metrics.name,
+                        feed: self.metrics.name.clone(),
                         dst_ip: self.dst_ip,
                         dst_port: self.dst_port,
                         payload: pkts[i][..len].to_vec(),
@@ -456,11 +892,13 @@ impl ShredReceiver {
                 self.metrics.shreds_received.fetch_add(1, Relaxed);
                 self.metrics.bytes_received.fetch_add(len as u64, Relaxed);
 
-                if self.tx.try_send(RawShred {
-                    data: pkts[i][..len].to_vec(),
-                    recv_timestamp_ns: ts,
-                }).is_err() {
-                    self.metrics.shreds_dropped.fetch_add(1, Relaxed);
+                let shred = RawShred { data: pkts[i][..len].to_vec(), recv_timestamp_ns: ts };
+                match self.tx.try_send(shred) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(_)) => {
+                        self.metrics.shreds_dropped.fetch_add(1, Relaxed);
+                    }
+                    Err(TrySendError::Disconnected(_)) => anyhow::bail!("the decoder has stopped"),
                 }
             }
         }
@@ -471,24 +909,40 @@ impl ShredReceiver {
     fn run_fallback(&mut self) -> Result<()> {
         let mut buf = vec![0u8; PKT_CAP];
         loop {
+            if self.stopped() {
+                return Ok(());
+            }
             let buf_uninit: &mut [std::mem::MaybeUninit<u8>] = unsafe {
                 std::slice::from_raw_parts_mut(buf.as_mut_ptr() as _, buf.len())
             };
-            let n = self.socket.recv(buf_uninit)?;
-            let ts = metrics::now_ns();
-            if n == 0 { continue; }
+            let (n, from) = match self.socket.recv_from(buf_uninit) {
+                Ok(received) => received,
+                // The read timeout set by `with_stop`.
+                Err(e) if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let ts = MonotonicNs::now();
+            if n == 0 || self.metrics.is_paused() { continue; }
 
             // DoubleZero heartbeat check.
             if n >= 4 && buf[0] == 0x44 && buf[1] == 0x5A && buf[2] == 0x00 && buf[3] == 0x01 {
-                self.metrics.last_heartbeat_ns.store(ts, Relaxed);
+                self.metrics.last_heartbeat_ns.store(ts.0, Relaxed);
                 continue;
             }
 
-            // Minimum length + variant validation.
-            if n < 89 {
-                self.metrics.shreds_invalid.fetch_add(1, Relaxed);
+            // Non-shred pre-filter, then variant validation.
+            if !is_shred_shaped(&buf[..n]) || !self.slot_gate.admits(shred_slot(&buf), ts) {
+                self.metrics.non_shred_packets.fetch_add(1, Relaxed);
                 continue;
             }
+            self.metrics.shred_mix.record_variant(&buf[..n]);
+            if let Some(from) = from.as_socket_ipv4() {
+                let first = self.first_seen.insert(shred_slot(&buf), shred_index(&buf));
+                self.metrics.senders.record(*from.ip(), first);
+            }
             let variant = buf[64];
             let is_data = variant == 0xa5 || matches!(variant & 0xF0, 0x80 | 0x90 | 0xa0 | 0xb0);
             let is_code = matches!(variant & 0xF0, 0x40 | 0x50 | 0x60 | 0x70) && variant != 0x5a;
@@ -500,9 +954,20 @@ impl ShredReceiver {
             if let Some(ver) = self.shred_version {
                 if n >= 79 {
                     let v = u16::from_le_bytes([buf[77], buf[78]]);
-                    if v != ver { continue; }
+                    if v != ver {
+                        self.metrics.shred_mix.record_filtered_version();
+                        continue;
+                    }
                 }
             }
+            self.metrics.shred_mix.record_version(&buf[..n]);
+            self.slot_gate.accept(shred_slot(&buf), ts);
+
+            if self.metrics.is_standby() {
+                self.metrics.shreds_received.fetch_add(1, Relaxed);
+                self.metrics.bytes_received.fetch_add(n as u64, Relaxed);
+                continue;
+            }
 
             // Shred race: parse (slot, shred_index) from the shred header.
             if n >= 77 {
@@ -510,10 +975,11 @@ impl ShredReceiver {
                     let slot = u64::from_le_bytes(buf[65..73].try_into().unwrap());
                     let idx = u32::from_le_bytes(buf[73..77].try_into().unwrap());
                     let _ = rtx.try_send(ShredArrival {
-                        source: self.metrics.name,
+                        source: self.metrics.name.clone(),
                         slot,
                         idx,
                         recv_ns: ts,
+                        recovered: false,
                     });
                 }
             }
@@ -522,7 +988,7 @@ impl ShredReceiver {
             if let Some(ref ctx) = self.capture_tx {
                 let _ = ctx.try_send(CaptureEvent {
                     ts_ns: ts,
-                    feed: self.metrics.name,
+                    feed: self.metrics.name.clone(),
                     dst_ip: self.dst_ip,
                     dst_port: self.dst_port,
                     payload: buf[..n].to_vec(),
@@ -531,11 +997,13 @@ impl ShredReceiver {
 
             self.metrics.shreds_received.fetch_add(1, Relaxed);
             self.metrics.bytes_received.fetch_add(n as u64, Relaxed);
-            if self.tx.try_send(RawShred {
-                data: buf[..n].to_vec(),
-                recv_timestamp_ns: ts,
-            }).is_err() {
-                self.metrics.shreds_dropped.fetch_add(1, Relaxed);
+            let shred = RawShred { data: buf[..n].to_vec(), recv_timestamp_ns: ts };
+            match self.tx.try_send(shred) {
+                Ok(()) => self.tx.flush(),
+                Err(TrySendError::Full(_)) => {
+                    self.metrics.shreds_dropped.fetch_add(1, Relaxed);
+                }
+                Err(TrySendError::Disconnected(_)) => anyhow::bail!("the decoder has stopped"),
             }
         }
     }
@@ -579,37 +1047,57 @@ impl ShredReceiver {
     }
 }
 
-/// Sample CLOCK_REALTIME − CLOCK_MONOTONIC_RAW once at startup.
-///
-/// SO_TIMESTAMPNS delivers CLOCK_REALTIME timestamps. Subtracting this offset
-/// converts them into the CLOCK_MONOTONIC_RAW frame used by `metrics::now_ns()`.
-/// The offset is stable over the service lifetime (NTP slew is negligible vs
-/// our ~300 ms lead times). We take the minimum of 8 paired samples to reduce
-/// the effect of scheduler preemption between the two `clock_gettime` calls.
-fn sample_rt_to_mono_offset_ns() -> u64 {
-    #[cfg(target_os = "linux")]
-    {
-        let read_rt = || unsafe {
-            let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
-            libc::clock_gettime(libc::CLOCK_REALTIME, &mut ts);
-            ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
-        };
-        let read_mono = || unsafe {
-            let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
-            libc::clock_gettime(libc::CLOCK_MONOTONIC_RAW, &mut ts);
-            ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
-        };
-        // Read RT then MONO in tight succession; take min over 8 rounds to
-        // minimise preemption-induced inflation of the difference.
-        (0..8)
-            .map(|_| read_rt().saturating_sub(read_mono()))
-            .min()
-            .unwrap_or(0)
-    }
-    #[cfg(not(target_os = "linux"))]
-    {
-        0
+/// Classic BPF program for `SO_ATTACH_REUSEPORT_CBPF`: return
+/// `payload[73] % sockets` — the low byte of the little-endian shred index —
+/// as the socket index within the reuseport group. For reuseport programs the
+/// packet data starts at the UDP payload. Packets too short to contain the
+/// byte make the load fail, which returns 0 (first socket).
+#[cfg(target_os = "linux")]
+fn reuseport_index_filter(sockets: usize) -> [libc::sock_filter; 3] {
+    const BPF_LD: u16 = 0x00;
+    const BPF_B: u16 = 0x10;
+    const BPF_ABS: u16 = 0x20;
+    const BPF_ALU: u16 = 0x04;
+    const BPF_MOD: u16 = 0x90;
+    const BPF_K: u16 = 0x00;
+    const BPF_RET: u16 = 0x06;
+    const BPF_A: u16 = 0x10;
+    let ins = |code: u16, k: u32| libc::sock_filter { code, jt: 0, jf: 0, k };
+    [
+        ins(BPF_LD | BPF_B | BPF_ABS, 73),
+        ins(BPF_ALU | BPF_MOD | BPF_K, sockets as u32),
+        ins(BPF_RET | BPF_A, 0),
+    ]
+}
+
+#[cfg(target_os = "linux")]
+fn attach_reuseport_index_filter(socket: &Socket, sockets: usize) -> Result<()> {
+    use std::os::unix::io::AsRawFd;
+    // SO_ATTACH_REUSEPORT_CBPF from <asm-generic/socket.h>.
+    const SO_ATTACH_REUSEPORT_CBPF: libc::c_int = 51;
+    let mut filter = reuseport_index_filter(sockets);
+    let prog = libc::sock_fprog { len: filter.len() as u16, filter: filter.as_mut_ptr() };
+    let rc = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            SO_ATTACH_REUSEPORT_CBPF,
+            &prog as *const _ as _,
+            std::mem::size_of::<libc::sock_fprog>() as _,
+        )
+    };
+    if rc != 0 {
+        anyhow::bail!(
+            "SO_ATTACH_REUSEPORT_CBPF failed: {}",
+            std::io::Error::last_os_error()
+        );
     }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn attach_reuseport_index_filter(_socket: &Socket, _sockets: usize) -> Result<()> {
+    anyhow::bail!("multi-socket receive requires Linux")
 }
 
 /// Extract the kernel receive timestamp from a recvmmsg control message.
@@ -635,3 +1123,125 @@ fn kernel_ts(hdr: &libc::msghdr) -> Option<u64> {
     }
     None
 }
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::net::UdpSocket;
+    use std::time::Duration;
+
+    #[test]
+    fn test_unicast_group_fans_out_by_shred_index() {
+        let probe = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = probe.local_addr().unwrap().port();
+        drop(probe);
+
+        let (tx, _rx) = crossbeam_channel::bounded(16);
+        let metrics = SourceMetrics::new("test".into(), false);
+        let group =
+            ShredReceiver::new_unicast_group("127.0.0.1", port, 2, tx, metrics, None, None, None)
+                .unwrap();
+
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        for idx in [0u8, 1, 2, 3] {
+            let mut pkt = [0u8; 100];
+            pkt[73] = idx;
+            sender.send_to(&pkt, ("127.0.0.1", port)).unwrap();
+        }
+
+        for (i, r) in group.iter().enumerate() {
+            let sock: UdpSocket = r.socket.try_clone().unwrap().into();
+            sock.set_read_timeout(Some(Duration::from_millis(200))).unwrap();
+            let mut buf = [0u8; 128];
+            for _ in 0..2 {
+                let (n, _) = sock.recv_from(&mut buf).unwrap();
+                assert_eq!(n, 100);
+                assert_eq!(buf[73] as usize % 2, i);
+            }
+        }
+    }
+
+    #[test]
+    fn test_prefilter_rejects_non_shred_packets() {
+        let mut pkt = [0u8; 100];
+        for (variant, shaped) in [(0x96, true), (0x46, true), (0xa5, true), (0x5a, true),
+                                  (0x00, false), (0x3f, false), (0xc6, false), (0xff, false)] {
+            pkt[64] = variant;
+            assert_eq!(is_shred_shaped(&pkt), shaped, "variant {:#04x}", variant);
+        }
+        pkt[64] = 0x96;
+        assert!(!is_shred_shaped(&pkt[..88]));
+
+        let mut gate = SlotGate::default();
+        assert!(gate.admits(u64::MAX, MonotonicNs(0)), "no reference slot yet");
+        // A junk first packet does not seed the gate; agreeing ones do.
+        gate.accept(u64::MAX, MonotonicNs(0));
+        for _ in 0..SLOT_GATE_SEED_PACKETS - 1 {
+            gate.accept(300_000_000, MonotonicNs(1_000_000_000));
+        }
+        assert!(gate.admits(u64::MAX, MonotonicNs(1_000_000_000)), "not seeded yet");
+        gate.accept(300_000_000, MonotonicNs(1_000_000_000));
+        assert!(!gate.admits(u64::MAX, MonotonicNs(u64::MAX)), "no overflow");
+        assert!(gate.admits(300_000_000 + FUTURE_SLOT_MARGIN, MonotonicNs(1_000_000_000)));
+        assert!(!gate.admits(300_000_000 + FUTURE_SLOT_MARGIN + 1, MonotonicNs(1_000_000_000)));
+        assert!(!gate.admits(u64::MAX / 2, MonotonicNs(1_000_000_000)));
+        // An hour later the feed may legitimately be ~9000 slots ahead.
+        assert!(gate.admits(300_009_000, MonotonicNs(1_000_000_000 + 3_600_000_000_000)));
+        assert!(gate.admits(1, MonotonicNs(1_000_000_000)), "past slots are not filtered");
+    }
+
+    #[test]
+    fn test_socket_stats_report_queue_and_drops() {
+        use std::os::unix::io::AsRawFd;
+
+        let (tx, _rx) = crossbeam_channel::bounded(16);
+        let metrics = SourceMetrics::new("test".into(), false);
+        let mut r =
+            ShredReceiver::new_generic_unicast("127.0.0.1", 0, tx, metrics.clone(), None, None, None)
+                .unwrap();
+        // Shrink the buffer (the kernel enforces a small minimum) so it overflows.
+        r.socket.set_recv_buffer_size(4096).unwrap();
+        let port = r.socket.local_addr().unwrap().as_socket().unwrap().port();
+
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        for _ in 0..64 {
+            sender.send_to(&[0u8; 1000], ("127.0.0.1", port)).unwrap();
+        }
+
+        r.sample_socket_stats(r.socket.as_raw_fd());
+        let snap = metrics.snapshot();
+        assert!(snap.socket_rcvbuf_bytes > 0);
+        assert!(snap.socket_rmem_hwm_bytes > 0);
+        assert!(snap.socket_drops > 0);
+    }
+
+    #[test]
+    fn test_tuning_is_validated_and_applied() {
+        use std::os::unix::io::AsRawFd;
+
+        assert!(SocketTuning::default().validate().is_ok());
+        assert!(SocketTuning { batch: 0, ..Default::default() }.validate().is_err());
+        assert!(SocketTuning { batch: MAX_RECV_BATCH + 1, ..Default::default() }
+            .validate()
+            .is_err());
+        assert!(SocketTuning { recv_buf_bytes: 2 << 30, ..Default::default() }
+            .validate()
+            .is_err());
+
+        let (tx, _rx) = crossbeam_channel::bounded(16);
+        let metrics = SourceMetrics::new("test".into(), false);
+        // Small enough for SO_RCVBUF to grant without privileges.
+        let tuning = SocketTuning { recv_buf_bytes: 8192, ..Default::default() };
+        let r = ShredReceiver::new_generic_unicast("127.0.0.1", 0, tx, metrics, None, None, None)
+            .unwrap()
+            .with_tuning(tuning);
+        r.apply_tuning(r.socket.as_raw_fd());
+        // The kernel doubles the request for bookkeeping overhead.
+        let granted = r.socket.recv_buffer_size().unwrap();
+        assert!((8192..=65536).contains(&granted), "granted {}", granted);
+    }
+}
@@ -0,0 +1,331 @@
+//! Repair requests for slots stuck just short of complete.
+//!
+//! A lossy feed often ends a slot a few data shreds short: too many were lost
+//! in one FEC set to recover them, so the slot never decodes in full. With a
+//! [`RepairClient`] set, the decoder asks repair peers (validators' serve-repair
+//! ports) for the missing indices of any slot whose last shred has arrived but
+//! which has made no progress for `stuck_ms`. Responses go back into the same
+//! decoder and are counted in `shreds_repaired`, never in `shreds_received`,
+//! and they take no part in the shred race.
+//!
+//! Requests are Agave `WindowIndex` repair requests, signed with the
+//! configured identity and tagged with a random nonce that the response
+//! echoes back. A response is only taken from the peer the request went to.
+//! A peer answers an identity it has not seen with a ping first. The client
+//! replies with a pong, and later requests are served; pings are only
+//! answered when signed by a configured peer that has a request outstanding.
+
+use anyhow::{Context, Result};
+use crossbeam_channel::Sender;
+use solana_keypair::Keypair;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_signer::Signer;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::clock::MonotonicNs;
+use crate::metrics;
+use crate::receiver::RawShred;
+
+/// `RepairProtocol::WindowIndex` discriminant.
+const WINDOW_INDEX_TAG: u32 = 8;
+/// `RepairProtocol::Pong` discriminant.
+const PONG_TAG: u32 = 7;
+/// A serialized `RepairResponse::Ping`: tag, sender, token, signature.
+const PING_SIZE: usize = 4 + 32 + 32 + 64;
+const PING_PONG_HASH_PREFIX: &[u8] = b"SOLANA_PING_PONG";
+/// Requests not answered within this long are forgotten.
+const PENDING_TTL_NS: u64 = 5_000_000_000;
+const PENDING_CAP: usize = 8192;
+
+/// A validator's serve-repair endpoint. The identity is part of each signed
+/// request; the peer drops requests addressed to anyone else.
+#[derive(Debug, Clone)]
+pub struct RepairPeer {
+    pub pubkey: Pubkey,
+    pub addr: SocketAddr,
+}
+
+impl FromStr for RepairPeer {
+    type Err = anyhow::Error;
+
+    /// `<identity pubkey>@<host>:<port>`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (pubkey, addr) = s
+            .split_once('@')
+            .with_context(|| format!("repair peer '{}' is not <pubkey>@<host>:<port>", s))?;
+        let pubkey = pubkey
+            .parse::<Pubkey>()
+            .map_err(|_| anyhow::anyhow!("repair peer '{}': invalid identity pubkey", s))?;
+        let addr = addr
+            .to_socket_addrs()
+            .with_context(|| format!("repair peer '{}': invalid address", s))?
+            .next()
+            .with_context(|| format!("repair peer '{}': address did not resolve", s))?;
+        Ok(Self { pubkey, addr })
+    }
+}
+
+/// When and how much the decoder asks for.
+#[derive(Debug, Clone)]
+pub struct RepairSettings {
+    pub peers: Vec<RepairPeer>,
+    /// A slot must have made no progress for this long before it is repaired.
+    pub stuck_ms: u64,
+    /// Slots missing more data shreds than this are left alone.
+    pub max_missing: u32,
+    /// Requests per slot before giving up on it.
+    pub max_attempts: u8,
+}
+
+/// Shared repair socket; cheap to clone, one per probe.
+#[derive(Clone)]
+pub struct RepairClient {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    socket: UdpSocket,
+    keypair: Keypair,
+    settings: RepairSettings,
+    next_peer: AtomicUsize,
+    /// Source of request nonces, which must not be guessable.
+    urandom: File,
+    pending: Mutex<HashMap<u32, Pending>>,
+}
+
+struct Pending {
+    reply: Sender<RawShred>,
+    /// Where the request went; replies from anywhere else are dropped.
+    peer: SocketAddr,
+    sent_ns: u64,
+}
+
+impl RepairClient {
+    /// Bind the repair socket on `port` (0 for any) and start the thread that
+    /// routes responses back to the decoders that asked.
+    pub fn start(settings: RepairSettings, keypair: Keypair, port: u16) -> Result<Self> {
+        anyhow::ensure!(!settings.peers.is_empty(), "repair needs at least one peer");
+        let socket = UdpSocket::bind(("0.0.0.0", port))
+            .with_context(|| format!("failed to bind repair socket on port {}", port))?;
+        let inner = Arc::new(Inner {
+            socket,
+            keypair,
+            settings,
+            next_peer: AtomicUsize::new(0),
+            urandom: File::open("/dev/urandom").context("failed to open /dev/urandom")?,
+            pending: Mutex::new(HashMap::new()),
+        });
+        let recv = inner.clone();
+        std::thread::Builder::new()
+            .name("repair-recv".into())
+            .spawn(move || recv.run())
+            .context("failed to spawn repair thread")?;
+        Ok(Self { inner })
+    }
+
+    pub(crate) fn settings(&self) -> &RepairSettings {
+        &self.inner.settings
+    }
+
+    /// Ask the next peer in turn for data shred `idx` of `slot`; the shred is
+    /// sent to `reply` when it arrives.
+    pub(crate) fn request(&self, slot: u64, idx: u32, reply: &Sender<RawShred>) -> Result<()> {
+        let inner = &self.inner;
+        let peers = &inner.settings.peers;
+        let peer = &peers[inner.next_peer.fetch_add(1, Relaxed) % peers.len()];
+        let now_ns = metrics::now_ns();
+        let nonce = {
+            let mut pending = inner.pending.lock().unwrap();
+            if pending.len() >= PENDING_CAP {
+                pending.retain(|_, p| now_ns.saturating_sub(p.sent_ns) < PENDING_TTL_NS);
+            }
+            let mut nonce = inner.random_nonce()?;
+            while pending.contains_key(&nonce) {
+                nonce = inner.random_nonce()?;
+            }
+            let p = Pending { reply: reply.clone(), peer: peer.addr, sent_ns: now_ns };
+            pending.insert(nonce, p);
+            nonce
+        };
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let packet =
+            window_index_request(&inner.keypair, &peer.pubkey, timestamp_ms, nonce, slot, idx);
+        inner.socket.send_to(&packet, peer.addr)?;
+        Ok(())
+    }
+}
+
+impl Inner {
+    fn run(&self) {
+        let mut buf = [0u8; 2048];
+        loop {
+            let (n, from) = match self.socket.recv_from(&mut buf) {
+                Ok(r) => r,
+                Err(e) => {
+                    tracing::warn!("repair socket receive failed: {}", e);
+                    continue;
+                }
+            };
+            let packet = &buf[..n];
+            if n == PING_SIZE && packet[..4] == [0; 4] {
+                if let Some(token) = self.verified_ping(packet, from) {
+                    let _ = self.socket.send_to(&pong(&self.keypair, &token), from);
+                }
+                continue;
+            }
+            let Some(split) = n.checked_sub(4).filter(|&s| s > 0) else {
+                continue;
+            };
+            let nonce = u32::from_le_bytes(packet[split..].try_into().unwrap());
+            let p = {
+                let mut pending = self.pending.lock().unwrap();
+                match pending.get(&nonce) {
+                    Some(p) if p.peer == from => pending.remove(&nonce).unwrap(),
+                    _ => continue,
+                }
+            };
+            let _ = p.reply.try_send(RawShred {
+                data: packet[..split].to_vec(),
+                recv_timestamp_ns: MonotonicNs::now(),
+            });
+        }
+    }
+
+    fn random_nonce(&self) -> Result<u32> {
+        let mut buf = [0u8; 4];
+        (&self.urandom).read_exact(&mut buf).context("failed to read /dev/urandom")?;
+        Ok(u32::from_ne_bytes(buf))
+    }
+
+    /// The token of a ping from `from`, if a configured peer at that address
+    /// signed it and has a request of ours outstanding. Anything else is left
+    /// unanswered, so the socket never reflects signed pongs to spoofed
+    /// sources.
+    fn verified_ping(&self, packet: &[u8], from: SocketAddr) -> Option<[u8; 32]> {
+        let sender: [u8; 32] = packet[4..36].try_into().unwrap();
+        let token: [u8; 32] = packet[36..68].try_into().unwrap();
+        let signature: [u8; 64] = packet[68..PING_SIZE].try_into().unwrap();
+        let peer = self.settings.peers.iter().find(|p| p.addr == from)?;
+        if peer.pubkey.to_bytes() != sender
+            || !self.pending.lock().unwrap().values().any(|p| p.peer == from)
+            || !Signature::from(signature).verify(&sender, &token)
+        {
+            return None;
+        }
+        Some(token)
+    }
+}
+
+/// A signed `RepairProtocol::WindowIndex` request, as bincode lays it out:
+/// tag, then the header (signature, sender, recipient, timestamp, nonce),
+/// then slot and shred index. The signature covers every other byte.
+fn window_index_request(
+    keypair: &Keypair,
+    recipient: &Pubkey,
+    timestamp_ms: u64,
+    nonce: u32,
+    slot: u64,
+    idx: u32,
+) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(160);
+    packet.extend_from_slice(&WINDOW_INDEX_TAG.to_le_bytes());
+    packet.extend_from_slice(&[0u8; 64]);
+    packet.extend_from_slice(keypair.pubkey().as_ref());
+    packet.extend_from_slice(recipient.as_ref());
+    packet.extend_from_slice(&timestamp_ms.to_le_bytes());
+    packet.extend_from_slice(&nonce.to_le_bytes());
+    packet.extend_from_slice(&slot.to_le_bytes());
+    packet.extend_from_slice(&(idx as u64).to_le_bytes());
+    let signable = [&packet[..4], &packet[68..]].concat();
+    let signature = keypair.sign_message(&signable);
+    packet[4..68].copy_from_slice(signature.as_ref());
+    packet
+}
+
+/// `RepairProtocol::Pong` answering a ping with `token`.
+fn pong(keypair: &Keypair, token: &[u8; 32]) -> Vec<u8> {
+    let hash = solana_sha256_hasher::hashv(&[PING_PONG_HASH_PREFIX, token]).to_bytes();
+    let mut packet = Vec::with_capacity(PING_SIZE);
+    packet.extend_from_slice(&PONG_TAG.to_le_bytes());
+    packet.extend_from_slice(keypair.pubkey().as_ref());
+    packet.extend_from_slice(&hash);
+    packet.extend_from_slice(keypair.sign_message(&hash).as_ref());
+    packet
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_index_request_layout() {
+        let keypair = Keypair::new();
+        let recipient = Pubkey::new_from_array([7; 32]);
+        let packet = window_index_request(&keypair, &recipient, 1_700_000_000_000, 42, 300, 17);
+        assert_eq!(packet.len(), 160);
+        assert_eq!(packet[..4], WINDOW_INDEX_TAG.to_le_bytes());
+        assert_eq!(packet[68..100], keypair.pubkey().to_bytes());
+        assert_eq!(packet[100..132], [7; 32]);
+        assert_eq!(packet[132..140], 1_700_000_000_000u64.to_le_bytes());
+        assert_eq!(packet[140..144], 42u32.to_le_bytes());
+        assert_eq!(packet[144..152], 300u64.to_le_bytes());
+        assert_eq!(packet[152..160], 17u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_ping_only_answered_for_outstanding_peer() {
+        let peer_key = Keypair::new();
+        let addr: SocketAddr = "127.0.0.1:8008".parse().unwrap();
+        let settings = RepairSettings {
+            peers: vec![RepairPeer { pubkey: peer_key.pubkey(), addr }],
+            stuck_ms: 50,
+            max_missing: 8,
+            max_attempts: 3,
+        };
+        let client = RepairClient::start(settings, Keypair::new(), 0).unwrap();
+        let token = [9u8; 32];
+        let ping = |keypair: &Keypair| {
+            let mut packet = vec![0u8; 4];
+            packet.extend_from_slice(keypair.pubkey().as_ref());
+            packet.extend_from_slice(&token);
+            packet.extend_from_slice(keypair.sign_message(&token).as_ref());
+            packet
+        };
+        let inner = &client.inner;
+        assert_eq!(inner.verified_ping(&ping(&peer_key), addr), None, "nothing requested yet");
+
+        let (tx, _rx) = crossbeam_channel::unbounded();
+        client.request(300, 17, &tx).unwrap();
+        assert_eq!(inner.verified_ping(&ping(&peer_key), addr), Some(token));
+        let spoofed: SocketAddr = "127.0.0.1:9009".parse().unwrap();
+        assert_eq!(inner.verified_ping(&ping(&peer_key), spoofed), None);
+        assert_eq!(inner.verified_ping(&ping(&Keypair::new()), addr), None);
+        let mut forged = ping(&peer_key);
+        forged[PING_SIZE - 1] ^= 1;
+        assert_eq!(inner.verified_ping(&forged, addr), None);
+    }
+
+    #[test]
+    fn test_parse_peer() {
+        let key = Pubkey::new_from_array([1; 32]);
+        let peer: RepairPeer = format!("{}@127.0.0.1:8008", key).parse().unwrap();
+        assert_eq!(peer.pubkey, key);
+        assert_eq!(peer.addr, "127.0.0.1:8008".parse().unwrap());
+        assert!("127.0.0.1:8008".parse::<RepairPeer>().is_err());
+    }
+}
@@ -1,126 +1,365 @@
-//! RPC block-polling transaction source.
+//! RPC block transaction source.
+//!
+//! Polls confirmed blocks via the Solana JSON-RPC API every 100ms, or, with
+//! [`RpcSource::with_ws_url`] set, receives them pushed over a `blockSubscribe`
+//! websocket subscription, which removes the polling jitter and the per-slot
+//! `getBlock` round trip. Slots the subscription skips over are fetched by
+//! polling, and if the subscription fails the source polls until it can
+//! resubscribe. Either way a block's transactions are timestamped when the
+//! block arrives, so lead times stay comparable.
+//!
+//! Slots missed while polling or subscribed are fetched with up to
+//! [`RpcSource::with_parallelism`] `getBlock` requests in flight, so a source
+//! that fell behind after a stall can catch up; blocks are still emitted in
+//! slot order.
 //!
-//! Polls confirmed blocks via the Solana JSON-RPC API every 100ms.
 //! Slower than shred ingestion (~400ms+ behind), but works without a multicast feed.
 //! Used as the baseline comparison source for lead-time measurement.
+//!
+//! It also resolves every slot against the cluster: with
+//! [`RpcSource::with_confirmations`] set, each confirmed block's transaction
+//! count, and each skipped slot, is reported to the shred-tier sources so
+//! their slot outcomes can be checked against what was actually confirmed.
 
 use anyhow::Result;
-use crossbeam_channel::Sender;
+use crossbeam_channel::{RecvTimeoutError, Sender};
+use solana_client::pubsub_client::PubsubClient;
 use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcBlockSubscribeConfig, RpcBlockSubscribeFilter};
 use solana_commitment_config::CommitmentConfig;
+use solana_transaction_status::{
+    EncodedTransactionWithStatusMeta, TransactionDetails, UiConfirmedBlock, UiTransactionEncoding,
+};
+use std::collections::HashSet;
 use std::sync::atomic::Ordering::Relaxed;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use crate::clock::MonotonicNs;
+use crate::cluster_tip::ClusterTip;
 use crate::decoder::DecodedTx;
+use crate::fan_in::send_batch;
 use crate::metrics;
-use crate::source_metrics::SourceMetrics;
+use crate::source_metrics::{Confirmation, SlotConfirmations, SourceErrorKind, SourceMetrics};
+
+/// A subscription that delivers nothing for this long is considered dead.
+const WS_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
 
-/// Polls confirmed blocks via RPC and emits transactions.
+/// After a subscription fails, poll for this long before resubscribing.
+const WS_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Default cap on concurrent `getBlock` requests while catching up.
+pub const DEFAULT_PARALLELISM: usize = 4;
+
+/// Polls (or subscribes to) confirmed blocks via RPC and emits transactions.
 pub struct RpcSource {
     rpc: RpcClient,
-    tx: Sender<DecodedTx>,
+    tx: Sender<Vec<DecodedTx>>,
     last_slot: u64,
     metrics: Arc<SourceMetrics>,
+    confirmations: Option<Arc<SlotConfirmations>>,
+    cluster_tip: Option<Arc<ClusterTip>>,
+    ws_url: Option<String>,
+    parallelism: usize,
 }
 
 impl RpcSource {
-    pub fn new(rpc_url: &str, tx: Sender<DecodedTx>, metrics: Arc<SourceMetrics>) -> Result<Self> {
+    pub fn new(
+        rpc_url: &str,
+        tx: Sender<Vec<DecodedTx>>,
+        metrics: Arc<SourceMetrics>,
+    ) -> Result<Self> {
         let rpc = RpcClient::new_with_commitment(
             rpc_url.to_string(),
             CommitmentConfig::confirmed(),
         );
         let last_slot = rpc.get_slot()?;
         tracing::info!("RPC source starting at slot {}", last_slot);
-        Ok(Self { rpc, tx, last_slot, metrics })
+        Ok(Self {
+            rpc,
+            tx,
+            last_slot,
+            metrics,
+            confirmations: None,
+            cluster_tip: None,
+            ws_url: None,
+            parallelism: DEFAULT_PARALLELISM,
+        })
+    }
+
+    /// Report each resolved slot to the shred-tier sources.
+    pub fn with_confirmations(mut self, confirmations: Option<Arc<SlotConfirmations>>) -> Self {
+        self.confirmations = confirmations;
+        self
     }
 
-    /// Main polling loop — runs on its own thread
+    /// Report each confirmed slot as the baseline's tip, for decoders that
+    /// expire slots against it (see [`crate::coverage::SlotExpiry`]).
+    pub fn with_cluster_tip(mut self, cluster_tip: Option<Arc<ClusterTip>>) -> Self {
+        self.cluster_tip = cluster_tip;
+        self
+    }
+
+    /// Receive blocks from a `blockSubscribe` websocket instead of polling.
+    pub fn with_ws_url(mut self, ws_url: Option<String>) -> Self {
+        self.ws_url = ws_url;
+        self
+    }
+
+    /// Most `getBlock` requests in flight while catching up (at least 1).
+    /// One slot behind is fetched alone; a backlog is fetched in windows of
+    /// up to this many slots.
+    pub fn with_parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Main loop — runs on its own thread
     pub fn run(&mut self) -> Result<()> {
-        tracing::info!("RPC transaction source started (polling mode)");
+        let Some(ws_url) = self.ws_url.clone() else {
+            tracing::info!("RPC transaction source started (polling mode)");
+            loop {
+                self.poll_once();
+            }
+        };
+        tracing::info!("RPC transaction source started (blockSubscribe mode)");
+        let mut failing = false;
         loop {
-            match self.poll_new_slots() {
-                Ok(count) => {
-                    if count > 0 {
-                        tracing::debug!("processed {} transactions from RPC", count);
-                    }
+            if let Err(e) = self.run_subscription(&ws_url, &mut failing) {
+                let error = format!("blockSubscribe: {:#}", e);
+                self.metrics.record_error(SourceErrorKind::Rpc, error);
+                if !failing {
+                    failing = true;
+                    tracing::warn!(
+                        "blockSubscribe on {} failed: {:#}; polling for {}s",
+                        ws_url,
+                        e,
+                        WS_RETRY_AFTER.as_secs()
+                    );
+                } else {
+                    tracing::debug!("blockSubscribe on {} failed: {:#}", ws_url, e);
                 }
-                Err(e) => {
-                    tracing::warn!("RPC poll error: {}, retrying...", e);
-                    std::thread::sleep(Duration::from_millis(500));
+            }
+            let until = Instant::now() + WS_RETRY_AFTER;
+            while Instant::now() < until {
+                self.poll_once();
+            }
+        }
+    }
+
+    fn poll_once(&mut self) {
+        match self.poll_new_slots() {
+            Ok(count) => {
+                if count > 0 {
+                    tracing::debug!("processed {} transactions from RPC", count);
+                }
+            }
+            Err(e) => {
+                tracing::warn!("RPC poll error: {}, retrying...", e);
+                self.metrics.record_error(SourceErrorKind::Rpc, format!("poll: {}", e));
+                std::thread::sleep(Duration::from_millis(500));
+            }
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    /// Process pushed blocks until the subscription fails; only returns an
+    /// error. A gap between the previous block and the next one is filled by
+    /// polling, which also reports the skipped slots in it.
+    fn run_subscription(&mut self, ws_url: &str, failing: &mut bool) -> Result<()> {
+        let config = RpcBlockSubscribeConfig {
+            commitment: Some(CommitmentConfig::confirmed()),
+            encoding: Some(UiTransactionEncoding::Base64),
+            transaction_details: Some(TransactionDetails::Full),
+            show_rewards: Some(false),
+            max_supported_transaction_version: Some(0),
+        };
+        let (_subscription, updates) =
+            PubsubClient::block_subscribe(ws_url, RpcBlockSubscribeFilter::All, Some(config))?;
+        if std::mem::take(failing) {
+            tracing::warn!("blockSubscribe on {} restored", ws_url);
+        }
+        loop {
+            let update = match updates.recv_timeout(WS_IDLE_TIMEOUT) {
+                Ok(update) => update.value,
+                Err(RecvTimeoutError::Timeout) => {
+                    anyhow::bail!("no block for {}s", WS_IDLE_TIMEOUT.as_secs())
+                }
+                Err(RecvTimeoutError::Disconnected) => anyhow::bail!("subscription closed"),
+            };
+            let recv_ts = MonotonicNs::now();
+            let slot = update.slot;
+            if slot <= self.last_slot {
+                continue;
+            }
+            if let Some(ref tip) = self.cluster_tip {
+                tip.observe_baseline(slot);
+            }
+            if slot > self.last_slot + 1 {
+                if let Err(e) = self.catch_up(slot - 1) {
+                    tracing::debug!("filling slots before {} failed: {}", slot, e);
+                }
+            }
+            match update.block {
+                Some(block) => {
+                    self.metrics.slots_attempted.fetch_add(1, Relaxed);
+                    let count = self.process_block(slot, block.transactions, recv_ts);
+                    tracing::trace!("slot {}: {} transactions pushed", slot, count);
+                    self.last_slot = slot;
+                }
+                // The node could not attach the block; fetch it instead.
+                None => {
+                    tracing::trace!("slot {} pushed without a block: {:?}", slot, update.err);
+                    if let Err(e) = self.catch_up(slot) {
+                        tracing::debug!("fetching slot {} failed: {}", slot, e);
+                    }
                 }
             }
-            std::thread::sleep(Duration::from_millis(100));
         }
     }
 
     fn poll_new_slots(&mut self) -> Result<usize> {
         let current_slot = self.rpc.get_slot()?;
+        self.catch_up(current_slot)
+    }
+
+    /// Fetch every slot after `last_slot` up to `current_slot`, a window of
+    /// slots at a time, emitting each window in slot order.
+    fn catch_up(&mut self, current_slot: u64) -> Result<usize> {
         if current_slot <= self.last_slot {
             return Ok(0);
         }
+        if let Some(ref tip) = self.cluster_tip {
+            tip.observe_baseline(current_slot);
+        }
+        self.metrics.rpc_slot_lag.store(current_slot - self.last_slot, Relaxed);
 
         let mut total_txs = 0;
 
-        for slot in (self.last_slot + 1)..=current_slot {
-            match self.process_slot(slot) {
-                Ok(count) => total_txs += count,
+        // Slots missing from the confirmed block list were skipped. Without
+        // the list every slot is fetched and nothing is reported as skipped.
+        let produced: Option<HashSet<u64>> = match self.confirmations {
+            Some(_) => match self.rpc.get_blocks(self.last_slot + 1, Some(current_slot)) {
+                Ok(blocks) => Some(blocks.into_iter().collect()),
                 Err(e) => {
-                    tracing::trace!("slot {} not available: {}", slot, e);
+                    tracing::debug!("getBlocks failed, skipped slots not reported: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        let slots: Vec<u64> = ((self.last_slot + 1)..=current_slot).collect();
+        for window in slots.chunks(self.parallelism) {
+            let wanted: Vec<u64> = window
+                .iter()
+                .copied()
+                .filter(|slot| match &produced {
+                    Some(p) => p.contains(slot),
+                    None => true,
+                })
+                .collect();
+            let mut fetched = self.fetch_blocks(&wanted).into_iter();
+            for &slot in window {
+                if !wanted.contains(&slot) {
+                    self.report(slot, Confirmation::Skipped);
+                    continue;
+                }
+                match fetched.next() {
+                    Some(Ok((block, recv_ts))) => {
+                        total_txs += self.process_block(slot, block.transactions, recv_ts);
+                    }
+                    Some(Err(e)) => tracing::trace!("slot {} not available: {}", slot, e),
+                    None => {}
                 }
             }
+            let done = *window.last().unwrap();
+            self.metrics.rpc_slot_lag.store(current_slot - done, Relaxed);
         }
 
         self.last_slot = current_slot;
         Ok(total_txs)
     }
 
-    fn process_slot(&self, slot: u64) -> Result<usize> {
+    /// Fetch `slots` concurrently, one request each; results in slot order.
+    fn fetch_blocks(&self, slots: &[u64]) -> Vec<Result<(UiConfirmedBlock, MonotonicNs)>> {
+        if let [slot] = slots {
+            return vec![self.fetch_block(*slot)];
+        }
+        std::thread::scope(|s| {
+            let handles: Vec<_> =
+                slots.iter().map(|&slot| s.spawn(move || self.fetch_block(slot))).collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or_else(|_| Err(anyhow::anyhow!("getBlock panicked"))))
+                .collect()
+        })
+    }
+
+    /// One block and the time it arrived.
+    fn fetch_block(&self, slot: u64) -> Result<(UiConfirmedBlock, MonotonicNs)> {
         self.metrics.slots_attempted.fetch_add(1, Relaxed);
 
         let block = self.rpc.get_block_with_config(
             slot,
             solana_client::rpc_config::RpcBlockConfig {
-                encoding: Some(solana_transaction_status::UiTransactionEncoding::Base64),
-                transaction_details: Some(solana_transaction_status::TransactionDetails::Full),
+                encoding: Some(UiTransactionEncoding::Base64),
+                transaction_details: Some(TransactionDetails::Full),
                 rewards: Some(false),
                 commitment: Some(CommitmentConfig::confirmed()),
                 max_supported_transaction_version: Some(0),
             },
         )?;
-        let recv_ts = metrics::now_ns();
+        Ok((block, MonotonicNs::now()))
+    }
 
+    /// Emit a confirmed block's transactions, all stamped `recv_ts`.
+    fn process_block(
+        &self,
+        slot: u64,
+        transactions: Option<Vec<EncodedTransactionWithStatusMeta>>,
+        recv_ts: MonotonicNs,
+    ) -> usize {
         let mut count = 0;
 
-        if let Some(transactions) = block.transactions {
-            for tx_with_meta in transactions {
-                if let Some(decoded) = self.decode_ui_transaction(tx_with_meta, slot, recv_ts) {
-                    let _ = self.tx.try_send(decoded);
-                    count += 1;
-                }
+        if let Some(transactions) = transactions {
+            self.report(slot, Confirmation::Confirmed(transactions.len() as u32));
+            let batch: Vec<DecodedTx> = transactions
+                .into_iter()
+                .filter_map(|tx_with_meta| self.decode_ui_transaction(tx_with_meta, slot, recv_ts))
+                .collect();
+            count = batch.len();
+            if count > 0 {
+                send_batch(&self.tx, batch, &self.metrics);
             }
         }
 
         self.metrics.slots_complete.fetch_add(1, Relaxed);
         self.metrics.txs_decoded.fetch_add(count as u64, Relaxed);
 
-        Ok(count)
+        count
+    }
+
+    fn report(&self, slot: u64, confirmation: Confirmation) {
+        if let Some(ref c) = self.confirmations {
+            c.report(slot, confirmation);
+        }
     }
 
     fn decode_ui_transaction(
         &self,
-        tx_with_meta: solana_transaction_status::EncodedTransactionWithStatusMeta,
+        tx_with_meta: EncodedTransactionWithStatusMeta,
         slot: u64,
-        recv_ts: u64,
+        recv_ts: MonotonicNs,
     ) -> Option<DecodedTx> {
-        let decode_start = metrics::now_ns();
+        let decode_start = MonotonicNs::now();
         let tx = tx_with_meta.transaction;
         match tx.decode() {
             Some(versioned_tx) => {
-                let decode_done = metrics::now_ns();
+                let decode_done = MonotonicNs::now();
                 metrics::METRICS.record_stage(
                     &metrics::METRICS.decode_ns,
-                    decode_done - decode_start,
+                    decode_done.since(decode_start),
                 );
                 Some(DecodedTx {
                     transaction: versioned_tx,
@@ -0,0 +1,171 @@
+//! Shred counts by UDP sender address.
+//!
+//! A multicast group can be fed by more than one upstream retransmitter, and
+//! every one of them delivers the same shreds. Counting packets per sender IP
+//! shows who is publishing; counting which sender delivered each
+//! `(slot, shred_index)` first shows who is actually fastest.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+/// Distinct senders tracked per source. Anything beyond this is counted as
+/// `other_packets`.
+const SENDER_SLOTS: usize = 16;
+
+/// Slots behind the newest one whose shreds are still remembered for
+/// first-arrival attribution. A copy arriving later than this counts as first.
+const FIRST_SEEN_SLOTS: u64 = 16;
+
+/// Per-source sender counters. Lock-free; safe to update from every receive
+/// thread of a fanout group.
+pub struct SenderMix {
+    /// IPv4 address + 1 claimed by each slot; 0 = free.
+    keys: [AtomicU64; SENDER_SLOTS],
+    packets: [AtomicU64; SENDER_SLOTS],
+    firsts: [AtomicU64; SENDER_SLOTS],
+    other_packets: AtomicU64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SenderSnapshot {
+    pub packets: u64,
+    /// Shreds this sender delivered before any other sender.
+    pub firsts: u64,
+    /// `firsts` as a share of all first arrivals on the source.
+    pub first_pct: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SenderMixSnapshot {
+    /// Keyed by sender IP.
+    pub senders: BTreeMap<String, SenderSnapshot>,
+    /// Packets from senders that did not fit in the tracked set.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub other_packets: u64,
+}
+
+fn is_zero(v: &u64) -> bool {
+    *v == 0
+}
+
+impl SenderMixSnapshot {
+    pub fn is_empty(&self) -> bool {
+        self.senders.is_empty() && self.other_packets == 0
+    }
+}
+
+impl Default for SenderMix {
+    fn default() -> Self {
+        Self {
+            keys: std::array::from_fn(|_| AtomicU64::new(0)),
+            packets: std::array::from_fn(|_| AtomicU64::new(0)),
+            firsts: std::array::from_fn(|_| AtomicU64::new(0)),
+            other_packets: AtomicU64::new(0),
+        }
+    }
+}
+
+impl SenderMix {
+    /// Count one packet from `sender`; `first` if no other sender delivered
+    /// the same shred before it.
+    pub fn record(&self, sender: Ipv4Addr, first: bool) {
+        let key = u32::from(sender) as u64 + 1;
+        for (i, slot) in self.keys.iter().enumerate() {
+            match slot.compare_exchange(0, key, Relaxed, Relaxed) {
+                Ok(_) => {}
+                Err(k) if k == key => {}
+                Err(_) => continue,
+            }
+            self.packets[i].fetch_add(1, Relaxed);
+            if first {
+                self.firsts[i].fetch_add(1, Relaxed);
+            }
+            return;
+        }
+        self.other_packets.fetch_add(1, Relaxed);
+    }
+
+    pub fn snapshot(&self) -> SenderMixSnapshot {
+        let mut snap = SenderMixSnapshot {
+            other_packets: self.other_packets.load(Relaxed),
+            ..Default::default()
+        };
+        for i in 0..SENDER_SLOTS {
+            let (k, packets) = (self.keys[i].load(Relaxed), self.packets[i].load(Relaxed));
+            if k == 0 || packets == 0 {
+                continue;
+            }
+            let ip = Ipv4Addr::from((k - 1) as u32);
+            let firsts = self.firsts[i].load(Relaxed);
+            snap.senders.insert(ip.to_string(), SenderSnapshot { packets, firsts, first_pct: 0.0 });
+        }
+        let total: u64 = snap.senders.values().map(|s| s.firsts).sum();
+        if total > 0 {
+            for s in snap.senders.values_mut() {
+                s.first_pct = s.firsts as f64 * 100.0 / total as f64;
+            }
+        }
+        snap
+    }
+}
+
+/// Shreds seen recently by one receive thread, to tell the first copy of a
+/// shred from later copies sent by other retransmitters.
+#[derive(Default)]
+pub(crate) struct FirstSeen {
+    seen: HashSet<(u64, u32)>,
+    highest: u64,
+}
+
+impl FirstSeen {
+    /// True if `(slot, idx)` has not been seen within the last
+    /// [`FIRST_SEEN_SLOTS`] slots.
+    pub(crate) fn insert(&mut self, slot: u64, idx: u32) -> bool {
+        if slot > self.highest {
+            self.highest = slot;
+            self.seen.retain(|&(s, _)| s.saturating_add(FIRST_SEEN_SLOTS) >= slot);
+        }
+        slot.saturating_add(FIRST_SEEN_SLOTS) < self.highest || self.seen.insert((slot, idx))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_and_first_share_by_sender() {
+        let (a, b) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2));
+        let mix = SenderMix::default();
+        let mut seen = FirstSeen::default();
+        for idx in 0..4 {
+            // a wins three of four shreds, b wins the last.
+            let order = if idx < 3 { [a, b] } else { [b, a] };
+            for sender in order {
+                mix.record(sender, seen.insert(100, idx));
+            }
+        }
+
+        let snap = mix.snapshot();
+        assert_eq!(snap.senders["10.0.0.1"].packets, 4);
+        assert_eq!(snap.senders["10.0.0.1"].firsts, 3);
+        assert_eq!(snap.senders["10.0.0.2"].firsts, 1);
+        assert_eq!(snap.senders["10.0.0.1"].first_pct, 75.0);
+    }
+
+    #[test]
+    fn test_first_seen_forgets_old_slots() {
+        let mut seen = FirstSeen::default();
+        assert!(seen.insert(10, 0));
+        assert!(!seen.insert(10, 0));
+        assert!(seen.insert(10 + FIRST_SEEN_SLOTS + 1, 0));
+        assert_eq!(seen.seen.len(), 1, "slot 10 pruned");
+        assert!(seen.insert(u64::MAX, 0), "a junk slot does not overflow");
+    }
+}
@@ -0,0 +1,201 @@
+//! Distribution of received shreds by variant byte and shred version.
+//!
+//! Variants are counted in the receive loop before any filtering, so shreds
+//! the pipeline rejects for an unknown variant still show up. When the
+//! cluster rolls out a new variant it appears here (as `unknown-0xNN`) before
+//! the decoder silently starts skipping it.
+//!
+//! Versions are counted once a shred is accepted, so stray packets that pass
+//! the pre-filter cannot claim the few tracked version slots. Shreds the
+//! `shred_version` filter drops are counted as `other_versions`.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering::Relaxed};
+
+/// Distinct shred versions tracked per source. A cluster runs one version at a
+/// time, two around a restart; anything beyond this is counted as `other`.
+const VERSION_SLOTS: usize = 8;
+
+/// Per-source variant and version counters. Lock-free; safe to update from
+/// every receive thread of a fanout group.
+pub struct ShredMix {
+    variants: [AtomicU64; 256],
+    /// Version + 1 claimed by each slot; 0 = free.
+    version_keys: [AtomicU32; VERSION_SLOTS],
+    version_counts: [AtomicU64; VERSION_SLOTS],
+    other_versions: AtomicU64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ShredMixSnapshot {
+    /// Shreds per variant family (see [`variant_name`]).
+    pub variants: BTreeMap<String, u64>,
+    pub versions: BTreeMap<u16, u64>,
+    /// Shreds dropped by the `shred_version` filter, or whose version did
+    /// not fit in the tracked set.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub other_versions: u64,
+}
+
+fn is_zero(v: &u64) -> bool {
+    *v == 0
+}
+
+impl ShredMixSnapshot {
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty() && self.versions.is_empty() && self.other_versions == 0
+    }
+}
+
+impl Default for ShredMix {
+    fn default() -> Self {
+        Self {
+            variants: std::array::from_fn(|_| AtomicU64::new(0)),
+            version_keys: std::array::from_fn(|_| AtomicU32::new(0)),
+            version_counts: std::array::from_fn(|_| AtomicU64::new(0)),
+            other_versions: AtomicU64::new(0),
+        }
+    }
+}
+
+impl ShredMix {
+    /// Count one shred's variant. `bytes` is the raw payload; packets too
+    /// short to carry a variant byte are ignored.
+    pub fn record_variant(&self, bytes: &[u8]) {
+        if let Some(&variant) = bytes.get(64) {
+            self.variants[variant as usize].fetch_add(1, Relaxed);
+        }
+    }
+
+    /// Count the version of an accepted shred. It is only read when the
+    /// common header is complete.
+    pub fn record_version(&self, bytes: &[u8]) {
+        if bytes.len() < 79 {
+            return;
+        }
+        let key = u16::from_le_bytes([bytes[77], bytes[78]]) as u32 + 1;
+        for (slot, count) in self.version_keys.iter().zip(&self.version_counts) {
+            match slot.compare_exchange(0, key, Relaxed, Relaxed) {
+                Ok(_) => {
+                    count.fetch_add(1, Relaxed);
+                    return;
+                }
+                Err(k) if k == key => {
+                    count.fetch_add(1, Relaxed);
+                    return;
+                }
+                Err(_) => {}
+            }
+        }
+        self.other_versions.fetch_add(1, Relaxed);
+    }
+
+    /// Count a shred dropped by the `shred_version` filter.
+    pub fn record_filtered_version(&self) {
+        self.other_versions.fetch_add(1, Relaxed);
+    }
+
+    pub fn snapshot(&self) -> ShredMixSnapshot {
+        let mut snap = ShredMixSnapshot {
+            other_versions: self.other_versions.load(Relaxed),
+            ..Default::default()
+        };
+        for (v, count) in self.variants.iter().enumerate() {
+            let n = count.load(Relaxed);
+            if n > 0 {
+                *snap.variants.entry(variant_name(v as u8)).or_default() += n;
+            }
+        }
+        for (key, count) in self.version_keys.iter().zip(&self.version_counts) {
+            let (k, n) = (key.load(Relaxed), count.load(Relaxed));
+            if k > 0 && n > 0 {
+                snap.versions.insert((k - 1) as u16, n);
+            }
+        }
+        snap
+    }
+}
+
+/// Family of a shred variant byte, ignoring the Merkle proof size in the low
+/// nibble. Bytes Agave does not define are reported as `unknown-0xNN`.
+pub fn variant_name(variant: u8) -> String {
+    let name = match variant {
+        0x5a => "legacy-code",
+        0xa5 => "legacy-data",
+        v => match v & 0xF0 {
+            0x40 => "merkle-code",
+            0x60 => "merkle-code-chained",
+            0x70 => "merkle-code-chained-resigned",
+            0x80 => "merkle-data",
+            0x90 => "merkle-data-chained",
+            0xb0 => "merkle-data-chained-resigned",
+            _ => return format!("unknown-{:#04x}", v),
+        },
+    };
+    name.to_string()
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shred(variant: u8, version: u16) -> Vec<u8> {
+        let mut buf = vec![0u8; 100];
+        buf[64] = variant;
+        buf[77..79].copy_from_slice(&version.to_le_bytes());
+        buf
+    }
+
+    #[test]
+    fn test_counts_by_family_and_version() {
+        let mix = ShredMix::default();
+        let record = |buf: &[u8]| {
+            mix.record_variant(buf);
+            mix.record_version(buf);
+        };
+        for _ in 0..3 {
+            record(&shred(0x96, 50093));
+        }
+        record(&shred(0x93, 50093));
+        record(&shred(0x66, 50093));
+        record(&shred(0xc6, 50094));
+        record(&[0u8; 10]);
+
+        let snap = mix.snapshot();
+        assert_eq!(snap.variants["merkle-data-chained"], 4);
+        assert_eq!(snap.variants["merkle-code-chained"], 1);
+        assert_eq!(snap.variants["unknown-0xc6"], 1);
+        assert_eq!(snap.versions, BTreeMap::from([(50093, 5), (50094, 1)]));
+        assert_eq!(snap.other_versions, 0);
+    }
+
+    #[test]
+    fn test_versions_beyond_tracked_set_go_to_other() {
+        let mix = ShredMix::default();
+        for v in 0..(VERSION_SLOTS as u16 + 2) {
+            mix.record_version(&shred(0x90, v));
+        }
+        let snap = mix.snapshot();
+        assert_eq!(snap.versions.len(), VERSION_SLOTS);
+        assert_eq!(snap.other_versions, 2);
+    }
+
+    #[test]
+    fn test_rejected_shreds_claim_no_version() {
+        let mix = ShredMix::default();
+        for v in 0..(VERSION_SLOTS as u16 * 2) {
+            mix.record_variant(&shred(0xc6, v));
+        }
+        mix.record_filtered_version();
+        mix.record_version(&shred(0x90, 50093));
+        let snap = mix.snapshot();
+        assert_eq!(snap.variants["unknown-0xc6"], VERSION_SLOTS as u64 * 2);
+        assert_eq!(snap.versions, BTreeMap::from([(50093, 1)]));
+        assert_eq!(snap.other_versions, 1);
+    }
+}
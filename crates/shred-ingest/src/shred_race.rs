@@ -6,17 +6,77 @@
 //! ## Architecture
 //! `ShredReceiver` hot loops call `try_send(ShredArrival)` (~20 ns, non-blocking)
 //! into a bounded channel. A background thread drains the channel, maintains a
-//! `(slot, idx) → first_arrival` map, and records per-pair win counts/latencies.
-//! A second thread evicts stale entries every 5 s. Drops on a full channel are
-//! acceptable — this is a sampling metric, not a correctness path.
+//! `(slot, idx) → first_arrival` window, and records per-pair win counts/latencies.
+//! Drops on a full channel are acceptable — this is a sampling metric, not a
+//! correctness path.
+//!
+//! The window is a fixed ring of per-slot buckets indexed by `slot % WINDOW_SLOTS`,
+//! each a `Vec` indexed by shred index. A bucket is reset wholesale when a newer
+//! slot maps onto it, so expiry costs nothing per entry and there is no eviction
+//! thread or retain scan to stall the processing thread under load.
+//!
+//! ## Slot-completion race
+//! A second race dimension records which feed first made each slot fully
+//! decodable (all data shreds contiguous through last-in-slot, directly or via
+//! FEC recovery). Decoders report completions through
+//! [`ShredRaceTracker::record_slot_complete`]; at a few events per second per
+//! feed this is done inline under a mutex rather than through a channel.
+//!
+//! ## Slot start race
+//! A fourth dimension races each feed's first shred of a slot, whatever its
+//! index: who hears about a new slot first, and by how much — what a block
+//! builder reacts to, where the wire race weighs every shred of the block
+//! alike. Slots that open a leader's window (every fourth slot) are raced
+//! again on their own: there the first shred comes from a new leader, and
+//! the lead shows which feed picks up a leader handoff first.
+//!
+//! ## Availability race
+//! The wire race only sees packets. When a feed loses a data shred but rebuilds
+//! it from coding shreds before the other feed's packet arrives, the data was
+//! available first even though no packet won. Decoders report such shreds as
+//! [`ShredArrival`]s with `recovered` set, timestamped with the packet that made
+//! recovery possible. They are kept out of the wire race and feed a third
+//! dimension in which a feed's earliest copy — received or recovered — races;
+//! `fec_wins` counts the races decided by a recovered copy.
+//!
+//! ## More than two feeds
+//! Every feed's first copy of a shred is kept until its slot leaves the
+//! window, so a third feed races both earlier ones rather than starting over.
+//! When the slot is evicted, each shred two or more feeds delivered ranks
+//! them by receive time; [`ShredRaceTracker::rankings`] reports how often
+//! each feed placed first overall, second, and so on.
+//!
+//! ## Recent windows
+//! Pair counts are cumulative since start, so after hours of running a
+//! routing change barely moves them. Each pair also keeps per-minute win
+//! counts for the last hour, from which snapshots report the last 5 minutes
+//! and the last hour next to the lifetime figures.
+//!
+//! ## Pair rules
+//! Some pairs are not worth racing, such as a local proxy that republishes
+//! the shreds of a multicast feed on the same host: their race measures a
+//! loopback hop. A [`PairRule`] set with [`ShredRaceTracker::set_pair_rule`]
+//! leaves such a pair out of the snapshots of all three races, or attaches a
+//! note to it. Excluded pairs are still tracked and persisted, so removing
+//! the rule brings back their full history. N-way rankings are unaffected.
+//!
+//! ## Race events
+//! A callback set with [`ShredRaceTracker::on_race`] sees every wire race as
+//! a [`RaceEvent`] when it is recorded, e.g. to stream races to an
+//! [`crate::output::OutputSink`].
 
-use crossbeam_channel::{bounded, Sender};
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
 use dashmap::DashMap;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicI64, AtomicU64, Ordering::Relaxed};
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 
-use crate::metrics;
+use crate::budget::{BudgetShare, BudgetUser, MemoryBudget};
+use crate::clock::MonotonicNs;
+use crate::cluster_tip::ClusterTip;
+use crate::coverage::SlotLengths;
+use crate::lead_hist::{AtomicLeadHistogram, LeadHistogram};
 
 // ---------------------------------------------------------------------------
 // Wire type sent from ShredReceiver hot loop
@@ -24,16 +84,311 @@ use crate::metrics;
 
 /// Sent from a [`crate::receiver::ShredReceiver`] hot loop to the race tracker.
 pub struct ShredArrival {
-    pub source: &'static str,
+    pub source: Arc<str>,
+    pub slot: u64,
+    pub idx: u32,
+    pub recv_ns: MonotonicNs,
+    /// Rebuilt by FEC recovery rather than received (availability race only).
+    pub recovered: bool,
+}
+
+/// One wire race between two feeds for the same shred, as recorded.
+#[derive(Debug, Clone)]
+pub struct RaceEvent {
     pub slot: u64,
     pub idx: u32,
-    pub recv_ns: u64,
+    pub winner: Arc<str>,
+    pub loser: Arc<str>,
+    /// How far ahead the winning copy arrived, µs.
+    pub lead_us: i64,
+    /// Receive time of the winning copy.
+    pub winner_recv_ns: MonotonicNs,
+    /// Receive time of the losing copy.
+    pub loser_recv_ns: MonotonicNs,
 }
 
+/// Called with every recorded wire race; see [`ShredRaceTracker::on_race`].
+pub type RaceCallback = Box<dyn Fn(&RaceEvent) + Send + Sync>;
+
+#[derive(Clone)]
 struct ShredFirstArrival {
-    recv_ns: u64,
-    source: &'static str,
-    inserted_ns: u64,
+    recv_ns: MonotonicNs,
+    source: Arc<str>,
+    recovered: bool,
+}
+
+// ---------------------------------------------------------------------------
+// Slot-bucketed arrival window
+// ---------------------------------------------------------------------------
+
+/// Number of slots kept in the window (~12.8 s at 400 ms/slot).
+const WINDOW_SLOTS: usize = 32;
+
+/// Upper bound on shred index per slot; larger indices are treated as garbage.
+const MAX_SHREDS_PER_SLOT: u32 = 32_768;
+
+/// Approximate bytes of one shred index's cell, and of one arrival in it.
+const CELL_BYTES: u64 = std::mem::size_of::<Vec<ShredFirstArrival>>() as u64;
+const ARRIVAL_BYTES: u64 = std::mem::size_of::<ShredFirstArrival>() as u64;
+
+struct SlotBucket {
+    slot: u64,
+    /// Each feed's first arrival per shred index, in processing order; grown
+    /// on demand.
+    first: Vec<Vec<ShredFirstArrival>>,
+    /// Approximate bytes held by `first`.
+    bytes: u64,
+}
+
+/// Arrivals that must agree on roughly the same slot before a
+/// [`SlotHorizon`] trusts it.
+const HORIZON_SEED_ARRIVALS: u32 = 8;
+/// Arrivals within this many slots of each other agree while seeding.
+const HORIZON_SEED_SPREAD: u64 = WINDOW_SLOTS as u64;
+/// Slots one arrival may move a seeded [`SlotHorizon`]: a leader's window.
+const HORIZON_MAX_STEP: u64 = 4;
+
+/// Highest slot a race window has seen, hardened against junk slots. Until
+/// [`HORIZON_SEED_ARRIVALS`] arrivals in a row agree on where the cluster
+/// is, each disagreeing arrival starts over from its own slot; after that,
+/// one arrival moves it at most [`HORIZON_MAX_STEP`] slots, and windows
+/// refuse slots past it. A real jump is caught up with over a few arrivals;
+/// a single junk slot far ahead can no longer push every real slot out of
+/// the window.
+#[derive(Default)]
+pub struct SlotHorizon {
+    highest: u64,
+    agreed: u32,
+}
+
+impl SlotHorizon {
+    pub fn get(&self) -> u64 {
+        self.highest
+    }
+
+    /// Take `slot` into account; returns the new horizon.
+    pub fn observe(&mut self, slot: u64) -> u64 {
+        if self.agreed < HORIZON_SEED_ARRIVALS {
+            if self.agreed == 0 || slot.abs_diff(self.highest) > HORIZON_SEED_SPREAD {
+                self.highest = slot;
+                self.agreed = 1;
+            } else {
+                self.highest = self.highest.max(slot);
+                self.agreed += 1;
+            }
+        } else if slot > self.highest {
+            self.highest = slot.min(self.highest.saturating_add(HORIZON_MAX_STEP));
+        }
+        self.highest
+    }
+}
+
+/// First-arrival window owned by the processing thread.
+struct ArrivalWindow {
+    buckets: Vec<SlotBucket>,
+    highest_slot: SlotHorizon,
+    /// Credited with the finishing order of each shred as its slot is evicted.
+    ranking: Option<Arc<FeedRanking>>,
+    /// Approximate bytes held by all buckets.
+    bytes: u64,
+    /// Newest slot shed to the memory budget; it and older slots are refused.
+    shed_through: Option<u64>,
+}
+
+impl ArrivalWindow {
+    fn new() -> Self {
+        Self {
+            buckets: (0..WINDOW_SLOTS)
+                .map(|_| SlotBucket { slot: 0, first: Vec::new(), bytes: 0 })
+                .collect(),
+            highest_slot: SlotHorizon::default(),
+            ranking: None,
+            bytes: 0,
+            shed_through: None,
+        }
+    }
+
+    fn with_ranking(ranking: Arc<FeedRanking>) -> Self {
+        Self { ranking: Some(ranking), ..Self::new() }
+    }
+
+    /// Slot entry for `(slot, idx)`, or `None` if the slot has already left the
+    /// window or the index is out of range.
+    fn entry(&mut self, slot: u64, idx: u32) -> Option<&mut Vec<ShredFirstArrival>> {
+        if idx >= MAX_SHREDS_PER_SLOT {
+            return None;
+        }
+        let highest = self.highest_slot.observe(slot);
+        if slot > highest
+            || slot.saturating_add(WINDOW_SLOTS as u64) <= highest
+            || self.shed_through.is_some_and(|s| slot <= s)
+        {
+            return None;
+        }
+
+        let bucket = &mut self.buckets[(slot % WINDOW_SLOTS as u64) as usize];
+        if bucket.slot != slot {
+            // A bucket holding a slot past the horizon holds junk taken in
+            // before the horizon was seeded; it gives way unranked.
+            let ahead = bucket.slot > highest;
+            if bucket.slot > slot && !ahead {
+                return None;
+            }
+            // A newer slot reuses this bucket — drop the old slot wholesale.
+            if let Some(ranking) = self.ranking.as_ref().filter(|_| !ahead) {
+                ranking.record_slot(&bucket.first);
+            }
+            bucket.slot = slot;
+            bucket.first.clear();
+            self.bytes -= bucket.bytes;
+            bucket.bytes = 0;
+        }
+        let i = idx as usize;
+        if bucket.first.len() <= i {
+            let grown = (i + 1 - bucket.first.len()) as u64 * CELL_BYTES;
+            bucket.first.resize(i + 1, Vec::new());
+            bucket.bytes += grown;
+            self.bytes += grown;
+        }
+        Some(&mut bucket.first[i])
+    }
+
+    /// Account for an arrival added to a cell of `slot`.
+    fn note_arrival(&mut self, slot: u64) {
+        self.buckets[(slot % WINDOW_SLOTS as u64) as usize].bytes += ARRIVAL_BYTES;
+        self.bytes += ARRIVAL_BYTES;
+    }
+
+    /// Oldest slot held, other than the newest.
+    fn oldest(&self) -> Option<u64> {
+        self.buckets
+            .iter()
+            .filter(|b| !b.first.is_empty() && b.slot < self.highest_slot.get())
+            .map(|b| b.slot)
+            .min()
+    }
+
+    /// Evict `slot` early, freeing its bucket, and refuse its shreds from
+    /// now on.
+    fn shed(&mut self, slot: u64) {
+        let bucket = &mut self.buckets[(slot % WINDOW_SLOTS as u64) as usize];
+        if let Some(ref ranking) = self.ranking {
+            ranking.record_slot(&bucket.first);
+        }
+        bucket.first = Vec::new();
+        self.bytes -= bucket.bytes;
+        bucket.bytes = 0;
+        self.shed_through = self.shed_through.max(Some(slot));
+    }
+}
+
+/// How often each feed finished first, second, ... among all feeds that
+/// delivered the same shred.
+#[derive(Default)]
+struct FeedRanking {
+    /// Per feed, the number of shreds it delivered in each position.
+    ranks: Mutex<BTreeMap<Arc<str>, Vec<u64>>>,
+}
+
+impl FeedRanking {
+    /// Rank the feeds of every shred of an evicted slot by receive time.
+    fn record_slot(&self, shreds: &[Vec<ShredFirstArrival>]) {
+        let mut ranks = self.ranks.lock().unwrap();
+        let mut order: Vec<&ShredFirstArrival> = Vec::new();
+        for arrivals in shreds.iter().filter(|a| a.len() > 1) {
+            order.clear();
+            order.extend(arrivals);
+            order.sort_by_key(|a| a.recv_ns);
+            for (pos, a) in order.iter().enumerate() {
+                let counts = ranks.entry(a.source.clone()).or_default();
+                if counts.len() <= pos {
+                    counts.resize(pos + 1, 0);
+                }
+                counts[pos] += 1;
+            }
+        }
+    }
+
+    fn snapshots(&self) -> Vec<ShredRankSnapshot> {
+        self.ranks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(source, ranks)| {
+                let races: u64 = ranks.iter().sum();
+                let first = ranks.first().copied().unwrap_or(0);
+                ShredRankSnapshot {
+                    source: source.clone(),
+                    races,
+                    first,
+                    first_pct: if races > 0 { first as f64 / races as f64 * 100.0 } else { 0.0 },
+                    ranks: ranks.clone(),
+                }
+            })
+            .collect()
+    }
+
+    fn export_state(&self) -> Vec<ShredRankState> {
+        let ranks = self.ranks.lock().unwrap();
+        ranks
+            .iter()
+            .map(|(source, ranks)| ShredRankState {
+                source: source.to_string(),
+                ranks: ranks.clone(),
+            })
+            .collect()
+    }
+
+    fn restore_state(&self, states: &[ShredRankState], sources: &[Arc<str>]) -> usize {
+        let mut ranks = self.ranks.lock().unwrap();
+        let mut restored = 0;
+        for st in states {
+            let Some(source) = sources.iter().find(|s| s.as_ref() == st.source) else {
+                continue;
+            };
+            let counts = ranks.entry(source.clone()).or_default();
+            if counts.len() < st.ranks.len() {
+                counts.resize(st.ranks.len(), 0);
+            }
+            for (c, n) in counts.iter_mut().zip(&st.ranks) {
+                *c += n;
+            }
+            restored += 1;
+        }
+        restored
+    }
+}
+
+/// Slots kept for the slot-completion and slot start races.
+const SLOT_WINDOW_SLOTS: u64 = 64;
+
+/// Consecutive slots of one leader; a slot that is a multiple of this opens
+/// a new leader's window.
+const LEADER_SLOTS: u64 = 4;
+
+/// Feeds raced in each recent slot, in the order they were recorded: those
+/// that completed it, or each feed's first shred of it.
+#[derive(Default)]
+struct SlotWindow {
+    highest_slot: SlotHorizon,
+    slots: HashMap<u64, Vec<ShredFirstArrival>>,
+}
+
+impl SlotWindow {
+    /// Feeds recorded in `slot` so far, or `None` once it has left the window.
+    fn slot(&mut self, slot: u64) -> Option<&mut Vec<ShredFirstArrival>> {
+        let before = self.highest_slot.get();
+        let highest = self.highest_slot.observe(slot);
+        if slot > highest || slot.saturating_add(SLOT_WINDOW_SLOTS) <= highest {
+            return None;
+        }
+        if highest != before {
+            // Junk taken in before the horizon was seeded lies past it.
+            self.slots
+                .retain(|&s, _| s.saturating_add(SLOT_WINDOW_SLOTS) > highest && s <= highest);
+        }
+        Some(self.slots.entry(slot).or_default())
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -61,6 +416,17 @@ impl RaceReservoir {
         }
     }
 
+    /// Samples in insertion order (oldest first).
+    fn samples(&self) -> Vec<i64> {
+        if self.len < RESERVOIR_CAP {
+            self.buf[..self.len].to_vec()
+        } else {
+            let mut out = self.buf[self.pos..].to_vec();
+            out.extend_from_slice(&self.buf[..self.pos]);
+            out
+        }
+    }
+
     /// Returns `(p50, p95, p99)` in µs, or `None` if empty.
     fn percentiles(&self) -> Option<(i64, i64, i64)> {
         if self.len == 0 {
@@ -78,18 +444,102 @@ impl RaceReservoir {
 }
 
 struct ShredPairMetrics {
-    source_a: &'static str,
-    source_b: &'static str,
+    source_a: Arc<str>,
+    source_b: Arc<str>,
     a_wins: AtomicU64,
     b_wins: AtomicU64,
     /// Sum of winner's lead time in µs (always ≥ 0).
     lead_sum_us: AtomicI64,
     lead_count: AtomicU64,
+    /// Races whose winning copy was FEC-recovered (availability race only).
+    fec_wins: AtomicU64,
     reservoir: Mutex<RaceReservoir>,
+    /// Every winner lead time since start (see [`crate::lead_hist`]).
+    hist: AtomicLeadHistogram,
+    recent: RecentRaces,
+}
+
+/// Minutes of per-minute race counts kept for the windowed stats.
+const RECENT_MINUTES: usize = 60;
+const MINUTE_NS: u64 = 60_000_000_000;
+
+/// One pair's races per minute over the last hour, by winner receive time.
+///
+/// Lock-free: a pair is only recorded by one thread at a time (the race
+/// processing thread, or a decoder holding the completions lock), so a
+/// bucket is reset for its new minute without racing another writer. A
+/// snapshot taken meanwhile may see that bucket half reset.
+struct RecentRaces {
+    /// Indexed by minute modulo [`RECENT_MINUTES`].
+    buckets: Vec<MinuteRaces>,
+    /// Receive time of the newest race recorded, ns.
+    latest: AtomicU64,
+}
+
+#[derive(Default)]
+struct MinuteRaces {
+    /// Which minute the counts belong to; older counts are stale.
+    minute: AtomicU64,
+    a_wins: AtomicU64,
+    b_wins: AtomicU64,
+    lead_sum_us: AtomicI64,
+}
+
+impl RecentRaces {
+    fn new() -> Self {
+        Self {
+            buckets: (0..RECENT_MINUTES).map(|_| MinuteRaces::default()).collect(),
+            latest: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, recv_ns: MonotonicNs, a_won: bool, lead_us: i64) {
+        let minute = recv_ns.0 / MINUTE_NS;
+        let b = &self.buckets[minute as usize % RECENT_MINUTES];
+        if b.minute.load(Relaxed) != minute {
+            b.a_wins.store(0, Relaxed);
+            b.b_wins.store(0, Relaxed);
+            b.lead_sum_us.store(0, Relaxed);
+            b.minute.store(minute, Relaxed);
+        }
+        if a_won {
+            b.a_wins.fetch_add(1, Relaxed);
+        } else {
+            b.b_wins.fetch_add(1, Relaxed);
+        }
+        b.lead_sum_us.fetch_add(lead_us, Relaxed);
+        self.latest.fetch_max(recv_ns.0, Relaxed);
+    }
+
+    fn latest(&self) -> MonotonicNs {
+        MonotonicNs(self.latest.load(Relaxed))
+    }
+
+    /// Races in the `minutes` whole minutes up to `now`, the current one
+    /// included; `None` if there were none.
+    fn window(&self, now: MonotonicNs, minutes: u64) -> Option<PairWindow> {
+        let now = now.0 / MINUTE_NS;
+        let (mut a_wins, mut b_wins, mut lead_sum_us) = (0, 0, 0);
+        for b in &self.buckets {
+            let minute = b.minute.load(Relaxed);
+            if minute <= now && now - minute < minutes {
+                a_wins += b.a_wins.load(Relaxed);
+                b_wins += b.b_wins.load(Relaxed);
+                lead_sum_us += b.lead_sum_us.load(Relaxed);
+            }
+        }
+        let races = a_wins + b_wins;
+        (races > 0).then(|| PairWindow {
+            a_wins,
+            b_wins,
+            a_win_pct: a_wins as f64 / races as f64 * 100.0,
+            lead_mean_us: lead_sum_us as f64 / races as f64,
+        })
+    }
 }
 
 impl ShredPairMetrics {
-    fn new(source_a: &'static str, source_b: &'static str) -> Arc<Self> {
+    fn new(source_a: Arc<str>, source_b: Arc<str>) -> Arc<Self> {
         Arc::new(Self {
             source_a,
             source_b,
@@ -97,22 +547,62 @@ impl ShredPairMetrics {
             b_wins: AtomicU64::new(0),
             lead_sum_us: AtomicI64::new(0),
             lead_count: AtomicU64::new(0),
+            fec_wins: AtomicU64::new(0),
             reservoir: Mutex::new(RaceReservoir::new()),
+            hist: AtomicLeadHistogram::default(),
+            recent: RecentRaces::new(),
         })
     }
 
-    fn record(&self, winner: &'static str, lead_us: i64) {
-        if winner == self.source_a {
+    fn record(&self, winner: &ShredFirstArrival, lead_us: i64) {
+        let a_won = winner.source == self.source_a;
+        if a_won {
             self.a_wins.fetch_add(1, Relaxed);
         } else {
             self.b_wins.fetch_add(1, Relaxed);
         }
+        if winner.recovered {
+            self.fec_wins.fetch_add(1, Relaxed);
+        }
         self.lead_sum_us.fetch_add(lead_us, Relaxed);
         self.lead_count.fetch_add(1, Relaxed);
         self.reservoir.lock().unwrap().push(lead_us);
+        self.hist.record(lead_us);
+        self.recent.record(winner.recv_ns, a_won, lead_us);
     }
 
-    fn snapshot(&self) -> ShredPairSnapshot {
+    fn export_state(&self) -> ShredPairState {
+        ShredPairState {
+            source_a: self.source_a.to_string(),
+            source_b: self.source_b.to_string(),
+            a_wins: self.a_wins.load(Relaxed),
+            b_wins: self.b_wins.load(Relaxed),
+            lead_sum_us: self.lead_sum_us.load(Relaxed),
+            lead_count: self.lead_count.load(Relaxed),
+            fec_wins: self.fec_wins.load(Relaxed),
+            lead_samples: self.reservoir.lock().unwrap().samples(),
+            lead_hist: self.hist.snapshot(),
+        }
+    }
+
+    fn restore_state(&self, state: &ShredPairState) {
+        self.a_wins.fetch_add(state.a_wins, Relaxed);
+        self.b_wins.fetch_add(state.b_wins, Relaxed);
+        self.lead_sum_us.fetch_add(state.lead_sum_us, Relaxed);
+        self.lead_count.fetch_add(state.lead_count, Relaxed);
+        self.fec_wins.fetch_add(state.fec_wins, Relaxed);
+        self.hist.merge(&state.lead_hist);
+        let mut res = self.reservoir.lock().unwrap();
+        let newer = res.samples();
+        *res = RaceReservoir::new();
+        for &v in state.lead_samples.iter().chain(newer.iter()) {
+            res.push(v);
+        }
+    }
+
+    /// Windows end at `now`; at the last race when `None`, for replays,
+    /// whose receive times are on their own clock.
+    fn snapshot(&self, now: Option<MonotonicNs>) -> ShredPairSnapshot {
         let a_wins = self.a_wins.load(Relaxed);
         let b_wins = self.b_wins.load(Relaxed);
         let total_matched = a_wins + b_wins;
@@ -136,17 +626,26 @@ impl ShredPairMetrics {
                 .map_or((None, None, None), |(p50, p95, p99)| (Some(p50), Some(p95), Some(p99)))
         };
 
+        let now = now.unwrap_or_else(|| self.recent.latest());
+        let (last_5m, last_1h) =
+            (self.recent.window(now, 5), self.recent.window(now, RECENT_MINUTES as u64));
+
         ShredPairSnapshot {
-            source_a: self.source_a,
-            source_b: self.source_b,
+            source_a: self.source_a.clone(),
+            source_b: self.source_b.clone(),
             a_wins,
             b_wins,
             total_matched,
+            fec_wins: self.fec_wins.load(Relaxed),
             a_win_pct,
             lead_mean_us,
             lead_p50_us,
             lead_p95_us,
             lead_p99_us,
+            lead_hist: self.hist.snapshot(),
+            last_5m,
+            last_1h,
+            note: None,
         }
     }
 }
@@ -157,11 +656,14 @@ impl ShredPairMetrics {
 
 #[derive(Serialize, Clone, Debug)]
 pub struct ShredPairSnapshot {
-    pub source_a: &'static str,
-    pub source_b: &'static str,
+    pub source_a: Arc<str>,
+    pub source_b: Arc<str>,
     pub a_wins: u64,
     pub b_wins: u64,
     pub total_matched: u64,
+    /// Races won by an FEC-recovered copy (availability race only).
+    #[serde(skip_serializing_if = "is_zero")]
+    pub fec_wins: u64,
     /// Win rate of source_a (0–100).
     pub a_win_pct: f64,
     /// Mean winner lead time in µs (always positive).
@@ -169,48 +671,266 @@ pub struct ShredPairSnapshot {
     pub lead_p50_us: Option<i64>,
     pub lead_p95_us: Option<i64>,
     pub lead_p99_us: Option<i64>,
+    /// Every winner lead time since start, for whole-run percentiles.
+    #[serde(skip_serializing_if = "LeadHistogram::is_empty")]
+    pub lead_hist: LeadHistogram,
+    /// The same pair over the last 5 minutes and the last hour, to the
+    /// minute; omitted when it did not race then.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_5m: Option<PairWindow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_1h: Option<PairWindow>,
+    /// Note from the pair's [`PairRule`], e.g. why its races are not
+    /// comparable to the others.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<Arc<str>>,
+}
+
+/// How a source pair is reported (see [`ShredRaceTracker::set_pair_rule`]).
+#[derive(Clone, Debug, Default)]
+pub struct PairRule {
+    /// Leave the pair out of race snapshots.
+    pub exclude: bool,
+    /// Shown with the pair's results.
+    pub note: Option<Arc<str>>,
+}
+
+/// Pair results over a recent window.
+#[derive(Serialize, Clone, Debug)]
+pub struct PairWindow {
+    pub a_wins: u64,
+    pub b_wins: u64,
+    pub a_win_pct: f64,
+    pub lead_mean_us: f64,
+}
+
+/// Cumulative pair metrics in a form that survives a restart.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShredPairState {
+    pub source_a: String,
+    pub source_b: String,
+    pub a_wins: u64,
+    pub b_wins: u64,
+    pub lead_sum_us: i64,
+    pub lead_count: u64,
+    #[serde(default)]
+    pub fec_wins: u64,
+    /// Reservoir contents, oldest first.
+    pub lead_samples: Vec<i64>,
+    #[serde(default)]
+    pub lead_hist: LeadHistogram,
+}
+
+/// One feed's finishing positions across shreds that several feeds delivered.
+#[derive(Serialize, Clone, Debug)]
+pub struct ShredRankSnapshot {
+    pub source: Arc<str>,
+    /// Shreds this feed delivered that at least one other feed also delivered.
+    pub races: u64,
+    /// Of those, how many this feed delivered before every other feed.
+    pub first: u64,
+    pub first_pct: f64,
+    /// `ranks[n]`: shreds this feed delivered in position `n + 1`.
+    pub ranks: Vec<u64>,
+}
+
+/// Cumulative finishing positions of one feed, for persistence.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ShredRankState {
+    pub source: String,
+    pub ranks: Vec<u64>,
+}
+
+fn is_zero(v: &u64) -> bool {
+    *v == 0
 }
 
 // ---------------------------------------------------------------------------
 // ShredRaceTracker
 // ---------------------------------------------------------------------------
 
+type PairKey = (Arc<str>, Arc<str>);
+type PairMap = DashMap<PairKey, Arc<ShredPairMetrics>>;
+
+/// Canonical (alphabetical) pair key, as used by [`PairMap`].
+fn pair_key(a: &str, b: &str) -> PairKey {
+    if a <= b {
+        (a.into(), b.into())
+    } else {
+        (b.into(), a.into())
+    }
+}
+
+/// Pair maps filled on the race processing thread, shared with the tracker.
+#[derive(Clone, Default)]
+struct ProcPairs {
+    wire: Arc<PairMap>,
+    avail: Arc<PairMap>,
+    start: Arc<PairMap>,
+    handoff: Arc<PairMap>,
+}
+
+/// Arrival windows of the wire, availability and slot start races.
+struct RaceWindows {
+    wire: ArrivalWindow,
+    avail: ArrivalWindow,
+    starts: SlotWindow,
+    on_race: Arc<OnceLock<RaceCallback>>,
+}
+
+impl RaceWindows {
+    fn new(ranking: Arc<FeedRanking>, on_race: Arc<OnceLock<RaceCallback>>) -> Self {
+        Self {
+            wire: ArrivalWindow::with_ranking(ranking),
+            avail: ArrivalWindow::new(),
+            starts: SlotWindow::default(),
+            on_race,
+        }
+    }
+
+    fn process(&mut self, pairs: &ProcPairs, arrival: &ShredArrival) {
+        process_arrival(&mut self.avail, &pairs.avail, arrival, None);
+        if !arrival.recovered {
+            process_arrival(&mut self.wire, &pairs.wire, arrival, self.on_race.get());
+            process_start(&mut self.starts, pairs, arrival);
+        }
+    }
+
+    /// Report the windows' size to the budget and, while the windows hold
+    /// more than their part of it, shed the oldest slot of either window.
+    fn enforce_budget(&mut self, share: &mut BudgetShare) {
+        share.set(self.wire.bytes + self.avail.bytes);
+        while share.over() {
+            let (window, slot) = match (self.wire.oldest(), self.avail.oldest()) {
+                (Some(w), Some(a)) if a < w => (&mut self.avail, a),
+                (Some(w), _) => (&mut self.wire, w),
+                (None, Some(a)) => (&mut self.avail, a),
+                (None, None) => break,
+            };
+            window.shed(slot);
+            share.budget().count_race_slot_shed();
+            share.set(self.wire.bytes + self.avail.bytes);
+        }
+    }
+}
+
 pub struct ShredRaceTracker {
     tx: Sender<ShredArrival>,
-    pairs: Arc<DashMap<(&'static str, &'static str), Arc<ShredPairMetrics>>>,
+    /// Deterministic mode only: the undrained arrival queue and the windows,
+    /// processed on the caller's thread by [`Self::process_pending`].
+    inline: Option<(Receiver<ShredArrival>, Mutex<RaceWindows>)>,
+    /// Wire, availability (received or FEC-recovered), slot start and leader
+    /// handoff race pairs.
+    pairs: ProcPairs,
+    /// Slot-completion race pairs, same layout as the others.
+    slot_pairs: PairMap,
+    completions: Mutex<SlotWindow>,
+    /// Slot lengths any feed has learned, for per-position coverage.
+    slot_lengths: SlotLengths,
+    /// Highest slot any feed has delivered; also held by RPC sources, which
+    /// report the baseline's confirmed slot to it.
+    cluster_tip: Arc<ClusterTip>,
+    /// N-way finishing positions in the wire race.
+    ranking: Arc<FeedRanking>,
+    /// Reporting rules by canonical pair key.
+    pair_rules: RwLock<HashMap<PairKey, PairRule>>,
+    /// Shared with the arrival windows, wherever they are processed.
+    on_race: Arc<OnceLock<RaceCallback>>,
 }
 
 impl ShredRaceTracker {
     pub fn new() -> Arc<Self> {
+        Self::with_budget(None)
+    }
+
+    /// Tracker whose arrival windows count against `budget` and shed their
+    /// oldest slots while it is exceeded (see [`crate::budget`]).
+    pub fn with_budget(budget: Option<MemoryBudget>) -> Arc<Self> {
         let (tx, rx) = bounded::<ShredArrival>(4096);
-        let arrivals: Arc<DashMap<(u64, u32), ShredFirstArrival>> = Arc::new(DashMap::new());
-        let pairs: Arc<DashMap<(&'static str, &'static str), Arc<ShredPairMetrics>>> =
-            Arc::new(DashMap::new());
+        let pairs = ProcPairs::default();
 
         // Processing thread: drain channel, match arrivals, record wins.
-        let arrivals_proc = arrivals.clone();
+        // The arrival windows are owned by this thread alone; no locking needed.
         let pairs_proc = pairs.clone();
+        let ranking = Arc::new(FeedRanking::default());
+        let ranking_proc = ranking.clone();
+        let on_race: Arc<OnceLock<RaceCallback>> = Arc::default();
+        let on_race_proc = on_race.clone();
         std::thread::Builder::new()
             .name("shred-race-proc".into())
             .spawn(move || {
+                let mut windows = RaceWindows::new(ranking_proc, on_race_proc);
+                let mut share = budget.map(|b| b.share(BudgetUser::Race));
                 for arrival in &rx {
-                    process_arrival(&arrivals_proc, &pairs_proc, arrival);
+                    arrival.recv_ns.check_live(MonotonicNs::now());
+                    windows.process(&pairs_proc, &arrival);
+                    if let Some(ref mut share) = share {
+                        windows.enforce_budget(share);
+                    }
                 }
             })
             .expect("failed to spawn shred-race-proc");
 
-        // Eviction thread: every 5s remove arrivals older than 10s.
-        let arrivals_evict = arrivals;
-        std::thread::Builder::new()
-            .name("shred-race-evict".into())
-            .spawn(move || loop {
-                std::thread::sleep(std::time::Duration::from_secs(5));
-                let cutoff_ns = metrics::now_ns().saturating_sub(10_000_000_000);
-                arrivals_evict.retain(|_, v| v.inserted_ns > cutoff_ns);
-            })
-            .expect("failed to spawn shred-race-evict");
+        Arc::new(Self {
+            tx,
+            inline: None,
+            pairs,
+            slot_pairs: DashMap::new(),
+            completions: Mutex::new(SlotWindow::default()),
+            slot_lengths: SlotLengths::default(),
+            cluster_tip: Arc::default(),
+            ranking,
+            pair_rules: RwLock::default(),
+            on_race,
+        })
+    }
 
-        Arc::new(Self { tx, pairs })
+    /// Tracker for deterministic replays: no processing thread and an
+    /// unbounded queue, so no arrival is dropped and arrivals are matched in
+    /// exactly the order they were sent. Call [`Self::process_pending`] after
+    /// each step of the replay.
+    pub fn deterministic() -> Arc<Self> {
+        let (tx, rx) = unbounded::<ShredArrival>();
+        let ranking = Arc::new(FeedRanking::default());
+        let on_race: Arc<OnceLock<RaceCallback>> = Arc::default();
+        let windows = RaceWindows::new(ranking.clone(), on_race.clone());
+        Arc::new(Self {
+            tx,
+            inline: Some((rx, Mutex::new(windows))),
+            pairs: ProcPairs::default(),
+            slot_pairs: DashMap::new(),
+            completions: Mutex::new(SlotWindow::default()),
+            slot_lengths: SlotLengths::default(),
+            cluster_tip: Arc::default(),
+            ranking,
+            pair_rules: RwLock::default(),
+            on_race,
+        })
+    }
+
+    /// Call `f` with every wire race as it is recorded. It runs on the race
+    /// processing thread, so it must not block. Only the first call counts.
+    pub fn on_race(&self, f: impl Fn(&RaceEvent) + Send + Sync + 'static) {
+        let _ = self.on_race.set(Box::new(f));
+    }
+
+    /// Match every queued arrival (deterministic mode; a no-op otherwise).
+    pub fn process_pending(&self) {
+        let Some((rx, windows)) = &self.inline else { return };
+        let mut windows = windows.lock().unwrap();
+        for arrival in rx.try_iter() {
+            windows.process(&self.pairs, &arrival);
+        }
+    }
+
+    /// Data shred counts of recent slots, shared by every shred-tier decoder.
+    pub fn slot_lengths(&self) -> &SlotLengths {
+        &self.slot_lengths
+    }
+
+    /// Highest slot delivered, shared by every shred-tier decoder.
+    pub fn cluster_tip(&self) -> &Arc<ClusterTip> {
+        &self.cluster_tip
     }
 
     /// Get a channel sender for use in a `ShredReceiver`.
@@ -218,13 +938,173 @@ impl ShredRaceTracker {
         self.tx.clone()
     }
 
+    /// Export every pair's cumulative metrics for persistence.
+    pub fn export_state(&self) -> Vec<ShredPairState> {
+        export_pairs(&self.pairs.wire)
+    }
+
+    /// Merge previously exported pair state. `sources` are the names of the
+    /// currently configured sources; pairs naming a source that no longer
+    /// exists are skipped. Returns the number of pairs restored.
+    pub fn restore_state(&self, states: &[ShredPairState], sources: &[Arc<str>]) -> usize {
+        restore_pairs(&self.pairs.wire, states, sources)
+    }
+
+    /// [`Self::export_state`] for the slot-completion race.
+    pub fn export_slot_state(&self) -> Vec<ShredPairState> {
+        export_pairs(&self.slot_pairs)
+    }
+
+    /// [`Self::restore_state`] for the slot-completion race.
+    pub fn restore_slot_state(&self, states: &[ShredPairState], sources: &[Arc<str>]) -> usize {
+        restore_pairs(&self.slot_pairs, states, sources)
+    }
+
+    /// Record that `source` made `slot` fully decodable with the shred received
+    /// at `recv_ns`. The first feed to complete a slot wins against every feed
+    /// that completes it later.
+    pub fn record_slot_complete(&self, source: Arc<str>, slot: u64, recv_ns: MonotonicNs) {
+        let mut w = self.completions.lock().unwrap();
+        let Some(done) = w.slot(slot) else { return };
+        if done.iter().any(|c| c.source == source) {
+            return;
+        }
+        let this = ShredFirstArrival { recv_ns, source, recovered: false };
+        for earlier in done.iter() {
+            record_pair(&self.slot_pairs, earlier, &this);
+        }
+        done.push(this);
+    }
+
+    /// [`Self::export_state`] for the availability race.
+    pub fn export_avail_state(&self) -> Vec<ShredPairState> {
+        export_pairs(&self.pairs.avail)
+    }
+
+    /// [`Self::restore_state`] for the availability race.
+    pub fn restore_avail_state(&self, states: &[ShredPairState], sources: &[Arc<str>]) -> usize {
+        restore_pairs(&self.pairs.avail, states, sources)
+    }
+
+    /// [`Self::export_state`] for the slot start race.
+    pub fn export_start_state(&self) -> Vec<ShredPairState> {
+        export_pairs(&self.pairs.start)
+    }
+
+    /// [`Self::restore_state`] for the slot start race.
+    pub fn restore_start_state(&self, states: &[ShredPairState], sources: &[Arc<str>]) -> usize {
+        restore_pairs(&self.pairs.start, states, sources)
+    }
+
+    /// [`Self::export_state`] for the slot start race at leader handoffs.
+    pub fn export_handoff_state(&self) -> Vec<ShredPairState> {
+        export_pairs(&self.pairs.handoff)
+    }
+
+    /// [`Self::restore_state`] for the slot start race at leader handoffs.
+    pub fn restore_handoff_state(&self, states: &[ShredPairState], sources: &[Arc<str>]) -> usize {
+        restore_pairs(&self.pairs.handoff, states, sources)
+    }
+
+    /// Set how the pair of `a` and `b` is reported in every race, in either
+    /// order. Replaces an earlier rule for the pair.
+    pub fn set_pair_rule(&self, a: &str, b: &str, rule: PairRule) {
+        self.pair_rules.write().unwrap().insert(pair_key(a, b), rule);
+    }
+
+    /// Snapshot the availability race pairs, sorted by source name.
+    pub fn avail_snapshots(&self) -> Vec<ShredPairSnapshot> {
+        self.apply_rules(snapshot_pairs(&self.pairs.avail, self.now()))
+    }
+
+    /// Snapshot the slot start race pairs, sorted by source name.
+    pub fn start_snapshots(&self) -> Vec<ShredPairSnapshot> {
+        self.apply_rules(snapshot_pairs(&self.pairs.start, self.now()))
+    }
+
+    /// Snapshot the slot start race pairs over slots that open a leader's
+    /// window only, sorted by source name.
+    pub fn handoff_snapshots(&self) -> Vec<ShredPairSnapshot> {
+        self.apply_rules(snapshot_pairs(&self.pairs.handoff, self.now()))
+    }
+
+    /// Snapshot the slot-completion race pairs, sorted by source name.
+    pub fn slot_snapshots(&self) -> Vec<ShredPairSnapshot> {
+        self.apply_rules(snapshot_pairs(&self.slot_pairs, self.now()))
+    }
+
     /// Snapshot all pair metrics; returns them sorted by source name for stable display.
     pub fn snapshots(&self) -> Vec<ShredPairSnapshot> {
-        let mut snaps: Vec<ShredPairSnapshot> =
-            self.pairs.iter().map(|e| e.value().snapshot()).collect();
-        snaps.sort_by(|a, b| a.source_a.cmp(b.source_a).then(a.source_b.cmp(b.source_b)));
+        self.apply_rules(snapshot_pairs(&self.pairs.wire, self.now()))
+    }
+
+    /// The end of the recent windows: now, or the last race in a replay.
+    fn now(&self) -> Option<MonotonicNs> {
+        self.inline.is_none().then(MonotonicNs::now)
+    }
+
+    /// Drop excluded pairs and attach notes.
+    fn apply_rules(&self, mut snaps: Vec<ShredPairSnapshot>) -> Vec<ShredPairSnapshot> {
+        let rules = self.pair_rules.read().unwrap();
+        if rules.is_empty() {
+            return snaps;
+        }
+        snaps.retain_mut(|s| {
+            let Some(rule) = rules.get(&(s.source_a.clone(), s.source_b.clone())) else {
+                return true;
+            };
+            s.note = rule.note.clone();
+            !rule.exclude
+        });
         snaps
     }
+
+    /// Each feed's finishing positions in the wire race, sorted by source
+    /// name. A shred is ranked once its slot leaves the arrival window.
+    pub fn rankings(&self) -> Vec<ShredRankSnapshot> {
+        self.ranking.snapshots()
+    }
+
+    /// Export the wire race rankings for persistence.
+    pub fn export_rank_state(&self) -> Vec<ShredRankState> {
+        self.ranking.export_state()
+    }
+
+    /// Merge previously exported rankings; like [`Self::restore_state`].
+    pub fn restore_rank_state(&self, states: &[ShredRankState], sources: &[Arc<str>]) -> usize {
+        self.ranking.restore_state(states, sources)
+    }
+}
+
+fn snapshot_pairs(pairs: &PairMap, now: Option<MonotonicNs>) -> Vec<ShredPairSnapshot> {
+    let mut snaps: Vec<ShredPairSnapshot> =
+        pairs.iter().map(|e| e.value().snapshot(now)).collect();
+    snaps.sort_by(|a, b| a.source_a.cmp(&b.source_a).then(a.source_b.cmp(&b.source_b)));
+    snaps
+}
+
+fn export_pairs(pairs: &PairMap) -> Vec<ShredPairState> {
+    let mut states: Vec<ShredPairState> = pairs.iter().map(|e| e.value().export_state()).collect();
+    states.sort_by(|a, b| a.source_a.cmp(&b.source_a).then(a.source_b.cmp(&b.source_b)));
+    states
+}
+
+fn restore_pairs(pairs: &PairMap, states: &[ShredPairState], sources: &[Arc<str>]) -> usize {
+    let lookup = |name: &str| sources.iter().find(|s| s.as_ref() == name).cloned();
+    let mut restored = 0;
+    for st in states {
+        let (Some(a), Some(b)) = (lookup(&st.source_a), lookup(&st.source_b)) else {
+            continue;
+        };
+        // Exported keys are already canonical (alphabetical), same as live ones.
+        let pair = pairs
+            .entry((a.clone(), b.clone()))
+            .or_insert_with(|| ShredPairMetrics::new(a, b))
+            .clone();
+        pair.restore_state(st);
+        restored += 1;
+    }
+    restored
 }
 
 // ---------------------------------------------------------------------------
@@ -232,47 +1112,382 @@ impl ShredRaceTracker {
 // ---------------------------------------------------------------------------
 
 fn process_arrival(
-    arrivals: &DashMap<(u64, u32), ShredFirstArrival>,
-    pairs: &DashMap<(&'static str, &'static str), Arc<ShredPairMetrics>>,
-    arrival: ShredArrival,
+    window: &mut ArrivalWindow,
+    pairs: &PairMap,
+    arrival: &ShredArrival,
+    on_race: Option<&RaceCallback>,
 ) {
-    let ShredArrival { source, slot, idx, recv_ns } = arrival;
-    let now = metrics::now_ns();
-
-    use dashmap::mapref::entry::Entry;
-    match arrivals.entry((slot, idx)) {
-        Entry::Occupied(e) => {
-            let first_source = e.get().source;
-            if first_source == source {
-                // Duplicate from the same feed — ignore.
-                return;
-            }
-            let first_recv_ns = e.get().recv_ns;
-            e.remove();
+    let Some(cell) = window.entry(arrival.slot, arrival.idx) else {
+        return;
+    };
+    let this = ShredFirstArrival {
+        recv_ns: arrival.recv_ns,
+        source: arrival.source.clone(),
+        recovered: arrival.recovered,
+    };
+    let alone = cell.len() == 1;
+    if let Some(same) = cell.iter_mut().find(|a| a.source == this.source) {
+        // Duplicate from the same feed. Until another feed has raced it, keep
+        // whichever copy was available first; after that the races stand.
+        if alone && this.recv_ns < same.recv_ns {
+            *same = this;
+        }
+        return;
+    }
+    for earlier in cell.iter() {
+        let Some((winner, lead_us)) = record_pair(pairs, earlier, &this) else { continue };
+        if let Some(f) = on_race {
+            let loser = if std::ptr::eq(winner, earlier) { &this } else { earlier };
+            f(&RaceEvent {
+                slot: arrival.slot,
+                idx: arrival.idx,
+                winner: winner.source.clone(),
+                loser: loser.source.clone(),
+                lead_us,
+                winner_recv_ns: winner.recv_ns,
+                loser_recv_ns: loser.recv_ns,
+            });
+        }
+    }
+    cell.push(this);
+    window.note_arrival(arrival.slot);
+}
 
-            // Discard if delta looks like an eviction artifact (>10s).
-            let lead_us = ((first_recv_ns as i64) - (recv_ns as i64)).abs() / 1000;
-            if lead_us >= 10_000_000 {
-                return;
-            }
+/// Race a feed's first shred of a slot against the other feeds' first
+/// shreds of it, and again in the handoff race when the slot opens a
+/// leader's window.
+fn process_start(window: &mut SlotWindow, pairs: &ProcPairs, arrival: &ShredArrival) {
+    let Some(firsts) = window.slot(arrival.slot) else { return };
+    let this = ShredFirstArrival {
+        recv_ns: arrival.recv_ns,
+        source: arrival.source.clone(),
+        recovered: false,
+    };
+    let alone = firsts.len() == 1;
+    if let Some(same) = firsts.iter_mut().find(|a| a.source == this.source) {
+        // As in the wire race: a feed's earlier shred, processed late, counts
+        // until another feed has raced it.
+        if alone && this.recv_ns < same.recv_ns {
+            *same = this;
+        }
+        return;
+    }
+    let leader_position = arrival.slot % LEADER_SLOTS;
+    for earlier in firsts.iter() {
+        record_pair(&pairs.start, earlier, &this);
+        if leader_position == 0 {
+            record_pair(&pairs.handoff, earlier, &this);
+        }
+    }
+    firsts.push(this);
+}
 
-            let winner = if first_recv_ns <= recv_ns { first_source } else { source };
+/// Record one race between two distinct feeds under the canonical pair key.
+/// Returns the winner and its lead in µs, or `None` if the race was discarded.
+fn record_pair<'a>(
+    pairs: &PairMap,
+    x: &'a ShredFirstArrival,
+    y: &'a ShredFirstArrival,
+) -> Option<(&'a ShredFirstArrival, i64)> {
+    // Discard if delta looks like a measurement artifact (>10s).
+    let lead_us = x.recv_ns.delta(y.recv_ns).abs() / 1000;
+    if lead_us >= 10_000_000 {
+        return None;
+    }
 
-            // Canonical key: alphabetically sorted so (a,b) == (b,a).
-            let (key_a, key_b) = if first_source <= source {
-                (first_source, source)
-            } else {
-                (source, first_source)
-            };
+    let winner = if x.recv_ns <= y.recv_ns { x } else { y };
+
+    // Canonical key: alphabetically sorted so (a,b) == (b,a).
+    let (key_a, key_b) = if x.source <= y.source { (&x.source, &y.source) } else { (&y.source, &x.source) };
+
+    let pair = pairs
+        .entry((key_a.clone(), key_b.clone()))
+        .or_insert_with(|| ShredPairMetrics::new(key_a.clone(), key_b.clone()))
+        .clone();
+    pair.record(winner, lead_us);
+    Some((winner, lead_us))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arrival(source: &str, slot: u64, idx: u32, recv_ns: u64) -> ShredArrival {
+        let recv_ns = MonotonicNs(recv_ns);
+        ShredArrival { source: source.into(), slot, idx, recv_ns, recovered: false }
+    }
+
+    fn key(a: &str, b: &str) -> (Arc<str>, Arc<str>) {
+        (a.into(), b.into())
+    }
+
+    #[test]
+    fn test_pair_recorded_once_per_shred() {
+        let mut window = ArrivalWindow::new();
+        let pairs = DashMap::new();
+        process_arrival(&mut window, &pairs, &arrival("b", 100, 5, 1_000_000), None);
+        process_arrival(&mut window, &pairs, &arrival("a", 100, 5, 1_300_000), None);
+        // A later copy from a feed already in the race is ignored.
+        process_arrival(&mut window, &pairs, &arrival("a", 100, 5, 1_400_000), None);
+
+        let snap = pairs.get(&key("a", "b")).unwrap().snapshot(None);
+        assert_eq!(snap.total_matched, 1);
+        assert_eq!(snap.b_wins, 1);
+        assert_eq!(snap.lead_mean_us, Some(300.0));
+    }
+
+    #[test]
+    fn test_on_race_sees_winner_and_loser() {
+        let tracker = ShredRaceTracker::deterministic();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        tracker.on_race(move |e| {
+            sink.lock().unwrap().push((e.winner.clone(), e.loser.clone(), e.lead_us));
+        });
+        let tx = tracker.sender();
+        tx.send(arrival("b", 100, 5, 1_000_000)).unwrap();
+        tx.send(arrival("a", 100, 5, 1_300_000)).unwrap();
+        tracker.process_pending();
+        assert_eq!(*seen.lock().unwrap(), vec![("b".into(), "a".into(), 300)]);
+    }
+
+    #[test]
+    fn test_three_feeds_race_pairwise_and_rank() {
+        let ranking = Arc::new(FeedRanking::default());
+        let mut window = ArrivalWindow::with_ranking(ranking.clone());
+        let pairs = DashMap::new();
+        // Processed out of receive order: c arrived first, then a, then b.
+        process_arrival(&mut window, &pairs, &arrival("a", 100, 0, 2_000_000), None);
+        process_arrival(&mut window, &pairs, &arrival("b", 100, 0, 3_000_000), None);
+        process_arrival(&mut window, &pairs, &arrival("c", 100, 0, 1_000_000), None);
+        // Only a and b deliver shred 1.
+        process_arrival(&mut window, &pairs, &arrival("b", 100, 1, 4_000_000), None);
+        process_arrival(&mut window, &pairs, &arrival("a", 100, 1, 5_000_000), None);
 
-            let pair = pairs
-                .entry((key_a, key_b))
-                .or_insert_with(|| ShredPairMetrics::new(key_a, key_b))
-                .clone();
-            pair.record(winner, lead_us);
+        let snap = |a: &str, b: &str| pairs.get(&key(a, b)).unwrap().snapshot(None);
+        assert_eq!((snap("a", "b").a_wins, snap("a", "b").b_wins), (1, 1));
+        assert_eq!((snap("a", "c").a_wins, snap("a", "c").b_wins), (0, 1));
+        assert_eq!((snap("b", "c").a_wins, snap("b", "c").b_wins), (0, 1));
+        assert!(ranking.snapshots().is_empty(), "ranked only on eviction");
+
+        let reuse = 100 + WINDOW_SLOTS as u64;
+        process_arrival(&mut window, &pairs, &arrival("a", reuse, 0, 9_000_000), None);
+        let ranks: BTreeMap<String, Vec<u64>> =
+            ranking.snapshots().into_iter().map(|r| (r.source.to_string(), r.ranks)).collect();
+        assert_eq!(ranks["c"], vec![1]);
+        assert_eq!(ranks["a"], vec![0, 2]);
+        assert_eq!(ranks["b"], vec![1, 0, 1]);
+    }
+
+    #[test]
+    fn test_same_source_duplicate_ignored() {
+        let mut window = ArrivalWindow::new();
+        let pairs = DashMap::new();
+        process_arrival(&mut window, &pairs, &arrival("a", 100, 1, 1_000), None);
+        process_arrival(&mut window, &pairs, &arrival("a", 100, 1, 2_000), None);
+        assert!(pairs.is_empty());
+    }
+
+    #[test]
+    fn test_window_drops_expired_slots_wholesale() {
+        let mut window = ArrivalWindow::new();
+        let pairs = DashMap::new();
+        process_arrival(&mut window, &pairs, &arrival("a", 100, 1, 1_000), None);
+
+        // A slot that maps onto the same bucket evicts slot 100.
+        let reuse = 100 + WINDOW_SLOTS as u64;
+        process_arrival(&mut window, &pairs, &arrival("a", reuse, 1, 2_000), None);
+        process_arrival(&mut window, &pairs, &arrival("b", 100, 1, 3_000), None);
+        assert!(pairs.is_empty(), "slot 100 is outside the window");
+
+        process_arrival(&mut window, &pairs, &arrival("b", reuse, 1, 2_500), None);
+        assert_eq!(pairs.get(&key("a", "b")).unwrap().snapshot(None).a_wins, 1);
+    }
+
+    #[test]
+    fn test_window_sheds_oldest_slot() {
+        let mut window = ArrivalWindow::new();
+        for slot in [10, 11, 12] {
+            let cell = window.entry(slot, 3).unwrap();
+            cell.push(ShredFirstArrival {
+                recv_ns: MonotonicNs(0),
+                source: "a".into(),
+                recovered: false,
+            });
+            window.note_arrival(slot);
+        }
+        let per_slot = 4 * CELL_BYTES + ARRIVAL_BYTES;
+        assert_eq!(window.bytes, 3 * per_slot);
+
+        assert_eq!(window.oldest(), Some(10));
+        window.shed(10);
+        assert_eq!(window.bytes, 2 * per_slot);
+        assert_eq!(window.oldest(), Some(11));
+        assert!(window.entry(10, 0).is_none(), "shed slot is refused");
+        window.shed(11);
+        assert_eq!(window.oldest(), None, "the newest slot is kept");
+    }
+
+    #[test]
+    fn test_junk_slot_does_not_stop_racing() {
+        // Junk ahead of the very first arrival, and again once the horizon
+        // is seeded: neither overflows nor locks the real slots out.
+        let mut window = ArrivalWindow::new();
+        let pairs = DashMap::new();
+        // Maps onto slot 100's bucket.
+        let junk = u64::MAX - 27;
+        process_arrival(&mut window, &pairs, &arrival("x", junk, 0, 1_000), None);
+        for idx in 0..HORIZON_SEED_ARRIVALS {
+            process_arrival(&mut window, &pairs, &arrival("a", 100, idx, 1_000), None);
+        }
+        process_arrival(&mut window, &pairs, &arrival("x", u64::MAX - 1, 0, 1_000), None);
+        process_arrival(&mut window, &pairs, &arrival("b", 100, 0, 2_000), None);
+        assert!(window.highest_slot.get() <= 100 + HORIZON_MAX_STEP);
+        assert_eq!(pairs.get(&key("a", "b")).unwrap().snapshot(None).a_wins, 1);
+        assert!(pairs.get(&key("a", "x")).is_none());
+
+        let mut slots = SlotWindow::default();
+        assert!(slots.slot(junk).is_some());
+        for slot in 300..300 + HORIZON_SEED_ARRIVALS as u64 {
+            assert!(slots.slot(slot).is_some());
+        }
+        assert!(slots.slot(u64::MAX - 1).is_none(), "junk past the seeded horizon");
+        assert!(slots.slot(300 + HORIZON_SEED_ARRIVALS as u64).is_some());
+        assert!(!slots.slots.contains_key(&junk));
+    }
+
+    #[test]
+    fn test_window_rejects_out_of_range_index() {
+        let mut window = ArrivalWindow::new();
+        assert!(window.entry(1, MAX_SHREDS_PER_SLOT).is_none());
+        assert!(window.entry(1, MAX_SHREDS_PER_SLOT - 1).is_some());
+    }
+
+    #[test]
+    fn test_slot_completion_race() {
+        let t = ShredRaceTracker::new();
+        t.record_slot_complete("b".into(), 200, MonotonicNs(5_000_000));
+        // Same feed again: ignored.
+        t.record_slot_complete("b".into(), 200, MonotonicNs(5_100_000));
+        t.record_slot_complete("a".into(), 200, MonotonicNs(7_000_000));
+        t.record_slot_complete("c".into(), 200, MonotonicNs(9_000_000));
+
+        let snaps = t.slot_snapshots();
+        assert_eq!(snaps.len(), 3, "c races both earlier completers");
+        let ab = snaps.iter().find(|s| (&*s.source_a, &*s.source_b) == ("a", "b")).unwrap();
+        assert_eq!((ab.a_wins, ab.b_wins), (0, 1));
+        assert_eq!(ab.lead_mean_us, Some(2_000.0));
+        assert!(t.snapshots().is_empty(), "shred race is separate");
+
+        // Slots that fell out of the window are ignored.
+        for slot in 201..=200 + SLOT_WINDOW_SLOTS {
+            t.record_slot_complete("a".into(), slot, MonotonicNs(1));
         }
-        Entry::Vacant(e) => {
-            e.insert(ShredFirstArrival { recv_ns, source, inserted_ns: now });
+        t.record_slot_complete("a".into(), 150, MonotonicNs(1));
+        t.record_slot_complete("b".into(), 150, MonotonicNs(2));
+        assert_eq!(t.slot_snapshots().iter().map(|s| s.total_matched).sum::<u64>(), 3);
+    }
+
+    #[test]
+    fn test_slot_start_race() {
+        let t = ShredRaceTracker::deterministic();
+        let tx = t.sender();
+        // Slot 401: "b" sends index 9 first, but "a" has index 0 300µs
+        // earlier; "a"'s later index 1 is not a new start.
+        tx.send(arrival("b", 401, 9, 2_000_000)).unwrap();
+        tx.send(arrival("a", 401, 0, 1_700_000)).unwrap();
+        tx.send(arrival("a", 401, 1, 1_800_000)).unwrap();
+        // Slot 404 opens a leader's window; "b" hears of it 1ms ahead.
+        tx.send(arrival("b", 404, 3, 5_000_000)).unwrap();
+        tx.send(arrival("a", 404, 0, 6_000_000)).unwrap();
+        // FEC-recovered shreds do not start a slot.
+        tx.send(ShredArrival { recovered: true, ..arrival("a", 405, 0, 7_000_000) }).unwrap();
+        tx.send(arrival("b", 405, 0, 7_500_000)).unwrap();
+        t.process_pending();
+
+        let start = &t.start_snapshots()[0];
+        assert_eq!((start.a_wins, start.b_wins), (1, 1));
+        assert_eq!(start.lead_mean_us, Some(650.0));
+        let handoff = &t.handoff_snapshots()[0];
+        assert_eq!((handoff.a_wins, handoff.b_wins), (0, 1));
+        assert_eq!(handoff.lead_mean_us, Some(1_000.0));
+
+        let restored = ShredRaceTracker::deterministic();
+        let names: Vec<Arc<str>> = vec!["a".into(), "b".into()];
+        assert_eq!(restored.restore_start_state(&t.export_start_state(), &names), 1);
+        assert_eq!(restored.restore_handoff_state(&t.export_handoff_state(), &names), 1);
+        assert_eq!(restored.start_snapshots()[0].total_matched, 2);
+        assert_eq!(restored.handoff_snapshots()[0].total_matched, 1);
+    }
+
+    #[test]
+    fn test_recovered_copy_races_availability_only() {
+        let t = ShredRaceTracker::new();
+        let tx = t.sender();
+        // "b" lost the packet but rebuilt it 400µs before "a" received it.
+        tx.send(ShredArrival { recovered: true, ..arrival("b", 300, 7, 1_000_000) }).unwrap();
+        tx.send(arrival("a", 300, 7, 1_400_000)).unwrap();
+        // Both received shred 8 — counts in both races.
+        tx.send(arrival("a", 300, 8, 2_000_000)).unwrap();
+        tx.send(arrival("b", 300, 8, 2_100_000)).unwrap();
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        let matched = |snaps: Vec<ShredPairSnapshot>| snaps.first().map_or(0, |s| s.total_matched);
+        while matched(t.avail_snapshots()) < 2 || matched(t.snapshots()) < 1 {
+            assert!(std::time::Instant::now() < deadline, "race thread did not catch up");
+            std::thread::sleep(std::time::Duration::from_millis(1));
         }
+
+        let avail = &t.avail_snapshots()[0];
+        assert_eq!((avail.a_wins, avail.b_wins, avail.fec_wins), (1, 1, 1));
+        let wire = &t.snapshots()[0];
+        assert_eq!((wire.a_wins, wire.b_wins, wire.fec_wins), (1, 0, 0));
+    }
+
+    #[test]
+    fn test_recent_windows() {
+        let recent = RecentRaces::new();
+        let min = |m: u64| MonotonicNs(m * MINUTE_NS);
+        recent.record(min(100), true, 100);
+        recent.record(min(150), false, 300);
+        recent.record(min(158).saturating_add(1), true, 500);
+        recent.record(min(159), false, 700);
+
+        let now = min(159).saturating_add(10);
+        let w5 = recent.window(now, 5).unwrap();
+        assert_eq!((w5.a_wins, w5.b_wins, w5.a_win_pct, w5.lead_mean_us), (1, 1, 50.0, 600.0));
+        let w1h = recent.window(now, 60).unwrap();
+        assert_eq!((w1h.a_wins, w1h.b_wins), (2, 2));
+        assert!(recent.window(min(300), 60).is_none());
+
+        // Minute 160 reuses minute 100's bucket, whose count is dropped.
+        recent.record(min(160), true, 0);
+        assert_eq!(recent.window(min(160), 61).unwrap().a_wins, 2);
+    }
+
+    #[test]
+    fn test_pair_rules_exclude_and_annotate() {
+        let tracker = ShredRaceTracker::deterministic();
+        let tx = tracker.sender();
+        for (source, recv_ns) in [("dz", 1_000), ("proxy", 2_000), ("rpc", 3_000)] {
+            tx.send(arrival(source, 100, 0, recv_ns)).unwrap();
+        }
+        tracker.process_pending();
+        assert_eq!(tracker.snapshots().len(), 3);
+
+        tracker.set_pair_rule("proxy", "dz", PairRule { exclude: true, note: None });
+        let note = PairRule { exclude: false, note: Some("same host".into()) };
+        tracker.set_pair_rule("rpc", "proxy", note);
+        let snaps = tracker.snapshots();
+        let pairs: Vec<(&str, &str, Option<&str>)> = snaps
+            .iter()
+            .map(|s| (&*s.source_a, &*s.source_b, s.note.as_deref()))
+            .collect();
+        assert_eq!(pairs, vec![("dz", "rpc", None), ("proxy", "rpc", Some("same host"))]);
+        assert_eq!(tracker.export_state().len(), 3, "excluded pairs are still kept");
     }
 }
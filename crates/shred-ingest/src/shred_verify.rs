@@ -0,0 +1,308 @@
+//! Leader signature checks on received shreds.
+//!
+//! A relay can inject shreds it made up, or replay shreds of other slots, and
+//! the decoder would take them like any others. With a [`ShredVerifier`] set,
+//! each shred-tier decoder checks every shred against the identity of the slot
+//! leader in a [`LeaderSchedule`], and counts the result on its source:
+//! `shreds_sig_verified`, `shreds_sig_invalid`, or `shreds_sig_unchecked`
+//! when the leader is not known yet or the shred is not a Merkle shred.
+//!
+//! A Merkle shred's signature covers the root of its FEC set's Merkle tree.
+//! The root is rebuilt from every shred and its proof, but the ed25519 check
+//! runs once per FEC set: a later shred of the set that leads to the same root
+//! and carries the same signature reuses the result. Legacy shreds, gone from
+//! mainnet, are not checked.
+//!
+//! With `drop_invalid`, a shred that fails the check is dropped before it is
+//! decoded. Shreds that could not be checked are always kept. The decoder
+//! then also reports shreds to the wire race, once they pass, in place of the
+//! receive threads: a forged shred must not win races, count toward coverage
+//! or start slots either.
+
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::{Arc, RwLock};
+
+use crate::decoder::{erasure_shard, parse_coding_header, shred_slot_index};
+use crate::source_metrics::SourceMetrics;
+
+const MERKLE_HASH_PREFIX_LEAF: &[u8] = b"\x00SOLANA_MERKLE_SHREDS_LEAF";
+const MERKLE_HASH_PREFIX_NODE: &[u8] = b"\x01SOLANA_MERKLE_SHREDS_NODE";
+const MERKLE_PROOF_ENTRY_SIZE: usize = 20;
+const SIGNATURE_SIZE: usize = 64;
+const VARIANT_OFF: usize = 64;
+
+/// Verified FEC sets kept per decoder before the oldest slots are dropped.
+const SIG_CACHE_CAP: usize = 8192;
+/// Slots behind the newest one whose FEC sets survive a cache prune.
+const SIG_CACHE_SLOTS: u64 = 32;
+
+/// Slot leaders by slot; cheap to clone, shared by every decoder.
+#[derive(Clone, Default)]
+pub struct LeaderSchedule {
+    leaders: Arc<RwLock<BTreeMap<u64, Pubkey>>>,
+}
+
+/// What a decoder checks shreds against.
+#[derive(Clone)]
+pub struct ShredVerifier {
+    pub leaders: LeaderSchedule,
+    /// Drop shreds whose signature does not match the slot leader.
+    pub drop_invalid: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Verdict {
+    Valid,
+    Invalid,
+    /// Leader unknown, or not a Merkle shred.
+    Unchecked,
+}
+
+/// FEC sets a decoder has already checked, by (slot, fec_set_index).
+#[derive(Default)]
+pub(crate) struct SigCache {
+    sets: HashMap<(u64, u32), CheckedSet>,
+    highest_slot: u64,
+}
+
+struct CheckedSet {
+    root: [u8; 32],
+    signature: [u8; 64],
+    valid: bool,
+}
+
+impl LeaderSchedule {
+    /// Record the leaders of `first_slot` and the slots after it.
+    pub fn insert(&self, first_slot: u64, leaders: &[Pubkey]) {
+        let mut map = self.leaders.write().unwrap();
+        for (i, leader) in leaders.iter().enumerate() {
+            map.insert(first_slot + i as u64, *leader);
+        }
+    }
+
+    pub fn leader(&self, slot: u64) -> Option<Pubkey> {
+        self.leaders.read().unwrap().get(&slot).copied()
+    }
+}
+
+impl ShredVerifier {
+    /// Whether shreds only enter the wire race once checked, from the
+    /// decoder rather than the receive threads.
+    pub(crate) fn gates_race(&self) -> bool {
+        self.drop_invalid
+    }
+
+    /// Check one shred and count the result on `metrics`. Returns whether
+    /// the shred should be decoded.
+    pub(crate) fn admit(&self, cache: &mut SigCache, pkt: &[u8], metrics: &SourceMetrics) -> bool {
+        match self.check(cache, pkt) {
+            Verdict::Valid => {
+                metrics.shreds_sig_verified.fetch_add(1, Relaxed);
+                true
+            }
+            Verdict::Invalid => {
+                metrics.shreds_sig_invalid.fetch_add(1, Relaxed);
+                !self.drop_invalid
+            }
+            Verdict::Unchecked => {
+                metrics.shreds_sig_unchecked.fetch_add(1, Relaxed);
+                true
+            }
+        }
+    }
+
+    pub(crate) fn check(&self, cache: &mut SigCache, pkt: &[u8]) -> Verdict {
+        let Some((slot, _, fec_set_index)) = shred_slot_index(pkt) else {
+            return Verdict::Unchecked;
+        };
+        let Some(root) = merkle_root(pkt) else { return Verdict::Unchecked };
+        let signature: [u8; 64] = pkt[..SIGNATURE_SIZE].try_into().unwrap();
+        let key = (slot, fec_set_index);
+        let cached = cache.sets.get(&key);
+        if let Some(set) = cached.filter(|s| s.root == root && s.signature == signature) {
+            return if set.valid { Verdict::Valid } else { Verdict::Invalid };
+        }
+        let Some(leader) = self.leaders.leader(slot) else { return Verdict::Unchecked };
+        let valid = Signature::from(signature).verify(leader.as_ref(), &root);
+        // A forged shred of a set must not displace the set's valid entry.
+        if valid || !cached.is_some_and(|s| s.valid) {
+            cache.insert(key, CheckedSet { root, signature, valid });
+        }
+        if valid { Verdict::Valid } else { Verdict::Invalid }
+    }
+}
+
+impl SigCache {
+    fn insert(&mut self, key: (u64, u32), set: CheckedSet) {
+        self.highest_slot = self.highest_slot.max(key.0);
+        if self.sets.len() >= SIG_CACHE_CAP {
+            let oldest = self.highest_slot.saturating_sub(SIG_CACHE_SLOTS);
+            self.sets.retain(|&(slot, _), _| slot >= oldest);
+        }
+        self.sets.insert(key, set);
+    }
+}
+
+/// Root of the FEC set's Merkle tree, rebuilt from the shred and its proof,
+/// or `None` for legacy and malformed shreds (Agave shred/merkle.rs).
+fn merkle_root(pkt: &[u8]) -> Option<[u8; 32]> {
+    let variant = *pkt.get(VARIANT_OFF)?;
+    // The proof follows the erasure-coded region.
+    let shard = erasure_shard(variant)?;
+    let proof_offset = shard.offset + shard.len;
+    let proof_size = (variant & 0x0F) as usize;
+    let proof = pkt.get(proof_offset..proof_offset + proof_size * MERKLE_PROOF_ENTRY_SIZE)?;
+    let (_, index, fec_set_index) = shred_slot_index(pkt)?;
+    let mut position = match parse_coding_header(pkt) {
+        Some(code) => code.num_data as usize + code.position as usize,
+        None => index.checked_sub(fec_set_index)? as usize,
+    };
+    let leaf = &pkt[SIGNATURE_SIZE..proof_offset];
+    let mut node = solana_sha256_hasher::hashv(&[MERKLE_HASH_PREFIX_LEAF, leaf]).to_bytes();
+    for sibling in proof.chunks_exact(MERKLE_PROOF_ENTRY_SIZE) {
+        let node_prefix = &node[..MERKLE_PROOF_ENTRY_SIZE];
+        let (left, right) = if position % 2 == 0 {
+            (node_prefix, sibling)
+        } else {
+            (sibling, node_prefix)
+        };
+        node = solana_sha256_hasher::hashv(&[MERKLE_HASH_PREFIX_NODE, left, right]).to_bytes();
+        position >>= 1;
+    }
+    (position == 0).then_some(node)
+}
+
+// ---------------------------------------------------------------------------
+// Refresh from RPC
+// ---------------------------------------------------------------------------
+
+/// Filling the schedule from a JSON-RPC endpoint (`rpc` feature). Without
+/// it, embedders [`LeaderSchedule::insert`] the leaders themselves.
+#[cfg(feature = "rpc")]
+mod refresh {
+    use anyhow::Result;
+    use solana_client::rpc_client::RpcClient;
+    use solana_commitment_config::CommitmentConfig;
+    use std::thread::JoinHandle;
+    use std::time::Duration;
+
+    use super::LeaderSchedule;
+
+    /// Leaders are fetched this many slots past the cluster tip.
+    const LEADER_LOOKAHEAD: u64 = 2000;
+    /// Leaders of slots this far behind the tip are forgotten.
+    const LEADER_KEEP_BEHIND: u64 = 1000;
+    const LEADER_REFRESH: Duration = Duration::from_secs(10);
+
+    impl LeaderSchedule {
+        /// Keep the schedule filled from `rpc_url` with `getSlotLeaders`, from
+        /// a little behind the cluster tip to [`LEADER_LOOKAHEAD`] slots past it.
+        pub fn spawn_refresh(&self, rpc_url: String) -> std::io::Result<JoinHandle<()>> {
+            let schedule = self.clone();
+            std::thread::Builder::new().name("leader-schedule".into()).spawn(move || {
+                let rpc = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+                let mut failing = false;
+                loop {
+                    match schedule.refresh(&rpc) {
+                        Ok(()) if failing => {
+                            failing = false;
+                            tracing::info!("shred_verify: leader schedule reachable again");
+                        }
+                        Ok(()) => {}
+                        Err(e) if !failing => {
+                            failing = true;
+                            tracing::warn!("shred_verify: leader schedule fetch failed: {:#}", e);
+                        }
+                        Err(_) => {}
+                    }
+                    std::thread::sleep(LEADER_REFRESH);
+                }
+            })
+        }
+
+        fn refresh(&self, rpc: &RpcClient) -> Result<()> {
+            let tip = rpc.get_slot()?;
+            let lo = tip.saturating_sub(LEADER_KEEP_BEHIND);
+            let known = self.leaders.read().unwrap().last_key_value().map(|(&s, _)| s + 1);
+            let start = known.unwrap_or(0).max(lo);
+            let end = tip + LEADER_LOOKAHEAD;
+            if start < end {
+                let leaders = rpc.get_slot_leaders(start, end - start)?;
+                self.insert(start, &leaders);
+            }
+            let mut map = self.leaders.write().unwrap();
+            *map = map.split_off(&lo);
+            Ok(())
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chained Merkle data shred (variant 0x91: proof of one entry) at
+    /// `index` of the FEC set starting at 0, with `sibling` as its proof.
+    fn data_shred(slot: u64, index: u32, sibling: [u8; 20]) -> Vec<u8> {
+        let mut buf = vec![0u8; 1203];
+        buf[VARIANT_OFF] = 0x91;
+        buf[65..73].copy_from_slice(&slot.to_le_bytes());
+        buf[73..77].copy_from_slice(&index.to_le_bytes());
+        buf[100] = index as u8;
+        let shard = erasure_shard(0x91).unwrap();
+        let proof_offset = shard.offset + shard.len;
+        buf[proof_offset..proof_offset + 20].copy_from_slice(&sibling);
+        buf
+    }
+
+    fn leaf(pkt: &[u8]) -> [u8; 20] {
+        let shard = erasure_shard(pkt[VARIANT_OFF]).unwrap();
+        let end = shard.offset + shard.len;
+        let hash = solana_sha256_hasher::hashv(&[MERKLE_HASH_PREFIX_LEAF, &pkt[64..end]]);
+        hash.to_bytes()[..20].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_merkle_root_agrees_across_a_set() {
+        // Two leaves, each the other's proof: both must lead to one root.
+        let mut a = data_shred(7, 0, [0; 20]);
+        let mut b = data_shred(7, 1, [0; 20]);
+        let (la, lb) = (leaf(&a), leaf(&b));
+        let offset = {
+            let shard = erasure_shard(0x91).unwrap();
+            shard.offset + shard.len
+        };
+        a[offset..offset + 20].copy_from_slice(&lb);
+        b[offset..offset + 20].copy_from_slice(&la);
+        let expected = solana_sha256_hasher::hashv(&[MERKLE_HASH_PREFIX_NODE, &la, &lb]);
+        assert_eq!(merkle_root(&a), Some(expected.to_bytes()));
+        assert_eq!(merkle_root(&b), Some(expected.to_bytes()));
+
+        // Legacy shreds have no root.
+        let mut legacy = a.clone();
+        legacy[VARIANT_OFF] = 0xa5;
+        assert_eq!(merkle_root(&legacy), None);
+    }
+
+    #[test]
+    fn test_unsigned_shred_is_invalid_once_leader_known() {
+        let verifier = ShredVerifier { leaders: LeaderSchedule::default(), drop_invalid: true };
+        let metrics = SourceMetrics::new("relay".into(), false);
+        let mut cache = SigCache::default();
+        let pkt = data_shred(7, 0, [0; 20]);
+
+        assert!(verifier.admit(&mut cache, &pkt, &metrics), "leader unknown: kept");
+        verifier.leaders.insert(7, &[Pubkey::new_from_array([9; 32])]);
+        assert!(!verifier.admit(&mut cache, &pkt, &metrics));
+        assert!(!verifier.admit(&mut cache, &pkt, &metrics), "cached verdict");
+        assert_eq!(metrics.shreds_sig_unchecked.load(Relaxed), 1);
+        assert_eq!(metrics.shreds_sig_invalid.load(Relaxed), 2);
+    }
+}
@@ -19,19 +19,20 @@ pub enum SourceConfig {
         shred_version: Option<u16>,
     },
     /// RPC block polling (highest latency, always available)
+    #[cfg(feature = "rpc")]
     Rpc { url: String },
 }
 
 /// Start the configured transaction source on a new thread.
 pub fn start_source(
     config: SourceConfig,
-    tx: Sender<DecodedTx>,
+    tx: Sender<Vec<DecodedTx>>,
     pin_core: Option<usize>,
     metrics: Arc<SourceMetrics>,
 ) -> Result<std::thread::JoinHandle<()>> {
     match config {
         SourceConfig::Shred { multicast_addr, port, interface, shred_version } => {
-            let (shred_tx, shred_rx) = crossbeam_channel::bounded(4096);
+            let (shred_tx, shred_rx) = crate::receiver::shred_queue(1);
 
             let recv_metrics = metrics.clone();
             let handle = std::thread::Builder::new()
@@ -63,6 +64,7 @@ pub fn start_source(
 
             Ok(handle)
         }
+        #[cfg(feature = "rpc")]
         SourceConfig::Rpc { url } => {
             let handle = std::thread::Builder::new()
                 .name("rpc-source".into())
@@ -1,8 +1,14 @@
-use serde::Serialize;
-use std::collections::VecDeque;
-use std::sync::atomic::{AtomicI64, AtomicU64, Ordering::Relaxed};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering::Relaxed};
 use std::sync::{Arc, Mutex};
 
+use crate::coverage::{PositionCoverage, PositionCoverageSnapshot};
+use crate::lead_hist::{AtomicLeadHistogram, LeadHistogram};
+use crate::senders::{SenderMix, SenderMixSnapshot};
+use crate::shred_mix::{ShredMix, ShredMixSnapshot};
+use crate::tx_profile::{EarlyTxSnapshot, EarlyTxStats, TxProfile};
+
 // ---------------------------------------------------------------------------
 // Per-slot stats emitted by the decoder when a slot is finalised
 // ---------------------------------------------------------------------------
@@ -11,6 +17,18 @@ use std::sync::{Arc, Mutex};
 /// At ~400ms per slot this covers roughly 3 minutes of history.
 const SLOT_LOG_CAP: usize = 500;
 
+/// Confirmations for slots this source has not finalized yet are held until
+/// its decoder is this many slots past them (well beyond the decoder's own
+/// expiry window), then counted as missed.
+const PENDING_CONFIRM_HORIZON: u64 = 64;
+
+/// Upper bound on held confirmations, for a source that has gone silent.
+const PENDING_CONFIRM_CAP: usize = 1024;
+
+/// First copies of a slot forwarded after it was finalized are matched
+/// against this many of the newest slot log entries.
+const FIRSTS_LOOKBACK: usize = 32;
+
 /// Outcome of a single slot's decode attempt.
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -21,6 +39,36 @@ pub enum SlotOutcome {
     Partial,
     /// The slot expired with no decoded transactions.
     Dropped,
+    /// Delivered by a transaction stream (`geyser`, `jito-grpc`), which has
+    /// no shred counts to judge completeness by; see [`crate::stream_slots`].
+    Streamed,
+}
+
+/// What went wrong in a [`SourceError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceErrorKind {
+    /// A receive socket could not be created, bound or joined to its group.
+    Bind,
+    /// A receive loop stopped on a socket error.
+    Receive,
+    /// A gRPC stream ended; the source reconnects.
+    Disconnect,
+    /// An RPC request or `blockSubscribe` failed.
+    Rpc,
+    /// A jito-udp heartbeat registration was lost.
+    Registration,
+    /// A source thread panicked or stopped; see [`crate::supervise`].
+    Crash,
+}
+
+/// A failure reported by one of a source's threads.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceError {
+    pub kind: SourceErrorKind,
+    pub message: String,
+    /// When it was reported, as Unix seconds.
+    pub at: u64,
 }
 
 /// Per-slot decode statistics collected by [`ShredDecoder`].
@@ -33,7 +81,77 @@ pub struct SlotStats {
     pub fec_recovered: u32,
     /// Transactions decoded from this slot.
     pub txs_decoded: u32,
+    /// Data shreds between the first index seen and the highest index seen
+    /// (inclusive). Relays that only forward the tail of a block anchor at their
+    /// first shred, so this is the denominator for per-slot coverage. Zero if no
+    /// data shred index was ever observed.
+    pub shreds_expected: u32,
+    /// Time from the first shred of this slot to completion, in µs.
+    /// `None` unless the outcome is `Complete`. For `Streamed` slots, the
+    /// time from the first message of the slot to the last.
+    pub completion_us: Option<u64>,
+    /// Receive time of the shred that completed the slot, as Unix-epoch ns.
+    /// Comparable across hosts whose clocks are synchronized. `None` unless
+    /// the outcome is `Complete`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at_ns: Option<u64>,
+    /// Receive time of the slot's first transaction, as Unix-epoch ns. Only
+    /// set for [`SlotOutcome::Streamed`] slots.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_at_ns: Option<u64>,
     pub outcome: SlotOutcome,
+    /// Transactions in the block the cluster confirmed for this slot, once a
+    /// baseline source has reported it (see [`SourceMetrics::confirm_slot`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmed_txs: Option<u32>,
+    /// The cluster skipped this slot: the shreds seen belong to a block that
+    /// was never confirmed.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub skipped: bool,
+    /// Longest gap between two directly received data shreds, in µs.
+    /// `None` with fewer than two.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_gap_us: Option<u64>,
+    /// Standard deviation of the gaps between data shreds, in µs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gap_stddev_us: Option<u64>,
+    /// FEC sets announced by a coding shred, and those of them whose data
+    /// shreds all arrived or were recovered.
+    pub fec_sets: u32,
+    pub fec_sets_complete: u32,
+    /// Entries deserialized from this slot.
+    pub entries: u32,
+    /// Transactions of this slot this source forwarded as first copies.
+    /// Counted by the fan-in as they are forwarded, so it can still grow
+    /// for a moment after the slot is logged.
+    pub txs_first: u32,
+}
+
+/// The cluster's verdict on a slot, as reported by a baseline source that
+/// sees whole confirmed blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    /// A block was confirmed with this many transactions.
+    Confirmed(u32),
+    /// No block was confirmed for the slot.
+    Skipped,
+}
+
+/// Hands a baseline source's slot verdicts to every shred-tier source.
+pub struct SlotConfirmations {
+    feeds: Vec<Arc<SourceMetrics>>,
+}
+
+impl SlotConfirmations {
+    pub fn new(feeds: Vec<Arc<SourceMetrics>>) -> Self {
+        Self { feeds }
+    }
+
+    pub fn report(&self, slot: u64, confirmation: Confirmation) {
+        for feed in &self.feeds {
+            feed.confirm_slot(slot, confirmation);
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -67,6 +185,17 @@ impl LeadTimeReservoir {
         }
     }
 
+    /// Samples in insertion order (oldest first).
+    fn samples(&self) -> Vec<i64> {
+        if self.len < RESERVOIR_CAP {
+            self.buf[..self.len].to_vec()
+        } else {
+            let mut out = self.buf[self.pos..].to_vec();
+            out.extend_from_slice(&self.buf[..self.pos]);
+            out
+        }
+    }
+
     /// Returns (p50, p95, p99) in µs, or None if empty.
     /// Sorts a clone of the buffer — called at most once every snapshot interval.
     fn percentiles(&self) -> Option<(i64, i64, i64)> {
@@ -83,6 +212,17 @@ impl LeadTimeReservoir {
     }
 }
 
+/// p95 of a handful of per-slot values, or None if there are none.
+fn p95(values: impl Iterator<Item = u64>) -> Option<u64> {
+    let mut sorted: Vec<u64> = values.collect();
+    if sorted.is_empty() {
+        return None;
+    }
+    sorted.sort_unstable();
+    let n = sorted.len();
+    Some(sorted[(n * 95 / 100).min(n - 1)])
+}
+
 // ---------------------------------------------------------------------------
 // SourceMetrics
 // ---------------------------------------------------------------------------
@@ -90,7 +230,10 @@ impl LeadTimeReservoir {
 /// Atomic per-source quality counters.
 /// All atomic writes use Relaxed ordering — these are sampling metrics, not synchronisation.
 pub struct SourceMetrics {
-    pub name: &'static str,
+    pub name: Arc<str>,
+    /// Free-form tags from the source's `labels` config (region, provider, …),
+    /// carried into every output so sources can be grouped without parsing names.
+    pub labels: Arc<BTreeMap<String, String>>,
     /// True for RPC-tier sources (rpc, geyser); false for shred-tier feeds.
     /// Used by the dashboard to show `—` instead of 0 for shred-only columns.
     pub is_rpc: bool,
@@ -101,13 +244,55 @@ pub struct SourceMetrics {
     /// Shreds silently dropped because the receiver→decoder channel was full
     /// (backpressure from the decoder falling behind).
     pub shreds_dropped: AtomicU64,
-    /// Packets rejected in the hot path: too short, unknown variant byte, or
-    /// heartbeat packets. These never reach the decoder.
+    /// Shred-shaped packets rejected in the hot path because their variant
+    /// byte is not one the decoder understands. These never reach the decoder.
     pub shreds_invalid: AtomicU64,
+    /// Packets that cannot be shreds at all — too short, a variant byte no
+    /// shred type can encode, or a slot far beyond any recently seen — such as
+    /// control traffic sharing the multicast group. Not counted anywhere else.
+    pub non_shred_packets: AtomicU64,
+    /// Shreds received by variant family, counted after the non-shred
+    /// pre-filter but before the variant filter, and by shred version once
+    /// accepted.
+    pub shred_mix: ShredMix,
+    /// Shreds received per UDP sender address, and how many of them each
+    /// sender delivered first. Counted alongside `shred_mix`.
+    pub senders: SenderMix,
     /// Monotonic nanosecond timestamp of the last DoubleZero heartbeat packet
     /// received on this source's socket. Zero if no heartbeat has been seen.
     /// Heartbeat magic: `0x44 0x5A 0x00 0x01` ("DZ\x00\x01").
     pub last_heartbeat_ns: AtomicU64,
+    /// Set at runtime (e.g. `shredtop ctl pause`). While true, the receive
+    /// loop discards everything it reads and the fan-in relay drops this
+    /// source's transactions, so it contributes nothing to races or lead times.
+    pub paused: AtomicBool,
+    /// Standby member of a failover group (see [`crate::failover`]). Packets
+    /// are still counted as received — that is how liveness is judged — but
+    /// nothing is forwarded to the decoder, fan-in or shred race.
+    pub standby: AtomicBool,
+    /// Subtracted from this source's receive timestamps before dedup and
+    /// lead-time accounting: the configured `timestamp_offset_us` plus any
+    /// auto-calibrated decode overhead (see [`crate::calibration`]).
+    pub recv_offset_ns: AtomicI64,
+    /// RPC sources: slots still to fetch behind the cluster tip, as of the
+    /// last poll or catch-up window. Stays above 0 while a backlog drains.
+    pub rpc_slot_lag: AtomicU64,
+
+    // Kernel receive socket, sampled via SO_MEMINFO (UDP sources on Linux only)
+    /// Receive buffer the kernel actually granted (sk_rcvbuf), in bytes.
+    pub socket_rcvbuf_bytes: AtomicU64,
+    /// Highest receive-queue memory use observed (sk_rmem_alloc), in bytes.
+    /// For a fanout group this is the fullest single socket.
+    pub socket_rmem_hwm_bytes: AtomicU64,
+    /// Datagrams the kernel discarded because the receive buffer was full.
+    pub socket_drops: AtomicU64,
+    /// recvmmsg calls that returned packets, and the packets they returned.
+    pub recv_calls: AtomicU64,
+    pub recv_packets: AtomicU64,
+    /// Receive-thread time blocked in recvmmsg, and spent handling the
+    /// batches it returned. Summed over the threads of a fanout group.
+    pub recv_wait_ns: AtomicU64,
+    pub recv_busy_ns: AtomicU64,
 
     // Slot outcomes
     pub slots_attempted: AtomicU64,
@@ -118,17 +303,68 @@ pub struct SourceMetrics {
     // Coverage (data shreds)
     pub coverage_shreds_seen: AtomicU64,
     pub coverage_shreds_expected: AtomicU64,
+    /// Coverage split into head / middle / tail thirds of each slot whose
+    /// length is known, counted when the decoder retires the slot.
+    pub position_coverage: PositionCoverage,
 
     // FEC recovery
     pub fec_recovered_shreds: AtomicU64,
 
+    // Repair (see [`crate::repair`])
+    /// Data shreds requested from repair peers.
+    pub repair_requests: AtomicU64,
+    /// Missing data shreds a repair response filled. Not in `shreds_received`.
+    pub shreds_repaired: AtomicU64,
+
+    /// Shreds left undecoded because their slot was too far behind the
+    /// cluster tip (see [`crate::decoder::ShredDecoder::with_max_lag_slots`]).
+    /// Still in `shreds_received` and the races.
+    pub shreds_decode_skipped: AtomicU64,
+    /// Slots expired because the shared cluster tip passed them while this
+    /// feed's own highest slot would have kept them (see
+    /// [`crate::coverage::SlotExpiry`]).
+    pub slots_expired_shared_tip: AtomicU64,
+
+    // Shred signatures (see [`crate::shred_verify`])
+    /// Shreds signed by the scheduled leader.
+    pub shreds_sig_verified: AtomicU64,
+    /// Shreds whose signature does not match the scheduled leader.
+    pub shreds_sig_invalid: AtomicU64,
+    /// Shreds not checked: leader unknown, or the shred unparseable.
+    pub shreds_sig_unchecked: AtomicU64,
+
     // Tx flow
     pub txs_decoded: AtomicU64,
     pub txs_emitted: AtomicU64,
+    /// Decoded transactions lost because the channel to the fan-in was full
+    /// (the fan-in falling behind), counted per dropped batch.
+    pub txs_dropped: AtomicU64,
     /// Won the fan-in dedup race (first arrival)
     pub txs_first: AtomicU64,
     /// Lost the fan-in dedup race (duplicate)
     pub txs_duplicate: AtomicU64,
+    /// Delivered in a slot its signature was not first seen in (resubmitted
+    /// after a fork was abandoned)
+    pub txs_slot_mismatch: AtomicU64,
+
+    // Cross-check against confirmed blocks (shred-tier sources, needs an `rpc` baseline)
+    /// Finalized slots matched to a confirmed block.
+    pub confirmed_slots: AtomicU64,
+    /// Confirmed blocks this source produced no slot record for.
+    pub confirmed_slots_missed: AtomicU64,
+    /// Finalized slots the cluster skipped.
+    pub skipped_slots_seen: AtomicU64,
+    /// Transactions decoded from confirmed slots, capped per slot at the block's count.
+    pub confirmed_txs_seen: AtomicU64,
+    /// Transactions in all confirmed blocks, including missed ones.
+    pub confirmed_txs_total: AtomicU64,
+    /// Set by a headers-only decoder; its slots carry no transaction counts,
+    /// so only the slot-level cross-check applies.
+    pub(crate) headers_only: AtomicBool,
+    /// Confirmations that arrived before this source finalized the slot.
+    pending_confirmations: Mutex<BTreeMap<u64, Confirmation>>,
+    /// First copies forwarded for slots this source has not finalized yet.
+    pending_firsts: Mutex<BTreeMap<u64, u32>>,
 
     // Lead time relative to RPC (µs, positive = shred arrived before RPC)
     pub lead_time_count: AtomicU64,
@@ -137,84 +373,449 @@ pub struct SourceMetrics {
     pub lead_time_sum_us: AtomicI64,
     /// Rolling reservoir of recent samples; sorted at snapshot time to compute percentiles.
     lead_time_reservoir: Mutex<LeadTimeReservoir>,
+    /// Every accepted sample since start (see [`crate::lead_hist`]).
+    lead_time_hist: AtomicLeadHistogram,
 
     /// Rolling log of per-slot decode outcomes emitted by the decoder.
     /// Capped at SLOT_LOG_CAP; oldest entries are evicted when full.
     /// Only populated for shred-type sources (never for RPC/Geyser).
     slot_log: Mutex<VecDeque<SlotStats>>,
+
+    /// Fee/priority distribution of transactions that beat RPC by more than the
+    /// fan-in's early threshold. Empty unless the threshold is configured.
+    early_txs: Mutex<EarlyTxStats>,
+
+    /// Failures reported by the source's threads (see [`Self::record_error`]).
+    pub errors_total: AtomicU64,
+    last_error: Mutex<Option<SourceError>>,
+    /// Times the source was restarted after its threads stopped, and whether
+    /// it is down now (see [`crate::supervise`]).
+    pub restarts: AtomicU64,
+    pub degraded: AtomicBool,
 }
 
 /// Plain-struct snapshot of SourceMetrics for display (no atomics).
 #[derive(Debug, Clone)]
 pub struct SourceMetricsSnapshot {
-    pub name: &'static str,
+    pub name: Arc<str>,
+    pub labels: Arc<BTreeMap<String, String>>,
     pub is_rpc: bool,
     pub shreds_received: u64,
     pub bytes_received: u64,
     pub shreds_dropped: u64,
     pub shreds_invalid: u64,
+    pub non_shred_packets: u64,
+    pub shred_mix: ShredMixSnapshot,
+    pub senders: SenderMixSnapshot,
     /// Seconds since the last DZ heartbeat, or None if no heartbeat ever seen.
     pub secs_since_heartbeat: Option<u64>,
+    pub paused: bool,
+    pub standby: bool,
+    pub recv_offset_ns: i64,
+    pub rpc_slot_lag: u64,
+    pub socket_rcvbuf_bytes: u64,
+    pub socket_rmem_hwm_bytes: u64,
+    pub socket_drops: u64,
+    pub recv_calls: u64,
+    pub recv_packets: u64,
+    pub recv_wait_ns: u64,
+    pub recv_busy_ns: u64,
     pub slots_attempted: u64,
     pub slots_complete: u64,
     pub slots_partial: u64,
     pub slots_dropped: u64,
     pub coverage_shreds_seen: u64,
     pub coverage_shreds_expected: u64,
+    pub position_coverage: PositionCoverageSnapshot,
     pub fec_recovered_shreds: u64,
+    pub repair_requests: u64,
+    pub shreds_repaired: u64,
+    pub shreds_decode_skipped: u64,
+    pub slots_expired_shared_tip: u64,
+    pub shreds_sig_verified: u64,
+    pub shreds_sig_invalid: u64,
+    pub shreds_sig_unchecked: u64,
     pub txs_decoded: u64,
     pub txs_emitted: u64,
+    pub txs_dropped: u64,
     pub txs_first: u64,
     pub txs_duplicate: u64,
+    pub txs_slot_mismatch: u64,
+    pub confirmed_slots: u64,
+    pub confirmed_slots_missed: u64,
+    pub skipped_slots_seen: u64,
+    pub confirmed_txs_seen: u64,
+    pub confirmed_txs_total: u64,
     pub lead_time_count: u64,
     pub lead_wins: u64,
     pub lead_time_sum_us: i64,
     pub lead_time_p50_us: Option<i64>,
     pub lead_time_p95_us: Option<i64>,
     pub lead_time_p99_us: Option<i64>,
+    pub lead_time_hist: LeadHistogram,
+    /// p95 over the slot log of each slot's longest shred inter-arrival gap, µs.
+    pub slot_max_gap_p95_us: Option<u64>,
+    /// p95 over the slot log of each slot's inter-arrival standard deviation, µs.
+    pub slot_jitter_p95_us: Option<u64>,
     /// Per-slot decode outcomes from the rolling log (up to SLOT_LOG_CAP entries).
     pub slot_log: Vec<SlotStats>,
+    /// Distribution of transactions that beat RPC by more than the early threshold.
+    pub early_txs: EarlyTxSnapshot,
+    pub errors_total: u64,
+    /// Most recent failure reported by the source, if any.
+    pub last_error: Option<SourceError>,
+    pub restarts: u64,
+    pub degraded: bool,
+}
+
+impl SourceMetricsSnapshot {
+    /// Data shreds seen against those the FEC sets and slot indices imply.
+    /// An estimate: a feed that only relays the tail of each block looks
+    /// complete because the missing head never announces itself.
+    pub fn shred_coverage_pct(&self) -> Option<f64> {
+        if self.coverage_shreds_expected == 0 {
+            return None;
+        }
+        let seen = self.coverage_shreds_seen as f64;
+        Some((seen / self.coverage_shreds_expected as f64 * 100.0).min(100.0))
+    }
+
+    /// Decoded share of the transactions in confirmed blocks, missed slots
+    /// included, or None before a confirmed block has been cross-checked.
+    pub fn confirmed_coverage_pct(&self) -> Option<f64> {
+        if self.confirmed_txs_total == 0 {
+            return None;
+        }
+        Some(self.confirmed_txs_seen as f64 / self.confirmed_txs_total as f64 * 100.0)
+    }
+
+    /// Whether any slot has been checked against the cluster.
+    pub fn cross_checked(&self) -> bool {
+        self.confirmed_slots + self.confirmed_slots_missed + self.skipped_slots_seen > 0
+    }
+
+    /// Share of receive-thread time spent handling packets rather than
+    /// waiting in recvmmsg, %, or None before the first batch.
+    pub fn recv_utilization_pct(&self) -> Option<f64> {
+        let total = self.recv_wait_ns + self.recv_busy_ns;
+        (total > 0).then(|| self.recv_busy_ns as f64 * 100.0 / total as f64)
+    }
+
+    /// Mean packets returned per recvmmsg call, or None before the first.
+    pub fn recv_batch_fill(&self) -> Option<f64> {
+        (self.recv_calls > 0).then(|| self.recv_packets as f64 / self.recv_calls as f64)
+    }
+}
+
+/// The plain counters of a [`SourceMetrics`], read without taking any lock
+/// or computing percentiles, so they can be sampled several times a second.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SourceCounters {
+    pub shreds_received: u64,
+    pub txs_decoded: u64,
+    pub txs_first: u64,
+    pub slots_complete: u64,
+    pub slots_partial: u64,
+    pub slots_dropped: u64,
+    pub fec_recovered_shreds: u64,
+    pub lead_time_count: u64,
+    pub lead_wins: u64,
+    pub lead_time_sum_us: i64,
+}
+
+impl SourceCounters {
+    /// What was counted since `earlier`. Counters that went backwards (a
+    /// state restore in between) count as zero.
+    pub fn since(&self, earlier: &SourceCounters) -> SourceCounters {
+        let lead_time_count = self.lead_time_count.saturating_sub(earlier.lead_time_count);
+        SourceCounters {
+            shreds_received: self.shreds_received.saturating_sub(earlier.shreds_received),
+            txs_decoded: self.txs_decoded.saturating_sub(earlier.txs_decoded),
+            txs_first: self.txs_first.saturating_sub(earlier.txs_first),
+            slots_complete: self.slots_complete.saturating_sub(earlier.slots_complete),
+            slots_partial: self.slots_partial.saturating_sub(earlier.slots_partial),
+            slots_dropped: self.slots_dropped.saturating_sub(earlier.slots_dropped),
+            fec_recovered_shreds: self
+                .fec_recovered_shreds
+                .saturating_sub(earlier.fec_recovered_shreds),
+            lead_time_count,
+            lead_wins: self.lead_wins.saturating_sub(earlier.lead_wins),
+            lead_time_sum_us: if lead_time_count > 0 {
+                self.lead_time_sum_us - earlier.lead_time_sum_us
+            } else {
+                0
+            },
+        }
+    }
+}
+
+/// Cumulative counters and lead-time samples of a [`SourceMetrics`], in a form
+/// that can be written to disk on shutdown and merged back in on startup.
+/// Rates, heartbeat age, socket statistics and the slot log are deliberately
+/// not persisted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SourceMetricsState {
+    pub shreds_received: u64,
+    pub bytes_received: u64,
+    pub shreds_dropped: u64,
+    pub shreds_invalid: u64,
+    pub non_shred_packets: u64,
+    pub slots_attempted: u64,
+    pub slots_complete: u64,
+    pub slots_partial: u64,
+    pub slots_dropped: u64,
+    pub coverage_shreds_seen: u64,
+    pub coverage_shreds_expected: u64,
+    pub position_coverage: PositionCoverageSnapshot,
+    pub fec_recovered_shreds: u64,
+    pub repair_requests: u64,
+    pub shreds_repaired: u64,
+    pub shreds_decode_skipped: u64,
+    pub slots_expired_shared_tip: u64,
+    pub shreds_sig_verified: u64,
+    pub shreds_sig_invalid: u64,
+    pub shreds_sig_unchecked: u64,
+    pub txs_decoded: u64,
+    pub txs_emitted: u64,
+    pub txs_dropped: u64,
+    pub txs_first: u64,
+    pub txs_duplicate: u64,
+    pub txs_slot_mismatch: u64,
+    pub confirmed_slots: u64,
+    pub confirmed_slots_missed: u64,
+    pub skipped_slots_seen: u64,
+    pub confirmed_txs_seen: u64,
+    pub confirmed_txs_total: u64,
+    pub lead_time_count: u64,
+    pub lead_wins: u64,
+    pub lead_time_sum_us: i64,
+    /// Reservoir contents, oldest first.
+    pub lead_time_samples: Vec<i64>,
+    pub lead_time_hist: LeadHistogram,
 }
 
 impl SourceMetrics {
-    pub fn new(name: &'static str, is_rpc: bool) -> Arc<Self> {
+    pub fn new(name: Arc<str>, is_rpc: bool) -> Arc<Self> {
+        Self::with_labels(name, is_rpc, BTreeMap::new())
+    }
+
+    pub fn with_labels(
+        name: Arc<str>,
+        is_rpc: bool,
+        labels: BTreeMap<String, String>,
+    ) -> Arc<Self> {
         Arc::new(Self {
             name,
+            labels: Arc::new(labels),
             is_rpc,
             shreds_received: AtomicU64::new(0),
             bytes_received: AtomicU64::new(0),
             shreds_dropped: AtomicU64::new(0),
             shreds_invalid: AtomicU64::new(0),
+            non_shred_packets: AtomicU64::new(0),
+            shred_mix: ShredMix::default(),
+            senders: SenderMix::default(),
             last_heartbeat_ns: AtomicU64::new(0),
+            paused: AtomicBool::new(false),
+            standby: AtomicBool::new(false),
+            recv_offset_ns: AtomicI64::new(0),
+            rpc_slot_lag: AtomicU64::new(0),
+            socket_rcvbuf_bytes: AtomicU64::new(0),
+            socket_rmem_hwm_bytes: AtomicU64::new(0),
+            socket_drops: AtomicU64::new(0),
+            recv_calls: AtomicU64::new(0),
+            recv_packets: AtomicU64::new(0),
+            recv_wait_ns: AtomicU64::new(0),
+            recv_busy_ns: AtomicU64::new(0),
             slots_attempted: AtomicU64::new(0),
             slots_complete: AtomicU64::new(0),
             slots_partial: AtomicU64::new(0),
             slots_dropped: AtomicU64::new(0),
             coverage_shreds_seen: AtomicU64::new(0),
             coverage_shreds_expected: AtomicU64::new(0),
+            position_coverage: PositionCoverage::default(),
             fec_recovered_shreds: AtomicU64::new(0),
+            repair_requests: AtomicU64::new(0),
+            shreds_repaired: AtomicU64::new(0),
+            shreds_decode_skipped: AtomicU64::new(0),
+            slots_expired_shared_tip: AtomicU64::new(0),
+            shreds_sig_verified: AtomicU64::new(0),
+            shreds_sig_invalid: AtomicU64::new(0),
+            shreds_sig_unchecked: AtomicU64::new(0),
             txs_decoded: AtomicU64::new(0),
             txs_emitted: AtomicU64::new(0),
+            txs_dropped: AtomicU64::new(0),
             txs_first: AtomicU64::new(0),
             txs_duplicate: AtomicU64::new(0),
+            txs_slot_mismatch: AtomicU64::new(0),
+            confirmed_slots: AtomicU64::new(0),
+            confirmed_slots_missed: AtomicU64::new(0),
+            skipped_slots_seen: AtomicU64::new(0),
+            confirmed_txs_seen: AtomicU64::new(0),
+            confirmed_txs_total: AtomicU64::new(0),
+            headers_only: AtomicBool::new(false),
+            pending_confirmations: Mutex::new(BTreeMap::new()),
+            pending_firsts: Mutex::new(BTreeMap::new()),
             lead_time_count: AtomicU64::new(0),
             lead_wins: AtomicU64::new(0),
             lead_time_sum_us: AtomicI64::new(0),
             lead_time_reservoir: Mutex::new(LeadTimeReservoir::new()),
+            lead_time_hist: AtomicLeadHistogram::default(),
             slot_log: Mutex::new(VecDeque::with_capacity(SLOT_LOG_CAP)),
+            early_txs: Mutex::new(EarlyTxStats::default()),
+            errors_total: AtomicU64::new(0),
+            last_error: Mutex::new(None),
+            restarts: AtomicU64::new(0),
+            degraded: AtomicBool::new(false),
         })
     }
 
     /// Record a per-slot decode outcome from the shred decoder.
     /// The log is bounded to SLOT_LOG_CAP entries; the oldest entry is dropped when full.
-    pub fn push_slot_stats(&self, stats: SlotStats) {
+    pub fn push_slot_stats(&self, mut stats: SlotStats) {
+        // Lock order: slot log, then pending confirmations (as in `confirm_slot`).
         let mut log = self.slot_log.lock().unwrap();
+        let mut pending = self.pending_confirmations.lock().unwrap();
+        if let Some(c) = pending.remove(&stats.slot) {
+            self.apply_confirmation(&mut stats, c);
+        }
+        while let Some(entry) = pending.first_entry() {
+            if *entry.key() + PENDING_CONFIRM_HORIZON >= stats.slot {
+                break;
+            }
+            self.count_missed(entry.remove());
+        }
+        drop(pending);
+        let mut firsts = self.pending_firsts.lock().unwrap();
+        stats.txs_first += firsts.remove(&stats.slot).unwrap_or(0);
+        while let Some(entry) = firsts.first_entry() {
+            if *entry.key() + PENDING_CONFIRM_HORIZON >= stats.slot {
+                break;
+            }
+            entry.remove();
+        }
+        drop(firsts);
         if log.len() >= SLOT_LOG_CAP {
             log.pop_front();
         }
         log.push_back(stats);
     }
 
+    /// Count `n` transactions of `slot` forwarded as first copies, towards
+    /// the slot's [`SlotStats::txs_first`].
+    pub fn record_slot_firsts(&self, slot: u64, n: u32) {
+        // Lock order: slot log, then pending firsts (as in `push_slot_stats`).
+        let mut log = self.slot_log.lock().unwrap();
+        if let Some(stats) = log.iter_mut().rev().take(FIRSTS_LOOKBACK).find(|s| s.slot == slot) {
+            stats.txs_first += n;
+            return;
+        }
+        let mut pending = self.pending_firsts.lock().unwrap();
+        *pending.entry(slot).or_default() += n;
+        while pending.len() > PENDING_CONFIRM_CAP {
+            pending.pop_first();
+        }
+    }
+
+    /// Cross-check a finalized slot against the cluster's verdict on it.
+    ///
+    /// Called by a baseline source for every slot it resolves. If this source
+    /// has already finalized the slot, its log entry is annotated; otherwise
+    /// the confirmation is held until it does. Slots that never show up are
+    /// counted in `confirmed_slots_missed`, and their transactions still count
+    /// towards `confirmed_txs_total`, so confirmed coverage is seen/actual.
+    pub fn confirm_slot(&self, slot: u64, confirmation: Confirmation) {
+        let mut log = self.slot_log.lock().unwrap();
+        if let Some(stats) = log.iter_mut().rev().find(|s| s.slot == slot) {
+            self.apply_confirmation(stats, confirmation);
+            return;
+        }
+        let mut pending = self.pending_confirmations.lock().unwrap();
+        pending.insert(slot, confirmation);
+        while pending.len() > PENDING_CONFIRM_CAP {
+            if let Some((_, c)) = pending.pop_first() {
+                self.count_missed(c);
+            }
+        }
+    }
+
+    fn apply_confirmation(&self, stats: &mut SlotStats, confirmation: Confirmation) {
+        if stats.confirmed_txs.is_some() || stats.skipped {
+            return;
+        }
+        match confirmation {
+            Confirmation::Confirmed(txs) => {
+                stats.confirmed_txs = Some(txs);
+                self.confirmed_slots.fetch_add(1, Relaxed);
+                if !self.headers_only.load(Relaxed) {
+                    self.confirmed_txs_total.fetch_add(txs as u64, Relaxed);
+                    self.confirmed_txs_seen.fetch_add(stats.txs_decoded.min(txs) as u64, Relaxed);
+                }
+            }
+            Confirmation::Skipped => {
+                stats.skipped = true;
+                self.skipped_slots_seen.fetch_add(1, Relaxed);
+            }
+        }
+    }
+
+    fn count_missed(&self, confirmation: Confirmation) {
+        if let Confirmation::Confirmed(txs) = confirmation {
+            self.confirmed_slots_missed.fetch_add(1, Relaxed);
+            if !self.headers_only.load(Relaxed) {
+                self.confirmed_txs_total.fetch_add(txs as u64, Relaxed);
+            }
+        }
+    }
+
+    /// Record the profile of a transaction that beat RPC by more than the early threshold.
+    pub fn record_early_tx(&self, profile: &TxProfile) {
+        self.early_txs.lock().unwrap().record(profile);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Relaxed)
+    }
+
+    /// Count a failure and keep it as the source's most recent one, so it
+    /// reaches the metrics log and `status` rather than only the journal.
+    pub fn record_error(&self, kind: SourceErrorKind, message: String) {
+        let at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.errors_total.fetch_add(1, Relaxed);
+        *self.last_error.lock().unwrap() = Some(SourceError { kind, message, at });
+    }
+
+    pub fn is_standby(&self) -> bool {
+        self.standby.load(Relaxed)
+    }
+
+    pub fn recv_offset_ns(&self) -> i64 {
+        self.recv_offset_ns.load(Relaxed)
+    }
+
+    /// Add to the receive-timestamp offset (see [`Self::recv_offset_ns`]).
+    pub fn add_recv_offset_ns(&self, ns: i64) {
+        self.recv_offset_ns.fetch_add(ns, Relaxed);
+    }
+
+    /// Record one recvmmsg call that returned `packets` after blocking for
+    /// `wait_ns`, and the time spent on the batch before it.
+    pub fn record_recv_batch(&self, packets: u64, wait_ns: u64, busy_ns: u64) {
+        self.recv_calls.fetch_add(1, Relaxed);
+        self.recv_packets.fetch_add(packets, Relaxed);
+        self.recv_wait_ns.fetch_add(wait_ns, Relaxed);
+        self.recv_busy_ns.fetch_add(busy_ns, Relaxed);
+    }
+
+    /// Pause or resume this source. Returns the previous state.
+    pub fn set_paused(&self, paused: bool) -> bool {
+        self.paused.swap(paused, Relaxed)
+    }
+
     /// Outlier bounds for lead-time samples (µs).
     /// Samples outside this range are silently discarded — they indicate measurement
     /// artifacts (e.g. RPC block-fetch retry) rather than real network latency.
@@ -234,6 +835,7 @@ impl SourceMetrics {
         }
         self.lead_time_sum_us.fetch_add(us, Relaxed);
         self.lead_time_reservoir.lock().unwrap().push(us);
+        self.lead_time_hist.record(us);
     }
 
     /// Mean lead time in µs, or None if no samples yet.
@@ -264,6 +866,112 @@ impl SourceMetrics {
         Some(first as f64 / (first + dup) as f64 * 100.0)
     }
 
+    /// Export cumulative counters and reservoir samples for persistence.
+    pub fn export_state(&self) -> SourceMetricsState {
+        SourceMetricsState {
+            shreds_received: self.shreds_received.load(Relaxed),
+            bytes_received: self.bytes_received.load(Relaxed),
+            shreds_dropped: self.shreds_dropped.load(Relaxed),
+            shreds_invalid: self.shreds_invalid.load(Relaxed),
+            non_shred_packets: self.non_shred_packets.load(Relaxed),
+            slots_attempted: self.slots_attempted.load(Relaxed),
+            slots_complete: self.slots_complete.load(Relaxed),
+            slots_partial: self.slots_partial.load(Relaxed),
+            slots_dropped: self.slots_dropped.load(Relaxed),
+            coverage_shreds_seen: self.coverage_shreds_seen.load(Relaxed),
+            coverage_shreds_expected: self.coverage_shreds_expected.load(Relaxed),
+            position_coverage: self.position_coverage.snapshot(),
+            fec_recovered_shreds: self.fec_recovered_shreds.load(Relaxed),
+            repair_requests: self.repair_requests.load(Relaxed),
+            shreds_repaired: self.shreds_repaired.load(Relaxed),
+            shreds_decode_skipped: self.shreds_decode_skipped.load(Relaxed),
+            slots_expired_shared_tip: self.slots_expired_shared_tip.load(Relaxed),
+            shreds_sig_verified: self.shreds_sig_verified.load(Relaxed),
+            shreds_sig_invalid: self.shreds_sig_invalid.load(Relaxed),
+            shreds_sig_unchecked: self.shreds_sig_unchecked.load(Relaxed),
+            txs_decoded: self.txs_decoded.load(Relaxed),
+            txs_emitted: self.txs_emitted.load(Relaxed),
+            txs_dropped: self.txs_dropped.load(Relaxed),
+            txs_first: self.txs_first.load(Relaxed),
+            txs_duplicate: self.txs_duplicate.load(Relaxed),
+            txs_slot_mismatch: self.txs_slot_mismatch.load(Relaxed),
+            confirmed_slots: self.confirmed_slots.load(Relaxed),
+            confirmed_slots_missed: self.confirmed_slots_missed.load(Relaxed),
+            skipped_slots_seen: self.skipped_slots_seen.load(Relaxed),
+            confirmed_txs_seen: self.confirmed_txs_seen.load(Relaxed),
+            confirmed_txs_total: self.confirmed_txs_total.load(Relaxed),
+            lead_time_count: self.lead_time_count.load(Relaxed),
+            lead_wins: self.lead_wins.load(Relaxed),
+            lead_time_sum_us: self.lead_time_sum_us.load(Relaxed),
+            lead_time_samples: self.lead_time_reservoir.lock().unwrap().samples(),
+            lead_time_hist: self.lead_time_hist.snapshot(),
+        }
+    }
+
+    /// Merge previously exported state into this instance. Counters are added,
+    /// so calling this after the pipeline has started loses nothing; restored
+    /// samples are pushed into the reservoir ahead of any newer ones.
+    pub fn restore_state(&self, state: &SourceMetricsState) {
+        self.shreds_received.fetch_add(state.shreds_received, Relaxed);
+        self.bytes_received.fetch_add(state.bytes_received, Relaxed);
+        self.shreds_dropped.fetch_add(state.shreds_dropped, Relaxed);
+        self.shreds_invalid.fetch_add(state.shreds_invalid, Relaxed);
+        self.non_shred_packets.fetch_add(state.non_shred_packets, Relaxed);
+        self.slots_attempted.fetch_add(state.slots_attempted, Relaxed);
+        self.slots_complete.fetch_add(state.slots_complete, Relaxed);
+        self.slots_partial.fetch_add(state.slots_partial, Relaxed);
+        self.slots_dropped.fetch_add(state.slots_dropped, Relaxed);
+        self.coverage_shreds_seen.fetch_add(state.coverage_shreds_seen, Relaxed);
+        self.coverage_shreds_expected.fetch_add(state.coverage_shreds_expected, Relaxed);
+        self.position_coverage.add(&state.position_coverage);
+        self.fec_recovered_shreds.fetch_add(state.fec_recovered_shreds, Relaxed);
+        self.repair_requests.fetch_add(state.repair_requests, Relaxed);
+        self.shreds_repaired.fetch_add(state.shreds_repaired, Relaxed);
+        self.shreds_decode_skipped.fetch_add(state.shreds_decode_skipped, Relaxed);
+        self.slots_expired_shared_tip.fetch_add(state.slots_expired_shared_tip, Relaxed);
+        self.shreds_sig_verified.fetch_add(state.shreds_sig_verified, Relaxed);
+        self.shreds_sig_invalid.fetch_add(state.shreds_sig_invalid, Relaxed);
+        self.shreds_sig_unchecked.fetch_add(state.shreds_sig_unchecked, Relaxed);
+        self.txs_decoded.fetch_add(state.txs_decoded, Relaxed);
+        self.txs_emitted.fetch_add(state.txs_emitted, Relaxed);
+        self.txs_dropped.fetch_add(state.txs_dropped, Relaxed);
+        self.txs_first.fetch_add(state.txs_first, Relaxed);
+        self.txs_duplicate.fetch_add(state.txs_duplicate, Relaxed);
+        self.txs_slot_mismatch.fetch_add(state.txs_slot_mismatch, Relaxed);
+        self.confirmed_slots.fetch_add(state.confirmed_slots, Relaxed);
+        self.confirmed_slots_missed.fetch_add(state.confirmed_slots_missed, Relaxed);
+        self.skipped_slots_seen.fetch_add(state.skipped_slots_seen, Relaxed);
+        self.confirmed_txs_seen.fetch_add(state.confirmed_txs_seen, Relaxed);
+        self.confirmed_txs_total.fetch_add(state.confirmed_txs_total, Relaxed);
+        self.lead_time_count.fetch_add(state.lead_time_count, Relaxed);
+        self.lead_wins.fetch_add(state.lead_wins, Relaxed);
+        self.lead_time_sum_us.fetch_add(state.lead_time_sum_us, Relaxed);
+        self.lead_time_hist.merge(&state.lead_time_hist);
+
+        let mut res = self.lead_time_reservoir.lock().unwrap();
+        let newer = res.samples();
+        *res = LeadTimeReservoir::new();
+        for &v in state.lead_time_samples.iter().chain(newer.iter()) {
+            res.push(v);
+        }
+    }
+
+    /// Counters only; see [`SourceCounters`].
+    pub fn counters(&self) -> SourceCounters {
+        SourceCounters {
+            shreds_received: self.shreds_received.load(Relaxed),
+            txs_decoded: self.txs_decoded.load(Relaxed),
+            txs_first: self.txs_first.load(Relaxed),
+            slots_complete: self.slots_complete.load(Relaxed),
+            slots_partial: self.slots_partial.load(Relaxed),
+            slots_dropped: self.slots_dropped.load(Relaxed),
+            fec_recovered_shreds: self.fec_recovered_shreds.load(Relaxed),
+            lead_time_count: self.lead_time_count.load(Relaxed),
+            lead_wins: self.lead_wins.load(Relaxed),
+            lead_time_sum_us: self.lead_time_sum_us.load(Relaxed),
+        }
+    }
+
     /// Capture a consistent point-in-time snapshot (slight skew possible on atomics;
     /// reservoir lock is held only for the percentile sort).
     pub fn snapshot(&self) -> SourceMetricsSnapshot {
@@ -275,10 +983,14 @@ impl SourceMetrics {
                 })
         };
 
-        let slot_log = {
+        let slot_log: Vec<SlotStats> = {
             let log = self.slot_log.lock().unwrap();
             log.iter().cloned().collect()
         };
+        let slot_max_gap_p95_us = p95(slot_log.iter().filter_map(|s| s.max_gap_us));
+        let slot_jitter_p95_us = p95(slot_log.iter().filter_map(|s| s.gap_stddev_us));
+
+        let early_txs = self.early_txs.lock().unwrap().snapshot();
 
         let now_ns = crate::metrics::now_ns();
         let last_hb = self.last_heartbeat_ns.load(Relaxed);
@@ -289,31 +1001,69 @@ impl SourceMetrics {
         };
 
         SourceMetricsSnapshot {
-            name: self.name,
+            name: self.name.clone(),
+            labels: self.labels.clone(),
             is_rpc: self.is_rpc,
             shreds_received: self.shreds_received.load(Relaxed),
             bytes_received: self.bytes_received.load(Relaxed),
             shreds_dropped: self.shreds_dropped.load(Relaxed),
             shreds_invalid: self.shreds_invalid.load(Relaxed),
+            non_shred_packets: self.non_shred_packets.load(Relaxed),
+            shred_mix: self.shred_mix.snapshot(),
+            senders: self.senders.snapshot(),
             secs_since_heartbeat,
+            paused: self.is_paused(),
+            standby: self.is_standby(),
+            recv_offset_ns: self.recv_offset_ns(),
+            rpc_slot_lag: self.rpc_slot_lag.load(Relaxed),
+            socket_rcvbuf_bytes: self.socket_rcvbuf_bytes.load(Relaxed),
+            socket_rmem_hwm_bytes: self.socket_rmem_hwm_bytes.load(Relaxed),
+            socket_drops: self.socket_drops.load(Relaxed),
+            recv_calls: self.recv_calls.load(Relaxed),
+            recv_packets: self.recv_packets.load(Relaxed),
+            recv_wait_ns: self.recv_wait_ns.load(Relaxed),
+            recv_busy_ns: self.recv_busy_ns.load(Relaxed),
             slots_attempted: self.slots_attempted.load(Relaxed),
             slots_complete: self.slots_complete.load(Relaxed),
             slots_partial: self.slots_partial.load(Relaxed),
             slots_dropped: self.slots_dropped.load(Relaxed),
             coverage_shreds_seen: self.coverage_shreds_seen.load(Relaxed),
             coverage_shreds_expected: self.coverage_shreds_expected.load(Relaxed),
+            position_coverage: self.position_coverage.snapshot(),
             fec_recovered_shreds: self.fec_recovered_shreds.load(Relaxed),
+            repair_requests: self.repair_requests.load(Relaxed),
+            shreds_repaired: self.shreds_repaired.load(Relaxed),
+            shreds_decode_skipped: self.shreds_decode_skipped.load(Relaxed),
+            slots_expired_shared_tip: self.slots_expired_shared_tip.load(Relaxed),
+            shreds_sig_verified: self.shreds_sig_verified.load(Relaxed),
+            shreds_sig_invalid: self.shreds_sig_invalid.load(Relaxed),
+            shreds_sig_unchecked: self.shreds_sig_unchecked.load(Relaxed),
             txs_decoded: self.txs_decoded.load(Relaxed),
             txs_emitted: self.txs_emitted.load(Relaxed),
+            txs_dropped: self.txs_dropped.load(Relaxed),
             txs_first: self.txs_first.load(Relaxed),
             txs_duplicate: self.txs_duplicate.load(Relaxed),
+            txs_slot_mismatch: self.txs_slot_mismatch.load(Relaxed),
+            confirmed_slots: self.confirmed_slots.load(Relaxed),
+            confirmed_slots_missed: self.confirmed_slots_missed.load(Relaxed),
+            skipped_slots_seen: self.skipped_slots_seen.load(Relaxed),
+            confirmed_txs_seen: self.confirmed_txs_seen.load(Relaxed),
+            confirmed_txs_total: self.confirmed_txs_total.load(Relaxed),
             lead_time_count: self.lead_time_count.load(Relaxed),
             lead_wins: self.lead_wins.load(Relaxed),
             lead_time_sum_us: self.lead_time_sum_us.load(Relaxed),
             lead_time_p50_us: lead_p50,
             lead_time_p95_us: lead_p95,
             lead_time_p99_us: lead_p99,
+            lead_time_hist: self.lead_time_hist.snapshot(),
+            slot_max_gap_p95_us,
+            slot_jitter_p95_us,
             slot_log,
+            early_txs,
+            errors_total: self.errors_total.load(Relaxed),
+            last_error: self.last_error.lock().unwrap().clone(),
+            restarts: self.restarts.load(Relaxed),
+            degraded: self.degraded.load(Relaxed),
         }
     }
 }
@@ -322,9 +1072,39 @@ impl SourceMetrics {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_record_error_keeps_latest() {
+        let m = SourceMetrics::new("test".into(), false);
+        assert!(m.snapshot().last_error.is_none());
+        m.record_error(SourceErrorKind::Disconnect, "stream reset".into());
+        m.record_error(SourceErrorKind::Rpc, "timed out".into());
+        let snap = m.snapshot();
+        assert_eq!(snap.errors_total, 2);
+        let last = snap.last_error.unwrap();
+        assert_eq!(last.kind, SourceErrorKind::Rpc);
+        assert_eq!(last.message, "timed out");
+    }
+
+    #[test]
+    fn test_counters_since() {
+        let m = SourceMetrics::new("test".into(), false);
+        m.shreds_received.fetch_add(10, Relaxed);
+        m.record_lead_time_us(100);
+        let before = m.counters();
+        m.shreds_received.fetch_add(5, Relaxed);
+        m.record_lead_time_us(300);
+        m.record_lead_time_us(500);
+        let d = m.counters().since(&before);
+        assert_eq!(d.shreds_received, 5);
+        assert_eq!(d.lead_time_count, 2);
+        assert_eq!(d.lead_time_sum_us, 800);
+        // Going backwards, e.g. across a restore, yields zeros.
+        assert_eq!(before.since(&m.counters()), SourceCounters::default());
+    }
+
     #[test]
     fn test_lead_time_percentiles() {
-        let m = SourceMetrics::new("test", false);
+        let m = SourceMetrics::new("test".into(), false);
         // Insert 100 values 1µs..=100µs
         for i in 1i64..=100 {
             m.record_lead_time_us(i);
@@ -344,7 +1124,7 @@ mod tests {
 
     #[test]
     fn test_lead_time_outlier_cap() {
-        let m = SourceMetrics::new("test", false);
+        let m = SourceMetrics::new("test".into(), false);
         m.record_lead_time_us(1_000_000);
         m.record_lead_time_us(-400_000);
         m.record_lead_time_us(2_000_001); // outlier, discarded
@@ -359,7 +1139,7 @@ mod tests {
 
     #[test]
     fn test_win_rate() {
-        let m = SourceMetrics::new("test", false);
+        let m = SourceMetrics::new("test".into(), false);
         assert!(m.win_rate().is_none());
         m.txs_first.fetch_add(7, Relaxed);
         m.txs_duplicate.fetch_add(3, Relaxed);
@@ -369,7 +1149,7 @@ mod tests {
 
     #[test]
     fn test_coverage_pct() {
-        let m = SourceMetrics::new("test", false);
+        let m = SourceMetrics::new("test".into(), false);
         assert!(m.coverage_pct().is_none());
         m.coverage_shreds_seen.store(67, Relaxed);
         m.coverage_shreds_expected.store(100, Relaxed);
@@ -379,19 +1159,66 @@ mod tests {
 
     #[test]
     fn test_snapshot() {
-        let m = SourceMetrics::new("snap", false);
+        let m = SourceMetrics::new("snap".into(), false);
         m.shreds_received.store(100, Relaxed);
         m.txs_decoded.store(42, Relaxed);
         let s = m.snapshot();
-        assert_eq!(s.name, "snap");
+        assert_eq!(&*s.name, "snap");
         assert_eq!(s.shreds_received, 100);
         assert_eq!(s.txs_decoded, 42);
         assert!(s.lead_time_p50_us.is_none());
+        assert!(!s.paused);
+    }
+
+    #[test]
+    fn test_pause_resume() {
+        let m = SourceMetrics::new("p".into(), false);
+        assert!(!m.set_paused(true));
+        assert!(m.snapshot().paused);
+        assert!(m.set_paused(false));
+        assert!(!m.is_paused());
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let a = SourceMetrics::new("a".into(), false);
+        a.shreds_received.store(1000, Relaxed);
+        a.txs_first.store(7, Relaxed);
+        for i in 1i64..=10 {
+            a.record_lead_time_us(i * 100);
+        }
+        let state = a.export_state();
+        assert_eq!(state.lead_time_samples.len(), 10);
+
+        let b = SourceMetrics::new("a".into(), false);
+        b.shreds_received.store(5, Relaxed);
+        b.record_lead_time_us(-50);
+        b.restore_state(&state);
+
+        assert_eq!(b.shreds_received.load(Relaxed), 1005);
+        assert_eq!(b.txs_first.load(Relaxed), 7);
+        assert_eq!(b.lead_time_count.load(Relaxed), 11);
+        assert_eq!(b.lead_time_sum_us.load(Relaxed), 5500 - 50);
+        let samples = b.export_state().lead_time_samples;
+        assert_eq!(samples.first(), Some(&100));
+        assert_eq!(samples.last(), Some(&-50), "newer samples stay newest");
+    }
+
+    #[test]
+    fn test_reservoir_samples_chronological_after_wrap() {
+        let mut r = LeadTimeReservoir::new();
+        for i in 0..(RESERVOIR_CAP as i64 + 3) {
+            r.push(i);
+        }
+        let s = r.samples();
+        assert_eq!(s.len(), RESERVOIR_CAP);
+        assert_eq!(s[0], 3);
+        assert_eq!(*s.last().unwrap(), RESERVOIR_CAP as i64 + 2);
     }
 
     #[test]
     fn test_reservoir_wraps() {
-        let m = SourceMetrics::new("wrap", false);
+        let m = SourceMetrics::new("wrap".into(), false);
         // Fill past capacity; all values are the same constant
         for _ in 0..RESERVOIR_CAP + 100 {
             m.record_lead_time_us(500_000);
@@ -400,4 +1227,81 @@ mod tests {
         assert_eq!(snap.lead_time_p50_us, Some(500_000));
         assert_eq!(snap.lead_time_p99_us, Some(500_000));
     }
+
+    fn slot_stats(slot: u64, txs_decoded: u32) -> SlotStats {
+        SlotStats {
+            slot,
+            shreds_seen: 10,
+            fec_recovered: 0,
+            txs_decoded,
+            shreds_expected: 10,
+            completion_us: None,
+            completed_at_ns: None,
+            first_at_ns: None,
+            outcome: SlotOutcome::Partial,
+            confirmed_txs: None,
+            skipped: false,
+            max_gap_us: None,
+            gap_stddev_us: None,
+            fec_sets: 0,
+            fec_sets_complete: 0,
+            entries: 0,
+            txs_first: 0,
+        }
+    }
+
+    #[test]
+    fn test_confirmation_in_either_order() {
+        let m = SourceMetrics::new("tail".into(), false);
+        // Finalized first, then confirmed.
+        m.push_slot_stats(slot_stats(100, 40));
+        m.confirm_slot(100, Confirmation::Confirmed(100));
+        // Confirmed first, then finalized.
+        m.confirm_slot(101, Confirmation::Confirmed(50));
+        m.push_slot_stats(slot_stats(101, 50));
+        // A repeated report changes nothing.
+        m.confirm_slot(100, Confirmation::Confirmed(100));
+
+        let snap = m.snapshot();
+        assert_eq!(snap.confirmed_slots, 2);
+        assert_eq!(snap.confirmed_txs_total, 150);
+        assert_eq!(snap.confirmed_txs_seen, 90);
+        assert_eq!(snap.confirmed_coverage_pct(), Some(60.0));
+        assert_eq!(snap.slot_log[0].confirmed_txs, Some(100));
+        assert_eq!(snap.slot_log[1].confirmed_txs, Some(50));
+    }
+
+    #[test]
+    fn test_unseen_confirmed_slot_counts_as_missed() {
+        let m = SourceMetrics::new("gappy".into(), false);
+        m.confirm_slot(200, Confirmation::Confirmed(30));
+        m.confirm_slot(201, Confirmation::Skipped);
+        m.push_slot_stats(slot_stats(201, 0));
+        assert_eq!(m.confirmed_slots_missed.load(Relaxed), 0, "still within the horizon");
+
+        m.push_slot_stats(slot_stats(200 + PENDING_CONFIRM_HORIZON + 1, 0));
+        let snap = m.snapshot();
+        assert_eq!(snap.confirmed_slots_missed, 1);
+        assert_eq!(snap.skipped_slots_seen, 1);
+        assert_eq!(snap.confirmed_txs_total, 30);
+        assert_eq!(snap.confirmed_coverage_pct(), Some(0.0));
+        assert!(snap.slot_log[0].skipped);
+    }
+
+    #[test]
+    fn test_slot_firsts_before_and_after_finalize() {
+        let m = SourceMetrics::new("funnel".into(), false);
+        m.record_slot_firsts(300, 5);
+        m.record_slot_firsts(300, 2);
+        m.push_slot_stats(slot_stats(300, 10));
+        m.record_slot_firsts(300, 1);
+        // Never finalized: dropped once the source is past the horizon.
+        m.record_slot_firsts(301, 4);
+        m.push_slot_stats(slot_stats(301 + PENDING_CONFIRM_HORIZON + 1, 0));
+
+        let snap = m.snapshot();
+        assert_eq!(snap.slot_log[0].txs_first, 8);
+        assert_eq!(snap.slot_log[1].txs_first, 0);
+        assert!(m.pending_firsts.lock().unwrap().is_empty());
+    }
 }
@@ -0,0 +1,142 @@
+//! Slot log for sources that deliver transactions rather than shreds.
+//!
+//! `geyser` and `jito-grpc` never see a shred, so the decoder's per-slot
+//! accounting does not run for them. [`StreamSlots`] builds the equivalent
+//! [`SlotStats`] from the stream itself: the transactions and entries each
+//! slot delivered, when its first message arrived (`first_at_ns`), and the
+//! time from its first message to its last (`completion_us`). A slot is
+//! logged, with outcome [`SlotOutcome::Streamed`], once a message for a slot
+//! [`SLOT_HORIZON`] or more later has arrived, or when the stream ends.
+//!
+//! Shred-tier stream sources (`jito-grpc`) get their slots cross-checked
+//! against the cluster like any shred source, so with a baseline configured
+//! their `confirmed_txs` per slot, and their confirmed coverage, follow.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::clock::MonotonicNs;
+use crate::source_metrics::{SlotOutcome, SlotStats, SourceMetrics};
+
+/// A slot is logged once a message this many slots later arrives. Streams
+/// deliver slots nearly in order, but a slot's last entries can trail the
+/// next slot's first ones.
+pub const SLOT_HORIZON: u64 = 4;
+
+/// Open slots of one stream, logged into its source's metrics.
+pub(crate) struct StreamSlots {
+    metrics: Arc<SourceMetrics>,
+    open: BTreeMap<u64, OpenSlot>,
+    /// Highest slot logged so far; later messages for it are not counted
+    /// again.
+    logged_through: Option<u64>,
+}
+
+struct OpenSlot {
+    first_ns: MonotonicNs,
+    last_ns: MonotonicNs,
+    txs: u32,
+    entries: u32,
+}
+
+impl StreamSlots {
+    pub(crate) fn new(metrics: Arc<SourceMetrics>) -> Self {
+        Self { metrics, open: BTreeMap::new(), logged_through: None }
+    }
+
+    /// Count one message of `slot`, received at `recv_ns`.
+    pub(crate) fn record(&mut self, slot: u64, recv_ns: MonotonicNs, txs: u32, entries: u32) {
+        if matches!(self.logged_through, Some(done) if slot <= done) {
+            return;
+        }
+        let open = self.open.entry(slot).or_insert(OpenSlot {
+            first_ns: recv_ns,
+            last_ns: recv_ns,
+            txs: 0,
+            entries: 0,
+        });
+        open.last_ns = open.last_ns.max(recv_ns);
+        open.txs += txs;
+        open.entries += entries;
+
+        while let Some(entry) = self.open.first_entry() {
+            if *entry.key() + SLOT_HORIZON > slot {
+                break;
+            }
+            let (slot, open) = entry.remove_entry();
+            self.log(slot, open);
+        }
+    }
+
+    /// Log every open slot; called when the stream ends.
+    pub(crate) fn flush(&mut self) {
+        while let Some((slot, open)) = self.open.pop_first() {
+            self.log(slot, open);
+        }
+    }
+
+    fn log(&mut self, slot: u64, open: OpenSlot) {
+        self.logged_through = Some(slot);
+        self.metrics.push_slot_stats(SlotStats {
+            slot,
+            shreds_seen: 0,
+            fec_recovered: 0,
+            txs_decoded: open.txs,
+            shreds_expected: 0,
+            completion_us: Some(open.last_ns.since(open.first_ns) / 1000),
+            completed_at_ns: None,
+            first_at_ns: Some(open.first_ns.to_realtime().0),
+            outcome: SlotOutcome::Streamed,
+            confirmed_txs: None,
+            skipped: false,
+            max_gap_us: None,
+            gap_stddev_us: None,
+            fec_sets: 0,
+            fec_sets_complete: 0,
+            entries: open.entries,
+            txs_first: 0,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source_metrics::Confirmation;
+
+    #[test]
+    fn test_slots_logged_past_the_horizon() {
+        let metrics = SourceMetrics::new("jito".into(), false);
+        let mut slots = StreamSlots::new(metrics.clone());
+        slots.record(10, MonotonicNs(1_000_000), 5, 2);
+        slots.record(11, MonotonicNs(1_500_000), 3, 1);
+        slots.record(10, MonotonicNs(3_000_000), 4, 1);
+        assert!(metrics.snapshot().slot_log.is_empty(), "nothing past the horizon yet");
+
+        slots.record(14, MonotonicNs(9_000_000), 1, 1);
+        // Slot 10 is logged; a straggler for it is not counted again.
+        slots.record(10, MonotonicNs(9_500_000), 7, 1);
+        let log = metrics.snapshot().slot_log;
+        assert_eq!(log.len(), 1);
+        assert_eq!((log[0].slot, log[0].txs_decoded, log[0].entries), (10, 9, 3));
+        assert_eq!(log[0].completion_us, Some(2_000));
+        assert!(matches!(log[0].outcome, SlotOutcome::Streamed));
+
+        slots.flush();
+        let slots: Vec<u64> = metrics.snapshot().slot_log.iter().map(|s| s.slot).collect();
+        assert_eq!(slots, [10, 11, 14]);
+    }
+
+    #[test]
+    fn test_streamed_slots_are_cross_checked() {
+        let metrics = SourceMetrics::new("jito".into(), false);
+        let mut slots = StreamSlots::new(metrics.clone());
+        slots.record(10, MonotonicNs(1_000_000), 80, 4);
+        slots.flush();
+        metrics.confirm_slot(10, Confirmation::Confirmed(100));
+
+        let snap = metrics.snapshot();
+        assert_eq!(snap.slot_log[0].confirmed_txs, Some(100));
+        assert_eq!(snap.confirmed_coverage_pct(), Some(80.0));
+    }
+}
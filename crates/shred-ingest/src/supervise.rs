@@ -0,0 +1,342 @@
+//! Supervised source lifecycles.
+//!
+//! A source's threads can stop: a receive socket fails, a decoder panics.
+//! Without supervision the source stays in the table but delivers nothing.
+//! The fan-in instead hands every source's threads to one supervisor thread,
+//! which checks them every second. When one has stopped, the source is
+//! marked degraded, the failure (with the panic message, if any) is recorded
+//! as a [`SourceErrorKind::Crash`], and the instance's [`StopSignal`] tells
+//! its other threads to exit: one dead socket of several would otherwise
+//! leave the rest, and the decoder they feed, running for good. Once every
+//! thread of the instance has exited it is started again from a fresh copy
+//! (see [`TxSource::respawn`]), after a backoff that doubles per restart.
+//! After [`RestartPolicy::max_restarts`] restarts, or for a source that
+//! cannot be respawned, the source stays degraded and whatever still runs of
+//! it is left running.
+
+use crossbeam_channel::Sender;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::decoder::DecodedTx;
+use crate::fan_in::TxSource;
+use crate::shred_race::ShredRaceTracker;
+use crate::source_metrics::{SourceErrorKind, SourceMetrics};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How the fan-in restarts a source whose threads stopped.
+#[derive(Debug, Clone, Copy)]
+pub struct RestartPolicy {
+    /// Restarts per source over the process lifetime; 0 never restarts.
+    pub max_restarts: u32,
+    /// Wait before the first restart; doubled for each one after it.
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RestartPolicy {
+    fn backoff(&self, restarts: u32) -> Duration {
+        self.backoff.saturating_mul(1 << restarts.min(16)).min(self.max_backoff)
+    }
+}
+
+/// Tells the threads of one started source to exit. Cheap to clone; set
+/// through [`TxSource::set_stop`].
+#[derive(Debug, Clone, Default)]
+pub struct StopSignal(Arc<AtomicBool>);
+
+impl StopSignal {
+    pub fn stop(&self) {
+        self.0.store(true, Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(Relaxed)
+    }
+}
+
+/// A started source and what is needed to start it again.
+pub(crate) struct Supervised {
+    handles: Vec<JoinHandle<()>>,
+    /// Handed to the running instance; a fresh one goes to each restart.
+    stop: StopSignal,
+    /// Unstarted copy for the next restart.
+    spare: Option<Box<dyn TxSource>>,
+    tx: Sender<Vec<DecodedTx>>,
+    metrics: Arc<SourceMetrics>,
+    race: Option<Arc<ShredRaceTracker>>,
+    state: State,
+    restarts: u32,
+}
+
+enum State {
+    Running,
+    /// Failed; started again at `at` once the old threads are gone.
+    Restarting { at: Instant },
+    /// Failed with no restart left.
+    Failed,
+}
+
+impl Supervised {
+    pub(crate) fn new(
+        mut source: Box<dyn TxSource>,
+        tx: Sender<Vec<DecodedTx>>,
+        metrics: Arc<SourceMetrics>,
+        race: Option<Arc<ShredRaceTracker>>,
+    ) -> Self {
+        let spare = source.respawn();
+        let stop = StopSignal::default();
+        source.set_stop(stop.clone());
+        let handles = source.start(tx.clone(), metrics.clone(), race.clone());
+        Self { handles, stop, spare, tx, metrics, race, state: State::Running, restarts: 0 }
+    }
+
+    /// One supervision step at `now`.
+    fn check(&mut self, policy: &RestartPolicy, now: Instant) {
+        let (stopped, running): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.handles).into_iter().partition(|h| h.is_finished());
+        self.handles = running;
+        let any_stopped = !stopped.is_empty();
+        let panics: Vec<String> =
+            stopped.into_iter().filter_map(|h| h.join().err()).map(panic_message).collect();
+
+        match self.state {
+            // A source's threads only exit when it has failed.
+            State::Running if any_stopped => self.fail(policy, now, &panics),
+            State::Restarting { at } if now >= at && self.handles.is_empty() => self.restart(),
+            _ => {}
+        }
+    }
+
+    fn fail(&mut self, policy: &RestartPolicy, now: Instant, panics: &[String]) {
+        let name = &self.metrics.name;
+        let message = match panics.first() {
+            Some(panic) => format!("thread panicked: {}", panic),
+            None => "a source thread stopped".to_string(),
+        };
+        self.metrics.degraded.store(true, Relaxed);
+        self.metrics.record_error(SourceErrorKind::Crash, message.clone());
+        if self.spare.is_none() {
+            tracing::error!("source '{}': {}; it cannot be restarted", name, message);
+            self.state = State::Failed;
+        } else if self.restarts >= policy.max_restarts {
+            tracing::error!(
+                "source '{}': {}; giving up after {} restarts",
+                name,
+                message,
+                self.restarts
+            );
+            self.state = State::Failed;
+        } else {
+            let backoff = policy.backoff(self.restarts);
+            tracing::warn!("source '{}': {}; restarting in {:?}", name, message, backoff);
+            self.stop.stop();
+            self.state = State::Restarting { at: now + backoff };
+        }
+    }
+
+    fn restart(&mut self) {
+        let Some(mut source) = self.spare.take() else { return };
+        self.spare = source.respawn();
+        self.stop = StopSignal::default();
+        source.set_stop(self.stop.clone());
+        self.handles = source.start(self.tx.clone(), self.metrics.clone(), self.race.clone());
+        self.restarts += 1;
+        self.metrics.restarts.fetch_add(1, Relaxed);
+        self.metrics.degraded.store(false, Relaxed);
+        self.state = State::Running;
+        tracing::info!("source '{}' restarted ({} so far)", self.metrics.name, self.restarts);
+    }
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".into())
+}
+
+/// Supervise `sources` on a background thread.
+pub(crate) fn spawn(mut sources: Vec<Supervised>, policy: RestartPolicy) -> JoinHandle<()> {
+    std::thread::Builder::new()
+        .name("fan-in-supervise".into())
+        .spawn(move || loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            let now = Instant::now();
+            for source in &mut sources {
+                source.check(&policy, now);
+            }
+        })
+        .expect("failed to spawn supervisor thread")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    /// Its thread panics on the first `crashes` starts, then runs on.
+    #[derive(Clone)]
+    struct Flaky {
+        starts: Arc<AtomicU32>,
+        crashes: u32,
+    }
+
+    impl TxSource for Flaky {
+        fn name(&self) -> Arc<str> {
+            "flaky".into()
+        }
+
+        fn respawn(&self) -> Option<Box<dyn TxSource>> {
+            Some(Box::new(self.clone()))
+        }
+
+        fn start(
+            self: Box<Self>,
+            _tx: Sender<Vec<DecodedTx>>,
+            _metrics: Arc<SourceMetrics>,
+            _race: Option<Arc<ShredRaceTracker>>,
+        ) -> Vec<JoinHandle<()>> {
+            let n = self.starts.fetch_add(1, Relaxed);
+            let crash = n < self.crashes;
+            vec![std::thread::spawn(move || {
+                if crash {
+                    panic!("decoder crashed");
+                }
+                std::thread::sleep(Duration::from_secs(2));
+            })]
+        }
+    }
+
+    /// Two threads that run until stopped, except that on the first start
+    /// one of them fails straight away.
+    #[derive(Clone)]
+    struct Siblings {
+        starts: Arc<AtomicU32>,
+        stop: Option<StopSignal>,
+    }
+
+    impl TxSource for Siblings {
+        fn name(&self) -> Arc<str> {
+            "siblings".into()
+        }
+
+        fn respawn(&self) -> Option<Box<dyn TxSource>> {
+            Some(Box::new(self.clone()))
+        }
+
+        fn set_stop(&mut self, stop: StopSignal) {
+            self.stop = Some(stop);
+        }
+
+        fn start(
+            self: Box<Self>,
+            _tx: Sender<Vec<DecodedTx>>,
+            _metrics: Arc<SourceMetrics>,
+            _race: Option<Arc<ShredRaceTracker>>,
+        ) -> Vec<JoinHandle<()>> {
+            let first = self.starts.fetch_add(1, Relaxed) == 0;
+            let stop = self.stop.expect("stop signal set before start");
+            (0..2)
+                .map(|i| {
+                    let stop = stop.clone();
+                    std::thread::spawn(move || {
+                        if first && i == 0 {
+                            panic!("socket failed");
+                        }
+                        while !stop.is_stopped() {
+                            std::thread::sleep(Duration::from_millis(5));
+                        }
+                    })
+                })
+                .collect()
+        }
+    }
+
+    fn settle(s: &mut Supervised, policy: &RestartPolicy, at: Instant) {
+        for _ in 0..100 {
+            if s.handles.iter().all(|h| h.is_finished()) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        s.check(policy, at);
+    }
+
+    #[test]
+    fn test_crashed_source_is_restarted_until_the_limit() {
+        let policy = RestartPolicy { max_restarts: 1, ..RestartPolicy::default() };
+        let metrics = SourceMetrics::new("flaky".into(), false);
+        let source = Flaky { starts: Arc::default(), crashes: 2 };
+        let starts = source.starts.clone();
+        let (tx, _rx) = crossbeam_channel::unbounded();
+        let mut s = Supervised::new(Box::new(source), tx, metrics.clone(), None);
+
+        let t0 = Instant::now();
+        settle(&mut s, &policy, t0);
+        assert!(matches!(s.state, State::Restarting { .. }));
+        let snap = metrics.snapshot();
+        assert!(snap.degraded);
+        let error = snap.last_error.unwrap();
+        assert_eq!(error.kind, SourceErrorKind::Crash);
+        assert!(error.message.contains("decoder crashed"), "{}", error.message);
+
+        // Not before the backoff has passed.
+        s.check(&policy, t0);
+        assert_eq!(starts.load(Relaxed), 1);
+        s.check(&policy, t0 + policy.backoff);
+        assert_eq!(starts.load(Relaxed), 2);
+        assert_eq!(metrics.snapshot().restarts, 1);
+
+        // The second crash uses up the only restart.
+        settle(&mut s, &policy, t0 + policy.backoff);
+        assert!(matches!(s.state, State::Failed));
+        assert!(metrics.snapshot().degraded);
+        assert_eq!(starts.load(Relaxed), 2);
+    }
+
+    #[test]
+    fn test_failure_stops_the_other_threads_before_restart() {
+        let policy = RestartPolicy::default();
+        let metrics = SourceMetrics::new("siblings".into(), false);
+        let source = Siblings { starts: Arc::default(), stop: None };
+        let starts = source.starts.clone();
+        let (tx, _rx) = crossbeam_channel::unbounded();
+        let mut s = Supervised::new(Box::new(source), tx, metrics.clone(), None);
+
+        let t0 = Instant::now();
+        // One thread failed; the other is still running until told to stop.
+        settle(&mut s, &policy, t0);
+        assert!(matches!(s.state, State::Restarting { .. }));
+        assert!(s.stop.is_stopped());
+        let old_stop = s.stop.clone();
+
+        settle(&mut s, &policy, t0 + policy.backoff);
+        assert!(matches!(s.state, State::Running));
+        assert_eq!(starts.load(Relaxed), 2);
+        assert!(old_stop.is_stopped() && !s.stop.is_stopped());
+        s.stop.stop();
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        let policy = RestartPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(8));
+        assert_eq!(policy.backoff(40), Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,133 @@
+//! End-to-end traces of sampled transactions.
+//!
+//! Lead-time percentiles say how a feed does on the whole, not where the time
+//! went for one transaction. With a [`TraceTap`] set on the fan-in, one
+//! signature in [`TraceTap::every`] is traced. Each copy of it, from every
+//! source, yields a [`TxTrace`] with its shred receive, decode, relay and dedup
+//! times. The consumer adds an `emit` record when it hands the transaction
+//! on, through [`TraceTap::record_emit`].
+//!
+//! The choice depends only on the signature, so every source traces the same
+//! transactions and one trace holds all copies of each.
+
+use crossbeam_channel::Sender;
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::clock::{MonotonicNs, RealtimeNs, RealtimeOffset};
+use crate::decoder::DecodedTx;
+use crate::fan_in::ForwardedTx;
+
+/// Where traces go, and how many signatures are traced.
+#[derive(Debug, Clone)]
+pub struct TraceTap {
+    pub tx: Sender<TxTrace>,
+    /// One signature in this many is traced; 1 traces every transaction.
+    pub every: u64,
+}
+
+/// A point in a transaction's path through the probe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Hop {
+    /// The shred (or message, for gRPC and RPC sources) was received.
+    ShredRecv,
+    /// The decoder handed the transaction on.
+    DecodeDone,
+    /// The fan-in's relay thread took the batch holding it.
+    Relay,
+    /// The fan-in resolved it as the first copy or a duplicate.
+    Dedup,
+    /// The consumer of the fan-in handed it on.
+    Emit,
+}
+
+/// Hops of one copy of a traced transaction.
+#[derive(Debug, Clone, Serialize)]
+pub struct TxTrace {
+    pub signature: String,
+    /// Source that delivered this copy.
+    pub source: Arc<str>,
+    pub slot: u64,
+    /// Hops with their times as Unix-epoch ns, in pipeline order.
+    pub hops: Vec<(Hop, u64)>,
+    /// On a `dedup` hop of a duplicate: the winning source and this copy's
+    /// lag behind it, µs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<(Arc<str>, i64)>,
+}
+
+impl TraceTap {
+    /// Whether the transaction with first signature `sig` is traced.
+    pub fn samples(&self, sig: &[u8]) -> bool {
+        // The fan-in's own sampling keys on the leading bytes; these are
+        // independent of them.
+        let Some(bytes) = sig.get(8..16) else { return false };
+        u64::from_le_bytes(bytes.try_into().unwrap()) % self.every.max(1) == 0
+    }
+
+    /// Trace one copy through the fan-in, relayed at `relay_ns`.
+    pub(crate) fn record_copy(
+        &self,
+        decoded: &DecodedTx,
+        source: &Arc<str>,
+        relay_ns: MonotonicNs,
+        duplicate_of: Option<(Arc<str>, i64)>,
+    ) {
+        let Some(sig) = decoded.transaction.signatures.first() else { return };
+        // One offset for every hop, so the hops of a record are converted alike.
+        let offset = RealtimeOffset::sample();
+        let unix = |t: MonotonicNs| t.to_realtime_at(offset).0;
+        let mut hops = vec![(Hop::ShredRecv, unix(decoded.shred_recv_ns))];
+        if decoded.decode_done_ns > decoded.shred_recv_ns {
+            hops.push((Hop::DecodeDone, unix(decoded.decode_done_ns)));
+        }
+        hops.push((Hop::Relay, unix(relay_ns)));
+        hops.push((Hop::Dedup, unix(MonotonicNs::now())));
+        let _ = self.tx.try_send(TxTrace {
+            signature: sig.to_string(),
+            source: source.clone(),
+            slot: decoded.slot,
+            hops,
+            duplicate_of,
+        });
+    }
+
+    /// Add the `emit` hop of a forwarded transaction, if it is traced.
+    pub fn record_emit(&self, fwd: &ForwardedTx) {
+        let Some(sig) = fwd.decoded.transaction.signatures.first() else { return };
+        if !self.samples(sig.as_ref()) {
+            return;
+        }
+        let _ = self.tx.try_send(TxTrace {
+            signature: sig.to_string(),
+            source: fwd.source.clone(),
+            slot: fwd.decoded.slot,
+            hops: vec![(Hop::Emit, RealtimeNs::now().0)],
+            duplicate_of: None,
+        });
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_samples_one_in_every() {
+        let (tx, _rx) = crossbeam_channel::unbounded();
+        let tap = TraceTap { tx, every: 4 };
+        let sig = |n: u64| {
+            let mut sig = [0u8; 64];
+            sig[8..16].copy_from_slice(&n.to_le_bytes());
+            sig
+        };
+        let traced = (0..100).filter(|&n| tap.samples(&sig(n))).count();
+        assert_eq!(traced, 25);
+        assert!(TraceTap { every: 1, ..tap }.samples(&sig(7)));
+    }
+}
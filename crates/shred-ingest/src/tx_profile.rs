@@ -0,0 +1,247 @@
+//! Lightweight transaction profiling for early-arrival analysis.
+//!
+//! [`TxProfile`] extracts the fee payer and compute-budget instructions from a
+//! decoded transaction without touching any account state. [`EarlyTxStats`]
+//! accumulates profiles of transactions that beat the RPC baseline by more than
+//! a configured margin, answering "what kind of flow actually arrives early?".
+
+use serde::Serialize;
+use solana_pubkey::Pubkey;
+use solana_transaction::versioned::VersionedTransaction;
+use std::collections::HashMap;
+
+/// `ComputeBudget111111111111111111111111111111`
+const COMPUTE_BUDGET_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    3, 6, 70, 111, 229, 33, 23, 50, 255, 236, 173, 186, 114, 195, 155, 231, 188, 140, 229, 187,
+    197, 247, 18, 107, 44, 67, 155, 58, 64, 0, 0, 0,
+]);
+
+/// Compute-budget instruction discriminants (borsh enum tag, first data byte).
+const IX_SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const IX_SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+/// Upper bounds (exclusive) of the compute-unit price buckets in micro-lamports/CU.
+/// The final bucket is open-ended.
+pub const PRICE_BUCKET_BOUNDS: [u64; 5] = [1, 1_000, 10_000, 100_000, 1_000_000];
+
+/// Human-readable labels matching [`PRICE_BUCKET_BOUNDS`] plus the open-ended tail.
+pub const PRICE_BUCKET_LABELS: [&str; 6] = ["0", "1-1k", "1k-10k", "10k-100k", "100k-1M", ">=1M"];
+
+/// Cap on distinct fee payers tracked per source. New payers beyond this are
+/// folded into `other_payers` so a long run cannot grow the map without bound.
+const MAX_TRACKED_PAYERS: usize = 10_000;
+
+/// Number of fee payers reported in a snapshot.
+const TOP_PAYERS: usize = 10;
+
+// ---------------------------------------------------------------------------
+// TxProfile
+// ---------------------------------------------------------------------------
+
+/// Fee-relevant fields parsed from a single transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxProfile {
+    /// First static account key (always the fee payer).
+    pub fee_payer: Pubkey,
+    /// `SetComputeUnitPrice` value in micro-lamports per CU, if present.
+    pub cu_price: Option<u64>,
+    /// `SetComputeUnitLimit` value, if present.
+    pub cu_limit: Option<u32>,
+}
+
+impl TxProfile {
+    /// Parse the fee payer and compute-budget instructions from `tx`.
+    /// Returns `None` if the transaction has no account keys.
+    pub fn from_transaction(tx: &VersionedTransaction) -> Option<Self> {
+        let keys = tx.message.static_account_keys();
+        let fee_payer = *keys.first()?;
+        let mut cu_price = None;
+        let mut cu_limit = None;
+
+        for ix in tx.message.instructions() {
+            if keys.get(ix.program_id_index as usize) != Some(&COMPUTE_BUDGET_PROGRAM_ID) {
+                continue;
+            }
+            match ix.data.split_first() {
+                Some((&IX_SET_COMPUTE_UNIT_PRICE, rest)) if rest.len() >= 8 => {
+                    cu_price = Some(u64::from_le_bytes(rest[..8].try_into().unwrap()));
+                }
+                Some((&IX_SET_COMPUTE_UNIT_LIMIT, rest)) if rest.len() >= 4 => {
+                    cu_limit = Some(u32::from_le_bytes(rest[..4].try_into().unwrap()));
+                }
+                _ => {}
+            }
+        }
+
+        Some(Self { fee_payer, cu_price, cu_limit })
+    }
+}
+
+/// Index into [`PRICE_BUCKET_LABELS`] for a compute-unit price.
+pub fn price_bucket(cu_price: u64) -> usize {
+    PRICE_BUCKET_BOUNDS
+        .iter()
+        .position(|&bound| cu_price < bound)
+        .unwrap_or(PRICE_BUCKET_BOUNDS.len())
+}
+
+// ---------------------------------------------------------------------------
+// EarlyTxStats
+// ---------------------------------------------------------------------------
+
+/// Running distribution of transactions that beat the baseline by a margin.
+#[derive(Default)]
+pub struct EarlyTxStats {
+    count: u64,
+    with_cu_price: u64,
+    with_cu_limit: u64,
+    price_buckets: [u64; PRICE_BUCKET_LABELS.len()],
+    payers: HashMap<Pubkey, u64>,
+    other_payers: u64,
+}
+
+/// Plain snapshot of [`EarlyTxStats`] for reporting.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EarlyTxSnapshot {
+    /// Transactions whose lead exceeded the configured threshold.
+    pub count: u64,
+    /// How many of those set an explicit compute-unit price.
+    pub with_priority_fee: u64,
+    /// How many of those set an explicit compute-unit limit.
+    pub with_cu_limit: u64,
+    /// (bucket label, count) pairs over the compute-unit price in micro-lamports/CU.
+    /// Transactions without a price instruction land in the "0" bucket.
+    pub cu_price_buckets: Vec<(&'static str, u64)>,
+    /// Most frequent fee payers, descending by count.
+    pub top_fee_payers: Vec<(String, u64)>,
+    /// Distinct fee payers seen.
+    pub distinct_fee_payers: u64,
+}
+
+impl EarlyTxStats {
+    pub fn record(&mut self, profile: &TxProfile) {
+        self.count += 1;
+        if profile.cu_limit.is_some() {
+            self.with_cu_limit += 1;
+        }
+        let price = match profile.cu_price {
+            Some(p) => {
+                self.with_cu_price += 1;
+                p
+            }
+            None => 0,
+        };
+        self.price_buckets[price_bucket(price)] += 1;
+
+        if let Some(n) = self.payers.get_mut(&profile.fee_payer) {
+            *n += 1;
+        } else if self.payers.len() < MAX_TRACKED_PAYERS {
+            self.payers.insert(profile.fee_payer, 1);
+        } else {
+            self.other_payers += 1;
+        }
+    }
+
+    pub fn snapshot(&self) -> EarlyTxSnapshot {
+        let mut payers: Vec<(&Pubkey, &u64)> = self.payers.iter().collect();
+        payers.sort_unstable_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+        EarlyTxSnapshot {
+            count: self.count,
+            with_priority_fee: self.with_cu_price,
+            with_cu_limit: self.with_cu_limit,
+            cu_price_buckets: PRICE_BUCKET_LABELS
+                .iter()
+                .copied()
+                .zip(self.price_buckets.iter().copied())
+                .collect(),
+            top_fee_payers: payers
+                .into_iter()
+                .take(TOP_PAYERS)
+                .map(|(k, n)| (k.to_string(), *n))
+                .collect(),
+            distinct_fee_payers: self.payers.len() as u64
+                + u64::from(self.other_payers > 0),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_message::compiled_instruction::CompiledInstruction;
+    use solana_message::{Message, VersionedMessage};
+
+    fn tx_with(ixs: Vec<CompiledInstruction>) -> VersionedTransaction {
+        VersionedTransaction {
+            signatures: vec![],
+            message: VersionedMessage::Legacy(Message {
+                account_keys: vec![Pubkey::new_from_array([7; 32]), COMPUTE_BUDGET_PROGRAM_ID],
+                instructions: ixs,
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn budget_ix(tag: u8, value: &[u8]) -> CompiledInstruction {
+        let mut data = vec![tag];
+        data.extend_from_slice(value);
+        CompiledInstruction { program_id_index: 1, accounts: vec![], data }
+    }
+
+    #[test]
+    fn test_parse_compute_budget() {
+        let tx = tx_with(vec![
+            budget_ix(IX_SET_COMPUTE_UNIT_LIMIT, &200_000u32.to_le_bytes()),
+            budget_ix(IX_SET_COMPUTE_UNIT_PRICE, &50_000u64.to_le_bytes()),
+        ]);
+        let p = TxProfile::from_transaction(&tx).unwrap();
+        assert_eq!(p.fee_payer, Pubkey::new_from_array([7; 32]));
+        assert_eq!(p.cu_limit, Some(200_000));
+        assert_eq!(p.cu_price, Some(50_000));
+    }
+
+    #[test]
+    fn test_parse_ignores_other_programs_and_short_data() {
+        let mut other = budget_ix(IX_SET_COMPUTE_UNIT_PRICE, &9u64.to_le_bytes());
+        other.program_id_index = 0;
+        let tx = tx_with(vec![other, budget_ix(IX_SET_COMPUTE_UNIT_PRICE, &[1, 2])]);
+        let p = TxProfile::from_transaction(&tx).unwrap();
+        assert_eq!(p.cu_price, None);
+        assert_eq!(p.cu_limit, None);
+    }
+
+    #[test]
+    fn test_price_buckets() {
+        assert_eq!(price_bucket(0), 0);
+        assert_eq!(price_bucket(1), 1);
+        assert_eq!(price_bucket(999), 1);
+        assert_eq!(price_bucket(1_000), 2);
+        assert_eq!(price_bucket(999_999), 4);
+        assert_eq!(price_bucket(5_000_000), 5);
+    }
+
+    #[test]
+    fn test_early_stats_snapshot() {
+        let a = Pubkey::new_from_array([1; 32]);
+        let b = Pubkey::new_from_array([2; 32]);
+        let mut stats = EarlyTxStats::default();
+        stats.record(&TxProfile { fee_payer: a, cu_price: Some(5_000), cu_limit: None });
+        stats.record(&TxProfile { fee_payer: b, cu_price: None, cu_limit: Some(1) });
+        stats.record(&TxProfile { fee_payer: b, cu_price: Some(2_000_000), cu_limit: None });
+
+        let s = stats.snapshot();
+        assert_eq!(s.count, 3);
+        assert_eq!(s.with_priority_fee, 2);
+        assert_eq!(s.with_cu_limit, 1);
+        assert_eq!(s.cu_price_buckets[0], ("0", 1));
+        assert_eq!(s.cu_price_buckets[2], ("1k-10k", 1));
+        assert_eq!(s.cu_price_buckets[5], (">=1M", 1));
+        assert_eq!(s.distinct_fee_payers, 2);
+        assert_eq!(s.top_fee_payers[0], (b.to_string(), 2));
+    }
+}
@@ -0,0 +1,125 @@
+//! Alert rules evaluated by `shredtop run` on every snapshot.
+//!
+//! The rules are the per-source checks of `shredtop status --check`, with
+//! the thresholds of `[health]`: a shred source at or below
+//! `min_shreds_per_sec`, one under `min_coverage_pct`, and a baseline that
+//! decoded no transactions. A source whose threads stopped is `degraded`
+//! whatever the thresholds. A rule fires for a source once its condition has
+//! held for `alert_after_secs`, and stays firing until a snapshot clears it.
+//! Firing alerts, with the time their condition started, go into each
+//! metrics log entry as `alerts`; `monitor` and `status` show them as banners.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::color;
+use crate::config::HealthConfig;
+
+/// The inputs of the rules for one source.
+pub struct SourceCheck<'a> {
+    pub name: &'a str,
+    pub is_rpc: bool,
+    /// Paused and standby sources are idle on purpose and skipped.
+    pub active: bool,
+    pub shreds_per_sec: f64,
+    pub coverage_pct: Option<f64>,
+    pub txs_per_sec: f64,
+    /// Threads stopped; see `[restart]`.
+    pub degraded: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    /// The rule, e.g. `coverage<80%`.
+    pub rule: String,
+    pub source: String,
+    /// When the condition started to hold, Unix seconds.
+    pub since: u64,
+}
+
+/// Since when each rule's condition has held, by (rule, source).
+#[derive(Default)]
+pub struct Alerts {
+    pending: BTreeMap<(String, String), u64>,
+}
+
+impl Alerts {
+    /// Evaluate the rules on the snapshot taken at `ts` and return the
+    /// firing alerts.
+    pub fn evaluate(
+        &mut self,
+        health: &HealthConfig,
+        ts: u64,
+        sources: &[SourceCheck<'_>],
+    ) -> Vec<Alert> {
+        let mut held = BTreeMap::new();
+        for s in sources.iter().filter(|s| s.active) {
+            for rule in broken_rules(health, s) {
+                let key = (rule, s.name.to_string());
+                let since = self.pending.get(&key).copied().unwrap_or(ts);
+                held.insert(key, since);
+            }
+        }
+        self.pending = held;
+        self.pending
+            .iter()
+            .filter(|(_, &since)| ts.saturating_sub(since) >= health.alert_after_secs)
+            .map(|((rule, source), &since)| Alert {
+                rule: rule.clone(),
+                source: source.clone(),
+                since,
+            })
+            .collect()
+    }
+}
+
+fn broken_rules(health: &HealthConfig, s: &SourceCheck<'_>) -> Vec<String> {
+    let mut rules = Vec::new();
+    if s.degraded {
+        rules.push("degraded".to_string());
+    }
+    if s.is_rpc {
+        if health.check_baseline && s.txs_per_sec <= 0.0 {
+            rules.push("no baseline txs".to_string());
+        }
+        return rules;
+    }
+    if s.shreds_per_sec <= health.min_shreds_per_sec {
+        rules.push(format!("shreds<={}/s", health.min_shreds_per_sec));
+    }
+    if health.min_coverage_pct > 0.0 && s.coverage_pct.is_some_and(|p| p < health.min_coverage_pct)
+    {
+        rules.push(format!("coverage<{}%", health.min_coverage_pct));
+    }
+    rules
+}
+
+/// Banner lines for the `alerts` of a metrics log entry, e.g.
+/// `ALERT coverage<80% on bebop for 7m`. Empty when none fire.
+pub fn banners(entry: &serde_json::Value) -> Vec<String> {
+    let ts = entry["ts"].as_u64().unwrap_or(0);
+    let Some(alerts) = entry["alerts"].as_array() else {
+        return Vec::new();
+    };
+    alerts
+        .iter()
+        .map(|a| {
+            let since = a["since"].as_u64().unwrap_or(ts);
+            color::bold_red(&format!(
+                "ALERT {} on {} for {}",
+                a["rule"].as_str().unwrap_or("?"),
+                a["source"].as_str().unwrap_or("?"),
+                duration(ts.saturating_sub(since))
+            ))
+        })
+        .collect()
+}
+
+/// `45s`, `7m`, `2h 5m`.
+pub fn duration(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
@@ -1,18 +1,60 @@
 //! `shredtop analyze` — per-feed shred timing analysis from a pcap file.
 //!
 //! Reads any pcap written by `shredtop capture` (or any third-party capture
-//! of the same UDP multicast traffic), pairs shreds that arrived on multiple
-//! feeds, and prints a timing table identical in format to the live SHRED RACE
-//! output shown by `shredtop monitor`.
+//! of the same UDP multicast traffic) and races the feeds against each other
+//! the way `shredtop run` does: the data shreds go, in capture-timestamp
+//! order, through the live [`ShredRaceTracker`], and the resulting pair
+//! snapshots are printed with the SHRED RACE table of `shredtop monitor`.
+//! Below it, each pair's matched races as a share of the shreds either feed
+//! delivered. `--json FILE` writes the pairs as `shred_race`, in the shape of
+//! the metrics log entry, to diff a capture against the service log.
+//!
+//! With `--histogram FILE` the full signed lead-time distribution of every feed
+//! pair is written as JSON (or CSV when FILE ends in `.csv`); `--ascii` renders
+//! the same histograms in the terminal. Percentiles alone hide bimodal
+//! distributions, e.g. two routing paths behind one feed.
+//!
+//! Without `--feed` mappings every destination `IP:port` in the capture is
+//! treated as a feed of its own, named by that address, and the destinations
+//! are listed with their packet counts first.
+//!
+//! Every run also prints per-feed continuity: slots a feed never delivered
+//! while other feeds did, index gaps within slots, how far out of order shreds
+//! arrive, and inter-arrival jitter. A feed that wins races but drops whole
+//! slots is worse than its race numbers suggest.
+//!
+//! The input can also be a capture directory, in which case every pcap of
+//! the ring is read, and of each feed's ring in its subdirectories when the
+//! capture was written `per_feed`. `--slots FIRST-LAST` keeps only those
+//! slots and skips what it can: slot-named archives outside the range are
+//! not opened, and an archive with an index sidecar is read from the first
+//! record of the range on (see [`crate::capture`]).
+//!
+//! `--live` follows the active file of a running capture instead
+//! (`DIR/shreds.pcap`, each `DIR/<feed>/shreds.pcap` of a `per_feed`
+//! capture, or the file given): records are read as they are
+//! written, across rotations, and the race table is redrawn every
+//! `--interval` seconds until Ctrl-C. Feed mappings and timing can be checked
+//! on a fresh capture without stopping the ring or copying files off it.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use pcap_file::pcap::PcapReader;
-use std::collections::HashMap;
+use serde::Serialize;
+use shred_ingest::clock::{RealtimeNs, ReplayClock};
+use shred_ingest::shred_race::{ShredArrival, ShredPairSnapshot, ShredRaceTracker, SlotHorizon};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs::File;
-use std::net::Ipv4Addr;
-use std::path::Path;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use tracing::warn;
 
+use crate::capture::{archive_slots, read_slot_index, ring_file_format};
+use crate::capture_status::archive_order;
+
 // ─── Shred header constants (mirrors decoder.rs) ──────────────────────────────
 
 const VARIANT_OFF: usize = 64;
@@ -42,105 +84,292 @@ fn parse_slot_index(bytes: &[u8]) -> Option<(u64, u32)> {
     Some((slot, index))
 }
 
+/// Destination and UDP payload of an Ethernet/IPv4/UDP frame.
+fn udp_frame(data: &[u8]) -> Option<(SocketAddrV4, &[u8])> {
+    // Ethernet(14) + IPv4(20) + UDP(8) headers; EtherType IPv4 (0x0800) and
+    // IP protocol UDP (0x11).
+    if data.len() < 42 || data[12] != 0x08 || data[13] != 0x00 || data[23] != 0x11 {
+        return None;
+    }
+    // dst IP is at IPv4 header bytes 16-19 → frame bytes 30-33; the UDP dst
+    // port follows the src port at frame bytes 36-37.
+    let dst_ip = Ipv4Addr::new(data[30], data[31], data[32], data[33]);
+    let dst = SocketAddrV4::new(dst_ip, u16::from_be_bytes([data[36], data[37]]));
+    // UDP payload starts at byte 42 (14 + 20 + 8).
+    Some((dst, &data[42..]))
+}
+
 // ─── Internal types ───────────────────────────────────────────────────────────
 
-struct ShredEvent {
-    feed: String,
-    timestamp_ns: u64,
+/// Feeds per capture; each takes one bit of a shred's [`SeenBy`] mask.
+const MAX_FEEDS: usize = 64;
+
+/// One data shred as received; `feed` indexes the feed names.
+struct Arrival {
+    ts_ns: u64,
+    feed: usize,
+    slot: u64,
+    index: u32,
 }
 
-/// First two arrivals for a (slot, shred_index) pair.
-type RaceMap = HashMap<(u64, u32), (ShredEvent, Option<ShredEvent>)>;
+/// The feeds that delivered each (slot, shred_index), one bit per feed.
+type SeenBy = HashMap<(u64, u32), u64>;
 
-// ─── Entry point ─────────────────────────────────────────────────────────────
+/// Distinct shreds per [`SeenBy`] mask.
+type MaskCounts = HashMap<u64, u64>;
 
-pub fn run(pcap: &Path, feed_args: &[(Ipv4Addr, String)], min_matched: u64) -> Result<()> {
-    let file = File::open(pcap)?;
-    let mut reader = PcapReader::new(file)?;
+/// One pair of the race with the share of its shreds both feeds delivered.
+#[derive(Serialize)]
+struct PairReport {
+    #[serde(flatten)]
+    race: ShredPairSnapshot,
+    /// Distinct shreds either feed delivered.
+    shreds: u64,
+    /// `total_matched` as a share of `shreds` (0–100).
+    matched_pct: f64,
+}
 
-    // Build IP-octets → feed-name lookup.
-    let feed_map: HashMap<[u8; 4], &str> =
-        feed_args.iter().map(|(ip, name)| (ip.octets(), name.as_str())).collect();
+/// What `--json` writes; `shred_race` has the shape of the metrics log's.
+#[derive(Serialize)]
+struct RaceReport<'a> {
+    pcap: String,
+    shred_race: &'a [PairReport],
+}
 
-    let mut race: RaceMap = HashMap::new();
-    let mut packets_read: u64 = 0;
-    let mut shreds_parsed: u64 = 0;
+/// Reorder depth buckets: upper bounds (inclusive) of 0, 1, 2–3, 4–15, 16–63;
+/// the last bucket takes everything deeper.
+const DEPTH_BOUNDS: [u64; 5] = [0, 1, 3, 15, 63];
+const DEPTH_LABELS: [&str; 6] = ["0", "1", "2-3", "4-15", "16-63", "64+"];
 
-    while let Some(pkt_result) = reader.next_packet() {
-        let pkt = match pkt_result {
-            Ok(p) => p,
-            Err(e) => {
-                warn!("pcap read error: {}", e);
-                continue;
-            }
+/// Data shreds of one slot as received on one feed.
+#[derive(Default)]
+struct SlotTrack {
+    /// Distinct indices received, ascending.
+    indices: Vec<u32>,
+    last_ns: u64,
+    last_gap_ns: Option<u64>,
+}
+
+/// Continuity and ordering statistics for one feed.
+#[derive(Default)]
+struct FeedContinuity {
+    slots: HashMap<u64, SlotTrack>,
+    /// Arrivals per reorder-depth bucket. The depth of a shred is the number
+    /// of higher indices of its slot that arrived before it.
+    depth_buckets: [u64; 6],
+    max_depth: u64,
+    /// Gaps between consecutive data shreds of the same slot.
+    gaps_ns: Vec<u64>,
+    /// Sum and count of |gap − previous gap| (RFC 3550-style jitter, unsmoothed).
+    jitter_sum_ns: u128,
+    jitter_n: u64,
+}
+
+impl FeedContinuity {
+    fn record(&mut self, slot: u64, index: u32, ts_ns: u64) {
+        let track = self.slots.entry(slot).or_default();
+        let pos = match track.indices.binary_search(&index) {
+            Ok(_) => return, // retransmit / duplicate
+            Err(pos) => pos,
         };
-        packets_read += 1;
+        let depth = (track.indices.len() - pos) as u64;
+        track.indices.insert(pos, index);
+        let bucket =
+            DEPTH_BOUNDS.iter().position(|&b| depth <= b).unwrap_or(DEPTH_BOUNDS.len());
+        self.depth_buckets[bucket] += 1;
+        self.max_depth = self.max_depth.max(depth);
 
-        let data = &pkt.data;
-        // Minimum frame: Ethernet(14) + IPv4(20) + UDP(8) + shred header(77) = 119
-        if data.len() < 119 {
-            continue;
-        }
-        // EtherType must be IPv4 (0x0800).
-        if data[12] != 0x08 || data[13] != 0x00 {
-            continue;
+        if track.indices.len() > 1 {
+            let gap = ts_ns.saturating_sub(track.last_ns);
+            self.gaps_ns.push(gap);
+            if let Some(prev) = track.last_gap_ns {
+                self.jitter_sum_ns += gap.abs_diff(prev) as u128;
+                self.jitter_n += 1;
+            }
+            track.last_gap_ns = Some(gap);
         }
-        // IP protocol must be UDP (0x11).
-        if data[23] != 0x11 {
-            continue;
+        track.last_ns = ts_ns;
+    }
+
+    /// Largest run of missing indices between two received ones, with its
+    /// slot, and the number of slots that have any such hole.
+    fn index_gaps(&self) -> (u32, Option<u64>, u64) {
+        let (mut max_gap, mut max_slot, mut gappy) = (0u32, None, 0u64);
+        for (&slot, t) in &self.slots {
+            let gap = t.indices.windows(2).map(|w| w[1] - w[0] - 1).max().unwrap_or(0);
+            if gap > 0 {
+                gappy += 1;
+            }
+            if gap > max_gap {
+                max_gap = gap;
+                max_slot = Some(slot);
+            }
         }
+        (max_gap, max_slot, gappy)
+    }
+}
 
-        // dst IP is at IPv4 header bytes 16-19 → frame bytes 30-33.
-        let dst_ip = [data[30], data[31], data[32], data[33]];
-        let feed = feed_map
-            .get(&dst_ip)
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| format!("{}.{}.{}.{}", dst_ip[0], dst_ip[1], dst_ip[2], dst_ip[3]));
+/// Histogram output options for `--histogram` / `--ascii`.
+pub struct HistogramOpts {
+    /// Write per-pair histograms here (CSV if the extension is `.csv`, else JSON).
+    pub out: Option<std::path::PathBuf>,
+    /// Bucket width in microseconds.
+    pub bucket_us: u64,
+    /// Render histograms in the terminal.
+    pub ascii: bool,
+}
 
-        // UDP payload starts at byte 42 (14 + 20 + 8).
-        let udp_payload = &data[42..];
+/// Signed lead-time histogram for one feed pair. `delta = t(feed_b) − t(feed_a)`,
+/// so positive values mean `feed_a` arrived first.
+#[derive(Serialize)]
+struct PairHistogram {
+    feed_a: String,
+    feed_b: String,
+    samples: u64,
+    /// Bucket index (floor(delta_us / bucket_us)) → count.
+    #[serde(skip)]
+    counts: BTreeMap<i64, u64>,
+    buckets: Vec<HistogramBucket>,
+}
 
-        if !is_data_shred(udp_payload) {
-            continue;
-        }
-        let (slot, index) = match parse_slot_index(udp_payload) {
-            Some(v) => v,
-            None => continue,
-        };
+#[derive(Serialize)]
+struct HistogramBucket {
+    /// Inclusive lower bound in µs.
+    lo_us: i64,
+    /// Exclusive upper bound in µs.
+    hi_us: i64,
+    count: u64,
+}
 
-        shreds_parsed += 1;
-        let ts_ns = pkt.timestamp.as_nanos() as u64;
-        let key = (slot, index);
+#[derive(Serialize)]
+struct HistogramReport<'a> {
+    pcap: String,
+    bucket_us: u64,
+    pairs: &'a [PairHistogram],
+}
 
-        match race.entry(key) {
-            std::collections::hash_map::Entry::Vacant(e) => {
-                e.insert((ShredEvent { feed, timestamp_ns: ts_ns }, None));
-            }
-            std::collections::hash_map::Entry::Occupied(mut e) => {
-                let val = e.get_mut();
-                // Only record the second distinct-feed arrival; ignore 3rd+.
-                if val.1.is_none() && val.0.feed != feed {
-                    val.1 = Some(ShredEvent { feed, timestamp_ns: ts_ns });
+// ─── Entry point ─────────────────────────────────────────────────────────────
+
+pub fn run(
+    pcap: &Path,
+    feed_args: &[(Ipv4Addr, String)],
+    min_matched: u64,
+    slots: Option<(u64, u64)>,
+    hist: &HistogramOpts,
+    json: Option<&Path>,
+) -> Result<()> {
+    anyhow::ensure!(hist.bucket_us > 0, "--bucket-us must be greater than zero");
+
+    let inputs = pcap_inputs(pcap, slots)?;
+
+    // Build IP-octets → feed-name lookup. Without one, each destination
+    // address is a feed.
+    let feed_map: HashMap<[u8; 4], &str> =
+        feed_args.iter().map(|(ip, name)| (ip.octets(), name.as_str())).collect();
+    let discover = feed_args.is_empty();
+    let mut destinations: HashMap<SocketAddrV4, u64> = HashMap::new();
+
+    let mut feed_names: Vec<Arc<str>> = Vec::new();
+    let mut arrivals: Vec<Arrival> = Vec::new();
+    let mut seen: SeenBy = HashMap::new();
+    let mut continuity: BTreeMap<String, FeedContinuity> = BTreeMap::new();
+    let mut packets_read: u64 = 0;
+    let mut shreds_parsed: u64 = 0;
+
+    for (path, offset) in &inputs {
+        let mut reader = open_pcap_at(path, *offset)?;
+        while let Some(pkt_result) = reader.next_packet() {
+            let pkt = match pkt_result {
+                Ok(p) => p,
+                Err(e) => {
+                    warn!("pcap read error: {}", e);
+                    continue;
                 }
+            };
+            packets_read += 1;
+
+            let Some((dst, udp_payload)) = udp_frame(&pkt.data) else { continue };
+            if discover {
+                *destinations.entry(dst).or_insert(0) += 1;
+            }
+            if !is_data_shred(udp_payload) {
+                continue;
             }
+            let (slot, index) = match parse_slot_index(udp_payload) {
+                Some(v) => v,
+                None => continue,
+            };
+            if slots.is_some_and(|(lo, hi)| slot < lo || slot > hi) {
+                continue;
+            }
+
+            shreds_parsed += 1;
+            let ts_ns = pkt.timestamp.as_nanos() as u64;
+            let feed = feed_name(&feed_map, discover, dst);
+
+            match continuity.get_mut(&feed) {
+                Some(c) => c.record(slot, index, ts_ns),
+                None => continuity.entry(feed.clone()).or_default().record(slot, index, ts_ns),
+            }
+
+            let feed = feed_id(&mut feed_names, &feed)?;
+            *seen.entry((slot, index)).or_insert(0) |= 1 << feed;
+            arrivals.push(Arrival { ts_ns, feed, slot, index });
         }
     }
 
-    // ─── Aggregate ───────────────────────────────────────────────────────────
-
-    let mut wins: HashMap<String, u64> = HashMap::new();
-    let mut lead_ns: HashMap<String, Vec<u64>> = HashMap::new();
-    let mut pairs_matched: u64 = 0;
+    // ─── Race ────────────────────────────────────────────────────────────────
 
-    for (_, (first, second)) in &race {
-        let Some(second) = second else { continue };
-        pairs_matched += 1;
+    // Capture order is not timestamp order across ring files or merged
+    // captures; the tracker matches arrivals in the order it is given them.
+    arrivals.sort_by_key(|a| a.ts_ns);
+    let tracker = ShredRaceTracker::deterministic();
+    let want_hist = hist.out.is_some() || hist.ascii;
+    let histograms: Arc<Mutex<BTreeMap<(String, String), PairHistogram>>> = Arc::default();
+    if want_hist {
+        let histograms = histograms.clone();
+        let bucket_ns = hist.bucket_us as i128 * 1000;
+        tracker.on_race(move |race| {
+            let lead = race.loser_recv_ns.delta(race.winner_recv_ns) as i128;
+            // Orient the pair by name so A-vs-B and B-vs-A share one histogram.
+            let (a, b, delta_ns) = if race.winner < race.loser {
+                (&race.winner, &race.loser, lead)
+            } else {
+                (&race.loser, &race.winner, -lead)
+            };
+            let mut histograms = histograms.lock().unwrap();
+            let h = histograms.entry((a.to_string(), b.to_string())).or_insert_with(|| {
+                PairHistogram {
+                    feed_a: a.to_string(),
+                    feed_b: b.to_string(),
+                    samples: 0,
+                    counts: BTreeMap::new(),
+                    buckets: Vec::new(),
+                }
+            });
+            h.samples += 1;
+            *h.counts.entry(delta_ns.div_euclid(bucket_ns) as i64).or_insert(0) += 1;
+        });
+    }
+    let clock = ReplayClock::starting_at(RealtimeNs(arrivals.first().map_or(0, |a| a.ts_ns)));
+    let race_tx = tracker.sender();
+    for a in &arrivals {
+        let _ = race_tx.send(ShredArrival {
+            source: feed_names[a.feed].clone(),
+            slot: a.slot,
+            idx: a.index,
+            recv_ns: clock.to_monotonic(RealtimeNs(a.ts_ns)),
+            recovered: false,
+        });
+        tracker.process_pending();
+    }
 
-        let lead = second.timestamp_ns.saturating_sub(first.timestamp_ns);
-        *wins.entry(first.feed.clone()).or_insert(0) += 1;
-        lead_ns.entry(first.feed.clone()).or_default().push(lead);
-        wins.entry(second.feed.clone()).or_insert(0);
+    let mut masks = MaskCounts::new();
+    for &mask in seen.values() {
+        *masks.entry(mask).or_insert(0) += 1;
     }
+    let pairs = pair_reports(&tracker, &feed_names, &masks);
+    let pairs_matched: u64 = pairs.iter().map(|p| p.race.total_matched).sum();
 
     // ─── Output ──────────────────────────────────────────────────────────────
 
@@ -154,6 +383,12 @@ pub fn run(pcap: &Path, feed_args: &[(Ipv4Addr, String)], min_matched: u64) -> R
     );
     println!();
 
+    if discover {
+        for line in destination_lines(&destinations) {
+            println!("{}", line);
+        }
+    }
+
     if pairs_matched < min_matched {
         warn!(
             "only {} matched pairs (--min-matched {}); check --feed mappings or pcap content",
@@ -161,54 +396,718 @@ pub fn run(pcap: &Path, feed_args: &[(Ipv4Addr, String)], min_matched: u64) -> R
         );
     }
 
-    // Sort feeds: winner (most first-arrivals) first.
-    let mut feeds: Vec<String> = wins.keys().cloned().collect();
-    feeds.sort_by(|a, b| wins[b].cmp(&wins[a]));
+    println!("SHRED RACE  whole capture:");
+    for line in race_lines(&pairs)? {
+        println!("{}", line);
+    }
+    for name in &feed_names {
+        let raced = pairs.iter().any(|p| {
+            p.race.total_matched > 0 && (p.race.source_a == *name || p.race.source_b == *name)
+        });
+        if !raced {
+            warn!(
+                "feed '{}': no shred also arrived on another feed — check --feed IP mapping",
+                name
+            );
+        }
+    }
 
-    println!(
-        "  {:<24}  {:>6}  {:>10}  {:>10}  {:>10}  {:>10}",
-        "FEED", "WIN%", "MATCHED", "AVG LEAD", "LEAD p50", "LEAD p95",
+    if let Some(out) = json {
+        let report = RaceReport { pcap: pcap.display().to_string(), shred_race: &pairs };
+        let body = serde_json::to_string_pretty(&report)? + "\n";
+        std::fs::write(out, body).with_context(|| format!("failed to write {}", out.display()))?;
+        eprintln!("Wrote {} pair(s) to {}", pairs.len(), out.display());
+    }
+
+    println!();
+    print_continuity(&mut continuity);
+
+    if want_hist {
+        let bucket_us = hist.bucket_us as i64;
+        let mut pairs: Vec<PairHistogram> =
+            std::mem::take(&mut *histograms.lock().unwrap()).into_values().collect();
+        for p in &mut pairs {
+            p.buckets = p
+                .counts
+                .iter()
+                .map(|(&i, &count)| HistogramBucket {
+                    lo_us: i * bucket_us,
+                    hi_us: (i + 1) * bucket_us,
+                    count,
+                })
+                .collect();
+        }
+
+        if hist.ascii {
+            for p in &pairs {
+                print_ascii_histogram(p);
+            }
+        }
+        if let Some(out) = &hist.out {
+            write_histograms(out, pcap, hist.bucket_us, &pairs)?;
+            eprintln!("Wrote {} pair histogram(s) to {}", pairs.len(), out.display());
+        }
+    }
+
+    Ok(())
+}
+
+// ─── Feeds and races ─────────────────────────────────────────────────────────
+
+/// The feed a packet to `dst` belongs to: its `--feed` name, else its address.
+fn feed_name(feed_map: &HashMap<[u8; 4], &str>, discover: bool, dst: SocketAddrV4) -> String {
+    match feed_map.get(&dst.ip().octets()) {
+        Some(name) => name.to_string(),
+        None if discover => dst.to_string(),
+        None => dst.ip().to_string(),
+    }
+}
+
+/// The index of `feed` in `feed_names`, adding it if it is new.
+fn feed_id(feed_names: &mut Vec<Arc<str>>, feed: &str) -> Result<usize> {
+    if let Some(id) = feed_names.iter().position(|f| **f == *feed) {
+        return Ok(id);
+    }
+    anyhow::ensure!(
+        feed_names.len() < MAX_FEEDS,
+        "more than {} feeds in the capture; map them with --feed",
+        MAX_FEEDS
     );
-    println!("  {}", "-".repeat(78));
+    feed_names.push(feed.into());
+    Ok(feed_names.len() - 1)
+}
 
-    for feed in &feeds {
-        let feed_wins = wins[feed];
-        let win_pct = if pairs_matched > 0 {
-            100.0 * feed_wins as f64 / pairs_matched as f64
-        } else {
-            0.0
-        };
+/// The tracker's pairs with their shred counts, busiest pair first as in the
+/// race table.
+fn pair_reports(
+    tracker: &ShredRaceTracker,
+    feed_names: &[Arc<str>],
+    masks: &MaskCounts,
+) -> Vec<PairReport> {
+    let feed_bit = |name: &str| feed_names.iter().position(|f| **f == *name).map_or(0, |i| 1 << i);
+    let mut pairs: Vec<PairReport> = tracker
+        .snapshots()
+        .into_iter()
+        .map(|race| {
+            let bits = feed_bit(&race.source_a) | feed_bit(&race.source_b);
+            let shreds: u64 = masks.iter().filter(|(&m, _)| m & bits != 0).map(|(_, &n)| n).sum();
+            let matched_pct = 100.0 * race.total_matched as f64 / shreds.max(1) as f64;
+            PairReport { race, shreds, matched_pct }
+        })
+        .collect();
+    pairs.sort_by(|a, b| b.race.total_matched.cmp(&a.race.total_matched));
+    pairs
+}
 
-        if let Some(times) = lead_ns.get_mut(feed) {
-            times.sort_unstable();
-            let avg_us = times.iter().sum::<u64>() as f64 / times.len() as f64 / 1000.0;
-            let p50 = percentile(times, 50) as f64 / 1000.0;
-            let p95 = percentile(times, 95) as f64 / 1000.0;
-            println!(
-                "  {:<24}  {:>5.1}%  {:>10}  {:>10}  {:>10}  {:>10}",
-                feed,
-                win_pct,
-                fmt_num(feed_wins),
-                format!("{:+.0}µs", avg_us),
-                format!("{:+.0}µs", p50),
-                format!("{:+.0}µs", p95),
+/// The SHRED RACE table of `monitor`, then shreds and MATCH% per pair.
+fn race_lines(pairs: &[PairReport]) -> Result<Vec<String>> {
+    if pairs.is_empty() {
+        return Ok(vec!["  No shred arrived on more than one feed.".into()]);
+    }
+    let values = pairs
+        .iter()
+        .map(|p| serde_json::to_value(&p.race))
+        .collect::<serde_json::Result<Vec<_>>>()?;
+    let mut lines = crate::monitor::race_table(&values);
+    lines.push(String::new());
+    lines.push(format!("  {:<46}  {:>12}  {:>12}  {:>7}", "PAIR", "SHREDS", "RACES", "MATCH%"));
+    for p in pairs {
+        lines.push(format!(
+            "  {:<46}  {:>12}  {:>12}  {:>6.1}%",
+            format!("{} / {}", p.race.source_a, p.race.source_b),
+            fmt_num(p.shreds),
+            fmt_num(p.race.total_matched),
+            p.matched_pct,
+        ));
+    }
+    Ok(lines)
+}
+
+// ─── Inputs ──────────────────────────────────────────────────────────────────
+
+/// The pcap files to read for `path` (a file or a capture directory), each
+/// with the byte offset to start reading at, in the order `capture list`
+/// lists them. With `slots`, files that cannot hold them are left out.
+fn pcap_inputs(path: &Path, slots: Option<(u64, u64)>) -> Result<Vec<(PathBuf, u64)>> {
+    let mut files = if path.is_dir() {
+        let mut files = Vec::new();
+        for dir in std::iter::once(path.to_path_buf()).chain(feed_dirs(path)?) {
+            files.extend(
+                std::fs::read_dir(&dir)
+                    .with_context(|| format!("failed to read {}", dir.display()))?
+                    .filter_map(|e| e.ok())
+                    .filter(|e| ring_file_format(&e.file_name().to_string_lossy()) == Some("pcap"))
+                    .map(|e| e.path()),
             );
+        }
+        anyhow::ensure!(!files.is_empty(), "no pcap capture files in {}", path.display());
+        files.sort_by_key(|f| (archive_order(f), f.clone()));
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+    let Some((lo, hi)) = slots else {
+        return Ok(files.into_iter().map(|f| (f, 0)).collect());
+    };
+    anyhow::ensure!(lo <= hi, "--slots: {} is after {}", lo, hi);
+
+    files.retain(|f| {
+        let name = f.file_name().unwrap_or_default().to_string_lossy();
+        !archive_slots(&name).is_some_and(|(first, last)| first > hi || last < lo)
+    });
+    let mut inputs = Vec::new();
+    for file in files {
+        // Without an index the whole file is read.
+        let Ok(index) = read_slot_index(&file) else {
+            inputs.push((file, 0));
+            continue;
+        };
+        let start = index.iter().filter(|&&(s, _)| s >= lo && s <= hi).map(|&(_, o)| o).min();
+        if let Some(start) = start {
+            inputs.push((file, start));
+        }
+    }
+    Ok(inputs)
+}
+
+/// The subdirectories of a capture directory: one per feed when the capture
+/// was written `per_feed`.
+fn feed_dirs(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// A reader for the pcap at `path` that starts with the packet at byte
+/// `offset`, or with the first one if `offset` is 0.
+fn open_pcap_at(path: &Path, offset: u64) -> Result<PcapReader<Box<dyn Read>>> {
+    let mut file =
+        File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let reader: Box<dyn Read> = if offset == 0 {
+        Box::new(file)
+    } else {
+        // The file header still comes first; the packets follow from `offset`.
+        let mut header = [0u8; 24];
+        file.read_exact(&mut header)?;
+        file.seek(SeekFrom::Start(offset))?;
+        Box::new(Cursor::new(header).chain(file))
+    };
+    Ok(PcapReader::new(reader)?)
+}
+
+// ─── Live (`--live`) ─────────────────────────────────────────────────────────
+
+/// Arrivals are held back this long (capture time) before they are raced, so
+/// that records written slightly out of order still reach the tracker in
+/// timestamp order.
+const LIVE_REORDER_NS: u64 = 500_000_000;
+
+/// Slots kept per shred for the SHREDS column; older ones are folded into
+/// per-mask totals so a long session does not grow without bound.
+const LIVE_KEEP_SLOTS: u64 = 64;
+
+/// How often the active file is read.
+const LIVE_TICK: std::time::Duration = std::time::Duration::from_millis(100);
+
+static LIVE: AtomicBool = AtomicBool::new(true);
+
+extern "C" fn handle_sigint(_: libc::c_int) {
+    LIVE.store(false, Ordering::SeqCst);
+}
+
+/// The record layout of a pcap, from the magic number of its header.
+#[derive(Clone, Copy)]
+struct PcapFormat {
+    big_endian: bool,
+    nanos: bool,
+}
+
+/// Follows the active file of a capture ring: reads the records appended
+/// since the last poll, and moves on to the new active file when the ring
+/// rotates (the path then names another inode).
+struct PcapTail {
+    path: PathBuf,
+    file: Option<(File, u64)>,
+    /// Bytes read but not parsed yet: the header, or a record still being
+    /// written.
+    buf: Vec<u8>,
+    format: Option<PcapFormat>,
+    rotations: u64,
+}
+
+impl PcapTail {
+    fn new(path: PathBuf) -> Self {
+        Self { path, file: None, buf: Vec::new(), format: None, rotations: 0 }
+    }
+
+    /// Call `f` with the timestamp (ns) and frame of every complete record
+    /// written since the last poll. A missing file is waited for.
+    fn poll(&mut self, f: &mut impl FnMut(u64, &[u8]) -> Result<()>) -> Result<()> {
+        loop {
+            if self.file.is_none() {
+                let file = match File::open(&self.path) {
+                    Ok(file) => file,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+                    Err(e) => {
+                        let path = self.path.display();
+                        return Err(e).with_context(|| format!("failed to open {}", path));
+                    }
+                };
+                let ino = file.metadata()?.ino();
+                self.file = Some((file, ino));
+            }
+            let (file, ino) = self.file.as_mut().unwrap();
+            file.read_to_end(&mut self.buf)?;
+            let rotated = std::fs::metadata(&self.path).is_ok_and(|m| m.ino() != *ino);
+            if rotated {
+                // Renamed before we looked: whatever it got since is final.
+                file.read_to_end(&mut self.buf)?;
+            }
+            self.parse(f)?;
+            if !rotated {
+                return Ok(());
+            }
+            // A partial record left in a rotated file was never finished.
+            self.file = None;
+            self.buf.clear();
+            self.format = None;
+            self.rotations += 1;
+        }
+    }
+
+    fn parse(&mut self, f: &mut impl FnMut(u64, &[u8]) -> Result<()>) -> Result<()> {
+        let mut pos = 0;
+        let format = match self.format {
+            Some(format) => format,
+            None if self.buf.len() < 24 => return Ok(()),
+            None => {
+                let magic = u32::from_le_bytes(self.buf[..4].try_into().unwrap());
+                let (big_endian, nanos) = match magic {
+                    0xa1b2c3d4 => (false, false),
+                    0xa1b23c4d => (false, true),
+                    0xd4c3b2a1 => (true, false),
+                    0x4d3cb2a1 => (true, true),
+                    _ => anyhow::bail!("{} is not a pcap file", self.path.display()),
+                };
+                pos = 24;
+                *self.format.insert(PcapFormat { big_endian, nanos })
+            }
+        };
+        let word = |b: &[u8]| {
+            let b: [u8; 4] = b.try_into().unwrap();
+            if format.big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) }
+        };
+        while self.buf.len() - pos >= 16 {
+            let rec = &self.buf[pos..];
+            let (secs, frac, len) = (word(&rec[..4]), word(&rec[4..8]), word(&rec[8..12]));
+            let len = len as usize;
+            if rec.len() - 16 < len {
+                break;
+            }
+            let frac_ns = if format.nanos { frac as u64 } else { frac as u64 * 1000 };
+            f(secs as u64 * 1_000_000_000 + frac_ns, &rec[16..16 + len])?;
+            pos += 16 + len;
+        }
+        self.buf.drain(..pos);
+        Ok(())
+    }
+}
+
+/// Everything `--live` has read so far.
+struct LiveRace {
+    tracker: Arc<ShredRaceTracker>,
+    feed_names: Vec<Arc<str>>,
+    destinations: HashMap<SocketAddrV4, u64>,
+    packets_read: u64,
+    shreds_parsed: u64,
+    /// Not raced yet; see [`LIVE_REORDER_NS`].
+    pending: Vec<Arrival>,
+    /// Starts at the first arrival.
+    clock: Option<ReplayClock>,
+    newest_ns: u64,
+    newest_slot: SlotHorizon,
+    /// Shreds of the last [`LIVE_KEEP_SLOTS`] slots.
+    seen: SeenBy,
+    /// Shreds of older slots, by mask.
+    folded: MaskCounts,
+}
+
+impl LiveRace {
+    fn new() -> Self {
+        Self {
+            tracker: ShredRaceTracker::deterministic(),
+            feed_names: Vec::new(),
+            destinations: HashMap::new(),
+            packets_read: 0,
+            shreds_parsed: 0,
+            pending: Vec::new(),
+            clock: None,
+            newest_ns: 0,
+            newest_slot: SlotHorizon::default(),
+            seen: HashMap::new(),
+            folded: HashMap::new(),
+        }
+    }
+
+    fn record(
+        &mut self,
+        feed_map: &HashMap<[u8; 4], &str>,
+        discover: bool,
+        ts_ns: u64,
+        frame: &[u8],
+    ) -> Result<()> {
+        self.packets_read += 1;
+        let Some((dst, payload)) = udp_frame(frame) else { return Ok(()) };
+        if discover {
+            *self.destinations.entry(dst).or_insert(0) += 1;
+        }
+        if !is_data_shred(payload) {
+            return Ok(());
+        }
+        let Some((slot, index)) = parse_slot_index(payload) else { return Ok(()) };
+        self.shreds_parsed += 1;
+        let feed = feed_id(&mut self.feed_names, &feed_name(feed_map, discover, dst))?;
+        self.newest_ns = self.newest_ns.max(ts_ns);
+        self.clock.get_or_insert_with(|| ReplayClock::starting_at(RealtimeNs(ts_ns)));
+        self.keep(slot, index, feed);
+        self.pending.push(Arrival { ts_ns, feed, slot, index });
+        Ok(())
+    }
+
+    /// Mark `(slot, index)` as seen by `feed` if the slot is one of the last
+    /// [`LIVE_KEEP_SLOTS`]. A junk slot far ahead moves the newest slot by a
+    /// few slots at most, so it cannot push the real slots out.
+    fn keep(&mut self, slot: u64, index: u32, feed: usize) {
+        let newest = self.newest_slot.observe(slot);
+        if slot <= newest && slot.saturating_add(LIVE_KEEP_SLOTS) >= newest {
+            *self.seen.entry((slot, index)).or_insert(0) |= 1 << feed;
+        }
+    }
+
+    /// Race the arrivals that are old enough (all of them with `flush`),
+    /// then fold old slots.
+    fn race_ready(&mut self, flush: bool) {
+        self.pending.sort_by_key(|a| a.ts_ns);
+        let cutoff = if flush { u64::MAX } else { self.newest_ns.saturating_sub(LIVE_REORDER_NS) };
+        let ready = self.pending.partition_point(|a| a.ts_ns <= cutoff);
+        let race_tx = self.tracker.sender();
+        let clock = self.clock.unwrap_or_else(ReplayClock::identity);
+        for a in self.pending.drain(..ready) {
+            let _ = race_tx.send(ShredArrival {
+                source: self.feed_names[a.feed].clone(),
+                slot: a.slot,
+                idx: a.index,
+                recv_ns: clock.to_monotonic(RealtimeNs(a.ts_ns)),
+                recovered: false,
+            });
+        }
+        self.tracker.process_pending();
+
+        // Slots past the newest are what was kept while the newest slot was
+        // still being seeded from a junk one.
+        let newest = self.newest_slot.get();
+        let keep = newest.saturating_sub(LIVE_KEEP_SLOTS)..=newest;
+        let folded = &mut self.folded;
+        self.seen.retain(|&(slot, _), &mut mask| {
+            if !keep.contains(&slot) {
+                *folded.entry(mask).or_insert(0) += 1;
+            }
+            keep.contains(&slot)
+        });
+    }
+
+    fn masks(&self) -> MaskCounts {
+        let mut masks = self.folded.clone();
+        for &mask in self.seen.values() {
+            *masks.entry(mask).or_insert(0) += 1;
+        }
+        masks
+    }
+}
+
+/// `shredtop analyze --live`: follow the active file of a capture ring, of
+/// each feed's ring of a `per_feed` capture (or any pcap being written) and
+/// redraw the race table every `interval_secs` until Ctrl-C.
+pub fn run_live(path: &Path, feed_args: &[(Ipv4Addr, String)], interval_secs: u64) -> Result<()> {
+    anyhow::ensure!(interval_secs > 0, "--interval must be greater than zero");
+    let active: Vec<PathBuf> = if !path.is_dir() {
+        vec![path.to_path_buf()]
+    } else {
+        match feed_dirs(path)? {
+            dirs if dirs.is_empty() => vec![path.join("shreds.pcap")],
+            dirs => dirs.iter().map(|d| d.join("shreds.pcap")).collect(),
+        }
+    };
+    let feed_map: HashMap<[u8; 4], &str> =
+        feed_args.iter().map(|(ip, name)| (ip.octets(), name.as_str())).collect();
+    let discover = feed_args.is_empty();
+
+    // Records of several feeds' files meet in the reorder window.
+    let mut tails: Vec<PcapTail> = active.iter().cloned().map(PcapTail::new).collect();
+    let mut live = LiveRace::new();
+
+    LIVE.store(true, Ordering::SeqCst);
+    unsafe { libc::signal(libc::SIGINT, handle_sigint as *const () as libc::sighandler_t) };
+    println!("SHRED TIMING ANALYSIS (LIVE)  —  {}  —  Ctrl-C to stop", path.display());
+    println!();
+
+    // The file is read every tick, more often than the table is redrawn: a
+    // ring file that rotates out before it was opened is never read.
+    let interval = std::time::Duration::from_secs(interval_secs);
+    let mut next_draw = std::time::Instant::now();
+    let mut lines_drawn = 0usize;
+    let mut packets_drawn = 0u64;
+    while LIVE.load(Ordering::SeqCst) {
+        for tail in &mut tails {
+            tail.poll(&mut |ts_ns, frame| live.record(&feed_map, discover, ts_ns, frame))?;
+        }
+        if std::time::Instant::now() < next_draw {
+            std::thread::sleep(LIVE_TICK);
+            continue;
+        }
+        next_draw += interval;
+        // Nothing new since the last draw: the capture is idle, and nothing
+        // older can still arrive.
+        live.race_ready(live.packets_read == packets_drawn);
+        packets_drawn = live.packets_read;
+
+        let pairs = pair_reports(&live.tracker, &live.feed_names, &live.masks());
+        let pairs_matched: u64 = pairs.iter().map(|p| p.race.total_matched).sum();
+        let mut lines = vec![
+            format!(
+                "Packets read: {:>12}   Shreds parsed: {:>12}   Pairs matched: {:>12}",
+                fmt_num(live.packets_read),
+                fmt_num(live.shreds_parsed),
+                fmt_num(pairs_matched),
+            ),
+            format!(
+                "Newest slot: {:>13}   Rotations followed: {:>7}",
+                live.newest_slot.get(),
+                tails.iter().map(|t| t.rotations).sum::<u64>()
+            ),
+            String::new(),
+        ];
+        if tails.iter().all(|t| t.file.is_none()) {
+            let paths: Vec<String> = active.iter().map(|p| p.display().to_string()).collect();
+            lines.push(format!("Waiting for {}...", paths.join(", ")));
         } else {
-            println!(
-                "  {:<24}  {:>5.1}%  {:>10}  {:>10}  {:>10}  {:>10}",
-                feed, win_pct, "—", "—", "—", "—",
-            );
+            if discover {
+                lines.extend(destination_lines(&live.destinations));
+            }
+            lines.push("SHRED RACE  since start:".into());
+            lines.extend(race_lines(&pairs)?);
         }
 
-        if feed_wins == 0 {
-            warn!("feed '{}': no first-arrivals — check --feed IP mapping", feed);
+        if lines_drawn > 0 {
+            print!("\x1b[{}A\x1b[0J", lines_drawn);
+        }
+        for line in &lines {
+            println!("{}", line);
         }
+        lines_drawn = lines.len();
+        std::io::stdout().flush().ok();
+        std::thread::sleep(LIVE_TICK);
     }
+    println!();
+    Ok(())
+}
+
+// ─── Feed discovery output ───────────────────────────────────────────────────
+
+/// Destinations found when no `--feed` mappings were given, busiest first.
+fn destination_lines(destinations: &HashMap<SocketAddrV4, u64>) -> Vec<String> {
+    let mut rows: Vec<(&SocketAddrV4, &u64)> = destinations.iter().collect();
+    rows.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+    let mut lines = vec![
+        "DISCOVERED FEEDS  (no --feed given; each destination is a feed)".to_string(),
+        format!("  {:<24}  {:>12}", "DESTINATION", "PACKETS"),
+        format!("  {}", "-".repeat(38)),
+    ];
+    for (dst, packets) in rows {
+        lines.push(format!("  {:<24}  {:>12}", dst.to_string(), fmt_num(*packets)));
+    }
+    lines.push("  Name them with --feed IP=NAME.".into());
+    lines.push(String::new());
+    lines
+}
 
+// ─── Continuity output ───────────────────────────────────────────────────────
+
+fn print_continuity(feeds: &mut BTreeMap<String, FeedContinuity>) {
+    if feeds.is_empty() {
+        return;
+    }
+    // A slot counts as missing on a feed if any other feed delivered it while
+    // this feed was active (between its first and last slot). Slot numbers
+    // alone would count skipped leader slots as losses.
+    let all_slots: BTreeSet<u64> =
+        feeds.values().flat_map(|c| c.slots.keys().copied()).collect();
+
+    println!("SLOT CONTINUITY  (missing = delivered by another feed, not this one)");
+    println!(
+        "  {:<24}  {:>8}  {:>8}  {:>6}  {:>10}  {:>8}  {:>12}",
+        "FEED", "SLOTS", "MISSING", "MISS%", "GAP SLOTS", "MAX GAP", "IN SLOT",
+    );
+    println!("  {}", "-".repeat(88));
+    for (feed, c) in feeds.iter() {
+        let (Some(&first), Some(&last)) = (c.slots.keys().min(), c.slots.keys().max()) else {
+            continue;
+        };
+        let reference = all_slots.range(first..=last).count() as u64;
+        let missing = reference - c.slots.len() as u64;
+        let (max_gap, gap_slot, gappy) = c.index_gaps();
+        println!(
+            "  {:<24}  {:>8}  {:>8}  {:>5.1}%  {:>10}  {:>8}  {:>12}",
+            feed,
+            fmt_num(c.slots.len() as u64),
+            fmt_num(missing),
+            100.0 * missing as f64 / reference.max(1) as f64,
+            fmt_num(gappy),
+            fmt_num(max_gap as u64),
+            gap_slot.map(|s| s.to_string()).unwrap_or_else(|| "—".into()),
+        );
+    }
+    println!();
+
+    println!("ARRIVAL ORDER  (depth = higher indices of the slot that arrived earlier)");
+    let mut header = format!("  {:<24}", "FEED");
+    for label in DEPTH_LABELS {
+        header.push_str(&format!("  {:>6}", label));
+    }
+    header.push_str(&format!(
+        "  {:>6}  {:>9}  {:>9}  {:>9}",
+        "MAX", "IAT p50", "IAT p99", "JITTER"
+    ));
+    println!("{}", header);
+    println!("  {}", "-".repeat(header.chars().count() - 2));
+    for (feed, c) in feeds.iter_mut() {
+        let total: u64 = c.depth_buckets.iter().sum();
+        let mut line = format!("  {:<24}", feed);
+        for n in c.depth_buckets {
+            line.push_str(&format!("  {:>5.1}%", 100.0 * n as f64 / total.max(1) as f64));
+        }
+        c.gaps_ns.sort_unstable();
+        let us = |ns: u64| format!("{:.0}µs", ns as f64 / 1000.0);
+        let (p50, p99) = if c.gaps_ns.is_empty() {
+            ("—".to_string(), "—".to_string())
+        } else {
+            (us(percentile(&c.gaps_ns, 50)), us(percentile(&c.gaps_ns, 99)))
+        };
+        let jitter = if c.jitter_n > 0 {
+            us((c.jitter_sum_ns / c.jitter_n as u128) as u64)
+        } else {
+            "—".to_string()
+        };
+        line.push_str(&format!(
+            "  {:>6}  {:>9}  {:>9}  {:>9}",
+            fmt_num(c.max_depth),
+            p50,
+            p99,
+            jitter
+        ));
+        println!("{}", line);
+    }
     println!();
+}
+
+// ─── Histogram output ────────────────────────────────────────────────────────
+
+/// Bar width of the largest bucket in the ASCII rendering.
+const ASCII_BAR_WIDTH: u64 = 50;
+
+/// Tail mass (per side, in ‰) folded into the under/overflow rows of the ASCII
+/// rendering so a few outliers do not stretch the plot to thousands of rows.
+const ASCII_TAIL_PERMILLE: u64 = 5;
+
+fn write_histograms(out: &Path, pcap: &Path, bucket_us: u64, pairs: &[PairHistogram]) -> Result<()> {
+    let is_csv = out
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("csv"))
+        .unwrap_or(false);
+    let body = if is_csv {
+        let mut s = String::from("feed_a,feed_b,lo_us,hi_us,count\n");
+        for p in pairs {
+            for b in &p.buckets {
+                s.push_str(&format!(
+                    "{},{},{},{},{}\n",
+                    p.feed_a, p.feed_b, b.lo_us, b.hi_us, b.count
+                ));
+            }
+        }
+        s
+    } else {
+        let report = HistogramReport { pcap: pcap.display().to_string(), bucket_us, pairs };
+        serde_json::to_string_pretty(&report)? + "\n"
+    };
+    std::fs::write(out, body).with_context(|| format!("failed to write {}", out.display()))?;
     Ok(())
 }
 
+fn print_ascii_histogram(p: &PairHistogram) {
+    println!(
+        "LEAD HISTOGRAM  {} vs {}  ({} samples, + = {} first)",
+        p.feed_a,
+        p.feed_b,
+        fmt_num(p.samples),
+        p.feed_a
+    );
+    if p.buckets.is_empty() {
+        println!();
+        return;
+    }
+
+    // Trim the outer tails into single under/overflow rows.
+    let tail = p.samples * ASCII_TAIL_PERMILLE / 1000;
+    let (mut lo, mut hi) = (0, p.buckets.len() - 1);
+    let (mut under, mut over) = (0u64, 0u64);
+    while lo < hi && under + p.buckets[lo].count <= tail {
+        under += p.buckets[lo].count;
+        lo += 1;
+    }
+    while hi > lo && over + p.buckets[hi].count <= tail {
+        over += p.buckets[hi].count;
+        hi -= 1;
+    }
+
+    let shown = &p.buckets[lo..=hi];
+    let max = shown.iter().map(|b| b.count).max().unwrap_or(1).max(1);
+    let label = |lo_us: i64, hi_us: i64| format!("[{:+}, {:+})µs", lo_us, hi_us);
+
+    if under > 0 {
+        println!("  {:>22}  {:>10}", format!("< {:+}µs", shown[0].lo_us), fmt_num(under));
+    }
+    // Walk every bucket in range, including empty ones, so gaps stay visible.
+    let width = shown[0].hi_us - shown[0].lo_us;
+    let mut it = shown.iter().peekable();
+    let mut cur = shown[0].lo_us;
+    while let Some(b) = it.peek() {
+        let count = if b.lo_us == cur {
+            it.next().unwrap().count
+        } else {
+            0
+        };
+        let bar = (count * ASCII_BAR_WIDTH).div_ceil(max) as usize;
+        println!(
+            "  {:>22}  {:>10}  {}",
+            label(cur, cur + width),
+            fmt_num(count),
+            "#".repeat(bar)
+        );
+        cur += width;
+    }
+    if over > 0 {
+        let last = shown[shown.len() - 1].hi_us;
+        println!("  {:>22}  {:>10}", format!(">= {:+}µs", last), fmt_num(over));
+    }
+    println!();
+}
+
 // ─── Helpers ─────────────────────────────────────────────────────────────────
 
 fn percentile(sorted: &[u64], pct: usize) -> u64 {
@@ -230,3 +1129,33 @@ fn fmt_num(n: u64) -> String {
     }
     out.chars().rev().collect()
 }
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_live_junk_slot_keeps_real_slots() {
+        // Junk before the first real shred, and again once the newest slot is
+        // seeded: neither overflows nor pushes slot 1000 out.
+        let mut live = LiveRace::new();
+        live.keep(u64::MAX - 1, 0, 0);
+        for idx in 0..16 {
+            live.keep(1000, idx, 0);
+        }
+        live.keep(u64::MAX, 0, 1);
+        for idx in 0..16 {
+            live.keep(1000, idx, 1);
+        }
+        live.race_ready(true);
+
+        assert!(live.newest_slot.get() < 1000 + LIVE_KEEP_SLOTS);
+        assert_eq!(live.seen.len(), 16);
+        assert!(live.seen.values().all(|&mask| mask == 0b11));
+        assert_eq!(live.folded.get(&0b1), Some(&1), "the first junk shred is folded");
+    }
+}
@@ -2,74 +2,408 @@
 //!
 //! Runs all configured sources for a fixed duration, then emits a JSON report
 //! with per-source statistics including lead-time histogram, win rate, FEC recovery,
-//! and coverage percentage.
+//! and coverage percentage. For shred-tier sources the report also breaks down the
+//! transactions that beat RPC by more than `--early-ms`: compute-unit price buckets
+//! and the most frequent fee payers.
+//!
+//! `--interval-ms MS` adds a time series to the report, one point every `MS`
+//! milliseconds (see [`crate::sample`]).
+//!
+//! `--offline PCAP` benchmarks the decoder alone instead: the capture is
+//! replayed from memory as fast as the decoder will take it, and the report
+//! gives throughput and CPU time per decoder stage. The input is fixed, so
+//! runs are comparable across builds (e.g. in CI).
+//!
+//! `--busy-poll-us`, `--rcvbuf-mb`, `--batch` and `--pin` change the receive
+//! socket settings of a run. With `--matrix` each takes a list, and every
+//! combination gets its own `--duration` window. The windows run one after
+//! another, each in a child `shredtop bench` so it starts from fresh sockets
+//! and threads, and the results are reported side by side.
 
-use anyhow::Result;
-use serde::Serialize;
-use shred_ingest::{DecodedTx, FanInSource, SourceMetricsSnapshot};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use shred_ingest::{EarlyTxSnapshot, FanInSource, ForwardedTx, SocketTuning, SourceMetricsSnapshot};
 use shred_ingest::source_metrics::SlotStats;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::time::{Duration, Instant};
 
-use crate::config::ProbeConfig;
-use crate::monitor::build_source;
+use crate::config::{ProbeConfig, SourceEntry};
+use crate::monitor::{build_failover_groups, build_source};
+use crate::sample::{self, Sample, Sampler};
+use crate::upgrade::private_temp_dir;
 
 #[derive(Debug, Serialize)]
 pub struct BenchReport {
     pub duration_secs: u64,
+    /// Lead threshold (ms) above which a transaction counts as "early".
+    pub early_threshold_ms: u64,
+    /// `latency_budget_ms` of the config; see `in_budget_pct`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_budget_ms: Option<u64>,
+    /// Receive settings, when not the defaults.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settings: Option<RunSettings>,
     pub sources: Vec<SourceReport>,
+    /// Per-interval rates, oldest first (`--interval-ms` only).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub samples: Vec<Sample>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct SourceReport {
     pub name: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
     pub shreds_received: u64,
     pub shreds_per_sec: f64,
     pub bytes_received_mb: f64,
     pub shreds_dropped: u64,
+    /// Kernel receive buffer, its high-water mark, and datagrams the kernel
+    /// dropped on overflow (UDP sources on Linux only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket_rcvbuf_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket_rmem_hwm_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket_drops: Option<u64>,
+    /// Receive thread time spent handling packets rather than waiting in
+    /// recvmmsg, %, and mean packets per call (UDP sources on Linux only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recv_utilization_pct: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recv_batch_fill: Option<f64>,
     pub slots_attempted: u64,
     pub slots_complete: u64,
     pub slots_partial: u64,
     pub slots_dropped: u64,
     pub coverage_pct: Option<f64>,
+    /// Decoded share of the transactions in confirmed blocks, and the slot
+    /// cross-check counts behind it (shred sources with an `rpc` baseline).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmed_coverage_pct: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slots_missed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slots_skipped: Option<u64>,
     pub fec_recovered_shreds: u64,
     pub txs_decoded: u64,
     pub txs_per_sec: f64,
     pub win_rate_pct: Option<f64>,
+    /// % of matched transactions that beat RPC by at least the latency
+    /// budget (shred sources, with `latency_budget_ms` set).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_budget_pct: Option<f64>,
     pub lead_time_mean_us: Option<f64>,
     pub lead_time_p50_us: Option<i64>,
     pub lead_time_p95_us: Option<i64>,
     pub lead_time_p99_us: Option<i64>,
     pub lead_time_samples: u64,
+    /// Fee/priority distribution of transactions that beat RPC by more than the
+    /// early threshold (shred sources only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub early_txs: Option<EarlyTxSnapshot>,
     /// Per-slot decode outcomes (shred sources only; up to 500 most recent slots).
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub slot_breakdown: Vec<SlotStats>,
 }
 
-pub fn run(config: &ProbeConfig, duration_secs: u64, output: Option<PathBuf>) -> Result<()> {
+/// Receive socket settings of one run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunSettings {
+    pub busy_poll_us: u32,
+    pub rcvbuf_mb: usize,
+    pub batch: usize,
+    /// Whether the sources' `pin_recv_core` and `pin_decode_core` apply.
+    pub pin: bool,
+}
+
+impl Default for RunSettings {
+    fn default() -> Self {
+        let t = SocketTuning::default();
+        Self {
+            busy_poll_us: t.busy_poll_us,
+            rcvbuf_mb: t.recv_buf_bytes >> 20,
+            batch: t.batch,
+            pin: true,
+        }
+    }
+}
+
+impl RunSettings {
+    fn socket(&self) -> SocketTuning {
+        SocketTuning {
+            busy_poll_us: self.busy_poll_us,
+            recv_buf_bytes: self.rcvbuf_mb.saturating_mul(1 << 20),
+            batch: self.batch,
+        }
+    }
+
+    /// The `bench` flags that select these settings.
+    fn args(&self) -> Vec<String> {
+        vec![
+            format!("--busy-poll-us={}", self.busy_poll_us),
+            format!("--rcvbuf-mb={}", self.rcvbuf_mb),
+            format!("--batch={}", self.batch),
+            format!("--pin={}", if self.pin { "on" } else { "off" }),
+        ]
+    }
+
+    fn label(&self) -> String {
+        format!(
+            "busy_poll={}µs rcvbuf={}MB batch={} pin={}",
+            self.busy_poll_us,
+            self.rcvbuf_mb,
+            self.batch,
+            if self.pin { "on" } else { "off" }
+        )
+    }
+}
+
+/// The values given on the command line for each receive setting.
+pub struct Sweep {
+    busy_poll_us: Vec<u32>,
+    rcvbuf_mb: Vec<usize>,
+    batch: Vec<usize>,
+    pin: Vec<bool>,
+}
+
+impl Sweep {
+    pub fn new(
+        busy_poll_us: Vec<u32>,
+        rcvbuf_mb: Vec<usize>,
+        batch: Vec<usize>,
+        pin: &[String],
+    ) -> Self {
+        Self { busy_poll_us, rcvbuf_mb, batch, pin: pin.iter().map(|p| p == "on").collect() }
+    }
+
+    /// Every combination of the given values, in the order given, with the
+    /// default standing in for a setting left out.
+    fn cells(&self) -> Vec<RunSettings> {
+        let d = RunSettings::default();
+        let mut cells = Vec::new();
+        for busy_poll_us in axis(&self.busy_poll_us, d.busy_poll_us) {
+            for rcvbuf_mb in axis(&self.rcvbuf_mb, d.rcvbuf_mb) {
+                for batch in axis(&self.batch, d.batch) {
+                    for pin in axis(&self.pin, d.pin) {
+                        cells.push(RunSettings { busy_poll_us, rcvbuf_mb, batch, pin });
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    /// The settings of a run without `--matrix`.
+    pub fn single(&self) -> Result<RunSettings> {
+        let cells = self.cells();
+        anyhow::ensure!(
+            cells.len() == 1,
+            "several values for --busy-poll-us, --rcvbuf-mb, --batch or --pin need --matrix"
+        );
+        cells[0].socket().validate()?;
+        Ok(cells[0])
+    }
+}
+
+/// `values` without repeats, or just `default` when empty.
+fn axis<T: Copy + PartialEq>(values: &[T], default: T) -> Vec<T> {
+    let mut out = Vec::new();
+    for &v in values {
+        if !out.contains(&v) {
+            out.push(v);
+        }
+    }
+    if out.is_empty() {
+        out.push(default);
+    }
+    out
+}
+
+/// Result of `bench --matrix`: one cell per combination of settings.
+#[derive(Debug, Serialize)]
+pub struct MatrixReport {
+    /// Length of each window.
+    pub window_secs: u64,
+    pub cells: Vec<MatrixCell>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MatrixCell {
+    #[serde(flatten)]
+    pub settings: RunSettings,
+    pub sources: Vec<CellSource>,
+}
+
+/// The receive-side fields of a [`SourceReport`], read back from the report
+/// of a window.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CellSource {
+    pub name: String,
+    pub shreds_per_sec: f64,
+    pub shreds_dropped: u64,
+    pub socket_rcvbuf_bytes: Option<u64>,
+    pub socket_drops: Option<u64>,
+    pub recv_utilization_pct: Option<f64>,
+    pub recv_batch_fill: Option<f64>,
+    pub coverage_pct: Option<f64>,
+    pub lead_time_p50_us: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct WindowReport {
+    sources: Vec<CellSource>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OfflineBenchReport {
+    pub pcap: String,
+    /// UDP payloads replayed (non-shred packets included).
+    pub packets: u64,
+    pub entries: u64,
+    pub txs: u64,
+    pub slots_complete: u64,
+    pub fec_recovered_shreds: u64,
+    /// Wall time of the decode pass.
+    pub decode_secs: f64,
+    pub shreds_per_sec: f64,
+    pub entries_per_sec: f64,
+    pub txs_per_sec: f64,
+    pub cpu_ms: StageCpuMs,
+}
+
+/// CPU time per stage in milliseconds. `pcap_load` precedes the decode pass;
+/// the others add up to `decoder_total`.
+#[derive(Debug, Serialize)]
+pub struct StageCpuMs {
+    pub pcap_load: f64,
+    /// Header parsing, slot and FEC-set bookkeeping, payload copies.
+    pub shred: f64,
+    pub fec_recovery: f64,
+    pub entry_decode: f64,
+    pub tx_emit: f64,
+    pub decoder_total: f64,
+}
+
+pub fn run_offline(pcap: &Path, output: Option<PathBuf>) -> Result<()> {
+    eprintln!("shredtop bench — decoding {} offline...", pcap.display());
+    let b = shred_ingest::offline::bench_pcap(pcap)?;
+
+    let secs = (b.decode_wall_ns as f64 / 1e9).max(1e-9);
+    let ms = |ns: u64| ns as f64 / 1e6;
+    let report = OfflineBenchReport {
+        pcap: pcap.display().to_string(),
+        packets: b.packets,
+        entries: b.entries,
+        txs: b.txs,
+        slots_complete: b.slots_complete,
+        fec_recovered_shreds: b.fec_recovered_shreds,
+        decode_secs: secs,
+        shreds_per_sec: b.packets as f64 / secs,
+        entries_per_sec: b.entries as f64 / secs,
+        txs_per_sec: b.txs as f64 / secs,
+        cpu_ms: StageCpuMs {
+            pcap_load: ms(b.load_cpu_ns),
+            shred: ms(b.shred_cpu_ns()),
+            fec_recovery: ms(b.fec_cpu_ns),
+            entry_decode: ms(b.entries_cpu_ns),
+            tx_emit: ms(b.emit_cpu_ns),
+            decoder_total: ms(b.decode_cpu_ns),
+        },
+    };
+
+    write_report(&report, output)?;
+
+    let c = &report.cpu_ms;
+    let pct = |v: f64| if c.decoder_total > 0.0 { v / c.decoder_total * 100.0 } else { 0.0 };
+    eprintln!();
+    eprintln!("=== OFFLINE BENCH ({:.3}s decode) ===", secs);
+    eprintln!(
+        "  shreds/s={:.0}  entries/s={:.0}  txs/s={:.0}  slots={}  fec-rec={}",
+        report.shreds_per_sec,
+        report.entries_per_sec,
+        report.txs_per_sec,
+        report.slots_complete,
+        report.fec_recovered_shreds,
+    );
+    eprintln!(
+        "  cpu ms: shred={:.1} ({:.0}%)  fec={:.1} ({:.0}%)  entries={:.1} ({:.0}%)  emit={:.1} ({:.0}%)  total={:.1}  [pcap load {:.1}]",
+        c.shred, pct(c.shred),
+        c.fec_recovery, pct(c.fec_recovery),
+        c.entry_decode, pct(c.entry_decode),
+        c.tx_emit, pct(c.tx_emit),
+        c.decoder_total,
+        c.pcap_load,
+    );
+    Ok(())
+}
+
+pub fn run(
+    config: &ProbeConfig,
+    duration_secs: u64,
+    output: Option<PathBuf>,
+    early_ms: u64,
+    interval_ms: Option<u64>,
+    settings: RunSettings,
+) -> Result<()> {
     if config.sources.is_empty() {
         anyhow::bail!(
             "no sources configured — run `shredtop init > probe.toml` to create a config"
         );
     }
+    if let Some(ms) = interval_ms {
+        anyhow::ensure!(
+            ms >= sample::MIN_INTERVAL_MS,
+            "--interval-ms must be at least {}",
+            sample::MIN_INTERVAL_MS
+        );
+    }
 
     eprintln!(
         "shredtop bench — running for {}s with {} source(s)...",
         duration_secs,
         config.sources.len()
     );
+    let custom = settings != RunSettings::default();
+    if custom {
+        eprintln!("  receive settings: {}", settings.label());
+    }
 
     let mut fan_in = FanInSource::new();
     fan_in.filter_programs = config.filter_programs.clone();
+    fan_in.sample_tx_prefix_bits = config.sample_tx_prefix_bits;
+    fan_in.dedup_signature_only = config.dedup_signature_only;
+    fan_in.slot_expiry = config.slot_expiry()?;
+    fan_in.early_tx_threshold_us = Some(early_ms as i64 * 1000);
+    if settings.socket() != SocketTuning::default() {
+        fan_in.tuning = Some(settings.socket());
+    }
 
     for entry in &config.sources {
-        let (source, metrics) = build_source(entry, None)?;
+        let mut entry = entry.clone();
+        if !settings.pin {
+            entry.pin_recv_core = None;
+            entry.pin_decode_core = None;
+        }
+        let (source, metrics) = build_source(&entry, None)?;
         fan_in.add_source(source, metrics);
     }
 
-    let (out_tx, out_rx) = crossbeam_channel::bounded::<DecodedTx>(4096);
+    let (out_tx, out_rx) = crossbeam_channel::bounded::<ForwardedTx>(4096);
     let (all_metrics, _race_tracker, _handles) = fan_in.start(out_tx);
 
+    let groups = build_failover_groups(&config.sources, &all_metrics)?;
+    if !groups.is_empty() {
+        shred_ingest::spawn_failover_monitor(groups, |ev| {
+            eprintln!(
+                "  failover group '{}': {} silent for {}s, promoted {}",
+                ev.group, ev.from, ev.silent_secs, ev.to
+            );
+        });
+    }
+
     // Drain thread
     std::thread::spawn(move || {
         for _ in out_rx {}
@@ -77,11 +411,17 @@ pub fn run(config: &ProbeConfig, duration_secs: u64, output: Option<PathBuf>) ->
 
     let start = Instant::now();
     let target = Duration::from_secs(duration_secs);
+    let step = interval_ms.map_or(Duration::from_secs(1), Duration::from_millis);
+    let mut sampler = interval_ms.map(|_| Sampler::new(&all_metrics));
+    let mut samples = Vec::new();
 
     // Progress indicator every 10s
     let mut next_tick = 10u64;
     while start.elapsed() < target {
-        std::thread::sleep(Duration::from_secs(1));
+        std::thread::sleep(step.min(target.saturating_sub(start.elapsed())));
+        if let Some(ref mut sampler) = sampler {
+            samples.push(sampler.sample());
+        }
         let elapsed = start.elapsed().as_secs();
         if elapsed >= next_tick {
             eprintln!("  ...{}s / {}s", elapsed, duration_secs);
@@ -94,23 +434,17 @@ pub fn run(config: &ProbeConfig, duration_secs: u64, output: Option<PathBuf>) ->
 
     let report = BenchReport {
         duration_secs,
+        early_threshold_ms: early_ms,
+        latency_budget_ms: config.latency_budget_ms,
+        settings: custom.then_some(settings),
         sources: snapshots
             .iter()
-            .map(|s| source_report(s, elapsed_secs))
+            .map(|s| source_report(s, elapsed_secs, config.latency_budget_ms))
             .collect(),
+        samples,
     };
 
-    let json = serde_json::to_string_pretty(&report)?;
-
-    match output {
-        Some(path) => {
-            std::fs::write(&path, &json)?;
-            eprintln!("Report written to {}", path.display());
-        }
-        None => {
-            println!("{}", json);
-        }
-    }
+    write_report(&report, output)?;
 
     // Also print a human-readable summary to stderr
     eprintln!();
@@ -120,17 +454,211 @@ pub fn run(config: &ProbeConfig, duration_secs: u64, output: Option<PathBuf>) ->
             "  {}  shreds/s={:.0}  coverage={}  win={}  lead={} µs  fec-rec={}",
             s.name,
             s.shreds_per_sec,
-            s.coverage_pct.map(|p| format!("{:.0}%", p)).unwrap_or("—".into()),
+            match (s.confirmed_coverage_pct, s.coverage_pct) {
+                (Some(p), _) => format!("{:.0}% of confirmed txs", p),
+                (None, Some(p)) => format!("{:.0}%", p),
+                (None, None) => "—".into(),
+            },
             s.win_rate_pct.map(|p| format!("{:.0}%", p)).unwrap_or("—".into()),
             s.lead_time_mean_us.map(|u| format!("{:+.0}", u)).unwrap_or("—".into()),
             s.fec_recovered_shreds,
         );
+        if let (Some(ms), Some(pct)) = (report.latency_budget_ms, s.in_budget_pct) {
+            eprintln!("    ≥{}ms before RPC: {:.1}% of matched txs", ms, pct);
+        }
+        if let Some(early) = s.early_txs.as_ref().filter(|e| e.count > 0) {
+            let buckets: Vec<String> = early
+                .cu_price_buckets
+                .iter()
+                .map(|(label, n)| format!("{}:{}", label, n))
+                .collect();
+            eprintln!(
+                "    early >{}ms: {} txs  prio-fee={:.0}%  µlamports/CU [{}]  payers={}",
+                early_ms,
+                early.count,
+                early.with_priority_fee as f64 / early.count as f64 * 100.0,
+                buckets.join(" "),
+                early.distinct_fee_payers,
+            );
+        }
     }
 
     Ok(())
 }
 
-fn source_report(s: &SourceMetricsSnapshot, elapsed_secs: f64) -> SourceReport {
+/// Run one window per combination of `sweep`, then report them side by side.
+/// Each window is a child `shredtop bench` with the same config and profile.
+pub fn run_matrix(
+    config: &ProbeConfig,
+    config_path: &Path,
+    profile: Option<&str>,
+    sweep: &Sweep,
+    window_secs: u64,
+    output: Option<PathBuf>,
+    early_ms: u64,
+) -> Result<()> {
+    if config.sources.is_empty() {
+        anyhow::bail!(
+            "no sources configured — run `shredtop init > probe.toml` to create a config"
+        );
+    }
+    let cells = sweep.cells();
+    anyhow::ensure!(
+        cells.len() > 1,
+        "--matrix needs several values for --busy-poll-us, --rcvbuf-mb, --batch or --pin"
+    );
+    for cell in &cells {
+        cell.socket().validate()?;
+    }
+    if cells.iter().any(|c| !c.pin) {
+        let pinned = |s: &SourceEntry| s.pin_recv_core.is_some() || s.pin_decode_core.is_some();
+        anyhow::ensure!(
+            config.sources.iter().any(pinned),
+            "--pin off makes no difference: no source sets pin_recv_core or pin_decode_core"
+        );
+    }
+
+    let exe = std::env::current_exe().context("failed to locate the shredtop binary")?;
+    eprintln!(
+        "shredtop bench --matrix — {} windows of {}s (about {} min) with {} source(s)...",
+        cells.len(),
+        window_secs,
+        (cells.len() as u64 * window_secs).div_ceil(60),
+        config.sources.len()
+    );
+    // Each window writes its report here; a fresh directory only we can
+    // enter, so no other user can plant or read the file.
+    let scratch = private_temp_dir("shredtop-matrix")?;
+    let mut report = MatrixReport { window_secs, cells: Vec::with_capacity(cells.len()) };
+    for (i, settings) in cells.iter().enumerate() {
+        eprintln!("  [{}/{}] {}", i + 1, cells.len(), settings.label());
+        let out = scratch.join(format!("window-{}.json", i + 1));
+        let sources = run_window(&exe, config_path, profile, settings, window_secs, early_ms, &out)
+            .with_context(|| format!("window {} ({})", i + 1, settings.label()));
+        let sources = match sources {
+            Ok(sources) => sources,
+            Err(e) => {
+                std::fs::remove_dir_all(&scratch).ok();
+                return Err(e);
+            }
+        };
+        report.cells.push(MatrixCell { settings: *settings, sources });
+    }
+    std::fs::remove_dir_all(&scratch).ok();
+
+    write_report(&report, output)?;
+    print_matrix(&report);
+    Ok(())
+}
+
+fn run_window(
+    exe: &Path,
+    config_path: &Path,
+    profile: Option<&str>,
+    settings: &RunSettings,
+    window_secs: u64,
+    early_ms: u64,
+    out: &Path,
+) -> Result<Vec<CellSource>> {
+    let mut cmd = Command::new(exe);
+    cmd.arg("--config").arg(config_path);
+    if let Some(name) = profile {
+        cmd.args(["--profile", name]);
+    }
+    cmd.arg("bench")
+        .arg(format!("--duration={}", window_secs))
+        .arg(format!("--early-ms={}", early_ms))
+        .arg("--output")
+        .arg(out)
+        .args(settings.args());
+    let result = cmd.output().context("failed to start shredtop bench")?;
+    if !result.status.success() {
+        anyhow::bail!(
+            "shredtop bench exited with {}: {}",
+            result.status,
+            String::from_utf8_lossy(&result.stderr).trim()
+        );
+    }
+    let text = std::fs::read_to_string(out)
+        .with_context(|| format!("failed to read {}", out.display()))?;
+    std::fs::remove_file(out).ok();
+    let window: WindowReport = serde_json::from_str(&text)?;
+    Ok(window.sources)
+}
+
+/// One row per source and window, grouped by source. `*` marks each source's
+/// window with the fewest drops (decoder and kernel), ties going to the
+/// lowest receive thread utilization.
+fn print_matrix(report: &MatrixReport) {
+    let mut names: Vec<&str> = Vec::new();
+    for cell in &report.cells {
+        for s in &cell.sources {
+            if !names.contains(&s.name.as_str()) {
+                names.push(&s.name);
+            }
+        }
+    }
+    let drops = |s: &CellSource| s.shreds_dropped + s.socket_drops.unwrap_or(0);
+    let util = |s: &CellSource| s.recv_utilization_pct.unwrap_or(0.0);
+    let dash = || "—".to_string();
+
+    eprintln!();
+    eprintln!("=== BENCH MATRIX ({} × {}s) ===", report.cells.len(), report.window_secs);
+    eprintln!(
+        "  {:<16} {:>7} {:>9} {:>6} {:>4} {:>10} {:>8} {:>10} {:>6} {:>6} {:>10}",
+        "SOURCE", "BUSY_µs", "RCVBUF_MB", "BATCH", "PIN", "SHREDS/s", "DROPS", "SOCK_DROPS",
+        "UTIL%", "FILL", "LEAD_P50"
+    );
+    for name in names {
+        let rows: Vec<(&RunSettings, &CellSource)> = report
+            .cells
+            .iter()
+            .filter_map(|c| c.sources.iter().find(|s| s.name == name).map(|s| (&c.settings, s)))
+            .collect();
+        let best = rows
+            .iter()
+            .min_by(|a, b| drops(a.1).cmp(&drops(b.1)).then(util(a.1).total_cmp(&util(b.1))))
+            .map(|(settings, _)| *settings);
+        for (settings, s) in &rows {
+            eprintln!(
+                "  {:<16} {:>7} {:>9} {:>6} {:>4} {:>10.0} {:>8} {:>10} {:>6} {:>6} {:>10}{}",
+                name,
+                settings.busy_poll_us,
+                settings.rcvbuf_mb,
+                settings.batch,
+                if settings.pin { "on" } else { "off" },
+                s.shreds_per_sec,
+                s.shreds_dropped,
+                s.socket_drops.map(|n| n.to_string()).unwrap_or_else(dash),
+                s.recv_utilization_pct.map(|p| format!("{:.1}", p)).unwrap_or_else(dash),
+                s.recv_batch_fill.map(|f| format!("{:.1}", f)).unwrap_or_else(dash),
+                s.lead_time_p50_us.map(|u| format!("{:+} µs", u)).unwrap_or_else(dash),
+                if rows.len() > 1 && best == Some(*settings) { "  *" } else { "" },
+            );
+        }
+    }
+    eprintln!("  * fewest drops for the source, then lowest receive thread utilization");
+}
+
+fn write_report(report: &impl Serialize, output: Option<PathBuf>) -> Result<()> {
+    let json = serde_json::to_string_pretty(report)?;
+    match output {
+        Some(path) => {
+            std::fs::write(&path, &json)?;
+            eprintln!("Report written to {}", path.display());
+        }
+        None => {
+            println!("{}", json);
+        }
+    }
+    Ok(())
+}
+
+fn source_report(
+    s: &SourceMetricsSnapshot,
+    elapsed_secs: f64,
+    latency_budget_ms: Option<u64>,
+) -> SourceReport {
     let coverage_pct = if s.coverage_shreds_expected > 0 {
         Some(s.coverage_shreds_seen as f64 / s.coverage_shreds_expected as f64 * 100.0)
     } else {
@@ -154,24 +682,38 @@ fn source_report(s: &SourceMetricsSnapshot, elapsed_secs: f64) -> SourceReport {
 
     SourceReport {
         name: s.name.to_string(),
+        labels: (*s.labels).clone(),
         shreds_received: s.shreds_received,
         shreds_per_sec: s.shreds_received as f64 / elapsed_secs,
         bytes_received_mb: s.bytes_received as f64 / 1_048_576.0,
         shreds_dropped: s.shreds_dropped,
+        socket_rcvbuf_bytes: (s.socket_rcvbuf_bytes > 0).then_some(s.socket_rcvbuf_bytes),
+        socket_rmem_hwm_bytes: (s.socket_rcvbuf_bytes > 0).then_some(s.socket_rmem_hwm_bytes),
+        socket_drops: (s.socket_rcvbuf_bytes > 0).then_some(s.socket_drops),
+        recv_utilization_pct: s.recv_utilization_pct(),
+        recv_batch_fill: s.recv_batch_fill(),
         slots_attempted: s.slots_attempted,
         slots_complete: s.slots_complete,
         slots_partial: s.slots_partial,
         slots_dropped: s.slots_dropped,
         coverage_pct,
+        confirmed_coverage_pct: s.confirmed_coverage_pct(),
+        slots_missed: s.cross_checked().then_some(s.confirmed_slots_missed),
+        slots_skipped: s.cross_checked().then_some(s.skipped_slots_seen),
         fec_recovered_shreds: s.fec_recovered_shreds,
         txs_decoded: s.txs_decoded,
         txs_per_sec: s.txs_decoded as f64 / elapsed_secs,
         win_rate_pct,
+        in_budget_pct: latency_budget_ms
+            .filter(|_| !s.is_rpc)
+            .and_then(|ms| s.lead_time_hist.fraction_at_least(ms as i64 * 1000))
+            .map(|f| f * 100.0),
         lead_time_mean_us: lead_mean,
         lead_time_p50_us: s.lead_time_p50_us,
         lead_time_p95_us: s.lead_time_p95_us,
         lead_time_p99_us: s.lead_time_p99_us,
         lead_time_samples: s.lead_time_count,
+        early_txs: if s.is_rpc { None } else { Some(s.early_txs.clone()) },
         slot_breakdown: s.slot_log.clone(),
     }
 }
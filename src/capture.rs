@@ -4,22 +4,55 @@
 //! channel and writes them to disk in the configured format (pcap, csv, jsonl).
 //! Rotation and ring-buffer management happen inside the capture thread so the
 //! hot path is never blocked.
+//!
+//! File I/O is one step further removed. Writers fill in-memory chunks and
+//! hand them to a dedicated flusher thread ([`Flusher`]), which writes them
+//! out and, every `fsync_ms`, syncs the files and drops the written pages from
+//! the page cache. Dirty pages never pile up into a writeback storm that
+//! stalls the decoders on the same host. When the flusher falls behind, the
+//! capture thread waits; that wait is counted as write-stall time (see
+//! [`io_snapshot`]).
+//!
+//! With `slot_names`, archived files are named by the lowest and highest slot
+//! they hold (`shreds.291438000-291438420.pcap`) and each gets an index
+//! sidecar (`shreds.291438000-291438420.pcap.idx`) with the byte offset of
+//! every slot's first record, so readers can skip straight to a slot.
+//!
+//! With `[capture.trigger]`, nothing is written continuously. The capture
+//! thread holds each source's packets of the last `pre_secs` in memory, and a
+//! watcher thread checks the sources once a second for a coverage drop, a
+//! silent feed or a collapsed lead time. When one fires, the held packets are
+//! written out, followed by everything received in the next `post_secs`, and
+//! the trigger is appended to `triggers.jsonl` in the capture directory.
+//!
+//! With `per_feed`, each feed gets its own directory under `output_dir`
+//! (named by [`feed_dir_name`]) holding its own rings, rotated and sized
+//! independently of the other feeds'.
 
-use crate::config::CaptureConfig;
-use crossbeam_channel::Receiver;
+use crate::config::{CaptureConfig, CaptureTriggerConfig};
+use anyhow::{Context, Result};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TrySendError};
 use pcap_file::pcap::{PcapHeader, PcapPacket, PcapWriter};
 use pcap_file::{DataLink, Endianness, TsResolution};
-use shred_ingest::CaptureEvent;
-use std::collections::VecDeque;
+use shred_ingest::clock::MonotonicNs;
+use shred_ingest::receiver::RawShred;
+use shred_ingest::shred_mix::variant_name;
+use shred_ingest::{CaptureEvent, ShredReceiver, SourceMetrics};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::{self, File};
-use std::io::{self, BufWriter, Write};
+use std::io::{self, Write};
+use std::net::SocketAddrV4;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{info, warn};
 
 // ─── Writer trait ────────────────────────────────────────────────────────────
 
 pub trait CaptureWriter: Send {
+    /// Write one packet received at `ts_ns`, Unix-epoch time.
     fn write_shred(
         &mut self,
         ts_ns: u64,
@@ -32,34 +65,246 @@ pub trait CaptureWriter: Send {
     fn flush(&mut self) -> io::Result<()>;
 }
 
+// ─── Flusher ─────────────────────────────────────────────────────────────────
+
+/// Bytes a file buffers before handing them to the flusher.
+const CHUNK_BYTES: usize = 256 * 1024;
+/// Chunks queued for the flusher before writers have to wait (16 MB).
+const FLUSH_QUEUE: usize = 64;
+
+static WRITE_STALL_NS: AtomicU64 = AtomicU64::new(0);
+static WRITE_STALLS: AtomicU64 = AtomicU64::new(0);
+static BYTES_WRITTEN: AtomicU64 = AtomicU64::new(0);
+static FSYNCS: AtomicU64 = AtomicU64::new(0);
+static TRIGGERS: AtomicU64 = AtomicU64::new(0);
+
+/// Capture I/O counters since the process started.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CaptureIoSnapshot {
+    /// Time the capture thread spent waiting for the flusher to take a chunk.
+    pub write_stall_ms: f64,
+    pub write_stalls: u64,
+    pub bytes_written: u64,
+    pub fsyncs: u64,
+    /// Anomalies that fired a triggered capture (`[capture.trigger]` only).
+    pub triggers: u64,
+}
+
+pub fn io_snapshot() -> CaptureIoSnapshot {
+    CaptureIoSnapshot {
+        write_stall_ms: WRITE_STALL_NS.load(Ordering::Relaxed) as f64 / 1e6,
+        write_stalls: WRITE_STALLS.load(Ordering::Relaxed),
+        bytes_written: BYTES_WRITTEN.load(Ordering::Relaxed),
+        fsyncs: FSYNCS.load(Ordering::Relaxed),
+        triggers: TRIGGERS.load(Ordering::Relaxed),
+    }
+}
+
+enum FlushJob {
+    Write(Arc<File>, Vec<u8>),
+    /// The file's writer is gone; sync it one last time.
+    Close(Arc<File>),
+    /// Acknowledged once every job queued before it is done.
+    Barrier(Sender<()>),
+}
+
+/// Handle to the thread that does the capture's file I/O.
+#[derive(Clone)]
+pub struct Flusher {
+    tx: Sender<FlushJob>,
+}
+
+impl Flusher {
+    /// Start the flusher thread; with `fsync_ms` > 0 it syncs the files it
+    /// has written at most that often.
+    pub fn spawn(fsync_ms: u64) -> io::Result<Self> {
+        let (tx, rx) = crossbeam_channel::bounded(FLUSH_QUEUE);
+        std::thread::Builder::new()
+            .name("capture-flush".into())
+            .spawn(move || flush_loop(rx, fsync_ms))?;
+        Ok(Self { tx })
+    }
+
+    pub fn create(&self, path: &Path) -> io::Result<FlushedFile> {
+        Ok(FlushedFile {
+            file: Arc::new(File::create(path)?),
+            buf: Vec::with_capacity(CHUNK_BYTES),
+            flusher: self.clone(),
+        })
+    }
+
+    /// Queue `job`, timing any wait for room as a write stall.
+    fn send(&self, job: FlushJob) {
+        let job = match self.tx.try_send(job) {
+            Ok(()) => return,
+            Err(TrySendError::Disconnected(_)) => return,
+            Err(TrySendError::Full(job)) => job,
+        };
+        let start = Instant::now();
+        let _ = self.tx.send(job);
+        WRITE_STALL_NS.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        WRITE_STALLS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn flush_loop(rx: Receiver<FlushJob>, fsync_ms: u64) {
+    let interval = Duration::from_millis(fsync_ms.max(1));
+    let mut dirty: Vec<Arc<File>> = Vec::new();
+    let mut last_sync = Instant::now();
+    loop {
+        match rx.recv_timeout(interval) {
+            Ok(FlushJob::Write(file, data)) => {
+                if let Err(e) = (&*file).write_all(&data) {
+                    warn!("capture write error: {}", e);
+                }
+                BYTES_WRITTEN.fetch_add(data.len() as u64, Ordering::Relaxed);
+                if fsync_ms > 0 && !dirty.iter().any(|f| Arc::ptr_eq(f, &file)) {
+                    dirty.push(file);
+                }
+            }
+            Ok(FlushJob::Close(file)) => {
+                if let Some(i) = dirty.iter().position(|f| Arc::ptr_eq(f, &file)) {
+                    sync(&dirty.swap_remove(i));
+                }
+            }
+            Ok(FlushJob::Barrier(ack)) => {
+                let _ = ack.send(());
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                dirty.iter().for_each(|f| sync(f));
+                return;
+            }
+        }
+        if !dirty.is_empty() && last_sync.elapsed() >= interval {
+            dirty.drain(..).for_each(|f| sync(&f));
+            last_sync = Instant::now();
+        }
+    }
+}
+
+/// Write the file's dirty pages out and drop them from the page cache, so
+/// capture data does not crowd out the decoders' working set.
+fn sync(file: &File) {
+    if let Err(e) = file.sync_data() {
+        warn!("capture fsync failed: {}", e);
+        return;
+    }
+    FSYNCS.fetch_add(1, Ordering::Relaxed);
+    #[cfg(target_os = "linux")]
+    unsafe {
+        use std::os::unix::io::AsRawFd;
+        libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED);
+    }
+}
+
+/// A capture file whose writes go through the [`Flusher`]. Dropping it
+/// queues what is left and closes the file.
+pub struct FlushedFile {
+    file: Arc<File>,
+    buf: Vec<u8>,
+    flusher: Flusher,
+}
+
+impl FlushedFile {
+    fn hand_off(&mut self) {
+        if !self.buf.is_empty() {
+            let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(CHUNK_BYTES));
+            self.flusher.send(FlushJob::Write(self.file.clone(), chunk));
+        }
+    }
+}
+
+impl Write for FlushedFile {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= CHUNK_BYTES {
+            self.hand_off();
+        }
+        Ok(data.len())
+    }
+
+    /// Queue the buffered bytes and wait until the flusher has written them.
+    fn flush(&mut self) -> io::Result<()> {
+        self.hand_off();
+        let (ack_tx, ack_rx) = crossbeam_channel::bounded(1);
+        self.flusher.send(FlushJob::Barrier(ack_tx));
+        ack_rx
+            .recv()
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "capture flusher stopped"))
+    }
+}
+
+impl Drop for FlushedFile {
+    fn drop(&mut self) {
+        self.hand_off();
+        self.flusher.send(FlushJob::Close(self.file.clone()));
+    }
+}
+
 // ─── Rotation state ──────────────────────────────────────────────────────────
 
+/// Bytes of the pcap file header, and of the header before each packet.
+const PCAP_FILE_HEADER_LEN: usize = 24;
+const PCAP_RECORD_HEADER_LEN: usize = 16;
+
 /// Tracks the ring-buffer of on-disk capture files.
 ///
 /// The active (currently-writing) file is always `shreds.{ext}`.  When it
-/// fills, it is renamed to `shreds.{ext}.{N}` and a fresh active file is
-/// opened.  The oldest archived file is deleted once the ring exceeds
-/// `ring_files` entries.
+/// fills, it is renamed to `shreds.{ext}.{N}` (or `shreds.{first}-{last}.{ext}`
+/// with `slot_names`) and a fresh active file is opened.  The oldest archived
+/// file is deleted once the ring exceeds `ring_files` entries.
 struct RotationState {
     dir: PathBuf,
     ext: &'static str,
     max_bytes: u64,
     ring_files: usize,
+    /// Bytes written to the active file, which is also the offset of the
+    /// next record.
     current_bytes: u64,
     next_gen: u32,
     ring: VecDeque<PathBuf>,
+    /// Slots of the active file, when archives are named by slot.
+    index: Option<SlotIndex>,
+}
+
+/// Slots seen in the active file and where each first appears.
+#[derive(Default)]
+struct SlotIndex {
+    seen: HashSet<u64>,
+    /// (slot, byte offset of its first record), in file order.
+    offsets: Vec<(u64, u64)>,
+}
+
+impl SlotIndex {
+    fn range(&self) -> Option<(u64, u64)> {
+        let lo = self.offsets.iter().map(|&(s, _)| s).min()?;
+        let hi = self.offsets.iter().map(|&(s, _)| s).max()?;
+        Some((lo, hi))
+    }
 }
 
 impl RotationState {
-    fn new(output_dir: &str, ext: &'static str, rotate_mb: u64, ring_files: usize) -> Self {
+    fn new(
+        output_dir: &str,
+        ext: &'static str,
+        rotate_mb: u64,
+        ring_files: usize,
+        slot_names: bool,
+    ) -> Self {
+        let dir = PathBuf::from(output_dir);
+        // Slot-named archives are never overwritten by a later rotation, so
+        // those left by an earlier run join the ring and are evicted first.
+        let ring = if slot_names { slot_named_archives(&dir, ext) } else { VecDeque::new() };
         Self {
-            dir: PathBuf::from(output_dir),
+            dir,
             ext,
             max_bytes: rotate_mb * 1024 * 1024,
             ring_files,
             current_bytes: 0,
             next_gen: 1,
-            ring: VecDeque::new(),
+            ring,
+            index: slot_names.then(SlotIndex::default),
         }
     }
 
@@ -74,10 +319,24 @@ impl RotationState {
     /// Rename the active file to the next archive slot; evict oldest if needed.
     fn rotate(&mut self) -> io::Result<()> {
         let active = self.active_path();
-        let archive = self.dir.join(format!("shreds.{}.{}", self.ext, self.next_gen));
+        let numbered = self.dir.join(format!("shreds.{}.{}", self.ext, self.next_gen));
+        let index = self.index.as_mut().map(std::mem::take);
+        let archive = match index.as_ref().and_then(SlotIndex::range) {
+            Some((lo, hi)) => {
+                let named = self.dir.join(format!("shreds.{}-{}.{}", lo, hi, self.ext));
+                // Two files can hold the same range when one slot spans both.
+                if named.exists() { numbered } else { named }
+            }
+            None => numbered,
+        };
         if active.exists() {
             fs::rename(&active, &archive)?;
             info!("capture: archived {} → {}", active.display(), archive.display());
+            if let Some(index) = index {
+                if let Err(e) = write_slot_index(&archive, &index.offsets) {
+                    warn!("capture: writing index of {} failed: {}", archive.display(), e);
+                }
+            }
         }
         self.ring.push_back(archive);
         self.next_gen += 1;
@@ -89,29 +348,129 @@ impl RotationState {
                     Ok(()) => info!("capture: deleted old file {}", old.display()),
                     Err(e) => warn!("capture: delete {} failed: {}", old.display(), e),
                 }
+                let _ = fs::remove_file(index_path(&old));
             }
         }
         Ok(())
     }
 
+    /// Note that the next record belongs to `slot`.
+    fn record_slot(&mut self, slot: u64) {
+        if let Some(ref mut index) = self.index {
+            if index.seen.insert(slot) {
+                index.offsets.push((slot, self.current_bytes));
+            }
+        }
+    }
+
     fn account(&mut self, n: usize) {
         self.current_bytes += n as u64;
     }
 }
 
+/// Slot-named archives of format `ext` already in `dir`, lowest slots first.
+fn slot_named_archives(dir: &Path, ext: &str) -> VecDeque<PathBuf> {
+    let mut found: Vec<(u64, PathBuf)> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().into_string().ok()?;
+            if !name.ends_with(ext) {
+                return None;
+            }
+            archive_slots(&name).map(|(lo, _)| (lo, e.path()))
+        })
+        .collect();
+    found.sort();
+    found.into_iter().map(|(_, path)| path).collect()
+}
+
+/// The slot range in a slot-named archive's file name:
+/// `shreds.291438000-291438420.pcap` → (291438000, 291438420).
+pub fn archive_slots(file_name: &str) -> Option<(u64, u64)> {
+    let rest = file_name.strip_prefix("shreds.")?;
+    let (range, ext) = rest.split_once('.')?;
+    if !matches!(ext, "pcap" | "csv" | "jsonl") {
+        return None;
+    }
+    let (lo, hi) = range.split_once('-')?;
+    Some((lo.parse().ok()?, hi.parse().ok()?))
+}
+
+/// Format of a capture ring file ("pcap", "csv" or "jsonl"), whichever way
+/// it is named; None for index sidecars and other files.
+pub fn ring_file_format(file_name: &str) -> Option<&'static str> {
+    let rest = file_name.strip_prefix("shreds.")?;
+    if rest.ends_with(".idx") {
+        return None;
+    }
+    rest.split('.').find_map(|part| match part {
+        "pcap" => Some("pcap"),
+        "csv" => Some("csv"),
+        "jsonl" => Some("jsonl"),
+        _ => None,
+    })
+}
+
+/// Index sidecar of a capture file: `<file>.idx`.
+pub fn index_path(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(".idx");
+    PathBuf::from(name)
+}
+
+/// One `slot offset` line per slot, in file order.
+fn write_slot_index(file: &Path, offsets: &[(u64, u64)]) -> io::Result<()> {
+    let mut out = String::with_capacity(offsets.len() * 24);
+    for (slot, offset) in offsets {
+        out.push_str(&format!("{} {}\n", slot, offset));
+    }
+    fs::write(index_path(file), out)
+}
+
+/// The index sidecar of `file`: each slot in it and the byte offset of that
+/// slot's first record.
+pub fn read_slot_index(file: &Path) -> Result<Vec<(u64, u64)>> {
+    let path = index_path(file);
+    let text = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    text.lines()
+        .map(|line| {
+            let (slot, offset) = line.split_once(' ').context("expected 'slot offset'")?;
+            Ok((slot.parse()?, offset.parse()?))
+        })
+        .collect::<Result<_>>()
+        .with_context(|| format!("malformed index {}", path.display()))
+}
+
+/// Slot of a shred payload, if it is long enough to carry one.
+fn payload_slot(payload: &[u8]) -> Option<u64> {
+    Some(u64::from_le_bytes(payload.get(65..73)?.try_into().unwrap()))
+}
+
 // ─── pcap writer ─────────────────────────────────────────────────────────────
 
 pub struct PcapCaptureWriter {
-    writer: Option<PcapWriter<BufWriter<File>>>,
+    writer: Option<PcapWriter<FlushedFile>>,
     rotation: RotationState,
+    flusher: Flusher,
 }
 
 impl PcapCaptureWriter {
-    pub fn new(output_dir: &str, rotate_mb: u64, ring_files: usize) -> io::Result<Self> {
+    pub fn new(
+        output_dir: &str,
+        rotate_mb: u64,
+        ring_files: usize,
+        slot_names: bool,
+        flusher: Flusher,
+    ) -> io::Result<Self> {
         fs::create_dir_all(output_dir)?;
-        let rotation = RotationState::new(output_dir, "pcap", rotate_mb, ring_files);
-        let writer = open_pcap_writer(&rotation.active_path())?;
-        Ok(Self { writer: Some(writer), rotation })
+        let mut rotation =
+            RotationState::new(output_dir, "pcap", rotate_mb, ring_files, slot_names);
+        let writer = open_pcap_writer(&flusher, &rotation.active_path())?;
+        rotation.account(PCAP_FILE_HEADER_LEN);
+        Ok(Self { writer: Some(writer), rotation, flusher })
     }
 }
 
@@ -128,9 +487,8 @@ fn ns_pcap_header() -> PcapHeader {
     }
 }
 
-fn open_pcap_writer(path: &Path) -> io::Result<PcapWriter<BufWriter<File>>> {
-    let file = File::create(path)?;
-    PcapWriter::with_header(BufWriter::new(file), ns_pcap_header())
+fn open_pcap_writer(flusher: &Flusher, path: &Path) -> io::Result<PcapWriter<FlushedFile>> {
+    PcapWriter::with_header(flusher.create(path)?, ns_pcap_header())
         .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
 }
 
@@ -190,10 +548,15 @@ impl CaptureWriter for PcapCaptureWriter {
         let frame_len = frame.len();
 
         if self.rotation.should_rotate(frame_len) {
-            // Dropping the PcapWriter flushes its BufWriter before the rename.
+            // Dropping the PcapWriter queues its last chunk; the flusher
+            // still holds the file, so the rename does not lose it.
             self.writer = None;
             self.rotation.rotate()?;
-            self.writer = Some(open_pcap_writer(&self.rotation.active_path())?);
+            self.writer = Some(open_pcap_writer(&self.flusher, &self.rotation.active_path())?);
+            self.rotation.account(PCAP_FILE_HEADER_LEN);
+        }
+        if let Some(slot) = payload_slot(payload) {
+            self.rotation.record_slot(slot);
         }
 
         let timestamp = Duration::new(ts_ns / 1_000_000_000, (ts_ns % 1_000_000_000) as u32);
@@ -202,30 +565,43 @@ impl CaptureWriter for PcapCaptureWriter {
             w.write_packet(&pkt)
                 .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
         }
-        self.rotation.account(frame_len);
+        self.rotation.account(PCAP_RECORD_HEADER_LEN + frame_len);
         Ok(())
     }
 
     fn flush(&mut self) -> io::Result<()> {
-        // BufWriter flushes on rotation (via drop) and on process exit.
-        Ok(())
+        match self.writer {
+            Some(ref mut w) => w.get_mut().flush(),
+            None => Ok(()),
+        }
     }
 }
 
 // ─── CSV writer ──────────────────────────────────────────────────────────────
 
+const CSV_HEADER: &str = "recv_ns,feed,slot,shred_idx\n";
+
 pub struct CsvCaptureWriter {
-    writer: BufWriter<File>,
+    writer: FlushedFile,
     rotation: RotationState,
+    flusher: Flusher,
 }
 
 impl CsvCaptureWriter {
-    pub fn new(output_dir: &str, rotate_mb: u64, ring_files: usize) -> io::Result<Self> {
+    pub fn new(
+        output_dir: &str,
+        rotate_mb: u64,
+        ring_files: usize,
+        slot_names: bool,
+        flusher: Flusher,
+    ) -> io::Result<Self> {
         fs::create_dir_all(output_dir)?;
-        let rotation = RotationState::new(output_dir, "csv", rotate_mb, ring_files);
-        let mut writer = BufWriter::new(File::create(rotation.active_path())?);
-        writeln!(writer, "recv_ns,feed,slot,shred_idx")?;
-        Ok(Self { writer, rotation })
+        let mut rotation =
+            RotationState::new(output_dir, "csv", rotate_mb, ring_files, slot_names);
+        let mut writer = flusher.create(&rotation.active_path())?;
+        writer.write_all(CSV_HEADER.as_bytes())?;
+        rotation.account(CSV_HEADER.len());
+        Ok(Self { writer, rotation, flusher })
     }
 }
 
@@ -252,11 +628,12 @@ impl CaptureWriter for CsvCaptureWriter {
         let line_len = line.len();
 
         if self.rotation.should_rotate(line_len) {
-            self.writer.flush()?;
             self.rotation.rotate()?;
-            self.writer = BufWriter::new(File::create(self.rotation.active_path())?);
-            writeln!(self.writer, "recv_ns,feed,slot,shred_idx")?;
+            self.writer = self.flusher.create(&self.rotation.active_path())?;
+            self.writer.write_all(CSV_HEADER.as_bytes())?;
+            self.rotation.account(CSV_HEADER.len());
         }
+        self.rotation.record_slot(slot);
 
         self.writer.write_all(line.as_bytes())?;
         self.rotation.account(line_len);
@@ -270,17 +647,89 @@ impl CaptureWriter for CsvCaptureWriter {
 
 // ─── JSONL writer ────────────────────────────────────────────────────────────
 
+/// Parsed header fields a JSONL record can carry beyond the fixed
+/// `recv_ns`, `feed`, `slot` and `shred_idx` (`[capture] fields`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonlField {
+    FecSetIndex,
+    /// Variant family, as in the metrics log's `shred_mix`.
+    Variant,
+    /// Data shred flags byte; `null` for coding shreds.
+    Flags,
+    /// Datagram length in bytes.
+    Size,
+    ShredVersion,
+}
+
+impl JsonlField {
+    pub const NAMES: &'static str = "fec_set_index, variant, flags, size, shred_version";
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "fec_set_index" => Some(Self::FecSetIndex),
+            "variant" => Some(Self::Variant),
+            "flags" => Some(Self::Flags),
+            "size" => Some(Self::Size),
+            "shred_version" => Some(Self::ShredVersion),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::FecSetIndex => "fec_set_index",
+            Self::Variant => "variant",
+            Self::Flags => "flags",
+            Self::Size => "size",
+            Self::ShredVersion => "shred_version",
+        }
+    }
+
+    /// The field's JSON value for one packet; `null` if the packet is too
+    /// short to carry it.
+    fn value(self, payload: &[u8]) -> String {
+        let null = || "null".to_string();
+        match self {
+            Self::FecSetIndex => payload
+                .get(79..83)
+                .map_or_else(null, |b| u32::from_le_bytes(b.try_into().unwrap()).to_string()),
+            Self::Variant => {
+                payload.get(64).map_or_else(null, |&v| format!("\"{}\"", variant_name(v)))
+            }
+            // Data variants (legacy 0xa5, Merkle 0x8_/0x9_/0xb_) have top bits 10.
+            Self::Flags => match payload.get(64) {
+                Some(v) if v & 0xC0 == 0x80 => payload.get(85).map_or_else(null, u8::to_string),
+                _ => null(),
+            },
+            Self::Size => payload.len().to_string(),
+            Self::ShredVersion => payload
+                .get(77..79)
+                .map_or_else(null, |b| u16::from_le_bytes(b.try_into().unwrap()).to_string()),
+        }
+    }
+}
+
 pub struct JsonlCaptureWriter {
-    writer: BufWriter<File>,
+    writer: FlushedFile,
     rotation: RotationState,
+    fields: Vec<JsonlField>,
+    flusher: Flusher,
 }
 
 impl JsonlCaptureWriter {
-    pub fn new(output_dir: &str, rotate_mb: u64, ring_files: usize) -> io::Result<Self> {
+    pub fn new(
+        output_dir: &str,
+        rotate_mb: u64,
+        ring_files: usize,
+        slot_names: bool,
+        fields: Vec<JsonlField>,
+        flusher: Flusher,
+    ) -> io::Result<Self> {
         fs::create_dir_all(output_dir)?;
-        let rotation = RotationState::new(output_dir, "jsonl", rotate_mb, ring_files);
-        let writer = BufWriter::new(File::create(rotation.active_path())?);
-        Ok(Self { writer, rotation })
+        let rotation =
+            RotationState::new(output_dir, "jsonl", rotate_mb, ring_files, slot_names);
+        let writer = flusher.create(&rotation.active_path())?;
+        Ok(Self { writer, rotation, fields, flusher })
     }
 }
 
@@ -303,17 +752,21 @@ impl CaptureWriter for JsonlCaptureWriter {
         } else {
             0
         };
-        let line = format!(
-            "{{\"recv_ns\":{},\"feed\":\"{}\",\"slot\":{},\"shred_idx\":{}}}\n",
+        let mut line = format!(
+            "{{\"recv_ns\":{},\"feed\":\"{}\",\"slot\":{},\"shred_idx\":{}",
             ts_ns, feed, slot, idx
         );
+        for field in &self.fields {
+            line.push_str(&format!(",\"{}\":{}", field.name(), field.value(payload)));
+        }
+        line.push_str("}\n");
         let line_len = line.len();
 
         if self.rotation.should_rotate(line_len) {
-            self.writer.flush()?;
             self.rotation.rotate()?;
-            self.writer = BufWriter::new(File::create(self.rotation.active_path())?);
+            self.writer = self.flusher.create(&self.rotation.active_path())?;
         }
+        self.rotation.record_slot(slot);
 
         self.writer.write_all(line.as_bytes())?;
         self.rotation.account(line_len);
@@ -356,57 +809,505 @@ impl CaptureWriter for MultiWriter {
     }
 }
 
+/// Reject unknown formats and JSONL fields before any file is opened, and
+/// with `per_feed`, `feeds` (the source names) that would share a directory.
+pub fn validate(config: &CaptureConfig, feeds: &[&str]) -> Result<()> {
+    let known = |f: &&String| matches!(f.as_str(), "pcap" | "csv" | "jsonl");
+    if let Some(f) = config.formats.iter().find(|f| !known(f)) {
+        anyhow::bail!("unknown capture format '{}' (expected pcap, csv or jsonl)", f);
+    }
+    if let Some(f) = config.fields.iter().find(|f| JsonlField::parse(f).is_none()) {
+        anyhow::bail!("unknown capture field '{}' (expected one of: {})", f, JsonlField::NAMES);
+    }
+    if let Some(ref t) = config.trigger {
+        anyhow::ensure!(
+            t.coverage_below_pct.is_some() || t.silent_secs.is_some() || t.lead_below_ms.is_some(),
+            "capture.trigger needs coverage_below_pct, silent_secs or lead_below_ms"
+        );
+        anyhow::ensure!(t.buffer_mb > 0, "capture.trigger.buffer_mb must be > 0");
+    }
+    anyhow::ensure!(
+        config.per_feed || config.feed_max_size_mb.is_empty(),
+        "capture.feed_max_size_mb needs capture.per_feed = true"
+    );
+    if let Some(f) = config.feed_max_size_mb.keys().find(|f| !feeds.contains(&f.as_str())) {
+        anyhow::bail!("capture.feed_max_size_mb: no source named '{}'", f);
+    }
+    if config.per_feed {
+        let mut dirs: HashMap<String, &str> = HashMap::new();
+        for &feed in feeds {
+            if let Some(other) = dirs.insert(feed_dir_name(feed), feed) {
+                anyhow::bail!(
+                    "capture.per_feed: sources '{}' and '{}' would share the directory '{}'; \
+                     rename one",
+                    other,
+                    feed,
+                    feed_dir_name(feed)
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
 fn make_writer(config: &CaptureConfig) -> Box<dyn CaptureWriter> {
-    let writers: Vec<Box<dyn CaptureWriter>> = config
-        .formats
-        .iter()
-        .enumerate()
-        .map(|(idx, fmt)| -> Box<dyn CaptureWriter> {
-            let ring = config.ring_files_for(idx);
-            match fmt.as_str() {
-                "csv" => Box::new(
-                    CsvCaptureWriter::new(&config.output_dir, config.rotate_mb, ring)
-                        .expect("failed to create CSV capture writer"),
-                ),
-                "jsonl" => Box::new(
-                    JsonlCaptureWriter::new(&config.output_dir, config.rotate_mb, ring)
-                        .expect("failed to create JSONL capture writer"),
-                ),
-                _ => Box::new(
-                    PcapCaptureWriter::new(&config.output_dir, config.rotate_mb, ring)
-                        .expect("failed to create pcap capture writer"),
-                ),
+    let flusher = Flusher::spawn(config.fsync_ms).expect("failed to spawn capture flusher");
+    if config.per_feed {
+        return Box::new(PerFeedWriter { config: config.clone(), flusher, feeds: HashMap::new() });
+    }
+    Box::new(
+        format_writers(config, &config.output_dir, None, &flusher)
+            .expect("failed to create capture writers"),
+    )
+}
+
+/// A writer per configured format, each with its ring in `dir`, sized for
+/// `feed` (see [`CaptureConfig::ring_files_for`]).
+fn format_writers(
+    config: &CaptureConfig,
+    dir: &str,
+    feed: Option<&str>,
+    flusher: &Flusher,
+) -> io::Result<MultiWriter> {
+    let fields: Vec<JsonlField> =
+        config.fields.iter().filter_map(|f| JsonlField::parse(f)).collect();
+    let mut writers: Vec<Box<dyn CaptureWriter>> = Vec::new();
+    for (idx, fmt) in config.formats.iter().enumerate() {
+        let ring = config.ring_files_for(idx, feed);
+        let (mb, named, flusher) = (config.rotate_mb, config.slot_names, flusher.clone());
+        writers.push(match fmt.as_str() {
+            "csv" => Box::new(CsvCaptureWriter::new(dir, mb, ring, named, flusher)?),
+            "jsonl" => {
+                Box::new(JsonlCaptureWriter::new(dir, mb, ring, named, fields.clone(), flusher)?)
             }
-        })
+            _ => Box::new(PcapCaptureWriter::new(dir, mb, ring, named, flusher)?),
+        });
+    }
+    Ok(MultiWriter { writers })
+}
+
+// ─── Per-feed writer ─────────────────────────────────────────────────────────
+
+/// With `per_feed`: each feed's packets go to writers of their own in
+/// `<output_dir>/<feed>/`, opened when the feed's first packet arrives. A
+/// feed whose directory cannot be set up is reported once and not captured.
+struct PerFeedWriter {
+    config: CaptureConfig,
+    flusher: Flusher,
+    feeds: HashMap<String, Option<MultiWriter>>,
+}
+
+impl CaptureWriter for PerFeedWriter {
+    fn write_shred(
+        &mut self,
+        ts_ns: u64,
+        feed: &str,
+        dst_ip: [u8; 4],
+        dst_port: u16,
+        payload: &[u8],
+    ) -> io::Result<()> {
+        if !self.feeds.contains_key(feed) {
+            let dir = Path::new(&self.config.output_dir).join(feed_dir_name(feed));
+            let dir = dir.to_string_lossy();
+            let writer = format_writers(&self.config, &dir, Some(feed), &self.flusher)
+                .map_err(|e| warn!("capture: cannot write feed {} to {}: {}", feed, dir, e))
+                .ok();
+            self.feeds.insert(feed.to_string(), writer);
+        }
+        match self.feeds.get_mut(feed) {
+            Some(Some(w)) => w.write_shred(ts_ns, feed, dst_ip, dst_port, payload),
+            _ => Ok(()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        for w in self.feeds.values_mut().flatten() {
+            w.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Name of `feed`'s directory with `per_feed`: the feed name with anything
+/// but ASCII letters, digits, `-`, `_` and `.` replaced by `_`. Two feeds can
+/// map to the same name; [`validate`] refuses such a config.
+pub fn feed_dir_name(feed: &str) -> String {
+    let name: String = feed
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
         .collect();
-    Box::new(MultiWriter { writers })
+    if name.chars().all(|c| c == '.') {
+        return name.replace('.', "_");
+    }
+    name
 }
 
 /// Spawn the background capture thread and return immediately.
 ///
 /// The thread drains `rx`, writes each event via the configured writer, and
 /// handles rotation/ring-buffer management internally. It runs for the lifetime
-/// of the process.
+/// of the process. With `[capture.trigger]`, events are only written around
+/// the [`Trigger`]s received on `triggers`.
 pub fn spawn_capture_thread(
     config: &CaptureConfig,
     rx: Receiver<CaptureEvent>,
+    triggers: Option<Receiver<Trigger>>,
 ) -> std::thread::JoinHandle<()> {
     let mut writer = make_writer(config);
+    let triggered = config
+        .trigger
+        .as_ref()
+        .zip(triggers)
+        .map(|(t, triggers)| (TriggeredCapture::new(config, t), triggers));
 
     std::thread::Builder::new()
         .name("capture".into())
-        .spawn(move || {
-            for event in &rx {
-                if let Err(e) = writer.write_shred(
-                    event.ts_ns,
-                    event.feed,
-                    event.dst_ip,
-                    event.dst_port,
-                    &event.payload,
-                ) {
-                    warn!("capture write error: {}", e);
+        .spawn(move || match triggered {
+            None => {
+                for event in &rx {
+                    if let Err(e) = write_event(writer.as_mut(), &event) {
+                        warn!("capture write error: {}", e);
+                    }
                 }
             }
+            Some((mut capture, mut triggers)) => loop {
+                crossbeam_channel::select! {
+                    recv(rx) -> event => {
+                        let Ok(event) = event else { return };
+                        if let Err(e) = capture.push(event, writer.as_mut()) {
+                            warn!("capture write error: {}", e);
+                        }
+                    }
+                    recv(triggers) -> trigger => match trigger {
+                        Ok(trigger) => {
+                            if let Err(e) = capture.fire(&trigger, writer.as_mut()) {
+                                warn!("triggered capture failed: {}", e);
+                            }
+                        }
+                        Err(_) => triggers = crossbeam_channel::never(),
+                    },
+                }
+            },
         })
         .expect("failed to spawn capture thread")
 }
+
+fn write_event(writer: &mut dyn CaptureWriter, event: &CaptureEvent) -> io::Result<()> {
+    let ts_ns = event.ts_ns.to_realtime().0;
+    writer.write_shred(ts_ns, &event.feed, event.dst_ip, event.dst_port, &event.payload)
+}
+
+// ─── Triggered capture ───────────────────────────────────────────────────────
+
+/// An anomaly on one source, sent by the watcher to the capture thread.
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    pub source: Arc<str>,
+    pub reason: String,
+}
+
+/// One source's packets of the last `pre_secs`.
+#[derive(Default)]
+struct PacketRing {
+    events: VecDeque<CaptureEvent>,
+    bytes: usize,
+}
+
+/// State of the capture thread under `[capture.trigger]`.
+struct TriggeredCapture {
+    pre_ns: u64,
+    post_ns: u64,
+    max_bytes: usize,
+    rings: HashMap<Arc<str>, PacketRing>,
+    /// Events received up to this time are written straight out.
+    recording_until: MonotonicNs,
+    log_path: PathBuf,
+}
+
+impl TriggeredCapture {
+    fn new(config: &CaptureConfig, t: &CaptureTriggerConfig) -> Self {
+        Self {
+            pre_ns: t.pre_secs.saturating_mul(1_000_000_000),
+            post_ns: t.post_secs.saturating_mul(1_000_000_000),
+            max_bytes: t.buffer_mb.saturating_mul(1 << 20) as usize,
+            rings: HashMap::new(),
+            recording_until: MonotonicNs::default(),
+            log_path: Path::new(&config.output_dir).join("triggers.jsonl"),
+        }
+    }
+
+    /// Write `event` while a trigger's window is open; otherwise hold it in
+    /// its source's ring, dropping what is older than `pre_secs` or over the
+    /// ring's memory.
+    fn push(&mut self, event: CaptureEvent, writer: &mut dyn CaptureWriter) -> io::Result<()> {
+        if event.ts_ns <= self.recording_until {
+            return write_event(writer, &event);
+        }
+        let horizon = event.ts_ns.saturating_sub(self.pre_ns);
+        let ring = self.rings.entry(event.feed.clone()).or_default();
+        ring.bytes += event.payload.len();
+        ring.events.push_back(event);
+        while let Some(front) = ring.events.front() {
+            if front.ts_ns >= horizon && ring.bytes <= self.max_bytes {
+                break;
+            }
+            ring.bytes -= front.payload.len();
+            ring.events.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Write every held packet in arrival order, keep writing for
+    /// `post_secs`, and log the trigger.
+    fn fire(&mut self, trigger: &Trigger, writer: &mut dyn CaptureWriter) -> io::Result<()> {
+        let mut held: Vec<CaptureEvent> = self
+            .rings
+            .values_mut()
+            .flat_map(|ring| {
+                ring.bytes = 0;
+                ring.events.drain(..)
+            })
+            .collect();
+        held.sort_by_key(|e| e.ts_ns);
+        for event in &held {
+            write_event(writer, event)?;
+        }
+        writer.flush()?;
+        let now = MonotonicNs::now();
+        self.recording_until = self.recording_until.max(now.saturating_add(self.post_ns));
+        TRIGGERS.fetch_add(1, Ordering::Relaxed);
+        info!(
+            "capture triggered by {}: {} ({} packets held)",
+            trigger.source,
+            trigger.reason,
+            held.len()
+        );
+
+        let record = serde_json::json!({
+            "ts": now.to_realtime().0 / 1_000_000_000,
+            "source": &*trigger.source,
+            "reason": trigger.reason,
+            "packets_before": held.len(),
+            "first_packet_ns": held.first().map(|e| e.ts_ns.to_realtime().0),
+            "record_until_ns": self.recording_until.to_realtime().0,
+        });
+        let mut log = fs::OpenOptions::new().create(true).append(true).open(&self.log_path)?;
+        writeln!(log, "{}", record)
+    }
+}
+
+/// Per-source state of the trigger watcher.
+struct WatchState {
+    shreds: u64,
+    /// When `shreds` last changed; `None` until the source has received any.
+    last_change: Option<Instant>,
+    silence_fired: bool,
+    coverage_start: (u64, u64, Instant),
+    lead: (u64, i64),
+    last_fired: Option<Instant>,
+}
+
+/// Coverage is compared against `coverage_below_pct` over windows this long.
+const COVERAGE_WINDOW: Duration = Duration::from_secs(10);
+/// A one-second lead mean needs this many samples to count.
+const MIN_LEAD_SAMPLES: u64 = 10;
+
+/// Start the thread that checks `metrics` once a second and sends a
+/// [`Trigger`] for each anomaly. A source fires at most once per
+/// `post_secs`.
+pub fn spawn_trigger_watch(
+    t: &CaptureTriggerConfig,
+    metrics: Vec<Arc<SourceMetrics>>,
+    tx: Sender<Trigger>,
+) -> Result<()> {
+    let t = t.clone();
+    std::thread::Builder::new().name("capture-trigger".into()).spawn(move || {
+        let now = Instant::now();
+        let mut states: Vec<WatchState> = metrics
+            .iter()
+            .map(|m| WatchState {
+                shreds: m.shreds_received.load(Ordering::Relaxed),
+                last_change: None,
+                silence_fired: false,
+                coverage_start: (
+                    m.coverage_shreds_seen.load(Ordering::Relaxed),
+                    m.coverage_shreds_expected.load(Ordering::Relaxed),
+                    now,
+                ),
+                lead: (
+                    m.lead_time_count.load(Ordering::Relaxed),
+                    m.lead_time_sum_us.load(Ordering::Relaxed),
+                ),
+                last_fired: None,
+            })
+            .collect();
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+            for (m, state) in metrics.iter().zip(states.iter_mut()) {
+                let Some(reason) = check_anomaly(&t, m, state) else { continue };
+                let cooldown = Duration::from_secs(t.post_secs);
+                if state.last_fired.is_some_and(|at| at.elapsed() < cooldown) {
+                    continue;
+                }
+                state.last_fired = Some(Instant::now());
+                if tx.send(Trigger { source: m.name.clone(), reason }).is_err() {
+                    return;
+                }
+            }
+        }
+    })?;
+    Ok(())
+}
+
+/// Advance `state` by one tick and describe the anomaly `m` shows, if any.
+fn check_anomaly(
+    t: &CaptureTriggerConfig,
+    m: &SourceMetrics,
+    state: &mut WatchState,
+) -> Option<String> {
+    let now = Instant::now();
+    let idle = m.paused.load(Ordering::Relaxed) || m.standby.load(Ordering::Relaxed);
+    let mut reason = None;
+
+    let shreds = m.shreds_received.load(Ordering::Relaxed);
+    if shreds != state.shreds {
+        state.shreds = shreds;
+        state.last_change = Some(now);
+        state.silence_fired = false;
+    } else if let (Some(secs), Some(since)) = (t.silent_secs, state.last_change) {
+        if !m.is_rpc && !idle && !state.silence_fired && since.elapsed().as_secs() >= secs {
+            state.silence_fired = true;
+            reason = Some(format!("no shreds for {}s", secs));
+        }
+    }
+
+    let (seen0, expected0, since) = state.coverage_start;
+    if since.elapsed() >= COVERAGE_WINDOW {
+        let seen = m.coverage_shreds_seen.load(Ordering::Relaxed);
+        let expected = m.coverage_shreds_expected.load(Ordering::Relaxed);
+        state.coverage_start = (seen, expected, now);
+        let (seen, expected) = (seen.saturating_sub(seen0), expected.saturating_sub(expected0));
+        if let Some(min) = t.coverage_below_pct.filter(|_| expected > 0 && !idle) {
+            let pct = seen as f64 * 100.0 / expected as f64;
+            if pct < min {
+                reason = reason.or(Some(format!("coverage {:.1}% over 10s", pct)));
+            }
+        }
+    }
+
+    let count = m.lead_time_count.load(Ordering::Relaxed);
+    let sum = m.lead_time_sum_us.load(Ordering::Relaxed);
+    let (n, total) = (count.saturating_sub(state.lead.0), sum - state.lead.1);
+    state.lead = (count, sum);
+    if let Some(min) = t.lead_below_ms.filter(|_| n >= MIN_LEAD_SAMPLES) {
+        let mean_ms = total as f64 / n as f64 / 1000.0;
+        if mean_ms < min {
+            reason = reason.or(Some(format!("mean lead {:+.1}ms over 1s", mean_ms)));
+        }
+    }
+    reason
+}
+
+// ─── Ad-hoc capture (`shredtop capture start`) ───────────────────────────────
+
+static CAPTURING: AtomicBool = AtomicBool::new(true);
+
+extern "C" fn handle_sigint(_: libc::c_int) {
+    CAPTURING.store(false, Ordering::SeqCst);
+}
+
+/// `shredtop capture start`: join each group with a bare receiver — no
+/// decoder, races or metrics log — and write its packets with the writers of
+/// `config` on this thread until `duration` elapses or Ctrl-C.
+pub fn start(
+    groups: &[SocketAddrV4],
+    iface: &str,
+    duration: Option<Duration>,
+    config: &CaptureConfig,
+) -> Result<()> {
+    // Each group's receiver is named, and with `per_feed` written, after its
+    // `addr:port`.
+    let names: Vec<String> = groups.iter().map(|g| g.to_string()).collect();
+    validate(config, &names.iter().map(String::as_str).collect::<Vec<_>>())?;
+
+    let (cap_tx, cap_rx) = crossbeam_channel::bounded::<CaptureEvent>(4096);
+    let mut feeds = Vec::new();
+    for group in groups {
+        let metrics = SourceMetrics::new(group.to_string().into(), false);
+        // Shreds are only needed for the capture tap; drain and discard them.
+        let (shred_tx, shred_rx) = crossbeam_channel::bounded::<RawShred>(4096);
+        let mut receiver = ShredReceiver::new(
+            &group.ip().to_string(),
+            group.port(),
+            iface,
+            shred_tx,
+            metrics.clone(),
+            None,
+            None,
+            Some(cap_tx.clone()),
+        )
+        .with_context(|| format!("failed to join {} on {}", group, iface))?;
+        std::thread::Builder::new()
+            .name(format!("capture-recv-{}", group))
+            .spawn(move || {
+                if let Err(e) = receiver.run() {
+                    warn!("capture receiver crashed: {}", e);
+                }
+            })?;
+        std::thread::Builder::new()
+            .name("capture-drain".into())
+            .spawn(move || for _ in shred_rx {})?;
+        feeds.push(metrics);
+    }
+    drop(cap_tx);
+
+    let mut writer = make_writer(config);
+    CAPTURING.store(true, Ordering::SeqCst);
+    unsafe { libc::signal(libc::SIGINT, handle_sigint as *const () as libc::sighandler_t) };
+    let until = match duration {
+        Some(d) => format!("for {}s", d.as_secs()),
+        None => "until Ctrl-C".into(),
+    };
+    eprintln!(
+        "shredtop capture — {} on {} → {} ({}) {}",
+        groups.iter().map(|g| g.to_string()).collect::<Vec<_>>().join(", "),
+        iface,
+        config.output_dir,
+        config.formats.join(", "),
+        until,
+    );
+
+    let deadline = duration.map(|d| Instant::now() + d);
+    let mut written = 0u64;
+    while CAPTURING.load(Ordering::SeqCst) {
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            break;
+        }
+        match cap_rx.recv_timeout(Duration::from_millis(200)) {
+            Ok(event) => {
+                write_event(writer.as_mut(), &event)?;
+                written += 1;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    writer.flush()?;
+
+    let io = io_snapshot();
+    eprintln!(
+        "shredtop capture — wrote {} packets ({:.1} MB, {:.0} ms write stall)",
+        written,
+        io.bytes_written as f64 / 1_048_576.0,
+        io.write_stall_ms
+    );
+    for m in &feeds {
+        eprintln!(
+            "  {:<22} received={}  non-shred={}",
+            m.name,
+            m.shreds_received.load(Ordering::Relaxed),
+            m.non_shred_packets.load(Ordering::Relaxed),
+        );
+    }
+    Ok(())
+}
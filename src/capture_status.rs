@@ -1,16 +1,33 @@
 //! `shredtop capture list` — display the on-disk capture ring.
+//!
+//! `--stats` scans every ring file and adds a line per file with its shred
+//! count, distinct slots and per-feed packet counts, so a file can be checked
+//! for the feed of interest before pulling it. `--watch N` redraws the listing
+//! every N seconds as the ring rotates. With `per_feed`, each feed's
+//! directory is listed as a ring of its own.
 
 use anyhow::Result;
 use chrono::{TimeZone, Utc};
 use pcap_file::pcap::PcapReader;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::net::Ipv4Addr;
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use crate::capture::{archive_slots, feed_dir_name, ring_file_format};
 use crate::color;
-use crate::config::ProbeConfig;
+use crate::config::{CaptureConfig, ProbeConfig};
 
-pub fn run(config_path: &Path) -> Result<()> {
-    let config = ProbeConfig::load(config_path)?;
+pub fn run(
+    config_path: &Path,
+    profile: Option<&str>,
+    stats: bool,
+    watch: Option<u64>,
+) -> Result<()> {
+    let config = ProbeConfig::load(config_path, profile)?;
     let cap = config.capture.as_ref().ok_or_else(|| {
         anyhow::anyhow!(
             "no [capture] section in probe.toml — run `shredtop discover` to configure capture"
@@ -22,44 +39,140 @@ pub fn run(config_path: &Path) -> Result<()> {
         return Ok(());
     }
 
+    // pcap frames carry only the destination address; map it back to the
+    // source name configured for that multicast group.
+    let feed_names: HashMap<[u8; 4], String> = config
+        .sources
+        .iter()
+        .filter_map(|s| {
+            let ip: Ipv4Addr = s.multicast_addr.as_deref()?.parse().ok()?;
+            Some((ip.octets(), s.name.clone()))
+        })
+        .collect();
+
     let output_dir = Path::new(&cap.output_dir);
-    if !output_dir.exists() {
-        println!("Capture directory {} does not exist yet.", output_dir.display());
-        println!("Start the service to begin capture: shredtop service start");
+    let Some(secs) = watch else {
+        for line in render(cap, output_dir, stats.then_some(&feed_names), &mut StatsCache::new()) {
+            println!("{}", line);
+        }
         return Ok(());
+    };
+
+    println!(
+        "{}",
+        color::bold(&format!("SHREDTOP CAPTURE RING  —  refreshing every {}s, Ctrl-C to close", secs))
+    );
+    println!();
+    let mut cache = StatsCache::new();
+    let mut lines_drawn = 0usize;
+    loop {
+        let lines = render(cap, output_dir, stats.then_some(&feed_names), &mut cache);
+        if lines_drawn > 0 {
+            print!("\x1b[{}A\x1b[0J", lines_drawn);
+        }
+        for line in &lines {
+            println!("{}", line);
+        }
+        lines_drawn = lines.len();
+        std::thread::sleep(Duration::from_secs(secs.max(1)));
     }
+}
 
+/// Build the listing. `feed_names` is set when per-file statistics are wanted.
+fn render(
+    cap: &CaptureConfig,
+    output_dir: &Path,
+    feed_names: Option<&HashMap<[u8; 4], String>>,
+    cache: &mut StatsCache,
+) -> Vec<String> {
+    let mut out = Vec::new();
+    if !output_dir.exists() {
+        out.push(format!("Capture directory {} does not exist yet.", output_dir.display()));
+        out.push("Start the service to begin capture: shredtop service start".into());
+        return out;
+    }
+    if !cap.per_feed {
+        return ring_lines(cap, output_dir, None, feed_names, cache).unwrap_or_else(|| {
+            vec![
+                format!("No capture files in {}.", output_dir.display()),
+                "Start the service and wait a moment: shredtop service start".into(),
+            ]
+        });
+    }
+
+    let mut dirs: Vec<PathBuf> = std::fs::read_dir(output_dir)
+        .map(|rd| rd.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_dir()).collect())
+        .unwrap_or_default();
+    dirs.sort();
+    for dir in &dirs {
+        // Budgets are keyed by feed name; the directory holds its sanitized form.
+        let dir_name = dir.file_name().unwrap_or_default().to_string_lossy();
+        let feed = cap.feed_max_size_mb.keys().find(|f| feed_dir_name(f) == dir_name);
+        if let Some(lines) = ring_lines(cap, dir, feed.map(|f| f.as_str()), feed_names, cache) {
+            if !out.is_empty() {
+                out.push(String::new());
+            }
+            out.extend(lines);
+        }
+    }
+    if out.is_empty() {
+        out.push(format!("No feed capture directories in {}.", output_dir.display()));
+        out.push("Start the service and wait a moment: shredtop service start".into());
+    }
+    out
+}
+
+/// The listing of the ring in `dir`, sized for `feed`; `None` if the
+/// directory holds no capture files.
+fn ring_lines(
+    cap: &CaptureConfig,
+    dir: &Path,
+    feed: Option<&str>,
+    feed_names: Option<&HashMap<[u8; 4], String>>,
+    cache: &mut StatsCache,
+) -> Option<Vec<String>> {
+    let mut out = Vec::new();
     // Collect all capture files.
-    let mut files: Vec<PathBuf> = std::fs::read_dir(output_dir)?
-        .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .filter(|p| {
-            p.file_name()
-                .and_then(|n| n.to_str())
-                .map(|n| n.starts_with("shreds."))
-                .unwrap_or(false)
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|rd| {
+            rd.filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| ring_file_format(n).is_some())
+                })
+                .collect()
         })
-        .collect();
+        .unwrap_or_default();
 
     if files.is_empty() {
-        println!("No capture files in {}.", output_dir.display());
-        println!("Start the service and wait a moment: shredtop service start");
-        return Ok(());
+        return None;
     }
 
-    // Sort: generation 0 (active, no numeric suffix) first; higher numbers are
-    // more recent archives. Display oldest → newest → current.
-    files.sort_by_key(|p| archive_generation(p));
+    // Sort: active files first, then numbered archives by generation, then
+    // slot-named archives by first slot.
+    files.sort_by_key(|p| archive_order(p));
 
     let mut total_bytes: u64 = 0;
-    println!("{}", color::bold_cyan(&format!("CAPTURE RING  {}", output_dir.display())));
+    out.push(color::bold_cyan(&format!("CAPTURE RING  {}", dir.display())));
 
     for path in &files {
-        let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let meta = std::fs::metadata(path).ok();
+        let size = meta.as_ref().map(|m| m.len()).unwrap_or(0);
         total_bytes += size;
 
-        let is_active = archive_generation(path) == 0;
-        let (first_ts, last_ts) = read_timestamps(path, is_active);
+        let is_active = archive_order(path) == (0, 0);
+        let stats = match (feed_names, &meta) {
+            (Some(names), Some(m)) => Some(cache.get(path, m, is_active, names)),
+            _ => None,
+        };
+        // The stats scan already saw every record, including csv/jsonl files
+        // the pcap timestamp reader cannot open.
+        let (first_ts, last_ts) = match &stats {
+            Some(s) => (s.first_ns, if is_active { None } else { s.last_ns }),
+            None => read_timestamps(path, is_active),
+        };
 
         let name = path.file_name().unwrap_or_default().to_string_lossy();
         let size_str = human_size(size);
@@ -74,9 +187,12 @@ pub fn run(config_path: &Path) -> Result<()> {
 
         let row = format!("  {:<30}  {:>8}  {}", name, size_str, ts_str);
         if is_active {
-            println!("{}", color::bold(&row));
+            out.push(color::bold(&row));
         } else {
-            println!("{}", color::dim(&row));
+            out.push(color::dim(&row));
+        }
+        if let Some(s) = stats {
+            out.push(stats_line(&s));
         }
     }
 
@@ -84,34 +200,150 @@ pub fn run(config_path: &Path) -> Result<()> {
         .formats
         .iter()
         .enumerate()
-        .map(|(i, _)| cap.ring_files_for(i) as u64 * cap.rotate_mb)
+        .map(|(i, _)| cap.ring_files_for(i, feed) as u64 * cap.rotate_mb)
         .sum();
-    println!(
-        "{}",
-        color::bold(&format!(
-            "  Total: {}   ({} file(s), ring capacity {} MB across {} format(s))",
-            human_size(total_bytes),
-            files.len(),
-            ring_cap_mb,
-            cap.formats.len(),
-        ))
-    );
+    out.push(color::bold(&format!(
+        "  Total: {}   ({} file(s), ring capacity {} MB across {} format(s))",
+        human_size(total_bytes),
+        files.len(),
+        ring_cap_mb,
+        cap.formats.len(),
+    )));
+    Some(out)
+}
 
-    Ok(())
+fn stats_line(s: &FileStats) -> String {
+    let slots = match (s.slots.iter().min(), s.slots.iter().max()) {
+        (Some(lo), Some(hi)) => format!("{} slots ({}–{})", format_num(s.slots.len() as u64), lo, hi),
+        _ => "0 slots".into(),
+    };
+    let feeds = if s.per_feed.is_empty() {
+        "no shreds".to_string()
+    } else {
+        s.per_feed
+            .iter()
+            .map(|(feed, n)| format!("{} {}", feed, format_num(*n)))
+            .collect::<Vec<_>>()
+            .join(" · ")
+    };
+    format!("      {} shreds  {}  {}", format_num(s.shreds), slots, feeds)
+}
+
+// ─── Per-file statistics ──────────────────────────────────────────────────────
+
+#[derive(Default, Clone)]
+struct FileStats {
+    shreds: u64,
+    slots: HashSet<u64>,
+    /// Shred count per feed name (or destination IP for unmapped pcap frames).
+    per_feed: BTreeMap<String, u64>,
+    first_ns: Option<u64>,
+    last_ns: Option<u64>,
+}
+
+impl FileStats {
+    fn add(&mut self, ts_ns: u64, feed: String, slot: u64) {
+        self.shreds += 1;
+        self.slots.insert(slot);
+        *self.per_feed.entry(feed).or_default() += 1;
+        self.first_ns.get_or_insert(ts_ns);
+        self.last_ns = Some(ts_ns);
+    }
+}
+
+/// Scan results for archived files, keyed by inode and size. Rotation renames
+/// `shreds.pcap.N` to `N+1` without touching the contents, so `--watch` only
+/// rescans the active file and whichever archive was just rotated in.
+struct StatsCache {
+    archived: HashMap<(u64, u64), FileStats>,
+}
+
+impl StatsCache {
+    fn new() -> Self {
+        Self { archived: HashMap::new() }
+    }
+
+    fn get(
+        &mut self,
+        path: &Path,
+        meta: &std::fs::Metadata,
+        is_active: bool,
+        feed_names: &HashMap<[u8; 4], String>,
+    ) -> FileStats {
+        if is_active {
+            return scan_file(path, feed_names);
+        }
+        let key = (meta.ino(), meta.len());
+        self.archived
+            .entry(key)
+            .or_insert_with(|| scan_file(path, feed_names))
+            .clone()
+    }
+}
+
+/// Count shreds, slots and per-feed packets in one ring file of any format.
+fn scan_file(path: &Path, feed_names: &HashMap<[u8; 4], String>) -> FileStats {
+    let mut stats = FileStats::default();
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let Ok(file) = File::open(path) else { return stats };
+
+    let format = ring_file_format(&name);
+    if format == Some("pcap") {
+        let Ok(mut reader) = PcapReader::new(file) else { return stats };
+        while let Some(pkt) = reader.next_packet() {
+            let Ok(pkt) = pkt else { continue };
+            let data = &pkt.data;
+            // Ethernet(14) + IPv4(20) + UDP(8) + slot/index header.
+            if data.len() < 42 + 77 || data[12] != 0x08 || data[13] != 0x00 || data[23] != 0x11 {
+                continue;
+            }
+            let dst = [data[30], data[31], data[32], data[33]];
+            let feed = feed_names
+                .get(&dst)
+                .cloned()
+                .unwrap_or_else(|| Ipv4Addr::from(dst).to_string());
+            let slot = u64::from_le_bytes(data[42 + 65..42 + 73].try_into().unwrap());
+            stats.add(pkt.timestamp.as_nanos() as u64, feed, slot);
+        }
+    } else if format == Some("csv") {
+        for line in BufReader::new(file).lines().map_while(|l| l.ok()) {
+            let mut cols = line.split(',');
+            let (Some(ts), Some(feed), Some(slot)) = (cols.next(), cols.next(), cols.next()) else {
+                continue;
+            };
+            // Skips the header row as well.
+            let (Ok(ts), Ok(slot)) = (ts.parse(), slot.parse()) else { continue };
+            stats.add(ts, feed.to_string(), slot);
+        }
+    } else if format == Some("jsonl") {
+        for line in BufReader::new(file).lines().map_while(|l| l.ok()) {
+            let Ok(v) = serde_json::from_str::<serde_json::Value>(&line) else { continue };
+            let (Some(ts), Some(feed), Some(slot)) =
+                (v["recv_ns"].as_u64(), v["feed"].as_str(), v["slot"].as_u64())
+            else {
+                continue;
+            };
+            stats.add(ts, feed.to_string(), slot);
+        }
+    }
+    stats
 }
 
-/// Extract the archive generation number from the file name.
-/// `shreds.pcap` → 0 (active), `shreds.pcap.7` → 7.
-fn archive_generation(path: &Path) -> u32 {
+/// Where a file sorts in the listing: `shreds.pcap` → (0, 0) (active),
+/// `shreds.pcap.7` → (1, 7), `shreds.291438000-291438420.pcap` → (2, 291438000).
+pub fn archive_order(path: &Path) -> (u8, u64) {
     let name = path.file_name().unwrap_or_default().to_string_lossy();
+    if let Some((first, _)) = archive_slots(&name) {
+        return (2, first);
+    }
     // Try to parse trailing ".N"
     if let Some(dot) = name.rfind('.') {
         let suffix = &name[dot + 1..];
-        if let Ok(n) = suffix.parse::<u32>() {
-            return n;
+        if let Ok(n) = suffix.parse::<u64>() {
+            return (1, n);
         }
     }
-    0 // active file has no numeric suffix
+    (0, 0) // active file has no numeric suffix
 }
 
 /// Read first-packet and last-packet timestamps from a pcap file.
@@ -162,3 +394,15 @@ fn human_size(bytes: u64) -> String {
         format!("{:.0} KB", bytes as f64 / 1024.0)
     }
 }
+
+fn format_num(n: u64) -> String {
+    let s = n.to_string();
+    let mut out = String::new();
+    for (i, c) in s.chars().rev().enumerate() {
+        if i > 0 && i % 3 == 0 {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out.chars().rev().collect()
+}
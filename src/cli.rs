@@ -15,6 +15,11 @@ pub struct Cli {
     #[clap(long, short, default_value = "probe.toml")]
     pub config: PathBuf,
 
+    /// Use the `[profile.NAME]` settings of the config, and this profile's
+    /// own log, state, control socket and service unit
+    #[clap(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
     #[clap(subcommand)]
     pub command: Commands,
 }
@@ -22,7 +27,17 @@ pub struct Cli {
 #[derive(Subcommand)]
 pub enum Commands {
     /// Detect active shred feeds and write probe.toml
-    Discover,
+    Discover {
+        /// Detect on these hosts over SSH instead, without prompts, and write
+        /// probe.<host>.toml for each (comma-separated)
+        #[clap(long, value_delimiter = ',', value_name = "HOSTS")]
+        ssh: Vec<String>,
+
+        /// With --ssh, write every host into the --config file instead,
+        /// as one table per host
+        #[clap(long, requires = "ssh")]
+        combined: bool,
+    },
 
     /// Start background data collection as a systemd service
     ///
@@ -42,10 +57,21 @@ pub enum Commands {
         /// Dashboard refresh interval in seconds
         #[clap(long, default_value = "15")]
         interval: u64,
+
+        /// Show the most recent slots per source instead of aggregate metrics
+        #[clap(long)]
+        slots: bool,
     },
 
     /// Latest metrics snapshot from the service log (non-interactive)
-    Status,
+    Status {
+        /// Print a one-line verdict and exit with its health code instead
+        /// (0 ok, 2 no log, 3 stale log, 4 no shreds, 5 low coverage,
+        /// 6 baseline stale; 1 is an error); thresholds come from `[health]`
+        /// in the config
+        #[clap(long)]
+        check: bool,
+    },
 
     /// Run a timed benchmark and write a structured JSON report
     Bench {
@@ -56,10 +82,57 @@ pub enum Commands {
         /// Write JSON report to this file (default: stdout)
         #[clap(long)]
         output: Option<PathBuf>,
+
+        /// Profile fee payer and priority fee of txs that beat RPC by more than this many ms
+        #[clap(long, default_value = "50")]
+        early_ms: u64,
+
+        /// Also record a time series with one point every MS milliseconds
+        /// (e.g. 250), returned as `samples` in the report
+        #[clap(long, value_name = "MS")]
+        interval_ms: Option<u64>,
+
+        /// Measure decoder throughput on this pcap instead of running the
+        /// configured sources (no sockets, no pacing; ignores --duration)
+        #[clap(long, value_name = "PCAP")]
+        offline: Option<PathBuf>,
+
+        /// Run one --duration window for every combination of the
+        /// --busy-poll-us, --rcvbuf-mb, --batch and --pin values, one after
+        /// another, and report them side by side
+        #[clap(long, conflicts_with_all = ["offline", "interval_ms"])]
+        matrix: bool,
+
+        /// SO_BUSY_POLL of the receive sockets in µs, 0 to disable
+        /// (default 50; a comma-separated list with --matrix)
+        #[clap(long, value_delimiter = ',', value_name = "US")]
+        busy_poll_us: Vec<u32>,
+
+        /// Receive buffer requested per socket, in MB
+        /// (default 256; a comma-separated list with --matrix)
+        #[clap(long, value_delimiter = ',', value_name = "MB")]
+        rcvbuf_mb: Vec<usize>,
+
+        /// Datagrams per recvmmsg call
+        /// (default 64; a comma-separated list with --matrix)
+        #[clap(long, value_delimiter = ',', value_name = "N")]
+        batch: Vec<usize>,
+
+        /// `off` ignores the sources' pin_recv_core and pin_decode_core
+        /// (default on; `on,off` with --matrix)
+        #[clap(long, value_delimiter = ',', value_name = "on|off",
+               value_parser = ["on", "off"])]
+        pin: Vec<String>,
     },
 
     /// Print an example probe.toml to stdout
-    Init,
+    Init {
+        /// Detect the feeds this host is already receiving (multicast
+        /// memberships, validator and ShredStream proxy sockets, local RPC)
+        /// and print a probe.toml for them instead, without prompts
+        #[clap(long)]
+        from_running: bool,
+    },
 
     /// Remove all shredtop files from the system (service, binary, logs, capture files, config)
     Uninstall,
@@ -69,6 +142,27 @@ pub enum Commands {
         /// Pull main branch and rebuild from source instead of downloading a release
         #[clap(long)]
         source: bool,
+
+        /// `stable` (latest release) or `nightly` (latest build of main)
+        #[clap(long, default_value = "stable", value_parser = ["stable", "nightly"])]
+        channel: String,
+
+        /// minisign public key the release's SHA256SUMS signature is checked with
+        #[clap(long, value_name = "FILE", default_value = crate::upgrade::DEFAULT_PUBKEY)]
+        pubkey: PathBuf,
+
+        /// Check the release's GPG signature with gpgv against this keyring instead of the
+        /// minisign signature
+        #[clap(long, value_name = "FILE")]
+        gpg_keyring: Option<PathBuf>,
+
+        /// Install even when no signature can be verified (the checksum is still checked)
+        #[clap(long)]
+        skip_signature: bool,
+
+        /// Put back the binary the last upgrade replaced
+        #[clap(long, conflicts_with = "source")]
+        rollback: bool,
     },
 
     /// Manage and inspect the on-disk capture ring
@@ -80,24 +174,154 @@ pub enum Commands {
     /// Analyze a pcap capture file for per-feed shred timing
     ///
     /// Reads any pcap written by `shredtop capture` (or any third-party capture
-    /// of the same UDP multicast traffic), pairs shreds that arrived on multiple
-    /// feeds, and prints a timing table showing win rates and lead times.
+    /// of the same UDP multicast traffic), races the feeds as `shredtop run`
+    /// does, and prints the SHRED RACE table of `shredtop monitor`.
     ///
     /// Example:
     ///   shredtop analyze capture.pcap \
     ///     --feed 233.84.178.1=bebop \
     ///     --feed 233.84.178.2=jito-shredstream
+    ///
+    /// Full lead-time histograms per feed pair:
+    ///   shredtop analyze capture.pcap --feed ... --histogram hist.csv --bucket-us 50 --ascii
+    ///
+    /// A range of slots from the whole capture ring:
+    ///   shredtop analyze /var/log/shredtop-capture --slots 291438000-291438100
+    ///
+    /// The pairs as JSON, to diff against the service log:
+    ///   shredtop analyze capture.pcap --feed ... --json race.json
+    ///
+    /// Follow the ring while it captures, to check feed mappings and timing:
+    ///   shredtop analyze /var/log/shredtop-capture --live --feed ...
     Analyze {
-        /// pcap file to analyze
+        /// pcap file to analyze, or a capture directory to read every pcap in
         pcap: std::path::PathBuf,
 
-        /// Feed IP=name mappings (repeatable), e.g. --feed 233.84.178.1=bebop
+        /// Feed IP=name mappings (repeatable), e.g. --feed 233.84.178.1=bebop.
+        /// Omit to treat every destination IP:port in the pcap as a feed
         #[clap(long, value_parser = parse_feed_mapping)]
         feed: Vec<(std::net::Ipv4Addr, String)>,
 
         /// Minimum matched pairs required to display results
         #[clap(long, default_value_t = 10)]
         min_matched: u64,
+
+        /// Only analyze slots FIRST-LAST (inclusive); uses slot-named archives
+        /// and their index sidecars to skip the rest
+        #[clap(long, value_name = "FIRST-LAST", value_parser = parse_slot_range)]
+        slots: Option<(u64, u64)>,
+
+        /// Write per-pair lead-time histograms to FILE (CSV if it ends in .csv, else JSON)
+        #[clap(long, value_name = "FILE")]
+        histogram: Option<std::path::PathBuf>,
+
+        /// Histogram bucket width in microseconds
+        #[clap(long, default_value_t = 100)]
+        bucket_us: u64,
+
+        /// Render per-pair lead-time histograms in the terminal
+        #[clap(long)]
+        ascii: bool,
+
+        /// Write the race pairs to FILE as JSON, in the shape of the metrics
+        /// log's `shred_race`
+        #[clap(long, value_name = "FILE")]
+        json: Option<std::path::PathBuf>,
+
+        /// Follow the active capture file (DIR/shreds.pcap, or the FILE given)
+        /// across rotations and redraw the race table until Ctrl-C
+        #[clap(long, conflicts_with_all = ["slots", "histogram", "ascii", "json"])]
+        live: bool,
+
+        /// Seconds between redraws with --live
+        #[clap(long, value_name = "SECS", default_value_t = 2, requires = "live")]
+        interval: u64,
+    },
+
+    /// Replay a pcap deterministically through decoders, fan-in and races
+    ///
+    /// Runs every feed through the live pipeline on a single thread, in
+    /// capture-timestamp order, with packet timestamps as the clock. The same
+    /// pcap always gives the same JSON report, for regression-testing metric
+    /// changes against a golden output.
+    ///
+    /// Example:
+    ///   shredtop replay capture.pcap --feed 233.84.178.1=bebop \
+    ///     --feed 233.84.178.2=jito-shredstream --output golden.json
+    Replay {
+        /// pcap file to replay
+        pcap: std::path::PathBuf,
+
+        /// Feed IP=name mappings (repeatable), e.g. --feed 233.84.178.1=bebop
+        #[clap(long, value_parser = parse_feed_mapping)]
+        feed: Vec<(std::net::Ipv4Addr, String)>,
+
+        /// Write the JSON report to this file (default: stdout)
+        #[clap(long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Export lead time by hour-of-day and day-of-week from the metrics log
+    ///
+    /// Buckets the lead-time samples between snapshots of the log into a 7×24
+    /// UTC grid per source and writes it as CSV; `--png` also renders the
+    /// grids as an image (built with the `heatmap-png` feature).
+    Heatmap {
+        /// CSV output file
+        #[clap(long, default_value = "heatmap.csv")]
+        output: PathBuf,
+
+        /// Also render the heatmap as a PNG (needs the `heatmap-png` feature)
+        #[clap(long)]
+        png: Option<PathBuf>,
+
+        /// Metrics log(s) to read (repeatable; default: the profile's log,
+        /// /var/log/shredtop.jsonl without one)
+        #[clap(long)]
+        log: Vec<PathBuf>,
+    },
+
+    /// Compare feeds across probes at several sites
+    ///
+    /// Polls each probe's web dashboard API ([web] enabled = true) and shows
+    /// the same feeds side by side, plus which site completes the same slots
+    /// first. The cross-site numbers assume the hosts' clocks are synchronized.
+    ///
+    /// Example:
+    ///   shredtop fleet --probe fra=10.0.1.5:8080 --probe ams=10.0.2.5:8080
+    Fleet {
+        /// Probe as NAME=HOST:PORT (repeatable)
+        #[clap(long = "probe", required = true, value_parser = parse_probe)]
+        probes: Vec<(String, String)>,
+
+        /// Redraw every N seconds instead of printing once
+        #[clap(long, value_name = "SECS")]
+        watch: Option<u64>,
+
+        /// Print JSON instead of tables
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Pause or resume individual sources of the running service
+    ///
+    /// A paused source keeps its socket drained but contributes nothing to
+    /// metrics, lead times or shred races until resumed. Not persisted across
+    /// restarts.
+    Ctl {
+        #[clap(subcommand)]
+        action: CtlAction,
+
+        /// Control socket of the running service (default: the profile's,
+        /// /run/shredtop.sock without one)
+        #[clap(long)]
+        socket: Option<PathBuf>,
+    },
+
+    /// Inspect transactions traced by `[trace]` in probe.toml
+    Trace {
+        #[clap(subcommand)]
+        action: TraceAction,
     },
 
     /// Background data collection daemon (used by the systemd service)
@@ -107,12 +331,51 @@ pub enum Commands {
         #[clap(long, default_value = "15")]
         interval: u64,
 
-        /// Path to write metrics log (JSONL)
-        #[clap(long, default_value = crate::run::DEFAULT_LOG)]
-        log: std::path::PathBuf,
+        /// Between snapshots, also log a lightweight `sample` record every
+        /// MS milliseconds (e.g. 250 for short experiments)
+        #[clap(long, value_name = "MS")]
+        interval_ms: Option<u64>,
+
+        /// Also log a `funnel` record for every slot each shred source
+        /// finalizes: shreds, FEC sets, entries, txs decoded and txs first
+        #[clap(long)]
+        funnel: bool,
+
+        /// Path to write metrics log (JSONL; default: the profile's,
+        /// /var/log/shredtop.jsonl without one)
+        #[clap(long)]
+        log: Option<std::path::PathBuf>,
+
+        /// State file for cumulative counters and race stats (saved on
+        /// shutdown; default: the profile's, /var/lib/shredtop/state.json
+        /// without one)
+        #[clap(long)]
+        state: Option<std::path::PathBuf>,
+
+        /// Start with empty statistics instead of restoring the state file
+        #[clap(long)]
+        fresh: bool,
+
+        /// Unix socket for `shredtop ctl` commands (default: the profile's,
+        /// /run/shredtop.sock without one)
+        #[clap(long)]
+        ctl_socket: Option<PathBuf>,
     },
 }
 
+fn parse_duration(s: &str) -> std::result::Result<std::time::Duration, String> {
+    let (num, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let n: u64 = num.parse().map_err(|_| format!("invalid duration '{}'", s))?;
+    let scale = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        _ => return Err(format!("invalid duration '{}' (expected e.g. 90s, 10m, 1h)", s)),
+    };
+    let secs = n.checked_mul(scale).ok_or_else(|| format!("duration '{}' out of range", s))?;
+    Ok(std::time::Duration::from_secs(secs))
+}
+
 fn parse_feed_mapping(s: &str) -> std::result::Result<(std::net::Ipv4Addr, String), String> {
     let (ip_str, name) = s
         .split_once('=')
@@ -123,12 +386,99 @@ fn parse_feed_mapping(s: &str) -> std::result::Result<(std::net::Ipv4Addr, Strin
     Ok((ip, name.to_string()))
 }
 
+fn parse_slot_range(s: &str) -> std::result::Result<(u64, u64), String> {
+    let parse = |v: &str| v.parse::<u64>().map_err(|e| format!("invalid slot '{}': {}", v, e));
+    match s.split_once('-') {
+        Some((lo, hi)) => Ok((parse(lo)?, parse(hi)?)),
+        None => parse(s).map(|slot| (slot, slot)),
+    }
+}
+
+fn parse_probe(s: &str) -> std::result::Result<(String, String), String> {
+    let (name, addr) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=HOST:PORT, got '{}'", s))?;
+    if name.is_empty() || addr.is_empty() {
+        return Err(format!("expected NAME=HOST:PORT, got '{}'", s));
+    }
+    Ok((name.to_string(), addr.to_string()))
+}
+
 #[derive(Subcommand)]
 pub enum CaptureAction {
     /// List capture ring files with sizes and timestamp coverage
+    List {
+        /// Scan each file and show shred count, distinct slots and per-feed packets
+        #[clap(long)]
+        stats: bool,
+
+        /// Redraw every N seconds as the ring rotates (default 2)
+        #[clap(long, value_name = "SECS", num_args = 0..=1, default_missing_value = "2")]
+        watch: Option<u64>,
+    },
+    /// Capture multicast groups to disk without the service or decoder
+    ///
+    /// Joins each group with a bare receiver and writes every packet with the
+    /// capture writers until --duration elapses or Ctrl-C. Needs no probe.toml.
+    ///
+    /// Example:
+    ///   shredtop capture start --group 233.84.178.1:7733 --iface doublezero1 --duration 10m
+    Start {
+        /// Multicast group ADDR:PORT to join (repeatable)
+        #[clap(long, required = true)]
+        group: Vec<std::net::SocketAddrV4>,
+
+        /// Interface to join the groups on
+        #[clap(long, default_value = "doublezero1")]
+        iface: String,
+
+        /// Stop after this long, e.g. 90s, 10m, 1h (default: until Ctrl-C)
+        #[clap(long, value_parser = parse_duration)]
+        duration: Option<std::time::Duration>,
+
+        /// Directory to write capture files into
+        #[clap(long, default_value = ".")]
+        output_dir: String,
+
+        /// Output format: pcap, csv or jsonl (repeatable)
+        #[clap(long, default_value = "pcap")]
+        format: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CtlAction {
+    /// Stop counting and forwarding a source until resumed
+    Pause {
+        /// Source name from probe.toml
+        source: String,
+    },
+    /// Resume a paused source
+    Resume {
+        /// Source name from probe.toml
+        source: String,
+    },
+    /// Show each source and whether it is paused
     List,
 }
 
+#[derive(Subcommand)]
+pub enum TraceAction {
+    /// Print the timeline of one traced transaction across every source
+    ///
+    /// Each copy's shred receive, decode, fan-in relay and dedup times are
+    /// shown in order, with the emit time of the copy that was forwarded.
+    /// STEP is the time since the previous hop of the same source.
+    Show {
+        /// Transaction signature (base58)
+        signature: String,
+
+        /// Trace log to read (default: `[trace] output` in probe.toml)
+        #[clap(long)]
+        file: Option<PathBuf>,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum ServiceAction {
     /// Install the unit file, enable on boot, and start (run this once to set up)
@@ -145,4 +495,18 @@ pub enum ServiceAction {
     Disable,
     /// Stop, disable, and remove the unit file
     Uninstall,
+    /// Show the service's journal, highlighting known failure patterns
+    Logs {
+        /// Keep printing new entries as they are logged
+        #[clap(short, long)]
+        follow: bool,
+
+        /// Only show entries newer than this (journalctl time, e.g. "1h", "today")
+        #[clap(long)]
+        since: Option<String>,
+
+        /// Instance of a templated `shredtop@NAME` unit (default: every unit found)
+        #[clap(long)]
+        instance: Option<String>,
+    },
 }
@@ -0,0 +1,259 @@
+//! Clock discipline status, recorded with every metrics snapshot.
+//!
+//! Lead times and races are differences of receive timestamps, and kernel
+//! timestamps are wall-clock time. A wall clock that is unsynchronized,
+//! far off its reference, or stepped between two snapshots makes that
+//! interval's numbers unreliable. Each snapshot therefore records where the
+//! clock stood, from the first of these that answers (`source = "auto"`):
+//!
+//! - chrony, through `chronyc -c tracking`: offset, RMS offset as jitter,
+//!   stratum and reference;
+//! - ptp4l, through `pmc -u -b 0 'GET TIME_STATUS_NP'`: offset from the
+//!   grandmaster, and whether one is present;
+//! - the kernel, through `adjtimex(2)`: the `STA_UNSYNC` flag, offset and
+//!   estimated error, as set by whichever daemon disciplines the clock.
+//!
+//! Independently of the daemon, the wall clock is compared with
+//! `CLOCK_MONOTONIC`: a move of more than `max_step_us` since the previous
+//! snapshot is a step. Both clocks are slewed alike, so slewing alone never
+//! reads as a step. A snapshot is `trusted` when the clock is synced, within
+//! `max_offset_us` of its reference, and did not step. The race numbers in
+//! the log are cumulative, so the interval ending at an untrusted snapshot
+//! is the one to leave out.
+//!
+//! `chronyc` and `pmc` get [`COMMAND_TIMEOUT`] to answer, so a hung daemon
+//! cannot hold up the snapshot loop.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::config::ClockSyncConfig;
+
+/// ptp4l's management socket; `auto` only asks `pmc` when it exists.
+const PTP4L_SOCKET: &str = "/var/run/ptp4l";
+/// How long `chronyc` or `pmc` may take before the snapshot goes on without.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, PartialEq)]
+enum Source {
+    Auto,
+    Chrony,
+    Ptp,
+    Kernel,
+}
+
+/// Queried once per snapshot by the snapshot loop.
+pub struct ClockSync {
+    source: Source,
+    max_offset_us: f64,
+    max_step_us: i64,
+    /// Wall clock minus `CLOCK_MONOTONIC` at the previous snapshot, ns.
+    last_offset_ns: Option<i128>,
+    /// Whether the previous snapshot was trusted; changes are logged.
+    trusted: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClockSyncSnapshot {
+    /// `chrony`, `ptp` or `kernel`.
+    pub source: &'static str,
+    pub synced: bool,
+    /// Offset from the reference as the source reports it, µs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset_us: Option<f64>,
+    /// chrony's RMS offset, µs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jitter_us: Option<f64>,
+    /// The kernel's estimated error, µs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub est_error_us: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stratum: Option<u32>,
+    /// NTP server or PTP grandmaster the clock follows.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    /// How far the wall clock moved against `CLOCK_MONOTONIC` since the
+    /// previous snapshot, µs.
+    pub step_us: i64,
+    /// Synced, within `max_offset_us`, and not stepped: this interval's race
+    /// numbers can be used.
+    pub trusted: bool,
+}
+
+impl ClockSync {
+    /// `None` for `source = "off"`.
+    pub fn new(cfg: &ClockSyncConfig) -> Result<Option<Self>> {
+        let source = match cfg.source.as_str() {
+            "auto" => Source::Auto,
+            "chrony" => Source::Chrony,
+            "ptp" => Source::Ptp,
+            "kernel" => Source::Kernel,
+            "off" => return Ok(None),
+            other => anyhow::bail!(
+                "clock_sync.source must be auto, chrony, ptp, kernel or off, not {:?}",
+                other
+            ),
+        };
+        Ok(Some(Self {
+            source,
+            max_offset_us: cfg.max_offset_us as f64,
+            max_step_us: cfg.max_step_us as i64,
+            last_offset_ns: None,
+            trusted: true,
+        }))
+    }
+
+    /// The clock's status now. `None` when the configured source did not
+    /// answer.
+    pub fn snapshot(&mut self) -> Option<ClockSyncSnapshot> {
+        let offset_ns = wall_minus_monotonic_ns();
+        let step_us = self.last_offset_ns.map_or(0, |last| ((offset_ns - last) / 1000) as i64);
+        self.last_offset_ns = Some(offset_ns);
+
+        let mut snap = match self.source {
+            Source::Chrony => chrony(),
+            Source::Ptp => ptp(),
+            Source::Kernel => kernel(),
+            Source::Auto => chrony()
+                .or_else(|| Path::new(PTP4L_SOCKET).exists().then(ptp).flatten())
+                .or_else(kernel),
+        }?;
+        snap.step_us = step_us;
+        snap.trusted = snap.synced
+            && !snap.offset_us.is_some_and(|us| us.abs() > self.max_offset_us)
+            && step_us.abs() <= self.max_step_us;
+        if snap.trusted != self.trusted {
+            self.trusted = snap.trusted;
+            if snap.trusted {
+                tracing::info!("clock_sync: {} clock trusted again", snap.source);
+            } else {
+                tracing::warn!(
+                    "clock_sync: {} clock untrusted (synced {}, offset {:?}us, step {}us); \
+                     races in this interval are unreliable",
+                    snap.source, snap.synced, snap.offset_us, snap.step_us
+                );
+            }
+        }
+        Some(snap)
+    }
+}
+
+/// `CLOCK_REALTIME − CLOCK_MONOTONIC`, ns. NTP and PTP slew both clocks by
+/// the same amount, so this only moves when the wall clock is stepped.
+/// Against `CLOCK_MONOTONIC_RAW`, which is never slewed, the frequency
+/// correction would build up and read as a step.
+fn wall_minus_monotonic_ns() -> i128 {
+    let read = |clock| {
+        let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+        unsafe { libc::clock_gettime(clock, &mut ts) };
+        ts.tv_sec as i128 * 1_000_000_000 + ts.tv_nsec as i128
+    };
+    read(libc::CLOCK_REALTIME) - read(libc::CLOCK_MONOTONIC)
+}
+
+/// Run `cmd` for its stdout. `None` if it cannot be started or has not
+/// exited after [`COMMAND_TIMEOUT`], in which case it is killed.
+fn output(cmd: &mut Command) -> Option<Output> {
+    let mut child =
+        cmd.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn().ok()?;
+    let deadline = Instant::now() + COMMAND_TIMEOUT;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(5)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    };
+    let mut stdout = Vec::new();
+    child.stdout.take()?.read_to_end(&mut stdout).ok()?;
+    Some(Output { status, stdout, stderr: Vec::new() })
+}
+
+fn unknown(source: &'static str, synced: bool) -> ClockSyncSnapshot {
+    ClockSyncSnapshot {
+        source,
+        synced,
+        offset_us: None,
+        jitter_us: None,
+        est_error_us: None,
+        stratum: None,
+        reference: None,
+        step_us: 0,
+        trusted: false,
+    }
+}
+
+/// `chronyc -c tracking`: one CSV line of reference ID, reference name,
+/// stratum, reference time, system time offset, last offset, RMS offset,
+/// frequency, residual frequency, skew, root delay, root dispersion, update
+/// interval and leap status. Offsets are in seconds.
+fn chrony() -> Option<ClockSyncSnapshot> {
+    let output = output(Command::new("chronyc").args(["-c", "tracking"]))?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let fields: Vec<&str> = text.trim().split(',').collect();
+    if fields.len() < 14 {
+        return None;
+    }
+    let secs = |i: usize| fields[i].parse::<f64>().ok().map(|s| s * 1e6);
+    let stratum = fields[2].parse().ok();
+    let synced = fields[13] != "Not synchronised" && stratum.is_some_and(|s| s > 0);
+    Some(ClockSyncSnapshot {
+        offset_us: secs(4),
+        jitter_us: secs(6),
+        stratum,
+        reference: Some(fields[1].to_string()).filter(|r| !r.is_empty()),
+        ..unknown("chrony", synced)
+    })
+}
+
+/// `pmc -u -b 0 'GET TIME_STATUS_NP'`; `master_offset` is in ns.
+fn ptp() -> Option<ClockSyncSnapshot> {
+    let output = output(Command::new("pmc").args(["-u", "-b", "0", "GET TIME_STATUS_NP"]))?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let value = |key: &str| {
+        text.lines().find_map(|l| {
+            let mut parts = l.split_whitespace();
+            (parts.next() == Some(key)).then(|| parts.next()).flatten()
+        })
+    };
+    let offset_ns: f64 = value("master_offset")?.parse().ok()?;
+    Some(ClockSyncSnapshot {
+        offset_us: Some(offset_ns / 1000.0),
+        reference: value("gmIdentity").map(str::to_string),
+        ..unknown("ptp", value("gmPresent") == Some("true"))
+    })
+}
+
+/// `adjtimex(2)` without modes: only reads the kernel's NTP state.
+#[cfg(target_os = "linux")]
+fn kernel() -> Option<ClockSyncSnapshot> {
+    let mut tx: libc::timex = unsafe { std::mem::zeroed() };
+    let state = unsafe { libc::adjtimex(&mut tx) };
+    if state < 0 {
+        return None;
+    }
+    let synced = state != libc::TIME_ERROR && tx.status & libc::STA_UNSYNC == 0;
+    let offset_us =
+        if tx.status & libc::STA_NANO != 0 { tx.offset as f64 / 1000.0 } else { tx.offset as f64 };
+    Some(ClockSyncSnapshot {
+        offset_us: Some(offset_us),
+        est_error_us: Some(tx.esterror as f64),
+        ..unknown("kernel", synced)
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn kernel() -> Option<ClockSyncSnapshot> {
+    None
+}
@@ -31,6 +31,7 @@ pub fn red(s: &str) -> String        { wrap("31",   s) }
 pub fn cyan(s: &str) -> String       { wrap("36",   s) }
 pub fn bold_cyan(s: &str) -> String  { wrap("1;36", s) }
 pub fn bold_green(s: &str) -> String { wrap("1;32", s) }
+pub fn bold_red(s: &str) -> String   { wrap("1;31", s) }
 
 /// Count visible characters in a string, ignoring ANSI escape sequences.
 pub fn visible_len(s: &str) -> usize {
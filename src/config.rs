@@ -2,6 +2,7 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 
 /// Top-level probe configuration.
@@ -15,12 +16,107 @@ pub struct ProbeConfig {
     /// RPC-tier sources (rpc, geyser, jito-grpc) are always exempt.
     #[serde(default)]
     pub filter_programs: Vec<String>,
+    /// Only count transactions whose first signature starts with this many
+    /// zero bits (a deterministic 1-in-2^N sample, at most 16) in dedup and
+    /// lead-time statistics. 0 (the default) counts every transaction.
+    #[serde(default)]
+    pub sample_tx_prefix_bits: u8,
+    /// Dedup transactions on their first signature alone, ignoring the slot
+    /// they were delivered in. Off by default: a copy in another slot than
+    /// the first, such as a resubmission after an abandoned fork, races on
+    /// its own and is counted in `txs_slot_mismatch`.
+    #[serde(default)]
+    pub dedup_signature_only: bool,
+    /// Source whose copy of a transaction is forwarded downstream (tx sink,
+    /// outputs) when it arrives within `tie_window_us` of the first copy.
+    /// Win rates and lead times still count the true first arrival.
+    #[serde(default)]
+    pub preferred_source: Option<String>,
+    /// Receive-time difference that still counts as a tie for
+    /// `preferred_source`, in µs. First copies from other sources are held
+    /// this long before they are forwarded.
+    #[serde(default = "ProbeConfig::default_tie_window_us")]
+    pub tie_window_us: u64,
+    /// What shred decoders expire slots against: `local` (the default), each
+    /// decoder's own highest slot; `sources`, the highest slot across the
+    /// shred-tier feeds; `baseline`, the slot the `rpc` source last
+    /// confirmed.
+    #[serde(default)]
+    pub slot_expiry: Option<String>,
+    /// How far ahead of confirmation a transaction must arrive to still be
+    /// useful, in ms. When set, monitor, status and bench show the share of
+    /// each feed's transactions that beat RPC by at least this much in place
+    /// of BEAT%.
+    #[serde(default)]
+    pub latency_budget_ms: Option<u64>,
     /// Raw shred capture configuration. Omit to disable capture.
     #[serde(default)]
     pub capture: Option<CaptureConfig>,
     /// Prometheus metrics HTTP endpoint. Omit or set enabled=false to disable.
     #[serde(default)]
     pub metrics: MetricsConfig,
+    /// Push the Prometheus metric set to a remote-write endpoint, for hosts
+    /// that cannot be scraped. Omit to disable.
+    #[serde(default)]
+    pub remote_write: Option<RemoteWriteConfig>,
+    /// Browser dashboard served by `shredtop run`. Omit or set enabled=false to disable.
+    #[serde(default)]
+    pub web: WebConfig,
+    /// Rotation of the `shredtop run` metrics log. Omit to let it grow unbounded.
+    #[serde(default)]
+    pub log: LogConfig,
+    /// Thresholds for `shredtop status --check`.
+    #[serde(default)]
+    pub health: HealthConfig,
+    /// Resource settings of the unit `shredtop service start` installs.
+    #[serde(default)]
+    pub service: ServiceConfig,
+    /// Restarts of sources whose threads stopped.
+    #[serde(default)]
+    pub restart: RestartConfig,
+    /// Clock discipline status recorded with every snapshot.
+    #[serde(default)]
+    pub clock_sync: ClockSyncConfig,
+    /// Per-epoch latency summaries written by `shredtop run`. Omit to disable.
+    #[serde(default)]
+    pub epoch_report: Option<EpochReportConfig>,
+    /// Stream decoded transactions to a unix socket in Solana wire format.
+    /// Omit to disable.
+    #[serde(default)]
+    pub tx_sink: Option<TxSinkConfig>,
+    /// `[[outputs]]` tables, one sink each, built by `type` (see
+    /// `shred_ingest::output`). Empty for none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub outputs: Vec<toml::Table>,
+    /// Dump the shred indices and FEC sets of badly covered slots. Omit to
+    /// disable.
+    #[serde(default)]
+    pub forensics: Option<ForensicsConfig>,
+    /// Request missing shreds of near-complete slots from repair peers.
+    /// Omit to disable.
+    #[serde(default)]
+    pub repair: Option<RepairConfig>,
+    /// Check sampled winning transactions against their on-chain block time.
+    /// Omit to disable.
+    #[serde(default)]
+    pub confirmation_audit: Option<ConfirmationAuditConfig>,
+    /// Probe the round-trip time to the DZ ingress and the rpc and geyser
+    /// endpoints. Omit to disable.
+    #[serde(default)]
+    pub rtt: Option<RttConfig>,
+    /// Process-wide resource limits. Omit for none.
+    #[serde(default)]
+    pub limits: Option<LimitsConfig>,
+    /// Source pairs to leave out of the race tables, or to annotate there.
+    #[serde(default)]
+    pub race_pairs: Vec<RacePairConfig>,
+    /// Record the path of sampled transactions through the probe. Omit to
+    /// disable.
+    #[serde(default)]
+    pub trace: Option<TraceConfig>,
+    /// Check shred signatures against the leader schedule. Omit to disable.
+    #[serde(default)]
+    pub shred_verify: Option<ShredVerifyConfig>,
 }
 
 /// Configuration for the optional Prometheus metrics HTTP endpoint.
@@ -44,6 +140,390 @@ impl Default for MetricsConfig {
     }
 }
 
+/// Prometheus remote-write push. Sends the same series as `/metrics` every
+/// `interval_secs`, snappy-compressed protobuf over HTTP(S).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RemoteWriteConfig {
+    /// Endpoint, e.g. `https://prometheus-prod.grafana.net/api/prom/push`.
+    pub url: String,
+    #[serde(default = "RemoteWriteConfig::default_interval_secs")]
+    pub interval_secs: u64,
+    /// Optional auth header: `{ scheme = "basic", token = "user:password" }`,
+    /// `scheme = "bearer"`, or `scheme = "header"` with a `header` name.
+    #[serde(default)]
+    pub auth: Option<AuthConfig>,
+    /// Labels added to every series, e.g. `{ instance = "fra-probe-1" }`.
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl RemoteWriteConfig {
+    fn default_interval_secs() -> u64 { 15 }
+}
+
+/// Configuration for the optional web dashboard. When enabled, `shredtop run`
+/// serves it at `http://0.0.0.0:<port>/`. Disabled by default.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "WebConfig::default_port")]
+    pub port: u16,
+}
+
+impl WebConfig {
+    fn default_port() -> u16 { 8080 }
+}
+
+impl Default for WebConfig {
+    fn default() -> Self {
+        Self { enabled: false, port: Self::default_port() }
+    }
+}
+
+/// Rotation of the metrics JSONL log written by `shredtop run`. The live file
+/// is renamed to `<log>.1` (`<log>.1.gz` with `gzip`), older files shift up
+/// one, and anything beyond `keep` is deleted. Both limits 0 disables rotation.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LogConfig {
+    /// Rotate once the live log reaches this many megabytes. 0 = no size limit.
+    #[serde(default)]
+    pub rotate_mb: u64,
+    /// Rotate once the live log is this many hours old. 0 = no age limit.
+    #[serde(default)]
+    pub rotate_hours: u64,
+    /// Number of rotated files to keep.
+    #[serde(default = "LogConfig::default_keep")]
+    pub keep: usize,
+    /// Compress rotated files with gzip.
+    #[serde(default)]
+    pub gzip: bool,
+}
+
+impl LogConfig {
+    fn default_keep() -> usize { 5 }
+
+    pub fn enabled(&self) -> bool {
+        self.rotate_mb > 0 || self.rotate_hours > 0
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self { rotate_mb: 0, rotate_hours: 0, keep: Self::default_keep(), gzip: false }
+    }
+}
+
+/// systemd unit settings for `shredtop service start`. Every field is
+/// optional; with none set the unit runs as root with systemd's defaults.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ServiceConfig {
+    /// `CPUAffinity=`: CPUs the whole process may run on, e.g. "2-5" or "2 3".
+    #[serde(default)]
+    pub cpu_affinity: Option<String>,
+    /// `Nice=`, from -20 (highest priority) to 19.
+    #[serde(default)]
+    pub nice: Option<i32>,
+    /// `IOSchedulingClass=`: "realtime", "best-effort" or "idle".
+    #[serde(default)]
+    pub io_scheduling_class: Option<String>,
+    /// `LimitMEMLOCK=`, e.g. "infinity" or "512M". Locked memory for
+    /// kernel-bypass receive paths.
+    #[serde(default)]
+    pub limit_memlock: Option<String>,
+    /// Run as this user instead of root, with `CAP_NET_RAW` and
+    /// `CAP_NET_ADMIN` as ambient capabilities.
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+/// Thresholds used by `shredtop status --check` to classify the latest metrics
+/// snapshot. Checks run in order and the first failure sets the exit code.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HealthConfig {
+    /// The newest log entry may be at most this many seconds old. 0 = no limit.
+    #[serde(default = "HealthConfig::default_max_log_age_secs")]
+    pub max_log_age_secs: u64,
+    /// Every active shred-tier source must deliver more shreds/s than this,
+    /// so the default only fails a feed that has gone silent.
+    #[serde(default)]
+    pub min_shreds_per_sec: f64,
+    /// Every active shred-tier source must reach this coverage %. 0 = off.
+    #[serde(default)]
+    pub min_coverage_pct: f64,
+    /// Fail when an active baseline source decoded no transactions in the
+    /// last interval.
+    #[serde(default = "HealthConfig::default_check_baseline")]
+    pub check_baseline: bool,
+    /// `shredtop run` raises an alert once one of the checks above has
+    /// failed for a source this long (see `alerts`).
+    #[serde(default = "HealthConfig::default_alert_after_secs")]
+    pub alert_after_secs: u64,
+}
+
+impl HealthConfig {
+    fn default_max_log_age_secs() -> u64 { 60 }
+    fn default_check_baseline() -> bool { true }
+    fn default_alert_after_secs() -> u64 { 60 }
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            max_log_age_secs: Self::default_max_log_age_secs(),
+            min_shreds_per_sec: 0.0,
+            min_coverage_pct: 0.0,
+            check_baseline: Self::default_check_baseline(),
+            alert_after_secs: Self::default_alert_after_secs(),
+        }
+    }
+}
+
+/// Where `shredtop run` reads the clock discipline status from, and when a
+/// snapshot's race numbers stop being trusted (see `clock_sync`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClockSyncConfig {
+    /// `"auto"` (chrony, else ptp4l, else the kernel), `"chrony"`, `"ptp"`,
+    /// `"kernel"`, or `"off"`.
+    #[serde(default = "ClockSyncConfig::default_source")]
+    pub source: String,
+    /// Largest offset from the reference, in µs, that is still trusted.
+    #[serde(default = "ClockSyncConfig::default_max_offset_us")]
+    pub max_offset_us: u64,
+    /// Largest move of the wall clock against the monotonic clock between
+    /// two snapshots, in µs, that is still trusted.
+    #[serde(default = "ClockSyncConfig::default_max_step_us")]
+    pub max_step_us: u64,
+}
+
+impl ClockSyncConfig {
+    fn default_source() -> String { "auto".into() }
+    fn default_max_offset_us() -> u64 { 1000 }
+    fn default_max_step_us() -> u64 { 1000 }
+}
+
+impl Default for ClockSyncConfig {
+    fn default() -> Self {
+        Self {
+            source: Self::default_source(),
+            max_offset_us: Self::default_max_offset_us(),
+            max_step_us: Self::default_max_step_us(),
+        }
+    }
+}
+
+/// Per-epoch summary files: race, lead-time and slot outcome statistics rolled
+/// up per leader epoch and per leader, rewritten as `epoch-<N>.json` every
+/// interval.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EpochReportConfig {
+    /// Directory the summary files are written to.
+    #[serde(default = "EpochReportConfig::default_output_dir")]
+    pub output_dir: String,
+    /// JSON-RPC endpoint for epoch boundaries and the leader schedule.
+    /// Defaults to the `url` of the first `rpc` source.
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+}
+
+impl EpochReportConfig {
+    pub fn default_output_dir() -> String { "/var/log/shredtop-epochs".into() }
+}
+
+/// Slot forensics: every slot a shred source retires dropped or partial with
+/// coverage under `min_coverage_pct` is appended to `output` as one JSON line.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ForensicsConfig {
+    #[serde(default = "ForensicsConfig::default_output")]
+    pub output: String,
+    #[serde(default = "ForensicsConfig::default_min_coverage_pct")]
+    pub min_coverage_pct: f64,
+}
+
+impl ForensicsConfig {
+    pub fn default_output() -> String { "/var/log/shredtop-forensics.jsonl".into() }
+    fn default_min_coverage_pct() -> f64 { 80.0 }
+}
+
+/// Shred repair: a slot whose last shred has arrived, that lacks at most
+/// `max_missing` data shreds and that has not progressed for `stuck_ms` gets
+/// its missing shreds requested from `peers`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RepairConfig {
+    /// Serve-repair endpoints as `<identity pubkey>@<host>:<port>`.
+    pub peers: Vec<String>,
+    /// Keypair file that signs the requests. Peers may only serve
+    /// identities they know, such as staked nodes.
+    pub keypair: String,
+    /// Local UDP port for requests and responses; 0 picks any.
+    #[serde(default)]
+    pub port: u16,
+    #[serde(default = "RepairConfig::default_stuck_ms")]
+    pub stuck_ms: u64,
+    #[serde(default = "RepairConfig::default_max_missing")]
+    pub max_missing: u32,
+    /// Request rounds per slot before giving up on it.
+    #[serde(default = "RepairConfig::default_max_attempts")]
+    pub max_attempts: u8,
+}
+
+impl RepairConfig {
+    fn default_stuck_ms() -> u64 { 100 }
+    fn default_max_missing() -> u32 { 32 }
+    fn default_max_attempts() -> u8 { 3 }
+}
+
+/// Confirmation audit: `samples_per_min` first-copy transactions are looked up
+/// with `getTransaction` after `delay_secs`, and their shred arrival compared
+/// with the block's `blockTime`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConfirmationAuditConfig {
+    #[serde(default = "ConfirmationAuditConfig::default_samples_per_min")]
+    pub samples_per_min: u32,
+    /// Wait before the lookup, so the block is confirmed and indexed.
+    #[serde(default = "ConfirmationAuditConfig::default_delay_secs")]
+    pub delay_secs: u64,
+    /// JSON-RPC endpoint for the lookups. Defaults to the `url` of the first
+    /// `rpc` source.
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+}
+
+impl ConfirmationAuditConfig {
+    fn default_samples_per_min() -> u32 { 10 }
+    fn default_delay_secs() -> u64 { 30 }
+}
+
+/// RTT probes: every `interval_secs`, the DZ ingress and the hosts of the
+/// `rpc` and `geyser` sources are pinged, falling back to a TCP connect.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RttConfig {
+    #[serde(default = "RttConfig::default_interval_secs")]
+    pub interval_secs: u64,
+    /// Wait for each reply or connect.
+    #[serde(default = "RttConfig::default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Host or IP of the DoubleZero ingress. Defaults to the remote end of
+    /// the tunnel on the first shred source's `interface`.
+    #[serde(default)]
+    pub dz_ingress: Option<String>,
+}
+
+impl RttConfig {
+    fn default_interval_secs() -> u64 { 60 }
+    fn default_timeout_ms() -> u64 { 1000 }
+}
+
+/// When a source's receive or decode threads stop, `shredtop run` marks it
+/// degraded and starts it again after `backoff_secs`, doubled per restart up
+/// to `max_backoff_secs`, at most `max_restarts` times (0 = never).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RestartConfig {
+    #[serde(default = "RestartConfig::default_max_restarts")]
+    pub max_restarts: u32,
+    #[serde(default = "RestartConfig::default_backoff_secs")]
+    pub backoff_secs: u64,
+    #[serde(default = "RestartConfig::default_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+}
+
+impl RestartConfig {
+    fn default_max_restarts() -> u32 { 5 }
+    fn default_backoff_secs() -> u64 { 1 }
+    fn default_max_backoff_secs() -> u64 { 60 }
+
+    pub fn policy(&self) -> shred_ingest::RestartPolicy {
+        shred_ingest::RestartPolicy {
+            max_restarts: self.max_restarts,
+            backoff: std::time::Duration::from_secs(self.backoff_secs),
+            max_backoff: std::time::Duration::from_secs(self.max_backoff_secs),
+        }
+    }
+}
+
+impl Default for RestartConfig {
+    fn default() -> Self {
+        Self {
+            max_restarts: Self::default_max_restarts(),
+            backoff_secs: Self::default_backoff_secs(),
+            max_backoff_secs: Self::default_max_backoff_secs(),
+        }
+    }
+}
+
+/// Limits on the probe's own resource use.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LimitsConfig {
+    /// Budget for decoder slot state, FEC sets, the dedup map and the shred
+    /// race window, in MB. While it is exceeded each sheds its oldest state
+    /// and counts what it shed.
+    pub max_memory_mb: u64,
+}
+
+/// One `[[race_pairs]]` entry. Races between two feeds on the same host,
+/// such as a local proxy republishing a multicast feed, measure a loopback
+/// hop rather than the network.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RacePairConfig {
+    /// The two source names, in either order.
+    pub sources: Vec<String>,
+    /// Leave the pair out of the shred, slot and availability race tables.
+    #[serde(default)]
+    pub exclude: bool,
+    /// Shown under the pair in the race tables.
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Transaction tracing: every copy of one signature in `sample_every` is
+/// appended to `output` as JSON lines, one per copy with its timestamps at
+/// each hop. Read back with `shredtop trace show <signature>`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TraceConfig {
+    #[serde(default = "TraceConfig::default_output")]
+    pub output: String,
+    #[serde(default = "TraceConfig::default_sample_every")]
+    pub sample_every: u64,
+}
+
+impl TraceConfig {
+    pub fn default_output() -> String { "/var/log/shredtop-trace.jsonl".into() }
+    fn default_sample_every() -> u64 { 10_000 }
+}
+
+/// Leader signature checks on shred-tier sources. The leader schedule is read
+/// from `rpc_url`, or the `url` of the first `rpc` source.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ShredVerifyConfig {
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+    /// Drop shreds whose signature does not match the slot leader, instead of
+    /// only counting them.
+    #[serde(default)]
+    pub drop_invalid: bool,
+}
+
+/// Unix socket that `shredtop run` streams decoded transactions to, each as a
+/// `u32` little-endian length followed by the bincode-serialized
+/// `VersionedTransaction`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TxSinkConfig {
+    #[serde(default = "TxSinkConfig::default_socket")]
+    pub socket: String,
+    /// Also stream duplicate copies, marked with the winning source and how
+    /// far behind it they arrived. Switches every frame to the marked format.
+    #[serde(default)]
+    pub duplicates: bool,
+    /// Cap on duplicates streamed per second; the rest are dropped.
+    #[serde(default = "TxSinkConfig::default_max_duplicates_per_sec")]
+    pub max_duplicates_per_sec: u32,
+}
+
+impl TxSinkConfig {
+    pub fn default_socket() -> String { "/run/shredtop/txs.sock".into() }
+    fn default_max_duplicates_per_sec() -> u32 { 20_000 }
+}
+
 /// Configuration for the always-on ring-buffer capture subsystem.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CaptureConfig {
@@ -65,18 +545,87 @@ pub struct CaptureConfig {
     /// Rotate to a new file after this many megabytes.
     #[serde(default = "CaptureConfig::default_rotate_mb")]
     pub rotate_mb: u64,
+    /// Parsed header fields added to each JSONL record, in this order:
+    /// any of "fec_set_index", "variant", "flags", "size", "shred_version".
+    #[serde(default)]
+    pub fields: Vec<String>,
+    /// Sync written data to disk at most this often and drop it from the
+    /// page cache; 0 leaves writeback to the kernel.
+    #[serde(default = "CaptureConfig::default_fsync_ms")]
+    pub fsync_ms: u64,
+    /// Name archived files by the slots they hold
+    /// (`shreds.<first>-<last>.<ext>`) and write a `.idx` sidecar with the
+    /// offset of each slot's first record.
+    #[serde(default)]
+    pub slot_names: bool,
+    /// Write each feed into a subdirectory of its own,
+    /// `<output_dir>/<feed>/`, with rings of its own: a busy feed no longer
+    /// rotates a quiet feed's history out of a shared ring. Every feed gets
+    /// the full `max_size_mb` of each format unless `feed_max_size_mb` says
+    /// otherwise.
+    #[serde(default)]
+    pub per_feed: bool,
+    /// With `per_feed`, the disk space (MB) of the named feeds' rings, in
+    /// place of `max_size_mb`, for each format.
+    #[serde(default)]
+    pub feed_max_size_mb: BTreeMap<String, u64>,
+    /// Keep packets in memory and write them only around anomalies, instead
+    /// of capturing continuously. Omit for continuous capture.
+    #[serde(default)]
+    pub trigger: Option<CaptureTriggerConfig>,
+}
+
+/// Triggered capture (`[capture.trigger]`). Each source's packets of the last
+/// `pre_secs` are held in memory; when a source shows one of the configured
+/// anomalies they are written out, and so is everything that arrives in the
+/// next `post_secs`. At least one condition must be set.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CaptureTriggerConfig {
+    #[serde(default = "CaptureTriggerConfig::default_pre_secs")]
+    pub pre_secs: u64,
+    #[serde(default = "CaptureTriggerConfig::default_post_secs")]
+    pub post_secs: u64,
+    /// Memory for each source's pre-trigger packets, MB. The oldest are
+    /// dropped first once it is full.
+    #[serde(default = "CaptureTriggerConfig::default_buffer_mb")]
+    pub buffer_mb: u64,
+    /// Fire when a shred source's data-shred coverage over 10 s falls below
+    /// this %.
+    #[serde(default)]
+    pub coverage_below_pct: Option<f64>,
+    /// Fire when a shred source that was receiving gets nothing for this
+    /// many seconds.
+    #[serde(default)]
+    pub silent_secs: Option<u64>,
+    /// Fire when a source's mean lead time over one second falls below this
+    /// many ms, e.g. -50.
+    #[serde(default)]
+    pub lead_below_ms: Option<f64>,
+}
+
+impl CaptureTriggerConfig {
+    fn default_pre_secs() -> u64 { 10 }
+    fn default_post_secs() -> u64 { 10 }
+    fn default_buffer_mb() -> u64 { 256 }
 }
 
 impl CaptureConfig {
     fn default_enabled() -> bool { true }
     fn default_formats() -> Vec<String> { vec!["pcap".into()] }
-    fn default_output_dir() -> String { "/var/log/shredtop-capture".into() }
+    pub fn default_output_dir() -> String { "/var/log/shredtop-capture".into() }
     fn default_rotate_mb() -> u64 { 500 }
+    fn default_fsync_ms() -> u64 { 1000 }
 
-    /// Number of ring files to keep for format at `idx`.
-    /// Derived from `max_size_mb[idx] / rotate_mb`, minimum 2.
-    pub fn ring_files_for(&self, idx: usize) -> usize {
-        let max = self.max_size_mb.get(idx).copied().unwrap_or(10_000);
+    /// Number of ring files to keep for format at `idx`, in `feed`'s
+    /// directory with `per_feed` (`None` for the shared ring).
+    /// Derived from `feed_max_size_mb[feed]`, else `max_size_mb[idx]`,
+    /// divided by `rotate_mb`; minimum 2.
+    pub fn ring_files_for(&self, idx: usize, feed: Option<&str>) -> usize {
+        let max = feed
+            .and_then(|f| self.feed_max_size_mb.get(f))
+            .or(self.max_size_mb.get(idx))
+            .copied()
+            .unwrap_or(10_000);
         ((max / self.rotate_mb) as usize).max(2)
     }
 }
@@ -89,6 +638,12 @@ impl Default for CaptureConfig {
             max_size_mb: vec![10_000],
             output_dir: Self::default_output_dir(),
             rotate_mb: Self::default_rotate_mb(),
+            fields: Vec::new(),
+            fsync_ms: Self::default_fsync_ms(),
+            slot_names: false,
+            per_feed: false,
+            feed_max_size_mb: BTreeMap::new(),
+            trigger: None,
         }
     }
 }
@@ -98,19 +653,29 @@ impl Default for CaptureConfig {
 pub struct SourceEntry {
     /// Human-readable name shown in the dashboard (e.g. "bebop", "jito-shredstream", "rpc")
     pub name: String,
-    /// Source type: "shred", "turbine", "rpc", "geyser", or "jito-grpc"
+    /// Source type: "shred", "turbine", "unicast", "rpc", "geyser", "jito-grpc",
+    /// "jito-udp", or "chaos"
     #[serde(rename = "type")]
     pub source_type: String,
     /// Multicast group IP (shred only)
     pub multicast_addr: Option<String>,
     /// UDP port (shred only; bebop=7733, jito-shredstream=20001)
     pub port: Option<u16>,
+    /// First and last UDP port, for relays that stripe shreds across a range,
+    /// e.g. `[20001, 20004]` (shred only, instead of `port`). One socket per
+    /// port, all feeding the source's one decoder and metrics.
+    #[serde(default)]
+    pub port_range: Option<[u16; 2]>,
     /// Network interface for multicast (shred only, e.g. "doublezero1")
     pub interface: Option<String>,
-    /// RPC endpoint URL (rpc or geyser)
+    /// Endpoint URL (rpc, geyser, jito-grpc; the block engine for jito-udp)
     pub url: Option<String>,
     /// Authentication token sent as `x-token` header (geyser only)
     pub x_token: Option<String>,
+    /// Alternative authentication scheme (geyser only) for providers that do not
+    /// use `x-token`. Takes precedence over `x_token` when set.
+    #[serde(default)]
+    pub auth: Option<AuthConfig>,
     /// CPU core to pin receiver thread to (optional)
     pub pin_recv_core: Option<usize>,
     /// CPU core to pin decoder thread to (optional)
@@ -119,59 +684,298 @@ pub struct SourceEntry {
     /// Useful during forks or network upgrades. Omit to accept all versions.
     #[serde(default)]
     pub shred_version: Option<u16>,
+    /// Number of receive sockets bound to the port (unicast only). Values > 1
+    /// use an SO_REUSEPORT group that fans out by shred index, one recv thread
+    /// per socket pinned to `pin_recv_core + i`. Default 1.
+    #[serde(default)]
+    pub recv_sockets: Option<usize>,
+    /// Sources sharing a failover group act as primary/standby: the first one
+    /// listed is active, the rest only take over when it goes silent.
+    #[serde(default)]
+    pub failover_group: Option<String>,
+    /// Seconds of silence before the active member is replaced (default 5).
+    /// Read from the group's first entry.
+    #[serde(default)]
+    pub failover_after_secs: Option<u64>,
+    /// Fault injection for `type = "chaos"` sources (debug builds only).
+    #[serde(default)]
+    pub chaos: Option<ChaosEntry>,
+    /// `"full"` (default) or `"headers-only"`: track shred coverage, slot
+    /// completion and races without deserializing transactions (shred,
+    /// turbine and unicast sources only).
+    #[serde(default)]
+    pub decode: Option<String>,
+    /// Leave slots more than this many slots behind the highest slot any
+    /// shred feed has delivered undecoded. Receive counters and races still
+    /// count them (shred, turbine, unicast and jito-udp sources only).
+    #[serde(default)]
+    pub decode_max_lag_slots: Option<u64>,
+    /// Free-form tags, e.g. `labels = { region = "fra", provider = "dz" }`.
+    /// Added to this source's Prometheus series and carried in the JSONL log
+    /// and bench reports so dashboards can group sources by them.
+    #[serde(default)]
+    pub labels: Option<BTreeMap<String, String>>,
+    /// Solana keypair file approved for ShredStream (jito-udp only)
+    #[serde(default)]
+    pub keypair: Option<String>,
+    /// Public IP that Jito should send shreds to (jito-udp only)
+    #[serde(default)]
+    pub public_ip: Option<String>,
+    /// Block engine regions to receive shreds from (jito-udp only)
+    #[serde(default)]
+    pub regions: Option<Vec<String>>,
+    /// Websocket endpoint (rpc only, e.g. "ws://127.0.0.1:8900"). When set,
+    /// blocks are received via `blockSubscribe` instead of polled, with
+    /// polling as the fallback.
+    #[serde(default)]
+    pub ws_url: Option<String>,
+    /// Most concurrent `getBlock` requests when fetching missed slots
+    /// (rpc only, default 4). Blocks are still emitted in slot order.
+    #[serde(default)]
+    pub rpc_parallelism: Option<usize>,
+    /// How much later than arrival this source's receive timestamps are
+    /// taken, in microseconds; subtracted before lead times are computed.
+    /// Useful for geyser and jito-grpc, which are stamped after gRPC decode
+    /// while UDP sources use kernel timestamps.
+    #[serde(default)]
+    pub timestamp_offset_us: Option<i64>,
+    /// Measure the gRPC decode overhead on the first messages after start
+    /// and add it to `timestamp_offset_us` (geyser and jito-grpc only).
+    #[serde(default)]
+    pub calibrate_timestamps: Option<bool>,
+    /// Connection settings for geyser, jito-grpc and jito-udp sources.
+    #[serde(default)]
+    pub grpc: Option<GrpcEntry>,
+}
+
+/// gRPC connection settings, e.g.
+/// `grpc = { keepalive_secs = 15, tls = { ca_cert = "/etc/ssl/provider.pem" } }`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GrpcEntry {
+    /// Time allowed to establish the connection (default 10000).
+    #[serde(default)]
+    pub connect_timeout_ms: Option<u64>,
+    /// Time allowed for a call's response to start (default none). Does not
+    /// limit how long a subscription stream runs.
+    #[serde(default)]
+    pub request_timeout_ms: Option<u64>,
+    /// HTTP/2 keepalive ping interval (default 30); 0 sends no pings.
+    #[serde(default)]
+    pub keepalive_secs: Option<u64>,
+    /// Seconds a ping may go unanswered before reconnecting (default 10).
+    #[serde(default)]
+    pub keepalive_timeout_secs: Option<u64>,
+    /// TLS options for `https://` URLs, which otherwise use the system roots.
+    #[serde(default)]
+    pub tls: Option<TlsEntry>,
+    /// Response compression to ask for: "gzip" or "zstd". Default none.
+    #[serde(default)]
+    pub compression: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TlsEntry {
+    /// PEM file with the CA certificate(s) to trust instead of the system roots.
+    #[serde(default)]
+    pub ca_cert: Option<String>,
+    /// Name to verify the server certificate against, if not the URL's host.
+    #[serde(default)]
+    pub domain: Option<String>,
+    /// Accept any server certificate. For testing only.
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+}
+
+/// `type = "chaos"` wraps a shred/turbine/unicast source and degrades it
+/// between receiver and decoder, e.g.
+/// `chaos = { wraps = "shred", drop_pct = 5.0, reorder_window = 16 }`.
+/// The wrapped source is configured by the entry's other fields as usual.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ChaosEntry {
+    /// Type of the wrapped source: "shred", "turbine", or "unicast".
+    pub wraps: String,
+    /// Percentage of shreds dropped (0–100).
+    #[serde(default)]
+    pub drop_pct: f64,
+    /// Reorder buffer size in shreds.
+    #[serde(default)]
+    pub reorder_window: usize,
+    /// Latency added to every shred, in microseconds.
+    #[serde(default)]
+    pub delay_us: u64,
+    /// Extra uniform random latency, up to this many microseconds.
+    #[serde(default)]
+    pub jitter_us: u64,
+    /// RNG seed; omit for a different run each time.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// Per-source authentication for geyser endpoints, e.g.
+/// `auth = { scheme = "bearer", token = "..." }`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AuthConfig {
+    /// "x-token", "bearer", "basic", or "header"
+    pub scheme: String,
+    /// Token value; for "basic" this is `user:password`.
+    pub token: String,
+    /// Header name, required when `scheme = "header"` (e.g. "x-api-key").
+    #[serde(default)]
+    pub header: Option<String>,
 }
 
 impl ProbeConfig {
-    pub fn load(path: &Path) -> Result<Self> {
+    fn default_tie_window_us() -> u64 { 50 }
+
+    /// Load `path`, with the settings of `profile` if given (see
+    /// [`crate::profile`]).
+    pub fn load(path: &Path, profile: Option<&str>) -> Result<Self> {
         let text = std::fs::read_to_string(path)
             .with_context(|| format!("failed to read config file: {}", path.display()))?;
-        let cfg: Self = toml::from_str(&text)
+        let table: toml::Table = toml::from_str(&text)
             .with_context(|| format!("failed to parse config file: {}", path.display()))?;
+        let table = crate::profile::select(table, profile)?;
+        let cfg: Self = table
+            .try_into()
+            .with_context(|| format!("failed to parse config file: {}", path.display()))?;
+        anyhow::ensure!(
+            cfg.sample_tx_prefix_bits <= 16,
+            "sample_tx_prefix_bits must be at most 16 (got {})",
+            cfg.sample_tx_prefix_bits
+        );
         Ok(cfg)
     }
 
+    /// `slot_expiry`, checked against the sources: `baseline` needs an `rpc`
+    /// source to report confirmed slots.
+    pub fn slot_expiry(&self) -> Result<shred_ingest::SlotExpiry> {
+        use shred_ingest::SlotExpiry;
+        let Some(ref name) = self.slot_expiry else { return Ok(SlotExpiry::Local) };
+        let expiry = SlotExpiry::from_name(name).context("slot_expiry")?;
+        anyhow::ensure!(
+            expiry != SlotExpiry::Baseline || self.sources.iter().any(|s| s.source_type == "rpc"),
+            "slot_expiry = \"baseline\" needs an rpc source"
+        );
+        Ok(expiry)
+    }
+
     /// Returns a default config that matches the standard DoubleZero + RPC setup.
     pub fn default_example() -> Self {
         Self {
             filter_programs: Vec::new(),
+            sample_tx_prefix_bits: 0,
+            dedup_signature_only: false,
+            preferred_source: None,
+            tie_window_us: Self::default_tie_window_us(),
+            slot_expiry: None,
+            latency_budget_ms: None,
             capture: None,
             metrics: MetricsConfig::default(),
+            remote_write: None,
+            web: WebConfig::default(),
+            log: LogConfig::default(),
+            health: HealthConfig::default(),
+            restart: RestartConfig::default(),
+            clock_sync: ClockSyncConfig::default(),
+            service: ServiceConfig::default(),
+            epoch_report: None,
+            forensics: None,
+            repair: None,
+            confirmation_audit: None,
+            rtt: None,
+            limits: None,
+            race_pairs: Vec::new(),
+            trace: None,
+            shred_verify: None,
+            tx_sink: None,
+            outputs: Vec::new(),
             sources: vec![
                 SourceEntry {
                     name: "bebop".into(),
                     source_type: "shred".into(),
                     multicast_addr: Some("233.84.178.1".into()),
                     port: Some(7733),
+                    port_range: None,
                     interface: Some("doublezero1".into()),
                     url: None,
                     x_token: None,
                     pin_recv_core: None,
                     pin_decode_core: None,
                     shred_version: None,
+                    recv_sockets: None,
+                    failover_group: None,
+                    failover_after_secs: None,
+                    chaos: None,
+                    decode: None,
+                    decode_max_lag_slots: None,
+                    labels: None,
+                    keypair: None,
+                    public_ip: None,
+                    regions: None,
+                    ws_url: None,
+                    rpc_parallelism: None,
+                    timestamp_offset_us: None,
+                    calibrate_timestamps: None,
+                    grpc: None,
+                    auth: None,
                 },
                 SourceEntry {
                     name: "jito-shredstream".into(),
                     source_type: "shred".into(),
                     multicast_addr: Some("233.84.178.2".into()),
                     port: Some(20001),
+                    port_range: None,
                     interface: Some("doublezero1".into()),
                     url: None,
                     x_token: None,
                     pin_recv_core: None,
                     pin_decode_core: None,
                     shred_version: None,
+                    recv_sockets: None,
+                    failover_group: None,
+                    failover_after_secs: None,
+                    chaos: None,
+                    decode: None,
+                    decode_max_lag_slots: None,
+                    labels: None,
+                    keypair: None,
+                    public_ip: None,
+                    regions: None,
+                    ws_url: None,
+                    rpc_parallelism: None,
+                    timestamp_offset_us: None,
+                    calibrate_timestamps: None,
+                    grpc: None,
+                    auth: None,
                 },
                 SourceEntry {
                     name: "rpc".into(),
                     source_type: "rpc".into(),
                     multicast_addr: None,
                     port: None,
+                    port_range: None,
                     interface: None,
                     url: Some("http://127.0.0.1:8899".into()),
                     x_token: None,
                     pin_recv_core: None,
                     pin_decode_core: None,
                     shred_version: None,
+                    recv_sockets: None,
+                    failover_group: None,
+                    failover_after_secs: None,
+                    chaos: None,
+                    decode: None,
+                    decode_max_lag_slots: None,
+                    labels: None,
+                    keypair: None,
+                    public_ip: None,
+                    regions: None,
+                    ws_url: None,
+                    rpc_parallelism: None,
+                    timestamp_offset_us: None,
+                    calibrate_timestamps: None,
+                    grpc: None,
+                    auth: None,
                 },
             ],
         }
@@ -0,0 +1,254 @@
+//! Audit of winning transactions against their on-chain confirmation time.
+//!
+//! Lead times are relative: one feed against another, or against the RPC
+//! baseline. As an absolute check, the auditor takes `samples_per_min` of the
+//! transactions forwarded as first copies, spread evenly over each minute. It
+//! waits `delay_secs` and then asks the baseline RPC for their block with
+//! `getTransaction`. The gap from the shred arrival to the block's
+//! `blockTime` is kept as a distribution, overall and per winning feed.
+//!
+//! `blockTime` is the cluster's stake-weighted estimate of when the block was
+//! produced, in whole seconds, so single samples are only good to a second.
+//! The distribution is what matters. A feed that claims hundreds of ms of
+//! lead should deliver within about a slot of the block time, give or take
+//! clock skew. Samples seconds late point to a clock or pipeline problem.
+
+use anyhow::{Context, Result};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use serde::Serialize;
+use serde_json::{json, Value};
+use shred_ingest::clock::MonotonicNs;
+use shred_ingest::ForwardedTx;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::{ConfirmationAuditConfig, SourceEntry};
+
+/// Deltas kept for the percentiles; older ones are dropped first.
+const KEEP_DELTAS: usize = 4096;
+/// Sampled transactions waiting for their query; more are not taken.
+const MAX_PENDING: usize = 1024;
+
+/// Picks the transactions to audit. Lives on the thread that reads the
+/// forwarded stream.
+pub struct ConfirmationAudit {
+    tx: Sender<Candidate>,
+    /// Earliest receive time of the next transaction to sample.
+    next: MonotonicNs,
+    spacing_ns: u64,
+}
+
+/// Shared results, read by the snapshot loop.
+#[derive(Clone, Default)]
+pub struct AuditStats {
+    inner: Arc<Mutex<Totals>>,
+}
+
+struct Candidate {
+    signature: String,
+    source: Arc<str>,
+    /// Shred arrival, Unix ns.
+    recv_ns: u64,
+}
+
+#[derive(Default)]
+struct Totals {
+    sampled: u64,
+    confirmed: u64,
+    not_found: u64,
+    rpc_errors: u64,
+    /// Block time minus shred arrival, ms, newest last.
+    deltas_ms: VecDeque<i64>,
+    by_source: BTreeMap<Arc<str>, (u64, i64)>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditSnapshot {
+    /// Transactions sampled so far, and how their queries ended.
+    pub sampled: u64,
+    pub confirmed: u64,
+    /// Not returned at `confirmed` commitment: dropped, or not yet landed.
+    pub not_found: u64,
+    pub rpc_errors: u64,
+    /// Block time minus shred arrival over the last [`KEEP_DELTAS`]
+    /// confirmed samples, ms. Negative when the shred arrived after the
+    /// block's timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm_after_arrival_ms: Option<Distribution>,
+    /// Confirmed samples and their mean per feed that delivered them first.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_source: BTreeMap<Arc<str>, SourceAudit>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Distribution {
+    pub mean: f64,
+    pub p50: i64,
+    pub p95: i64,
+    pub p99: i64,
+    pub min: i64,
+    pub max: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SourceAudit {
+    pub confirmed: u64,
+    pub mean_ms: f64,
+}
+
+/// Start the query thread. The RPC endpoint is `rpc_url`, or else the `url`
+/// of the first `rpc` source.
+pub fn spawn(
+    cfg: &ConfirmationAuditConfig,
+    sources: &[SourceEntry],
+) -> Result<(ConfirmationAudit, AuditStats, String)> {
+    anyhow::ensure!(cfg.samples_per_min > 0, "confirmation_audit.samples_per_min must be > 0");
+    let rpc_url = cfg
+        .rpc_url
+        .clone()
+        .or_else(|| sources.iter().find(|s| s.source_type == "rpc").and_then(|s| s.url.clone()))
+        .context("confirmation_audit needs rpc_url, or an `rpc` source with a url")?;
+    let (tx, rx) = crossbeam_channel::bounded::<Candidate>(MAX_PENDING);
+    let stats = AuditStats::default();
+    let thread_stats = stats.clone();
+    let url = rpc_url.clone();
+    let delay = Duration::from_secs(cfg.delay_secs);
+    std::thread::Builder::new()
+        .name("confirm-audit".into())
+        .spawn(move || query_loop(&url, delay, rx, &thread_stats))?;
+    let audit = ConfirmationAudit {
+        tx,
+        next: MonotonicNs::default(),
+        spacing_ns: 60_000_000_000 / cfg.samples_per_min as u64,
+    };
+    Ok((audit, stats, rpc_url))
+}
+
+impl ConfirmationAudit {
+    /// Consider one forwarded transaction; first copies are sampled at the
+    /// configured rate.
+    pub fn offer(&mut self, fwd: &ForwardedTx) {
+        let recv_ns = fwd.decoded.shred_recv_ns;
+        if fwd.duplicate_of.is_some() || recv_ns < self.next {
+            return;
+        }
+        let Some(sig) = fwd.decoded.transaction.signatures.first() else { return };
+        self.next = recv_ns.saturating_add(self.spacing_ns);
+        let _ = self.tx.try_send(Candidate {
+            signature: sig.to_string(),
+            source: fwd.source.clone(),
+            recv_ns: recv_ns.to_realtime().0,
+        });
+    }
+}
+
+impl AuditStats {
+    pub fn snapshot(&self) -> AuditSnapshot {
+        let t = self.inner.lock().unwrap();
+        let mut sorted: Vec<i64> = t.deltas_ms.iter().copied().collect();
+        sorted.sort_unstable();
+        let pct = |p: usize| sorted[(sorted.len() * p / 100).min(sorted.len() - 1)];
+        let confirm_after_arrival_ms = (!sorted.is_empty()).then(|| Distribution {
+            mean: sorted.iter().sum::<i64>() as f64 / sorted.len() as f64,
+            p50: pct(50),
+            p95: pct(95),
+            p99: pct(99),
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+        });
+        AuditSnapshot {
+            sampled: t.sampled,
+            confirmed: t.confirmed,
+            not_found: t.not_found,
+            rpc_errors: t.rpc_errors,
+            confirm_after_arrival_ms,
+            by_source: t
+                .by_source
+                .iter()
+                .map(|(name, &(n, sum))| {
+                    (name.clone(), SourceAudit { confirmed: n, mean_ms: sum as f64 / n as f64 })
+                })
+                .collect(),
+        }
+    }
+}
+
+fn query_loop(url: &str, delay: Duration, rx: Receiver<Candidate>, stats: &AuditStats) {
+    let agent = ureq::AgentBuilder::new().timeout(Duration::from_secs(5)).build();
+    let mut waiting: VecDeque<Candidate> = VecDeque::new();
+    let mut failing = false;
+    loop {
+        match rx.recv_timeout(Duration::from_secs(1)) {
+            Ok(c) => {
+                stats.inner.lock().unwrap().sampled += 1;
+                waiting.push_back(c);
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        let due_ns = now_ns().saturating_sub(delay.as_nanos() as u64);
+        while waiting.front().is_some_and(|c| c.recv_ns <= due_ns) {
+            let c = waiting.pop_front().expect("front checked above");
+            let result = block_time(&agent, url, &c.signature);
+            let mut t = stats.inner.lock().unwrap();
+            match result {
+                Ok(Some(block_time)) => {
+                    let delta = block_time * 1000 - (c.recv_ns / 1_000_000) as i64;
+                    t.confirmed += 1;
+                    if t.deltas_ms.len() == KEEP_DELTAS {
+                        t.deltas_ms.pop_front();
+                    }
+                    t.deltas_ms.push_back(delta);
+                    let s = t.by_source.entry(c.source).or_default();
+                    s.0 += 1;
+                    s.1 += delta;
+                    if failing {
+                        failing = false;
+                        tracing::info!("confirmation_audit: {} reachable again", url);
+                    }
+                }
+                Ok(None) => t.not_found += 1,
+                Err(e) => {
+                    t.rpc_errors += 1;
+                    if !failing {
+                        failing = true;
+                        tracing::warn!("confirmation_audit: getTransaction failed: {:#}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `blockTime` of the block holding `signature`, or None if the transaction
+/// is not known at `confirmed` commitment or its block has no timestamp.
+fn block_time(agent: &ureq::Agent, url: &str, signature: &str) -> Result<Option<i64>> {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "getTransaction",
+        "params": [signature, {
+            "encoding": "json",
+            "commitment": "confirmed",
+            "maxSupportedTransactionVersion": 0,
+        }],
+    });
+    let text = agent
+        .post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&body.to_string())?
+        .into_string()?;
+    let resp: Value = serde_json::from_str(&text)?;
+    if let Some(err) = resp.get("error") {
+        anyhow::bail!("{}", err);
+    }
+    Ok(resp["result"]["blockTime"].as_i64())
+}
+
+fn now_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
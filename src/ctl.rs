@@ -0,0 +1,113 @@
+//! Runtime control socket for `shredtop run` and the `shredtop ctl` client.
+//!
+//! The daemon listens on a unix socket and accepts one line-based command per
+//! connection:
+//!
+//! ```text
+//! pause <source>    stop counting/forwarding a source until resumed
+//! resume <source>   undo a pause
+//! list              one "<source> active|paused" line per source
+//! ```
+//!
+//! Replies are a single line starting with `ok` or `error:` (or the `list`
+//! table). Pausing is runtime-only and is not written to the state file.
+
+use anyhow::{Context, Result};
+use shred_ingest::SourceMetrics;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const DEFAULT_CTL_SOCKET: &str = "/run/shredtop.sock";
+
+/// Bind the control socket and serve commands on a background thread.
+/// A stale socket file from a previous run is removed first.
+pub fn spawn(path: &Path, metrics: Vec<Arc<SourceMetrics>>) -> Result<()> {
+    let _ = std::fs::remove_file(path);
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("failed to bind control socket {}", path.display()))?;
+    // Pausing a source changes what the daemon measures — keep it to root/group.
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o660))?;
+
+    std::thread::Builder::new()
+        .name("ctl".into())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(s) => {
+                        if let Err(e) = serve(s, &metrics) {
+                            tracing::warn!("control socket: {:#}", e);
+                        }
+                    }
+                    Err(e) => tracing::warn!("control socket accept failed: {}", e),
+                }
+            }
+        })?;
+    Ok(())
+}
+
+fn serve(stream: UnixStream, metrics: &[Arc<SourceMetrics>]) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let reply = handle(line.trim(), metrics);
+    (&stream).write_all(reply.as_bytes())?;
+    Ok(())
+}
+
+fn handle(cmd: &str, metrics: &[Arc<SourceMetrics>]) -> String {
+    let mut parts = cmd.split_whitespace();
+    let verb = parts.next().unwrap_or("");
+    let arg = parts.next();
+
+    match (verb, arg) {
+        ("list", None) => metrics
+            .iter()
+            .map(|m| {
+                let state = if m.is_paused() { "paused" } else { "active" };
+                format!("{} {}\n", m.name, state)
+            })
+            .collect(),
+        ("pause" | "resume", Some(name)) => {
+            let Some(m) = metrics.iter().find(|m| &*m.name == name) else {
+                return format!("error: unknown source '{}'\n", name);
+            };
+            let pause = verb == "pause";
+            let was = m.set_paused(pause);
+            let state = if pause { "paused" } else { "resumed" };
+            if was == pause {
+                format!("ok: {} already {}\n", name, if pause { "paused" } else { "active" })
+            } else {
+                tracing::warn!("source '{}' {} via control socket", name, state);
+                format!("ok: {} {}\n", name, state)
+            }
+        }
+        _ => format!("error: unrecognised command '{}'\n", cmd),
+    }
+}
+
+/// Client side of `shredtop ctl`: send one command and print the reply.
+pub fn send(path: &Path, cmd: &str) -> Result<()> {
+    let mut stream = UnixStream::connect(path).with_context(|| {
+        format!(
+            "cannot connect to {} — is the service running? (`shredtop service start`)",
+            path.display()
+        )
+    })?;
+    stream.write_all(format!("{}\n", cmd).as_bytes())?;
+    stream.shutdown(std::net::Shutdown::Write)?;
+
+    let mut reply = String::new();
+    for line in BufReader::new(stream).lines() {
+        reply.push_str(&line?);
+        reply.push('\n');
+    }
+    if let Some(err) = reply.strip_prefix("error: ") {
+        anyhow::bail!("{}", err.trim_end());
+    }
+    print!("{}", reply);
+    Ok(())
+}
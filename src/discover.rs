@@ -5,17 +5,26 @@
 //! installed. On completion, offers to write detected sources back to probe.toml.
 
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::{self, Read, Write};
-use std::net::TcpStream;
+use std::net::{Ipv4Addr, TcpStream, UdpSocket};
 use std::path::Path;
 use std::process::Command;
 use std::time::Duration;
 
 use crate::color;
 use crate::config::{CaptureConfig, ProbeConfig, SourceEntry};
-
-pub fn run(config: &ProbeConfig, config_path: &Path) -> Result<()> {
+use crate::profile::Paths;
+
+/// The sources found, and `[capture]` if set up, replace those of probe.toml,
+/// or of its `[profile.NAME]` with a `profile`; the rest of the file is kept.
+/// The unit of the profile is then restarted.
+pub fn run(
+    config: &ProbeConfig,
+    config_path: &Path,
+    profile: Option<&str>,
+    paths: &Paths,
+) -> Result<()> {
     // -----------------------------------------------------------------------
     // Configured sources
     // -----------------------------------------------------------------------
@@ -180,12 +189,29 @@ pub fn run(config: &ProbeConfig, config_path: &Path) -> Result<()> {
                         source_type: "shred".into(),
                         multicast_addr: Some(g.multicast_ip.clone()),
                         port,
+                        port_range: None,
                         interface: Some(iface),
                         url: None,
                         x_token: None,
                         pin_recv_core: None,
                         pin_decode_core: None,
                         shred_version: None,
+                        recv_sockets: None,
+                        failover_group: None,
+                        failover_after_secs: None,
+                        chaos: None,
+                        decode: None,
+                        decode_max_lag_slots: None,
+                        labels: None,
+                        keypair: None,
+                        public_ip: None,
+                        regions: None,
+                        ws_url: None,
+                        rpc_parallelism: None,
+                        timestamp_offset_us: None,
+                        calibrate_timestamps: None,
+                        grpc: None,
+                        auth: None,
                     });
                 }
 
@@ -278,12 +304,29 @@ pub fn run(config: &ProbeConfig, config_path: &Path) -> Result<()> {
                                 source_type: "rpc".into(),
                                 multicast_addr: None,
                                 port: None,
+                                port_range: None,
                                 interface: None,
                                 url: Some(url),
                                 x_token: None,
                                 pin_recv_core: None,
                                 pin_decode_core: None,
                                 shred_version: None,
+                                recv_sockets: None,
+                                failover_group: None,
+                                failover_after_secs: None,
+                                chaos: None,
+                                decode: None,
+                                decode_max_lag_slots: None,
+                                labels: None,
+                                keypair: None,
+                                public_ip: None,
+                                regions: None,
+                                ws_url: None,
+                                rpc_parallelism: None,
+                                timestamp_offset_us: None,
+                                calibrate_timestamps: None,
+                                grpc: None,
+                                auth: None,
                             });
                         }
                     }
@@ -300,12 +343,29 @@ pub fn run(config: &ProbeConfig, config_path: &Path) -> Result<()> {
                     source_type: "rpc".into(),
                     multicast_addr: None,
                     port: None,
+                    port_range: None,
                     interface: None,
                     url: Some(url),
                     x_token: None,
                     pin_recv_core: None,
                     pin_decode_core: None,
                     shred_version: None,
+                    recv_sockets: None,
+                    failover_group: None,
+                    failover_after_secs: None,
+                    chaos: None,
+                    decode: None,
+                    decode_max_lag_slots: None,
+                    labels: None,
+                    keypair: None,
+                    public_ip: None,
+                    regions: None,
+                    ws_url: None,
+                    rpc_parallelism: None,
+                    timestamp_offset_us: None,
+                    calibrate_timestamps: None,
+                    grpc: None,
+                    auth: None,
                 });
             }
             _ => {
@@ -318,7 +378,7 @@ pub fn run(config: &ProbeConfig, config_path: &Path) -> Result<()> {
     // -----------------------------------------------------------------------
     // Capture configuration
     // -----------------------------------------------------------------------
-    let capture_cfg = configure_capture();
+    let capture_cfg = configure_capture(profile);
 
     // -----------------------------------------------------------------------
     // Preserve existing baseline sources
@@ -335,22 +395,23 @@ pub fn run(config: &ProbeConfig, config_path: &Path) -> Result<()> {
     // Write probe.toml
     // -----------------------------------------------------------------------
     if !sources_to_write.is_empty() {
-        let cfg = ProbeConfig {
-            sources: sources_to_write,
-            filter_programs: Vec::new(),
-            capture: capture_cfg,
-            metrics: crate::config::MetricsConfig::default(),
-        };
-        let toml_str = toml::to_string_pretty(&cfg)?;
-        std::fs::write(config_path, toml_str)?;
+        crate::profile::write_sources(
+            config_path,
+            profile,
+            &sources_to_write,
+            capture_cfg.as_ref(),
+        )?;
         println!();
-        println!("Written to {}.", config_path.display());
+        match profile {
+            Some(name) => println!("Written to [profile.{}] of {}.", name, config_path.display()),
+            None => println!("Written to {}.", config_path.display()),
+        }
         println!();
         println!("  Sources configured:");
-        for src in &cfg.sources {
+        for src in &sources_to_write {
             println!("    {}", src.name);
         }
-        if let Some(ref cap) = cfg.capture {
+        if let Some(ref cap) = capture_cfg {
             for (i, fmt) in cap.formats.iter().enumerate() {
                 let max_mb = cap.max_size_mb.get(i).copied().unwrap_or(10_000);
                 println!("  Capture ({}): {} → {}  (≤{} MB)", fmt, fmt, cap.output_dir, max_mb);
@@ -362,7 +423,7 @@ pub fn run(config: &ProbeConfig, config_path: &Path) -> Result<()> {
 
         // Restart the background service so the new config takes effect.
         let svc_restarted = std::process::Command::new("systemctl")
-            .args(["restart", "shredtop"])
+            .args(["restart", &paths.unit])
             .stdout(std::process::Stdio::null())
             .stderr(std::process::Stdio::null())
             .status()
@@ -420,9 +481,12 @@ fn fetch_dz_groups() -> Option<Vec<DzGroup>> {
         return None;
     }
 
-    let text = String::from_utf8_lossy(&output.stdout);
-    let mut groups = Vec::new();
+    Some(parse_dz_groups(&String::from_utf8_lossy(&output.stdout)))
+}
 
+/// Groups in the table printed by `doublezero multicast group list`.
+fn parse_dz_groups(text: &str) -> Vec<DzGroup> {
+    let mut groups = Vec::new();
     for line in text.lines() {
         // Table format:  account | code | multicast_ip | max_bandwidth | publishers | subscribers | status | owner
         let fields: Vec<&str> = line.split('|').map(|f| f.trim()).collect();
@@ -450,8 +514,7 @@ fn fetch_dz_groups() -> Option<Vec<DzGroup>> {
             status,
         });
     }
-
-    Some(groups)
+    groups
 }
 
 // ---------------------------------------------------------------------------
@@ -465,22 +528,9 @@ fn collect_and_show_memberships() -> HashMap<String, String> {
     {
         let mut map = HashMap::new();
         if let Ok(output) = Command::new("ip").args(["maddr", "show"]).output() {
-            let text = String::from_utf8_lossy(&output.stdout);
-            let mut current_iface = String::new();
-            for line in text.lines() {
-                if line.starts_with(|c: char| c.is_ascii_digit()) {
-                    if let Some(name) = line.split_whitespace().nth(1) {
-                        current_iface = name.trim_end_matches(':').to_string();
-                    }
-                } else if line.trim().starts_with("inet ") {
-                    let addr = line.trim().split_whitespace().nth(1).unwrap_or("");
-                    let first_octet: u8 =
-                        addr.split('.').next().unwrap_or("0").parse().unwrap_or(0);
-                    if (224..=239).contains(&first_octet) {
-                        println!("  {}  {}", current_iface, addr);
-                        map.insert(addr.to_string(), current_iface.clone());
-                    }
-                }
+            for (iface, addr) in parse_maddr(&String::from_utf8_lossy(&output.stdout)) {
+                println!("  {}  {}", iface, addr);
+                map.insert(addr, iface);
             }
             if map.is_empty() {
                 println!("  (no multicast memberships found)");
@@ -498,6 +548,106 @@ fn collect_and_show_memberships() -> HashMap<String, String> {
     }
 }
 
+/// `(interface, group)` for every IPv4 multicast membership in `ip maddr show`
+/// output.
+fn parse_maddr(text: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    let mut current_iface = String::new();
+    for line in text.lines() {
+        if line.starts_with(|c: char| c.is_ascii_digit()) {
+            if let Some(name) = line.split_whitespace().nth(1) {
+                current_iface = name.trim_end_matches(':').to_string();
+            }
+        } else if line.trim().starts_with("inet ") {
+            let addr = line.trim().split_whitespace().nth(1).unwrap_or("");
+            let first_octet: u8 = addr.split('.').next().unwrap_or("0").parse().unwrap_or(0);
+            if (224..=239).contains(&first_octet) {
+                out.push((current_iface.clone(), addr.to_string()));
+            }
+        }
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Interface detection
+// ---------------------------------------------------------------------------
+
+/// Interfaces that are up and have an IPv4 address (the receiver joins
+/// multicast groups by interface address), from `ip -o -4 addr show up`.
+/// Loopback is left out.
+fn list_ipv4_interfaces() -> Vec<(String, Ipv4Addr)> {
+    let Ok(output) = Command::new("ip").args(["-o", "-4", "addr", "show", "up"]).output() else {
+        return Vec::new();
+    };
+    let mut out: Vec<(String, Ipv4Addr)> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        // `2: eth0    inet 10.0.0.5/24 brd 10.0.0.255 scope global eth0 ...`
+        let mut fields = line.split_whitespace();
+        let Some(name) = fields.nth(1) else { continue };
+        let name = name.trim_end_matches(':');
+        let addr = fields
+            .skip_while(|f| *f != "inet")
+            .nth(1)
+            .and_then(|a| a.split('/').next())
+            .and_then(|a| a.parse().ok());
+        if let Some(addr) = addr {
+            if name != "lo" && !out.iter().any(|(n, _)| n == name) {
+                out.push((name.to_string(), addr));
+            }
+        }
+    }
+    out
+}
+
+/// Find the interface a multicast group arrives on. In order: an existing
+/// membership for the group, traffic seen within 1 s of joining it on each
+/// candidate interface, then the kernel's route for the group. Returns the
+/// interface and how it was found.
+fn detect_multicast_interface(
+    group: &str,
+    port: u16,
+    candidates: &[(String, Ipv4Addr)],
+) -> Option<(String, &'static str)> {
+    let output = Command::new("ip").args(["maddr", "show"]).output().ok();
+    let memberships = output.map(|o| parse_maddr(&String::from_utf8_lossy(&o.stdout)));
+    if let Some((iface, _)) = memberships.into_iter().flatten().find(|(_, g)| g == group) {
+        return Some((iface, "existing membership"));
+    }
+
+    let group_addr: Ipv4Addr = group.parse().ok()?;
+    for (name, addr) in candidates {
+        print!("  Listening for {}:{} on {} (1s)...", group, port, name);
+        io::stdout().flush().ok();
+        let seen = receives_group(group_addr, port, *addr);
+        println!(" {}", if seen { "traffic" } else { "nothing" });
+        if seen {
+            return Some((name.clone(), "traffic"));
+        }
+    }
+
+    let output = Command::new("ip").args(["route", "get", group]).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut fields = text.split_whitespace();
+    fields.find(|f| *f == "dev")?;
+    let dev = fields.next()?;
+    candidates.iter().any(|(n, _)| n == dev).then(|| (dev.to_string(), "route"))
+}
+
+/// Join `group` on the interface with address `iface_addr` and wait up to
+/// one second for a datagram on `port`. False if the port is already bound
+/// by another process or nothing arrives.
+fn receives_group(group: Ipv4Addr, port: u16, iface_addr: Ipv4Addr) -> bool {
+    let Ok(sock) = UdpSocket::bind((group, port)) else { return false };
+    if sock.join_multicast_v4(&group, &iface_addr).is_err()
+        || sock.set_read_timeout(Some(Duration::from_secs(1))).is_err()
+    {
+        return false;
+    }
+    let mut buf = [0u8; 2048];
+    sock.recv_from(&mut buf).is_ok()
+}
+
 // ---------------------------------------------------------------------------
 // Traffic-based port detection
 // ---------------------------------------------------------------------------
@@ -537,22 +687,31 @@ fn detect_shred_ports_from_traffic(
         // tcpdump writes packet lines to stdout; combine with stderr for safety.
         let stdout = String::from_utf8_lossy(&output.stdout);
         let stderr = String::from_utf8_lossy(&output.stderr);
-        for line in stdout.lines().chain(stderr.lines()) {
-            if let Some((ip, port)) = parse_dst_from_tcpdump_line(line) {
-                if ips.contains(&ip) {
-                    result.entry(ip).or_insert(port);
-                }
-            }
-            // Stop early if we have ports for all groups on this interface.
-            if ips.iter().all(|ip| result.contains_key(ip)) {
-                break;
-            }
-        }
+        ports_from_tcpdump(stdout.lines().chain(stderr.lines()), ips, &mut result);
     }
 
     result
 }
 
+/// Record the first destination port seen for each of `ips`.
+fn ports_from_tcpdump<'a>(
+    lines: impl Iterator<Item = &'a str>,
+    ips: &[String],
+    result: &mut HashMap<String, u16>,
+) {
+    for line in lines {
+        if let Some((ip, port)) = parse_dst_from_tcpdump_line(line) {
+            if ips.contains(&ip) {
+                result.entry(ip).or_insert(port);
+            }
+        }
+        // Stop early if we have ports for all groups on this interface.
+        if ips.iter().all(|ip| result.contains_key(ip)) {
+            break;
+        }
+    }
+}
+
 /// Parse a tcpdump `-q` output line and return the destination (multicast IP, port).
 ///
 /// Expected format: `HH:MM:SS.usec IP src.sport > dst.dport: UDP, length N`
@@ -617,6 +776,16 @@ fn show_configured_sources(config: &ProbeConfig) {
                         "-",
                     );
                 }
+                "jito-udp" => {
+                    println!(
+                        "  {:<20} {:<10} {:<20} {:<8} {:<14}",
+                        s.name,
+                        "jito-udp",
+                        s.public_ip.as_deref().unwrap_or("?"),
+                        s.port.unwrap_or(20000),
+                        "-",
+                    );
+                }
                 "turbine" => {
                     println!(
                         "  {:<20} {:<10} {:<20} {:<8} {:<14}",
@@ -772,19 +941,61 @@ fn collect_manual_sources() -> Vec<SourceEntry> {
                         continue;
                     }
                 };
-                let interface =
-                    prompt_with_default("  Interface", "doublezero1", "network interface");
+                let candidates = list_ipv4_interfaces();
+                let default = match detect_multicast_interface(&multicast_addr, port, &candidates)
+                {
+                    Some((iface, how)) => {
+                        println!("  Detected interface {} ({}).", color::green(&iface), how);
+                        iface
+                    }
+                    None => {
+                        println!("  No interface receives {} yet.", multicast_addr);
+                        "doublezero1".to_string()
+                    }
+                };
+                let interface = loop {
+                    let iface = prompt_with_default("  Interface", &default, "network interface");
+                    if candidates.is_empty() || candidates.iter().any(|(n, _)| *n == iface) {
+                        break iface;
+                    }
+                    let names: Vec<&str> = candidates.iter().map(|(n, _)| n.as_str()).collect();
+                    println!(
+                        "  {}",
+                        color::yellow(&format!(
+                            "No IPv4 interface named '{}' is up (found: {}).",
+                            iface,
+                            names.join(", ")
+                        ))
+                    );
+                };
                 SourceEntry {
                     name,
                     source_type: "shred".into(),
                     multicast_addr: Some(multicast_addr),
                     port: Some(port),
+                    port_range: None,
                     interface: Some(interface),
                     url: None,
                     x_token: None,
                     pin_recv_core: None,
                     pin_decode_core: None,
                     shred_version: None,
+                    recv_sockets: None,
+                    failover_group: None,
+                    failover_after_secs: None,
+                    chaos: None,
+                    decode: None,
+                    decode_max_lag_slots: None,
+                    labels: None,
+                    keypair: None,
+                    public_ip: None,
+                    regions: None,
+                    ws_url: None,
+                    rpc_parallelism: None,
+                    timestamp_offset_us: None,
+                    calibrate_timestamps: None,
+                    grpc: None,
+                    auth: None,
                 }
             }
             "2" | "unicast" => {
@@ -803,12 +1014,29 @@ fn collect_manual_sources() -> Vec<SourceEntry> {
                     source_type: "unicast".into(),
                     multicast_addr: Some(addr),
                     port: Some(port),
+                    port_range: None,
                     interface: None,
                     url: None,
                     x_token: None,
                     pin_recv_core: None,
                     pin_decode_core: None,
                     shred_version: None,
+                    recv_sockets: None,
+                    failover_group: None,
+                    failover_after_secs: None,
+                    chaos: None,
+                    decode: None,
+                    decode_max_lag_slots: None,
+                    labels: None,
+                    keypair: None,
+                    public_ip: None,
+                    regions: None,
+                    ws_url: None,
+                    rpc_parallelism: None,
+                    timestamp_offset_us: None,
+                    calibrate_timestamps: None,
+                    grpc: None,
+                    auth: None,
                 }
             }
             "3" | "rpc" => {
@@ -819,12 +1047,29 @@ fn collect_manual_sources() -> Vec<SourceEntry> {
                     source_type: "rpc".into(),
                     multicast_addr: None,
                     port: None,
+                    port_range: None,
                     interface: None,
                     url: Some(url),
                     x_token: None,
                     pin_recv_core: None,
                     pin_decode_core: None,
                     shred_version: None,
+                    recv_sockets: None,
+                    failover_group: None,
+                    failover_after_secs: None,
+                    chaos: None,
+                    decode: None,
+                    decode_max_lag_slots: None,
+                    labels: None,
+                    keypair: None,
+                    public_ip: None,
+                    regions: None,
+                    ws_url: None,
+                    rpc_parallelism: None,
+                    timestamp_offset_us: None,
+                    calibrate_timestamps: None,
+                    grpc: None,
+                    auth: None,
                 }
             }
             "4" | "geyser" => {
@@ -837,12 +1082,29 @@ fn collect_manual_sources() -> Vec<SourceEntry> {
                     source_type: "geyser".into(),
                     multicast_addr: None,
                     port: None,
+                    port_range: None,
                     interface: None,
                     url: Some(url),
                     x_token,
                     pin_recv_core: None,
                     pin_decode_core: None,
                     shred_version: None,
+                    recv_sockets: None,
+                    failover_group: None,
+                    failover_after_secs: None,
+                    chaos: None,
+                    decode: None,
+                    decode_max_lag_slots: None,
+                    labels: None,
+                    keypair: None,
+                    public_ip: None,
+                    regions: None,
+                    ws_url: None,
+                    rpc_parallelism: None,
+                    timestamp_offset_us: None,
+                    calibrate_timestamps: None,
+                    grpc: None,
+                    auth: None,
                 }
             }
             "5" | "jito-grpc" => {
@@ -854,12 +1116,29 @@ fn collect_manual_sources() -> Vec<SourceEntry> {
                     source_type: "jito-grpc".into(),
                     multicast_addr: None,
                     port: None,
+                    port_range: None,
                     interface: None,
                     url: Some(url),
                     x_token: None,
                     pin_recv_core: None,
                     pin_decode_core: None,
                     shred_version: None,
+                    recv_sockets: None,
+                    failover_group: None,
+                    failover_after_secs: None,
+                    chaos: None,
+                    decode: None,
+                    decode_max_lag_slots: None,
+                    labels: None,
+                    keypair: None,
+                    public_ip: None,
+                    regions: None,
+                    ws_url: None,
+                    rpc_parallelism: None,
+                    timestamp_offset_us: None,
+                    calibrate_timestamps: None,
+                    grpc: None,
+                    auth: None,
                 }
             }
             _ => {
@@ -1043,7 +1322,7 @@ fn parse_size_mb(s: &str) -> Option<u64> {
 /// Ask the user whether to enable raw shred capture, and if so, collect
 /// formats, disk, and per-format max sizes.
 /// Returns `None` if the user skips capture.
-fn configure_capture() -> Option<CaptureConfig> {
+fn configure_capture(profile: Option<&str>) -> Option<CaptureConfig> {
     println!();
     println!("{}", color::bold_cyan("=== Raw shred capture (optional) ==="));
     println!("  Stores raw packets to disk for offline analysis and replay.");
@@ -1093,7 +1372,8 @@ fn configure_capture() -> Option<CaptureConfig> {
     }
 
     let output_dir = if disks.is_empty() {
-        prompt_with_default("Output directory", "/var/log/shredtop-capture", "full path")
+        let default = crate::profile::suffixed(&CaptureConfig::default_output_dir(), profile);
+        prompt_with_default("Output directory", &default, "full path")
     } else {
         print!("{}", color::yellow(&format!("Disk [1-{}, or enter path]: ", disks.len())));
         io::stdout().flush().ok();
@@ -1113,7 +1393,7 @@ fn configure_capture() -> Option<CaptureConfig> {
             disks[0].mount.trim_end_matches('/').to_string()
         };
 
-        format!("{}/shredtop-capture", mount)
+        crate::profile::suffixed(&format!("{}/shredtop-capture", mount), profile)
     };
 
     // ── Step 3: max size per format ──────────────────────────────────────────
@@ -1165,5 +1445,258 @@ fn configure_capture() -> Option<CaptureConfig> {
         max_size_mb,
         output_dir,
         rotate_mb,
+        ..Default::default()
     })
 }
+
+// ---------------------------------------------------------------------------
+// Remote discovery over SSH
+// ---------------------------------------------------------------------------
+
+/// `shredtop discover --ssh`: run the detection steps on each host over SSH,
+/// without prompts, and write one config per host next to `config_path`
+/// (`probe.<host>.toml`), or with `combined` a single file keyed by host.
+///
+/// Per host: multicast memberships from `ip maddr show`, group names from
+/// `doublezero multicast group list` if the CLI is installed, ports sniffed
+/// with `tcpdump` (falling back to the known DoubleZero ports), and a local
+/// RPC baseline probed with `curl`. Memberships whose port cannot be found
+/// are left out.
+pub fn run_ssh(hosts: &[String], config_path: &Path, combined: bool) -> Result<()> {
+    let mut configs: BTreeMap<String, ProbeConfig> = BTreeMap::new();
+    for host in hosts {
+        println!("{}", color::bold_cyan(&format!("=== {} ===", host)));
+        match discover_host(host) {
+            Ok((cfg, notes)) => {
+                for note in &notes {
+                    println!("  {}", color::yellow(note));
+                }
+                for s in &cfg.sources {
+                    match (&s.multicast_addr, s.port, &s.interface, &s.url) {
+                        (Some(ip), Some(port), Some(iface), _) => {
+                            println!("  {:<22} {}:{} on {}", s.name, ip, port, iface)
+                        }
+                        (_, _, _, Some(url)) => println!("  {:<22} {}", s.name, url),
+                        _ => println!("  {}", s.name),
+                    }
+                }
+                if cfg.sources.is_empty() {
+                    println!("  {}", color::yellow("no feeds found"));
+                }
+                configs.insert(host_label(host), cfg);
+            }
+            Err(e) => println!("  {}", color::red(&format!("skipped: {:#}", e))),
+        }
+    }
+    anyhow::ensure!(!configs.is_empty(), "discovery failed on every host");
+
+    println!();
+    if combined {
+        std::fs::write(config_path, toml::to_string_pretty(&configs)?)?;
+        println!("Written {} host(s) to {}.", configs.len(), config_path.display());
+        return Ok(());
+    }
+    let stem = config_path.file_stem().and_then(|s| s.to_str()).unwrap_or("probe");
+    for (host, cfg) in &configs {
+        let path = config_path.with_file_name(format!("{}.{}.toml", stem, host));
+        std::fs::write(&path, toml::to_string_pretty(cfg)?)?;
+        println!("Written {}.", path.display());
+    }
+    Ok(())
+}
+
+fn discover_host(host: &str) -> Result<(ProbeConfig, Vec<String>)> {
+    let run = |cmd: &str| ssh(host, cmd);
+    let mut notes = Vec::new();
+    let mut cfg = detect_feeds(&run, &mut notes)?;
+    if let Some(url) = detect_remote_rpc_url(host) {
+        cfg.sources.push(SourceEntry { url: Some(url), ..bare_entry("rpc".into(), "rpc") });
+    }
+    Ok((cfg, notes))
+}
+
+/// `shredtop init --from-running`: the same detection as `discover --ssh`,
+/// run on this host. Anything worth a warning goes into `notes`.
+pub fn from_running(notes: &mut Vec<String>) -> Result<ProbeConfig> {
+    let mut cfg = detect_feeds(&local_sh, notes)?;
+    if let Some(url) = detect_rpc_url() {
+        cfg.sources.push(SourceEntry { url: Some(url), ..bare_entry("rpc".into(), "rpc") });
+    }
+    Ok(cfg)
+}
+
+/// Feeds the target is already receiving, without prompts: one `shred`
+/// source per multicast membership whose port is known, a `turbine` source
+/// if a validator holds the TVU port, and a `jito-grpc` source if a
+/// ShredStream proxy is listening. `run` executes a shell command on the
+/// target and returns its stdout.
+fn detect_feeds(
+    run: &dyn Fn(&str) -> Result<String>,
+    notes: &mut Vec<String>,
+) -> Result<ProbeConfig> {
+    // 224.0.0.0/24 is link-local control traffic (all-hosts, IGMP), never a feed.
+    let mut memberships = parse_maddr(&run("ip maddr show")?);
+    memberships.retain(|(_, ip)| !ip.starts_with("224.0.0."));
+    let groups = run("doublezero multicast group list 2>/dev/null")
+        .map(|text| parse_dz_groups(&text))
+        .unwrap_or_default();
+
+    let mut by_iface: HashMap<&str, Vec<String>> = HashMap::new();
+    for (iface, ip) in &memberships {
+        by_iface.entry(iface.as_str()).or_default().push(ip.clone());
+    }
+    let mut ports: HashMap<String, u16> = HashMap::new();
+    for (iface, ips) in &by_iface {
+        let filter = ips.iter().map(|ip| format!("dst {}", ip)).collect::<Vec<_>>().join(" or ");
+        let cmd = format!(
+            "timeout 3 tcpdump -c 30 -ni {} -q {} 2>&1",
+            shell_quote(iface),
+            shell_quote(&filter)
+        );
+        if let Ok(text) = run(&cmd) {
+            ports_from_tcpdump(text.lines(), ips, &mut ports);
+        }
+    }
+
+    let mut cfg = ProbeConfig::default_example();
+    cfg.sources.clear();
+    cfg.capture = None;
+    for (iface, ip) in &memberships {
+        let group = groups.iter().find(|g| &g.multicast_ip == ip);
+        let name = group.map_or_else(|| format!("mcast-{}", ip), |g| g.code.clone());
+        let known = group.and_then(|g| known_port_for_group(&g.code));
+        let Some(port) = ports.get(ip).copied().or(known) else {
+            notes.push(format!("{} on {}: no port found, skipped", ip, iface));
+            continue;
+        };
+        cfg.sources.push(SourceEntry {
+            multicast_addr: Some(ip.clone()),
+            port: Some(port),
+            interface: Some(iface.clone()),
+            ..bare_entry(name, "shred")
+        });
+    }
+
+    let udp = run("ss -Hulnp 2>/dev/null").map(|t| parse_ss(&t)).unwrap_or_default();
+    let tcp = run("ss -Htlnp 2>/dev/null").map(|t| parse_ss(&t)).unwrap_or_default();
+    if udp.iter().any(|(port, proc)| *port == TVU_PORT && VALIDATORS.contains(&proc.as_str())) {
+        cfg.sources.push(SourceEntry {
+            port: Some(TVU_PORT),
+            ..bare_entry("turbine".into(), "turbine")
+        });
+    }
+    if let Some((port, _)) = tcp.iter().find(|(_, proc)| proc.contains("shredstream-proxy")) {
+        cfg.sources.push(SourceEntry {
+            url: Some(format!("http://127.0.0.1:{}", port)),
+            ..bare_entry("jito-shredstream".into(), "jito-grpc")
+        });
+    }
+    if udp.is_empty() && tcp.is_empty() {
+        notes.push("ss not available or not permitted, listening sockets not checked".into());
+    }
+    Ok(cfg)
+}
+
+/// The port `turbine` sources bind by default, and the validator processes
+/// that may hold it.
+const TVU_PORT: u16 = 8002;
+const VALIDATORS: &[&str] = &["agave-validator", "solana-validator", "fdctl", "firedancer"];
+
+/// `(local port, process name)` for each socket in `ss -Hlnp` output, e.g.
+/// `UNCONN 0 0 0.0.0.0:8002 0.0.0.0:* users:(("agave-validator",pid=1,fd=9))`.
+/// Sockets without a visible process are left out.
+fn parse_ss(text: &str) -> Vec<(u16, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let local = line.split_whitespace().nth(3)?;
+            let port = local.rsplit(':').next()?.parse().ok()?;
+            let proc = line.split("((\"").nth(1)?.split('"').next()?;
+            Some((port, proc.to_string()))
+        })
+        .collect()
+}
+
+/// Run `command` with `sh` on this host and return its stdout.
+fn local_sh(command: &str) -> Result<String> {
+    let output = Command::new("sh")
+        .args(["-c", command])
+        .output()
+        .map_err(|e| anyhow::anyhow!("failed to run sh: {}", e))?;
+    if !output.status.success() && output.stdout.is_empty() {
+        anyhow::bail!("`{}` failed", command);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The remote counterpart of [`detect_rpc_url`], with `curl` on the host.
+fn detect_remote_rpc_url(host: &str) -> Option<String> {
+    let cmd = "for p in 8899 58000 8900 9000 8080; do \
+        curl -s -m 1 -H 'Content-Type: application/json' \
+        -d '{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"getHealth\"}' \
+        http://127.0.0.1:$p | grep -q '\"result\"' && echo $p && break; done";
+    let port: u16 = ssh(host, cmd).ok()?.trim().parse().ok()?;
+    Some(format!("http://127.0.0.1:{}", port))
+}
+
+/// Run `command` on `host` and return its stdout. Never prompts: keys must
+/// be loaded, and the remote user must be allowed to run `tcpdump`.
+fn ssh(host: &str, command: &str) -> Result<String> {
+    let output = Command::new("ssh")
+        .args(["-o", "BatchMode=yes", "-o", "ConnectTimeout=5", host, command])
+        .output()
+        .map_err(|e| anyhow::anyhow!("failed to run ssh: {}", e))?;
+    // 255 is ssh's own failure; anything else is the remote command's.
+    if output.status.code() == Some(255) {
+        anyhow::bail!("ssh: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    if !output.status.success() && output.stdout.is_empty() {
+        anyhow::bail!("`{}` failed on {}", command, host);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// `host` as used in file names and config keys: without a `user@` prefix,
+/// and with anything but letters, digits, `.` and `-` replaced.
+fn host_label(host: &str) -> String {
+    let host = host.rsplit('@').next().unwrap_or(host);
+    host.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect()
+}
+
+/// A source with only its name and type set.
+fn bare_entry(name: String, source_type: &str) -> SourceEntry {
+    SourceEntry {
+        name,
+        source_type: source_type.into(),
+        multicast_addr: None,
+        port: None,
+        port_range: None,
+        interface: None,
+        url: None,
+        x_token: None,
+        pin_recv_core: None,
+        pin_decode_core: None,
+        shred_version: None,
+        recv_sockets: None,
+        failover_group: None,
+        failover_after_secs: None,
+        chaos: None,
+        decode: None,
+        decode_max_lag_slots: None,
+        labels: None,
+        keypair: None,
+        public_ip: None,
+        regions: None,
+        ws_url: None,
+        rpc_parallelism: None,
+        timestamp_offset_us: None,
+        calibrate_timestamps: None,
+        grpc: None,
+        auth: None,
+    }
+}
@@ -0,0 +1,487 @@
+//! Per-epoch latency summaries for `shredtop run`.
+//!
+//! Every interval the daemon folds what happened since the previous interval
+//! into a summary of the current leader epoch and rewrites
+//! `<output_dir>/epoch-<N>.json`:
+//!
+//! - **per source** — lead-time samples, wins vs RPC, first deliveries and
+//!   slot outcomes;
+//! - **per race pair** — shred and slot race wins and mean lead;
+//! - **per leader** — slot outcomes and completion times of every source,
+//!   broken down by the validator that produced the slots.
+//!
+//! Epoch boundaries come from `getEpochInfo` and slot leaders from
+//! `getSlotLeaders` on the configured JSON-RPC endpoint. Counter deltas are
+//! attributed to the epoch the cluster is in when they are read, so an
+//! interval that straddles a boundary lands wholly in the new epoch. Slot
+//! records are attributed by slot number; they are held back for
+//! [`SLOT_HOLDBACK`] slots so every source has finalized a slot before the
+//! first-to-complete comparison is made. Once the last slot of an epoch has
+//! been folded in, its file is written a final time with `complete: true`.
+//!
+//! A file left by an earlier run for the same, still incomplete, epoch is
+//! picked up and extended, so a restart keeps the epoch's history.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use shred_ingest::{
+    ShredPairState, ShredRaceTracker, SlotOutcome, SlotStats, SourceMetricsSnapshot,
+};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::{EpochReportConfig, SourceEntry};
+
+/// Slots a finalized slot record waits before it is folded in, so the
+/// slower feeds have finalized it too (~25 s at 400 ms slots).
+const SLOT_HOLDBACK: u64 = 64;
+
+/// `getSlotLeaders` returns at most this many leaders per call.
+const MAX_LEADERS_PER_CALL: u64 = 5000;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct EpochSummary {
+    epoch: u64,
+    first_slot: u64,
+    last_slot: u64,
+    /// Unix seconds of the first and the latest update.
+    started_at: u64,
+    updated_at: u64,
+    /// True once every slot of the epoch has been folded in.
+    complete: bool,
+    sources: BTreeMap<String, SourceTotals>,
+    shred_race: Vec<PairTotals>,
+    slot_race: Vec<PairTotals>,
+    /// Keyed by leader identity pubkey.
+    leaders: BTreeMap<String, LeaderTotals>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SourceTotals {
+    lead_samples: u64,
+    lead_wins: u64,
+    lead_sum_us: i64,
+    txs_first: u64,
+    txs_duplicate: u64,
+    beat_rpc_pct: Option<f64>,
+    lead_mean_us: Option<f64>,
+    #[serde(flatten)]
+    slots: SlotTally,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SlotTally {
+    slots: u64,
+    complete: u64,
+    partial: u64,
+    dropped: u64,
+    /// Slots of a transaction stream (`jito-grpc`), which are never complete
+    /// or partial.
+    streamed: u64,
+    /// Slots this source completed before every other shred source.
+    first_complete: u64,
+    completion_sum_us: u64,
+    shreds_seen: u64,
+    shreds_expected: u64,
+    completion_mean_us: Option<f64>,
+    coverage_pct: Option<f64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct LeaderTotals {
+    /// Leader slots finalized by at least one source.
+    slots: u64,
+    sources: BTreeMap<String, SlotTally>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PairTotals {
+    source_a: String,
+    source_b: String,
+    a_wins: u64,
+    b_wins: u64,
+    lead_sum_us: i64,
+    lead_count: u64,
+    a_win_pct: Option<f64>,
+    lead_mean_us: Option<f64>,
+}
+
+/// The per-source counters the summary is built from, as of the last update.
+#[derive(Default, Clone, Copy)]
+struct Counters {
+    lead_samples: u64,
+    lead_wins: u64,
+    lead_sum_us: i64,
+    txs_first: u64,
+    txs_duplicate: u64,
+}
+
+impl Counters {
+    fn of(s: &SourceMetricsSnapshot) -> Self {
+        Self {
+            lead_samples: s.lead_time_count,
+            lead_wins: s.lead_wins,
+            lead_sum_us: s.lead_time_sum_us,
+            txs_first: s.txs_first,
+            txs_duplicate: s.txs_duplicate,
+        }
+    }
+}
+
+struct EpochInfo {
+    epoch: u64,
+    first_slot: u64,
+    slots_in_epoch: u64,
+}
+
+pub struct EpochReporter {
+    dir: PathBuf,
+    rpc_url: String,
+    agent: ureq::Agent,
+    current: Option<EpochSummary>,
+    /// The previous epoch, kept open until its held-back slots are folded in.
+    closing: Option<EpochSummary>,
+    prev: BTreeMap<String, Counters>,
+    prev_shred_race: Vec<ShredPairState>,
+    prev_slot_race: Vec<ShredPairState>,
+    /// Highest slot record taken per source.
+    last_slot: BTreeMap<String, u64>,
+    /// Slot records waiting out [`SLOT_HOLDBACK`], keyed by slot.
+    pending: BTreeMap<u64, Vec<(String, SlotStats)>>,
+    leaders: BTreeMap<u64, String>,
+    failing: bool,
+}
+
+impl EpochReporter {
+    /// Validate `cfg` and take the starting counters, so the first update
+    /// only covers what happens from now on.
+    pub fn new(
+        cfg: &EpochReportConfig,
+        sources: &[SourceEntry],
+        snaps: &[SourceMetricsSnapshot],
+        race: &ShredRaceTracker,
+    ) -> Result<Self> {
+        let rpc_url = cfg
+            .rpc_url
+            .clone()
+            .or_else(|| {
+                sources.iter().find(|s| s.source_type == "rpc").and_then(|s| s.url.clone())
+            })
+            .context("epoch_report needs rpc_url, or an `rpc` source with a url")?;
+        std::fs::create_dir_all(&cfg.output_dir)
+            .with_context(|| format!("failed to create {}", cfg.output_dir))?;
+        let mut reporter = Self {
+            dir: PathBuf::from(&cfg.output_dir),
+            rpc_url,
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(5)).build(),
+            current: None,
+            closing: None,
+            prev: BTreeMap::new(),
+            prev_shred_race: race.export_state(),
+            prev_slot_race: race.export_slot_state(),
+            last_slot: BTreeMap::new(),
+            pending: BTreeMap::new(),
+            leaders: BTreeMap::new(),
+            failing: false,
+        };
+        for s in snaps.iter().filter(|s| !s.is_rpc) {
+            reporter.prev.insert(s.name.to_string(), Counters::of(s));
+            let last = s.slot_log.iter().map(|st| st.slot).max().unwrap_or(0);
+            reporter.last_slot.insert(s.name.to_string(), last);
+        }
+        Ok(reporter)
+    }
+
+    pub fn rpc_url(&self) -> &str {
+        &self.rpc_url
+    }
+
+    /// Fold one interval into the current epoch and rewrite its file.
+    /// An unreachable RPC endpoint defers the whole interval to the next call.
+    pub fn update(&mut self, snaps: &[SourceMetricsSnapshot], race: &ShredRaceTracker) {
+        let info = match self.epoch_info() {
+            Ok(info) => {
+                if self.failing {
+                    self.failing = false;
+                    tracing::info!("epoch_report: {} reachable again", self.rpc_url);
+                }
+                info
+            }
+            Err(e) => {
+                if !self.failing {
+                    self.failing = true;
+                    tracing::warn!("epoch_report: getEpochInfo failed: {:#}", e);
+                }
+                return;
+            }
+        };
+
+        if !matches!(&self.current, Some(c) if c.epoch == info.epoch) {
+            if let Some(mut old) = self.closing.take() {
+                self.finish(&mut old);
+            }
+            self.closing = self.current.take();
+            self.current = Some(self.load_or_new(&info));
+        }
+
+        for s in snaps.iter().filter(|s| !s.is_rpc) {
+            let last = self.last_slot.entry(s.name.to_string()).or_default();
+            let newest = s.slot_log.iter().map(|st| st.slot).max().unwrap_or(0);
+            for st in s.slot_log.iter().filter(|st| st.slot > *last) {
+                self.pending.entry(st.slot).or_default().push((s.name.to_string(), st.clone()));
+            }
+            *last = (*last).max(newest);
+        }
+        let newest = self.pending.keys().next_back().copied().unwrap_or(0);
+        self.flush(newest.saturating_sub(SLOT_HOLDBACK));
+
+        let current = self.current.as_mut().expect("current epoch set above");
+        for s in snaps.iter().filter(|s| !s.is_rpc) {
+            let now = Counters::of(s);
+            let prev = self.prev.insert(s.name.to_string(), now).unwrap_or_default();
+            let t = current.sources.entry(s.name.to_string()).or_default();
+            t.lead_samples += now.lead_samples.saturating_sub(prev.lead_samples);
+            t.lead_wins += now.lead_wins.saturating_sub(prev.lead_wins);
+            t.lead_sum_us += now.lead_sum_us - prev.lead_sum_us;
+            t.txs_first += now.txs_first.saturating_sub(prev.txs_first);
+            t.txs_duplicate += now.txs_duplicate.saturating_sub(prev.txs_duplicate);
+        }
+        let shred_race = race.export_state();
+        let slot_race = race.export_slot_state();
+        add_pair_deltas(&mut current.shred_race, &shred_race, &self.prev_shred_race);
+        add_pair_deltas(&mut current.slot_race, &slot_race, &self.prev_slot_race);
+        self.prev_shred_race = shred_race;
+        self.prev_slot_race = slot_race;
+
+        if let Some(mut current) = self.current.take() {
+            self.write(&mut current);
+            self.current = Some(current);
+        }
+    }
+
+    /// Fold every pending slot up to `upto` into its epoch, closing the
+    /// previous epoch once nothing older than the current one is left.
+    fn flush(&mut self, upto: u64) {
+        let ready: Vec<u64> = self.pending.range(..=upto).map(|(slot, _)| *slot).collect();
+        if let (Some(&lo), Some(&hi)) = (ready.first(), ready.last()) {
+            if let Err(e) = self.fetch_leaders(lo, hi) {
+                tracing::debug!("epoch_report: getSlotLeaders failed: {:#}", e);
+            }
+        }
+        for slot in ready {
+            let records = self.pending.remove(&slot).unwrap_or_default();
+            let leader = self.leaders.get(&slot).cloned();
+            let target = [self.current.as_mut(), self.closing.as_mut()]
+                .into_iter()
+                .flatten()
+                .find(|e| (e.first_slot..=e.last_slot).contains(&slot));
+            if let Some(summary) = target {
+                fold_slot(summary, &records, leader.as_deref());
+            }
+        }
+        if let Some(lo) = self.pending.keys().next() {
+            let lo = *lo;
+            self.leaders = self.leaders.split_off(&lo);
+        }
+
+        let first = self.current.as_ref().map_or(0, |c| c.first_slot);
+        if upto >= first {
+            if let Some(mut old) = self.closing.take() {
+                self.finish(&mut old);
+            }
+        }
+    }
+
+    fn finish(&self, summary: &mut EpochSummary) {
+        summary.complete = true;
+        self.write(summary);
+    }
+
+    fn path(&self, epoch: u64) -> PathBuf {
+        self.dir.join(format!("epoch-{}.json", epoch))
+    }
+
+    /// Resume an incomplete summary left by an earlier run, or start afresh.
+    fn load_or_new(&self, info: &EpochInfo) -> EpochSummary {
+        let existing = std::fs::read(self.path(info.epoch))
+            .ok()
+            .and_then(|b| serde_json::from_slice::<EpochSummary>(&b).ok())
+            .filter(|s| s.epoch == info.epoch && !s.complete);
+        existing.unwrap_or_else(|| EpochSummary {
+            epoch: info.epoch,
+            first_slot: info.first_slot,
+            last_slot: info.first_slot + info.slots_in_epoch.saturating_sub(1),
+            started_at: unix_now(),
+            ..Default::default()
+        })
+    }
+
+    /// Recompute the derived fields and write atomically (temp file + rename).
+    fn write(&self, summary: &mut EpochSummary) {
+        summary.updated_at = unix_now();
+        for t in summary.sources.values_mut() {
+            t.beat_rpc_pct = pct(t.lead_wins, t.lead_samples);
+            t.lead_mean_us = mean(t.lead_sum_us as f64, t.lead_samples);
+            t.slots.derive();
+        }
+        for l in summary.leaders.values_mut() {
+            l.sources.values_mut().for_each(SlotTally::derive);
+        }
+        for p in summary.shred_race.iter_mut().chain(summary.slot_race.iter_mut()) {
+            p.a_win_pct = pct(p.a_wins, p.a_wins + p.b_wins);
+            p.lead_mean_us = mean(p.lead_sum_us as f64, p.lead_count);
+        }
+
+        let path = self.path(summary.epoch);
+        if let Err(e) = write_atomic(&path, summary) {
+            tracing::warn!("epoch_report: failed to write {}: {:#}", path.display(), e);
+        }
+    }
+
+    fn epoch_info(&self) -> Result<EpochInfo> {
+        let r = self.call("getEpochInfo", json!([{ "commitment": "processed" }]))?;
+        let field = |k: &str| r[k].as_u64().with_context(|| format!("getEpochInfo: no {}", k));
+        Ok(EpochInfo {
+            epoch: field("epoch")?,
+            first_slot: field("absoluteSlot")? - field("slotIndex")?,
+            slots_in_epoch: field("slotsInEpoch")?,
+        })
+    }
+
+    /// Make sure the leaders of `lo..=hi` are cached.
+    fn fetch_leaders(&mut self, lo: u64, hi: u64) -> Result<()> {
+        let mut start = lo;
+        while start <= hi {
+            if self.leaders.contains_key(&start) {
+                start += 1;
+                continue;
+            }
+            let limit = (hi - start + 1).min(MAX_LEADERS_PER_CALL);
+            let r = self.call("getSlotLeaders", json!([start, limit]))?;
+            let list = r.as_array().context("getSlotLeaders: result is not an array")?;
+            for (i, leader) in list.iter().enumerate() {
+                if let Some(id) = leader.as_str() {
+                    self.leaders.insert(start + i as u64, id.to_string());
+                }
+            }
+            start += limit;
+        }
+        Ok(())
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let text = self
+            .agent
+            .post(&self.rpc_url)
+            .set("Content-Type", "application/json")
+            .send_string(&body.to_string())?
+            .into_string()?;
+        let mut resp: Value = serde_json::from_str(&text)?;
+        if let Some(err) = resp.get("error") {
+            anyhow::bail!("{}: {}", method, err);
+        }
+        Ok(resp["result"].take())
+    }
+}
+
+impl SlotTally {
+    fn add(&mut self, st: &SlotStats, first: bool) {
+        self.slots += 1;
+        match st.outcome {
+            SlotOutcome::Complete => self.complete += 1,
+            SlotOutcome::Partial => self.partial += 1,
+            SlotOutcome::Dropped => self.dropped += 1,
+            SlotOutcome::Streamed => self.streamed += 1,
+        }
+        if first {
+            self.first_complete += 1;
+        }
+        if matches!(st.outcome, SlotOutcome::Complete) {
+            self.completion_sum_us += st.completion_us.unwrap_or(0);
+        }
+        self.shreds_seen += st.shreds_seen as u64;
+        self.shreds_expected += st.shreds_expected as u64;
+    }
+
+    fn derive(&mut self) {
+        self.completion_mean_us = mean(self.completion_sum_us as f64, self.complete);
+        self.coverage_pct = pct(self.shreds_seen.min(self.shreds_expected), self.shreds_expected);
+    }
+}
+
+/// Add one slot's records from every source. The first-to-complete credit
+/// only counts when at least two sources completed the slot.
+fn fold_slot(summary: &mut EpochSummary, records: &[(String, SlotStats)], leader: Option<&str>) {
+    let completed: Vec<(u64, &str)> = records
+        .iter()
+        .filter_map(|(name, st)| st.completed_at_ns.map(|t| (t, name.as_str())))
+        .collect();
+    let winner = completed.iter().min().filter(|_| completed.len() >= 2).map(|&(_, n)| n);
+
+    if let Some(leader) = leader {
+        summary.leaders.entry(leader.to_string()).or_default().slots += 1;
+    }
+    for (name, st) in records {
+        let first = winner == Some(name.as_str());
+        summary.sources.entry(name.clone()).or_default().slots.add(st, first);
+        if let Some(leader) = leader {
+            let l = summary.leaders.get_mut(leader).expect("inserted above");
+            l.sources.entry(name.clone()).or_default().add(st, first);
+        }
+    }
+}
+
+fn add_pair_deltas(totals: &mut Vec<PairTotals>, now: &[ShredPairState], prev: &[ShredPairState]) {
+    for p in now {
+        let before = prev.iter().find(|q| q.source_a == p.source_a && q.source_b == p.source_b);
+        let (a, b, sum, count) = before
+            .map_or((0, 0, 0, 0), |q| (q.a_wins, q.b_wins, q.lead_sum_us, q.lead_count));
+        let t = match totals
+            .iter()
+            .position(|t| t.source_a == p.source_a && t.source_b == p.source_b)
+        {
+            Some(i) => &mut totals[i],
+            None => {
+                totals.push(PairTotals {
+                    source_a: p.source_a.clone(),
+                    source_b: p.source_b.clone(),
+                    ..Default::default()
+                });
+                totals.last_mut().unwrap()
+            }
+        };
+        t.a_wins += p.a_wins.saturating_sub(a);
+        t.b_wins += p.b_wins.saturating_sub(b);
+        t.lead_sum_us += p.lead_sum_us - sum;
+        t.lead_count += p.lead_count.saturating_sub(count);
+    }
+}
+
+fn pct(part: u64, whole: u64) -> Option<f64> {
+    (whole > 0).then(|| part as f64 / whole as f64 * 100.0)
+}
+
+fn mean(sum: f64, count: u64) -> Option<f64> {
+    (count > 0).then(|| sum / count as f64)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+fn write_atomic(path: &Path, summary: &EpochSummary) -> Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(summary)?)
+        .with_context(|| format!("failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path)
+        .with_context(|| format!("failed to rename {} → {}", tmp.display(), path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,398 @@
+//! `shredtop fleet` — one view over probes running at several sites.
+//!
+//! Pulls each probe's metrics log entries from its web dashboard API
+//! (`[web] enabled = true`, see `web.rs`) and prints two tables:
+//!
+//! * **FEEDS BY SITE** — every feed side by side at each site that runs it:
+//!   rate, coverage, and lead time against that site's own baseline.
+//! * **CROSS-SITE SLOT COMPLETION** — for each feed seen at two or more sites,
+//!   which site has the same slots fully decodable first, and by how much.
+//!   Built from `completed_at_ns` in the probes' recent-slot logs, so it is
+//!   only as accurate as the clock sync between the hosts (PTP, or chrony well
+//!   under a millisecond).
+//!
+//! The first poll of a probe reads `/api/history` so the cross-site table has
+//! data straight away; `--watch` then polls `/api/latest` and keeps
+//! accumulating slots.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::color;
+
+/// Completion times kept per (probe, feed) for cross-site matching.
+const SLOTS_KEPT: usize = 5_000;
+
+/// Connect/read timeout for one probe request.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Probe {
+    name: String,
+    addr: String,
+    /// Most recent log entry received.
+    latest: Option<Value>,
+    /// Error from the most recent poll, if it failed.
+    error: Option<String>,
+    /// Feed name → slot → `completed_at_ns`.
+    completions: HashMap<String, BTreeMap<u64, u64>>,
+    /// Whether `/api/history` has been read.
+    primed: bool,
+}
+
+impl Probe {
+    fn new(name: String, addr: String) -> Self {
+        Self {
+            name,
+            addr,
+            latest: None,
+            error: None,
+            completions: HashMap::new(),
+            primed: false,
+        }
+    }
+
+    fn poll(&mut self) {
+        let path = if self.primed { "/api/latest" } else { "/api/history" };
+        let body = match http_get_json(&self.addr, path) {
+            Ok(v) => v,
+            Err(e) => {
+                self.error = Some(format!("{:#}", e));
+                return;
+            }
+        };
+        self.error = None;
+        let mut entries = match body {
+            Value::Array(entries) => entries,
+            Value::Null => Vec::new(),
+            entry => vec![entry],
+        };
+        for entry in &entries {
+            self.absorb(entry);
+        }
+        if let Some(last) = entries.pop() {
+            self.latest = Some(last);
+        }
+        self.primed = true;
+    }
+
+    fn absorb(&mut self, entry: &Value) {
+        for s in entry["sources"].as_array().into_iter().flatten() {
+            let Some(name) = s["name"].as_str() else { continue };
+            for st in s["recent_slots"].as_array().into_iter().flatten() {
+                let (Some(slot), Some(at)) = (st["slot"].as_u64(), st["completed_at_ns"].as_u64())
+                else {
+                    continue;
+                };
+                let slots = self.completions.entry(name.to_string()).or_default();
+                slots.insert(slot, at);
+                if slots.len() > SLOTS_KEPT {
+                    slots.pop_first();
+                }
+            }
+        }
+    }
+
+    /// Shred-tier sources in the latest entry.
+    fn feeds(&self) -> Vec<&Value> {
+        self.latest
+            .as_ref()
+            .and_then(|e| e["sources"].as_array())
+            .into_iter()
+            .flatten()
+            .filter(|s| !s["is_rpc"].as_bool().unwrap_or(false))
+            .collect()
+    }
+}
+
+/// One feed compared between two sites.
+struct CrossSite {
+    feed: String,
+    faster: String,
+    slower: String,
+    slots: usize,
+    /// Share of matched slots the faster site completed first.
+    win_pct: f64,
+    lead_p50_us: f64,
+    lead_p95_us: f64,
+}
+
+pub fn run(probes: &[(String, String)], watch: Option<u64>, as_json: bool) -> Result<()> {
+    let mut probes: Vec<Probe> = probes
+        .iter()
+        .map(|(name, addr)| Probe::new(name.clone(), normalize_addr(addr)))
+        .collect();
+
+    let Some(secs) = watch else {
+        poll_all(&mut probes);
+        if as_json {
+            println!("{}", serde_json::to_string_pretty(&to_json(&probes))?);
+        } else {
+            for line in render(&probes) {
+                println!("{}", line);
+            }
+        }
+        return Ok(());
+    };
+
+    println!(
+        "{}",
+        color::bold(&format!("SHREDTOP FLEET  —  refreshing every {}s, Ctrl-C to close", secs))
+    );
+    println!();
+    let mut lines_drawn = 0usize;
+    loop {
+        poll_all(&mut probes);
+        if as_json {
+            println!("{}", serde_json::to_string(&to_json(&probes))?);
+        } else {
+            let lines = render(&probes);
+            if lines_drawn > 0 {
+                print!("\x1b[{}A\x1b[0J", lines_drawn);
+            }
+            for line in &lines {
+                println!("{}", line);
+            }
+            lines_drawn = lines.len();
+        }
+        std::thread::sleep(Duration::from_secs(secs.max(1)));
+    }
+}
+
+/// Poll every probe concurrently so one slow site doesn't skew the others'
+/// snapshot times.
+fn poll_all(probes: &mut [Probe]) {
+    std::thread::scope(|s| {
+        for p in probes.iter_mut() {
+            s.spawn(move || p.poll());
+        }
+    });
+}
+
+// ─── Cross-site comparison ────────────────────────────────────────────────────
+
+fn cross_site(probes: &[Probe]) -> Vec<CrossSite> {
+    let feeds: BTreeSet<&String> = probes.iter().flat_map(|p| p.completions.keys()).collect();
+    let mut out = Vec::new();
+    for feed in feeds {
+        for (i, a) in probes.iter().enumerate() {
+            for b in &probes[i + 1..] {
+                let (Some(sa), Some(sb)) = (a.completions.get(feed), b.completions.get(feed)) else {
+                    continue;
+                };
+                // Positive: site `a` completed the slot first.
+                let mut deltas: Vec<i64> = sa
+                    .iter()
+                    .filter_map(|(slot, &ta)| sb.get(slot).map(|&tb| tb as i64 - ta as i64))
+                    .collect();
+                if deltas.is_empty() {
+                    continue;
+                }
+                deltas.sort_unstable();
+                let a_first = deltas.iter().filter(|&&d| d > 0).count();
+                let a_faster = percentile(&deltas, 50) >= 0;
+                let (faster, slower, wins) = if a_faster {
+                    (a, b, a_first)
+                } else {
+                    for d in &mut deltas {
+                        *d = -*d;
+                    }
+                    deltas.reverse();
+                    (b, a, deltas.iter().filter(|&&d| d > 0).count())
+                };
+                out.push(CrossSite {
+                    feed: feed.clone(),
+                    faster: faster.name.clone(),
+                    slower: slower.name.clone(),
+                    slots: deltas.len(),
+                    win_pct: wins as f64 / deltas.len() as f64 * 100.0,
+                    lead_p50_us: percentile(&deltas, 50) as f64 / 1000.0,
+                    lead_p95_us: percentile(&deltas, 95) as f64 / 1000.0,
+                });
+            }
+        }
+    }
+    out
+}
+
+fn percentile(sorted: &[i64], pct: usize) -> i64 {
+    let idx = (sorted.len() * pct / 100).min(sorted.len() - 1);
+    sorted[idx]
+}
+
+// ─── Rendering ────────────────────────────────────────────────────────────────
+
+fn render(probes: &[Probe]) -> Vec<String> {
+    let mut out = Vec::new();
+    let now = chrono::Utc::now().timestamp();
+
+    out.push(color::bold_cyan("PROBES"));
+    for p in probes {
+        let state = match (&p.error, &p.latest) {
+            (Some(e), _) => color::red(&format!("unreachable: {}", e)),
+            (None, None) => color::yellow("no snapshot yet"),
+            (None, Some(e)) => {
+                let age = now - e["ts"].as_i64().unwrap_or(now);
+                format!("snapshot {}s old", age.max(0))
+            }
+        };
+        out.push(format!("  {}  {}  {}", color::rpad(&p.name, 12), color::rpad(&p.addr, 24), state));
+    }
+
+    out.push(String::new());
+    out.push(format!(
+        "{}  {}",
+        color::bold_cyan("FEEDS BY SITE"),
+        color::dim("lead vs each site's own baseline")
+    ));
+    out.push(color::bold(&format!(
+        "  {}  {}  {}  {}  {}  {}  {}",
+        color::rpad("FEED", 20),
+        color::rpad("SITE", 12),
+        color::lpad("SHREDS/s", 9),
+        color::lpad("COV%", 6),
+        color::lpad("BEAT%", 6),
+        color::lpad("LEAD p50", 9),
+        color::lpad("LEAD p95", 9),
+    )));
+    let mut by_feed: BTreeMap<&str, Vec<(&str, &Value)>> = BTreeMap::new();
+    for p in probes {
+        for s in p.feeds() {
+            if let Some(name) = s["name"].as_str() {
+                by_feed.entry(name).or_default().push((p.name.as_str(), s));
+            }
+        }
+    }
+    if by_feed.is_empty() {
+        out.push(color::dim("  No feeds reported yet."));
+    }
+    for (feed, sites) in &by_feed {
+        for (i, (site, s)) in sites.iter().enumerate() {
+            out.push(format!(
+                "  {}  {}  {}  {}  {}  {}  {}",
+                color::rpad(if i == 0 { feed } else { "" }, 20),
+                color::rpad(site, 12),
+                color::lpad(&fmt_num(s["shreds_per_sec"].as_f64()), 9),
+                color::lpad(&fmt_pct(s["coverage_pct"].as_f64()), 6),
+                color::lpad(&fmt_pct(s["beat_rpc_pct"].as_f64()), 6),
+                color::lpad(&fmt_ms(s["lead_time_p50_us"].as_f64()), 9),
+                color::lpad(&fmt_ms(s["lead_time_p95_us"].as_f64()), 9),
+            ));
+        }
+    }
+
+    out.push(String::new());
+    out.push(format!(
+        "{}  {}",
+        color::bold_cyan("CROSS-SITE SLOT COMPLETION"),
+        color::dim("same feed, same slots — requires synchronized clocks")
+    ));
+    let rows = cross_site(probes);
+    if rows.is_empty() {
+        out.push(color::dim(
+            "  No slots completed at more than one site yet (needs shred feeds at ≥2 probes).",
+        ));
+        return out;
+    }
+    out.push(color::bold(&format!(
+        "  {}  {}  {}  {}  {}  {}  {}",
+        color::rpad("FEED", 20),
+        color::rpad("FASTER", 12),
+        color::rpad("SLOWER", 12),
+        color::lpad("WIN%", 6),
+        color::lpad("SLOTS", 7),
+        color::lpad("BY p50", 9),
+        color::lpad("BY p95", 9),
+    )));
+    for r in &rows {
+        out.push(format!(
+            "  {}  {}  {}  {}  {}  {}  {}",
+            color::rpad(&r.feed, 20),
+            color::green(&color::rpad(&r.faster, 12)),
+            color::rpad(&r.slower, 12),
+            color::lpad(&format!("{:.1}%", r.win_pct), 6),
+            color::lpad(&r.slots.to_string(), 7),
+            color::lpad(&format!("{:.2}ms", r.lead_p50_us / 1000.0), 9),
+            color::lpad(&format!("{:.2}ms", r.lead_p95_us / 1000.0), 9),
+        ));
+    }
+    out
+}
+
+fn to_json(probes: &[Probe]) -> Value {
+    json!({
+        "probes": probes.iter().map(|p| json!({
+            "name": p.name,
+            "addr": p.addr,
+            "error": p.error,
+            "latest": p.latest,
+        })).collect::<Vec<_>>(),
+        "cross_site": cross_site(probes).iter().map(|r| json!({
+            "feed": r.feed,
+            "faster": r.faster,
+            "slower": r.slower,
+            "slots": r.slots,
+            "win_pct": r.win_pct,
+            "lead_p50_us": r.lead_p50_us,
+            "lead_p95_us": r.lead_p95_us,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+fn fmt_num(v: Option<f64>) -> String {
+    match v {
+        Some(v) => format!("{:.0}", v),
+        None => "—".into(),
+    }
+}
+
+fn fmt_pct(v: Option<f64>) -> String {
+    match v {
+        Some(v) => format!("{:.1}%", v),
+        None => "—".into(),
+    }
+}
+
+fn fmt_ms(us: Option<f64>) -> String {
+    match us {
+        Some(us) => format!("{:+.1}ms", us / 1000.0),
+        None => "—".into(),
+    }
+}
+
+// ─── HTTP ─────────────────────────────────────────────────────────────────────
+
+/// Accept `host:port`, `http://host:port` and a trailing slash.
+fn normalize_addr(addr: &str) -> String {
+    addr.trim_start_matches("http://").trim_end_matches('/').to_string()
+}
+
+/// Minimal HTTP/1.0 GET — the probe's web server closes the connection after
+/// each response, so the body is everything after the headers.
+fn http_get_json(addr: &str, path: &str) -> Result<Value> {
+    let sock = addr
+        .to_socket_addrs()
+        .with_context(|| format!("cannot resolve {}", addr))?
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("cannot resolve {}", addr))?;
+    let mut stream = TcpStream::connect_timeout(&sock, HTTP_TIMEOUT)
+        .with_context(|| format!("cannot connect to {}", addr))?;
+    stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+    write!(stream, "GET {} HTTP/1.0\r\nHost: {}\r\n\r\n", path, addr)?;
+    let mut resp = Vec::new();
+    stream.read_to_end(&mut resp)?;
+
+    let split = resp
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| anyhow::anyhow!("malformed HTTP response"))?;
+    let head = String::from_utf8_lossy(&resp[..split]);
+    let status = head.lines().next().unwrap_or("");
+    if status.split_whitespace().nth(1) != Some("200") {
+        anyhow::bail!("{} returned '{}' (is [web] enabled on the probe?)", path, status);
+    }
+    serde_json::from_slice(&resp[split + 4..]).with_context(|| format!("invalid JSON from {}", path))
+}
@@ -0,0 +1,82 @@
+//! Per-slot funnel records (`run --funnel`).
+//!
+//! Every slot a shred source finalizes has passed through the same stages:
+//! data shreds seen, FEC sets complete, entries deserialized, transactions
+//! decoded, and transactions forwarded as first copies. Where the counts
+//! fall off shows where that feed loses data. A slot with all its shreds but
+//! few entries points at deserialization; one with many transactions but
+//! few firsts simply lost the race.
+//!
+//! With `--funnel`, `run` appends one `funnel` record per source and
+//! finalized slot to the metrics log at each snapshot, so a dashboard can
+//! follow every slot rather than the snapshot's `recent_slots`.
+
+use serde::Serialize;
+use shred_ingest::{SlotOutcome, SlotStats, SourceMetricsSnapshot};
+use std::collections::BTreeSet;
+
+/// Value of the `type` field that marks a funnel record in the metrics log.
+pub const FUNNEL: &str = "funnel";
+
+#[derive(Serialize)]
+struct FunnelRecord<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    source: &'a str,
+    slot: u64,
+    outcome: &'a SlotOutcome,
+    shreds_expected: u32,
+    shreds_seen: u32,
+    fec_sets: u32,
+    fec_sets_complete: u32,
+    entries: u32,
+    txs_decoded: u32,
+    txs_first: u32,
+}
+
+/// Tracks which slots of each source have been written.
+pub struct FunnelLog {
+    written: Vec<BTreeSet<u64>>,
+}
+
+impl FunnelLog {
+    pub fn new(sources: usize) -> Self {
+        Self { written: vec![BTreeSet::new(); sources] }
+    }
+
+    /// Records for the slots finalized since the previous call, one log line
+    /// each. `snaps` are in the same source order on every call.
+    pub fn lines(&mut self, snaps: &[SourceMetricsSnapshot]) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (snap, written) in snaps.iter().zip(self.written.iter_mut()) {
+            for st in &snap.slot_log {
+                if written.insert(st.slot) {
+                    if let Ok(line) = serde_json::to_string(&record(&snap.name, st)) {
+                        lines.push(line);
+                    }
+                }
+            }
+            // Slots that left the rolling log cannot come back.
+            if let Some(oldest) = snap.slot_log.iter().map(|st| st.slot).min() {
+                written.retain(|&s| s >= oldest);
+            }
+        }
+        lines
+    }
+}
+
+fn record<'a>(source: &'a str, st: &'a SlotStats) -> FunnelRecord<'a> {
+    FunnelRecord {
+        kind: FUNNEL,
+        source,
+        slot: st.slot,
+        outcome: &st.outcome,
+        shreds_expected: st.shreds_expected,
+        shreds_seen: st.shreds_seen,
+        fec_sets: st.fec_sets,
+        fec_sets_complete: st.fec_sets_complete,
+        entries: st.entries,
+        txs_decoded: st.txs_decoded,
+        txs_first: st.txs_first,
+    }
+}
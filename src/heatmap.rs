@@ -0,0 +1,286 @@
+//! `shredtop heatmap` — lead time by hour-of-day and day-of-week.
+//!
+//! Replays the snapshots in the metrics log (rotated files included). The
+//! lead-time counters in a snapshot are cumulative, so each snapshot is
+//! differenced against the source's previous one, and the samples recorded
+//! in between go into a 7×24 grid (UTC) by the later snapshot's time.
+//! Congestion that only shows up at certain hours disappears in cumulative
+//! percentiles; the grid makes it visible. The first snapshot of a log, and
+//! one whose counters went down (a restart without saved state), only start
+//! a new baseline.
+//!
+//! Output is a CSV with one row per populated cell. `--png` additionally
+//! draws one panel per source with plotters: hours (UTC) across, Monday at
+//! the top, each cell coloured by its median lead on the source's own scale,
+//! red for its smallest and green for its largest, with the scale beside the
+//! panel. plotters is behind the `heatmap-png` feature.
+
+use anyhow::{Context, Result};
+use chrono::{Datelike, TimeZone, Timelike, Utc};
+use shred_ingest::LeadHistogram;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::metrics_log::is_snapshot;
+
+const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Lead-time samples of one hour of the week, over every interval that
+/// ended in it.
+#[derive(Default, Clone)]
+struct Cell {
+    intervals: u64,
+    samples: u64,
+    sum_us: f64,
+    hist: LeadHistogram,
+}
+
+impl Cell {
+    fn mean_us(&self) -> f64 {
+        self.sum_us / self.samples as f64
+    }
+
+    /// From the histogram; `None` for logs written without one.
+    fn percentile(&self, p: f64) -> Option<i64> {
+        self.hist.percentile(p)
+    }
+}
+
+type Grid = Vec<[Cell; 24]>;
+
+/// A source's cumulative lead-time counters as of one snapshot.
+struct Counters {
+    samples: u64,
+    sum_us: f64,
+    hist: LeadHistogram,
+}
+
+impl Counters {
+    fn read(s: &serde_json::Value) -> Option<Self> {
+        let samples = s["lead_time_samples"].as_u64()?;
+        let mean = s["lead_time_mean_us"].as_f64().unwrap_or(0.0);
+        let hist = serde_json::from_value(s["lead_time_hist"].clone()).unwrap_or_default();
+        Some(Self { samples, sum_us: mean * samples as f64, hist })
+    }
+
+    /// Samples, their sum and their histogram since `earlier`; `None` if
+    /// the counters were reset in between.
+    fn since(&self, earlier: &Counters) -> Option<(u64, f64, LeadHistogram)> {
+        let samples = self.samples.checked_sub(earlier.samples)?;
+        Some((samples, self.sum_us - earlier.sum_us, self.hist.since(&earlier.hist)?))
+    }
+}
+
+pub fn run(logs: &[PathBuf], output: &Path, png: Option<&Path>) -> Result<()> {
+    anyhow::ensure!(
+        png.is_none() || cfg!(feature = "heatmap-png"),
+        "--png needs a build with the heatmap-png feature (cargo build --features heatmap-png)"
+    );
+    // Source order follows first appearance in the log (i.e. config order).
+    let mut order: Vec<String> = Vec::new();
+    let mut grids: BTreeMap<String, Grid> = BTreeMap::new();
+    let mut lines_read: u64 = 0;
+
+    for log in logs {
+        let content = crate::metrics_log::read_all(log)
+            .with_context(|| format!("failed to read {}", log.display()))?;
+        let mut last: HashMap<String, Counters> = HashMap::new();
+        for line in content.lines().filter(|l| is_snapshot(l)) {
+            let Ok(entry) = serde_json::from_str::<serde_json::Value>(line) else { continue };
+            let Some(ts) = entry["ts"].as_i64() else { continue };
+            let Some(dt) = Utc.timestamp_opt(ts, 0).single() else { continue };
+            lines_read += 1;
+            let day = dt.weekday().num_days_from_monday() as usize;
+            let hour = dt.hour() as usize;
+
+            for s in entry["sources"].as_array().into_iter().flatten() {
+                if s["is_rpc"].as_bool().unwrap_or(false) {
+                    continue;
+                }
+                let Some(name) = s["name"].as_str() else { continue };
+                let Some(now) = Counters::read(s) else { continue };
+                let interval = last.get(name).and_then(|earlier| now.since(earlier));
+                last.insert(name.to_string(), now);
+                let Some((samples, sum_us, hist)) = interval.filter(|i| i.0 > 0) else {
+                    continue;
+                };
+                if !grids.contains_key(name) {
+                    order.push(name.to_string());
+                }
+                let grid = grids
+                    .entry(name.to_string())
+                    .or_insert_with(|| vec![std::array::from_fn(|_| Cell::default()); 7]);
+                let cell = &mut grid[day][hour];
+                cell.intervals += 1;
+                cell.samples += samples;
+                cell.sum_us += sum_us;
+                cell.hist.merge(&hist);
+            }
+        }
+    }
+
+    if grids.is_empty() {
+        anyhow::bail!(
+            "no lead-time samples between {} snapshot(s) — a baseline source (rpc/geyser) is \
+             required",
+            lines_read
+        );
+    }
+
+    let mut csv = String::from(
+        "source,weekday,hour_utc,intervals,samples,lead_mean_us,lead_p50_us,lead_p95_us,\
+         lead_p99_us\n",
+    );
+    let opt = |v: Option<i64>| v.map(|v| v.to_string()).unwrap_or_default();
+    for name in &order {
+        let grid = &grids[name];
+        for (day, row) in grid.iter().enumerate() {
+            for (hour, c) in row.iter().enumerate().filter(|(_, c)| c.samples > 0) {
+                csv.push_str(&format!(
+                    "{},{},{},{},{},{:.0},{},{},{}\n",
+                    name,
+                    DAYS[day],
+                    hour,
+                    c.intervals,
+                    c.samples,
+                    c.mean_us(),
+                    opt(c.percentile(50.0)),
+                    opt(c.percentile(95.0)),
+                    opt(c.percentile(99.0)),
+                ));
+            }
+        }
+    }
+    std::fs::write(output, csv).with_context(|| format!("failed to write {}", output.display()))?;
+    eprintln!(
+        "Wrote {} ({} source(s), {} snapshot(s))",
+        output.display(),
+        order.len(),
+        lines_read
+    );
+
+    #[cfg(feature = "heatmap-png")]
+    if let Some(png) = png {
+        let panels: Vec<(&str, &Grid)> = order.iter().map(|n| (n.as_str(), &grids[n])).collect();
+        png::write(png, &panels)?;
+        eprintln!("Wrote {} (panels top to bottom: {})", png.display(), order.join(", "));
+    }
+    Ok(())
+}
+
+// ─── PNG rendering ────────────────────────────────────────────────────────────
+
+#[cfg(feature = "heatmap-png")]
+mod png {
+    use anyhow::Result;
+    use plotters::prelude::*;
+    use std::path::Path;
+
+    use super::{Cell, Grid, DAYS};
+
+    /// Size of one source's panel, legend included, in pixels.
+    const PANEL_PX: (u32, u32) = (960, 300);
+
+    /// Width of the colour scale on the right of each panel, in pixels.
+    const LEGEND_PX: u32 = 140;
+
+    /// Colour steps in the scale.
+    const LEGEND_STEPS: u32 = 64;
+
+    /// Red (smallest lead) → yellow → green (largest lead).
+    fn scale_color(us: f64, lo: f64, hi: f64) -> RGBColor {
+        let t = if hi > lo { (us - lo) / (hi - lo) } else { 1.0 };
+        let t = t.clamp(0.0, 1.0);
+        let r = if t < 0.5 { 255.0 } else { 255.0 * (1.0 - t) * 2.0 };
+        let g = if t < 0.5 { 255.0 * t * 2.0 } else { 255.0 };
+        RGBColor(r as u8, g as u8, 0)
+    }
+
+    /// Grey for cells without samples.
+    fn cell_color(cell: &Cell, lo: f64, hi: f64) -> RGBColor {
+        if cell.samples == 0 {
+            return RGBColor(64, 64, 64);
+        }
+        scale_color(median_us(cell), lo, hi)
+    }
+
+    /// The cell's median lead, or its mean for logs without histograms.
+    fn median_us(cell: &Cell) -> f64 {
+        cell.percentile(50.0).map_or_else(|| cell.mean_us(), |p| p as f64)
+    }
+
+    /// One panel per source, top to bottom.
+    pub(super) fn write(path: &Path, panels: &[(&str, &Grid)]) -> Result<()> {
+        let size = (PANEL_PX.0, PANEL_PX.1 * panels.len() as u32);
+        let root = BitMapBackend::new(path, size).into_drawing_area();
+        root.fill(&WHITE)?;
+        for (&(name, grid), area) in panels.iter().zip(root.split_evenly((panels.len(), 1))) {
+            let values = grid.iter().flatten().filter(|c| c.samples > 0).map(median_us);
+            let lo = values.clone().fold(f64::INFINITY, f64::min);
+            let hi = values.fold(f64::NEG_INFINITY, f64::max);
+            let (cells, legend) = area.split_horizontally(PANEL_PX.0 - LEGEND_PX);
+
+            // Rows count up from Sunday so that Monday is drawn at the top.
+            let mut chart = ChartBuilder::on(&cells)
+                .caption(name, ("sans-serif", 18))
+                .margin(8)
+                .x_label_area_size(32)
+                .y_label_area_size(40)
+                .build_cartesian_2d((0u32..23).into_segmented(), (0u32..6).into_segmented())?;
+            chart
+                .configure_mesh()
+                .disable_mesh()
+                .x_labels(24)
+                .x_desc("hour (UTC)")
+                .x_label_formatter(&|v| match v {
+                    SegmentValue::CenterOf(hour) => hour.to_string(),
+                    _ => String::new(),
+                })
+                .y_labels(7)
+                .y_label_formatter(&|v| match v {
+                    SegmentValue::CenterOf(row) => {
+                        6usize.checked_sub(*row as usize).map_or("", |day| DAYS[day]).into()
+                    }
+                    _ => String::new(),
+                })
+                .draw()?;
+            chart.draw_series(grid.iter().enumerate().flat_map(|(day, row)| {
+                let y = 6 - day as u32;
+                row.iter().enumerate().map(move |(hour, cell)| {
+                    let x = hour as u32;
+                    let corners = [
+                        (SegmentValue::Exact(x), SegmentValue::Exact(y)),
+                        (SegmentValue::Exact(x + 1), SegmentValue::Exact(y + 1)),
+                    ];
+                    Rectangle::new(corners, cell_color(cell, lo, hi).filled())
+                })
+            }))?;
+
+            // A source whose cells all have one value still gets a scale.
+            let (lo, hi) = if hi > lo { (lo, hi) } else { (lo - 1.0, lo + 1.0) };
+            let mut scale = ChartBuilder::on(&legend)
+                .caption("median lead (µs)", ("sans-serif", 13))
+                .margin(8)
+                .margin_left(4)
+                .x_label_area_size(32)
+                .y_label_area_size(56)
+                .build_cartesian_2d(0.0..1.0, lo..hi)?;
+            scale
+                .configure_mesh()
+                .disable_mesh()
+                .x_labels(0)
+                .x_desc("grey: no data")
+                .y_labels(6)
+                .y_label_formatter(&|us| format!("{:.0}", us))
+                .draw()?;
+            let step = (hi - lo) / LEGEND_STEPS as f64;
+            scale.draw_series((0..LEGEND_STEPS).map(|i| {
+                let from = lo + step * i as f64;
+                let color = scale_color(from + step / 2.0, lo, hi);
+                Rectangle::new([(0.0, from), (1.0, from + step)], color.filled())
+            }))?;
+        }
+        root.present()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+//! Recent per-source history carried in every metrics log entry.
+//!
+//! `shredtop run` keeps each source's shreds/s and lead p50 from the last
+//! [`HISTORY_LEN`] snapshots and writes them, oldest first, as the entry's
+//! `history`. `status` draws them as sparklines from the latest entry alone,
+//! without reading back through the log. At the default 15s interval the
+//! history covers 15 minutes.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+
+use crate::color;
+
+/// Snapshots kept per source.
+pub const HISTORY_LEN: usize = 60;
+
+/// Characters per sparkline in `status`; older points are averaged together.
+const SPARK_WIDTH: usize = 20;
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Serialize)]
+pub struct History {
+    /// Seconds between points.
+    interval_secs: u64,
+    sources: Vec<SourceHistory>,
+}
+
+#[derive(Serialize)]
+struct SourceHistory {
+    name: String,
+    shreds_per_sec: VecDeque<f64>,
+    /// `null` where the source had no lead samples.
+    lead_p50_ms: VecDeque<Option<f64>>,
+}
+
+impl History {
+    pub fn new<'a>(interval_secs: u64, names: impl Iterator<Item = &'a str>) -> Self {
+        let sources = names
+            .map(|name| SourceHistory {
+                name: name.to_string(),
+                shreds_per_sec: VecDeque::with_capacity(HISTORY_LEN),
+                lead_p50_ms: VecDeque::with_capacity(HISTORY_LEN),
+            })
+            .collect();
+        Self { interval_secs, sources }
+    }
+
+    /// Add one snapshot: a `(shreds/s, lead p50 µs)` per source, in the order
+    /// the sources were given to [`History::new`].
+    pub fn push(&mut self, points: impl Iterator<Item = (f64, Option<i64>)>) {
+        for (s, (shreds_per_sec, lead_p50_us)) in self.sources.iter_mut().zip(points) {
+            if s.shreds_per_sec.len() == HISTORY_LEN {
+                s.shreds_per_sec.pop_front();
+                s.lead_p50_ms.pop_front();
+            }
+            s.shreds_per_sec.push_back((shreds_per_sec * 10.0).round() / 10.0);
+            s.lead_p50_ms.push_back(lead_p50_us.map(|us| (us as f64 / 10.0).round() / 100.0));
+        }
+    }
+}
+
+/// The `status` section for the `history` of a metrics log entry: one line
+/// per source with sparklines of shreds/s and lead p50. Empty when the entry
+/// has no history.
+pub fn status_lines(entry: &Value) -> Vec<String> {
+    let history = &entry["history"];
+    let Some(sources) = history["sources"].as_array() else {
+        return Vec::new();
+    };
+    let points = sources.iter().map(|s| series(&s["shreds_per_sec"]).len()).max().unwrap_or(0);
+    if points < 2 {
+        return Vec::new();
+    }
+    let span_secs = history["interval_secs"].as_u64().unwrap_or(0) * points as u64;
+    let mut lines = vec![
+        color::bold(&format!("HISTORY (last {}):", crate::alerts::duration(span_secs))),
+        color::bold(&format!(
+            "  {:<20}  {:<w$}  {:>8}  {:<w$}  {:>9}",
+            "SOURCE",
+            "SHREDS/s",
+            "NOW",
+            "LEAD p50",
+            "NOW",
+            w = SPARK_WIDTH
+        )),
+    ];
+    let rpc: Vec<&str> = entry["sources"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|s| s["is_rpc"].as_bool().unwrap_or(false))
+        .filter_map(|s| s["name"].as_str())
+        .collect();
+    for s in sources {
+        let name = s["name"].as_str().unwrap_or("?");
+        let shreds = series(&s["shreds_per_sec"]);
+        let lead = series(&s["lead_p50_ms"]);
+        let is_rpc = rpc.contains(&name);
+        let (shreds_spark, shreds_now) = if is_rpc {
+            (String::new(), "—".to_string())
+        } else {
+            let now = shreds.last().copied().flatten().unwrap_or(0.0);
+            (sparkline(&shreds), format!("{:.0}", now))
+        };
+        let lead_now = match lead.last().copied().flatten() {
+            Some(ms) => format!("{:+.1}ms", ms),
+            None => "—".to_string(),
+        };
+        lines.push(format!(
+            "  {:<20}  {:<w$}  {:>8}  {:<w$}  {:>9}",
+            name,
+            shreds_spark,
+            shreds_now,
+            sparkline(&lead),
+            lead_now,
+            w = SPARK_WIDTH
+        ));
+    }
+    lines
+}
+
+fn series(values: &Value) -> Vec<Option<f64>> {
+    values.as_array().map(|a| a.iter().map(Value::as_f64).collect()).unwrap_or_default()
+}
+
+/// `values` scaled between their minimum and maximum, at most
+/// [`SPARK_WIDTH`] characters; gaps are blank.
+fn sparkline(values: &[Option<f64>]) -> String {
+    let chunk = values.len().div_ceil(SPARK_WIDTH).max(1);
+    let points: Vec<Option<f64>> = values
+        .chunks(chunk)
+        .map(|c| {
+            let known: Vec<f64> = c.iter().flatten().copied().collect();
+            (!known.is_empty()).then(|| known.iter().sum::<f64>() / known.len() as f64)
+        })
+        .collect();
+    let known = points.iter().flatten();
+    let min = known.clone().copied().fold(f64::INFINITY, f64::min);
+    let max = known.copied().fold(f64::NEG_INFINITY, f64::max);
+    points
+        .iter()
+        .map(|p| match p {
+            None => ' ',
+            Some(_) if max <= min => SPARK_CHARS[SPARK_CHARS.len() / 2],
+            Some(v) => {
+                let level = (v - min) / (max - min) * (SPARK_CHARS.len() - 1) as f64;
+                SPARK_CHARS[level.round() as usize]
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,192 @@
+//! InfluxDB output sink (`type = "influx"` in `[[outputs]]`).
+//!
+//! Every metrics snapshot is written to the InfluxDB v2 write API
+//! (`/api/v2/write`, which InfluxDB 1.8 also serves) as line protocol:
+//!
+//! - `shredtop_source`, one point per source, tagged `source`, `tier`
+//!   (`shred` or `rpc`) and the source's `labels`;
+//! - `shredtop_race`, one point per source pair of the shred, slot, slot
+//!   start, leader handoff and availability races, tagged `race`,
+//!   `source_a` and `source_b`.
+//!
+//! The fields are the numeric and boolean fields of the source or pair in
+//! the metrics log entry, so a field added there shows up here as well.
+//! Nested objects (histograms, windows, errors) are left out. Points carry
+//! the snapshot's time in seconds.
+//!
+//! A failed write is not retried; the next snapshot is written as usual.
+//! Failures are logged once until a write succeeds again.
+
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use shred_ingest::output::{EventMask, OutputSink, Snapshot};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InfluxSettings {
+    /// Server, e.g. "http://127.0.0.1:8086".
+    url: String,
+    bucket: String,
+    /// API token; for InfluxDB 1.8, "user:password".
+    token: Option<String>,
+    /// Required by InfluxDB 2.x unless the token is scoped to one org.
+    org: Option<String>,
+}
+
+pub struct InfluxSink {
+    agent: ureq::Agent,
+    /// The write endpoint.
+    url: String,
+    settings: InfluxSettings,
+    failing: bool,
+}
+
+impl InfluxSink {
+    pub fn from_settings(settings: Value) -> Result<Box<dyn OutputSink>> {
+        let settings: InfluxSettings = serde_json::from_value(settings)?;
+        if !settings.url.starts_with("http://") && !settings.url.starts_with("https://") {
+            anyhow::bail!("influx url must start with http:// or https://");
+        }
+        anyhow::ensure!(!settings.bucket.is_empty(), "influx bucket must not be empty");
+        Ok(Box::new(Self {
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(10)).build(),
+            url: format!("{}/api/v2/write", settings.url.trim_end_matches('/')),
+            settings,
+            failing: false,
+        }))
+    }
+
+    fn write(&self, body: &str) -> Result<()> {
+        let mut req = self
+            .agent
+            .post(&self.url)
+            .query("bucket", &self.settings.bucket)
+            .query("precision", "s")
+            .set("Content-Type", "text/plain; charset=utf-8")
+            .set("User-Agent", concat!("shredtop/", env!("CARGO_PKG_VERSION")));
+        if let Some(org) = &self.settings.org {
+            req = req.query("org", org);
+        }
+        if let Some(token) = &self.settings.token {
+            req = req.set("Authorization", &format!("Token {}", token));
+        }
+        match req.send_string(body) {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(code, resp)) => {
+                let text = resp.into_string().unwrap_or_default();
+                anyhow::bail!("HTTP {}: {}", code, text.trim())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl OutputSink for InfluxSink {
+    fn on_snapshot(&mut self, snapshot: &Snapshot) {
+        let Ok(entry) = serde_json::from_str::<Value>(&snapshot.json) else { return };
+        let body = lines(&entry, snapshot.ts);
+        if body.is_empty() {
+            return;
+        }
+        match self.write(&body) {
+            Ok(()) if self.failing => {
+                self.failing = false;
+                tracing::info!("influx output: writes to {} succeeding again", self.url);
+            }
+            Ok(()) => {}
+            Err(e) if !self.failing => {
+                self.failing = true;
+                tracing::warn!("influx output: write to {} failed: {:#}", self.url, e);
+            }
+            Err(_) => {}
+        }
+    }
+
+    fn events(&self) -> EventMask {
+        EventMask { transactions: false, snapshots: true, races: false }
+    }
+}
+
+/// The line protocol points of one metrics log entry.
+fn lines(entry: &Value, ts: u64) -> String {
+    let mut out = String::new();
+    for s in entry["sources"].as_array().into_iter().flatten() {
+        let Some(name) = s["name"].as_str() else { continue };
+        let tier = if s["is_rpc"].as_bool().unwrap_or(false) { "rpc" } else { "shred" };
+        let mut tags = vec![("source", name), ("tier", tier)];
+        if let Some(labels) = s["labels"].as_object() {
+            tags.extend(labels.iter().filter_map(|(k, v)| Some((k.as_str(), v.as_str()?))));
+        }
+        point(&mut out, "shredtop_source", &tags, s, &["is_rpc"], ts);
+    }
+    let races = [
+        ("shred_race", "shred"),
+        ("slot_race", "slot"),
+        ("start_race", "start"),
+        ("handoff_race", "handoff"),
+        ("avail_race", "avail"),
+    ];
+    for (key, race) in races {
+        for p in entry[key].as_array().into_iter().flatten() {
+            let (Some(a), Some(b)) = (p["source_a"].as_str(), p["source_b"].as_str()) else {
+                continue;
+            };
+            let tags = [("race", race), ("source_a", a), ("source_b", b)];
+            point(&mut out, "shredtop_race", &tags, p, &[], ts);
+        }
+    }
+    let clock = &entry["clock_sync"];
+    if let Some(source) = clock["source"].as_str() {
+        point(&mut out, "shredtop_clock_sync", &[("source", source)], clock, &[], ts);
+    }
+    out
+}
+
+/// Append one point with the scalar fields of `obj`, except `skip`.
+fn point(
+    out: &mut String,
+    measurement: &str,
+    tags: &[(&str, &str)],
+    obj: &Value,
+    skip: &[&str],
+    ts: u64,
+) {
+    let Some(obj) = obj.as_object() else { return };
+    let fields = fields(obj, skip);
+    if fields.is_empty() {
+        return;
+    }
+    out.push_str(measurement);
+    for (k, v) in tags.iter().filter(|(_, v)| !v.is_empty()) {
+        let _ = write!(out, ",{}={}", escape(k), escape(v));
+    }
+    out.push(' ');
+    out.push_str(&fields);
+    let _ = writeln!(out, " {}", ts);
+}
+
+fn fields(obj: &Map<String, Value>, skip: &[&str]) -> String {
+    let mut fields = String::new();
+    for (k, v) in obj.iter().filter(|(k, _)| !skip.contains(&k.as_str())) {
+        let value = match v {
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) if n.is_f64() => n.to_string(),
+            Value::Number(n) => format!("{}i", n),
+            _ => continue,
+        };
+        if !fields.is_empty() {
+            fields.push(',');
+        }
+        let _ = write!(fields, "{}={}", escape(k), value);
+    }
+    fields
+}
+
+/// Escape a measurement, tag key, tag value or field key.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace(',', "\\,").replace('=', "\\=").replace(' ', "\\ ")
+}
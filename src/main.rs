@@ -7,23 +7,44 @@ use anyhow::Result;
 use clap::Parser;
 use tracing_subscriber::EnvFilter;
 
+mod alerts;
 mod analyze;
 mod bench;
 mod capture;
 mod color;
 mod capture_status;
 mod cli;
+mod clock_sync;
 mod config;
+mod confirm_audit;
+mod ctl;
 mod discover;
+mod epoch_report;
+mod fleet;
+mod funnel;
+mod heatmap;
+mod history;
+mod influx;
+mod metrics_log;
 mod metrics_server;
 mod monitor;
+mod profile;
+mod remote_write;
+mod replay;
+mod rtt;
 mod run;
+mod run_start;
+mod sample;
 mod service;
+mod state;
 mod status;
+mod trace;
+mod tx_sink;
 mod uninstall;
 mod upgrade;
+mod web;
 
-use cli::{CaptureAction, Cli, Commands, ServiceAction};
+use cli::{CaptureAction, Cli, Commands, CtlAction, ServiceAction, TraceAction};
 
 fn main() -> Result<()> {
     tracing_subscriber::fmt()
@@ -31,10 +52,22 @@ fn main() -> Result<()> {
         .init();
 
     let cli = Cli::parse();
+    let profile = cli.profile.as_deref();
+    if let Some(name) = profile {
+        profile::validate(name)?;
+    }
+    let paths = profile::Paths::new(profile);
 
     // Load config (except for commands that don't need it)
     let config = match &cli.command {
-        Commands::Init | Commands::Upgrade { .. } | Commands::Status | Commands::Service { .. } | Commands::Monitor { .. } | Commands::Capture { .. } | Commands::Analyze { .. } | Commands::Uninstall => None,
+        Commands::Discover { ssh, .. } if !ssh.is_empty() => None,
+        // Discovery writes a profile that may not exist yet.
+        Commands::Discover { .. }
+            if profile.is_some_and(|name| !profile::defined(&cli.config, name).unwrap_or(true)) =>
+        {
+            Some(toml::from_str::<config::ProbeConfig>("")?)
+        }
+        Commands::Init { .. } | Commands::Bench { offline: Some(_), .. } | Commands::Upgrade { .. } | Commands::Status { .. } | Commands::Service { .. } | Commands::Monitor { .. } | Commands::Capture { .. } | Commands::Analyze { .. } | Commands::Replay { .. } | Commands::Ctl { .. } | Commands::Trace { .. } | Commands::Heatmap { .. } | Commands::Fleet { .. } | Commands::Uninstall => None,
         _ => {
             if !cli.config.exists() {
                 std::fs::write(&cli.config, b"")?;
@@ -43,51 +76,159 @@ fn main() -> Result<()> {
                     cli.config.display()
                 );
             }
-            Some(config::ProbeConfig::load(&cli.config)?)
+            Some(config::ProbeConfig::load(&cli.config, profile)?)
         }
     };
 
     match cli.command {
-        Commands::Init => {
+        Commands::Init { from_running: false } => {
             let example = config::ProbeConfig::default_example();
             print!("{}", toml::to_string_pretty(&example)?);
         }
-        Commands::Upgrade { source } => {
-            if source {
+        Commands::Init { from_running: true } => {
+            let mut notes = Vec::new();
+            let cfg = discover::from_running(&mut notes)?;
+            for note in &notes {
+                eprintln!("shredtop init: {}", note);
+            }
+            if cfg.sources.is_empty() {
+                eprintln!("shredtop init: no feeds found");
+            }
+            print!("{}", toml::to_string_pretty(&cfg)?);
+        }
+        Commands::Upgrade { source, channel, pubkey, gpg_keyring, skip_signature, rollback } => {
+            if rollback {
+                upgrade::rollback()?;
+            } else if source {
                 upgrade::run_from_source()?;
             } else {
-                upgrade::run()?;
+                let opts = upgrade::UpgradeOptions {
+                    channel: &channel,
+                    pubkey: &pubkey,
+                    gpg_keyring: gpg_keyring.as_deref(),
+                    skip_signature,
+                };
+                upgrade::run(&opts)?;
             }
         }
-        Commands::Discover => {
-            discover::run(config.as_ref().unwrap(), &cli.config)?;
+        Commands::Discover { ssh, combined } if !ssh.is_empty() => {
+            discover::run_ssh(&ssh, &cli.config, combined)?;
+        }
+        Commands::Discover { .. } => {
+            discover::run(config.as_ref().unwrap(), &cli.config, profile, &paths)?;
         }
-        Commands::Monitor { interval } => {
-            monitor::run(interval)?;
+        Commands::Monitor { interval, slots } => {
+            monitor::run(interval, slots, &paths.log)?;
         }
-        Commands::Bench { duration, output } => {
-            bench::run(config.as_ref().unwrap(), duration, output)?;
+        Commands::Bench { offline: Some(pcap), output, .. } => {
+            bench::run_offline(&pcap, output)?;
         }
-        Commands::Run { interval, log } => {
-            run::run(config.as_ref().unwrap(), interval, log)?;
+        Commands::Bench {
+            duration,
+            output,
+            early_ms,
+            interval_ms,
+            offline: None,
+            matrix,
+            busy_poll_us,
+            rcvbuf_mb,
+            batch,
+            pin,
+        } => {
+            let config = config.as_ref().unwrap();
+            let sweep = bench::Sweep::new(busy_poll_us, rcvbuf_mb, batch, &pin);
+            if matrix {
+                let path = &cli.config;
+                bench::run_matrix(config, path, profile, &sweep, duration, output, early_ms)?;
+            } else {
+                let settings = sweep.single()?;
+                bench::run(config, duration, output, early_ms, interval_ms, settings)?;
+            }
+        }
+        Commands::Run { interval, interval_ms, funnel, log, state, fresh, ctl_socket } => {
+            let config = config.as_ref().unwrap();
+            let log = log.unwrap_or(paths.log);
+            let state = state.unwrap_or(paths.state);
+            let ctl_socket = ctl_socket.unwrap_or(paths.ctl_socket);
+            run::run(config, interval, interval_ms, funnel, log, state, fresh, ctl_socket)?;
         }
-        Commands::Status => {
-            status::run()?;
+        Commands::Status { check: false } => {
+            status::run(&paths.log)?;
+        }
+        Commands::Status { check: true } => {
+            let health = if cli.config.exists() {
+                config::ProbeConfig::load(&cli.config, profile)?.health
+            } else {
+                config::HealthConfig::default()
+            };
+            std::process::exit(status::check(&health, &paths.log));
         }
         Commands::Service { action } => match action {
-            ServiceAction::Start => service::install(&cli.config)?,
-            ServiceAction::Stop => service::control("stop")?,
-            ServiceAction::Uninstall => service::uninstall()?,
-            ServiceAction::Restart => service::control("restart")?,
-            ServiceAction::Status => service::control("status")?,
-            ServiceAction::Enable => service::control("enable")?,
-            ServiceAction::Disable => service::control("disable")?,
+            ServiceAction::Start => service::install(&cli.config, profile, &paths)?,
+            ServiceAction::Stop => service::control("stop", &paths.unit)?,
+            ServiceAction::Uninstall => service::uninstall(&paths.unit)?,
+            ServiceAction::Restart => service::control("restart", &paths.unit)?,
+            ServiceAction::Status => service::control("status", &paths.unit)?,
+            ServiceAction::Enable => service::control("enable", &paths.unit)?,
+            ServiceAction::Disable => service::control("disable", &paths.unit)?,
+            ServiceAction::Logs { follow, since, instance } => {
+                service::logs(follow, since.as_deref(), instance.as_deref().or(profile))?
+            }
         },
         Commands::Capture { action } => match action {
-            CaptureAction::List => capture_status::run(&cli.config)?,
+            CaptureAction::List { stats, watch } => {
+                capture_status::run(&cli.config, profile, stats, watch)?
+            }
+            CaptureAction::Start { group, iface, duration, output_dir, format } => {
+                let cfg = config::CaptureConfig {
+                    formats: format,
+                    output_dir,
+                    ..Default::default()
+                };
+                capture::start(&group, &iface, duration, &cfg)?
+            }
         },
-        Commands::Analyze { pcap, feed, min_matched } => {
-            analyze::run(&pcap, &feed, min_matched)?;
+        Commands::Analyze {
+            pcap,
+            feed,
+            min_matched,
+            slots,
+            histogram,
+            bucket_us,
+            ascii,
+            json,
+            live,
+            interval,
+        } => {
+            if live {
+                analyze::run_live(&pcap, &feed, interval)?;
+            } else {
+                let hist = analyze::HistogramOpts { out: histogram, bucket_us, ascii };
+                analyze::run(&pcap, &feed, min_matched, slots, &hist, json.as_deref())?;
+            }
+        }
+        Commands::Replay { pcap, feed, output } => {
+            replay::run(&pcap, &feed, output)?;
+        }
+        Commands::Heatmap { output, png, mut log } => {
+            if log.is_empty() {
+                log.push(paths.log);
+            }
+            heatmap::run(&log, &output, png.as_deref())?;
+        }
+        Commands::Fleet { probes, watch, json } => {
+            fleet::run(&probes, watch, json)?;
+        }
+        Commands::Ctl { action, socket } => {
+            let socket = socket.unwrap_or(paths.ctl_socket);
+            match action {
+                CtlAction::Pause { source } => ctl::send(&socket, &format!("pause {}", source))?,
+                CtlAction::Resume { source } => ctl::send(&socket, &format!("resume {}", source))?,
+                CtlAction::List => ctl::send(&socket, "list")?,
+            }
+        }
+        Commands::Trace { action: TraceAction::Show { signature, file } } => {
+            trace::show(&cli.config, profile, file, &signature)?;
         }
         Commands::Uninstall => {
             uninstall::run(&cli.config)?;
@@ -0,0 +1,229 @@
+//! The metrics JSONL log written by `shredtop run`, with optional rotation.
+//!
+//! [`LogWriter`] appends one line per snapshot and, when `[log]` limits are
+//! set, rotates the live file to `<log>.1` (`<log>.1.gz` with `gzip`), shifting
+//! older files up by one and deleting any beyond `keep`.
+//!
+//! Every file starts with the run's `run_start` record (see
+//! [`crate::run_start`]), rewritten after each rotation. With `--interval-ms`,
+//! `sample` records (see [`crate::sample`]) sit between the snapshots.
+//! Snapshot readers skip both.
+//!
+//! The readers look at the live file first and fall back to the newest rotated
+//! file, so `status` and `monitor` keep showing the last snapshot across the
+//! rotation boundary. Gzipped files are decompressed transparently.
+//!
+//! The forensics and trace logs are written through a [`LogWriter`] too, with
+//! the same limits. Each keeps its history across restarts, so it is only
+//! rotated once a limit is reached.
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::config::LogConfig;
+use crate::run_start::is_run_start;
+
+pub struct LogWriter {
+    path: PathBuf,
+    cfg: LogConfig,
+    bytes: u64,
+    opened_at: Instant,
+    /// Written at the top of the log and of every file after a rotation.
+    header: Option<String>,
+}
+
+impl LogWriter {
+    /// Start a log for a new run. With rotation enabled, a previous run's log
+    /// is rotated away so its history is kept; otherwise it is truncated so
+    /// readers immediately reflect this run.
+    pub fn open(path: PathBuf, cfg: LogConfig) -> Self {
+        let mut w = Self { path, cfg, bytes: 0, opened_at: Instant::now(), header: None };
+        let has_data = fs::metadata(&w.path).map(|m| m.len() > 0).unwrap_or(false);
+        if w.cfg.enabled() && has_data {
+            if let Err(e) = w.rotate() {
+                tracing::warn!("log rotation of {} failed: {}", w.path.display(), e);
+            }
+        } else if let Ok(f) = File::create(&w.path) {
+            drop(f);
+        }
+        w
+    }
+
+    /// Continue a log that is kept across restarts, rotating it only once a
+    /// `[log]` limit is reached. Its age counts from now.
+    pub fn append_to(path: PathBuf, cfg: LogConfig) -> io::Result<Self> {
+        let bytes = OpenOptions::new().create(true).append(true).open(&path)?.metadata()?.len();
+        Ok(Self { path, cfg, bytes, opened_at: Instant::now(), header: None })
+    }
+
+    /// Write `line` now and again at the top of every file after a rotation.
+    pub fn set_header(&mut self, line: String) {
+        self.write(&line);
+        self.header = Some(line);
+    }
+
+    /// Append one serialized entry, rotating first if a limit has been reached.
+    pub fn append(&mut self, line: &str) {
+        if self.due() {
+            match self.rotate() {
+                Ok(()) => {
+                    if let Some(header) = self.header.clone() {
+                        self.write(&header);
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("log rotation of {} failed: {}", self.path.display(), e)
+                }
+            }
+        }
+        self.write(line);
+    }
+
+    fn write(&mut self, line: &str) {
+        // Reopened per write so a log deleted or moved by hand is recreated.
+        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&self.path) {
+            if writeln!(file, "{}", line).is_ok() {
+                self.bytes += line.len() as u64 + 1;
+            }
+        }
+    }
+
+    fn due(&self) -> bool {
+        let by_size = self.cfg.rotate_mb > 0 && self.bytes >= self.cfg.rotate_mb * 1024 * 1024;
+        let by_age = self.cfg.rotate_hours > 0
+            && self.opened_at.elapsed() >= Duration::from_secs(self.cfg.rotate_hours * 3600);
+        by_size || by_age
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.bytes = 0;
+        self.opened_at = Instant::now();
+
+        // Drop the oldest, then shift `.N` → `.N+1`. Both the plain and the
+        // gzipped name are handled so toggling `gzip` leaves nothing behind.
+        for gz in [false, true] {
+            remove_if_exists(&numbered(&self.path, self.cfg.keep, gz))?;
+        }
+        for n in (1..self.cfg.keep).rev() {
+            for gz in [false, true] {
+                let from = numbered(&self.path, n, gz);
+                if from.exists() {
+                    fs::rename(&from, numbered(&self.path, n + 1, gz))?;
+                }
+            }
+        }
+
+        if self.cfg.keep == 0 {
+            remove_if_exists(&self.path)?;
+            return Ok(());
+        }
+        let first = numbered(&self.path, 1, false);
+        fs::rename(&self.path, &first)?;
+        if self.cfg.gzip {
+            let mut input = File::open(&first)?;
+            let out = File::create(numbered(&self.path, 1, true))?;
+            let mut enc = GzEncoder::new(out, Compression::default());
+            io::copy(&mut input, &mut enc)?;
+            enc.finish()?.sync_all()?;
+            fs::remove_file(&first)?;
+        }
+        Ok(())
+    }
+}
+
+/// `<log>.N`, or `<log>.N.gz`.
+fn numbered(path: &Path, n: usize, gz: bool) -> PathBuf {
+    let mut s = path.as_os_str().to_owned();
+    s.push(format!(".{}", n));
+    if gz {
+        s.push(".gz");
+    }
+    PathBuf::from(s)
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Rotated files of `path` that exist, newest first.
+pub fn rotated(path: &Path) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    for n in 1.. {
+        let found: Vec<PathBuf> = [numbered(path, n, false), numbered(path, n, true)]
+            .into_iter()
+            .filter(|p| p.exists())
+            .collect();
+        if found.is_empty() {
+            break;
+        }
+        out.extend(found);
+    }
+    out
+}
+
+/// Whether the live log or any rotated file exists.
+pub fn exists(path: &Path) -> bool {
+    path.exists() || !rotated(path).is_empty()
+}
+
+/// Contents of one log file, gunzipping `.gz` files.
+pub fn read_file(path: &Path) -> io::Result<String> {
+    if path.extension().is_some_and(|e| e == "gz") {
+        let mut s = String::new();
+        GzDecoder::new(File::open(path)?).read_to_string(&mut s)?;
+        Ok(s)
+    } else {
+        fs::read_to_string(path)
+    }
+}
+
+/// Every line of the log, oldest first: rotated files, then the live file.
+pub fn read_all(path: &Path) -> io::Result<String> {
+    let mut content = String::new();
+    for p in rotated(path).iter().rev() {
+        content.push_str(&read_file(p)?);
+    }
+    match read_file(path) {
+        Ok(s) => content.push_str(&s),
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !content.is_empty() => {}
+        Err(e) => return Err(e),
+    }
+    Ok(content)
+}
+
+/// The most recent snapshot: the last line of the live log, or of the newest
+/// rotated file if the live log has nothing yet.
+pub fn read_last_entry(path: &Path) -> Option<serde_json::Value> {
+    let last = |content: String| {
+        content.lines().filter(|l| is_snapshot(l)).last().map(str::to_string)
+    };
+    let line = read_file(path)
+        .ok()
+        .and_then(last)
+        .or_else(|| rotated(path).first().and_then(|p| read_file(p).ok()).and_then(last))?;
+    serde_json::from_str(&line).ok()
+}
+
+/// Whether a log line is a snapshot rather than a typed record such as
+/// `run_start` or `sample`.
+pub fn is_snapshot(line: &str) -> bool {
+    !line.is_empty() && !line.starts_with("{\"type\":")
+}
+
+/// The `run_start` record of the current run: the first line of the live log,
+/// or of the newest rotated file if the live log is missing.
+pub fn read_run_start(path: &Path) -> Option<serde_json::Value> {
+    let content = read_file(path)
+        .ok()
+        .or_else(|| rotated(path).first().and_then(|p| read_file(p).ok()))?;
+    let line = content.lines().next().filter(|l| is_run_start(l))?;
+    serde_json::from_str(line).ok()
+}
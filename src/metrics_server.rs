@@ -6,17 +6,33 @@
 //! The server runs on its own thread and reads the latest snapshot via a
 //! `crossbeam_channel::Receiver<MetricsSnapshot>`. The sender drops old
 //! values — only the most recent snapshot is served.
+//!
+//! The metric set itself comes from [`samples`], which the remote-write
+//! client ([`crate::remote_write`]) ships as well.
 
 use std::io::Write;
 use std::net::TcpListener;
 use std::sync::{Arc, Mutex};
 
-use shred_ingest::SourceMetricsSnapshot;
+use shred_ingest::{BudgetSnapshot, ClockCheckSnapshot, SourceMetricsSnapshot};
+
+use crate::capture::CaptureIoSnapshot;
+use crate::clock_sync::ClockSyncSnapshot;
 
 /// Snapshot of all source metrics at a point in time.
 #[derive(Clone)]
 pub struct MetricsSnapshot {
     pub sources: Vec<SourceMetricsSnapshot>,
+    /// Capture file I/O, when `[capture]` is enabled.
+    pub capture: Option<CaptureIoSnapshot>,
+    /// Memory budget use, when `[limits]` sets one.
+    pub memory: Option<BudgetSnapshot>,
+    /// `latency_budget_ms` of the config, for `shredtop_in_budget_pct`.
+    pub latency_budget_ms: Option<u64>,
+    /// Timestamp check counts (see `shred_ingest::clock`).
+    pub clock: ClockCheckSnapshot,
+    /// Clock discipline status, unless `[clock_sync]` is off or unanswered.
+    pub clock_sync: Option<ClockSyncSnapshot>,
 }
 
 /// Spawn the metrics server thread.
@@ -72,82 +88,309 @@ impl MetricsUpdater {
     }
 }
 
+/// Label names shredtop sets on a source's series itself, here or in the
+/// InfluxDB output. Configured source labels may not use them.
+pub const RESERVED_LABELS: &[&str] = &["source", "tier", "quantile", "variant", "version", "sender"];
+
+/// One gauge sample of the exported metric set.
+pub struct Sample {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+}
+
 /// Render a `MetricsSnapshot` as Prometheus text format.
 fn render(snap: &MetricsSnapshot) -> String {
+    use std::fmt::Write;
     let mut out = String::with_capacity(2048);
+    for s in samples(snap) {
+        let _ = writeln!(out, "# HELP {} {}", s.name, s.help);
+        let _ = writeln!(out, "# TYPE {} gauge", s.name);
+        if s.labels.is_empty() {
+            let _ = writeln!(out, "{} {}", s.name, s.value);
+        } else {
+            let lstr: Vec<String> =
+                s.labels.iter().map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v))).collect();
+            let _ = writeln!(out, "{}{{{}}} {}", s.name, lstr.join(","), s.value);
+        }
+    }
+    out
+}
+
+/// Label values escape `\`, `"` and newlines; source names and configured
+/// labels are free-form.
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Every exported sample for `snap`.
+pub fn samples(snap: &MetricsSnapshot) -> Vec<Sample> {
+    let mut samples = Vec::new();
 
     for s in &snap.sources {
-        let name = s.name;
+        let name = &*s.name;
+        let first = samples.len();
 
-        gauge(&mut out, "shredtop_shreds_received_total",
+        gauge(&mut samples, "shredtop_shreds_received_total",
             &[("source", name)], s.shreds_received as f64,
             "Total shreds received");
-        gauge(&mut out, "shredtop_shreds_dropped_total",
+        gauge(&mut samples, "shredtop_shreds_dropped_total",
             &[("source", name)], s.shreds_dropped as f64,
             "Shreds dropped (channel full)");
-        gauge(&mut out, "shredtop_shreds_invalid_total",
+        gauge(&mut samples, "shredtop_shreds_invalid_total",
             &[("source", name)], s.shreds_invalid as f64,
-            "Malformed/unknown packets rejected before decoder");
+            "Shred packets with an unknown variant rejected before decoder");
+        gauge(&mut samples, "shredtop_non_shred_packets_total",
+            &[("source", name)], s.non_shred_packets as f64,
+            "Packets that could not be shreds, dropped before any shred counter");
+        gauge(&mut samples, "shredtop_repair_requests_total",
+            &[("source", name)], s.repair_requests as f64,
+            "Data shreds requested from repair peers");
+        gauge(&mut samples, "shredtop_shreds_repaired_total",
+            &[("source", name)], s.shreds_repaired as f64,
+            "Missing data shreds filled by repair responses");
+        gauge(&mut samples, "shredtop_shreds_decode_skipped_total",
+            &[("source", name)], s.shreds_decode_skipped as f64,
+            "Shreds left undecoded as too far behind the cluster tip");
+        gauge(&mut samples, "shredtop_slots_expired_shared_tip_total",
+            &[("source", name)], s.slots_expired_shared_tip as f64,
+            "Slots expired by the shared cluster tip that the feed's own tip would have kept");
+        gauge(&mut samples, "shredtop_shred_sig_verified_total",
+            &[("source", name)], s.shreds_sig_verified as f64,
+            "Shreds signed by the scheduled slot leader");
+        gauge(&mut samples, "shredtop_shred_sig_invalid_total",
+            &[("source", name)], s.shreds_sig_invalid as f64,
+            "Shreds whose signature does not match the scheduled slot leader");
+        gauge(&mut samples, "shredtop_shred_sig_unchecked_total",
+            &[("source", name)], s.shreds_sig_unchecked as f64,
+            "Shreds not checked: slot leader unknown, or not a Merkle shred");
+        gauge(&mut samples, "shredtop_txs_slot_mismatch_total",
+            &[("source", name)], s.txs_slot_mismatch as f64,
+            "Transactions delivered in another slot than their signature was first seen in");
+        gauge(&mut samples, "shredtop_txs_dropped_total",
+            &[("source", name)], s.txs_dropped as f64,
+            "Decoded transactions dropped (fan-in channel full)");
+        gauge(&mut samples, "shredtop_source_errors_total",
+            &[("source", name)], s.errors_total as f64,
+            "Failures reported by the source (bind, receive, disconnect, rpc)");
+        gauge(&mut samples, "shredtop_source_restarts_total",
+            &[("source", name)], s.restarts as f64,
+            "Restarts of the source after its threads stopped");
+        gauge(&mut samples, "shredtop_source_degraded",
+            &[("source", name)], if s.degraded { 1.0 } else { 0.0 },
+            "1 while the source's threads are stopped");
+        for (variant, n) in &s.shred_mix.variants {
+            gauge(&mut samples, "shredtop_shreds_by_variant_total",
+                &[("source", name), ("variant", variant)], *n as f64,
+                "Shreds received by variant family, before filtering");
+        }
+        for (version, n) in &s.shred_mix.versions {
+            gauge(&mut samples, "shredtop_shreds_by_version_total",
+                &[("source", name), ("version", &version.to_string())], *n as f64,
+                "Shreds received by shred version, before filtering");
+        }
+        for (sender, c) in &s.senders.senders {
+            gauge(&mut samples, "shredtop_shreds_by_sender_total",
+                &[("source", name), ("sender", sender)], c.packets as f64,
+                "Shreds received by UDP sender address");
+            gauge(&mut samples, "shredtop_first_arrivals_by_sender_total",
+                &[("source", name), ("sender", sender)], c.firsts as f64,
+                "Shreds a sender delivered before any other sender on the same source");
+        }
+
+        if s.socket_rcvbuf_bytes > 0 {
+            gauge(&mut samples, "shredtop_socket_rcvbuf_bytes",
+                &[("source", name)], s.socket_rcvbuf_bytes as f64,
+                "Kernel receive buffer size granted to the socket");
+            gauge(&mut samples, "shredtop_socket_rmem_peak_bytes",
+                &[("source", name)], s.socket_rmem_hwm_bytes as f64,
+                "Highest receive-queue memory use observed");
+            gauge(&mut samples, "shredtop_socket_drops_total",
+                &[("source", name)], s.socket_drops as f64,
+                "Datagrams dropped by the kernel (receive buffer full)");
+        }
+
+        if s.recv_calls > 0 {
+            gauge(&mut samples, "shredtop_receiver_busy_seconds_total",
+                &[("source", name)], s.recv_busy_ns as f64 / 1e9,
+                "Receive thread time spent handling packets between recvmmsg calls");
+            gauge(&mut samples, "shredtop_receiver_wait_seconds_total",
+                &[("source", name)], s.recv_wait_ns as f64 / 1e9,
+                "Receive thread time spent blocked in recvmmsg");
+            gauge(&mut samples, "shredtop_receiver_recv_calls_total",
+                &[("source", name)], s.recv_calls as f64,
+                "recvmmsg calls that returned packets");
+            gauge(&mut samples, "shredtop_receiver_packets_total",
+                &[("source", name)], s.recv_packets as f64,
+                "Packets returned by recvmmsg (divide by calls for batch fill)");
+        }
 
         if !s.is_rpc {
-            if let Some(cov) = coverage_pct(s) {
-                gauge(&mut out, "shredtop_coverage_pct",
+            if let Some(cov) = s.confirmed_coverage_pct().or(s.shred_coverage_pct()) {
+                gauge(&mut samples, "shredtop_coverage_pct",
                     &[("source", name)], cov,
-                    "Block shred coverage percent");
+                    "Block coverage percent (confirmed transactions when cross-checked)");
+            }
+
+            if s.cross_checked() {
+                gauge(&mut samples, "shredtop_slots_missed_total",
+                    &[("source", name)], s.confirmed_slots_missed as f64,
+                    "Confirmed blocks the feed delivered no shreds for");
+                gauge(&mut samples, "shredtop_slots_skipped_total",
+                    &[("source", name)], s.skipped_slots_seen as f64,
+                    "Slots the feed delivered shreds for that the cluster skipped");
             }
 
             if s.lead_time_count > 0 {
                 let beat_pct = s.lead_wins as f64 / s.lead_time_count as f64 * 100.0;
-                gauge(&mut out, "shredtop_beat_rpc_pct",
+                gauge(&mut samples, "shredtop_beat_rpc_pct",
                     &[("source", name)], beat_pct,
                     "Percent of matched transactions where feed beat RPC");
+                let budget_us = snap.latency_budget_ms.map(|ms| ms as i64 * 1000);
+                if let Some(f) = budget_us.and_then(|us| s.lead_time_hist.fraction_at_least(us)) {
+                    gauge(&mut samples, "shredtop_in_budget_pct",
+                        &[("source", name)], f * 100.0,
+                        "Percent of matched transactions that beat RPC by the latency budget");
+                }
 
                 let mean_ms = s.lead_time_sum_us as f64 / s.lead_time_count as f64 / 1000.0;
-                gauge(&mut out, "shredtop_lead_time_mean_ms",
+                gauge(&mut samples, "shredtop_lead_time_mean_ms",
                     &[("source", name)], mean_ms,
                     "Mean lead time over RPC in milliseconds (positive = ahead)");
 
                 if let Some(p50) = s.lead_time_p50_us {
-                    gauge(&mut out, "shredtop_lead_time_ms",
+                    gauge(&mut samples, "shredtop_lead_time_ms",
                         &[("source", name), ("quantile", "0.5")], p50 as f64 / 1000.0,
                         "Lead time quantile in milliseconds");
                 }
                 if let Some(p95) = s.lead_time_p95_us {
-                    gauge(&mut out, "shredtop_lead_time_ms",
+                    gauge(&mut samples, "shredtop_lead_time_ms",
                         &[("source", name), ("quantile", "0.95")], p95 as f64 / 1000.0,
                         "Lead time quantile in milliseconds");
                 }
                 if let Some(p99) = s.lead_time_p99_us {
-                    gauge(&mut out, "shredtop_lead_time_ms",
+                    gauge(&mut samples, "shredtop_lead_time_ms",
                         &[("source", name), ("quantile", "0.99")], p99 as f64 / 1000.0,
                         "Lead time quantile in milliseconds");
                 }
             }
 
+            if let Some(gap) = s.slot_max_gap_p95_us {
+                gauge(&mut samples, "shredtop_slot_max_gap_p95_ms",
+                    &[("source", name)], gap as f64 / 1000.0,
+                    "p95 over recent slots of the longest gap between data shreds");
+            }
+            if let Some(jitter) = s.slot_jitter_p95_us {
+                gauge(&mut samples, "shredtop_slot_jitter_p95_ms",
+                    &[("source", name)], jitter as f64 / 1000.0,
+                    "p95 over recent slots of the standard deviation of shred gaps");
+            }
+
             if let Some(secs) = s.secs_since_heartbeat {
-                gauge(&mut out, "shredtop_heartbeat_age_secs",
+                gauge(&mut samples, "shredtop_heartbeat_age_secs",
                     &[("source", name)], secs as f64,
                     "Seconds since last DoubleZero heartbeat (0 if just received)");
             }
+        } else {
+            gauge(&mut samples, "shredtop_rpc_slot_lag",
+                &[("source", name)], s.rpc_slot_lag as f64,
+                "Slots the RPC baseline still has to fetch behind the cluster tip");
+        }
+
+        // Configured source labels go on every series of that source.
+        for sample in &mut samples[first..] {
+            sample.labels.extend(s.labels.iter().map(|(k, v)| (k.clone(), v.clone())));
         }
     }
 
-    out
-}
+    if let Some(ref c) = snap.capture {
+        gauge(&mut samples, "shredtop_capture_write_stall_seconds_total",
+            &[], c.write_stall_ms / 1000.0,
+            "Time the capture thread waited for the flusher to take a chunk");
+        gauge(&mut samples, "shredtop_capture_bytes_written_total",
+            &[], c.bytes_written as f64,
+            "Bytes written to capture files");
+        gauge(&mut samples, "shredtop_capture_fsyncs_total",
+            &[], c.fsyncs as f64,
+            "Capture file syncs");
+        gauge(&mut samples, "shredtop_capture_triggers_total",
+            &[], c.triggers as f64,
+            "Anomalies that fired a triggered capture");
+    }
 
-fn coverage_pct(s: &SourceMetricsSnapshot) -> Option<f64> {
-    if s.coverage_shreds_expected == 0 { return None; }
-    Some((s.coverage_shreds_seen as f64 / s.coverage_shreds_expected as f64 * 100.0).min(100.0))
-}
+    if let Some(ref m) = snap.memory {
+        gauge(&mut samples, "shredtop_memory_limit_bytes",
+            &[], m.limit_bytes as f64,
+            "Memory budget from [limits] max_memory_mb");
+        for (holder, bytes) in
+            [("decoder", m.decoder_bytes), ("dedup", m.dedup_bytes), ("race", m.race_bytes)]
+        {
+            gauge(&mut samples, "shredtop_memory_bytes",
+                &[("holder", holder)], bytes as f64,
+                "Approximate bytes held against the memory budget");
+        }
+        let shed = [
+            ("slots", m.slots_shed),
+            ("fec_sets", m.fec_sets_shed),
+            ("dedup_entries", m.dedup_entries_shed),
+            ("race_slots", m.race_slots_shed),
+        ];
+        for (kind, n) in shed {
+            gauge(&mut samples, "shredtop_memory_shed_total",
+                &[("kind", kind)], n as f64,
+                "State dropped early to stay within the memory budget");
+        }
+    }
 
-fn gauge(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64, help: &str) {
-    use std::fmt::Write;
-    let _ = writeln!(out, "# HELP {} {}", name, help);
-    let _ = writeln!(out, "# TYPE {} gauge", name);
-    if labels.is_empty() {
-        let _ = writeln!(out, "{} {}", name, value);
-    } else {
-        let lstr: Vec<String> = labels.iter().map(|(k, v)| format!("{}=\"{}\"", k, v)).collect();
-        let _ = writeln!(out, "{}{{{}}} {}", name, lstr.join(","), value);
+    gauge(&mut samples, "shredtop_clock_negative_deltas_total",
+        &[], snap.clock.negative_deltas as f64,
+        "Timestamp differences that came out negative and were read as 0");
+    gauge(&mut samples, "shredtop_clock_cross_domain_total",
+        &[], snap.clock.cross_domain as f64,
+        "Timestamps found outside the monotonic clock's domain");
+    if let Some(ref c) = snap.clock_sync {
+        let source = [("source", c.source)];
+        gauge(&mut samples, "shredtop_clock_synced",
+            &source, c.synced as u8 as f64,
+            "1 while the clock discipline source reports the clock synchronized");
+        gauge(&mut samples, "shredtop_clock_trusted",
+            &source, c.trusted as u8 as f64,
+            "1 while the clock is synced, within max_offset_us, and has not stepped");
+        if let Some(offset) = c.offset_us {
+            gauge(&mut samples, "shredtop_clock_offset_us",
+                &source, offset,
+                "Offset from the clock reference");
+        }
+        if let Some(jitter) = c.jitter_us {
+            gauge(&mut samples, "shredtop_clock_jitter_us",
+                &source, jitter,
+                "RMS offset from the clock reference");
+        }
+        if let Some(stratum) = c.stratum {
+            gauge(&mut samples, "shredtop_clock_stratum",
+                &source, stratum as f64,
+                "NTP stratum of the clock");
+        }
+        gauge(&mut samples, "shredtop_clock_step_us",
+            &source, c.step_us as f64,
+            "Move of the wall clock against the monotonic clock over the last interval");
     }
+
+    samples
+}
+
+fn gauge(
+    out: &mut Vec<Sample>,
+    name: &'static str,
+    labels: &[(&'static str, &str)],
+    value: f64,
+    help: &'static str,
+) {
+    out.push(Sample {
+        name,
+        help,
+        labels: labels.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect(),
+        value,
+    });
 }
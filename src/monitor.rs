@@ -3,18 +3,29 @@
 //! This command is a read-only view. It reads `/var/log/shredtop.jsonl` written
 //! by `shredtop run` / `shredtop service start` and redraws the dashboard every
 //! N seconds. Ctrl-C closes the view; the background service keeps running.
+//!
+//! With `--slots` the view switches to a per-slot table: the most recent slots
+//! finalized by each shred source (and by a geyser baseline), with shreds or
+//! transactions seen, coverage, completion time and decode outcome. Aggregates
+//! hide per-slot misbehaviour such as periodic drops aligned to specific
+//! leaders; this view makes it visible.
 
 use anyhow::Result;
 use chrono::{TimeZone, Utc};
 use libc;
-use shred_ingest::{GeyserTxSource, JitoShredstreamSource, RpcTxSource, ShredTxSource, TurbineTxSource, UnicastTxSource, SourceMetrics};
+use shred_ingest::{FailoverGroup, GeyserAuth, GeyserTxSource, GrpcCompression, GrpcSettings, GrpcTls, JitoShredstreamSource, JitoUdpSource, RpcTxSource, ShredTxSource, TurbineTxSource, UnicastTxSource, SourceMetrics};
+use std::collections::BTreeMap;
 use std::io::Write;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::color;
-use crate::config::SourceEntry;
-use crate::run::DEFAULT_LOG;
+use crate::config::{GrpcEntry, SourceEntry};
+use crate::metrics_log;
+use crate::metrics_server::RESERVED_LABELS;
+use crate::run::RECENT_SLOTS;
 
 static RUNNING: AtomicBool = AtomicBool::new(true);
 
@@ -22,16 +33,31 @@ extern "C" fn handle_sigint(_: libc::c_int) {
     RUNNING.store(false, Ordering::SeqCst);
 }
 
-fn log_has_data() -> bool {
-    std::fs::metadata(DEFAULT_LOG)
-        .map(|m| m.len() > 0)
-        .unwrap_or(false)
+/// Header and log field of the BEAT% column. With `latency_budget_ms` set
+/// it shows the share of transactions inside the budget instead.
+pub(crate) fn beat_column(entry: &serde_json::Value) -> (String, &'static str) {
+    match entry["latency_budget_ms"].as_u64() {
+        Some(ms) => (format!("≥{}ms", ms), "in_budget_pct"),
+        None => ("BEAT%".into(), "beat_rpc_pct"),
+    }
+}
+
+/// Legend line entry for [`beat_column`].
+pub(crate) fn beat_legend(entry: &serde_json::Value) -> String {
+    match entry["latency_budget_ms"].as_u64() {
+        Some(ms) => format!("≥{0}ms = % of matched txs ≥{0}ms before RPC (latency_budget_ms)", ms),
+        None => "BEAT% = % of matched txs where feed beat RPC".into(),
+    }
+}
+
+fn log_has_data(log: &Path) -> bool {
+    metrics_log::read_last_entry(log).is_some()
 }
 
-pub fn run(interval_secs: u64) -> Result<()> {
+pub fn run(interval_secs: u64, slots_view: bool, log: &Path) -> Result<()> {
     // If the log file doesn't exist at all, the service isn't installed.
-    if std::fs::metadata(DEFAULT_LOG).is_err() {
-        eprintln!("No metrics log found at {}.", DEFAULT_LOG);
+    if !metrics_log::exists(log) {
+        eprintln!("No metrics log found at {}.", log.display());
         eprintln!();
         eprintln!("Start the background service first:");
         eprintln!("  shredtop service start");
@@ -41,7 +67,7 @@ pub fn run(interval_secs: u64) -> Result<()> {
     }
 
     // Log exists but is empty — service just started. Poll up to 30s.
-    if !log_has_data() {
+    if !log_has_data(log) {
         println!(
             "{}",
             color::yellow("Service recently started — monitor will appear in under 30s...")
@@ -50,7 +76,7 @@ pub fn run(interval_secs: u64) -> Result<()> {
         loop {
             std::thread::sleep(std::time::Duration::from_secs(5));
             waited += 5;
-            if log_has_data() {
+            if log_has_data(log) {
                 // Clear the waiting message before launching dashboard
                 print!("\x1b[1A\x1b[2K");
                 break;
@@ -68,16 +94,18 @@ pub fn run(interval_secs: u64) -> Result<()> {
     RUNNING.store(true, Ordering::SeqCst);
     unsafe { libc::signal(libc::SIGINT, handle_sigint as *const () as libc::sighandler_t) };
 
-    println!(
-        "{}",
-        color::bold("SHREDTOP MONITOR  —  Ctrl-C to close  (service keeps running)")
-    );
+    let title = if slots_view {
+        "SHREDTOP MONITOR (SLOTS)  —  Ctrl-C to close  (service keeps running)"
+    } else {
+        "SHREDTOP MONITOR  —  Ctrl-C to close  (service keeps running)"
+    };
+    println!("{}", color::bold(title));
     println!();
 
     let mut lines_drawn = 0usize;
 
     while RUNNING.load(Ordering::SeqCst) {
-        let snapshot = read_last_entry(DEFAULT_LOG);
+        let snapshot = metrics_log::read_last_entry(log);
 
         // Overwrite previous dashboard draw
         if lines_drawn > 0 {
@@ -85,6 +113,7 @@ pub fn run(interval_secs: u64) -> Result<()> {
         }
 
         lines_drawn = match snapshot {
+            Some(entry) if slots_view => draw_slots(&entry),
             Some(entry) => draw_dashboard(&entry),
             None => {
                 let line = "Waiting for first snapshot...";
@@ -109,12 +138,6 @@ pub fn run(interval_secs: u64) -> Result<()> {
     Ok(())
 }
 
-fn read_last_entry(path: &str) -> Option<serde_json::Value> {
-    let content = std::fs::read_to_string(path).ok()?;
-    let line = content.lines().filter(|l| !l.is_empty()).last()?;
-    serde_json::from_str(line).ok()
-}
-
 fn draw_dashboard(entry: &serde_json::Value) -> usize {
     const W: usize = 100;
     let mut out: Vec<String> = Vec::new();
@@ -150,6 +173,7 @@ fn draw_dashboard(entry: &serde_json::Value) -> usize {
     out.push(color::bold(&"=".repeat(W)));
     out.push(color::bold_cyan(&format!("{:^W$}", format!("  SHREDTOP FEED QUALITY  {}  ", time_str))));
     out.push(color::bold(&"=".repeat(W)));
+    out.extend(crate::alerts::banners(entry));
     out.push(color::dim(&format!("  Started: {}   Uptime: {}", started_str, uptime_str)));
     out.push(String::new());
 
@@ -166,10 +190,11 @@ fn draw_dashboard(entry: &serde_json::Value) -> usize {
     }
 
     // Column headers — BEAT%/LEAD columns only shown when a baseline exists
+    let (beat_label, beat_key) = beat_column(entry);
     if has_rpc {
         out.push(color::bold(&format!(
             "{:<20}  {:>5}  {:>9}  {:>5}  {:>6}  {:>6}  {:>9}  {:>9}  {:>9}  {:>9}",
-            "SOURCE", "LINK", "SHREDS/s", "COV%", "TXS/s", "BEAT%", "LEAD avg", "LEAD p50", "LEAD p95", "LEAD p99",
+            "SOURCE", "LINK", "SHREDS/s", "COV%", "TXS/s", beat_label, "LEAD avg", "LEAD p50", "LEAD p95", "LEAD p99",
         )));
     } else {
         out.push(color::bold(&format!(
@@ -188,7 +213,13 @@ fn draw_dashboard(entry: &serde_json::Value) -> usize {
 
             // LINK column: DZ heartbeat freshness indicator (shred sources only).
             // OK = heartbeat seen ≤10s ago, STALE = 10-60s, DEAD = >60s or never.
-            let link_str: String = if is_rpc {
+            let link_str: String = if s["paused"].as_bool().unwrap_or(false) {
+                color::yellow("PAUSED")
+            } else if s["silent"].as_bool().unwrap_or(false) {
+                color::red("SILENT")
+            } else if s["standby"].as_bool().unwrap_or(false) {
+                color::dim("STANDBY")
+            } else if is_rpc {
                 "—".into()
             } else {
                 match s["secs_since_heartbeat"].as_u64() {
@@ -216,7 +247,7 @@ fn draw_dashboard(entry: &serde_json::Value) -> usize {
                 let beat_str = if is_rpc {
                     "—".into()
                 } else {
-                    s["beat_rpc_pct"]
+                    s[beat_key]
                         .as_f64()
                         .map(|p| format!("{:.0}%", p))
                         .unwrap_or_else(|| "—".into())
@@ -254,7 +285,7 @@ fn draw_dashboard(entry: &serde_json::Value) -> usize {
             // Colorize entire row based on source type and edge health
             let row = if is_rpc {
                 color::dim(&row)
-            } else if let Some(beat) = s["beat_rpc_pct"].as_f64() {
+            } else if let Some(beat) = s[beat_key].as_f64() {
                 if beat >= 60.0 {
                     color::green(&row)
                 } else if beat >= 40.0 {
@@ -291,6 +322,7 @@ fn draw_dashboard(entry: &serde_json::Value) -> usize {
     }
 
     out.push(color::dim(&"-".repeat(W)));
+    out.extend(silent_section(entry));
 
     // Shred race section — directly under the feed table, before edge assessment
     out.push(String::new());
@@ -304,51 +336,7 @@ fn draw_dashboard(entry: &serde_json::Value) -> usize {
             "  No races yet — waiting for same slot to appear on multiple shred feeds.",
         ));
     } else {
-        out.push(color::bold(&format!(
-            "  {:<22}  {:>7}  {:>9}  {:>10}  {:>9}  {:>9}",
-            "CONTENDER", "WIN%", "RACES", "FASTER BY", "LEAD p50", "LEAD p95",
-        )));
-        let mut pairs: Vec<&serde_json::Value> = race_pairs.unwrap().iter().collect();
-        pairs.sort_by(|a, b| {
-            let ma = a["total_matched"].as_u64().unwrap_or(0);
-            let mb = b["total_matched"].as_u64().unwrap_or(0);
-            mb.cmp(&ma)
-        });
-        for (i, p) in pairs.iter().enumerate() {
-            if i > 0 {
-                out.push("  \u{00b7}\u{00b7}\u{00b7}\u{00b7}\u{00b7}".into());
-            }
-            let sa = p["source_a"].as_str().unwrap_or("?");
-            let sb = p["source_b"].as_str().unwrap_or("?");
-            let matched = p["total_matched"].as_u64().unwrap_or(0);
-            let a_pct = p["a_win_pct"].as_f64().unwrap_or(0.0);
-            let b_pct = 100.0 - a_pct;
-            let (faster, f_pct, slower, s_pct) = if a_pct >= b_pct {
-                (sa, a_pct, sb, b_pct)
-            } else {
-                (sb, b_pct, sa, a_pct)
-            };
-            let avg_str = p["lead_mean_us"]
-                .as_f64()
-                .map(|v| format!("+{:.2}ms", v / 1000.0))
-                .unwrap_or_else(|| "—".into());
-            let p50_str = p["lead_p50_us"]
-                .as_f64()
-                .map(|v| format!("+{:.1}ms", v / 1000.0))
-                .unwrap_or_else(|| "—".into());
-            let p95_str = p["lead_p95_us"]
-                .as_f64()
-                .map(|v| format!("+{:.1}ms", v / 1000.0))
-                .unwrap_or_else(|| "—".into());
-            out.push(color::green(&format!(
-                "  {:<22}  {:>6.1}%  {:>9}  {:>10}  {:>9}  {:>9}",
-                faster, f_pct, format_num(matched), avg_str, p50_str, p95_str,
-            )));
-            out.push(color::dim(&format!(
-                "  {:<22}  {:>6.1}%  {:>9}  {:>10}  {:>9}  {:>9}",
-                slower, s_pct, "—", "—", "—", "—",
-            )));
-        }
+        out.extend(race_table(race_pairs.unwrap()));
     }
     out.push(String::new());
     out.push(color::dim(
@@ -361,6 +349,16 @@ fn draw_dashboard(entry: &serde_json::Value) -> usize {
         "  timestamp (SO_TIMESTAMPNS), before any userspace processing.",
     ));
 
+    // Slot-completion race: only shown once at least one pair has raced.
+    if let Some(slot_pairs) = entry["slot_race"].as_array().filter(|p| !p.is_empty()) {
+        out.push(String::new());
+        out.push(color::bold("SLOT RACE  first feed to make each slot fully decodable:"));
+        out.extend(race_table(slot_pairs));
+    }
+    out.extend(start_race_section(entry));
+    out.extend(avail_race_section(entry));
+    out.extend(first_overall_section(entry));
+
     out.push(String::new());
 
     // Edge assessment
@@ -384,13 +382,14 @@ fn draw_dashboard(entry: &serde_json::Value) -> usize {
     out.push(String::new());
     out.push(color::dim(&"-".repeat(W)));
     if has_rpc {
-        out.push(color::dim(
-            "LINK = DZ heartbeat (OK ≤10s / STALE ≤60s / DEAD)  COV% = block shreds received  \
-             BEAT% = % of matched txs where feed beat RPC  LEAD = ms before RPC  p50/p95/p99 = percentiles",
-        ));
+        out.push(color::dim(&format!(
+            "LINK = DZ heartbeat (OK ≤10s / STALE ≤60s / DEAD, PAUSED = `shredtop ctl pause`, STANDBY = failover, SILENT = no data since start)  COV% = block shreds received  \
+             {}  LEAD = ms before RPC  p50/p95/p99 = percentiles",
+            beat_legend(entry),
+        )));
     } else {
         out.push(color::dim(
-            "LINK = DZ heartbeat (OK ≤10s / STALE ≤60s / DEAD)  COV% = block shreds received  \
+            "LINK = DZ heartbeat (OK ≤10s / STALE ≤60s / DEAD, PAUSED = `shredtop ctl pause`, STANDBY = failover, SILENT = no data since start)  COV% = block shreds received  \
              (add a baseline to unlock BEAT%/LEAD columns)",
         ));
     }
@@ -402,6 +401,337 @@ fn draw_dashboard(entry: &serde_json::Value) -> usize {
     count
 }
 
+/// Sources that have delivered nothing since start, each with the most
+/// likely reason. Empty when every source is producing.
+pub fn silent_section(entry: &serde_json::Value) -> Vec<String> {
+    let silent: Vec<&serde_json::Value> = entry["sources"]
+        .as_array()
+        .map(|a| a.iter().filter(|s| s["silent"].as_bool().unwrap_or(false)).collect())
+        .unwrap_or_default();
+    if silent.is_empty() {
+        return Vec::new();
+    }
+    let mut out = vec![
+        String::new(),
+        color::bold_red("SILENT  configured but nothing received since start:"),
+    ];
+    for s in silent {
+        out.push(color::red(&format!(
+            "  {:<20}  {}",
+            s["name"].as_str().unwrap_or("?"),
+            silent_cause(s)
+        )));
+    }
+    out
+}
+
+/// The reported error if there is one, otherwise a guess from the counters.
+fn silent_cause(s: &serde_json::Value) -> String {
+    if let Some(error) = s["last_error"]["message"].as_str() {
+        return error.to_string();
+    }
+    let rejected = s["non_shred_packets"].as_u64().unwrap_or(0)
+        + s["shreds_invalid"].as_u64().unwrap_or(0);
+    if s["is_rpc"].as_bool().unwrap_or(false) {
+        "no transactions yet — is the endpoint reachable?".into()
+    } else if rejected > 0 {
+        "packets arrive but none are accepted as shreds — check the port and shred_version".into()
+    } else if s["secs_since_heartbeat"].as_u64().is_some() {
+        "DoubleZero heartbeats arrive but no shreds — nothing is publishing to the group".into()
+    } else {
+        "no packets — is the multicast group joined (`ip maddr show`) or the sender pointed here?"
+            .into()
+    }
+}
+
+/// The slot start race, then the same race over the slots that open a
+/// leader's window; shown once two feeds have started the same slot.
+pub fn start_race_section(entry: &serde_json::Value) -> Vec<String> {
+    let Some(pairs) = entry["start_race"].as_array().filter(|p| !p.is_empty()) else {
+        return Vec::new();
+    };
+    let mut out = vec![
+        String::new(),
+        color::bold("SLOT START RACE  first shred of each new slot:"),
+    ];
+    out.extend(race_table(pairs));
+    if let Some(handoff) = entry["handoff_race"].as_array().filter(|p| !p.is_empty()) {
+        out.push(String::new());
+        out.push(color::bold("  at leader handoffs (first slot of each leader):"));
+        out.extend(race_table(handoff));
+    }
+    out
+}
+
+/// The availability race, shown only once an FEC-recovered copy has won a
+/// race — until then it matches the shred race.
+pub fn avail_race_section(entry: &serde_json::Value) -> Vec<String> {
+    let Some(pairs) = entry["avail_race"].as_array() else {
+        return Vec::new();
+    };
+    let fec_wins: u64 = pairs.iter().map(|p| p["fec_wins"].as_u64().unwrap_or(0)).sum();
+    if fec_wins == 0 {
+        return Vec::new();
+    }
+    let total: u64 = pairs.iter().map(|p| p["total_matched"].as_u64().unwrap_or(0)).sum();
+    let mut out = vec![
+        String::new(),
+        color::bold("AVAILABILITY RACE  first copy received or FEC-recovered:"),
+    ];
+    out.extend(race_table(pairs));
+    out.push(color::dim(&format!(
+        "  {} of {} races won by a shred rebuilt from FEC before the other feed's packet arrived.",
+        format_num(fec_wins),
+        format_num(total)
+    )));
+    out
+}
+
+/// N-way ranking of the shred feeds, shown once three or more have raced —
+/// with two it repeats the shred race.
+pub fn first_overall_section(entry: &serde_json::Value) -> Vec<String> {
+    let Some(feeds) = entry["shred_rank"].as_array().filter(|f| f.len() >= 3) else {
+        return Vec::new();
+    };
+    let mut feeds: Vec<&serde_json::Value> = feeds.iter().collect();
+    feeds.sort_by(|a, b| {
+        let pa = a["first_pct"].as_f64().unwrap_or(0.0);
+        let pb = b["first_pct"].as_f64().unwrap_or(0.0);
+        pb.total_cmp(&pa)
+    });
+    let positions = feeds.len().min(4);
+    let mut header = format!("  {:<22}  {:>7}  {:>9}", "FEED", "FIRST%", "RACES");
+    for pos in 1..=positions {
+        header.push_str(&format!("  {:>9}", ordinal(pos)));
+    }
+    let mut out = vec![
+        String::new(),
+        color::bold("FIRST OVERALL  finishing position among all feeds that delivered the shred:"),
+        color::bold(&header),
+    ];
+    for f in feeds {
+        let mut row = format!(
+            "  {:<22}  {:>6.1}%  {:>9}",
+            f["source"].as_str().unwrap_or("?"),
+            f["first_pct"].as_f64().unwrap_or(0.0),
+            format_num(f["races"].as_u64().unwrap_or(0)),
+        );
+        for pos in 0..positions {
+            let n = f["ranks"][pos].as_u64().unwrap_or(0);
+            row.push_str(&format!("  {:>9}", format_num(n)));
+        }
+        out.push(row);
+    }
+    out
+}
+
+fn ordinal(n: usize) -> String {
+    match n {
+        1 => "1ST".into(),
+        2 => "2ND".into(),
+        3 => "3RD".into(),
+        _ => format!("{}TH", n),
+    }
+}
+
+/// Rows for a race table (`shred_race` / `slot_race` in the log): one
+/// faster/slower row pair per contender pair, most-raced first. `5M` and `1H`
+/// are the win rates over the last 5 minutes and hour, next to the lifetime
+/// `WIN%`.
+pub fn race_table(race_pairs: &[serde_json::Value]) -> Vec<String> {
+    let mut out = Vec::new();
+    out.push(color::bold(&format!(
+        "  {:<22}  {:>7}  {:>7}  {:>7}  {:>9}  {:>10}  {:>9}  {:>9}",
+        "CONTENDER", "WIN%", "5M", "1H", "RACES", "FASTER BY", "LEAD p50", "LEAD p95",
+    )));
+    let mut pairs: Vec<&serde_json::Value> = race_pairs.iter().collect();
+    pairs.sort_by(|a, b| {
+        let ma = a["total_matched"].as_u64().unwrap_or(0);
+        let mb = b["total_matched"].as_u64().unwrap_or(0);
+        mb.cmp(&ma)
+    });
+    for (i, p) in pairs.iter().enumerate() {
+        if i > 0 {
+            out.push("  \u{00b7}\u{00b7}\u{00b7}\u{00b7}\u{00b7}".into());
+        }
+        let sa = p["source_a"].as_str().unwrap_or("?");
+        let sb = p["source_b"].as_str().unwrap_or("?");
+        let matched = p["total_matched"].as_u64().unwrap_or(0);
+        let a_pct = p["a_win_pct"].as_f64().unwrap_or(0.0);
+        let b_pct = 100.0 - a_pct;
+        let a_faster = a_pct >= b_pct;
+        let (faster, f_pct, slower, s_pct) = if a_faster {
+            (sa, a_pct, sb, b_pct)
+        } else {
+            (sb, b_pct, sa, a_pct)
+        };
+        // Window win rates of the faster and the slower feed.
+        let window = |key: &str| match p[key]["a_win_pct"].as_f64() {
+            Some(a) => {
+                let f = if a_faster { a } else { 100.0 - a };
+                (format!("{:.1}%", f), format!("{:.1}%", 100.0 - f))
+            }
+            None => ("—".to_string(), "—".to_string()),
+        };
+        let (f_5m, s_5m) = window("last_5m");
+        let (f_1h, s_1h) = window("last_1h");
+        let avg_str = p["lead_mean_us"]
+            .as_f64()
+            .map(|v| format!("+{:.2}ms", v / 1000.0))
+            .unwrap_or_else(|| "—".into());
+        let p50_str = p["lead_p50_us"]
+            .as_f64()
+            .map(|v| format!("+{:.1}ms", v / 1000.0))
+            .unwrap_or_else(|| "—".into());
+        let p95_str = p["lead_p95_us"]
+            .as_f64()
+            .map(|v| format!("+{:.1}ms", v / 1000.0))
+            .unwrap_or_else(|| "—".into());
+        out.push(color::green(&format!(
+            "  {:<22}  {:>6.1}%  {:>7}  {:>7}  {:>9}  {:>10}  {:>9}  {:>9}",
+            faster, f_pct, f_5m, f_1h, format_num(matched), avg_str, p50_str, p95_str,
+        )));
+        out.push(color::dim(&format!(
+            "  {:<22}  {:>6.1}%  {:>7}  {:>7}  {:>9}  {:>10}  {:>9}  {:>9}",
+            slower, s_pct, s_5m, s_1h, "—", "—", "—", "—",
+        )));
+        if let Some(note) = p["note"].as_str() {
+            out.push(color::dim(&format!("    note: {}", note)));
+        }
+    }
+    out
+}
+
+/// Per-slot view: one row per slot (newest first), one column group per shred source.
+fn draw_slots(entry: &serde_json::Value) -> usize {
+    const COL: usize = 31;
+    let mut out: Vec<String> = Vec::new();
+
+    let ts = entry["ts"].as_u64().unwrap_or(0) as i64;
+    let time_str = Utc
+        .timestamp_opt(ts, 0)
+        .single()
+        .map(|d| d.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "—".into());
+
+    // Shred-tier sources, and baselines with a slot log (geyser).
+    let sources: Vec<&serde_json::Value> = entry["sources"]
+        .as_array()
+        .map(|a| {
+            a.iter()
+                .filter(|s| {
+                    let has_slots = s["recent_slots"].as_array().is_some_and(|v| !v.is_empty());
+                    !s["is_rpc"].as_bool().unwrap_or(false) || has_slots
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let w = 12 + sources.len().max(1) * (COL + 2);
+    out.push(color::bold(&"=".repeat(w)));
+    out.push(color::bold_cyan(&format!("{:^w$}", format!("  SHREDTOP RECENT SLOTS  {}  ", time_str))));
+    out.push(color::bold(&"=".repeat(w)));
+    out.extend(crate::alerts::banners(entry));
+
+    if sources.is_empty() {
+        out.push(color::dim("  No shred sources configured — per-slot view needs a shred-tier feed."));
+        let count = out.len();
+        for line in out {
+            println!("{}", line);
+        }
+        return count;
+    }
+
+    // slot → per-source stats, newest RECENT_SLOTS slots across all sources.
+    let mut by_slot: std::collections::BTreeMap<u64, Vec<Option<&serde_json::Value>>> =
+        std::collections::BTreeMap::new();
+    for (i, s) in sources.iter().enumerate() {
+        for st in s["recent_slots"].as_array().into_iter().flatten() {
+            if let Some(slot) = st["slot"].as_u64() {
+                by_slot.entry(slot).or_insert_with(|| vec![None; sources.len()])[i] = Some(st);
+            }
+        }
+    }
+
+    let mut header = format!("{:<12}", "SLOT");
+    let mut sub = format!("{:<12}", "");
+    for s in &sources {
+        let name = s["name"].as_str().unwrap_or("?");
+        header.push_str(&format!("  {:<COL$}", name));
+        sub.push_str(&format!(
+            "  {:>9} {:>4} {:>7} {:<8}",
+            "SEEN/EXP", "COV", "DONE", "OUTCOME"
+        ));
+    }
+    out.push(color::bold(&header));
+    out.push(color::dim(&sub));
+    out.push(color::dim(&"-".repeat(w)));
+
+    if by_slot.is_empty() {
+        out.push(color::dim("  Waiting for the first finalized slot..."));
+    }
+
+    for (slot, cells) in by_slot.iter().rev().take(RECENT_SLOTS) {
+        let mut row = format!("{:<12}", slot);
+        for cell in cells {
+            row.push_str("  ");
+            row.push_str(&format_slot_cell(*cell));
+        }
+        out.push(row);
+    }
+
+    out.push(color::dim(&"-".repeat(w)));
+    out.push(color::dim(
+        "SEEN/EXP = data shreds received / expected from first to highest index  \
+         COV = SEEN/EXP  DONE = first shred → slot complete",
+    ));
+    out.push(color::dim(
+        "streamed (geyser, jito-grpc): SEEN/EXP = transactions delivered / in the \
+         confirmed block  DONE = first → last transaction",
+    ));
+
+    let count = out.len();
+    for line in out {
+        println!("{}", line);
+    }
+    count
+}
+
+fn format_slot_cell(stats: Option<&serde_json::Value>) -> String {
+    let Some(st) = stats else {
+        return color::dim(&format!("{:>9} {:>4} {:>7} {:<8}", "—", "—", "—", "—"));
+    };
+    let outcome = st["outcome"].as_str().unwrap_or("?");
+    let (seen, expected) = if outcome == "streamed" {
+        (st["txs_decoded"].as_u64().unwrap_or(0), st["confirmed_txs"].as_u64().unwrap_or(0))
+    } else {
+        (st["shreds_seen"].as_u64().unwrap_or(0), st["shreds_expected"].as_u64().unwrap_or(0))
+    };
+    let seen_str = if expected > 0 {
+        format!("{}/{}", seen, expected)
+    } else {
+        format!("{}/?", seen)
+    };
+    let cov_str = if expected > 0 {
+        format!("{:.0}%", (seen as f64 / expected as f64 * 100.0).min(100.0))
+    } else {
+        "—".into()
+    };
+    let done_str = st["completion_us"]
+        .as_f64()
+        .map(|us| format!("{:.0}ms", us / 1000.0))
+        .unwrap_or_else(|| "—".into());
+    let cell = format!("{:>9} {:>4} {:>7} {:<8}", seen_str, cov_str, done_str, outcome);
+    match outcome {
+        "complete" => color::green(&cell),
+        "partial" => color::yellow(&cell),
+        "streamed" if expected == 0 => cell,
+        "streamed" if seen >= expected => color::green(&cell),
+        "streamed" => color::yellow(&cell),
+        _ => color::red(&cell),
+    }
+}
+
 fn format_num(n: u64) -> String {
     let s = n.to_string();
     let mut out = String::new();
@@ -418,35 +748,192 @@ fn format_num(n: u64) -> String {
 // Source construction — used by run.rs
 // ---------------------------------------------------------------------------
 
+/// Default silence window before a failover group promotes a standby.
+const DEFAULT_FAILOVER_AFTER_SECS: u64 = 5;
+
+/// Most ports a shred source's `port_range` may span, one recv thread each.
+const MAX_PORT_RANGE: u16 = 64;
+
+/// Group sources by `failover_group`. `metrics` must be in config order (as
+/// returned by `FanInSource::start`). Members keep their config order, so the
+/// first entry of each group starts as the active primary.
+pub fn build_failover_groups(
+    sources: &[SourceEntry],
+    metrics: &[Arc<SourceMetrics>],
+) -> Result<Vec<FailoverGroup>> {
+    let mut by_name: Vec<(String, u64, Vec<Arc<SourceMetrics>>)> = Vec::new();
+    for (entry, m) in sources.iter().zip(metrics) {
+        let Some(group) = &entry.failover_group else { continue };
+        match by_name.iter_mut().find(|(g, _, _)| g == group) {
+            Some((_, _, members)) => members.push(m.clone()),
+            None => by_name.push((
+                group.clone(),
+                entry.failover_after_secs.unwrap_or(DEFAULT_FAILOVER_AFTER_SECS),
+                vec![m.clone()],
+            )),
+        }
+    }
+
+    by_name
+        .into_iter()
+        .map(|(name, secs, members)| {
+            if members.len() < 2 {
+                anyhow::bail!("failover_group '{}' needs at least two sources", name);
+            }
+            Ok(FailoverGroup::new(name, members, Duration::from_secs(secs)))
+        })
+        .collect()
+}
+
+/// Source labels become Prometheus label names, so they must be valid ones and
+/// must not shadow the labels shredtop sets itself.
+fn check_labels(source: &str, labels: &BTreeMap<String, String>) -> Result<()> {
+    for key in labels.keys() {
+        let valid = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid || key.starts_with("__") {
+            anyhow::bail!(
+                "source '{}': invalid label name '{}' (letters, digits, _; no leading digit or __)",
+                source,
+                key
+            );
+        }
+        if RESERVED_LABELS.contains(&key.as_str()) {
+            anyhow::bail!("source '{}': label name '{}' is reserved", source, key);
+        }
+    }
+    Ok(())
+}
+
 pub fn build_source(
     entry: &SourceEntry,
     capture_tx: Option<crossbeam_channel::Sender<shred_ingest::CaptureEvent>>,
 ) -> Result<(Box<dyn shred_ingest::TxSource>, Arc<SourceMetrics>)> {
-    let name: &'static str = Box::leak(entry.name.clone().into_boxed_str());
+    let name: Arc<str> = entry.name.as_str().into();
     // rpc and geyser are baseline sources; shred and jito-grpc are shred-tier feeds.
     let is_rpc = matches!(entry.source_type.as_str(), "rpc" | "geyser");
-    let metrics = SourceMetrics::new(name, is_rpc);
+    let labels = entry.labels.clone().unwrap_or_default();
+    check_labels(&name, &labels)?;
+    let metrics = SourceMetrics::with_labels(name.clone(), is_rpc, labels);
+
+    let (source_type, chaos) = chaos_wrapper(entry, &name)?;
+
+    let headers_only = match entry.decode.as_deref() {
+        None | Some("full") => false,
+        Some("headers-only") => true,
+        Some(other) => anyhow::bail!(
+            "source '{}': unknown decode mode '{}' (expected \"full\" or \"headers-only\")",
+            name,
+            other
+        ),
+    };
+    if headers_only && !matches!(source_type, "shred" | "turbine" | "unicast" | "jito-udp") {
+        anyhow::bail!(
+            "source '{}': decode = \"headers-only\" is only supported for shred, turbine, unicast and jito-udp sources",
+            name
+        );
+    }
+
+    let max_lag_slots = entry.decode_max_lag_slots;
+    let decodes_shreds = matches!(source_type, "shred" | "turbine" | "unicast" | "jito-udp");
+    if max_lag_slots.is_some() && !decodes_shreds {
+        anyhow::bail!(
+            "source '{}': decode_max_lag_slots is only supported for shred, turbine, unicast and jito-udp sources",
+            name
+        );
+    }
+    if max_lag_slots == Some(0) {
+        anyhow::bail!("source '{}': decode_max_lag_slots must be at least 1", name);
+    }
+
+    let recv_sockets = entry.recv_sockets.unwrap_or(1);
+    if recv_sockets != 1 && !matches!(source_type, "unicast" | "jito-udp") {
+        // Multicast delivers every datagram to every member socket, and a
+        // turbine reuseport group is shared with the validator itself.
+        anyhow::bail!(
+            "source '{}': recv_sockets is only supported for unicast and jito-udp sources",
+            name
+        );
+    }
+
+    if let Some([first, last]) = entry.port_range {
+        if source_type != "shred" {
+            anyhow::bail!("source '{}': port_range is only supported for shred sources", name);
+        }
+        if entry.port.is_some() {
+            anyhow::bail!("source '{}': set either port or port_range, not both", name);
+        }
+        if first > last || last - first >= MAX_PORT_RANGE {
+            anyhow::bail!(
+                "source '{}': port_range must be [first, last] with at most {} ports",
+                name,
+                MAX_PORT_RANGE
+            );
+        }
+    }
+
+    if entry.ws_url.is_some() && source_type != "rpc" {
+        anyhow::bail!("source '{}': ws_url is only supported for rpc sources", name);
+    }
+    if entry.rpc_parallelism.is_some() && source_type != "rpc" {
+        anyhow::bail!("source '{}': rpc_parallelism is only supported for rpc sources", name);
+    }
+    if entry.rpc_parallelism == Some(0) {
+        anyhow::bail!("source '{}': rpc_parallelism must be at least 1", name);
+    }
+
+    let calibrate = entry.calibrate_timestamps.unwrap_or(false);
+    if calibrate && !matches!(source_type, "geyser" | "jito-grpc") {
+        anyhow::bail!(
+            "source '{}': calibrate_timestamps is only supported for geyser and jito-grpc sources",
+            name
+        );
+    }
+    if let Some(us) = entry.timestamp_offset_us {
+        metrics.add_recv_offset_ns(us.saturating_mul(1000));
+    }
+    if entry.grpc.is_some() && !matches!(source_type, "geyser" | "jito-grpc" | "jito-udp") {
+        anyhow::bail!(
+            "source '{}': grpc settings are only supported for geyser, jito-grpc and jito-udp sources",
+            name
+        );
+    }
+    let grpc = grpc_settings(entry.grpc.as_ref())
+        .map_err(|e| anyhow::anyhow!("source '{}': {:#}", name, e))?;
 
-    let source: Box<dyn shred_ingest::TxSource> = match entry.source_type.as_str() {
+    let source: Box<dyn shred_ingest::TxSource> = match source_type {
         "shred" => {
             let multicast_addr = entry
                 .multicast_addr
                 .clone()
                 .ok_or_else(|| anyhow::anyhow!("source '{}': missing multicast_addr", name))?;
-            let port = entry.port.unwrap_or(20001);
+            let (port, last_port) = match entry.port_range {
+                Some([first, last]) => (first, Some(last)),
+                None => (entry.port.unwrap_or(20001), None),
+            };
             let interface = entry
                 .interface
                 .clone()
                 .unwrap_or_else(|| "doublezero1".into());
             Box::new(ShredTxSource {
-                name,
+                name: name.clone(),
                 multicast_addr,
                 port,
+                last_port,
                 interface,
                 pin_recv_core: entry.pin_recv_core,
                 pin_decode_core: entry.pin_decode_core,
                 shred_version: entry.shred_version,
                 capture_tx,
+                chaos,
+                headers_only,
+                max_lag_slots,
+                forensics: None,
+                repair: None,
+                budget: None,
+                verifier: None,
+                tuning: None,
+                stop: None,
             })
         }
         "rpc" => {
@@ -454,44 +941,129 @@ pub fn build_source(
                 .url
                 .clone()
                 .unwrap_or_else(|| "http://127.0.0.1:8899".into());
-            Box::new(RpcTxSource { url, pin_core: entry.pin_recv_core })
+            Box::new(RpcTxSource {
+                url,
+                ws_url: entry.ws_url.clone(),
+                parallelism: entry
+                    .rpc_parallelism
+                    .unwrap_or(shred_ingest::rpc_source::DEFAULT_PARALLELISM),
+                pin_core: entry.pin_recv_core,
+                confirmations: None,
+                cluster_tip: None,
+            })
         }
         "geyser" => {
             let url = entry
                 .url
                 .clone()
                 .ok_or_else(|| anyhow::anyhow!("source '{}': missing url for geyser source", name))?;
-            Box::new(GeyserTxSource { name, url, x_token: entry.x_token.clone() })
+            let auth = match (&entry.auth, &entry.x_token) {
+                (Some(a), _) => GeyserAuth::from_scheme(&a.scheme, &a.token, a.header.as_deref())
+                    .map_err(|e| anyhow::anyhow!("source '{}': {}", name, e))?,
+                (None, Some(t)) => GeyserAuth::XToken(t.clone()),
+                (None, None) => GeyserAuth::None,
+            };
+            auth.validate().map_err(|e| anyhow::anyhow!("source '{}': {}", name, e))?;
+            grpc.validate(&url).map_err(|e| anyhow::anyhow!("source '{}': {:#}", name, e))?;
+            Box::new(GeyserTxSource { name: name.clone(), url, auth, grpc, calibrate })
         }
         "jito-grpc" => {
             let url = entry
                 .url
                 .clone()
                 .ok_or_else(|| anyhow::anyhow!("source '{}': missing url for jito-grpc source", name))?;
-            Box::new(JitoShredstreamSource { name, url })
+            grpc.validate(&url).map_err(|e| anyhow::anyhow!("source '{}': {:#}", name, e))?;
+            Box::new(JitoShredstreamSource { name: name.clone(), url, grpc, calibrate })
         }
         "turbine" => {
             let port = entry.port.unwrap_or(8002);
             Box::new(TurbineTxSource {
-                name,
+                name: name.clone(),
                 port,
                 pin_recv_core: entry.pin_recv_core,
                 pin_decode_core: entry.pin_decode_core,
                 shred_version: entry.shred_version,
                 capture_tx,
+                chaos,
+                headers_only,
+                max_lag_slots,
+                forensics: None,
+                repair: None,
+                budget: None,
+                verifier: None,
+                tuning: None,
+                stop: None,
             })
         }
         "unicast" => {
             let addr = entry.multicast_addr.as_deref().unwrap_or("0.0.0.0").to_string();
             let port = entry.port.unwrap_or(6000);
             Box::new(UnicastTxSource {
-                name,
+                name: name.clone(),
                 addr,
                 port,
                 pin_recv_core: entry.pin_recv_core,
                 pin_decode_core: entry.pin_decode_core,
                 shred_version: entry.shred_version,
                 capture_tx,
+                recv_sockets,
+                chaos,
+                headers_only,
+                max_lag_slots,
+                forensics: None,
+                repair: None,
+                budget: None,
+                verifier: None,
+                tuning: None,
+                stop: None,
+            })
+        }
+        "jito-udp" => {
+            let missing = |field: &str| {
+                anyhow::anyhow!("source '{}': missing {} for jito-udp source", name, field)
+            };
+            let block_engine_url = entry.url.clone().ok_or_else(|| missing("url"))?;
+            grpc.validate(&block_engine_url)
+                .map_err(|e| anyhow::anyhow!("source '{}': {:#}", name, e))?;
+            let keypair_path = entry.keypair.as_deref().ok_or_else(|| missing("keypair"))?;
+            let keypair = shred_ingest::jito_udp::read_keypair(Path::new(keypair_path))
+                .map_err(|e| anyhow::anyhow!("source '{}': {:#}", name, e))?;
+            let public_ip = entry
+                .public_ip
+                .as_deref()
+                .ok_or_else(|| missing("public_ip"))?
+                .parse()
+                .map_err(|e| anyhow::anyhow!("source '{}': public_ip: {}", name, e))?;
+            let regions = entry.regions.clone().unwrap_or_default();
+            if regions.is_empty() {
+                return Err(missing("regions"));
+            }
+            let addr = entry.multicast_addr.as_deref().unwrap_or("0.0.0.0").to_string();
+            Box::new(JitoUdpSource {
+                block_engine_url,
+                grpc,
+                keypair,
+                public_ip,
+                regions,
+                udp: UnicastTxSource {
+                    name: name.clone(),
+                    addr,
+                    port: entry.port.unwrap_or(20000),
+                    pin_recv_core: entry.pin_recv_core,
+                    pin_decode_core: entry.pin_decode_core,
+                    shred_version: entry.shred_version,
+                    capture_tx,
+                    recv_sockets,
+                    chaos,
+                    headers_only,
+                    max_lag_slots,
+                    forensics: None,
+                    repair: None,
+                    budget: None,
+                    verifier: None,
+                    tuning: None,
+                    stop: None,
+                },
             })
         }
         other => {
@@ -501,3 +1073,83 @@ pub fn build_source(
 
     Ok((source, metrics))
 }
+
+/// Connection settings from a source's `grpc` table; defaults without one.
+fn grpc_settings(entry: Option<&GrpcEntry>) -> Result<GrpcSettings> {
+    let mut settings = GrpcSettings::default();
+    let Some(g) = entry else { return Ok(settings) };
+    if let Some(ms) = g.connect_timeout_ms {
+        settings.connect_timeout = Duration::from_millis(ms);
+    }
+    settings.request_timeout = g.request_timeout_ms.map(Duration::from_millis);
+    match g.keepalive_secs {
+        Some(0) => settings.keepalive_interval = None,
+        Some(secs) => settings.keepalive_interval = Some(Duration::from_secs(secs)),
+        None => {}
+    }
+    if let Some(secs) = g.keepalive_timeout_secs {
+        settings.keepalive_timeout = Duration::from_secs(secs);
+    }
+    if let Some(ref tls) = g.tls {
+        let ca_cert_pem = match tls.ca_cert {
+            Some(ref path) => Some(
+                std::fs::read(path)
+                    .map_err(|e| anyhow::anyhow!("grpc.tls.ca_cert {}: {}", path, e))?,
+            ),
+            None => None,
+        };
+        settings.tls = GrpcTls {
+            ca_cert_pem,
+            domain: tls.domain.clone(),
+            insecure_skip_verify: tls.insecure_skip_verify,
+        };
+    }
+    settings.compression = g.compression.as_deref().map(GrpcCompression::from_name).transpose()?;
+    Ok(settings)
+}
+
+/// For `type = "chaos"`, the wrapped source type and its degradation;
+/// otherwise the entry's own type and `None`.
+fn chaos_wrapper<'a>(
+    entry: &'a SourceEntry,
+    name: &str,
+) -> Result<(&'a str, Option<shred_ingest::ChaosConfig>)> {
+    if entry.source_type != "chaos" {
+        return Ok((entry.source_type.as_str(), None));
+    }
+    if !cfg!(debug_assertions) {
+        anyhow::bail!(
+            "source '{}': chaos sources are a testing aid and only available in debug builds",
+            name
+        );
+    }
+    let c = entry
+        .chaos
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("source '{}': type \"chaos\" requires a chaos table", name))?;
+    if !matches!(c.wraps.as_str(), "shred" | "turbine" | "unicast") {
+        anyhow::bail!(
+            "source '{}': chaos can only wrap shred, turbine or unicast sources (got '{}')",
+            name,
+            c.wraps
+        );
+    }
+    if !(0.0..=100.0).contains(&c.drop_pct) {
+        anyhow::bail!("source '{}': chaos drop_pct must be between 0 and 100", name);
+    }
+    let seed = c.seed.unwrap_or_else(shred_ingest::metrics::now_ns);
+    tracing::warn!(
+        "source '{}': chaos enabled (drop {}%, reorder window {}, delay {}+{}µs, seed {})",
+        name, c.drop_pct, c.reorder_window, c.delay_us, c.jitter_us, seed
+    );
+    Ok((
+        c.wraps.as_str(),
+        Some(shred_ingest::ChaosConfig {
+            drop_pct: c.drop_pct,
+            reorder_window: c.reorder_window,
+            delay_us: c.delay_us,
+            jitter_us: c.jitter_us,
+            seed,
+        }),
+    ))
+}
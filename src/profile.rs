@@ -0,0 +1,214 @@
+//! Named profiles in one probe.toml (`[profile.NAME]`, `--profile NAME`).
+//!
+//! A host that benchmarks relays of several clusters runs one probe per
+//! cluster. The keys at the top of probe.toml are shared by every profile;
+//! the keys of `[profile.NAME]` replace them whole, so a profile that sets
+//! `sources` or `[capture]` brings its own and inherits the rest.
+//!
+//! Each profile also gets its own default files, so two probes on one host
+//! never write to the same place: `-NAME` is appended to the file name of the
+//! metrics log, the state file, the control socket, and of every path in
+//! probe.toml left at its default. The systemd unit becomes `shredtop@NAME`.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+use toml_edit::{ArrayOfTables, DocumentMut, Item};
+
+use crate::config::{
+    CaptureConfig, EpochReportConfig, ForensicsConfig, SourceEntry, TraceConfig, TxSinkConfig,
+};
+
+/// Default locations of the files a probe uses outside probe.toml.
+#[derive(Debug, Clone)]
+pub struct Paths {
+    pub log: PathBuf,
+    pub state: PathBuf,
+    pub ctl_socket: PathBuf,
+    /// systemd unit name, without `.service`.
+    pub unit: String,
+}
+
+impl Paths {
+    pub fn new(profile: Option<&str>) -> Self {
+        Self {
+            log: suffixed(crate::run::DEFAULT_LOG, profile).into(),
+            state: suffixed(crate::state::DEFAULT_STATE, profile).into(),
+            ctl_socket: suffixed(crate::ctl::DEFAULT_CTL_SOCKET, profile).into(),
+            unit: match profile {
+                Some(name) => format!("shredtop@{}", name),
+                None => "shredtop".into(),
+            },
+        }
+    }
+}
+
+/// Profile names end up in file and unit names.
+pub fn validate(name: &str) -> Result<()> {
+    anyhow::ensure!(
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || "_-".contains(c)),
+        "profile name '{}' may only contain letters, digits, '-' and '_'",
+        name
+    );
+    Ok(())
+}
+
+/// `path` with `-NAME` appended to its file stem, e.g.
+/// `/var/log/shredtop.jsonl` → `/var/log/shredtop-testnet.jsonl`.
+pub fn suffixed(path: &str, profile: Option<&str>) -> String {
+    let Some(name) = profile else { return path.to_string() };
+    let p = Path::new(path);
+    let stem = p.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let file = match p.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}-{}.{}", stem, name, ext),
+        None => format!("{}-{}", stem, name),
+    };
+    p.with_file_name(file).to_string_lossy().into_owned()
+}
+
+/// The settings of `profile` from the parsed probe.toml `table`: the shared
+/// keys overlaid with the profile's, and its default paths filled in.
+/// Without a profile, the shared keys alone.
+pub fn select(mut table: Table, profile: Option<&str>) -> Result<Table> {
+    let profiles = match table.remove("profile") {
+        Some(Value::Table(profiles)) => profiles,
+        Some(_) => anyhow::bail!("`profile` must be a table of [profile.NAME] sections"),
+        None => Table::new(),
+    };
+    let Some(name) = profile else {
+        anyhow::ensure!(
+            profiles.is_empty() || table.contains_key("sources"),
+            "probe.toml has profiles but no shared sources; pick one with --profile ({})",
+            names(&profiles).join(", ")
+        );
+        return Ok(table);
+    };
+    validate(name)?;
+    let Some(Value::Table(overlay)) = profiles.get(name) else {
+        anyhow::bail!(
+            "profile '{}' is not defined in probe.toml (defined: {})",
+            name,
+            if profiles.is_empty() { "none".into() } else { names(&profiles).join(", ") }
+        );
+    };
+    table.extend(overlay.clone());
+
+    let defaults = [
+        ("capture", "output_dir", CaptureConfig::default_output_dir()),
+        ("epoch_report", "output_dir", EpochReportConfig::default_output_dir()),
+        ("forensics", "output", ForensicsConfig::default_output()),
+        ("trace", "output", TraceConfig::default_output()),
+        ("tx_sink", "socket", TxSinkConfig::default_socket()),
+    ];
+    for (section, key, default) in defaults {
+        if let Some(Value::Table(section)) = table.get_mut(section) {
+            section
+                .entry(key)
+                .or_insert_with(|| Value::String(suffixed(&default, Some(name))));
+        }
+    }
+    Ok(table)
+}
+
+/// Whether `config_path` defines `[profile.NAME]`.
+pub fn defined(config_path: &Path, name: &str) -> Result<bool> {
+    let table = read(config_path)?;
+    Ok(table.get("profile").and_then(|p| p.get(name)).is_some())
+}
+
+/// Write `sources`, and `capture` if set, to `[profile.NAME]` of
+/// `config_path`, or to its shared keys without a profile. Other keys, other
+/// profiles, comments and formatting are left as they were.
+pub fn write_sources(
+    config_path: &Path,
+    profile: Option<&str>,
+    sources: &[SourceEntry],
+    capture: Option<&CaptureConfig>,
+) -> Result<()> {
+    let text = if config_path.exists() {
+        std::fs::read_to_string(config_path)
+            .with_context(|| format!("failed to read config file: {}", config_path.display()))?
+    } else {
+        String::new()
+    };
+    let mut doc: DocumentMut = text
+        .parse()
+        .with_context(|| format!("failed to parse config file: {}", config_path.display()))?;
+
+    let mut found = Table::new();
+    found.insert("sources".into(), Value::try_from(sources)?);
+    if let Some(capture) = capture {
+        found.insert("capture".into(), Value::try_from(capture)?);
+    }
+    let found = toml_edit::ser::to_document(&found)?;
+
+    let target = match profile {
+        None => doc.as_table_mut(),
+        Some(name) => doc
+            .entry("profile")
+            .or_insert_with(implicit_table)
+            .as_table_mut()
+            .context("`profile` must be a table of [profile.NAME] sections")?
+            .entry(name)
+            .or_insert_with(implicit_table)
+            .as_table_mut()
+            .with_context(|| format!("`profile.{}` must be a table", name))?,
+    };
+    // Shared sections go before the first table of the file.
+    let position = if profile.is_some() { target.position() } else { Some(0) };
+    for key in ["sources", "capture"] {
+        if let Some(item) = found.get(key) {
+            target.insert(key, sections(item.clone(), position));
+        }
+    }
+    std::fs::write(config_path, doc.to_string())?;
+    Ok(())
+}
+
+fn implicit_table() -> Item {
+    let mut table = toml_edit::Table::new();
+    table.set_implicit(true);
+    Item::Table(table)
+}
+
+/// `item` as `[table]` or `[[array]]` sections instead of an inline value,
+/// written right after the table at `position` (or after the one before
+/// them, without a position).
+fn sections(item: Item, position: Option<usize>) -> Item {
+    let section = |table: toml_edit::InlineTable| {
+        let mut table = table.into_table();
+        if let Some(position) = position {
+            table.set_position(position);
+        }
+        table
+    };
+    match item {
+        Item::Value(toml_edit::Value::InlineTable(table)) => Item::Table(section(table)),
+        Item::Value(toml_edit::Value::Array(array))
+            if array.iter().all(toml_edit::Value::is_inline_table) =>
+        {
+            let mut tables = ArrayOfTables::new();
+            for value in array {
+                if let toml_edit::Value::InlineTable(table) = value {
+                    tables.push(section(table));
+                }
+            }
+            Item::ArrayOfTables(tables)
+        }
+        other => other,
+    }
+}
+
+fn read(config_path: &Path) -> Result<Table> {
+    if !config_path.exists() {
+        return Ok(Table::new());
+    }
+    let text = std::fs::read_to_string(config_path)
+        .with_context(|| format!("failed to read config file: {}", config_path.display()))?;
+    toml::from_str(&text)
+        .with_context(|| format!("failed to parse config file: {}", config_path.display()))
+}
+
+fn names(profiles: &Table) -> Vec<String> {
+    profiles.keys().cloned().collect()
+}
@@ -0,0 +1,161 @@
+//! Prometheus remote-write push client.
+//!
+//! For probes behind NAT that cannot be scraped: every `interval_secs` the
+//! latest snapshot is converted to the same series `/metrics` serves (see
+//! [`metrics_server::samples`]) and POSTed as a snappy-compressed protobuf
+//! `WriteRequest` (remote-write protocol 1.0). Labels from `[remote_write]`
+//! are added to every series.
+//!
+//! A failed push is not retried; the next interval sends a fresh snapshot.
+//! Failures are logged once until a push succeeds again.
+
+use prost::Message;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use shred_ingest::GeyserAuth;
+
+use crate::config::RemoteWriteConfig;
+use crate::metrics_server::{self, MetricsSnapshot};
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    timeseries: Vec<TimeSeries>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct TimeSeries {
+    #[prost(message, repeated, tag = "1")]
+    labels: Vec<Label>,
+    #[prost(message, repeated, tag = "2")]
+    samples: Vec<Sample>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Label {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(string, tag = "2")]
+    value: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Sample {
+    #[prost(double, tag = "1")]
+    value: f64,
+    /// Unix milliseconds.
+    #[prost(int64, tag = "2")]
+    timestamp: i64,
+}
+
+/// Validate `cfg` and spawn the push thread.
+///
+/// Returns a `RemoteWriteUpdater` that `run.rs` feeds with every snapshot.
+pub fn spawn(cfg: &RemoteWriteConfig) -> anyhow::Result<RemoteWriteUpdater> {
+    if !cfg.url.starts_with("http://") && !cfg.url.starts_with("https://") {
+        anyhow::bail!("remote_write.url must start with http:// or https://");
+    }
+    if cfg.interval_secs == 0 {
+        anyhow::bail!("remote_write.interval_secs must be greater than zero");
+    }
+    let auth = match &cfg.auth {
+        Some(a) => GeyserAuth::from_scheme(&a.scheme, &a.token, a.header.as_deref())
+            .map_err(|e| anyhow::anyhow!("remote_write: {}", e))?,
+        None => GeyserAuth::None,
+    };
+
+    let state: Arc<Mutex<Option<MetricsSnapshot>>> = Arc::default();
+    let state_pusher = state.clone();
+    let cfg = cfg.clone();
+    let agent = ureq::AgentBuilder::new().timeout(Duration::from_secs(10)).build();
+
+    std::thread::Builder::new()
+        .name("remote-write".into())
+        .spawn(move || {
+            let mut failing = false;
+            loop {
+                std::thread::sleep(Duration::from_secs(cfg.interval_secs));
+                let Some(snap) = state_pusher.lock().unwrap().clone() else { continue };
+                match push(&agent, &cfg, &auth, &snap) {
+                    Ok(()) if failing => {
+                        failing = false;
+                        tracing::info!("remote_write: pushes to {} succeeding again", cfg.url);
+                    }
+                    Ok(()) => {}
+                    Err(e) if !failing => {
+                        failing = true;
+                        tracing::warn!("remote_write: push to {} failed: {:#}", cfg.url, e);
+                    }
+                    Err(_) => {}
+                }
+            }
+        })?;
+
+    Ok(RemoteWriteUpdater { state })
+}
+
+pub struct RemoteWriteUpdater {
+    state: Arc<Mutex<Option<MetricsSnapshot>>>,
+}
+
+impl RemoteWriteUpdater {
+    pub fn update(&self, snapshot: MetricsSnapshot) {
+        *self.state.lock().unwrap() = Some(snapshot);
+    }
+}
+
+fn push(
+    agent: &ureq::Agent,
+    cfg: &RemoteWriteConfig,
+    auth: &GeyserAuth,
+    snap: &MetricsSnapshot,
+) -> anyhow::Result<()> {
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64;
+    let body = snap::raw::Encoder::new()
+        .compress_vec(&write_request(snap, &cfg.labels, now_ms).encode_to_vec())?;
+
+    let mut req = agent
+        .post(&cfg.url)
+        .set("Content-Encoding", "snappy")
+        .set("Content-Type", "application/x-protobuf")
+        .set("X-Prometheus-Remote-Write-Version", "0.1.0")
+        .set("User-Agent", concat!("shredtop/", env!("CARGO_PKG_VERSION")));
+    if let Some((name, value)) = auth.header() {
+        req = req.set(&name, &value);
+    }
+    match req.send_bytes(&body) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, resp)) => {
+            let text = resp.into_string().unwrap_or_default();
+            anyhow::bail!("HTTP {}: {}", code, text.trim())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// One series per exported sample. Labels are sorted by name, as the
+/// protocol requires, with `__name__` carrying the metric name.
+fn write_request(
+    snap: &MetricsSnapshot,
+    extra: &BTreeMap<String, String>,
+    timestamp_ms: i64,
+) -> WriteRequest {
+    let timeseries = metrics_server::samples(snap)
+        .into_iter()
+        .map(|s| {
+            let mut labels: BTreeMap<String, String> = extra.clone();
+            labels.extend(s.labels);
+            labels.insert("__name__".into(), s.name.into());
+            TimeSeries {
+                labels: labels.into_iter().map(|(name, value)| Label { name, value }).collect(),
+                samples: vec![Sample { value: s.value, timestamp: timestamp_ms }],
+            }
+        })
+        .collect();
+    WriteRequest { timeseries }
+}
@@ -0,0 +1,40 @@
+//! `shredtop replay` — deterministic replay of a pcap through the pipeline.
+//!
+//! Every feed in the capture gets its own decoder, and the decoded
+//! transactions and shred arrivals go through the same fan-in dedup and race
+//! tracking as `shredtop run` — but on one thread, in capture-timestamp order,
+//! with the packet timestamps as the only clock. The JSON report is therefore
+//! byte-identical across runs and machines, so a checked-in report can serve
+//! as a golden output when changing metric code.
+
+use anyhow::Result;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+
+pub fn run(pcap: &Path, feeds: &[(Ipv4Addr, String)], output: Option<PathBuf>) -> Result<()> {
+    if feeds.is_empty() {
+        anyhow::bail!("at least one --feed IP=NAME is required");
+    }
+    eprintln!("shredtop replay — replaying {} deterministically...", pcap.display());
+    let report = shred_ingest::offline::replay_pcap(pcap, feeds)?;
+
+    let json = serde_json::to_string_pretty(&report)?;
+    match output {
+        Some(path) => {
+            std::fs::write(&path, format!("{}\n", json))?;
+            eprintln!("Report written to {}", path.display());
+        }
+        None => {
+            println!("{}", json);
+        }
+    }
+
+    eprintln!();
+    for f in &report.feeds {
+        eprintln!(
+            "  {:<20}  packets={}  slots={}  txs={}  first={}  dup={}",
+            f.name, f.packets, f.slots_complete, f.txs_decoded, f.txs_first, f.txs_duplicate,
+        );
+    }
+    Ok(())
+}
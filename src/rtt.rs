@@ -0,0 +1,319 @@
+//! Round-trip time from this host to the endpoints the feeds come from.
+//!
+//! A lead that moves by a few ms means little until it is known whether the
+//! network path under it moved too. With `[rtt]` configured, a background
+//! thread probes, every `interval_secs`:
+//!
+//! - the DoubleZero ingress: `dz_ingress`, or else the remote end of the
+//!   tunnel on the first shred source's `interface`;
+//! - the host of each `rpc` source's `url`;
+//! - the host of each `geyser` source's `url`.
+//!
+//! Each probe is `PROBES` ICMP echoes through the system `ping`. Hosts that
+//! drop ICMP, and hosts where `ping` is missing, are timed by TCP connect
+//! instead when their URL has a port: a connect takes one round trip. The
+//! results go into every metrics log entry as `rtt`.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::config::{RttConfig, SourceEntry};
+
+/// Echoes (or connects) per probe.
+const PROBES: u32 = 3;
+/// Probes kept per target for `p50_ms`; an hour at the default interval.
+const KEEP_PROBES: usize = 60;
+
+/// An endpoint to probe.
+struct Target {
+    /// `dz_ingress`, or the name of the source the host came from.
+    name: String,
+    kind: &'static str,
+    host: String,
+    /// TCP port for the connect fallback, if the endpoint has one.
+    port: Option<u16>,
+}
+
+/// Shared results, read by the snapshot loop.
+#[derive(Clone)]
+pub struct RttStats {
+    inner: Arc<Mutex<Vec<TargetStats>>>,
+}
+
+struct TargetStats {
+    target: Target,
+    last: Option<Probe>,
+    /// Mean RTT of the last [`KEEP_PROBES`] answered probes, ms.
+    recent_ms: VecDeque<f64>,
+    failures: u64,
+}
+
+struct Probe {
+    /// Unix seconds.
+    ts: u64,
+    method: &'static str,
+    mean_ms: f64,
+    min_ms: f64,
+    max_ms: f64,
+    lost: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RttSnapshot {
+    pub name: String,
+    /// `dz_ingress`, `rpc` or `geyser`.
+    pub kind: &'static str,
+    pub host: String,
+    /// `icmp` or `tcp`; omitted until a probe is answered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<&'static str>,
+    /// Unix seconds of the last probe that was answered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probed_at: Option<u64>,
+    /// Mean, min and max of the last answered probe, ms.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rtt_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_ms: Option<f64>,
+    /// Echoes of the last answered probe that got no reply.
+    pub lost: u32,
+    /// Median of the last [`KEEP_PROBES`] answered probes, ms: the baseline
+    /// `rtt_ms` is read against.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p50_ms: Option<f64>,
+    /// Probes that got no answer at all.
+    pub failures: u64,
+}
+
+/// Start the probe thread. Returns `None` when no target could be found.
+pub fn spawn(cfg: &RttConfig, sources: &[SourceEntry]) -> Result<Option<(RttStats, usize)>> {
+    anyhow::ensure!(cfg.interval_secs > 0, "rtt.interval_secs must be > 0");
+    let targets = targets(cfg, sources);
+    if targets.is_empty() {
+        return Ok(None);
+    }
+    let count = targets.len();
+    let stats = RttStats {
+        inner: Arc::new(Mutex::new(
+            targets
+                .into_iter()
+                .map(|target| TargetStats {
+                    target,
+                    last: None,
+                    recent_ms: VecDeque::with_capacity(KEEP_PROBES),
+                    failures: 0,
+                })
+                .collect(),
+        )),
+    };
+    let thread_stats = stats.clone();
+    let interval = Duration::from_secs(cfg.interval_secs);
+    let timeout = Duration::from_millis(cfg.timeout_ms.max(1));
+    std::thread::Builder::new()
+        .name("rtt-probe".into())
+        .spawn(move || probe_loop(&thread_stats, interval, timeout))?;
+    Ok(Some((stats, count)))
+}
+
+impl RttStats {
+    pub fn snapshot(&self) -> Vec<RttSnapshot> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .iter()
+            .map(|t| RttSnapshot {
+                name: t.target.name.clone(),
+                kind: t.target.kind,
+                host: t.target.host.clone(),
+                method: t.last.as_ref().map(|p| p.method),
+                probed_at: t.last.as_ref().map(|p| p.ts),
+                rtt_ms: t.last.as_ref().map(|p| round(p.mean_ms)),
+                min_ms: t.last.as_ref().map(|p| round(p.min_ms)),
+                max_ms: t.last.as_ref().map(|p| round(p.max_ms)),
+                lost: t.last.as_ref().map_or(0, |p| p.lost),
+                p50_ms: median(&t.recent_ms).map(round),
+                failures: t.failures,
+            })
+            .collect()
+    }
+}
+
+fn probe_loop(stats: &RttStats, interval: Duration, timeout: Duration) {
+    let hosts: Vec<(String, Option<u16>)> = {
+        let inner = stats.inner.lock().unwrap();
+        inner.iter().map(|t| (t.target.host.clone(), t.target.port)).collect()
+    };
+    loop {
+        let started = Instant::now();
+        for (i, (host, port)) in hosts.iter().enumerate() {
+            let probe =
+                ping(host, timeout).or_else(|| port.and_then(|p| connect(host, p, timeout)));
+            let mut inner = stats.inner.lock().unwrap();
+            let t = &mut inner[i];
+            match probe {
+                Some(probe) => {
+                    if t.recent_ms.len() == KEEP_PROBES {
+                        t.recent_ms.pop_front();
+                    }
+                    t.recent_ms.push_back(probe.mean_ms);
+                    t.last = Some(probe);
+                }
+                None => t.failures += 1,
+            }
+        }
+        std::thread::sleep(interval.saturating_sub(started.elapsed()));
+    }
+}
+
+/// [`PROBES`] echoes through the system `ping`; `None` if `ping` is missing
+/// or nothing came back.
+fn ping(host: &str, timeout: Duration) -> Option<Probe> {
+    let wait_secs = timeout.as_secs().max(1).to_string();
+    let output = Command::new("ping")
+        .args(["-n", "-q", "-c", &PROBES.to_string(), "-i", "0.2", "-W", &wait_secs, host])
+        .output()
+        .ok()?;
+    parse_ping(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// The summary lines of iputils and busybox `ping -q`:
+///
+/// ```text
+/// 3 packets transmitted, 3 received, 0% packet loss, time 402ms
+/// rtt min/avg/max/mdev = 0.412/0.455/0.519/0.046 ms
+/// ```
+fn parse_ping(text: &str) -> Option<Probe> {
+    let mut received = None;
+    let mut rtt = None;
+    for line in text.lines() {
+        if line.contains("packets transmitted") {
+            received = line
+                .split(',')
+                .find(|f| f.contains("received"))
+                .and_then(|f| f.split_whitespace().next()?.parse::<u32>().ok());
+        } else if let Some((_, values)) = line.split_once(" = ") {
+            let values: Vec<f64> = values
+                .split_whitespace()
+                .next()?
+                .split('/')
+                .filter_map(|v| v.parse().ok())
+                .collect();
+            if let [min, avg, max, ..] = values[..] {
+                rtt = Some((min, avg, max));
+            }
+        }
+    }
+    let (min_ms, mean_ms, max_ms) = rtt?;
+    let received = received.unwrap_or(PROBES).min(PROBES);
+    Some(Probe {
+        ts: unix_secs(),
+        method: "icmp",
+        mean_ms,
+        min_ms,
+        max_ms,
+        lost: PROBES - received,
+    })
+}
+
+/// [`PROBES`] TCP connects; `None` if none succeeded.
+fn connect(host: &str, port: u16, timeout: Duration) -> Option<Probe> {
+    let addr: SocketAddr = (host, port).to_socket_addrs().ok()?.next()?;
+    let times: Vec<f64> = (0..PROBES)
+        .filter_map(|_| {
+            let start = Instant::now();
+            TcpStream::connect_timeout(&addr, timeout).ok()?;
+            Some(start.elapsed().as_secs_f64() * 1000.0)
+        })
+        .collect();
+    if times.is_empty() {
+        return None;
+    }
+    Some(Probe {
+        ts: unix_secs(),
+        method: "tcp",
+        mean_ms: times.iter().sum::<f64>() / times.len() as f64,
+        min_ms: times.iter().copied().fold(f64::INFINITY, f64::min),
+        max_ms: times.iter().copied().fold(0.0, f64::max),
+        lost: PROBES - times.len() as u32,
+    })
+}
+
+/// The DZ ingress, then each rpc and geyser source with a url.
+fn targets(cfg: &RttConfig, sources: &[SourceEntry]) -> Vec<Target> {
+    let mut targets = Vec::new();
+    let ingress = cfg.dz_ingress.clone().or_else(|| {
+        let iface = sources
+            .iter()
+            .filter(|s| s.source_type == "shred")
+            .find_map(|s| s.interface.as_deref())?;
+        let remote = tunnel_remote(iface);
+        if remote.is_none() {
+            tracing::warn!("rtt: no tunnel remote on {}; set rtt.dz_ingress to probe it", iface);
+        }
+        remote
+    });
+    if let Some(host) = ingress {
+        targets.push(Target { name: "dz_ingress".into(), kind: "dz_ingress", host, port: None });
+    }
+    for s in sources {
+        let kind = match s.source_type.as_str() {
+            "rpc" => "rpc",
+            "geyser" => "geyser",
+            _ => continue,
+        };
+        let Some((host, port)) = s.url.as_deref().and_then(url_host) else { continue };
+        targets.push(Target { name: s.name.clone(), kind, host, port: Some(port) });
+    }
+    targets
+}
+
+/// The remote address of a GRE tunnel interface, from `ip -d -j link show`.
+fn tunnel_remote(iface: &str) -> Option<String> {
+    let output = Command::new("ip").args(["-d", "-j", "link", "show", "dev", iface]).output().ok()?;
+    let links: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    let remote = links[0]["linkinfo"]["info_data"]["remote"].as_str()?;
+    (remote != "any").then(|| remote.to_string())
+}
+
+/// Host and port of `scheme://host[:port]/…`; the port defaults by scheme.
+fn url_host(url: &str) -> Option<(String, u16)> {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("http", url));
+    let authority = rest.split(['/', '?']).next()?;
+    let authority = authority.rsplit_once('@').map_or(authority, |(_, a)| a);
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(v6) => {
+            let (host, rest) = v6.split_once(']')?;
+            (host, rest.strip_prefix(':'))
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    let port = match port {
+        Some(port) => port.parse().ok()?,
+        None if scheme.eq_ignore_ascii_case("https") => 443,
+        None => 80,
+    };
+    (!host.is_empty()).then(|| (host.to_string(), port))
+}
+
+fn median(values: &VecDeque<f64>) -> Option<f64> {
+    let mut sorted: Vec<f64> = values.iter().copied().collect();
+    sorted.sort_by(f64::total_cmp);
+    sorted.get(sorted.len() / 2).copied()
+}
+
+fn round(ms: f64) -> f64 {
+    (ms * 1000.0).round() / 1000.0
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
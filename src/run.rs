@@ -4,60 +4,295 @@
 //! snapshots to a JSONL log file every N seconds. Designed to run under
 //! systemd or in a tmux session. Use `shredtop status` to query the log,
 //! or `shredtop service install` to manage via systemd.
+//!
+//! The first line of the log is a `run_start` record describing the binary,
+//! host and configuration (see [`crate::run_start`]). With `--interval-ms`,
+//! lightweight `sample` records are written between snapshots (see
+//! [`crate::sample`]).
+//!
+//! On SIGTERM/SIGINT the cumulative counters and shred-race statistics are
+//! saved to a state file and restored on the next start (see [`crate::state`]).
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::Serialize;
-use shred_ingest::{CaptureEvent, DecodedTx, FanInSource, ShredPairSnapshot, SourceMetricsSnapshot};
-use std::fs::OpenOptions;
-use std::io::Write;
+use shred_ingest::output::{EventMask, NamedSink, OutputHub, SinkRegistry, Snapshot};
+use shred_ingest::{
+    BudgetSnapshot, CaptureEvent, ClockCheckSnapshot, FanInSource, ForensicsTap, ForwardedTx,
+    LeadHistogram, LeaderSchedule, PositionCoverageSnapshot, RepairClient, RepairPeer,
+    RepairSettings, SenderMixSnapshot, MemoryBudget, PairRule, ShredMixSnapshot, ShredPairSnapshot,
+    ShredRankSnapshot, ShredVerifier, SlotForensics, SlotStats, SourceError, SourceMetricsSnapshot,
+};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use crate::capture;
-use crate::config::ProbeConfig;
+use crate::alerts::{Alert, Alerts, SourceCheck};
+use crate::capture::{self, CaptureIoSnapshot};
+use crate::config::{ForensicsConfig, LogConfig, ProbeConfig, RacePairConfig, RepairConfig};
+use crate::clock_sync::{ClockSync, ClockSyncSnapshot};
+use crate::confirm_audit::{self, AuditSnapshot};
+use crate::rtt::{self, RttSnapshot};
+use crate::ctl;
+use crate::epoch_report::EpochReporter;
+use crate::funnel::FunnelLog;
+use crate::history::History;
+use crate::metrics_log::LogWriter;
 use crate::metrics_server::{self, MetricsSnapshot};
-use crate::monitor::build_source;
+use crate::monitor::{build_failover_groups, build_source};
+use crate::remote_write;
+use crate::run_start::RunStart;
+use crate::sample::{self, Sampler};
+use crate::state;
+use crate::trace;
+use crate::tx_sink;
+use crate::web;
 
 pub const DEFAULT_LOG: &str = "/var/log/shredtop.jsonl";
 
+/// Number of most recently finalized slots written per source for `monitor --slots`.
+pub const RECENT_SLOTS: usize = 20;
+
+static RUNNING: AtomicBool = AtomicBool::new(true);
+
+extern "C" fn handle_shutdown(_: libc::c_int) {
+    RUNNING.store(false, Ordering::SeqCst);
+}
+
 #[derive(Serialize)]
 struct LogEntry<'a> {
     ts: u64,
     started_at: u64,
+    /// `latency_budget_ms` of the config; see `in_budget_pct`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latency_budget_ms: Option<u64>,
     sources: Vec<SourceSnap<'a>>,
     shred_race: Vec<ShredPairSnapshot>,
+    /// Race to "slot fully decodable" between shred feeds.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    slot_race: Vec<ShredPairSnapshot>,
+    /// Race of each feed's first shred of a slot, over all slots and over the
+    /// slots that open a leader's window.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    start_race: Vec<ShredPairSnapshot>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    handoff_race: Vec<ShredPairSnapshot>,
+    /// Shred race counting FEC-recovered copies as available (see `fec_wins`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    avail_race: Vec<ShredPairSnapshot>,
+    /// How often each shred feed delivered a shred first among all feeds.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    shred_rank: Vec<ShredRankSnapshot>,
+    /// Shred arrival against on-chain block time for sampled winners.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confirmation_audit: Option<AuditSnapshot>,
+    /// Round trip to the DZ ingress and the rpc and geyser hosts.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    rtt: Vec<RttSnapshot>,
+    /// Capture file I/O since start (`[capture]` only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    capture: Option<CaptureIoSnapshot>,
+    /// Memory budget use and sheds since start (`[limits]` only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<BudgetSnapshot>,
+    /// Negative timestamp deltas and cross-clock timestamps since start;
+    /// omitted while both are 0.
+    #[serde(skip_serializing_if = "ClockCheckSnapshot::is_clean")]
+    clock: ClockCheckSnapshot,
+    /// Clock discipline status, and whether this interval's races can be
+    /// trusted (see [`crate::clock_sync`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clock_sync: Option<ClockSyncSnapshot>,
+    /// Health rules firing for a source (see [`crate::alerts`]).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    alerts: Vec<Alert>,
+    /// Shreds/s and lead p50 of each source over the last snapshots (see
+    /// [`crate::history`]).
+    history: &'a History,
 }
 
 #[derive(Serialize)]
 struct SourceSnap<'a> {
     name: &'a str,
+    /// Tags from the source's `labels` config.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    labels: &'a BTreeMap<String, String>,
     /// True for RPC-tier sources (rpc, geyser); false for shred-tier feeds.
     is_rpc: bool,
     shreds_per_sec: f64,
+    /// Decoded share of confirmed transactions once an `rpc` baseline has
+    /// cross-checked slots (`coverage_basis = "confirmed"`), otherwise the
+    /// shred-count estimate (`"shreds"`).
     coverage_pct: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    coverage_basis: Option<&'static str>,
+    /// The shred-count estimate, when `coverage_pct` is the confirmed figure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shred_coverage_pct: Option<f64>,
+    /// Data shreds seen / expected in the head, middle and tail thirds of
+    /// each slot (cumulative), for slots whose length some feed learned.
+    #[serde(skip_serializing_if = "PositionCoverageSnapshot::is_empty")]
+    position_coverage: &'a PositionCoverageSnapshot,
+    /// Confirmed blocks this source never produced a slot record for (cumulative).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slots_missed: Option<u64>,
+    /// Slots this source saw shreds for that the cluster skipped (cumulative).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slots_skipped: Option<u64>,
     /// % of matched transactions where this feed beat RPC (lead_time > 0)
     beat_rpc_pct: Option<f64>,
+    /// % of matched transactions where this feed beat RPC by at least
+    /// `latency_budget_ms` (cumulative, from `lead_time_hist`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    in_budget_pct: Option<f64>,
     lead_time_mean_us: Option<f64>,
     lead_time_p50_us: Option<i64>,
     lead_time_p95_us: Option<i64>,
     lead_time_p99_us: Option<i64>,
     lead_time_samples: u64,
+    /// p95 over recent slots of each slot's longest gap between data shreds, µs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slot_max_gap_p95_us: Option<u64>,
+    /// p95 over recent slots of the per-slot inter-shred standard deviation, µs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slot_jitter_p95_us: Option<u64>,
+    /// Every lead-time sample since start, as sparse `[bound_us, count]`
+    /// buckets (see [`shred_ingest::lead_hist`]). Cumulative across restarts.
+    #[serde(skip_serializing_if = "LeadHistogram::is_empty")]
+    lead_time_hist: &'a LeadHistogram,
     txs_per_sec: f64,
     /// Total transactions this source won the dedup race (first arrival, cumulative)
     txs_first: u64,
     /// Total transactions this source arrived as a duplicate (matched another source, cumulative)
     txs_duplicate: u64,
+    /// Transactions delivered in another slot than their signature was first
+    /// seen in, e.g. resubmitted after a fork was abandoned (cumulative).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    txs_slot_mismatch: Option<u64>,
+    /// Decoded transactions dropped because the fan-in fell behind and its
+    /// channel was full (cumulative).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    txs_dropped: Option<u64>,
     /// Seconds since last DoubleZero heartbeat, or null if never received.
     #[serde(skip_serializing_if = "Option::is_none")]
     secs_since_heartbeat: Option<u64>,
-    /// Packets rejected before the decoder (too short, unknown variant, or heartbeat).
+    /// Shred-shaped packets rejected before the decoder for an unknown variant.
     shreds_invalid: u64,
+    /// Packets that could not be shreds (too short, impossible variant byte,
+    /// or implausibly far-future slot), e.g. control traffic on the group.
+    non_shred_packets: u64,
+    /// Shreds received per variant family (cumulative, including unknown
+    /// variants) and per shred version of accepted shreds; shreds dropped by
+    /// the `shred_version` filter count as `other_versions`.
+    #[serde(skip_serializing_if = "ShredMixSnapshot::is_empty")]
+    shred_mix: &'a ShredMixSnapshot,
+    /// Shreds received per UDP sender IP, with each sender's share of first
+    /// arrivals when several retransmitters feed the same group.
+    #[serde(skip_serializing_if = "SenderMixSnapshot::is_empty")]
+    senders: &'a SenderMixSnapshot,
+    /// Kernel receive buffer granted to the socket (UDP sources on Linux).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    socket_rcvbuf_bytes: Option<u64>,
+    /// Highest receive-queue memory use seen since start.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    socket_rmem_hwm_bytes: Option<u64>,
+    /// Datagrams dropped by the kernel on a full receive buffer (cumulative).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    socket_drops: Option<u64>,
+    /// Share of the receive thread's time spent handling packets rather than
+    /// waiting in recvmmsg over the last interval, % (UDP sources on Linux).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recv_utilization_pct: Option<f64>,
+    /// Mean packets per recvmmsg call over the last interval.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recv_batch_fill: Option<f64>,
+    /// Paused at runtime via `shredtop ctl pause`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    paused: bool,
+    /// Standby member of a failover group (not contributing to races).
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    standby: bool,
+    /// Subtracted from receive timestamps before lead times are computed:
+    /// `timestamp_offset_us` plus any calibrated decode overhead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp_offset_us: Option<f64>,
+    /// RPC source only: slots behind the cluster tip still to be fetched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rpc_slot_lag: Option<u64>,
+    /// Most recently finalized slots (shred sources only), oldest first.
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    recent_slots: &'a [SlotStats],
+    /// Not paused, yet no shred or transaction has arrived since this run
+    /// started (reported once the run is [`SILENT_AFTER_SECS`] old).
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    silent: bool,
+    /// Data shreds requested from repair peers, and missing shreds their
+    /// responses filled (cumulative; see `[repair]`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repair_requests: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shreds_repaired: Option<u64>,
+    /// Shreds left undecoded as too far behind the cluster tip (cumulative;
+    /// see `decode_max_lag_slots`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shreds_decode_skipped: Option<u64>,
+    /// Slots expired by the shared cluster tip that this feed's own highest
+    /// slot would have kept (cumulative; see `slot_expiry`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slots_expired_shared_tip: Option<u64>,
+    /// Shreds signed by the slot leader, signed by someone else, and not
+    /// checked (cumulative; see `[shred_verify]`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shreds_sig_verified: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shreds_sig_invalid: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shreds_sig_unchecked: Option<u64>,
+    /// Failures the source reported since start, e.g. reconnects.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors_total: Option<u64>,
+    /// Failures per minute over the last interval.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors_per_min: Option<f64>,
+    /// Most recent failure: its kind (`bind`, `receive`, `disconnect`,
+    /// `rpc`, `registration`, `crash`), message and Unix time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_error: Option<&'a SourceError>,
+    /// Times the source was restarted after its threads stopped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    restarts: Option<u64>,
+    /// Threads stopped and the source is waiting to restart, or out of
+    /// restarts (see `[restart]`).
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    degraded: bool,
 }
 
-pub fn run(config: &ProbeConfig, interval_secs: u64, log_path: PathBuf) -> Result<()> {
+/// How long a source may go without data after start before it is reported
+/// as silent.
+const SILENT_AFTER_SECS: u64 = 10;
+
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    config: &ProbeConfig,
+    interval_secs: u64,
+    interval_ms: Option<u64>,
+    funnel: bool,
+    log_path: PathBuf,
+    state_path: PathBuf,
+    fresh: bool,
+    ctl_socket: PathBuf,
+) -> Result<()> {
     if config.sources.is_empty() {
         anyhow::bail!("no sources configured — run `shredtop discover` first");
     }
+    if let Some(ms) = interval_ms {
+        anyhow::ensure!(
+            ms >= sample::MIN_INTERVAL_MS,
+            "--interval-ms must be at least {}",
+            sample::MIN_INTERVAL_MS
+        );
+    }
 
     eprintln!(
         "shredtop run — {} source(s), logging to {} every {}s",
@@ -65,6 +300,27 @@ pub fn run(config: &ProbeConfig, interval_secs: u64, log_path: PathBuf) -> Resul
         log_path.display(),
         interval_secs
     );
+    if config.log.enabled() {
+        let mut limits = Vec::new();
+        if config.log.rotate_mb > 0 {
+            limits.push(format!("{} MB", config.log.rotate_mb));
+        }
+        if config.log.rotate_hours > 0 {
+            limits.push(format!("{} h", config.log.rotate_hours));
+        }
+        eprintln!(
+            "shredtop log — rotating every {}, keeping {}{}",
+            limits.join(" or "),
+            config.log.keep,
+            if config.log.gzip { " (gzip)" } else { "" },
+        );
+    }
+    if let Some(ms) = interval_ms {
+        eprintln!("shredtop run — sampling every {}ms between snapshots", ms);
+    }
+    if funnel {
+        eprintln!("shredtop run — logging a funnel record per finalized slot");
+    }
     eprintln!("Run `shredtop status` to check current metrics.");
 
     // Spin up the optional Prometheus metrics server.
@@ -74,11 +330,57 @@ pub fn run(config: &ProbeConfig, interval_secs: u64, log_path: PathBuf) -> Resul
         None
     };
 
+    // Start the optional remote-write pusher.
+    let remote_write_updater = match &config.remote_write {
+        Some(rw) => {
+            let updater = remote_write::spawn(rw)?;
+            eprintln!("shredtop remote_write — pushing to {} every {}s", rw.url, rw.interval_secs);
+            Some(updater)
+        }
+        None => None,
+    };
+
+    // Bind the transaction sink and build the [[outputs]] before any
+    // transaction is decoded.
+    let mut registry = SinkRegistry::default();
+    registry.register("influx", crate::influx::InfluxSink::from_settings);
+    let mut sinks = Vec::new();
+    if let Some(cfg) = &config.tx_sink {
+        let sink = tx_sink::spawn(cfg)?;
+        eprintln!("shredtop tx_sink — streaming transactions on {}", cfg.socket);
+        sinks.push(NamedSink {
+            name: "tx_sink".into(),
+            events: EventMask { transactions: true, snapshots: false, races: false },
+            sink: Box::new(sink),
+        });
+    }
+    for table in &config.outputs {
+        let sink = registry.build(serde_json::to_value(table)?)?;
+        eprintln!("shredtop outputs — '{}' enabled", sink.name);
+        sinks.push(sink);
+    }
+    let hub = Arc::new(OutputHub::spawn(sinks)?);
+
+    // Spin up the optional web dashboard.
+    let web_updater = if config.web.enabled {
+        Some(web::spawn(config.web.port))
+    } else {
+        None
+    };
+
     // Spin up the capture thread if [capture] is configured and enabled.
+    let mut trigger_tx = None;
     let cap_tx: Option<crossbeam_channel::Sender<CaptureEvent>> =
         if let Some(cap_cfg) = config.capture.as_ref().filter(|c| c.enabled) {
+            let feeds: Vec<&str> = config.sources.iter().map(|s| s.name.as_str()).collect();
+            capture::validate(cap_cfg, &feeds)?;
             let (tx, rx) = crossbeam_channel::bounded::<CaptureEvent>(4096);
-            capture::spawn_capture_thread(cap_cfg, rx);
+            let triggers = cap_cfg.trigger.as_ref().map(|_| {
+                let (ttx, trx) = crossbeam_channel::bounded::<capture::Trigger>(64);
+                trigger_tx = Some(ttx);
+                trx
+            });
+            capture::spawn_capture_thread(cap_cfg, rx, triggers);
             let sizes: Vec<String> = cap_cfg
                 .formats
                 .iter()
@@ -94,23 +396,202 @@ pub fn run(config: &ProbeConfig, interval_secs: u64, log_path: PathBuf) -> Resul
                 cap_cfg.output_dir,
                 cap_cfg.rotate_mb,
             );
+            if cap_cfg.per_feed {
+                eprintln!(
+                    "shredtop capture — one ring per feed under {}/<feed>, each sized as above",
+                    cap_cfg.output_dir,
+                );
+            }
+            if let Some(ref t) = cap_cfg.trigger {
+                eprintln!(
+                    "shredtop capture — triggered: {}s before / {}s after an anomaly, \
+                     {} MB held per source",
+                    t.pre_secs, t.post_secs, t.buffer_mb,
+                );
+            }
             Some(tx)
         } else {
             None
         };
 
     let mut fan_in = FanInSource::new();
+    fan_in.restart = config.restart.policy();
     fan_in.filter_programs = config.filter_programs.clone();
+    fan_in.sample_tx_prefix_bits = config.sample_tx_prefix_bits;
+    fan_in.dedup_signature_only = config.dedup_signature_only;
+    if let Some(ref name) = config.preferred_source {
+        anyhow::ensure!(
+            config.sources.iter().any(|s| &s.name == name),
+            "preferred_source: no source named '{}'",
+            name
+        );
+        fan_in.preferred_source = Some(name.clone());
+        fan_in.tie_window_us = config.tie_window_us;
+    }
+    fan_in.slot_expiry = config.slot_expiry()?;
+    fan_in.forward_duplicates_per_sec = config
+        .tx_sink
+        .as_ref()
+        .filter(|s| s.duplicates)
+        .map(|s| s.max_duplicates_per_sec);
+    if let Some(ref cfg) = config.forensics {
+        fan_in.forensics = Some(spawn_forensics_log(cfg, &config.log)?);
+        eprintln!(
+            "shredtop forensics — slots under {}% coverage → {}",
+            cfg.min_coverage_pct, cfg.output
+        );
+    }
+    if let Some(ref cfg) = config.repair {
+        fan_in.repair = Some(start_repair(cfg)?);
+        eprintln!(
+            "shredtop repair — {} peer(s), slots stuck {}ms with ≤{} shreds missing",
+            cfg.peers.len(),
+            cfg.stuck_ms,
+            cfg.max_missing
+        );
+    }
+    let trace = match config.trace {
+        Some(ref cfg) => {
+            let tap = trace::spawn(cfg, &config.log)?;
+            eprintln!("shredtop trace — 1 in {} transactions → {}", cfg.sample_every, cfg.output);
+            Some(tap)
+        }
+        None => None,
+    };
+    fan_in.trace = trace.clone();
+    if let Some(ref cfg) = config.shred_verify {
+        let rpc_url = cfg
+            .rpc_url
+            .clone()
+            .or_else(|| {
+                let rpc = config.sources.iter().find(|s| s.source_type == "rpc");
+                rpc.and_then(|s| s.url.clone())
+            })
+            .context("shred_verify needs rpc_url, or an `rpc` source with a url")?;
+        let leaders = LeaderSchedule::default();
+        leaders.spawn_refresh(rpc_url.clone())?;
+        fan_in.verifier = Some(ShredVerifier { leaders, drop_invalid: cfg.drop_invalid });
+        eprintln!(
+            "shredtop shred_verify — leader schedule from {}{}",
+            rpc_url,
+            if cfg.drop_invalid { ", dropping invalid shreds" } else { "" }
+        );
+    }
+    let budget = config.limits.as_ref().map(|l| MemoryBudget::new(l.max_memory_mb << 20));
+    if let Some(ref cfg) = config.limits {
+        eprintln!("shredtop limits — {} MB for slot, dedup and race state", cfg.max_memory_mb);
+    }
+    fan_in.budget = budget.clone();
     for entry in &config.sources {
         let (source, metrics) = build_source(entry, cap_tx.clone())?;
         fan_in.add_source(source, metrics);
     }
 
-    let (out_tx, out_rx) = crossbeam_channel::bounded::<DecodedTx>(4096);
+    let pair_rules = config
+        .race_pairs
+        .iter()
+        .map(|p| race_pair_rule(p, config))
+        .collect::<Result<Vec<_>>>()?;
+
+    let (out_tx, out_rx) = crossbeam_channel::bounded::<ForwardedTx>(4096);
     let (all_metrics, race_tracker, _handles) = fan_in.start(out_tx);
+    for (a, b, rule) in pair_rules {
+        race_tracker.set_pair_rule(a, b, rule);
+    }
+    if hub.wants_races() {
+        let hub = hub.clone();
+        race_tracker.on_race(move |e| hub.race(e));
+    }
+    if let (Some(tx), Some(t)) = (
+        trigger_tx,
+        config.capture.as_ref().and_then(|c| c.trigger.as_ref()),
+    ) {
+        capture::spawn_trigger_watch(t, all_metrics.clone(), tx)?;
+    }
 
+    if fresh {
+        eprintln!("shredtop state — --fresh: not restoring {}", state_path.display());
+    } else {
+        match state::restore(&state_path, &all_metrics, &race_tracker) {
+            Ok(Some(saved_at)) => eprintln!(
+                "shredtop state — restored from {} (saved {}s ago)",
+                state_path.display(),
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+                    .saturating_sub(saved_at),
+            ),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("state restore failed, starting fresh: {:#}", e),
+        }
+    }
+
+    let groups = build_failover_groups(&config.sources, &all_metrics)?;
+    if !groups.is_empty() {
+        for g in &groups {
+            eprintln!("shredtop failover — group '{}': {} active", g.name, g.active());
+        }
+        shred_ingest::spawn_failover_monitor(groups, |ev| {
+            tracing::warn!(
+                "failover group '{}': {} silent for {}s, promoted {}",
+                ev.group, ev.from, ev.silent_secs, ev.to
+            );
+        });
+    }
+
+    // The control socket is a convenience; the daemon runs fine without it.
+    match ctl::spawn(&ctl_socket, all_metrics.clone()) {
+        Ok(()) => eprintln!("shredtop ctl — listening on {}", ctl_socket.display()),
+        Err(e) => tracing::warn!("control socket disabled: {:#}", e),
+    }
+
+    RUNNING.store(true, Ordering::SeqCst);
+    unsafe {
+        libc::signal(libc::SIGTERM, handle_shutdown as *const () as libc::sighandler_t);
+        libc::signal(libc::SIGINT, handle_shutdown as *const () as libc::sighandler_t);
+    }
+
+    let (mut audit, audit_stats) = match &config.confirmation_audit {
+        Some(cfg) => {
+            let (audit, stats, url) = confirm_audit::spawn(cfg, &config.sources)?;
+            eprintln!(
+                "shredtop confirmation_audit — {} winners/min checked against {}",
+                cfg.samples_per_min, url
+            );
+            (Some(audit), Some(stats))
+        }
+        None => (None, None),
+    };
+
+    let rtt_stats = match &config.rtt {
+        Some(cfg) => match rtt::spawn(cfg, &config.sources)? {
+            Some((stats, targets)) => {
+                eprintln!(
+                    "shredtop rtt — probing {} endpoints every {}s",
+                    targets, cfg.interval_secs
+                );
+                Some(stats)
+            }
+            None => {
+                tracing::warn!("rtt: no DZ ingress, rpc or geyser endpoint to probe");
+                None
+            }
+        },
+        None => None,
+    };
+
+    let tx_hub = hub.clone();
     std::thread::spawn(move || {
-        for _ in out_rx {}
+        for tx in out_rx {
+            if let Some(ref mut audit) = audit {
+                audit.offer(&tx);
+            }
+            if let Some(ref trace) = trace {
+                trace.record_emit(&tx);
+            }
+            tx_hub.transaction(tx);
+        }
     });
 
     let started_at = SystemTime::now()
@@ -118,17 +599,64 @@ pub fn run(config: &ProbeConfig, interval_secs: u64, log_path: PathBuf) -> Resul
         .unwrap_or_default()
         .as_secs();
 
-    // Truncate the log at startup so the monitor immediately reflects this run.
-    if let Ok(f) = std::fs::File::create(&log_path) {
-        drop(f);
+    let mut clock_sync = ClockSync::new(&config.clock_sync)?;
+    // The first reading sets the baseline for step detection.
+    let _ = clock_sync.as_mut().and_then(|c| c.snapshot());
+
+    let mut log = LogWriter::open(log_path, config.log.clone());
+    if let Ok(line) = serde_json::to_string(&RunStart::new(config, started_at)) {
+        log.set_header(line);
     }
 
     let interval = Duration::from_secs(interval_secs);
+    let tick = interval_ms.map(Duration::from_millis);
+    let mut sampler = tick.map(|_| Sampler::new(&all_metrics));
+    let mut funnel_log = funnel.then(|| FunnelLog::new(all_metrics.len()));
+    let mut alert_rules = Alerts::default();
+    let mut history = History::new(interval_secs, all_metrics.iter().map(|m| &*m.name));
+    let mut next_snapshot = Instant::now() + interval;
+    let mut next_sample = tick.map(|t| Instant::now() + t);
     let mut prev: Vec<SourceMetricsSnapshot> = all_metrics.iter().map(|m| m.snapshot()).collect();
     let mut prev_time = Instant::now();
+    // Counters as restored, to tell what arrived during this run.
+    let initial = prev.clone();
 
-    loop {
-        std::thread::sleep(interval);
+    let mut epoch_reporter = match &config.epoch_report {
+        Some(cfg) => {
+            let reporter = EpochReporter::new(cfg, &config.sources, &prev, &race_tracker)?;
+            eprintln!(
+                "shredtop epoch_report — {} (epochs from {})",
+                cfg.output_dir,
+                reporter.rpc_url()
+            );
+            Some(reporter)
+        }
+        None => None,
+    };
+
+    while RUNNING.load(Ordering::SeqCst) {
+        // Sleep in small steps so a shutdown signal is acted on promptly.
+        let deadline = next_sample.map_or(next_snapshot, |s| s.min(next_snapshot));
+        while RUNNING.load(Ordering::SeqCst) && Instant::now() < deadline {
+            let left = deadline.saturating_duration_since(Instant::now());
+            std::thread::sleep(Duration::from_millis(200).min(left));
+        }
+        if !RUNNING.load(Ordering::SeqCst) {
+            break;
+        }
+
+        if let (Some(sampler), Some(tick), Some(at)) = (&mut sampler, tick, next_sample) {
+            if Instant::now() >= at {
+                if let Ok(line) = sampler.sample().to_log_line() {
+                    log.append(&line);
+                }
+                next_sample = Some((at + tick).max(Instant::now()));
+            }
+        }
+        if Instant::now() < next_snapshot {
+            continue;
+        }
+        next_snapshot = Instant::now() + interval;
 
         let now = Instant::now();
         let elapsed = now.duration_since(prev_time).as_secs_f64();
@@ -140,44 +668,126 @@ pub fn run(config: &ProbeConfig, interval_secs: u64, log_path: PathBuf) -> Resul
             .unwrap_or_default()
             .as_secs();
 
+        let check_silent = ts.saturating_sub(started_at) >= SILENT_AFTER_SECS;
+        let capture_io = cap_tx.is_some().then(capture::io_snapshot);
+        let memory = budget.as_ref().map(|b| b.snapshot());
+        let clock = shred_ingest::clock::snapshot();
+        let clock_sync = clock_sync.as_mut().and_then(|c| c.snapshot());
+        let sources: Vec<SourceSnap> = curr
+            .iter()
+            .zip(prev.iter())
+            .zip(initial.iter())
+            .map(|((c, p), i)| {
+                let silent = check_silent && is_silent(c, i);
+                make_snap(c, p, elapsed, silent, config.latency_budget_ms)
+            })
+            .collect();
+        let checks: Vec<SourceCheck> = sources
+            .iter()
+            .map(|s| SourceCheck {
+                name: s.name,
+                is_rpc: s.is_rpc,
+                active: !s.paused && !s.standby,
+                shreds_per_sec: s.shreds_per_sec,
+                coverage_pct: s.coverage_pct,
+                txs_per_sec: s.txs_per_sec,
+                degraded: s.degraded,
+            })
+            .collect();
+        let alerts = alert_rules.evaluate(&config.health, ts, &checks);
+        history.push(sources.iter().map(|s| (s.shreds_per_sec, s.lead_time_p50_us)));
         let entry = LogEntry {
             ts,
             started_at,
-            sources: curr
-                .iter()
-                .zip(prev.iter())
-                .map(|(c, p)| make_snap(c, p, elapsed))
-                .collect(),
+            latency_budget_ms: config.latency_budget_ms,
+            sources,
             shred_race: race_tracker.snapshots(),
+            slot_race: race_tracker.slot_snapshots(),
+            start_race: race_tracker.start_snapshots(),
+            handoff_race: race_tracker.handoff_snapshots(),
+            avail_race: race_tracker.avail_snapshots(),
+            shred_rank: race_tracker.rankings(),
+            confirmation_audit: audit_stats.as_ref().map(|s| s.snapshot()),
+            rtt: rtt_stats.as_ref().map(|s| s.snapshot()).unwrap_or_default(),
+            capture: capture_io.clone(),
+            memory: memory.clone(),
+            clock,
+            clock_sync: clock_sync.clone(),
+            alerts,
+            history: &history,
         };
 
-        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&log_path) {
-            if let Ok(line) = serde_json::to_string(&entry) {
-                let _ = writeln!(file, "{}", line);
+        if let Ok(line) = serde_json::to_string(&entry) {
+            log.append(&line);
+            if hub.wants_snapshots() {
+                hub.snapshot(Snapshot { ts, sources: curr.clone(), json: line.clone() });
+            }
+            if let Some(ref updater) = web_updater {
+                updater.push(line);
+            }
+        }
+        if let Some(ref mut funnel_log) = funnel_log {
+            for line in funnel_log.lines(&curr) {
+                log.append(&line);
             }
         }
 
+        let metrics_snap = MetricsSnapshot {
+            sources: curr.clone(),
+            capture: capture_io.clone(),
+            memory,
+            latency_budget_ms: config.latency_budget_ms,
+            clock,
+            clock_sync,
+        };
         if let Some(ref updater) = metrics_updater {
-            updater.update(MetricsSnapshot { sources: curr.clone() });
+            updater.update(metrics_snap.clone());
+        }
+        if let Some(ref updater) = remote_write_updater {
+            updater.update(metrics_snap);
+        }
+
+        if let Some(ref mut reporter) = epoch_reporter {
+            reporter.update(&curr, &race_tracker);
         }
 
         prev = curr;
     }
+
+    match state::save(&state_path, &all_metrics, &race_tracker) {
+        Ok(()) => eprintln!("shredtop run — state saved to {}", state_path.display()),
+        Err(e) => tracing::warn!("failed to save state: {:#}", e),
+    }
+    // Finish queued events so output files end cleanly.
+    hub.close(Duration::from_secs(5));
+    let _ = std::fs::remove_file(&ctl_socket);
+    Ok(())
 }
 
 fn make_snap<'a>(
     c: &'a SourceMetricsSnapshot,
     p: &SourceMetricsSnapshot,
     elapsed: f64,
+    silent: bool,
+    latency_budget_ms: Option<u64>,
 ) -> SourceSnap<'a> {
     let shreds_delta = c.shreds_received.saturating_sub(p.shreds_received);
     let txs_delta = c.txs_decoded.saturating_sub(p.txs_decoded);
 
-    let coverage_pct = if c.coverage_shreds_expected > 0 {
-        Some((c.coverage_shreds_seen as f64 / c.coverage_shreds_expected as f64 * 100.0).min(100.0))
-    } else {
-        None
+    let (coverage_pct, coverage_basis, shred_coverage_pct) = match c.confirmed_coverage_pct() {
+        Some(pct) => (Some(pct), Some("confirmed"), c.shred_coverage_pct()),
+        None => (c.shred_coverage_pct(), c.shred_coverage_pct().map(|_| "shreds"), None),
     };
+    let checked = c.cross_checked();
+    let sig_checked = c.shreds_sig_verified + c.shreds_sig_invalid + c.shreds_sig_unchecked > 0;
+
+    let recv_calls = c.recv_calls.saturating_sub(p.recv_calls);
+    let recv_wait_ns = c.recv_wait_ns.saturating_sub(p.recv_wait_ns);
+    let recv_busy_ns = c.recv_busy_ns.saturating_sub(p.recv_busy_ns);
+    let recv_utilization_pct = (recv_wait_ns + recv_busy_ns > 0)
+        .then(|| recv_busy_ns as f64 * 100.0 / (recv_wait_ns + recv_busy_ns) as f64);
+    let recv_batch_fill = (recv_calls > 0)
+        .then(|| c.recv_packets.saturating_sub(p.recv_packets) as f64 / recv_calls as f64);
 
     let beat_rpc_pct = if c.lead_time_count > 0 {
         Some(c.lead_wins as f64 / c.lead_time_count as f64 * 100.0)
@@ -192,20 +802,133 @@ fn make_snap<'a>(
     };
 
     SourceSnap {
-        name: c.name,
+        name: &c.name,
+        labels: &c.labels,
         is_rpc: c.is_rpc,
         shreds_per_sec: shreds_delta as f64 / elapsed,
         coverage_pct,
+        coverage_basis,
+        position_coverage: &c.position_coverage,
+        shred_coverage_pct,
+        slots_missed: checked.then_some(c.confirmed_slots_missed),
+        slots_skipped: checked.then_some(c.skipped_slots_seen),
         beat_rpc_pct,
+        in_budget_pct: latency_budget_ms
+            .filter(|_| !c.is_rpc)
+            .and_then(|ms| c.lead_time_hist.fraction_at_least(ms as i64 * 1000))
+            .map(|f| f * 100.0),
         lead_time_mean_us: lead_mean,
         lead_time_p50_us: c.lead_time_p50_us,
         lead_time_p95_us: c.lead_time_p95_us,
         lead_time_p99_us: c.lead_time_p99_us,
         lead_time_samples: c.lead_time_count,
+        slot_max_gap_p95_us: c.slot_max_gap_p95_us,
+        slot_jitter_p95_us: c.slot_jitter_p95_us,
+        lead_time_hist: &c.lead_time_hist,
         txs_per_sec: txs_delta as f64 / elapsed,
         txs_first: c.txs_first,
         txs_duplicate: c.txs_duplicate,
+        txs_slot_mismatch: (c.txs_slot_mismatch > 0).then_some(c.txs_slot_mismatch),
+        txs_dropped: (c.txs_dropped > 0).then_some(c.txs_dropped),
         secs_since_heartbeat: c.secs_since_heartbeat,
         shreds_invalid: c.shreds_invalid,
+        non_shred_packets: c.non_shred_packets,
+        shred_mix: &c.shred_mix,
+        senders: &c.senders,
+        socket_rcvbuf_bytes: socket_stat(c, c.socket_rcvbuf_bytes),
+        socket_rmem_hwm_bytes: socket_stat(c, c.socket_rmem_hwm_bytes),
+        socket_drops: socket_stat(c, c.socket_drops),
+        recv_utilization_pct,
+        recv_batch_fill,
+        paused: c.paused,
+        standby: c.standby,
+        timestamp_offset_us: (c.recv_offset_ns != 0).then(|| c.recv_offset_ns as f64 / 1000.0),
+        rpc_slot_lag: c.is_rpc.then_some(c.rpc_slot_lag),
+        recent_slots: &c.slot_log[c.slot_log.len().saturating_sub(RECENT_SLOTS)..],
+        silent,
+        repair_requests: (c.repair_requests > 0).then_some(c.repair_requests),
+        shreds_repaired: (c.repair_requests > 0).then_some(c.shreds_repaired),
+        shreds_decode_skipped: (c.shreds_decode_skipped > 0).then_some(c.shreds_decode_skipped),
+        slots_expired_shared_tip: (c.slots_expired_shared_tip > 0)
+            .then_some(c.slots_expired_shared_tip),
+        shreds_sig_verified: sig_checked.then_some(c.shreds_sig_verified),
+        shreds_sig_invalid: sig_checked.then_some(c.shreds_sig_invalid),
+        shreds_sig_unchecked: sig_checked.then_some(c.shreds_sig_unchecked),
+        errors_total: (c.errors_total > 0).then_some(c.errors_total),
+        errors_per_min: (c.errors_total > 0).then(|| {
+            c.errors_total.saturating_sub(p.errors_total) as f64 / elapsed * 60.0
+        }),
+        last_error: c.last_error.as_ref(),
+        restarts: (c.restarts > 0).then_some(c.restarts),
+        degraded: c.degraded,
+    }
+}
+
+/// No shred or transaction since `initial` was taken, and not paused.
+fn is_silent(c: &SourceMetricsSnapshot, initial: &SourceMetricsSnapshot) -> bool {
+    !c.paused
+        && c.shreds_received == initial.shreds_received
+        && c.txs_decoded == initial.txs_decoded
+}
+
+/// Socket statistics only exist once the receiver has sampled `SO_MEMINFO`.
+fn socket_stat(c: &SourceMetricsSnapshot, v: u64) -> Option<u64> {
+    (c.socket_rcvbuf_bytes > 0).then_some(v)
+}
+
+/// The two configured sources a `[[race_pairs]]` entry names, and its rule.
+fn race_pair_rule<'a>(
+    pair: &'a RacePairConfig,
+    config: &ProbeConfig,
+) -> Result<(&'a str, &'a str, PairRule)> {
+    let [a, b] = pair.sources.as_slice() else {
+        anyhow::bail!("race_pairs: expected two sources, got {:?}", pair.sources);
+    };
+    anyhow::ensure!(a != b, "race_pairs: '{}' is listed twice", a);
+    for name in [a, b] {
+        anyhow::ensure!(
+            config.sources.iter().any(|s| &s.name == name),
+            "race_pairs: no source named '{}'",
+            name
+        );
     }
+    let rule = PairRule { exclude: pair.exclude, note: pair.note.as_deref().map(Into::into) };
+    Ok((a, b, rule))
+}
+
+/// Bind the repair socket shared by every shred source.
+fn start_repair(cfg: &RepairConfig) -> Result<RepairClient> {
+    let peers = cfg
+        .peers
+        .iter()
+        .map(|p| p.parse::<RepairPeer>())
+        .collect::<Result<Vec<_>>>()?;
+    let keypair = shred_ingest::jito_udp::read_keypair(std::path::Path::new(&cfg.keypair))?;
+    let settings = RepairSettings {
+        peers,
+        stuck_ms: cfg.stuck_ms,
+        max_missing: cfg.max_missing,
+        max_attempts: cfg.max_attempts,
+    };
+    RepairClient::start(settings, keypair, cfg.port)
+}
+
+/// Append each slot forensics record to `cfg.output` from a background
+/// thread. The file is appended to across restarts and rotated with the
+/// `[log]` limits.
+fn spawn_forensics_log(cfg: &ForensicsConfig, log: &LogConfig) -> Result<ForensicsTap> {
+    let mut out = LogWriter::append_to(PathBuf::from(&cfg.output), log.clone())
+        .with_context(|| format!("failed to open forensics log {}", cfg.output))?;
+    let (tx, rx) = crossbeam_channel::bounded::<SlotForensics>(256);
+    std::thread::Builder::new()
+        .name("forensics".into())
+        .spawn(move || {
+            for record in rx {
+                match serde_json::to_string(&record) {
+                    Ok(line) => out.append(&line),
+                    Err(e) => tracing::warn!("forensics record not serialized: {}", e),
+                }
+            }
+        })?;
+    Ok(ForensicsTap { tx, min_coverage_pct: cfg.min_coverage_pct })
 }
@@ -0,0 +1,110 @@
+//! The `run_start` record at the top of the metrics log.
+//!
+//! Written once when `shredtop run` starts, and again at the top of every
+//! file after a rotation, so each log file says exactly what produced it:
+//! binary version and commit, host, kernel, the drivers of the configured
+//! interfaces, a hash of the config and every source's settings. Results
+//! from different probes or runs are only comparable once these are known.
+//!
+//! Secrets are kept out: `x_token` and `auth` are dropped from the source
+//! settings and query strings are stripped from URLs. The config hash covers
+//! the full config, so a changed token still changes the hash.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+use crate::config::ProbeConfig;
+
+/// Value of the `type` field that marks the record.
+pub const RUN_START: &str = "run_start";
+
+#[derive(Serialize)]
+pub struct RunStart {
+    /// Always [`RUN_START`]; serialized first so readers can spot the line
+    /// by its prefix.
+    #[serde(rename = "type")]
+    kind: &'static str,
+    ts: u64,
+    version: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    git_hash: Option<&'static str>,
+    host: Option<String>,
+    kernel: Option<String>,
+    /// Driver of each interface named by a source.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    nics: Vec<Nic>,
+    /// FNV-1a hash of the whole config, as 16 hex digits.
+    config_hash: String,
+    sources: Vec<Value>,
+}
+
+#[derive(Serialize)]
+struct Nic {
+    interface: String,
+    /// `None` for virtual interfaces such as tunnels.
+    driver: Option<String>,
+    driver_version: Option<String>,
+}
+
+impl RunStart {
+    pub fn new(config: &ProbeConfig, ts: u64) -> Self {
+        let interfaces: BTreeSet<&str> =
+            config.sources.iter().filter_map(|s| s.interface.as_deref()).collect();
+        Self {
+            kind: RUN_START,
+            ts,
+            version: env!("CARGO_PKG_VERSION"),
+            git_hash: option_env!("SHREDTOP_GIT_HASH"),
+            host: read_trimmed("/proc/sys/kernel/hostname"),
+            kernel: read_trimmed("/proc/sys/kernel/osrelease"),
+            nics: interfaces.into_iter().map(nic).collect(),
+            config_hash: format!("{:016x}", fnv1a(&serde_json::to_vec(config).unwrap_or_default())),
+            sources: config
+                .sources
+                .iter()
+                .filter_map(|s| serde_json::to_value(s).ok())
+                .map(redact)
+                .collect(),
+        }
+    }
+}
+
+/// Whether a log line is a `run_start` record rather than a snapshot.
+pub fn is_run_start(line: &str) -> bool {
+    line.starts_with(&format!("{{\"type\":\"{}\"", RUN_START))
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn nic(interface: &str) -> Nic {
+    let driver = std::fs::read_link(format!("/sys/class/net/{}/device/driver", interface))
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()));
+    let driver_version =
+        driver.as_ref().and_then(|d| read_trimmed(&format!("/sys/module/{}/version", d)));
+    Nic { interface: interface.to_string(), driver, driver_version }
+}
+
+/// Drop unset fields and credentials from one source's settings.
+fn redact(mut source: Value) -> Value {
+    if let Some(obj) = source.as_object_mut() {
+        obj.retain(|_, v| !v.is_null());
+        obj.remove("x_token");
+        obj.remove("auth");
+        for key in ["url", "ws_url"] {
+            if let Some(Value::String(url)) = obj.get_mut(key) {
+                if let Some(q) = url.find('?') {
+                    url.truncate(q);
+                }
+            }
+        }
+    }
+    source
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |h, &b| (h ^ b as u64).wrapping_mul(0x100000001b3))
+}
@@ -0,0 +1,127 @@
+//! High-resolution samples for short experiments (`--interval-ms`).
+//!
+//! A full snapshot takes every source's locks, sorts the lead-time reservoir
+//! and copies the slot log, which is fine every 15 s but too heavy to repeat
+//! several times a second. A [`Sampler`] reads only the lock-free
+//! [`SourceCounters`] and turns the difference since its previous call into
+//! rates, so a 30 s run at `--interval-ms 250` yields 120 points instead of
+//! two.
+//!
+//! `run` appends each sample to the metrics log as a `sample` record between
+//! the full snapshots, and `bench` returns them as `samples` in its report.
+
+use serde::Serialize;
+use shred_ingest::{SourceCounters, SourceMetrics};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Value of the `type` field that marks a sample in the metrics log.
+pub const SAMPLE: &str = "sample";
+
+/// Shortest accepted `--interval-ms`.
+pub const MIN_INTERVAL_MS: u64 = 50;
+
+pub struct Sampler {
+    metrics: Vec<Arc<SourceMetrics>>,
+    prev: Vec<SourceCounters>,
+    prev_at: Instant,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Sample {
+    /// Unix time in ms.
+    pub ts_ms: u64,
+    /// Time covered since the previous sample.
+    pub interval_ms: f64,
+    pub sources: Vec<SourceSample>,
+}
+
+/// One source over one sample interval; rates and counts cover that
+/// interval only.
+#[derive(Debug, Serialize)]
+pub struct SourceSample {
+    pub name: Arc<str>,
+    pub shreds_per_sec: f64,
+    pub txs_per_sec: f64,
+    pub txs_first: u64,
+    pub slots_complete: u64,
+    pub slots_partial: u64,
+    pub slots_dropped: u64,
+    pub fec_recovered_shreds: u64,
+    /// Lead-time mean and win rate over the samples recorded in the
+    /// interval; omitted when there were none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lead_time_mean_us: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub beat_rpc_pct: Option<f64>,
+    pub lead_time_samples: u64,
+}
+
+/// A sample as written to the metrics log.
+#[derive(Serialize)]
+struct SampleRecord<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(flatten)]
+    sample: &'a Sample,
+}
+
+impl Sampler {
+    pub fn new(metrics: &[Arc<SourceMetrics>]) -> Self {
+        Self {
+            metrics: metrics.to_vec(),
+            prev: metrics.iter().map(|m| m.counters()).collect(),
+            prev_at: Instant::now(),
+        }
+    }
+
+    /// Everything counted since the previous call (or since [`Self::new`]).
+    pub fn sample(&mut self) -> Sample {
+        let now = Instant::now();
+        let secs = now.duration_since(self.prev_at).as_secs_f64().max(1e-6);
+        self.prev_at = now;
+        let sources = self
+            .metrics
+            .iter()
+            .zip(self.prev.iter_mut())
+            .map(|(m, prev)| {
+                let curr = m.counters();
+                let d = curr.since(prev);
+                *prev = curr;
+                source_sample(m.name.clone(), &d, secs)
+            })
+            .collect();
+        Sample {
+            ts_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            interval_ms: secs * 1000.0,
+            sources,
+        }
+    }
+}
+
+impl Sample {
+    /// The sample as one metrics log line.
+    pub fn to_log_line(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&SampleRecord { kind: SAMPLE, sample: self })
+    }
+}
+
+fn source_sample(name: Arc<str>, d: &SourceCounters, secs: f64) -> SourceSample {
+    let leads = d.lead_time_count;
+    SourceSample {
+        name,
+        shreds_per_sec: d.shreds_received as f64 / secs,
+        txs_per_sec: d.txs_decoded as f64 / secs,
+        txs_first: d.txs_first,
+        slots_complete: d.slots_complete,
+        slots_partial: d.slots_partial,
+        slots_dropped: d.slots_dropped,
+        fec_recovered_shreds: d.fec_recovered_shreds,
+        lead_time_mean_us: (leads > 0).then(|| d.lead_time_sum_us as f64 / leads as f64),
+        beat_rpc_pct: (leads > 0).then(|| d.lead_wins as f64 * 100.0 / leads as f64),
+        lead_time_samples: leads,
+    }
+}
@@ -1,18 +1,32 @@
 //! `shredtop service` — systemd integration.
 //!
 //! Installs and manages a systemd unit that runs `shredtop run` in the
-//! background, logging metrics to /var/log/shredtop.jsonl.
+//! background, logging metrics to /var/log/shredtop.jsonl. With `--profile
+//! NAME` the unit is `shredtop@NAME`, running that profile with its own log
+//! (see [`crate::profile`]).
 
-use anyhow::Result;
-use std::process::Command;
+use anyhow::{Context, Result};
+use std::io::BufRead;
+use std::path::Path;
+use std::process::{Command, Stdio};
 
 use crate::color;
+use crate::config::{ProbeConfig, ServiceConfig};
+use crate::profile::{self, Paths};
 
 const UNIT_PATH: &str = "/etc/systemd/system/shredtop.service";
 
-pub fn install(config_path: &std::path::Path) -> Result<()> {
+/// Control socket of a unit that runs as a non-root user, which cannot
+/// create the default one directly under /run.
+const USER_CTL_SOCKET: &str = "/run/shredtop/ctl.sock";
+
+fn unit_path(unit: &str) -> String {
+    format!("/etc/systemd/system/{}.service", unit)
+}
+
+pub fn install(config_path: &std::path::Path, profile: Option<&str>, paths: &Paths) -> Result<()> {
     let already_active = Command::new("systemctl")
-        .args(["is-active", "--quiet", "shredtop"])
+        .args(["is-active", "--quiet", &paths.unit])
         .status()
         .map(|s| s.success())
         .unwrap_or(false);
@@ -30,58 +44,227 @@ pub fn install(config_path: &std::path::Path) -> Result<()> {
     let config_abs = config_path
         .canonicalize()
         .unwrap_or_else(|_| config_path.to_path_buf());
+    let settings = if config_path.exists() {
+        ProbeConfig::load(config_path, profile)?.service
+    } else {
+        ServiceConfig::default()
+    };
+
+    let unit = unit_file(&binary, &config_abs, &settings, profile, paths)?;
+    std::fs::write(unit_path(&paths.unit), unit)?;
 
-    let unit = format!(
+    let _ = Command::new("systemctl").arg("daemon-reload").status();
+    let _ = Command::new("systemctl").args(["enable", &paths.unit]).status();
+    let _ = Command::new("systemctl").args(["start", &paths.unit]).status();
+
+    println!("{}", color::bold_green("✓ Service installed, enabled, and started."));
+    println!();
+    println!("  shredtop monitor  — open live dashboard");
+    println!("  shredtop status   — view latest metrics");
+
+    Ok(())
+}
+
+/// The unit file running `binary -c config run`, with the `[service]`
+/// settings of probe.toml applied.
+fn unit_file(
+    binary: &Path,
+    config: &Path,
+    settings: &ServiceConfig,
+    profile: Option<&str>,
+    paths: &Paths,
+) -> Result<String> {
+    let mut exec = format!("{} -c {}", binary.display(), config.display());
+    if let Some(name) = profile {
+        exec.push_str(&format!(" --profile {}", name));
+    }
+    exec.push_str(" run");
+    let mut service = vec!["Type=simple".to_string()];
+    match settings.user.as_deref() {
+        None | Some("root") => service.push("User=root".into()),
+        Some(user) => {
+            anyhow::ensure!(
+                !user.is_empty()
+                    && user.chars().all(|c| c.is_ascii_alphanumeric() || "_-.".contains(c)),
+                "[service] user '{}' is not a valid user name",
+                user
+            );
+            service.push(format!("User={}", user));
+            // Enough to join multicast groups and tune sockets without root.
+            service.push("AmbientCapabilities=CAP_NET_RAW CAP_NET_ADMIN".into());
+            service.push("CapabilityBoundingSet=CAP_NET_RAW CAP_NET_ADMIN".into());
+            // /var/lib/shredtop and /run/shredtop are handed to the user; the
+            // metrics log is created for it by root before each start.
+            service.push("StateDirectory=shredtop".into());
+            service.push("RuntimeDirectory=shredtop".into());
+            service.push(format!(
+                "ExecStartPre=+/bin/sh -c 'touch {log} && chown {user} {log}'",
+                log = paths.log.display(),
+                user = user,
+            ));
+            let ctl_socket = profile::suffixed(USER_CTL_SOCKET, profile);
+            exec.push_str(&format!(" --ctl-socket {}", ctl_socket));
+        }
+    }
+    service.push(format!("ExecStart={}", exec));
+    service.extend(
+        ["Restart=always", "RestartSec=5", "StandardOutput=journal", "StandardError=journal"]
+            .map(String::from),
+    );
+
+    if let Some(ref cpus) = settings.cpu_affinity {
+        anyhow::ensure!(
+            cpus.chars().any(|c| c.is_ascii_digit())
+                && cpus.chars().all(|c| c.is_ascii_digit() || " ,-".contains(c)),
+            "[service] cpu_affinity '{}' is not a CPU list such as \"2-5\" or \"2 3\"",
+            cpus
+        );
+        service.push(format!("CPUAffinity={}", cpus));
+    }
+    if let Some(nice) = settings.nice {
+        anyhow::ensure!(
+            (-20..=19).contains(&nice),
+            "[service] nice must be between -20 and 19 (got {})",
+            nice
+        );
+        service.push(format!("Nice={}", nice));
+    }
+    if let Some(ref class) = settings.io_scheduling_class {
+        anyhow::ensure!(
+            matches!(class.as_str(), "realtime" | "best-effort" | "idle"),
+            "[service] io_scheduling_class '{}' must be realtime, best-effort or idle",
+            class
+        );
+        service.push(format!("IOSchedulingClass={}", class));
+    }
+    if let Some(ref limit) = settings.limit_memlock {
+        anyhow::ensure!(
+            !limit.is_empty() && limit.chars().all(|c| c.is_ascii_alphanumeric()),
+            "[service] limit_memlock '{}' is not a size such as \"512M\" or \"infinity\"",
+            limit
+        );
+        service.push(format!("LimitMEMLOCK={}", limit));
+    }
+
+    Ok(format!(
         r#"[Unit]
 Description=Shredtop — Solana shred feed latency monitor
 After=network.target
 
 [Service]
-Type=simple
-User=root
-ExecStart={binary} -c {config} run
-Restart=always
-RestartSec=5
-StandardOutput=journal
-StandardError=journal
+{}
 
 [Install]
 WantedBy=multi-user.target
 "#,
-        binary = binary.display(),
-        config = config_abs.display(),
-    );
-
-    std::fs::write(UNIT_PATH, unit)?;
+        service.join("\n"),
+    ))
+}
 
+pub fn uninstall(unit: &str) -> Result<()> {
+    let _ = Command::new("systemctl").args(["stop", unit]).status();
+    let _ = Command::new("systemctl")
+        .args(["disable", unit])
+        .status();
+    let path = unit_path(unit);
+    std::fs::remove_file(&path)?;
     let _ = Command::new("systemctl").arg("daemon-reload").status();
-    let _ = Command::new("systemctl").args(["enable", "shredtop"]).status();
-    let _ = Command::new("systemctl").args(["start", "shredtop"]).status();
+    println!("Removed {}.", path);
+    Ok(())
+}
 
-    println!("{}", color::bold_green("✓ Service installed, enabled, and started."));
-    println!();
-    println!("  shredtop monitor  — open live dashboard");
-    println!("  shredtop status   — view latest metrics");
+/// Known failure patterns and the label shown in front of matching lines.
+/// Each entry matches when the line contains any of its substrings.
+const FAILURE_PATTERNS: &[(&str, &[&str])] = &[
+    ("bind error", &["Address already in use", "Cannot assign requested address",
+                     "failed to bind"]),
+    ("interface not found", &["No such device", "getifaddrs failed"]),
+    ("RPC unreachable", &["RPC poll error", "failed to create RPC source",
+                          "Connection refused", "error sending request"]),
+];
 
-    Ok(())
+/// Label of the first failure pattern `line` matches. `interface X not found`
+/// is matched separately since the interface name sits in the middle.
+fn failure_label(line: &str) -> Option<&'static str> {
+    if line.contains("interface ") && line.contains(" not found") {
+        return Some("interface not found");
+    }
+    FAILURE_PATTERNS
+        .iter()
+        .find(|(_, needles)| needles.iter().any(|n| line.contains(n)))
+        .map(|(label, _)| *label)
 }
 
-pub fn uninstall() -> Result<()> {
-    let _ = Command::new("systemctl").args(["stop", "shredtop"]).status();
-    let _ = Command::new("systemctl")
-        .args(["disable", "shredtop"])
-        .status();
-    std::fs::remove_file(UNIT_PATH)?;
-    let _ = Command::new("systemctl").arg("daemon-reload").status();
-    println!("Removed {}.", UNIT_PATH);
+/// Units to read: the single `shredtop` unit if installed, plus every
+/// templated `shredtop@NAME` instance systemd knows about. An explicit
+/// `instance` selects just that one.
+fn log_units(instance: Option<&str>) -> Vec<String> {
+    if let Some(name) = instance {
+        return vec![format!("shredtop@{}.service", name)];
+    }
+    let mut units = Vec::new();
+    if std::path::Path::new(UNIT_PATH).exists() {
+        units.push("shredtop.service".to_string());
+    }
+    let listed = Command::new("systemctl")
+        .args(["list-units", "--all", "--plain", "--no-legend", "--type=service", "shredtop@*"])
+        .output();
+    if let Ok(out) = listed {
+        units.extend(
+            String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .filter_map(|l| l.split_whitespace().next())
+                .map(str::to_string),
+        );
+    }
+    if units.is_empty() {
+        // Nothing installed now; the journal may still hold an old unit's logs.
+        units.push("shredtop.service".to_string());
+    }
+    units
+}
+
+/// `shredtop service logs`: run journalctl for the service unit(s) and echo
+/// its output, marking lines that match a known failure pattern.
+pub fn logs(follow: bool, since: Option<&str>, instance: Option<&str>) -> Result<()> {
+    let mut cmd = Command::new("journalctl");
+    cmd.arg("--no-pager");
+    for unit in log_units(instance) {
+        cmd.args(["-u", &unit]);
+    }
+    if follow {
+        cmd.arg("-f");
+    }
+    if let Some(since) = since {
+        // journalctl wants "-1h" for relative times; accept the bare "1h" too.
+        let relative = since.starts_with(|c: char| c.is_ascii_digit())
+            && since.ends_with(|c: char| c.is_ascii_alphabetic());
+        let since = if relative { format!("-{}", since) } else { since.to_string() };
+        cmd.args(["--since", &since]);
+    }
+
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("failed to run journalctl")?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    for line in std::io::BufReader::new(stdout).lines() {
+        let line = line?;
+        match failure_label(&line) {
+            Some(label) => println!("{} {}", color::red(&format!("[{}]", label)), color::red(&line)),
+            None => println!("{}", line),
+        }
+    }
+    let status = child.wait()?;
+    anyhow::ensure!(status.success(), "journalctl exited with {}", status);
     Ok(())
 }
 
-pub fn control(action: &str) -> Result<()> {
+pub fn control(action: &str, unit: &str) -> Result<()> {
     let ok = Command::new("systemctl")
-        .args([action, "shredtop"])
+        .args([action, unit])
         .status()?
         .success();
-    anyhow::ensure!(ok, "systemctl {} shredtop failed", action);
+    anyhow::ensure!(ok, "systemctl {} {} failed", action, unit);
     Ok(())
 }
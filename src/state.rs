@@ -0,0 +1,101 @@
+//! Persisted race and metrics state for `shredtop run`.
+//!
+//! On graceful shutdown the daemon writes cumulative per-source counters and
+//! pair metrics of every race to a JSON state file; on the next start they are
+//! merged back in so a quick restart does not throw away hours of accumulated
+//! statistics. `shredtop run --fresh` skips the restore.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use shred_ingest::{
+    ShredPairState, ShredRaceTracker, ShredRankState, SourceMetrics, SourceMetricsState,
+};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_STATE: &str = "/var/lib/shredtop/state.json";
+
+#[derive(Serialize, Deserialize)]
+struct PersistedState {
+    /// Unix seconds when the state was written.
+    saved_at: u64,
+    /// Per-source counters keyed by source name.
+    sources: BTreeMap<String, SourceMetricsState>,
+    #[serde(default)]
+    shred_race: Vec<ShredPairState>,
+    #[serde(default)]
+    slot_race: Vec<ShredPairState>,
+    #[serde(default)]
+    avail_race: Vec<ShredPairState>,
+    #[serde(default)]
+    start_race: Vec<ShredPairState>,
+    #[serde(default)]
+    handoff_race: Vec<ShredPairState>,
+    #[serde(default)]
+    shred_rank: Vec<ShredRankState>,
+}
+
+/// Write the current state atomically (temp file + rename).
+pub fn save(path: &Path, metrics: &[Arc<SourceMetrics>], race: &ShredRaceTracker) -> Result<()> {
+    let state = PersistedState {
+        saved_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        sources: metrics
+            .iter()
+            .map(|m| (m.name.to_string(), m.export_state()))
+            .collect(),
+        shred_race: race.export_state(),
+        slot_race: race.export_slot_state(),
+        avail_race: race.export_avail_state(),
+        start_race: race.export_start_state(),
+        handoff_race: race.export_handoff_state(),
+        shred_rank: race.export_rank_state(),
+    };
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec(&state)?)
+        .with_context(|| format!("failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path)
+        .with_context(|| format!("failed to rename {} → {}", tmp.display(), path.display()))?;
+    Ok(())
+}
+
+/// Merge a saved state file into freshly created metrics. Sources are matched
+/// by name; entries for sources no longer configured are ignored.
+/// Returns the `saved_at` timestamp, or `None` if no state file exists.
+pub fn restore(
+    path: &Path,
+    metrics: &[Arc<SourceMetrics>],
+    race: &ShredRaceTracker,
+) -> Result<Option<u64>> {
+    let bytes = match std::fs::read(path) {
+        Ok(b) => b,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let state: PersistedState = serde_json::from_slice(&bytes)
+        .with_context(|| format!("failed to parse state file {}", path.display()))?;
+
+    for m in metrics {
+        if let Some(s) = state.sources.get(&*m.name) {
+            m.restore_state(s);
+        }
+    }
+    let names: Vec<Arc<str>> = metrics.iter().map(|m| m.name.clone()).collect();
+    race.restore_state(&state.shred_race, &names);
+    race.restore_slot_state(&state.slot_race, &names);
+    race.restore_avail_state(&state.avail_race, &names);
+    race.restore_start_state(&state.start_race, &names);
+    race.restore_handoff_state(&state.handoff_race, &names);
+    race.restore_rank_state(&state.shred_rank, &names);
+
+    Ok(Some(state.saved_at))
+}
@@ -1,34 +1,36 @@
 //! `shredtop status` — show the most recent snapshot from the metrics log.
 //!
-//! Reads the last line from /var/log/shredtop.jsonl and prints a static
-//! one-shot table. Use this to check on the running service without
-//! opening the live dashboard.
+//! Reads the last line from /var/log/shredtop.jsonl (or, right after a
+//! rotation, from the newest rotated file) and prints a static one-shot
+//! table. Use this to check on the running service without opening the live
+//! dashboard.
 
 use anyhow::Result;
 use chrono::{TimeZone, Utc};
+use serde_json::Value;
+use shred_ingest::{LeadHistogram, PositionCoverageSnapshot, POSITION_BANDS};
+
+use std::path::Path;
 
 use crate::color;
-use crate::run::DEFAULT_LOG;
-
-pub fn run() -> Result<()> {
-    let content = match std::fs::read_to_string(DEFAULT_LOG) {
-        Ok(c) => c,
-        Err(_) => {
-            eprintln!("No metrics log found at {}.", DEFAULT_LOG);
-            eprintln!("Start the service first:  shredtop service start");
-            return Ok(());
-        }
-    };
+use crate::config::HealthConfig;
+use crate::metrics_log;
+
+pub fn run(log: &Path) -> Result<()> {
+    if !metrics_log::exists(log) {
+        eprintln!("No metrics log found at {}.", log.display());
+        eprintln!("Start the service first:  shredtop service start");
+        return Ok(());
+    }
 
-    let line = match content.lines().filter(|l| !l.is_empty()).last() {
-        Some(l) => l,
+    let entry = match metrics_log::read_last_entry(log) {
+        Some(e) => e,
         None => {
             eprintln!("Metrics log is empty — service may just be starting.");
             return Ok(());
         }
     };
 
-    let entry: serde_json::Value = serde_json::from_str(line)?;
     let ts = entry["ts"].as_u64().unwrap_or(0) as i64;
     let dt = Utc.timestamp_opt(ts, 0).single();
     let time_str = dt
@@ -68,15 +70,25 @@ pub fn run() -> Result<()> {
         color::bold_cyan(&format!("{:^width$}", format!(" SHREDTOP STATUS  {} ", time_str)))
     );
     println!("{}", color::bold(&"=".repeat(width)));
+    for banner in crate::alerts::banners(&entry) {
+        println!("{}", banner);
+    }
     println!("{}", color::dim(&format!("  Started: {}   Uptime: {}", started_str, uptime_str)));
+    if let Some(start) = metrics_log::read_run_start(log) {
+        print_run_start(&start);
+    }
+    if entry["clock_sync"].is_object() {
+        print_clock_sync(&entry["clock_sync"]);
+    }
     println!();
 
+    let (beat_label, beat_key) = crate::monitor::beat_column(&entry);
     if has_rpc {
         println!(
             "{}",
             color::bold(&format!(
                 "{:<20}  {:>9}  {:>5}  {:>6}  {:>6}  {:>9}  {:>9}  {:>9}  {:>9}",
-                "SOURCE", "SHREDS/s", "COV%", "TXS/s", "BEAT%", "LEAD avg", "LEAD p50", "LEAD p95", "LEAD p99",
+                "SOURCE", "SHREDS/s", "COV%", "TXS/s", beat_label, "LEAD avg", "LEAD p50", "LEAD p95", "LEAD p99",
             ))
         );
     } else {
@@ -114,7 +126,7 @@ pub fn run() -> Result<()> {
                 let beat = if is_rpc {
                     "—".into()
                 } else {
-                    s["beat_rpc_pct"]
+                    s[beat_key]
                         .as_f64()
                         .map(|p| format!("{:.0}%", p))
                         .unwrap_or_else(|| "—".into())
@@ -149,7 +161,7 @@ pub fn run() -> Result<()> {
 
             let row = if is_rpc {
                 color::dim(&row)
-            } else if let Some(beat) = s["beat_rpc_pct"].as_f64() {
+            } else if let Some(beat) = s[beat_key].as_f64() {
                 if beat >= 60.0 { color::green(&row) }
                 else if beat >= 40.0 { color::yellow(&row) }
                 else { color::red(&row) }
@@ -161,8 +173,74 @@ pub fn run() -> Result<()> {
     }
 
     println!("{}", color::dim(&"-".repeat(width)));
+    let silent = crate::monitor::silent_section(&entry);
+    for line in silent.iter().skip(1) {
+        println!("{}", line);
+    }
+    let paused: Vec<&str> = entry["sources"]
+        .as_array()
+        .map(|a| {
+            a.iter()
+                .filter(|s| s["paused"].as_bool().unwrap_or(false))
+                .filter_map(|s| s["name"].as_str())
+                .collect()
+        })
+        .unwrap_or_default();
+    if !paused.is_empty() {
+        println!(
+            "{}",
+            color::yellow(&format!(
+                "Paused: {}  (resume with `shredtop ctl resume <source>`)",
+                paused.join(", ")
+            ))
+        );
+    }
     println!();
 
+    let history = crate::history::status_lines(&entry);
+    if !history.is_empty() {
+        for line in history {
+            println!("{}", line);
+        }
+        println!();
+    }
+
+    // Failures sources reported instead of only logging them.
+    let failing: Vec<&Value> = entry["sources"]
+        .as_array()
+        .map(|a| a.iter().filter(|s| s["errors_total"].as_u64().unwrap_or(0) > 0).collect())
+        .unwrap_or_default();
+    if !failing.is_empty() {
+        println!("{}", color::bold("ERRORS (since start):"));
+        println!(
+            "{}",
+            color::bold(&format!(
+                "  {:<20}  {:>7}  {:>7}  {:<12}  {:>6}  {}",
+                "SOURCE", "TOTAL", "PER MIN", "LAST", "AGE", "MESSAGE"
+            ))
+        );
+        for s in failing {
+            let last = &s["last_error"];
+            let age = last["at"]
+                .as_u64()
+                .map(|at| format!("{}s", (ts as u64).saturating_sub(at)))
+                .unwrap_or_else(|| "—".into());
+            println!(
+                "{}",
+                color::red(&format!(
+                    "  {:<20}  {:>7}  {:>7.1}  {:<12}  {:>6}  {}",
+                    s["name"].as_str().unwrap_or("?"),
+                    s["errors_total"].as_u64().unwrap_or(0),
+                    s["errors_per_min"].as_f64().unwrap_or(0.0),
+                    last["kind"].as_str().unwrap_or("?"),
+                    age,
+                    last["message"].as_str().unwrap_or(""),
+                ))
+            );
+        }
+        println!();
+    }
+
     // Dedup diagnostics
     println!("{}", color::bold("DEDUP (cumulative since start):"));
     println!(
@@ -182,71 +260,135 @@ pub fn run() -> Result<()> {
     }
     println!();
 
-    // Shred-level race section
-    println!("{}", color::bold(&format!(
-        "SHRED RACE  validator \u{2192} this machine  (since start):"
-    )));
-    let race_pairs = entry["shred_race"].as_array();
-    let has_race = race_pairs.map(|p| !p.is_empty()).unwrap_or(false);
-    if !has_race {
+    // Coverage by position: a tail-only feed shows an empty head band.
+    let positions: Vec<(&str, PositionCoverageSnapshot)> = entry["sources"]
+        .as_array()
+        .map(|a| {
+            a.iter()
+                .filter_map(|s| {
+                    let p: PositionCoverageSnapshot =
+                        serde_json::from_value(s["position_coverage"].clone()).ok()?;
+                    Some((s["name"].as_str().unwrap_or("?"), p))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if !positions.is_empty() {
+        println!("{}", color::bold("COVERAGE BY POSITION (thirds of each slot's data shreds):"));
         println!(
             "{}",
-            color::dim("  No races yet — waiting for same slot to appear on multiple shred feeds.")
+            color::bold(&format!(
+                "  {:<20}  {:>8}  {:>8}  {:>8}",
+                "SOURCE",
+                POSITION_BANDS[0].to_uppercase(),
+                POSITION_BANDS[1].to_uppercase(),
+                POSITION_BANDS[2].to_uppercase()
+            ))
         );
-    } else {
+        for (name, p) in &positions {
+            let [head, middle, tail] =
+                p.pct().map(|v| v.map(|v| format!("{:.1}%", v)).unwrap_or_else(|| "—".into()));
+            println!("  {:<20}  {:>8}  {:>8}  {:>8}", name, head, middle, tail);
+        }
+        println!();
+    }
+
+    // Lead-time percentiles over every sample, not just the reservoir window.
+    let hists: Vec<(&str, LeadHistogram)> = entry["sources"]
+        .as_array()
+        .map(|a| {
+            a.iter()
+                .filter_map(|s| {
+                    let h: LeadHistogram =
+                        serde_json::from_value(s["lead_time_hist"].clone()).ok()?;
+                    (!h.is_empty()).then(|| (s["name"].as_str().unwrap_or("?"), h))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if !hists.is_empty() {
+        println!("{}", color::bold("LEAD TIME (all samples, including restored state):"));
         println!(
             "{}",
             color::bold(&format!(
-                "  {:<22}  {:>7}  {:>9}  {:>10}  {:>9}  {:>9}",
-                "CONTENDER", "WIN%", "RACES", "FASTER BY", "LEAD p50", "LEAD p95",
+                "  {:<20}  {:>10}  {:>9}  {:>9}  {:>9}",
+                "SOURCE", "SAMPLES", "LEAD p50", "LEAD p95", "LEAD p99"
             ))
         );
-        let mut pairs: Vec<&serde_json::Value> = race_pairs.unwrap().iter().collect();
-        pairs.sort_by(|a, b| {
-            let ma = a["total_matched"].as_u64().unwrap_or(0);
-            let mb = b["total_matched"].as_u64().unwrap_or(0);
-            mb.cmp(&ma)
-        });
-        for (i, p) in pairs.iter().enumerate() {
-            if i > 0 {
-                println!("  \u{00b7}\u{00b7}\u{00b7}\u{00b7}\u{00b7}");
-            }
-            let sa = p["source_a"].as_str().unwrap_or("?");
-            let sb = p["source_b"].as_str().unwrap_or("?");
-            let matched = p["total_matched"].as_u64().unwrap_or(0);
-            let a_pct = p["a_win_pct"].as_f64().unwrap_or(0.0);
-            let b_pct = 100.0 - a_pct;
-            let (faster, f_pct, slower, s_pct) = if a_pct >= b_pct {
-                (sa, a_pct, sb, b_pct)
-            } else {
-                (sb, b_pct, sa, a_pct)
+        for (name, h) in &hists {
+            let pct = |p: f64| {
+                h.percentile(p)
+                    .map(|v| format!("{:+.1}ms", v as f64 / 1000.0))
+                    .unwrap_or_else(|| "—".into())
             };
-            let avg_str = p["lead_mean_us"]
-                .as_f64()
-                .map(|v| format!("+{:.2}ms", v / 1000.0))
-                .unwrap_or_else(|| "—".into());
-            let p50_str = p["lead_p50_us"]
-                .as_f64()
-                .map(|v| format!("+{:.1}ms", v / 1000.0))
-                .unwrap_or_else(|| "—".into());
-            let p95_str = p["lead_p95_us"]
-                .as_f64()
-                .map(|v| format!("+{:.1}ms", v / 1000.0))
-                .unwrap_or_else(|| "—".into());
             println!(
-                "{}",
-                color::green(&format!(
-                    "  {:<22}  {:>6.1}%  {:>9}  {:>10}  {:>9}  {:>9}",
-                    faster, f_pct, format_num(matched), avg_str, p50_str, p95_str,
-                ))
+                "  {:<20}  {:>10}  {:>9}  {:>9}  {:>9}",
+                name,
+                h.count(),
+                pct(50.0),
+                pct(95.0),
+                pct(99.0)
             );
-            println!(
-                "{}",
-                color::dim(&format!(
-                    "  {:<22}  {:>6.1}%  {:>9}  {:>10}  {:>9}  {:>9}",
-                    slower, s_pct, "—", "—", "—", "—",
-                ))
+        }
+        println!();
+    }
+
+    // Kernel receive-buffer headroom (UDP sources on Linux only)
+    let sockets: Vec<&serde_json::Value> = entry["sources"]
+        .as_array()
+        .map(|a| a.iter().filter(|s| s["socket_rcvbuf_bytes"].is_u64()).collect())
+        .unwrap_or_default();
+    if !sockets.is_empty() {
+        println!(
+            "{}",
+            color::bold("RECEIVE SOCKETS (kernel buffer since start, receiver last interval):")
+        );
+        println!(
+            "{}",
+            color::bold(&format!(
+                "  {:<20}  {:>10}  {:>10}  {:>6}  {:>12}  {:>6}  {:>6}",
+                "SOURCE", "RCVBUF", "PEAK", "PEAK%", "KERNEL DROPS", "BUSY%", "BATCH"
+            ))
+        );
+        for s in sockets {
+            let name = s["name"].as_str().unwrap_or("?");
+            let rcvbuf = s["socket_rcvbuf_bytes"].as_u64().unwrap_or(0);
+            let peak = s["socket_rmem_hwm_bytes"].as_u64().unwrap_or(0);
+            let drops = s["socket_drops"].as_u64().unwrap_or(0);
+            let pct = if rcvbuf > 0 { peak as f64 / rcvbuf as f64 * 100.0 } else { 0.0 };
+            let busy = s["recv_utilization_pct"].as_f64();
+            let fill = s["recv_batch_fill"].as_f64();
+            let row = format!(
+                "  {:<20}  {:>8.1}MB  {:>8.1}MB  {:>5.0}%  {:>12}  {:>6}  {:>6}",
+                name,
+                rcvbuf as f64 / 1_048_576.0,
+                peak as f64 / 1_048_576.0,
+                pct,
+                drops,
+                busy.map(|b| format!("{:.0}%", b)).unwrap_or_else(|| "-".into()),
+                fill.map(|f| format!("{:.1}", f)).unwrap_or_else(|| "-".into())
             );
+            let hot = pct >= 80.0 || busy.is_some_and(|b| b >= 80.0);
+            let row = if drops > 0 { color::red(&row) } else if hot { color::yellow(&row) } else { row };
+            println!("{}", row);
+        }
+        println!();
+    }
+
+    // Shred-level race section
+    println!("{}", color::bold(&format!(
+        "SHRED RACE  validator \u{2192} this machine  (since start):"
+    )));
+    let race_pairs = entry["shred_race"].as_array();
+    let has_race = race_pairs.map(|p| !p.is_empty()).unwrap_or(false);
+    if !has_race {
+        println!(
+            "{}",
+            color::dim("  No races yet — waiting for same slot to appear on multiple shred feeds.")
+        );
+    } else {
+        for line in crate::monitor::race_table(race_pairs.unwrap()) {
+            println!("{}", line);
         }
     }
     println!();
@@ -260,6 +402,42 @@ pub fn run() -> Result<()> {
         "  timestamp (SO_TIMESTAMPNS), before any userspace processing."
     ));
     println!();
+    if let Some(slot_pairs) = entry["slot_race"].as_array().filter(|p| !p.is_empty()) {
+        println!("{}", color::bold("SLOT RACE  first feed to make each slot fully decodable:"));
+        for line in crate::monitor::race_table(slot_pairs) {
+            println!("{}", line);
+        }
+        println!();
+    }
+    let start = crate::monitor::start_race_section(&entry);
+    if !start.is_empty() {
+        for line in &start[1..] {
+            println!("{}", line);
+        }
+        println!();
+    }
+    let avail = crate::monitor::avail_race_section(&entry);
+    if !avail.is_empty() {
+        for line in &avail[1..] {
+            println!("{}", line);
+        }
+        println!();
+    }
+    let ranking = crate::monitor::first_overall_section(&entry);
+    if !ranking.is_empty() {
+        for line in &ranking[1..] {
+            println!("{}", line);
+        }
+        println!();
+    }
+    if entry["confirmation_audit"].is_object() {
+        print_confirmation_audit(&entry["confirmation_audit"]);
+        println!();
+    }
+    if let Some(rtt) = entry["rtt"].as_array() {
+        print_rtt(rtt);
+        println!();
+    }
     if !has_rpc {
         println!(
             "{}",
@@ -271,20 +449,261 @@ pub fn run() -> Result<()> {
     }
     println!(
         "{}",
-        color::dim(&format!("Log: {}  (shredtop service status for service health)", DEFAULT_LOG))
+        color::dim(&format!(
+            "Log: {}  (shredtop service status for service health)",
+            log.display()
+        ))
     );
 
     Ok(())
 }
 
-fn format_num(n: u64) -> String {
-    let s = n.to_string();
-    let mut out = String::new();
-    for (i, c) in s.chars().rev().enumerate() {
-        if i > 0 && i % 3 == 0 {
-            out.push(',');
+/// Block time minus shred arrival for the sampled winners, overall and per
+/// winning feed.
+fn print_confirmation_audit(audit: &Value) {
+    let n = |k: &str| audit[k].as_u64().unwrap_or(0);
+    println!(
+        "{}",
+        color::bold(&format!(
+            "CONFIRMATION AUDIT  shred arrival \u{2192} block time  \
+             ({} sampled, {} confirmed, {} not found, {} rpc errors):",
+            n("sampled"), n("confirmed"), n("not_found"), n("rpc_errors"),
+        ))
+    );
+    let d = &audit["confirm_after_arrival_ms"];
+    if !d.is_object() {
+        println!("{}", color::dim("  No confirmed samples yet."));
+        return;
+    }
+    let ms = |k: &str| d[k].as_i64().unwrap_or(0);
+    println!(
+        "  {:<20}  mean {:>+7.0}ms  p50 {:>+6}ms  p95 {:>+6}ms  p99 {:>+6}ms  range {:+}..{:+}ms",
+        "all",
+        d["mean"].as_f64().unwrap_or(0.0),
+        ms("p50"), ms("p95"), ms("p99"), ms("min"), ms("max"),
+    );
+    for (name, s) in audit["by_source"].as_object().into_iter().flatten() {
+        println!(
+            "  {:<20}  mean {:>+7.0}ms  ({} confirmed)",
+            name,
+            s["mean_ms"].as_f64().unwrap_or(0.0),
+            s["confirmed"].as_u64().unwrap_or(0),
+        );
+    }
+    println!("{}", color::dim(
+        "  blockTime has one-second resolution; read the distribution, not single samples."
+    ));
+}
+
+/// Latest round trip to each probed endpoint, against its recent median.
+fn print_rtt(targets: &[Value]) {
+    println!("{}", color::bold("RTT:"));
+    println!(
+        "{}",
+        color::bold(&format!(
+            "  {:<20}  {:<10}  {:<24}  {:>9}  {:>9}  {:>6}",
+            "TARGET", "KIND", "HOST", "RTT", "p50", "VIA"
+        ))
+    );
+    for t in targets {
+        let ms = |k: &str| match t[k].as_f64() {
+            Some(ms) => format!("{:.2}ms", ms),
+            None => "—".to_string(),
+        };
+        println!(
+            "  {:<20}  {:<10}  {:<24}  {:>9}  {:>9}  {:>6}",
+            t["name"].as_str().unwrap_or("?"),
+            t["kind"].as_str().unwrap_or("?"),
+            t["host"].as_str().unwrap_or("?"),
+            ms("rtt_ms"),
+            ms("p50_ms"),
+            t["method"].as_str().unwrap_or("—"),
+        );
+    }
+}
+
+/// The clock discipline status of the latest snapshot; highlighted when its
+/// races are not to be trusted.
+fn print_clock_sync(clock: &Value) {
+    let mut parts = vec![clock["source"].as_str().unwrap_or("?").to_string()];
+    if let Some(stratum) = clock["stratum"].as_u64() {
+        parts.push(format!("stratum {}", stratum));
+    }
+    if let Some(offset) = clock["offset_us"].as_f64() {
+        parts.push(format!("offset {:+.1}µs", offset));
+    }
+    if let Some(jitter) = clock["jitter_us"].as_f64() {
+        parts.push(format!("jitter {:.1}µs", jitter));
+    }
+    if let Some(err) = clock["est_error_us"].as_f64() {
+        parts.push(format!("est. error {:.0}µs", err));
+    }
+    let step = clock["step_us"].as_i64().unwrap_or(0);
+    if step != 0 {
+        parts.push(format!("step {:+}µs", step));
+    }
+    let line = format!("  Clock:   {}", parts.join("   "));
+    if clock["trusted"].as_bool().unwrap_or(false) {
+        println!("{}", color::dim(&line));
+    } else {
+        let why = if clock["synced"].as_bool().unwrap_or(false) {
+            "offset or step over the limit"
+        } else {
+            "UNSYNCHRONIZED"
+        };
+        println!(
+            "{}",
+            color::yellow(&format!("{}   — {}; races this interval are unreliable", line, why))
+        );
+    }
+}
+
+/// What the running service is, from the log's `run_start` record.
+fn print_run_start(start: &Value) {
+    let mut build = format!("shredtop {}", start["version"].as_str().unwrap_or("?"));
+    if let Some(hash) = start["git_hash"].as_str() {
+        build.push_str(&format!(" ({})", hash));
+    }
+    let mut parts = vec![build];
+    if let Some(host) = start["host"].as_str() {
+        parts.push(format!("host {}", host));
+    }
+    if let Some(kernel) = start["kernel"].as_str() {
+        parts.push(format!("kernel {}", kernel));
+    }
+    if let Some(hash) = start["config_hash"].as_str() {
+        parts.push(format!("config {}", &hash[..hash.len().min(8)]));
+    }
+    println!("{}", color::dim(&format!("  Running: {}", parts.join("   "))));
+
+    let nics: Vec<String> = start["nics"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|n| {
+            let iface = n["interface"].as_str().unwrap_or("?");
+            match (n["driver"].as_str(), n["driver_version"].as_str()) {
+                (Some(d), Some(v)) => format!("{} {} {}", iface, d, v),
+                (Some(d), None) => format!("{} {}", iface, d),
+                _ => format!("{} (virtual)", iface),
+            }
+        })
+        .collect();
+    if !nics.is_empty() {
+        println!("{}", color::dim(&format!("  NICs:    {}", nics.join("   "))));
+    }
+}
+
+// ─── Health check ─────────────────────────────────────────────────────────────
+
+/// Exit codes of `shredtop status --check`, in the order the checks run.
+/// 1 is left to errors, which `main` exits with, so a broken config or
+/// unreadable profile never passes for a failed check.
+const CHECK_OK: i32 = 0;
+const CHECK_NO_LOG: i32 = 2;
+const CHECK_STALE_LOG: i32 = 3;
+const CHECK_NO_SHREDS: i32 = 4;
+const CHECK_LOW_COVERAGE: i32 = 5;
+const CHECK_BASELINE_STALE: i32 = 6;
+
+/// `shredtop status --check`: classify the latest snapshot against `health`,
+/// print a one-line verdict, and return the exit code for it.
+pub fn check(health: &HealthConfig, log: &Path) -> i32 {
+    let entry = metrics_log::read_last_entry(log);
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (code, msg) = classify(entry.as_ref(), now, health, log);
+    let label = if code == CHECK_OK { "OK" } else { "CRITICAL" };
+    println!("SHREDTOP {} - {}", label, msg);
+    code
+}
+
+fn classify(
+    entry: Option<&Value>,
+    now: u64,
+    health: &HealthConfig,
+    log: &Path,
+) -> (i32, String) {
+    let Some(entry) = entry else {
+        return (CHECK_NO_LOG, format!("no metrics log entries at {}", log.display()));
+    };
+    let age = now.saturating_sub(entry["ts"].as_u64().unwrap_or(0));
+    if health.max_log_age_secs > 0 && age > health.max_log_age_secs {
+        return (
+            CHECK_STALE_LOG,
+            format!("last log entry {}s old (limit {}s)", age, health.max_log_age_secs),
+        );
+    }
+
+    // Paused and standby sources are idle on purpose.
+    let active: Vec<&Value> = entry["sources"]
+        .as_array()
+        .map(|s| {
+            s.iter()
+                .filter(|s| !s["paused"].as_bool().unwrap_or(false))
+                .filter(|s| !s["standby"].as_bool().unwrap_or(false))
+                .collect()
+        })
+        .unwrap_or_default();
+    let name = |s: &Value| s["name"].as_str().unwrap_or("?").to_string();
+    let (baseline, shred): (Vec<&Value>, Vec<&Value>) =
+        active.into_iter().partition(|s| s["is_rpc"].as_bool().unwrap_or(false));
+
+    let silent: Vec<String> = shred
+        .iter()
+        .filter(|s| s["shreds_per_sec"].as_f64().unwrap_or(0.0) <= health.min_shreds_per_sec)
+        .map(|s| name(s))
+        .collect();
+    if !silent.is_empty() {
+        return (
+            CHECK_NO_SHREDS,
+            format!(
+                "shred rate at or below {}/s: {}",
+                health.min_shreds_per_sec,
+                silent.join(", ")
+            ),
+        );
+    }
+
+    if health.min_coverage_pct > 0.0 {
+        let low: Vec<String> = shred
+            .iter()
+            .filter_map(|s| {
+                let pct = s["coverage_pct"].as_f64()?;
+                (pct < health.min_coverage_pct).then(|| format!("{} {:.1}%", name(s), pct))
+            })
+            .collect();
+        if !low.is_empty() {
+            return (
+                CHECK_LOW_COVERAGE,
+                format!("coverage below {}%: {}", health.min_coverage_pct, low.join(", ")),
+            );
         }
-        out.push(c);
     }
-    out.chars().rev().collect()
+
+    if health.check_baseline {
+        let stale: Vec<String> = baseline
+            .iter()
+            .filter(|s| s["txs_per_sec"].as_f64().unwrap_or(0.0) <= 0.0)
+            .map(|s| name(s))
+            .collect();
+        if !stale.is_empty() {
+            return (
+                CHECK_BASELINE_STALE,
+                format!("baseline decoded no transactions: {}", stale.join(", ")),
+            );
+        }
+    }
+
+    (
+        CHECK_OK,
+        format!(
+            "{} shred source(s), {} baseline(s), log {}s old",
+            shred.len(),
+            baseline.len(),
+            age
+        ),
+    )
 }
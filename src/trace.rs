@@ -0,0 +1,132 @@
+//! Transaction traces (`[trace]`) and `shredtop trace show`.
+//!
+//! `shredtop run` appends every [`TxTrace`] the fan-in and the transaction
+//! consumer produce to the trace log, one JSON line per copy. `trace show`
+//! collects the lines of one signature and prints them as a single timeline,
+//! so the time between any two hops of any copy can be read off directly.
+//!
+//! The log is kept across restarts and rotated with the `[log]` limits, like
+//! the forensics log; `trace show` reads the rotated files too.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use shred_ingest::{TraceTap, TxTrace};
+use std::path::{Path, PathBuf};
+
+use crate::color;
+use crate::config::{LogConfig, ProbeConfig, TraceConfig};
+use crate::metrics_log::{self, LogWriter};
+
+/// Open the trace log and start the thread that writes to it.
+pub fn spawn(cfg: &TraceConfig, log: &LogConfig) -> Result<TraceTap> {
+    anyhow::ensure!(cfg.sample_every > 0, "trace.sample_every must be > 0");
+    let mut out = LogWriter::append_to(PathBuf::from(&cfg.output), log.clone())
+        .with_context(|| format!("failed to open trace log {}", cfg.output))?;
+    let (tx, rx) = crossbeam_channel::bounded::<TxTrace>(1024);
+    std::thread::Builder::new()
+        .name("trace".into())
+        .spawn(move || {
+            for record in rx {
+                match serde_json::to_string(&record) {
+                    Ok(line) => out.append(&line),
+                    Err(e) => tracing::warn!("trace record not serialized: {}", e),
+                }
+            }
+        })?;
+    Ok(TraceTap { tx, every: cfg.sample_every })
+}
+
+/// One hop of one copy, as read back from the log.
+struct HopLine {
+    at_ns: u64,
+    source: String,
+    hop: String,
+    detail: String,
+}
+
+/// Print the timeline of `signature`. The log is `file`, or the `[trace]`
+/// output of the config, or the default.
+pub fn show(
+    config_path: &Path,
+    profile: Option<&str>,
+    file: Option<PathBuf>,
+    signature: &str,
+) -> Result<()> {
+    let default = || crate::profile::suffixed(&TraceConfig::default_output(), profile);
+    let path = match file {
+        Some(path) => path,
+        None if config_path.exists() => {
+            let output = ProbeConfig::load(config_path, profile)?.trace.map(|t| t.output);
+            PathBuf::from(output.unwrap_or_else(default))
+        }
+        None => PathBuf::from(default()),
+    };
+    let content = metrics_log::read_all(&path)
+        .with_context(|| format!("failed to read trace log {}", path.display()))?;
+
+    let mut slot = None;
+    let mut hops: Vec<HopLine> = Vec::new();
+    for line in content.lines() {
+        if !line.contains(signature) {
+            continue;
+        }
+        let Ok(record) = serde_json::from_str::<Value>(line) else { continue };
+        if record["signature"].as_str() != Some(signature) {
+            continue;
+        }
+        slot = slot.or(record["slot"].as_u64());
+        let source = record["source"].as_str().unwrap_or("?").to_string();
+        for hop in record["hops"].as_array().into_iter().flatten() {
+            let (Some(name), Some(at_ns)) = (hop[0].as_str(), hop[1].as_u64()) else { continue };
+            let detail = match (name, record["duplicate_of"].as_array()) {
+                ("dedup", None) => "first copy".to_string(),
+                ("dedup", Some(d)) => format!(
+                    "duplicate, {:+.3}ms behind {}",
+                    d[1].as_i64().unwrap_or(0) as f64 / 1000.0,
+                    d[0].as_str().unwrap_or("?")
+                ),
+                _ => String::new(),
+            };
+            hops.push(HopLine { at_ns, source: source.clone(), hop: name.into(), detail });
+        }
+    }
+    anyhow::ensure!(!hops.is_empty(), "{} not found in {}", signature, path.display());
+    hops.sort_by_key(|h| h.at_ns);
+
+    println!("{}", color::bold(&format!("TRACE {}", signature)));
+    if let Some(slot) = slot {
+        println!("  slot {}", slot);
+    }
+    println!();
+    println!(
+        "{}",
+        color::bold(&format!(
+            "  {:>10}  {:<20}  {:<12}  {:>9}",
+            "AT (ms)", "SOURCE", "HOP", "STEP (ms)"
+        ))
+    );
+    let start = hops[0].at_ns;
+    let mut last_by_source: Vec<(&str, u64)> = Vec::new();
+    for h in &hops {
+        let step = match last_by_source.iter_mut().find(|(s, _)| *s == h.source) {
+            Some((_, last)) => {
+                let step = h.at_ns.saturating_sub(*last);
+                *last = h.at_ns;
+                format!("{:+.3}", step as f64 / 1e6)
+            }
+            None => {
+                last_by_source.push((&h.source, h.at_ns));
+                String::new()
+            }
+        };
+        println!(
+            "  {:>10.3}  {:<20}  {:<12}  {:>9}  {}",
+            (h.at_ns - start) as f64 / 1e6,
+            h.source,
+            h.hop,
+            step,
+            color::dim(&h.detail)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+//! Unix-socket transaction sink for `shredtop run`.
+//!
+//! Every transaction the fan-in forwards (the first copy of each signature,
+//! from whichever feed delivered it first) is written to every connected
+//! client as a Solana wire-format frame:
+//!
+//! ```text
+//! u32 little-endian length | bincode-serialized VersionedTransaction
+//! ```
+//!
+//! The payload is exactly what `sendTransaction` carries, so a consumer can
+//! deserialize it with the Solana SDK directly.
+//!
+//! With `duplicates = true` the fan-in forwards later copies too, and every
+//! frame body becomes a bincode [`MarkedFrame`] instead: the delivering source,
+//! the winning source and lag for a duplicate, then the transaction.
+//!
+//! Clients are served by [`UnixBroadcast`]: a client that cannot keep up
+//! loses whole frames and never slows the pipeline or the other clients.
+
+use anyhow::Result;
+use serde::Serialize;
+use shred_ingest::output::{OutputSink, UnixBroadcast};
+use shred_ingest::ForwardedTx;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::config::TxSinkConfig;
+
+pub struct TxSink {
+    socket: UnixBroadcast,
+    /// Write [`MarkedFrame`]s rather than bare transactions.
+    marked: bool,
+}
+
+/// Frame body when duplicates are streamed. `T` is the `VersionedTransaction`.
+#[derive(Serialize)]
+struct MarkedFrame<'a, T> {
+    source: &'a str,
+    /// Winning source and this copy's lag behind it in µs; `None` for the
+    /// first copy.
+    duplicate_of: Option<(&'a str, i64)>,
+    transaction: &'a T,
+}
+
+/// Bind the sink socket and accept clients on a background thread.
+pub fn spawn(cfg: &TxSinkConfig) -> Result<TxSink> {
+    let socket = UnixBroadcast::bind(Path::new(&cfg.socket), "tx-sink")?;
+    Ok(TxSink { socket, marked: cfg.duplicates })
+}
+
+impl OutputSink for TxSink {
+    /// Queue one transaction for every connected client.
+    fn on_transaction(&mut self, tx: &ForwardedTx) {
+        if !self.socket.has_clients() {
+            return;
+        }
+        if let Some(frame) = frame(tx, self.marked) {
+            self.socket.send(frame);
+        }
+    }
+}
+
+/// Length prefix plus the bincode wire encoding of the transaction, or of the
+/// marked frame around it.
+fn frame(tx: &ForwardedTx, marked: bool) -> Option<Arc<[u8]>> {
+    let body = if marked {
+        bincode::serialize(&MarkedFrame {
+            source: &tx.source,
+            duplicate_of: tx.duplicate_of.as_ref().map(|d| (&*d.winner, d.lag_us)),
+            transaction: &tx.decoded.transaction,
+        })
+    } else {
+        bincode::serialize(&tx.decoded.transaction)
+    }
+    .ok()?;
+    let mut buf = Vec::with_capacity(4 + body.len());
+    buf.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&body);
+    Some(buf.into())
+}
@@ -8,10 +8,11 @@ use std::process::Command;
 use crate::color;
 use crate::config::ProbeConfig;
 use crate::run::DEFAULT_LOG;
+use crate::state::DEFAULT_STATE;
 
 pub fn run(config_path: &Path) -> Result<()> {
     // Collect the capture dir before we potentially remove probe.toml
-    let capture_dir = ProbeConfig::load(config_path)
+    let capture_dir = ProbeConfig::load(config_path, None)
         .ok()
         .and_then(|c| c.capture)
         .map(|cap| cap.output_dir);
@@ -26,6 +27,7 @@ pub fn run(config_path: &Path) -> Result<()> {
         println!("  binary                  {}", bin);
     }
     println!("  metrics log             {}", DEFAULT_LOG);
+    println!("  saved state             {}", DEFAULT_STATE);
     if let Some(ref cap) = capture_dir {
         println!("  capture files           {}", cap);
     }
@@ -75,6 +77,9 @@ pub fn run(config_path: &Path) -> Result<()> {
 
     // 3. Remove metrics log
     step(&format!("Removing metrics log ({})", DEFAULT_LOG), || {
+        for rotated in crate::metrics_log::rotated(Path::new(DEFAULT_LOG)) {
+            std::fs::remove_file(rotated)?;
+        }
         if Path::new(DEFAULT_LOG).exists() {
             std::fs::remove_file(DEFAULT_LOG).map_err(anyhow::Error::from)
         } else {
@@ -82,6 +87,15 @@ pub fn run(config_path: &Path) -> Result<()> {
         }
     });
 
+    // 3b. Remove saved race/metrics state
+    step(&format!("Removing saved state ({})", DEFAULT_STATE), || {
+        if Path::new(DEFAULT_STATE).exists() {
+            std::fs::remove_file(DEFAULT_STATE).map_err(anyhow::Error::from)
+        } else {
+            Ok(())
+        }
+    });
+
     // 4. Remove capture files
     if let Some(ref cap) = capture_dir {
         step(&format!("Removing capture files ({})", cap), || {
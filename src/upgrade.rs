@@ -1,23 +1,56 @@
 //! `shredtop upgrade` — download the latest release binary from GitHub.
+//!
+//! Every release carries a `SHA256SUMS` file and a signature of it,
+//! `SHA256SUMS.minisig` (minisign) and optionally `SHA256SUMS.asc` (GPG). The
+//! signature is checked against a pinned key only: the minisign public key,
+//! or with `--gpg-keyring` that keyring through `gpgv`, never root's own GPG
+//! keyring. The binary is only installed once the signature verifies and the
+//! download matches its entry in `SHA256SUMS`. Downloads go to a fresh
+//! directory only its owner can enter. The replaced binary is kept beside the
+//! new one as `shredtop.prev`; if the new binary fails to run `--version` it
+//! is put back straight away, and `--rollback` puts it back later.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::color;
 
 const RELEASES_API: &str =
     "https://api.github.com/repos/Haruko-Haruhara-GSPB/shred-probe/releases/latest";
 const DOWNLOAD_URL: &str =
-    "https://github.com/Haruko-Haruhara-GSPB/shred-probe/releases/download/{tag}/shredtop";
+    "https://github.com/Haruko-Haruhara-GSPB/shred-probe/releases/download/{tag}/{file}";
 
-pub fn run() -> Result<()> {
+/// Release tag of the `nightly` channel, rebuilt from every push to main.
+const NIGHTLY_TAG: &str = "nightly";
+
+/// minisign public key used when `--pubkey` is not given.
+pub const DEFAULT_PUBKEY: &str = "/etc/shredtop/minisign.pub";
+
+pub struct UpgradeOptions<'a> {
+    /// `stable` or `nightly`.
+    pub channel: &'a str,
+    pub pubkey: &'a Path,
+    /// Check `SHA256SUMS.asc` with `gpgv` against this keyring instead of the
+    /// minisign signature.
+    pub gpg_keyring: Option<&'a Path>,
+    /// Install without a verified signature; the checksum is still checked.
+    pub skip_signature: bool,
+}
+
+pub fn run(opts: &UpgradeOptions<'_>) -> Result<()> {
     let current = env!("CARGO_PKG_VERSION");
     println!("Current:  v{}", current);
     print!("Latest:   ");
     io::stdout().flush()?;
 
-    let latest = fetch_latest_release();
+    let latest = if opts.channel == "nightly" {
+        Ok(NIGHTLY_TAG.to_string())
+    } else {
+        fetch_latest_release()
+    };
     match &latest {
         Ok(tag) => println!("{}", tag),
         Err(e) => {
@@ -32,19 +65,167 @@ pub fn run() -> Result<()> {
         return Ok(());
     }
 
-    println!("{}", color::cyan(&format!("Upgrading to {}...", tag)));
+    let dest = which_shredtop()?;
+    let dir = private_temp_dir("shredtop-upgrade")?;
+    let result = fetch_verified(&tag, &dir, opts).and_then(|binary| {
+        // A nightly keeps its tag, so compare the builds themselves.
+        if sha256(&dest).ok() == Some(binary.sha256.clone()) {
+            println!("{}", color::green("Already up to date."));
+            return Ok(());
+        }
+        println!("{}", color::cyan(&format!("Upgrading to {}...", tag)));
+        install(&binary.path, &dest)
+    });
+    let _ = std::fs::remove_dir_all(&dir);
+    result?;
+
+    println!("{}", color::bold_green(&format!("✓ Done. {} installed to {}.", tag, dest.display())));
+    println!("  The previous binary is kept as {}.", prev_path(&dest).display());
+    Ok(())
+}
 
-    let url = DOWNLOAD_URL.replace("{tag}", &tag);
+/// Put back the binary the last upgrade replaced.
+pub fn rollback() -> Result<()> {
     let dest = which_shredtop()?;
+    let prev = prev_path(&dest);
+    anyhow::ensure!(prev.exists(), "no previous binary at {}", prev.display());
     let tmp = dest.with_extension("tmp");
+    std::fs::copy(&prev, &tmp)?;
+    std::fs::rename(&tmp, &dest)?;
+    std::fs::remove_file(&prev)?;
+    let version = version_of(&dest).unwrap_or_else(|e| format!("({:#})", e));
+    println!("{}", color::bold_green(&format!("✓ Rolled back to {}.", version)));
+    Ok(())
+}
 
-    let ok = Command::new("curl")
-        .args(["-fsSL", "--max-time", "120", "-o"])
-        .arg(&tmp)
-        .arg(&url)
-        .status()?
-        .success();
-    anyhow::ensure!(ok, "download failed — check your internet connection");
+/// A new directory under the temp dir that only its owner can enter, named
+/// `<prefix>-<pid>-<nanos>`. It is created with `create`, not
+/// `create_dir_all`, so a directory someone else made in advance is refused
+/// rather than reused.
+pub(crate) fn private_temp_dir(prefix: &str) -> Result<PathBuf> {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+    let name = format!("{}-{}-{}", prefix, std::process::id(), nanos);
+    let dir = std::env::temp_dir().join(name);
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(&dir).with_context(|| format!("could not create {}", dir.display()))?;
+    Ok(dir)
+}
+
+struct Download {
+    path: PathBuf,
+    sha256: String,
+}
+
+/// Download the release binary of `tag` into `dir` and check it against the
+/// release's signed `SHA256SUMS`.
+fn fetch_verified(tag: &str, dir: &Path, opts: &UpgradeOptions<'_>) -> Result<Download> {
+    let sums = dir.join("SHA256SUMS");
+    anyhow::ensure!(
+        download(tag, "SHA256SUMS", &sums)?,
+        "release {} has no SHA256SUMS; refusing to install an unverified binary",
+        tag
+    );
+    verify_signature(tag, dir, &sums, opts)?;
+
+    let path = dir.join("shredtop");
+    anyhow::ensure!(
+        download(tag, "shredtop", &path)?,
+        "download failed — check your internet connection"
+    );
+    let text = std::fs::read_to_string(&sums)?;
+    let expected = expected_sha256(&text, "shredtop")
+        .with_context(|| format!("SHA256SUMS of {} has no entry for shredtop", tag))?;
+    let actual = sha256(&path)?;
+    anyhow::ensure!(
+        actual.eq_ignore_ascii_case(expected),
+        "checksum mismatch for shredtop: expected {}, got {}",
+        expected,
+        actual
+    );
+    println!("  checksum  {}", color::green("ok"));
+    Ok(Download { path, sha256: actual })
+}
+
+/// Verify `SHA256SUMS` with the GPG signature if `--gpg-keyring` was given,
+/// otherwise with the minisign signature.
+fn verify_signature(tag: &str, dir: &Path, sums: &Path, opts: &UpgradeOptions<'_>) -> Result<()> {
+    let verified = if let Some(keyring) = opts.gpg_keyring {
+        let sig = dir.join("SHA256SUMS.asc");
+        anyhow::ensure!(
+            download(tag, "SHA256SUMS.asc", &sig)?,
+            "release {} has no SHA256SUMS.asc to check with --gpg-keyring",
+            tag
+        );
+        // gpgv trusts every key in the keyrings it is given and no others. A
+        // keyring name without a slash would be looked up in ~/.gnupg.
+        let keyring = std::path::absolute(keyring)?;
+        let out = Command::new("gpgv")
+            .arg("--keyring")
+            .arg(&keyring)
+            .arg(&sig)
+            .arg(sums)
+            .output()
+            .context("gpgv not found — install gnupg, or drop --gpg-keyring to use minisign")?;
+        anyhow::ensure!(
+            out.status.success(),
+            "SHA256SUMS signature does not verify with {}: {}",
+            keyring.display(),
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+        Some("gpg")
+    } else if download(tag, "SHA256SUMS.minisig", &dir.join("SHA256SUMS.minisig"))? {
+        if opts.pubkey.exists() {
+            // minisign reads the signature from `<file>.minisig`.
+            let out = Command::new("minisign")
+                .arg("-Vq")
+                .arg("-p")
+                .arg(opts.pubkey)
+                .arg("-m")
+                .arg(sums)
+                .output()
+                .context("minisign not found — install it or pass --skip-signature")?;
+            anyhow::ensure!(
+                out.status.success(),
+                "SHA256SUMS signature does not verify with {}: {}",
+                opts.pubkey.display(),
+                String::from_utf8_lossy(&out.stderr).trim()
+            );
+            Some("minisign")
+        } else if opts.skip_signature {
+            None
+        } else {
+            anyhow::bail!(
+                "no minisign public key at {}; pass --pubkey FILE or --skip-signature",
+                opts.pubkey.display()
+            )
+        }
+    } else {
+        anyhow::ensure!(
+            opts.skip_signature,
+            "release {} has no SHA256SUMS.minisig; pass --gpg-keyring FILE to check its GPG \
+             signature, or --skip-signature to install anyway",
+            tag
+        );
+        None
+    };
+    match verified {
+        Some(tool) => println!("  signature {} ({})", color::green("ok"), tool),
+        None => println!("  signature {}", color::yellow("not verified (--skip-signature)")),
+    }
+    Ok(())
+}
+
+/// Replace `dest` with `new`, keeping the old binary as `<dest>.prev`, and
+/// put it back if the new one does not run.
+fn install(new: &Path, dest: &Path) -> Result<()> {
+    let tmp = dest.with_extension("tmp");
+    let prev = prev_path(dest);
+    std::fs::copy(new, &tmp)?;
 
     // chmod before replacing so there's no window where the binary is non-executable
     #[cfg(unix)]
@@ -55,13 +236,64 @@ pub fn run() -> Result<()> {
         std::fs::set_permissions(&tmp, perms)?;
     }
 
+    std::fs::copy(dest, &prev).context("could not keep the previous binary")?;
     // Atomic rename — works even while the old binary is running
-    std::fs::rename(&tmp, &dest)?;
+    std::fs::rename(&tmp, dest)?;
 
-    println!("{}", color::bold_green(&format!("✓ Done. {} installed to {}.", tag, dest.display())));
+    if let Err(e) = version_of(dest) {
+        std::fs::copy(&prev, &tmp)?;
+        std::fs::rename(&tmp, dest)?;
+        anyhow::bail!("the new binary does not run ({:#}); the previous one was restored", e);
+    }
     Ok(())
 }
 
+fn prev_path(dest: &Path) -> PathBuf {
+    dest.with_extension("prev")
+}
+
+/// `shredtop --version` of the binary at `path`.
+fn version_of(path: &Path) -> Result<String> {
+    let out = Command::new(path).arg("--version").output()?;
+    anyhow::ensure!(out.status.success(), "--version exited with {}", out.status);
+    Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+/// Download release asset `file` of `tag` to `to`. `Ok(false)` when the
+/// release has no such asset.
+fn download(tag: &str, file: &str, to: &Path) -> Result<bool> {
+    let url = DOWNLOAD_URL.replace("{tag}", tag).replace("{file}", file);
+    let status = Command::new("curl")
+        .args(["-fsSL", "--max-time", "120", "-o"])
+        .arg(to)
+        .arg(&url)
+        .status()?;
+    // curl exit 22 = HTTP 4xx/5xx (with -f flag)
+    match status.code() {
+        Some(0) => Ok(true),
+        Some(22) => Ok(false),
+        _ => anyhow::bail!("download of {} failed — check your internet connection", file),
+    }
+}
+
+fn sha256(path: &Path) -> Result<String> {
+    let out = Command::new("sha256sum").arg(path).output().context("sha256sum not found")?;
+    anyhow::ensure!(out.status.success(), "sha256sum {} failed", path.display());
+    let text = String::from_utf8_lossy(&out.stdout);
+    let hash = text.split_whitespace().next().context("no output from sha256sum")?;
+    Ok(hash.to_string())
+}
+
+/// The hash of `file` in a `sha256sum`-format listing.
+fn expected_sha256<'a>(sums: &'a str, file: &str) -> Option<&'a str> {
+    sums.lines().find_map(|line| {
+        let (hash, name) = line.split_once(char::is_whitespace)?;
+        let name = name.trim_start();
+        // `*` marks binary mode.
+        (name.strip_prefix('*').unwrap_or(name) == file).then_some(hash)
+    })
+}
+
 /// Fetch latest main and rebuild from source.
 /// Builds whatever is on main regardless of whether CI has published a release yet.
 pub fn run_from_source() -> Result<()> {
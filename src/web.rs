@@ -0,0 +1,120 @@
+//! Embedded browser dashboard for `shredtop run`.
+//!
+//! Serves a single static page plus a small JSON API over a plain HTTP/1.0
+//! `TcpListener`, like the Prometheus endpoint:
+//!
+//! ```text
+//! GET /              dashboard (HTML, polls the API below)
+//! GET /api/latest    most recent log entry — same JSON as a metrics log line
+//! GET /api/history   up to the last HISTORY_LEN entries, oldest first
+//! ```
+//!
+//! The page mirrors the terminal monitor (feed table, shred race, slot race)
+//! and adds history charts for lead time and shred rate. It has no external
+//! assets, so it works on hosts without internet access.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const INDEX_HTML: &str = include_str!("web/index.html");
+
+/// Entries kept for `/api/history` — three hours at the default 15 s interval.
+const HISTORY_LEN: usize = 720;
+
+#[derive(Default)]
+struct History {
+    /// Serialized log entries, oldest first.
+    entries: VecDeque<String>,
+}
+
+/// Spawn the web UI server thread.
+///
+/// Returns a `WebUpdater` that `run.rs` feeds with every serialized log entry.
+pub fn spawn(port: u16) -> WebUpdater {
+    let state: Arc<Mutex<History>> = Arc::default();
+    let state_server = state.clone();
+
+    std::thread::Builder::new()
+        .name("web-ui".into())
+        .spawn(move || {
+            let listener = match TcpListener::bind(("0.0.0.0", port)) {
+                Ok(l) => {
+                    eprintln!("shredtop web UI — http://0.0.0.0:{}/", port);
+                    l
+                }
+                Err(e) => {
+                    eprintln!("web UI failed to bind port {}: {}", port, e);
+                    return;
+                }
+            };
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                let _ = serve(stream, &state_server);
+            }
+        })
+        .expect("failed to spawn web-ui thread");
+
+    WebUpdater { state }
+}
+
+pub struct WebUpdater {
+    state: Arc<Mutex<History>>,
+}
+
+impl WebUpdater {
+    pub fn push(&self, entry_json: String) {
+        let mut h = self.state.lock().unwrap();
+        if h.entries.len() == HISTORY_LEN {
+            h.entries.pop_front();
+        }
+        h.entries.push_back(entry_json);
+    }
+}
+
+fn serve(stream: TcpStream, state: &Mutex<History>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    // Query strings are accepted and ignored (cache-busting from the page).
+    let path = parts.next().unwrap_or("").split('?').next().unwrap_or("");
+
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/" | "/index.html") => ("200 OK", "text/html; charset=utf-8", INDEX_HTML.to_string()),
+        ("GET", "/api/latest") => {
+            let h = state.lock().unwrap();
+            match h.entries.back() {
+                Some(e) => ("200 OK", "application/json", e.clone()),
+                None => ("200 OK", "application/json", "null".into()),
+            }
+        }
+        ("GET", "/api/history") => {
+            let h = state.lock().unwrap();
+            let mut body = String::from("[");
+            for (i, e) in h.entries.iter().enumerate() {
+                if i > 0 {
+                    body.push(',');
+                }
+                body.push_str(e);
+            }
+            body.push(']');
+            ("200 OK", "application/json", body)
+        }
+        ("GET", _) => ("404 Not Found", "text/plain", "not found\n".into()),
+        _ => ("405 Method Not Allowed", "text/plain", "method not allowed\n".into()),
+    };
+
+    let header = format!(
+        "HTTP/1.0 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\n\r\n",
+        status,
+        content_type,
+        body.len(),
+    );
+    let mut stream = stream;
+    stream.write_all(header.as_bytes())?;
+    stream.write_all(body.as_bytes())
+}